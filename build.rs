@@ -18,8 +18,14 @@ fn main() {
     if release {
         cmd.arg("--release");
     }
-    cmd.args(["--lib", "--crate-type=staticlib", "--target-dir", "target"])
-        .current_dir(&runtime_dir)
+    cmd.args(["--lib", "--crate-type=staticlib", "--target-dir", "target"]);
+    // A runtime of LLVM bitcode lets `otter build --lto` inline it into
+    // programs. It can then only be linked with clang and lld.
+    println!("cargo:rerun-if-env-changed=OTTER_RUNTIME_LTO");
+    if env::var_os("OTTER_RUNTIME_LTO").is_some_and(|value| value != "0") {
+        cmd.args(["--", "-Clinker-plugin-lto"]);
+    }
+    cmd.current_dir(&runtime_dir)
         .status()
         .expect("Failed to build otterc_runtime");
     let target_dir = PathBuf::from(if release {
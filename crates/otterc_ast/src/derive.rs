@@ -0,0 +1,703 @@
+//! Methods generated from `@derive(...)` attributes
+//!
+//! `@derive(json, eq, debug)` on a struct or enum adds `to_json`, `eq` and
+//! `to_string` methods to it before type checking. They are written out as
+//! ordinary Otter code, so the type checker, code generator and language
+//! server treat them like methods written by hand, and nothing is looked up
+//! at run time.
+//!
+//! Each generated node gets its own span past the end of the program's
+//! source. Types are recorded by span while checking, so generated code must
+//! not share spans with the code it was generated for.
+
+use std::collections::HashMap;
+
+use otterc_span::Span;
+
+use crate::nodes::{
+    BinaryOp, Block, Derive, EnumVariant, Expr, FStringPart, Function, Literal, MatchArm, Node,
+    Param, Pattern, Program, Statement, Type,
+};
+
+/// Type names whose values are written as they are by `json` and `debug`
+const SCALAR_TYPES: &[&str] = &[
+    "bool", "int", "float", "number", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f64",
+];
+
+const STRING_TYPES: &[&str] = &["str", "string"];
+
+/// A derive that could not be expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeriveError {
+    pub span: Span,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl DeriveError {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl Program {
+    /// Adds the methods of every `@derive(...)` attribute to the struct or
+    /// enum it is attached to. A derive that cannot be expanded adds nothing
+    /// and is reported.
+    pub fn expand_derives(&mut self) -> Vec<DeriveError> {
+        let derived: HashMap<String, Vec<Derive>> =
+            self.statements
+                .iter()
+                .filter_map(|statement| match statement.as_ref() {
+                    Statement::Struct { name, derives, .. }
+                    | Statement::Enum { name, derives, .. } => Some((
+                        name.clone(),
+                        derives.iter().map(|derive| *derive.as_ref()).collect(),
+                    )),
+                    _ => None,
+                })
+                .collect();
+        let end = self
+            .statements
+            .iter()
+            .map(|statement| statement.span().end())
+            .max()
+            .unwrap_or(0);
+        let mut expander = Expander {
+            derived,
+            builder: Builder { next: end + 1 },
+            errors: Vec::new(),
+        };
+
+        for statement in &mut self.statements {
+            match statement.as_mut() {
+                Statement::Struct {
+                    name,
+                    fields,
+                    methods,
+                    generics,
+                    derives,
+                    ..
+                } => {
+                    let shape = Shape {
+                        name: name.as_str(),
+                        generics: generics.as_slice(),
+                        fields: Fields::Struct(fields.as_slice()),
+                    };
+                    expander.expand(&shape, derives, methods);
+                }
+                Statement::Enum {
+                    name,
+                    variants,
+                    methods,
+                    generics,
+                    derives,
+                    ..
+                } => {
+                    let shape = Shape {
+                        name: name.as_str(),
+                        generics: generics.as_slice(),
+                        fields: Fields::Enum(variants.as_slice()),
+                    };
+                    expander.expand(&shape, derives, methods);
+                }
+                _ => {}
+            }
+        }
+        expander.errors
+    }
+}
+
+/// The parts of a struct or enum definition the generated methods depend on
+struct Shape<'a> {
+    name: &'a str,
+    generics: &'a [String],
+    fields: Fields<'a>,
+}
+
+enum Fields<'a> {
+    Struct(&'a [(String, Node<Type>)]),
+    Enum(&'a [Node<EnumVariant>]),
+}
+
+impl Shape<'_> {
+    /// `Name` or `Name<T, ...>`, the type of `other` in `eq`
+    fn self_type(&self, builder: &mut Builder) -> Type {
+        if self.generics.is_empty() {
+            return Type::Simple(self.name.to_string());
+        }
+        Type::Generic {
+            base: self.name.to_string(),
+            args: self
+                .generics
+                .iter()
+                .map(|generic| builder.node(Type::Simple(generic.clone())))
+                .collect(),
+        }
+    }
+
+    /// Every field with the name used for it in messages
+    fn labelled_fields(&self) -> Vec<(String, &Type)> {
+        match &self.fields {
+            Fields::Struct(fields) => fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.as_ref()))
+                .collect(),
+            Fields::Enum(variants) => variants
+                .iter()
+                .flat_map(|variant| {
+                    let variant = variant.as_ref();
+                    variant.fields.iter().enumerate().map(|(index, ty)| {
+                        let field = variant
+                            .field_names
+                            .get(index)
+                            .cloned()
+                            .unwrap_or_else(|| index.to_string());
+                        (format!("{}.{}", variant.name, field), ty.as_ref())
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How a field is written by the generated methods
+enum FieldKind<'a> {
+    Scalar,
+    Str,
+    /// A struct or enum of the program, with the traits it derives
+    Derived(&'a str, &'a [Derive]),
+    Other,
+}
+
+struct Expander {
+    derived: HashMap<String, Vec<Derive>>,
+    builder: Builder,
+    errors: Vec<DeriveError>,
+}
+
+impl Expander {
+    fn expand(
+        &mut self,
+        shape: &Shape<'_>,
+        derives: &[Node<Derive>],
+        methods: &mut Vec<Node<Function>>,
+    ) {
+        let mut seen = Vec::new();
+        for derive in derives {
+            let span = *derive.span();
+            let derive = *derive.as_ref();
+            if seen.contains(&derive) {
+                continue;
+            }
+            seen.push(derive);
+
+            if methods
+                .iter()
+                .any(|method| method.as_ref().name == derive.method())
+            {
+                self.errors.push(
+                    DeriveError::new(
+                        span,
+                        format!(
+                            "`{}` already has a method `{}`",
+                            shape.name,
+                            derive.method()
+                        ),
+                    )
+                    .with_help(format!(
+                        "remove `{derive}` from @derive(...) or the method written by hand"
+                    )),
+                );
+                continue;
+            }
+            if let Err(error) = self.check_fields(shape, derive, span) {
+                self.errors.push(error);
+                continue;
+            }
+
+            let method = match (derive, &shape.fields) {
+                (Derive::Json, Fields::Struct(fields)) => self.struct_to_json(fields),
+                (Derive::Json, Fields::Enum(variants)) => self.enum_to_json(shape, variants),
+                (Derive::Eq, Fields::Struct(fields)) => self.struct_eq(shape, fields),
+                (Derive::Eq, Fields::Enum(variants)) => self.enum_eq(shape, variants),
+                (Derive::Debug, Fields::Struct(fields)) => self.struct_to_string(shape, fields),
+                (Derive::Debug, Fields::Enum(variants)) => self.enum_to_string(shape, variants),
+            };
+            methods.push(method);
+        }
+    }
+
+    fn field_kind(&self, ty: &Type) -> FieldKind<'_> {
+        let name = match ty {
+            Type::Simple(name) => name,
+            Type::Generic { base, .. } => base,
+            Type::Union(_) => return FieldKind::Other,
+        };
+        if matches!(ty, Type::Simple(_)) && SCALAR_TYPES.contains(&name.as_str()) {
+            FieldKind::Scalar
+        } else if matches!(ty, Type::Simple(_)) && STRING_TYPES.contains(&name.as_str()) {
+            FieldKind::Str
+        } else if let Some((name, derives)) = self.derived.get_key_value(name) {
+            FieldKind::Derived(name, derives)
+        } else {
+            FieldKind::Other
+        }
+    }
+
+    /// Every field must be a type the derive can handle
+    fn check_fields(
+        &self,
+        shape: &Shape<'_>,
+        derive: Derive,
+        span: Span,
+    ) -> Result<(), DeriveError> {
+        for (label, ty) in shape.labelled_fields() {
+            let cannot = || {
+                format!(
+                    "cannot derive {derive} for `{}`: field `{label}` has type `{}`",
+                    shape.name,
+                    type_name(ty)
+                )
+            };
+            match self.field_kind(ty) {
+                FieldKind::Derived(name, derives) if !derives.contains(&derive) => {
+                    return Err(DeriveError::new(
+                        span,
+                        format!("{}, which does not derive {derive}", cannot()),
+                    )
+                    .with_help(format!("add `{derive}` to the @derive(...) of `{name}`")));
+                }
+                FieldKind::Other if derive == Derive::Json => {
+                    return Err(DeriveError::new(span, cannot())
+                        .with_help("json supports numbers, bool, str and types that derive json"));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// `to_json(self) -> str`, `{"x": 1, "name": "otter"}`
+    fn struct_to_json(&mut self, fields: &[(String, Node<Type>)]) -> Node<Function> {
+        let mut template = Template::default();
+        template.text("{");
+        for (index, (name, ty)) in fields.iter().enumerate() {
+            if index > 0 {
+                template.text(", ");
+            }
+            template.text(&format!("\"{name}\": "));
+            let value = self.builder.self_field(name);
+            self.json_value(&mut template, value, ty.as_ref());
+        }
+        template.text("}");
+        let body = vec![self.returned(template)];
+        self.builder
+            .method(Derive::Json.method(), Vec::new(), "str", body)
+    }
+
+    /// Externally tagged: `"Red"`, `{"Circle": {"radius": 1.0}}`, `{"Pair": [1, 2]}`
+    fn enum_to_json(
+        &mut self,
+        shape: &Shape<'_>,
+        variants: &[Node<EnumVariant>],
+    ) -> Node<Function> {
+        let arms = variants
+            .iter()
+            .map(|variant| {
+                let variant = variant.as_ref();
+                let bindings = binding_names("a", variant.fields.len());
+                let mut template = Template::default();
+                if variant.fields.is_empty() {
+                    template.text(&format!("\"{}\"", variant.name));
+                } else {
+                    let named = variant.field_names.len() == variant.fields.len();
+                    template.text(&format!("{{\"{}\": {}", variant.name, open(named)));
+                    for (index, (binding, ty)) in bindings.iter().zip(&variant.fields).enumerate() {
+                        if index > 0 {
+                            template.text(", ");
+                        }
+                        if named {
+                            template.text(&format!("\"{}\": ", variant.field_names[index]));
+                        }
+                        let value = self.builder.ident(binding);
+                        self.json_value(&mut template, value, ty.as_ref());
+                    }
+                    template.text(&format!("{}}}", close(named)));
+                }
+                let body = vec![self.returned(template)];
+                let pattern = self.variant_pattern(shape, variant, &bindings);
+                self.builder.arm(pattern, body)
+            })
+            .collect();
+        let body = self.match_self(arms, Literal::String(String::new()));
+        self.builder
+            .method(Derive::Json.method(), Vec::new(), "str", body)
+    }
+
+    fn json_value(&mut self, template: &mut Template, value: Node<Expr>, ty: &Type) {
+        match self.field_kind(ty) {
+            FieldKind::Str | FieldKind::Derived(..) => {
+                template.expr(self.builder.call_method(value, Derive::Json.method()));
+            }
+            FieldKind::Scalar | FieldKind::Other => template.expr(value),
+        }
+    }
+
+    /// `eq(self, other) -> bool`, comparing the fields in order
+    fn struct_eq(&mut self, shape: &Shape<'_>, fields: &[(String, Node<Type>)]) -> Node<Function> {
+        let other = if fields.is_empty() { "_other" } else { "other" };
+        let comparisons = fields
+            .iter()
+            .map(|(name, _)| {
+                let left = self.builder.self_field(name);
+                let other = self.builder.ident(other);
+                let right = self.builder.member(other, name);
+                self.builder.binary(BinaryOp::Eq, left, right)
+            })
+            .collect();
+        let result = self.builder.all(comparisons);
+        let other_type = shape.self_type(&mut self.builder);
+        let params = vec![self.builder.param(other, Some(other_type))];
+        let body = vec![self.builder.ret(result)];
+        self.builder
+            .method(Derive::Eq.method(), params, "bool", body)
+    }
+
+    /// Values of the same variant are equal when their fields are
+    fn enum_eq(&mut self, shape: &Shape<'_>, variants: &[Node<EnumVariant>]) -> Node<Function> {
+        let arms = variants
+            .iter()
+            .map(|variant| {
+                let variant = variant.as_ref();
+                let ours = binding_names("a", variant.fields.len());
+                let theirs = binding_names("b", variant.fields.len());
+                let comparisons = ours
+                    .iter()
+                    .zip(&theirs)
+                    .map(|(a, b)| {
+                        let left = self.builder.ident(a);
+                        let right = self.builder.ident(b);
+                        self.builder.binary(BinaryOp::Eq, left, right)
+                    })
+                    .collect();
+                let result = self.builder.all(comparisons);
+                let same = vec![self.builder.ret(result)];
+                let pattern = self.variant_pattern(shape, variant, &theirs);
+                let same = self.builder.arm(pattern, same);
+                let pass = self.builder.statement(Statement::Pass);
+                let wildcard = self.builder.pattern(Pattern::Wildcard);
+                let different = self.builder.arm(wildcard, vec![pass]);
+                let other = self.builder.ident("other");
+                let inner = self.builder.match_statement(other, vec![same, different]);
+                let pattern = self.variant_pattern(shape, variant, &ours);
+                self.builder.arm(pattern, vec![inner])
+            })
+            .collect();
+        let other_type = shape.self_type(&mut self.builder);
+        let params = vec![self.builder.param("other", Some(other_type))];
+        let body = self.match_self(arms, Literal::Bool(false));
+        self.builder
+            .method(Derive::Eq.method(), params, "bool", body)
+    }
+
+    /// `to_string(self) -> str`, `Point(x=1, name="otter")`
+    fn struct_to_string(
+        &mut self,
+        shape: &Shape<'_>,
+        fields: &[(String, Node<Type>)],
+    ) -> Node<Function> {
+        let mut template = Template::default();
+        template.text(&format!("{}(", shape.name));
+        for (index, (name, ty)) in fields.iter().enumerate() {
+            if index > 0 {
+                template.text(", ");
+            }
+            template.text(&format!("{name}="));
+            let value = self.builder.self_field(name);
+            self.debug_value(&mut template, value, ty.as_ref());
+        }
+        template.text(")");
+        let body = vec![self.returned(template)];
+        self.builder
+            .method(Derive::Debug.method(), Vec::new(), "str", body)
+    }
+
+    /// `Shape.Circle(radius=1.0)`, `Shape.Pair(1, 2)`, `Shape.Empty`
+    fn enum_to_string(
+        &mut self,
+        shape: &Shape<'_>,
+        variants: &[Node<EnumVariant>],
+    ) -> Node<Function> {
+        let arms = variants
+            .iter()
+            .map(|variant| {
+                let variant = variant.as_ref();
+                let bindings = binding_names("a", variant.fields.len());
+                let mut template = Template::default();
+                template.text(&format!("{}.{}", shape.name, variant.name));
+                if !variant.fields.is_empty() {
+                    template.text("(");
+                    for (index, (binding, ty)) in bindings.iter().zip(&variant.fields).enumerate() {
+                        if index > 0 {
+                            template.text(", ");
+                        }
+                        if let Some(name) = variant.field_names.get(index) {
+                            template.text(&format!("{name}="));
+                        }
+                        let value = self.builder.ident(binding);
+                        self.debug_value(&mut template, value, ty.as_ref());
+                    }
+                    template.text(")");
+                }
+                let body = vec![self.returned(template)];
+                let pattern = self.variant_pattern(shape, variant, &bindings);
+                self.builder.arm(pattern, body)
+            })
+            .collect();
+        let body = self.match_self(arms, Literal::String(String::new()));
+        self.builder
+            .method(Derive::Debug.method(), Vec::new(), "str", body)
+    }
+
+    fn debug_value(&mut self, template: &mut Template, value: Node<Expr>, ty: &Type) {
+        match self.field_kind(ty) {
+            // Quoted and escaped, so the boundaries of the string are visible
+            FieldKind::Str => template.expr(self.builder.call_method(value, Derive::Json.method())),
+            FieldKind::Derived(..) => {
+                template.expr(self.builder.call_method(value, Derive::Debug.method()));
+            }
+            FieldKind::Scalar | FieldKind::Other => template.expr(value),
+        }
+    }
+
+    /// `return f"..."`
+    fn returned(&mut self, template: Template) -> Node<Statement> {
+        let value = self.builder.fstring(template);
+        self.builder.ret(value)
+    }
+
+    /// `Name.Variant(a0, a1)`, binding each field of the variant
+    fn variant_pattern(
+        &mut self,
+        shape: &Shape<'_>,
+        variant: &EnumVariant,
+        bindings: &[String],
+    ) -> Node<Pattern> {
+        let fields = bindings
+            .iter()
+            .map(|binding| self.builder.pattern(Pattern::Identifier(binding.clone())))
+            .collect();
+        self.builder.pattern(Pattern::EnumVariant {
+            enum_name: shape.name.to_string(),
+            variant: variant.name.clone(),
+            fields,
+        })
+    }
+
+    /// `match self` over `arms`, each of which returns, followed by a return
+    /// of `fallback` for the code generator, which needs every path to end
+    /// in a return
+    fn match_self(&mut self, arms: Vec<Node<MatchArm>>, fallback: Literal) -> Vec<Node<Statement>> {
+        let subject = self.builder.ident("self");
+        let fallback = self.builder.literal(fallback);
+        vec![
+            self.builder.match_statement(subject, arms),
+            self.builder.ret(fallback),
+        ]
+    }
+}
+
+/// `a0`, `a1`, ... for the fields of a variant
+fn binding_names(prefix: &str, count: usize) -> Vec<String> {
+    (0..count).map(|index| format!("{prefix}{index}")).collect()
+}
+
+fn open(named: bool) -> &'static str {
+    if named { "{" } else { "[" }
+}
+
+fn close(named: bool) -> &'static str {
+    if named { "}" } else { "]" }
+}
+
+/// A type as it is written in source
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Simple(name) => name.clone(),
+        Type::Generic { base, args } => {
+            let args: Vec<String> = args.iter().map(|arg| type_name(arg.as_ref())).collect();
+            format!("{}<{}>", base, args.join(", "))
+        }
+        Type::Union(members) => {
+            let members: Vec<String> = members
+                .iter()
+                .map(|member| type_name(member.as_ref()))
+                .collect();
+            members.join(" | ")
+        }
+    }
+}
+
+/// The parts of a generated f-string
+#[derive(Default)]
+struct Template {
+    parts: Vec<FStringPart>,
+}
+
+impl Template {
+    fn text(&mut self, text: &str) {
+        match self.parts.last_mut() {
+            Some(FStringPart::Text(last)) => last.push_str(text),
+            _ => self.parts.push(FStringPart::Text(text.to_string())),
+        }
+    }
+
+    fn expr(&mut self, expr: Node<Expr>) {
+        self.parts.push(FStringPart::Expr(expr, None));
+    }
+}
+
+/// Creates nodes with consecutive one byte spans starting at `next`
+struct Builder {
+    next: usize,
+}
+
+impl Builder {
+    fn span(&mut self) -> Span {
+        let span = Span::new(self.next, self.next + 1);
+        self.next += 1;
+        span
+    }
+
+    fn node<T>(&mut self, value: T) -> Node<T> {
+        Node::new(value, self.span())
+    }
+
+    fn expr(&mut self, expr: Expr) -> Node<Expr> {
+        self.node(expr)
+    }
+
+    fn ident(&mut self, name: &str) -> Node<Expr> {
+        self.expr(Expr::Identifier(name.to_string()))
+    }
+
+    fn literal(&mut self, literal: Literal) -> Node<Expr> {
+        let literal = self.node(literal);
+        self.expr(Expr::Literal(literal))
+    }
+
+    fn member(&mut self, object: Node<Expr>, field: &str) -> Node<Expr> {
+        self.expr(Expr::Member {
+            object: Box::new(object),
+            field: field.to_string(),
+        })
+    }
+
+    fn self_field(&mut self, field: &str) -> Node<Expr> {
+        let object = self.ident("self");
+        self.member(object, field)
+    }
+
+    /// `object.method()`
+    fn call_method(&mut self, object: Node<Expr>, method: &str) -> Node<Expr> {
+        let func = self.member(object, method);
+        self.expr(Expr::Call {
+            func: Box::new(func),
+            args: Vec::new(),
+        })
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: Node<Expr>, right: Node<Expr>) -> Node<Expr> {
+        self.expr(Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    /// `a and b and ...`, `true` when there is nothing to check
+    fn all(&mut self, conditions: Vec<Node<Expr>>) -> Node<Expr> {
+        let mut conditions = conditions.into_iter();
+        let Some(first) = conditions.next() else {
+            return self.literal(Literal::Bool(true));
+        };
+        conditions.fold(first, |all, condition| {
+            self.binary(BinaryOp::And, all, condition)
+        })
+    }
+
+    fn fstring(&mut self, template: Template) -> Node<Expr> {
+        if let [FStringPart::Text(text)] = template.parts.as_slice() {
+            return self.literal(Literal::String(text.clone()));
+        }
+        let parts = template
+            .parts
+            .into_iter()
+            .map(|part| self.node(part))
+            .collect();
+        self.expr(Expr::FString { parts })
+    }
+
+    fn pattern(&mut self, pattern: Pattern) -> Node<Pattern> {
+        self.node(pattern)
+    }
+
+    fn statement(&mut self, statement: Statement) -> Node<Statement> {
+        self.node(statement)
+    }
+
+    fn ret(&mut self, expr: Node<Expr>) -> Node<Statement> {
+        self.statement(Statement::Return(Some(expr)))
+    }
+
+    fn block(&mut self, statements: Vec<Node<Statement>>) -> Node<Block> {
+        self.node(Block::new(statements))
+    }
+
+    fn arm(&mut self, pattern: Node<Pattern>, body: Vec<Node<Statement>>) -> Node<MatchArm> {
+        let body = self.block(body);
+        self.node(MatchArm {
+            pattern,
+            guard: None,
+            body,
+        })
+    }
+
+    fn match_statement(&mut self, value: Node<Expr>, arms: Vec<Node<MatchArm>>) -> Node<Statement> {
+        let value = self.expr(Expr::Match {
+            value: Box::new(value),
+            arms,
+        });
+        self.statement(Statement::Expr(value))
+    }
+
+    fn param(&mut self, name: &str, ty: Option<Type>) -> Node<Param> {
+        let name = self.node(name.to_string());
+        let ty = ty.map(|ty| self.node(ty));
+        self.node(Param::new(name, ty, None))
+    }
+
+    fn method(
+        &mut self,
+        name: &str,
+        params: Vec<Node<Param>>,
+        ret_ty: &str,
+        body: Vec<Node<Statement>>,
+    ) -> Node<Function> {
+        let mut all_params = vec![self.param("self", None)];
+        all_params.extend(params);
+        let ret_ty = self.node(Type::Simple(ret_ty.to_string()));
+        let body = self.block(body);
+        self.node(Function::new(name, all_params, Some(ret_ty), body))
+    }
+}
@@ -1,3 +1,4 @@
+pub mod derive;
 pub mod nodes;
 
 pub use nodes::{BinaryOp, Expr, Function, Literal, Program, Statement, UseImport};
@@ -104,6 +104,30 @@ impl Program {
         })
     }
 
+    /// Removes the functions and `use` statements whose `@cfg(...)`
+    /// condition does not hold, asking `holds` about each `key="value"` test
+    pub fn retain_cfg(&mut self, holds: impl Fn(&str, &str) -> bool) {
+        self.statements
+            .retain_mut(|statement| match statement.as_mut() {
+                Statement::Function(function) => function
+                    .as_ref()
+                    .cfg
+                    .as_ref()
+                    .is_none_or(|cfg| cfg.evaluate(&holds)),
+                Statement::Use { imports } => {
+                    imports.retain(|import| {
+                        import
+                            .as_ref()
+                            .cfg
+                            .as_ref()
+                            .is_none_or(|cfg| cfg.evaluate(&holds))
+                    });
+                    !imports.is_empty()
+                }
+                _ => true,
+            });
+    }
+
     /// Count the total number of statements recursively
     pub fn statement_count(&self) -> usize {
         self.statements
@@ -120,6 +144,36 @@ pub struct Function {
     pub ret_ty: Option<Node<Type>>,
     pub body: Node<Block>,
     pub public: bool,
+    /// Condition of the function's `@cfg(...)` attributes
+    pub cfg: Option<CfgPredicate>,
+    /// `@inline` or `@noinline`
+    pub inline: Option<InlineHint>,
+}
+
+/// How a function asks to be inlined, with `@inline` or `@noinline` on the
+/// line before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineHint {
+    /// `@inline`: inlined into every caller
+    Always,
+    /// `@noinline`: never inlined
+    Never,
+}
+
+impl InlineHint {
+    /// The attribute, without its `@`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InlineHint::Always => "inline",
+            InlineHint::Never => "noinline",
+        }
+    }
+}
+
+impl Display for InlineHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.as_str())
+    }
 }
 
 impl Function {
@@ -135,6 +189,8 @@ impl Function {
             ret_ty,
             body,
             public: false,
+            cfg: None,
+            inline: None,
         }
     }
 
@@ -150,6 +206,8 @@ impl Function {
             ret_ty,
             body,
             public: true,
+            cfg: None,
+            inline: None,
         }
     }
 }
@@ -157,7 +215,12 @@ impl Function {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Simple(String),
-    Generic { base: String, args: Vec<Node<Type>> },
+    Generic {
+        base: String,
+        args: Vec<Node<Type>>,
+    },
+    /// `int | str`, a value of any one of the member types
+    Union(Vec<Node<Type>>),
 }
 
 #[derive(Debug, Clone)]
@@ -188,6 +251,13 @@ impl Block {
 pub struct UseImport {
     pub module: String,
     pub alias: Option<String>,
+    /// Names brought into scope by `from module import ...`. Empty for a
+    /// plain `use`, which binds the module itself.
+    pub items: Vec<Node<ImportItem>>,
+    /// `from module import *`: every public item of the module is in scope
+    pub glob: bool,
+    /// Condition of the statement's `@cfg(...)` attributes
+    pub cfg: Option<CfgPredicate>,
 }
 
 impl UseImport {
@@ -195,14 +265,162 @@ impl UseImport {
         Self {
             module: module.into(),
             alias,
+            items: Vec::new(),
+            glob: false,
+            cfg: None,
+        }
+    }
+
+    pub fn with_items(module: impl Into<String>, items: Vec<Node<ImportItem>>) -> Self {
+        Self {
+            module: module.into(),
+            alias: None,
+            items,
+            glob: false,
+            cfg: None,
+        }
+    }
+
+    pub fn glob(module: impl Into<String>) -> Self {
+        Self {
+            module: module.into(),
+            alias: None,
+            items: Vec::new(),
+            glob: true,
+            cfg: None,
+        }
+    }
+
+    /// Whether this is a `from module import ...` rather than a plain `use`
+    pub fn is_from(&self) -> bool {
+        self.glob || !self.items.is_empty()
+    }
+}
+
+/// One name of a `from module import name [as alias]` statement
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl ImportItem {
+    pub fn new(name: impl Into<String>, alias: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            alias,
+        }
+    }
+
+    /// Name the item is visible under in the importing module
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Condition of a `@cfg(...)` attribute. The item it is attached to is
+/// removed after parsing unless the condition holds for the build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgPredicate {
+    /// `key="value"`, such as `os="linux"` or `feature="gpu"`
+    Equals {
+        key: String,
+        value: String,
+    },
+    Not(Box<CfgPredicate>),
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+}
+
+impl CfgPredicate {
+    /// Keys a `key="value"` condition may test
+    pub const KEYS: &'static [&'static str] = &["os", "arch", "family", "feature"];
+
+    /// Evaluates the condition, asking `holds` about each `key="value"` test
+    pub fn evaluate(&self, holds: &impl Fn(&str, &str) -> bool) -> bool {
+        match self {
+            CfgPredicate::Equals { key, value } => holds(key, value),
+            CfgPredicate::Not(inner) => !inner.evaluate(holds),
+            CfgPredicate::All(all) => all.iter().all(|predicate| predicate.evaluate(holds)),
+            CfgPredicate::Any(any) => any.iter().any(|predicate| predicate.evaluate(holds)),
+        }
+    }
+}
+
+impl Display for CfgPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |f: &mut std::fmt::Formatter<'_>, name: &str, items: &[CfgPredicate]| {
+            let items: Vec<String> = items.iter().map(ToString::to_string).collect();
+            write!(f, "{}({})", name, items.join(", "))
+        };
+        match self {
+            CfgPredicate::Equals { key, value } => write!(f, "{}=\"{}\"", key, value),
+            CfgPredicate::Not(inner) => write!(f, "not({})", inner),
+            CfgPredicate::All(all) => list(f, "all", all),
+            CfgPredicate::Any(any) => list(f, "any", any),
         }
     }
 }
 
+/// A trait named in a `@derive(...)` attribute of a struct or enum. Each one
+/// has a method generated for the type before type checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Derive {
+    /// `to_json(self) -> str`
+    Json,
+    /// `eq(self, other) -> bool`, also used by `==` and `!=`
+    Eq,
+    /// `to_string(self) -> str`
+    Debug,
+}
+
+impl Derive {
+    pub const ALL: [Derive; 3] = [Derive::Json, Derive::Eq, Derive::Debug];
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|derive| derive.as_str() == text)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Derive::Json => "json",
+            Derive::Eq => "eq",
+            Derive::Debug => "debug",
+        }
+    }
+
+    /// Name of the method generated for the derive
+    pub fn method(self) -> &'static str {
+        match self {
+            Derive::Json => "to_json",
+            Derive::Eq => "eq",
+            Derive::Debug => "to_string",
+        }
+    }
+}
+
+impl Display for Derive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `T: Numeric + Describable` in the generic parameters of a struct or enum.
+/// The type argument given for `param` must satisfy every bound, each a
+/// protocol or a builtin bound such as `Numeric`.
+#[derive(Debug, Clone)]
+pub struct GenericBound {
+    pub param: String,
+    pub bounds: Vec<Node<String>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct EnumVariant {
     pub name: String,
     pub fields: Vec<Node<Type>>,
+    /// Field names for a variant declared as `Variant: (name: T, ...)`, in
+    /// the same order as `fields`. Empty when the fields are positional.
+    pub field_names: Vec<String>,
 }
 
 impl EnumVariant {
@@ -210,8 +428,14 @@ impl EnumVariant {
         Self {
             name: name.into(),
             fields,
+            field_names: Vec::new(),
         }
     }
+
+    pub fn with_field_names(mut self, field_names: Vec<String>) -> Self {
+        self.field_names = field_names;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -227,6 +451,14 @@ pub enum Statement {
         name: Node<String>,
         expr: Node<Expr>,
     },
+    /// `const NAME: T = expr` at module scope. The value is computed at compile
+    /// time and stored once instead of being evaluated at every use.
+    Const {
+        name: Node<String>,
+        ty: Option<Node<Type>>,
+        expr: Node<Expr>,
+        public: bool,
+    },
 
     // Control flow
     If {
@@ -235,22 +467,49 @@ pub enum Statement {
         elif_blocks: Vec<(Node<Expr>, Node<Block>)>, // Vec<(condition, block)>
         else_block: Option<Node<Block>>,
     },
+    /// `label: for var in iterable:` - the optional label names the loop for
+    /// `break label` / `continue label`
     For {
         var: Node<String>,
         iterable: Node<Expr>,
         body: Node<Block>,
+        label: Option<Node<String>>,
+        /// `for await`, receiving from a stream until it is closed
+        is_await: bool,
     },
     While {
         cond: Node<Expr>,
         body: Node<Block>,
+        label: Option<Node<String>>,
+    },
+    /// `with expr as alias:` - the resource's `__exit__`/`close` method runs when the
+    /// block exits, including early returns and loop jumps
+    With {
+        expr: Node<Expr>,
+        alias: Option<Node<String>>,
+        body: Node<Block>,
     },
-    Break,
-    Continue,
+    /// `break` / `break label`
+    Break(Option<Node<String>>),
+    /// `continue` / `continue label`
+    Continue(Option<Node<String>>),
     Pass,
     Return(Option<Node<Expr>>),
+    /// `defer expr` - evaluates `expr` when the enclosing function exits, in reverse
+    /// order of registration
+    Defer(Node<Expr>),
+    /// `assert cond, "message"` - panics with the message and source location when
+    /// `cond` is false
+    Assert {
+        cond: Node<Expr>,
+        message: Option<Node<Expr>>,
+    },
 
     // Function definitions
     Function(Node<Function>),
+    /// `init:` - a block at the top level of a module that runs once, before
+    /// `main`
+    Init(Node<Block>),
 
     // Type definitions
     Struct {
@@ -259,12 +518,21 @@ pub enum Statement {
         methods: Vec<Node<Function>>, // Methods (functions with self parameter)
         public: bool,
         generics: Vec<String>, // Generic type parameters
+        /// Bounds of the generic parameters that declare any
+        bounds: Vec<GenericBound>,
+        /// Traits of the `@derive(...)` attributes, in source order
+        derives: Vec<Node<Derive>>,
     },
     Enum {
         name: String,
         variants: Vec<Node<EnumVariant>>,
+        methods: Vec<Node<Function>>, // Methods (functions with self parameter)
         public: bool,
         generics: Vec<String>,
+        /// Bounds of the generic parameters that declare any
+        bounds: Vec<GenericBound>,
+        /// Traits of the `@derive(...)` attributes, in source order
+        derives: Vec<Node<Derive>>,
     },
     TypeAlias {
         name: String,
@@ -272,6 +540,14 @@ pub enum Statement {
         public: bool,
         generics: Vec<String>, // Generic type parameters
     },
+    /// `protocol Name:` followed by method signatures. Any struct or enum
+    /// with methods of those signatures can be used where the protocol is
+    /// expected.
+    Protocol {
+        name: String,
+        methods: Vec<Node<ProtocolMethod>>,
+        public: bool,
+    },
 
     // Expressions as statements
     Expr(Node<Expr>),
@@ -292,22 +568,47 @@ pub enum Statement {
     Block(Node<Block>),
 }
 
+/// A method a protocol requires. `params` leaves out `self`.
+#[derive(Debug, Clone)]
+pub struct ProtocolMethod {
+    pub name: String,
+    pub params: Vec<Node<Param>>,
+    pub ret_ty: Option<Node<Type>>,
+}
+
+/// Methods that release a `with` resource, in lookup order
+pub const WITH_EXIT_METHODS: [&str; 2] = ["__exit__", "close"];
+
+/// The method a `with` statement calls to release its resource: the first of
+/// [`WITH_EXIT_METHODS`] that takes only `self`. `arity` gives the number of
+/// parameters of the resource's method of a name, `self` included, if it has
+/// one.
+pub fn with_exit_method(mut arity: impl FnMut(&str) -> Option<usize>) -> Option<&'static str> {
+    WITH_EXIT_METHODS
+        .into_iter()
+        .find(|method| arity(method) == Some(1))
+}
+
 impl Statement {
     /// Recursively count statements
     pub fn recursive_count(&self) -> usize {
         match self {
             Statement::Let { .. }
             | Statement::Assignment { .. }
-            | Statement::Break
-            | Statement::Continue
+            | Statement::Const { .. }
+            | Statement::Break(_)
+            | Statement::Continue(_)
             | Statement::Pass
             | Statement::Return(_)
+            | Statement::Defer(_)
+            | Statement::Assert { .. }
             | Statement::Expr(_)
             | Statement::Use { .. }
             | Statement::PubUse { .. }
             | Statement::Struct { .. }
             | Statement::Enum { .. }
-            | Statement::TypeAlias { .. } => 1,
+            | Statement::TypeAlias { .. }
+            | Statement::Protocol { .. } => 1,
 
             Statement::If {
                 then_block,
@@ -325,9 +626,10 @@ impl Statement {
                 }
                 count
             }
-            Statement::For { body, .. } | Statement::While { body, .. } => {
-                1 + body.as_ref().recursive_count()
-            }
+            Statement::For { body, .. }
+            | Statement::While { body, .. }
+            | Statement::With { body, .. }
+            | Statement::Init(body) => 1 + body.as_ref().recursive_count(),
             Statement::Function(func) => 1 + func.as_ref().body.as_ref().recursive_count(),
             Statement::Block(block) => block.as_ref().recursive_count(),
         }
@@ -337,7 +639,7 @@ impl Statement {
     pub fn is_pure(&self) -> bool {
         matches!(
             self,
-            Statement::Let { .. } | Statement::Break | Statement::Continue | Statement::Pass
+            Statement::Let { .. } | Statement::Break(_) | Statement::Continue(_) | Statement::Pass
         )
     }
 }
@@ -388,6 +690,12 @@ pub enum Expr {
         expr: Box<Node<Expr>>,
     },
 
+    // Explicit conversion: `value as f64`
+    Cast {
+        expr: Box<Node<Expr>>,
+        ty: Node<Type>,
+    },
+
     // Control flow expressions
     If {
         cond: Box<Node<Expr>>,
@@ -438,6 +746,12 @@ pub enum Expr {
         name: String,
         fields: Vec<(String, Node<Expr>)>, // field name -> value
     },
+
+    // Anonymous function: `fn(x: int) x * 2`
+    Lambda {
+        params: Vec<Node<Param>>,
+        body: Box<Node<Expr>>,
+    },
 }
 
 /// Match arm for pattern matching
@@ -455,6 +769,12 @@ pub enum Pattern {
     Wildcard,
     /// Literal pattern (1, true, "hello")
     Literal(Node<Literal>),
+    /// Range pattern (0..10 excludes `end`, 0..=10 includes it)
+    Range {
+        start: Node<Literal>,
+        end: Node<Literal>,
+        inclusive: bool,
+    },
     /// Identifier pattern (binds to variable)
     Identifier(String),
     /// Enum variant pattern (Enum.Variant(...))
@@ -473,12 +793,77 @@ pub enum Pattern {
         patterns: Vec<Node<Pattern>>,
         rest: Option<String>, // Variable name for rest pattern
     },
+    /// Dict/map pattern ({"status": s, "code": 200}), matches when every listed
+    /// key is present and its value matches; other keys are ignored
+    Dict {
+        entries: Vec<(Node<Literal>, Node<Pattern>)>,
+    },
+    /// Or-pattern (1 | 2 | 3), matches if any alternative matches
+    Or(Vec<Node<Pattern>>),
+    /// Binding pattern (n @ 1..10), binds the whole value when `pattern` matches
+    Binding {
+        name: String,
+        pattern: Box<Node<Pattern>>,
+    },
+}
+
+impl Pattern {
+    /// Names bound by this pattern, in source order
+    pub fn bindings(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_bindings(&mut names);
+        names
+    }
+
+    fn collect_bindings<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+            Pattern::Identifier(name) => names.push(name),
+            Pattern::EnumVariant { fields, .. } => {
+                for field in fields {
+                    field.as_ref().collect_bindings(names);
+                }
+            }
+            Pattern::Struct { fields, .. } => {
+                for (field_name, nested) in fields {
+                    match nested {
+                        Some(pattern) => pattern.as_ref().collect_bindings(names),
+                        None => names.push(field_name),
+                    }
+                }
+            }
+            Pattern::Array { patterns, rest } => {
+                for pattern in patterns {
+                    pattern.as_ref().collect_bindings(names);
+                }
+                if let Some(rest) = rest {
+                    names.push(rest);
+                }
+            }
+            Pattern::Dict { entries } => {
+                for (_, pattern) in entries {
+                    pattern.as_ref().collect_bindings(names);
+                }
+            }
+            Pattern::Binding { name, pattern } => {
+                names.push(name);
+                pattern.as_ref().as_ref().collect_bindings(names);
+            }
+            // Every alternative binds the same names
+            Pattern::Or(alternatives) => {
+                if let Some(first) = alternatives.first() {
+                    first.as_ref().collect_bindings(names);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum FStringPart {
     Text(String),
-    Expr(Node<Expr>),
+    /// `{expr}`, or `{expr:spec}` with the format specifier after the colon
+    Expr(Node<Expr>, Option<Node<String>>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -503,33 +888,179 @@ pub enum BinaryOp {
     // Logical
     And,
     Or,
+
+    // Bitwise
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnaryOp {
     Neg,
     Not,
+    /// Bitwise complement `~`
+    BitNot,
+}
+
+/// Type suffix of a number literal, e.g. the `u8` in `255u8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl NumberSuffix {
+    pub const ALL: [NumberSuffix; 10] = [
+        NumberSuffix::I8,
+        NumberSuffix::I16,
+        NumberSuffix::I32,
+        NumberSuffix::I64,
+        NumberSuffix::U8,
+        NumberSuffix::U16,
+        NumberSuffix::U32,
+        NumberSuffix::U64,
+        NumberSuffix::F32,
+        NumberSuffix::F64,
+    ];
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|suffix| suffix.as_str() == text)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NumberSuffix::I8 => "i8",
+            NumberSuffix::I16 => "i16",
+            NumberSuffix::I32 => "i32",
+            NumberSuffix::I64 => "i64",
+            NumberSuffix::U8 => "u8",
+            NumberSuffix::U16 => "u16",
+            NumberSuffix::U32 => "u32",
+            NumberSuffix::U64 => "u64",
+            NumberSuffix::F32 => "f32",
+            NumberSuffix::F64 => "f64",
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, NumberSuffix::F32 | NumberSuffix::F64)
+    }
+
+    /// Smallest and largest value of an integer suffix
+    pub fn int_range(self) -> Option<(i128, i128)> {
+        Some(match self {
+            NumberSuffix::I8 => (i8::MIN.into(), i8::MAX.into()),
+            NumberSuffix::I16 => (i16::MIN.into(), i16::MAX.into()),
+            NumberSuffix::I32 => (i32::MIN.into(), i32::MAX.into()),
+            NumberSuffix::I64 => (i64::MIN.into(), i64::MAX.into()),
+            NumberSuffix::U8 => (0, u8::MAX.into()),
+            NumberSuffix::U16 => (0, u16::MAX.into()),
+            NumberSuffix::U32 => (0, u32::MAX.into()),
+            NumberSuffix::U64 => (0, u64::MAX.into()),
+            NumberSuffix::F32 | NumberSuffix::F64 => return None,
+        })
+    }
+}
+
+impl Display for NumberSuffix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct NumberLiteral {
     pub value: f64,
+    /// Exact value of an integer literal written in the source; `value` loses
+    /// precision past 2^53
+    pub integer: Option<u64>,
     pub is_float_literal: bool,
+    /// 16 for `0x`, 2 for `0b`, 8 for `0o`, otherwise 10
+    pub radix: u32,
+    pub suffix: Option<NumberSuffix>,
+    /// The literal as written, `_` separators included, when it was parsed
+    /// from source
+    pub text: Option<String>,
 }
 
 impl NumberLiteral {
     pub fn new(value: f64, is_float_literal: bool) -> Self {
         Self {
             value,
+            integer: None,
             is_float_literal,
+            radix: 10,
+            suffix: None,
+            text: None,
         }
     }
+
+    /// The literal's integer value, exact when it was parsed from source
+    pub fn int_value(&self) -> i128 {
+        self.integer.map_or(self.value as i128, i128::from)
+    }
+
+    /// Parses the text of a number token, e.g. `1_000`, `2.5f32` or `0xFFu8`
+    pub fn parse(text: &str) -> Option<Self> {
+        let clean = text.replace('_', "");
+        let (radix, body) = match clean.get(..2) {
+            Some("0x" | "0X") => (16, &clean[2..]),
+            Some("0b" | "0B") => (2, &clean[2..]),
+            Some("0o" | "0O") => (8, &clean[2..]),
+            _ => (10, clean.as_str()),
+        };
+        let (digits, suffix) = if radix == 10 {
+            NumberSuffix::ALL
+                .into_iter()
+                .find_map(|suffix| Some((body.strip_suffix(suffix.as_str())?, Some(suffix))))
+                .unwrap_or((body, None))
+        } else {
+            let end = body
+                .find(|ch: char| !ch.is_digit(radix))
+                .unwrap_or(body.len());
+            let (digits, suffix) = body.split_at(end);
+            match suffix {
+                "" => (digits, None),
+                suffix => (digits, Some(NumberSuffix::parse(suffix)?)),
+            }
+        };
+        let has_fraction = radix == 10 && digits.contains(['.', 'e', 'E']);
+        let is_float_literal = has_fraction || suffix.is_some_and(NumberSuffix::is_float);
+        // Integers past u64, the widest integer type, are not numbers Otter
+        // can represent
+        let (value, integer) = if has_fraction {
+            (digits.parse().ok()?, None)
+        } else {
+            let integer = u64::from_str_radix(digits, radix).ok()?;
+            (integer as f64, (!is_float_literal).then_some(integer))
+        };
+        Some(Self {
+            value,
+            integer,
+            is_float_literal,
+            radix,
+            suffix,
+            text: Some(text.to_string()),
+        })
+    }
 }
 
 impl PartialEq for NumberLiteral {
     fn eq(&self, other: &Self) -> bool {
         self.is_float_literal == other.is_float_literal
             && self.value.to_bits() == other.value.to_bits()
+            && self.suffix == other.suffix
     }
 }
 
@@ -539,12 +1070,23 @@ impl Hash for NumberLiteral {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.value.to_bits().hash(state);
         self.is_float_literal.hash(state);
+        self.suffix.hash(state);
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum Literal {
     String(String),
+    /// `b"..."` byte string
+    Bytes(Vec<u8>),
+    /// A string checked at compile time by the validator registered for
+    /// `tag`, like `sql"SELECT 1"`. Evaluates to `value`.
+    Checked {
+        tag: String,
+        value: String,
+    },
+    /// `:name` symbol, interned so equal symbols share one value
+    Symbol(String),
     Number(NumberLiteral),
     Bool(bool),
     None,
@@ -554,7 +1096,17 @@ pub enum Literal {
 impl PartialEq for Literal {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Literal::String(a), Literal::String(b)) => a == b,
+            (Literal::String(a), Literal::String(b)) | (Literal::Symbol(a), Literal::Symbol(b)) => {
+                a == b
+            }
+            (Literal::Bytes(a), Literal::Bytes(b)) => a == b,
+            (
+                Literal::Checked { tag, value },
+                Literal::Checked {
+                    tag: other_tag,
+                    value: other_value,
+                },
+            ) => tag == other_tag && value == other_value,
             (Literal::Bool(a), Literal::Bool(b)) => a == b,
             (Literal::Number(a), Literal::Number(b)) => a == b,
             (Literal::None, Literal::None) | (Literal::Unit, Literal::Unit) => true,
@@ -586,6 +1138,19 @@ impl Hash for Literal {
             Literal::Unit => {
                 4u8.hash(state);
             }
+            Literal::Bytes(bytes) => {
+                5u8.hash(state);
+                bytes.hash(state);
+            }
+            Literal::Checked { tag, value } => {
+                6u8.hash(state);
+                tag.hash(state);
+                value.hash(state);
+            }
+            Literal::Symbol(name) => {
+                7u8.hash(state);
+                name.hash(state);
+            }
         }
     }
 }
@@ -2,11 +2,13 @@
 pub mod manager;
 pub mod metadata;
 pub mod path;
+pub mod shared;
 
 // Re-exports for convenience
 pub use manager::{CacheEntry, CacheManager};
 pub use metadata::CacheMetadata;
 pub use path::{cache_key_for_file, cache_root, ensure_cache_dir};
+pub use shared::SharedCache;
 
 /// Build options for caching
 #[derive(Debug, Clone)]
@@ -15,8 +17,27 @@ pub struct CacheBuildOptions {
     pub cache_dir: std::path::PathBuf,
     pub max_cache_size: usize,
     pub release: bool,
-    pub lto: bool,
+    /// LTO mode: `off`, `thin` or `full`
+    pub lto: String,
+    /// Optimization level, such as `O2` or `Oz`
+    pub opt_level: String,
+    /// `--passes` pipeline run instead of the level's
+    pub passes: Option<String>,
+    /// Sanitizers the program is instrumented with, such as `address`
+    pub sanitizers: Vec<String>,
     pub emit_ir: bool,
+    /// Whether DWARF debug info is emitted
+    pub debug_info: bool,
+    /// Whether capability calls are compiled to the `--sandbox` stubs
+    pub sandbox: bool,
+    /// Whether `assert` statements are compiled out, for `--release-asserts-off`
+    pub strip_asserts: bool,
+    /// Whether task loops check in with the scheduler, for `--preempt-loops`
+    pub preempt_loops: bool,
+    /// Target triple, when not building for the host
+    pub target: Option<String>,
+    /// Language and `@cfg` features the program is compiled with
+    pub features: Vec<String>,
 }
 
 /// Compilation inputs for caching
@@ -1,10 +1,15 @@
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
+use crate::shared::SharedCache;
+
 /// Compilation cache manager
 pub struct CacheManager {
-    _cache_dir: PathBuf,
+    cache_dir: PathBuf,
     entries: HashMap<String, CacheEntry>,
+    shared: Option<SharedCache>,
 }
 
 #[derive(Debug, Clone)]
@@ -19,8 +24,9 @@ pub struct CacheEntry {
 impl Default for CacheManager {
     fn default() -> Self {
         Self {
-            _cache_dir: PathBuf::from("./cache"),
+            cache_dir: PathBuf::from("./cache"),
             entries: HashMap::new(),
+            shared: None,
         }
     }
 }
@@ -30,6 +36,12 @@ impl CacheManager {
         Self::default()
     }
 
+    /// Looks up and stores binaries in `shared` as well as locally
+    pub fn with_shared(mut self, shared: Option<SharedCache>) -> Self {
+        self.shared = shared;
+        self
+    }
+
     pub fn get(&self, key: &str) -> Option<&CacheEntry> {
         self.entries.get(key)
     }
@@ -42,24 +54,81 @@ impl CacheManager {
         self.entries.clear();
     }
 
+    /// Hash of everything the binary depends on: the contents of the source
+    /// and its imports, the build options and the compiler version. Paths
+    /// are left out so machines sharing a cache agree on the key.
     pub fn fingerprint(
         &self,
-        _inputs: &super::CompilationInputs,
-        _options: &super::CacheBuildOptions,
-        _version: &str,
+        inputs: &super::CompilationInputs,
+        options: &super::CacheBuildOptions,
+        version: &str,
     ) -> String {
-        // Simple fingerprinting - in a real implementation this would hash the inputs
-        format!(
-            "cache_key_{}",
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        )
+        let mut hasher = DefaultHasher::new();
+        version.hash(&mut hasher);
+        std::fs::read(&inputs.source_path).ok().hash(&mut hasher);
+        let mut imports = inputs.imports.clone();
+        imports.sort();
+        for import in &imports {
+            std::fs::read(import).ok().hash(&mut hasher);
+        }
+        inputs.dependencies.hash(&mut hasher);
+        options.release.hash(&mut hasher);
+        options.lto.hash(&mut hasher);
+        options.opt_level.hash(&mut hasher);
+        options.passes.hash(&mut hasher);
+        options.sanitizers.hash(&mut hasher);
+        options.emit_ir.hash(&mut hasher);
+        options.debug_info.hash(&mut hasher);
+        options.sandbox.hash(&mut hasher);
+        options.strip_asserts.hash(&mut hasher);
+        options.preempt_loops.hash(&mut hasher);
+        options.features.hash(&mut hasher);
+        match &options.target {
+            Some(target) => target.hash(&mut hasher),
+            None => (std::env::consts::ARCH, std::env::consts::OS).hash(&mut hasher),
+        }
+        format!("{:016x}", hasher.finish())
     }
 
+    /// The entry for `key`, downloading it from the shared cache when this
+    /// process has not built it
     pub fn lookup(&self, key: &str) -> Option<CacheEntry> {
-        self.entries.get(key).cloned()
+        if let Some(entry) = self.entries.get(key) {
+            return Some(entry.clone());
+        }
+        self.fetch_shared(key)
+    }
+
+    fn fetch_shared(&self, key: &str) -> Option<CacheEntry> {
+        let shared = self.shared.as_ref()?;
+        let metadata_path = self.cache_dir.join(format!("{key}.json"));
+        if !shared
+            .fetch(&format!("bin/{key}.json"), &metadata_path)
+            .ok()?
+        {
+            return None;
+        }
+        let mut metadata: super::metadata::CacheMetadata =
+            serde_json::from_slice(&std::fs::read(&metadata_path).ok()?).ok()?;
+        let binary_path = self.cache_dir.join(key);
+        if !shared.fetch(&format!("bin/{key}"), &binary_path).ok()? {
+            return None;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&binary_path, std::fs::Permissions::from_mode(0o755)).ok()?;
+        }
+
+        metadata.binary_path = binary_path.clone();
+        metadata.cache_path = self.cache_dir.clone();
+        Some(CacheEntry {
+            path: metadata.cache_path.clone(),
+            last_modified: metadata.created_at,
+            size: metadata.binary_size,
+            metadata,
+            binary_path,
+        })
     }
 
     pub fn binary_path(&self, key: &str) -> Option<PathBuf> {
@@ -78,6 +147,90 @@ impl CacheManager {
             binary_path: metadata.binary_path.clone(),
         };
         self.entries.insert(metadata.key.clone(), entry);
+
+        if let Some(shared) = self.shared.as_ref().filter(|shared| shared.is_writable()) {
+            let key = &metadata.key;
+            std::fs::create_dir_all(&self.cache_dir)?;
+            let metadata_path = self.cache_dir.join(format!("{key}.json"));
+            std::fs::write(&metadata_path, serde_json::to_vec(metadata)?)?;
+            // The binary goes first so a reader that finds the metadata can
+            // also fetch the binary
+            shared.publish(&format!("bin/{key}"), &metadata.binary_path)?;
+            shared.publish(&format!("bin/{key}.json"), &metadata_path)?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CacheBuildOptions, CompilationInputs};
+
+    #[test]
+    fn fingerprints_follow_contents_and_options() {
+        let dir = std::env::temp_dir().join(format!("otter-fingerprint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("main.ot");
+        std::fs::write(&source, "fn main():\n    pass\n").unwrap();
+
+        let manager = CacheManager::new();
+        let inputs = CompilationInputs::new(source.clone(), Vec::new());
+        let mut options = CacheBuildOptions {
+            enable_cache: true,
+            cache_dir: dir.clone(),
+            max_cache_size: 0,
+            release: false,
+            lto: "off".to_string(),
+            opt_level: "O2".to_string(),
+            passes: None,
+            sanitizers: Vec::new(),
+            emit_ir: false,
+            debug_info: false,
+            sandbox: false,
+            strip_asserts: false,
+            preempt_loops: false,
+            target: None,
+            features: Vec::new(),
+        };
+        let key = manager.fingerprint(&inputs, &options, "1.0");
+        assert_eq!(key, manager.fingerprint(&inputs, &options, "1.0"));
+
+        options.release = true;
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.release = false;
+
+        options.lto = "thin".to_string();
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.lto = "off".to_string();
+
+        options.opt_level = "Oz".to_string();
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.opt_level = "O2".to_string();
+
+        options.passes = Some("function(instcombine)".to_string());
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.passes = None;
+
+        options.sanitizers = vec!["address".to_string()];
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.sanitizers.clear();
+
+        options.sandbox = true;
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.sandbox = false;
+
+        options.strip_asserts = true;
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.strip_asserts = false;
+
+        options.preempt_loops = true;
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+        options.preempt_loops = false;
+
+        std::fs::write(&source, "fn main():\n    print(\"hi\")\n").unwrap();
+        assert_ne!(key, manager.fingerprint(&inputs, &options, "1.0"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
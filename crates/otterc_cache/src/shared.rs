@@ -0,0 +1,162 @@
+//! Artifact cache shared between machines
+//!
+//! `OTTER_REMOTE_CACHE` names a directory (such as a network mount) or an
+//! `http://`/`https://` URL. Artifacts missing from the local cache are
+//! looked up there before being built. Builds only upload what they produce
+//! when `OTTER_REMOTE_CACHE_WRITE=1`, so a CI job can fill the cache while
+//! developer machines just read from it.
+//!
+//! Over HTTP an artifact is `GET <url>/<key>` and uploaded with
+//! `PUT <url>/<key>`; a 404 is a miss.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub const REMOTE_CACHE_ENV: &str = "OTTER_REMOTE_CACHE";
+pub const REMOTE_CACHE_WRITE_ENV: &str = "OTTER_REMOTE_CACHE_WRITE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedBackend {
+    Directory(PathBuf),
+    Http(String),
+}
+
+/// A read-through cache behind the local one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedCache {
+    backend: SharedBackend,
+    writable: bool,
+}
+
+impl SharedCache {
+    /// `location` is a directory or an HTTP(S) URL
+    pub fn new(location: &str, writable: bool) -> Self {
+        let backend = if location.starts_with("http://") || location.starts_with("https://") {
+            SharedBackend::Http(location.trim_end_matches('/').to_string())
+        } else {
+            SharedBackend::Directory(PathBuf::from(location))
+        };
+        Self { backend, writable }
+    }
+
+    /// The cache configured by `OTTER_REMOTE_CACHE`, if any
+    pub fn from_env() -> Option<Self> {
+        let location = std::env::var(REMOTE_CACHE_ENV).ok()?;
+        if location.trim().is_empty() {
+            return None;
+        }
+        let writable = std::env::var(REMOTE_CACHE_WRITE_ENV)
+            .is_ok_and(|value| matches!(value.as_str(), "1" | "true" | "yes"));
+        Some(Self::new(location.trim(), writable))
+    }
+
+    pub fn backend(&self) -> &SharedBackend {
+        &self.backend
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Copies the artifact stored under `key` to `dest`. Returns `false` on a
+    /// miss, leaving `dest` untouched.
+    pub fn fetch(&self, key: &str, dest: &Path) -> Result<bool> {
+        let staged = staging_path(dest);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match &self.backend {
+            SharedBackend::Directory(root) => {
+                let source = root.join(key);
+                if !source.is_file() {
+                    return Ok(false);
+                }
+                fs::copy(&source, &staged)?;
+            }
+            SharedBackend::Http(url) => {
+                let response = match ureq::get(&format!("{url}/{key}")).call() {
+                    Ok(response) => response,
+                    Err(ureq::Error::Status(404, _)) => return Ok(false),
+                    Err(err) => return Err(err.into()),
+                };
+                let mut file = fs::File::create(&staged)?;
+                io::copy(&mut response.into_reader(), &mut file)?;
+            }
+        }
+        fs::rename(&staged, dest)?;
+        Ok(true)
+    }
+
+    /// Stores `source` under `key`. Does nothing unless the cache was opened
+    /// for writing.
+    pub fn publish(&self, key: &str, source: &Path) -> Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+        match &self.backend {
+            SharedBackend::Directory(root) => {
+                let dest = root.join(key);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // Readers never see a partially written artifact
+                let staged = staging_path(&dest);
+                fs::copy(source, &staged)?;
+                fs::rename(&staged, &dest)?;
+            }
+            SharedBackend::Http(url) => {
+                let bytes = fs::read(source)?;
+                ureq::put(&format!("{url}/{key}"))
+                    .set("Content-Type", "application/octet-stream")
+                    .send_bytes(&bytes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn staging_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".partial-{}", std::process::id()));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_pick_a_backend() {
+        assert_eq!(
+            SharedCache::new("https://cache.example.com/otter/", false).backend(),
+            &SharedBackend::Http("https://cache.example.com/otter".to_string())
+        );
+        assert_eq!(
+            SharedCache::new("/mnt/otter-cache", false).backend(),
+            &SharedBackend::Directory(PathBuf::from("/mnt/otter-cache"))
+        );
+    }
+
+    #[test]
+    fn directory_cache_reads_through_and_only_writes_when_allowed() {
+        let root = std::env::temp_dir().join(format!("otter-shared-cache-{}", std::process::id()));
+        let artifact = root.join("local/artifact.bin");
+        fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+        fs::write(&artifact, b"compiled").unwrap();
+        let location = root.join("shared").display().to_string();
+
+        let reader = SharedCache::new(&location, false);
+        reader.publish("bin/abc", &artifact).unwrap();
+        assert!(!reader.fetch("bin/abc", &root.join("out")).unwrap());
+
+        let writer = SharedCache::new(&location, true);
+        writer.publish("bin/abc", &artifact).unwrap();
+        assert!(reader.fetch("bin/abc", &root.join("out")).unwrap());
+        assert_eq!(fs::read(root.join("out")).unwrap(), b"compiled");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,3 +1,5 @@
 pub mod llvm;
 
-pub use llvm::{BuildArtifact, build_executable, build_shared_library, current_llvm_version};
+pub use llvm::{
+    BuildArtifact, build_executable, build_shared_library, current_llvm_version, merge_pgo_profiles,
+};
@@ -7,7 +7,7 @@ use otterc_ast::nodes::{Program, Statement};
 use otterc_ffi::{BridgeSymbolRegistry, CargoBridge, DynamicLibraryLoader, FunctionSpec, TypeSpec};
 
 use otterc_ffi::register_dynamic_exports;
-use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+use otterc_symbol::registry::{Capability, FfiFunction, FfiSignature, FfiType, SymbolRegistry};
 
 pub(crate) fn prepare_rust_bridges(
     program: &Program,
@@ -17,6 +17,9 @@ pub(crate) fn prepare_rust_bridges(
     if imports.is_empty() {
         return Ok(Vec::new());
     }
+    if registry.is_denied(Capability::FfiPlugin) {
+        bail!("cannot import Rust crates: FFI plugins are disabled in sandbox mode");
+    }
 
     let bridge_registry = BridgeSymbolRegistry::global().clone();
     let cargo_bridge = CargoBridge::new(bridge_registry.clone())?;
@@ -103,7 +106,7 @@ fn register_bridge_functions(
         let result = type_spec_to_ffi(&function.result, "return", &canonical_name)?;
         let signature = FfiSignature::new(params.clone(), result.clone());
 
-        registry.register(FfiFunction {
+        registry.register_plugin(FfiFunction {
             name: canonical_name.clone(),
             symbol: function.symbol.clone(),
             signature: signature.clone(),
@@ -111,7 +114,7 @@ fn register_bridge_functions(
 
         for alias in aliases {
             let alias_name = alias_name(alias, crate_name, &canonical_name);
-            registry.register(FfiFunction {
+            registry.register_plugin(FfiFunction {
                 name: alias_name,
                 symbol: function.symbol.clone(),
                 signature: FfiSignature::new(params.clone(), result.clone()),
@@ -119,14 +122,14 @@ fn register_bridge_functions(
         }
     }
 
-    registry.register(FfiFunction {
+    registry.register_plugin(FfiFunction {
         name: format!("{crate_name}.__call_json"),
         symbol: "otter_call_json".into(),
         signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
     });
 
     for alias in aliases {
-        registry.register(FfiFunction {
+        registry.register_plugin(FfiFunction {
             name: format!("{alias}.__call_json"),
             symbol: "otter_call_json".into(),
             signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
@@ -12,7 +12,9 @@ use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Tar
 use otterc_ast::nodes::Program;
 use otterc_span::Span;
 
-use otterc_config::{CodegenOptLevel, CodegenOptions, TargetTriple};
+use otterc_config::{
+    CodegenOptLevel, CodegenOptions, EmitKind, LtoMode, PgoMode, Sanitizer, TargetTriple,
+};
 use otterc_typecheck::{EnumLayout, TypeInfo};
 
 use super::bridges::prepare_rust_bridges;
@@ -24,6 +26,13 @@ const RUNTIME_CODE_EMBEDDED: &str = include_str!("runtimes/embedded.c");
 const RUNTIME_CODE_WASM: &str = include_str!("runtimes/wasm.c");
 const RUNTIME_CODE_SHIM: &str = include_str!("runtimes/shim.c");
 
+/// Appended to the C shim of an AddressSanitizer build. Objects the collector
+/// still holds at exit are not leaks, so LeakSanitizer stays off unless
+/// `ASAN_OPTIONS` turns it back on.
+const ASAN_DEFAULT_OPTIONS: &str = "
+const char *__asan_default_options(void) { return \"detect_leaks=0\"; }
+";
+
 /// Check if a library is available on the system
 fn check_library_available(lib_name: &str) -> bool {
     // Try pkg-config first
@@ -75,48 +84,143 @@ pub fn current_llvm_version() -> String {
     "15.0".to_string()
 }
 
+/// Merges the raw profiles an instrumented (`--pgo-gen`) build wrote into the
+/// `.profdata` file `--pgo-use` reads, returning its path. `profile` is the
+/// directory the runs wrote to, a single `.profraw` file or an already merged
+/// `.profdata` file, which is used as is. The merge runs
+/// `OTTER_LLVM_PROFDATA`, or `llvm-profdata` from the `PATH`.
+pub fn merge_pgo_profiles(profile: &Path) -> Result<PathBuf> {
+    if profile.extension().is_some_and(|ext| ext == "profdata") {
+        return Ok(profile.to_path_buf());
+    }
+    let (raw_profiles, merged) = if profile.is_dir() {
+        let mut raw_profiles: Vec<PathBuf> = fs::read_dir(profile)
+            .with_context(|| format!("failed to read {}", profile.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "profraw"))
+            .collect();
+        raw_profiles.sort();
+        (raw_profiles, profile.join("merged.profdata"))
+    } else if profile.exists() {
+        (
+            vec![profile.to_path_buf()],
+            profile.with_extension("profdata"),
+        )
+    } else {
+        (Vec::new(), profile.with_extension("profdata"))
+    };
+    if raw_profiles.is_empty() {
+        bail!(
+            "no profiles in {}; run the program built with --pgo-gen first",
+            profile.display()
+        );
+    }
+
+    let profdata = env::var("OTTER_LLVM_PROFDATA")
+        .ok()
+        .filter(|tool| !tool.is_empty())
+        .unwrap_or_else(|| "llvm-profdata".to_string());
+    let output = Command::new(&profdata)
+        .arg("merge")
+        .arg("-o")
+        .arg(&merged)
+        .args(&raw_profiles)
+        .output()
+        .with_context(|| {
+            format!("failed to run {profdata}; set OTTER_LLVM_PROFDATA to llvm-profdata")
+        })?;
+    if !output.status.success() {
+        bail!(
+            "{profdata} failed to merge the profiles:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(merged)
+}
+
 /// Find the Rust runtime static library
-fn find_runtime_library(runtime_triple: &TargetTriple) -> Result<PathBuf> {
-    // Use `OTTERC_RUNTIME_LIB` environment variable if set
-    if let Ok(path) = env::var("OTTERC_RUNTIME_LIB") {
-        let lib_path = PathBuf::from(path);
-        let runtime_lib = if runtime_triple.is_windows() {
-            lib_path.join("otterc_runtime.lib")
+///
+/// A cross build needs the runtime compiled for its target, which is looked
+/// for in a directory named after the target triple, such as the
+/// `target/<triple>/release` directory `cargo build --target <triple>`
+/// writes to
+fn find_runtime_library(runtime_triple: &TargetTriple, is_native_target: bool) -> Result<PathBuf> {
+    let file_name = if runtime_triple.is_windows() {
+        "otterc_runtime.lib"
+    } else {
+        "libotterc_runtime.a"
+    };
+    let triple = runtime_triple.to_llvm_triple();
+    let candidates = |dir: &Path| -> Vec<PathBuf> {
+        if is_native_target {
+            vec![dir.join(file_name)]
         } else {
-            lib_path.join("libotterc_runtime.a")
-        };
-        if runtime_lib.exists() {
-            return Ok(runtime_lib);
+            vec![
+                dir.join(&triple).join(file_name),
+                dir.join("..").join(&triple).join("release").join(file_name),
+            ]
         }
+    };
+
+    let mut search_dirs = Vec::new();
+    // Use `OTTERC_RUNTIME_LIB` environment variable if set
+    if let Ok(path) = env::var("OTTERC_RUNTIME_LIB") {
+        search_dirs.push(PathBuf::from(path));
     }
     // Search in `PATH`
     if let Some(paths) = env::var_os("PATH") {
-        for path in env::split_paths(&paths) {
-            let runtime_lib = if runtime_triple.is_windows() {
-                path.join("otterc_runtime.lib")
-            } else {
-                path.join("libotterc_runtime.a")
-            };
-            if runtime_lib.exists() {
-                return Ok(runtime_lib);
-            }
-        }
+        search_dirs.extend(env::split_paths(&paths));
     }
     // Default to looking in the standard location relative to the executable
     let exe_path = env::current_exe().context("failed to get current executable path")?;
     let exe_dir = exe_path
         .parent()
         .ok_or_else(|| anyhow!("failed to get executable directory"))?;
-    let runtime_lib = if runtime_triple.is_windows() {
-        exe_dir.join("otterc_runtime.lib")
-    } else {
-        exe_dir.join("libotterc_runtime.a")
-    };
-    if runtime_lib.exists() {
-        Ok(runtime_lib)
-    } else {
+    search_dirs.push(exe_dir.to_path_buf());
+
+    if let Some(runtime_lib) = search_dirs
+        .iter()
+        .flat_map(|dir| candidates(dir))
+        .find(|path| path.exists())
+    {
+        return Ok(runtime_lib);
+    }
+    // WebAssembly and bare-metal builds take the library host builds use
+    if !is_native_target && (runtime_triple.is_wasm() || runtime_triple.is_embedded()) {
+        return find_runtime_library(runtime_triple, true);
+    }
+    if is_native_target {
         bail!("failed to find runtime library")
     }
+    bail!(
+        "failed to find a runtime library for {triple}; build one with \
+         `cargo build --release -p otterc_runtime --target {triple}` and set \
+         OTTERC_RUNTIME_LIB to the directory holding `{triple}/{file_name}`"
+    )
+}
+
+/// The driver that compiles and links for the target: `OTTER_LINKER` when
+/// set, `default` for the host and clang, which takes `-target`, for any
+/// other platform, for an LTO or PGO build, whose bitcode only clang reads,
+/// or for a sanitized build, whose instrumentation needs clang's runtimes
+fn target_driver(default: String, needs_clang: bool) -> String {
+    match env::var("OTTER_LINKER") {
+        Ok(driver) if !driver.is_empty() => driver,
+        _ if !needs_clang || default != "cc" => default,
+        _ => "clang".to_string(),
+    }
+}
+
+/// The system root to compile and link against: `OTTER_SYSROOT`, or for
+/// WASI the `WASI_SYSROOT` wasi-sdk uses. Without one the driver's own
+/// default applies.
+fn target_sysroot(runtime_triple: &TargetTriple) -> Option<std::ffi::OsString> {
+    env::var_os("OTTER_SYSROOT").or_else(|| {
+        runtime_triple
+            .is_wasi()
+            .then(|| env::var_os("WASI_SYSROOT"))
+            .flatten()
+    })
 }
 
 pub fn build_executable(
@@ -153,8 +257,16 @@ pub fn build_executable(
         enum_layouts.clone(),
         Some(runtime_triple.clone()),
     );
+    compiler.strip_asserts = options.strip_asserts;
+    compiler.preempt_loops = options.preempt_loops;
+    compiler.profile_functions = options.profile_functions;
+    compiler.source_lines = options.source.clone();
+    if options.debug_info {
+        compiler.enable_debug_info(!matches!(options.opt_level, CodegenOptLevel::None));
+    }
 
     compiler.lower_program(program, true)?; // Require main for executables
+    compiler.finalize_debug_info();
     compiler
         .module
         .verify()
@@ -173,8 +285,8 @@ pub fn build_executable(
 
     // Check if we're compiling for the native target
     let native_triple = inkwell::targets::TargetMachine::get_default_triple();
-    let is_native_target =
-        llvm_triple_to_string(&llvm_triple) == llvm_triple_to_string(&native_triple);
+    let is_native_target = TargetTriple::parse(&llvm_triple_to_string(&native_triple))
+        .is_ok_and(|native| runtime_triple.same_platform(&native));
     compiler.module.set_triple(&llvm_triple);
 
     let target = Target::from_triple(&llvm_triple)
@@ -209,39 +321,90 @@ pub fn build_executable(
         .module
         .set_data_layout(&target_machine.get_target_data().get_data_layout());
 
+    // With LTO the program is linked as bitcode, so the linker can inline
+    // across it, the C shim and a runtime built as bitcode
+    let (lto, pgo) = if runtime_triple.is_wasm() {
+        (LtoMode::Off, PgoMode::Off)
+    } else {
+        (options.lto, options.pgo.clone())
+    };
+    let lto_flag = lto.clang_flag();
+
+    // Sanitizers instrument the program and link clang's runtimes for them,
+    // which only exist for hosted targets
+    if !options.sanitizers.is_empty() && (runtime_triple.is_wasm() || runtime_triple.is_embedded())
+    {
+        bail!("--sanitize needs a hosted target; {triple_str} has no sanitizer runtimes");
+    }
+    let sanitize_flag = Sanitizer::clang_flag(&options.sanitizers);
+    compiler.sanitize_address = options.sanitizers.contains(&Sanitizer::Address);
+
     compiler.run_default_passes(
         options.opt_level,
-        options.enable_pgo,
-        options.pgo_profile_file.as_deref(),
+        options.passes.as_deref(),
+        &pgo,
         options.inline_threshold,
+        lto,
         &target_machine,
-    );
+    )?;
 
     if let Some(parent) = output.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create output directory {}", parent.display()))?;
     }
 
+    // `--emit` files are named after the binary and show the module as it
+    // is linked, after optimization. The object file is the one linked.
+    let mut emitted = Vec::new();
+    for &kind in &options.emit {
+        let path = output.with_extension(kind.extension());
+        match kind {
+            EmitKind::LlvmIr => compiler
+                .module
+                .print_to_file(&path)
+                .map_err(|e| anyhow!("failed to write LLVM IR to {}: {e}", path.display()))?,
+            // Code generation may change the module it runs on, so the
+            // assembly comes from a copy
+            EmitKind::Asm => target_machine
+                .write_to_file(&compiler.module.clone(), FileType::Assembly, &path)
+                .map_err(|e| anyhow!("failed to write assembly to {}: {e}", path.display()))?,
+            EmitKind::Obj => {}
+        }
+        emitted.push(path);
+    }
+
     let object_path = output.with_extension("o");
-    target_machine
-        .write_to_file(&compiler.module, FileType::Object, &object_path)
-        .map_err(|e| {
-            anyhow!(
-                "failed to emit object file at {}: {e}",
-                object_path.display()
-            )
-        })?;
+    if lto.is_enabled() || pgo.is_enabled() {
+        if !compiler.module.write_bitcode_to_path(&object_path) {
+            bail!("failed to emit bitcode at {}", object_path.display());
+        }
+    } else {
+        target_machine
+            .write_to_file(&compiler.module, FileType::Object, &object_path)
+            .map_err(|e| {
+                anyhow!(
+                    "failed to emit object file at {}: {e}",
+                    object_path.display()
+                )
+            })?;
+    }
 
-    // Build and link the runtime static library (check once)
-    let runtime_lib = find_runtime_library(&runtime_triple)?;
-    let use_rust_runtime = runtime_lib.exists();
+    // Build and link the runtime static library (check once). WASI programs
+    // get the C runtime instead, which needs neither threads nor an OS.
+    let (runtime_lib, use_rust_runtime) = if runtime_triple.is_wasi() {
+        (PathBuf::new(), false)
+    } else {
+        let runtime_lib = find_runtime_library(&runtime_triple, is_native_target)?;
+        let use_rust_runtime = runtime_lib.exists();
+        (runtime_lib, use_rust_runtime)
+    };
 
     // Create a C runtime shim for the FFI functions (target-specific)
-    let runtime_c = if runtime_triple.is_wasm() {
+    let runtime_c = if runtime_triple.is_wasm() && !runtime_triple.is_wasi() {
         None
     } else {
         let runtime_c = output.with_extension("runtime.c");
-        let runtime_c_content = if use_rust_runtime {
+        let mut runtime_c_content = if use_rust_runtime {
             RUNTIME_CODE_SHIM.to_string()
         } else if runtime_triple.is_wasm() {
             RUNTIME_CODE_WASM.to_string()
@@ -250,6 +413,9 @@ pub fn build_executable(
         } else {
             RUNTIME_CODE_STANDARD.to_string()
         };
+        if compiler.sanitize_address {
+            runtime_c_content.push_str(ASAN_DEFAULT_OPTIONS);
+        }
         fs::write(&runtime_c, runtime_c_content).context("failed to write runtime C file")?;
         Some(runtime_c)
     };
@@ -257,12 +423,21 @@ pub fn build_executable(
     // Compile runtime C file (target-specific)
     let runtime_o = if let Some(ref rt_c) = runtime_c {
         let runtime_o = output.with_extension("runtime.o");
-        let c_compiler = runtime_triple.c_compiler();
+        let c_compiler = target_driver(
+            runtime_triple.c_compiler(),
+            !is_native_target || lto_flag.is_some() || sanitize_flag.is_some(),
+        );
         let mut cc = Command::new(&c_compiler);
 
         // Add target-specific compiler flags
         cc.arg("-c");
-        if runtime_triple.needs_pic() && !runtime_triple.is_windows() {
+        if let Some(flag) = lto_flag {
+            cc.arg(flag);
+        }
+        if let Some(flag) = &sanitize_flag {
+            cc.arg(flag).arg("-fno-omit-frame-pointer");
+        }
+        if runtime_triple.needs_pic() && !runtime_triple.is_windows() && !runtime_triple.is_wasm() {
             cc.arg("-fPIC");
         }
 
@@ -275,6 +450,12 @@ pub fn build_executable(
         if !is_native_target {
             let compiler_target_flag = preferred_target_flag(&c_compiler);
             cc.arg(compiler_target_flag).arg(&triple_str);
+            if let Some(sysroot) = target_sysroot(&runtime_triple) {
+                cc.arg("--sysroot").arg(sysroot);
+            }
+        }
+        if runtime_triple.is_wasi() {
+            cc.arg("-O2");
         }
 
         cc.arg(rt_c).arg("-o").arg(&runtime_o);
@@ -291,11 +472,26 @@ pub fn build_executable(
     };
 
     // Link the object files together (target-specific)
-    let linker = runtime_triple.linker();
+    let linker = target_driver(
+        runtime_triple.linker(),
+        !is_native_target || lto_flag.is_some() || pgo.is_enabled() || sanitize_flag.is_some(),
+    );
     let mut cc = Command::new(&linker);
 
     // Add target-specific linker flags
-    if runtime_triple.is_wasm() {
+    if runtime_triple.is_wasi() {
+        // A WASI command, linked by clang against wasi-libc
+        let linker_target_flag = preferred_target_flag(&linker);
+        cc.arg(linker_target_flag).arg(&triple_str);
+        if let Some(sysroot) = target_sysroot(&runtime_triple) {
+            cc.arg("--sysroot").arg(sysroot);
+        }
+        cc.arg(&object_path);
+        if let Some(ref rt_o) = runtime_o {
+            cc.arg(rt_o);
+        }
+        cc.arg("-o").arg(output);
+    } else if runtime_triple.is_wasm() {
         // WebAssembly linking
         let linker_target_flag = preferred_target_flag(&linker);
         cc.arg(linker_target_flag)
@@ -310,6 +506,14 @@ pub fn build_executable(
         if !is_native_target {
             let linker_target_flag = preferred_target_flag(&linker);
             cc.arg(linker_target_flag).arg(&triple_str);
+            // The host's own linker only writes binaries for the host, lld
+            // writes them for any platform
+            if env::var_os("OTTER_LINKER").is_none() {
+                cc.arg("-fuse-ld=lld");
+            }
+            if let Some(sysroot) = target_sysroot(&runtime_triple) {
+                cc.arg("--sysroot").arg(sysroot);
+            }
         }
 
         // Add macOS version minimum and suppress compatibility warnings
@@ -333,8 +537,17 @@ pub fn build_executable(
             cc.arg(format!("-Wl,/LIBPATH:{}", path.display()));
         }
 
-        // Always link the generated object first
-        cc.arg(&object_path);
+        // Always link the generated object first. For PGO it is bitcode
+        // that clang compiles, instrumenting it or reading the profile.
+        if pgo.is_enabled() {
+            cc.arg("-x")
+                .arg("ir")
+                .arg(&object_path)
+                .arg("-x")
+                .arg("none");
+        } else {
+            cc.arg(&object_path);
+        }
 
         if let Some(ref rt_o) = runtime_o {
             cc.arg(rt_o);
@@ -351,28 +564,38 @@ pub fn build_executable(
         cc.arg(&flag);
     }
 
-    if options.enable_lto && !runtime_triple.is_wasm() {
-        cc.arg("-flto");
-        // Note: clang doesn't support -flto=O2/O3, use -O flags instead
-        match options.opt_level {
-            CodegenOptLevel::None => {}
-            CodegenOptLevel::Default => {
-                cc.arg("-O2");
-            }
-            CodegenOptLevel::Aggressive => {
-                cc.arg("-O3");
-            }
+    if let Some(flag) = lto_flag {
+        cc.arg(flag);
+        // Bitcode needs a linker that reads it; lld does on every platform
+        // but macOS, whose own linker does
+        if runtime_triple.os != "darwin"
+            && is_native_target
+            && env::var_os("OTTER_LINKER").is_none()
+        {
+            cc.arg("-fuse-ld=lld");
         }
     }
+    if lto_flag.is_some() || pgo.is_enabled() {
+        // Note: clang doesn't support -flto=O2/O3, use -O flags instead
+        cc.arg(options.opt_level.clang_flag());
+    }
 
-    // PGO support: if profile file is provided, use it for optimization
-    if options.enable_pgo && !runtime_triple.is_wasm() {
-        if let Some(ref profile_file) = options.pgo_profile_file {
-            cc.arg("-fprofile-use");
-            cc.arg(profile_file);
-        } else {
-            // Generate profile instrumentation
-            cc.arg("-fprofile-instr-generate");
+    // Links the sanitizer runtimes, and under PGO instruments the bitcode
+    if let Some(flag) = &sanitize_flag {
+        cc.arg(flag);
+    }
+
+    match &pgo {
+        PgoMode::Off => {}
+        PgoMode::Generate { dir } => {
+            let mut flag = std::ffi::OsString::from("-fprofile-generate=");
+            flag.push(dir);
+            cc.arg(flag);
+        }
+        PgoMode::Use { profile } => {
+            let mut flag = std::ffi::OsString::from("-fprofile-use=");
+            flag.push(profile);
+            cc.arg(flag);
         }
     }
 
@@ -465,9 +688,27 @@ pub fn build_executable(
     let status = cc.status().context("failed to invoke system linker (cc)")?;
 
     if !status.success() {
+        if lto.is_enabled() {
+            bail!(
+                "linker invocation failed with status {status} (--lto={lto} needs clang and lld; \
+                 link without it with --lto=off)"
+            );
+        }
         bail!("linker invocation failed with status {status}");
     }
 
+    // The macOS linker leaves the DWARF in the object file; gather it into a
+    // .dSYM bundle next to the binary before the object is removed
+    if options.debug_info && runtime_triple.os == "darwin" {
+        let status = Command::new("dsymutil")
+            .arg(output)
+            .status()
+            .context("failed to invoke dsymutil")?;
+        if !status.success() {
+            bail!("dsymutil failed with status {status}");
+        }
+    }
+
     // Clean up temporary files
     if let Some(ref rt_c) = runtime_c {
         fs::remove_file(rt_c)?;
@@ -476,11 +717,17 @@ pub fn build_executable(
         fs::remove_file(rt_o)?;
     }
 
-    fs::remove_file(&object_path)?;
+    if !options.emit.contains(&EmitKind::Obj) {
+        fs::remove_file(&object_path)?;
+    }
 
     Ok(BuildArtifact {
         binary: output.to_path_buf(),
         ir: compiler.cached_ir.take(),
+        opt_report: options
+            .emit_opt_report
+            .then(|| std::mem::take(&mut compiler.opt_report)),
+        emitted,
     })
 }
 
@@ -525,8 +772,16 @@ pub fn build_shared_library(
         enum_layouts.clone(),
         Some(runtime_triple.clone()),
     );
+    compiler.strip_asserts = options.strip_asserts;
+    compiler.preempt_loops = options.preempt_loops;
+    compiler.profile_functions = options.profile_functions;
+    compiler.source_lines = options.source.clone();
+    if options.debug_info {
+        compiler.enable_debug_info(!matches!(options.opt_level, CodegenOptLevel::None));
+    }
 
     compiler.lower_program(program, false)?; // Don't require main for shared libraries
+    compiler.finalize_debug_info();
     compiler
         .module
         .verify()
@@ -571,11 +826,12 @@ pub fn build_shared_library(
 
     compiler.run_default_passes(
         options.opt_level,
-        options.enable_pgo,
-        options.pgo_profile_file.as_deref(),
+        options.passes.as_deref(),
+        &PgoMode::Off,
         options.inline_threshold,
+        LtoMode::Off,
         &target_machine,
-    );
+    )?;
 
     if let Some(parent) = output.parent() {
         fs::create_dir_all(parent)
@@ -660,7 +916,7 @@ pub fn build_shared_library(
     };
 
     // Build and check runtime static library (check once)
-    let runtime_lib = find_runtime_library(&runtime_triple)?;
+    let runtime_lib = find_runtime_library(&runtime_triple, true)?;
     let use_rust_runtime = runtime_lib.exists();
 
     // Link as shared library (target-specific)
@@ -668,7 +924,17 @@ pub fn build_shared_library(
     let mut cc = Command::new(&linker);
 
     let linker_target_flag = preferred_target_flag(&linker);
-    if runtime_triple.is_wasm() {
+    if runtime_triple.is_wasi() {
+        // A reactor module with no `_start`, for a host to call into
+        cc.arg(linker_target_flag)
+            .arg(&triple_str)
+            .arg("-nostartfiles")
+            .arg("-Wl,--no-entry")
+            .arg("-Wl,--export-dynamic")
+            .arg("-o")
+            .arg(&lib_path)
+            .arg(&object_path);
+    } else if runtime_triple.is_wasm() {
         cc.arg(linker_target_flag)
             .arg(&triple_str)
             .arg("--no-entry")
@@ -702,29 +968,10 @@ pub fn build_shared_library(
         cc.arg(&flag);
     }
 
-    if options.enable_lto && !runtime_triple.is_wasm() {
+    if options.lto.is_enabled() && !runtime_triple.is_wasm() {
         cc.arg("-flto");
         // Note: clang doesn't support -flto=O2/O3, use -O flags instead
-        match options.opt_level {
-            CodegenOptLevel::None => {}
-            CodegenOptLevel::Default => {
-                cc.arg("-O2");
-            }
-            CodegenOptLevel::Aggressive => {
-                cc.arg("-O3");
-            }
-        }
-    }
-
-    // PGO support: if profile file is provided, use it for optimization
-    if options.enable_pgo && !runtime_triple.is_wasm() {
-        if let Some(ref profile_file) = options.pgo_profile_file {
-            cc.arg("-fprofile-use");
-            cc.arg(profile_file);
-        } else {
-            // Generate profile instrumentation
-            cc.arg("-fprofile-instr-generate");
-        }
+        cc.arg(options.opt_level.clang_flag());
     }
 
     for lib in &bridge_libraries {
@@ -805,5 +1052,9 @@ pub fn build_shared_library(
     Ok(BuildArtifact {
         binary: lib_path,
         ir: compiler.cached_ir.take(),
+        opt_report: options
+            .emit_opt_report
+            .then(|| std::mem::take(&mut compiler.opt_report)),
+        emitted: Vec::new(),
     })
 }
@@ -0,0 +1,268 @@
+//! DWARF debug info for `otter build -g`
+//!
+//! Every compiled function gets a subprogram, every statement the line and
+//! column it starts at, and every parameter and `let` binding a variable
+//! description of its stack slot, so a debugger steps through `.ot` lines and
+//! shows locals. Lists, maps and other values the runtime owns show as
+//! 64-bit handles; struct and tuple values are not described yet.
+
+use inkwell::AddressSpace;
+use inkwell::debug_info::{
+    AsDIScope, DICompileUnit, DIFile, DIFlags, DIFlagsConstants, DILocation, DIType,
+    DWARFEmissionKind, DWARFSourceLanguage, DebugInfoBuilder,
+};
+use inkwell::module::FlagBehavior;
+use inkwell::values::{FunctionValue, PointerValue};
+
+use crate::llvm::compiler::Compiler;
+use crate::llvm::compiler::types::OtterType;
+use otterc_span::Span;
+
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_SIGNED_CHAR: u32 = 0x06;
+const DW_ATE_UNSIGNED: u32 = 0x07;
+
+pub(crate) struct DebugInfo<'ctx> {
+    builder: DebugInfoBuilder<'ctx>,
+    unit: DICompileUnit<'ctx>,
+    file: DIFile<'ctx>,
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Starts describing the module to debuggers. Locations come from
+    /// `source_lines`, so without a source nothing is emitted.
+    pub(crate) fn enable_debug_info(&mut self, optimized: bool) {
+        let Some(lines) = &self.source_lines else {
+            return;
+        };
+        let path = std::path::Path::new(lines.name());
+        let filename = path.file_name().map_or_else(
+            || lines.name().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let directory = path
+            .parent()
+            .and_then(|dir| std::path::absolute(dir).ok())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let i32_type = self.context.i32_type();
+        self.module.add_basic_value_flag(
+            "Debug Info Version",
+            FlagBehavior::Warning,
+            i32_type.const_int(3, false),
+        );
+        self.module.add_basic_value_flag(
+            "Dwarf Version",
+            FlagBehavior::Warning,
+            i32_type.const_int(4, false),
+        );
+
+        // DWARF has no language code for Otter; C makes debuggers show values
+        // as they are stored
+        let (builder, unit) = self.module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::C,
+            &filename,
+            &directory,
+            concat!("otterc ", env!("CARGO_PKG_VERSION")),
+            optimized,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+        let file = unit.get_file();
+        self.debug_info = Some(DebugInfo {
+            builder,
+            unit,
+            file,
+        });
+    }
+
+    /// Resolves the references between the debug info emitted so far, before
+    /// the module is verified
+    pub(crate) fn finalize_debug_info(&self) {
+        if let Some(debug) = &self.debug_info {
+            debug.builder.finalize();
+        }
+    }
+
+    /// Gives `function` a subprogram starting at `span` and moves the builder
+    /// into it. Returns the location to hand back to
+    /// [`leave_debug_function`](Self::leave_debug_function) once the body is
+    /// compiled. Without a span the function is left undescribed.
+    pub(crate) fn enter_debug_function(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        name: &str,
+        span: Option<Span>,
+    ) -> Option<DILocation<'ctx>> {
+        let saved = self.builder.get_current_debug_location();
+        self.builder.unset_current_debug_location();
+        let (Some(debug), Some(span)) = (&self.debug_info, span) else {
+            return saved;
+        };
+        let (line, _) = self.debug_line_col(span);
+        let subroutine_type =
+            debug
+                .builder
+                .create_subroutine_type(debug.file, None, &[], DIFlags::ZERO);
+        let subprogram = debug.builder.create_function(
+            debug.unit.as_debug_info_scope(),
+            name,
+            function.get_name().to_str().ok(),
+            debug.file,
+            line,
+            subroutine_type,
+            true,
+            true,
+            line,
+            DIFlags::ZERO,
+            false,
+        );
+        function.set_subprogram(subprogram);
+        self.set_debug_location(span);
+        saved
+    }
+
+    /// Puts the builder back where it was before
+    /// [`enter_debug_function`](Self::enter_debug_function)
+    pub(crate) fn leave_debug_function(&self, saved: Option<DILocation<'ctx>>) {
+        match saved {
+            Some(location) => self.builder.set_current_debug_location(location),
+            None => self.builder.unset_current_debug_location(),
+        }
+    }
+
+    /// Attributes the instructions built from here on to `span`
+    pub(crate) fn set_debug_location(&self, span: Span) {
+        let Some(debug) = &self.debug_info else {
+            return;
+        };
+        let subprogram = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .and_then(|function| function.get_subprogram());
+        let Some(subprogram) = subprogram else {
+            self.builder.unset_current_debug_location();
+            return;
+        };
+        let (line, column) = self.debug_line_col(span);
+        let location = debug.builder.create_debug_location(
+            self.context,
+            line,
+            column,
+            subprogram.as_debug_info_scope(),
+            None,
+        );
+        self.builder.set_current_debug_location(location);
+    }
+
+    /// Describes `storage` as the variable `name` bound at `span`, or as the
+    /// `arg_no`th parameter (1-based) of the current function
+    pub(crate) fn declare_debug_variable(
+        &self,
+        name: &str,
+        storage: PointerValue<'ctx>,
+        ty: &OtterType,
+        span: Span,
+        arg_no: Option<u32>,
+    ) {
+        let Some(debug) = &self.debug_info else {
+            return;
+        };
+        let Some(block) = self.builder.get_insert_block() else {
+            return;
+        };
+        let Some(subprogram) = block.get_parent().and_then(|f| f.get_subprogram()) else {
+            return;
+        };
+        let Some(di_type) = self.debug_type(debug, ty) else {
+            return;
+        };
+        let scope = subprogram.as_debug_info_scope();
+        let (line, column) = self.debug_line_col(span);
+        let variable = match arg_no {
+            Some(arg_no) => debug.builder.create_parameter_variable(
+                scope,
+                name,
+                arg_no,
+                debug.file,
+                line,
+                di_type,
+                true,
+                DIFlags::ZERO,
+            ),
+            None => debug.builder.create_auto_variable(
+                scope,
+                name,
+                debug.file,
+                line,
+                di_type,
+                true,
+                DIFlags::ZERO,
+                0,
+            ),
+        };
+        let location = debug
+            .builder
+            .create_debug_location(self.context, line, column, scope, None);
+        debug
+            .builder
+            .insert_declare_at_end(storage, Some(variable), None, location, block);
+    }
+
+    /// How a debugger should read a value of `ty`, if it has one
+    fn debug_type(&self, debug: &DebugInfo<'ctx>, ty: &OtterType) -> Option<DIType<'ctx>> {
+        let basic = |name: &str, bits: u64, encoding: u32| {
+            debug
+                .builder
+                .create_basic_type(name, bits, encoding, DIFlags::ZERO)
+                .ok()
+                .map(|ty| ty.as_type())
+        };
+        match ty {
+            OtterType::Bool => basic("bool", 8, DW_ATE_BOOLEAN),
+            OtterType::F64 => basic("f64", 64, DW_ATE_FLOAT),
+            OtterType::Str => {
+                let char_type = basic("char", 8, DW_ATE_SIGNED_CHAR)?;
+                Some(
+                    debug
+                        .builder
+                        .create_pointer_type("str", char_type, 64, 0, AddressSpace::default())
+                        .as_type(),
+                )
+            }
+            OtterType::Unit | OtterType::Struct(_) | OtterType::Tuple(_) => None,
+            OtterType::Opaque | OtterType::List(_) | OtterType::Map => {
+                basic("handle", 64, DW_ATE_UNSIGNED)
+            }
+            ty => {
+                let (bits, signed) = ty.int_layout()?;
+                let name = format!("{}{bits}", if signed { 'i' } else { 'u' });
+                let encoding = if signed {
+                    DW_ATE_SIGNED
+                } else {
+                    DW_ATE_UNSIGNED
+                };
+                basic(&name, u64::from(bits), encoding)
+            }
+        }
+    }
+
+    fn debug_line_col(&self, span: Span) -> (u32, u32) {
+        self.source_lines.as_ref().map_or((0, 0), |lines| {
+            let (line, column) = lines.line_col(span.start());
+            (line as u32, column as u32)
+        })
+    }
+}
@@ -2,13 +2,19 @@ use anyhow::{Result, anyhow, bail};
 use inkwell::AddressSpace;
 use inkwell::IntPredicate;
 use inkwell::types::{BasicTypeEnum, PointerType, StructType};
-use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue, StructValue,
+};
 use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
 
 use crate::llvm::compiler::Compiler;
 use crate::llvm::compiler::types::{EvaluatedValue, FunctionContext, OtterType, Variable};
-use otterc_ast::nodes::{BinaryOp, Block, Expr, FStringPart, Literal, Node, Statement, UnaryOp};
-use otterc_typecheck::TypeInfo;
+use otterc_ast::nodes::{
+    BinaryOp, Block, Expr, FStringPart, Function, Literal, Node, Param, Statement, UnaryOp,
+};
+use otterc_symbol::registry::FfiType;
+use otterc_typecheck::{ConstValue, TypeInfo};
 
 struct CapturedVariable<'ctx> {
     name: String,
@@ -35,6 +41,78 @@ impl<'ctx> Compiler<'ctx> {
         })
     }
 
+    /// Lifts `fn(params) body` into a function of its own, named after the
+    /// enclosing function, and evaluates to its address like a function name
+    fn eval_lambda_expr(
+        &mut self,
+        params: &[Node<Param>],
+        body: &Node<Expr>,
+        ctx: &FunctionContext<'ctx>,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let mut names = BTreeSet::new();
+        self.collect_captured_names(body.as_ref(), ctx, &mut names);
+        for param in params {
+            names.remove(param.as_ref().name.as_ref());
+        }
+        if let Some(name) = names.first() {
+            bail!(
+                "anonymous functions cannot capture `{name}`; pass it as an argument or use a nested function"
+            );
+        }
+
+        let enclosing = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or_else(|| anyhow!("anonymous function outside of a function body"))?;
+        let enclosing_name = enclosing.get_name().to_string_lossy();
+        let enclosing_name = if enclosing_name == "otter_entry" {
+            "main"
+        } else {
+            enclosing_name.as_ref()
+        };
+        let index = self.lambda_counter.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{enclosing_name}.lambda{index}");
+
+        let span = *body.span();
+        let ret_ty = self
+            .expr_type(body.as_ref())
+            .filter(|ty| !matches!(ty, TypeInfo::Unit))
+            .and_then(|ty| self.type_argument_annotation(ty))
+            .map(|ty| Node::new(ty, span));
+        let statement = if ret_ty.is_some() {
+            Statement::Return(Some(body.clone()))
+        } else {
+            Statement::Expr(body.clone())
+        };
+        let func = Function::new(
+            name.clone(),
+            params.to_vec(),
+            ret_ty,
+            Node::new(Block::new(vec![Node::new(statement, span)]), span),
+        );
+        // The body is a copy, so its types are found through its spans
+        self.record_function_spans(&func);
+        let function = self.declare_function(&func, &name, 0)?;
+
+        let resume = self.builder.get_insert_block();
+        let compiled = self.compile_function_body(&func, function, &[]);
+        if let Some(block) = resume {
+            self.builder.position_at_end(block);
+        }
+        compiled?;
+
+        let address = self.builder.build_ptr_to_int(
+            function.as_global_value().as_pointer_value(),
+            self.context.i64_type(),
+            &format!("{name}_addr"),
+        )?;
+        Ok(EvaluatedValue::with_value(
+            address.into(),
+            OtterType::Opaque,
+        ))
+    }
+
     fn eval_spawn_expr(
         &mut self,
         expr: &Expr,
@@ -125,6 +203,11 @@ impl<'ctx> Compiler<'ctx> {
         let entry = self.context.append_basic_block(function, "entry");
         let prev_block = self.builder.get_insert_block();
         self.builder.position_at_end(entry);
+        let span = self
+            .expr_spans
+            .get(&(expr as *const Expr as usize))
+            .copied();
+        let enclosing_location = self.enter_debug_function(function, &fn_name, span);
 
         let mut wrapper_ctx = FunctionContext::new();
         let mut raw_ptr: Option<inkwell::values::PointerValue<'ctx>> = None;
@@ -171,6 +254,7 @@ impl<'ctx> Compiler<'ctx> {
         }
 
         self.builder.build_return(None)?;
+        self.leave_debug_function(enclosing_location);
 
         if let Some(block) = prev_block {
             self.builder.position_at_end(block);
@@ -191,6 +275,16 @@ impl<'ctx> Compiler<'ctx> {
                 if ctx.get(name).is_some() {
                     captures.insert(name.clone());
                 }
+                // Calling a nested function passes it the variables it captured
+                if let Some(nested) = self.nested_functions.get(name) {
+                    captures.extend(
+                        nested
+                            .captures
+                            .iter()
+                            .filter(|capture| ctx.get(capture).is_some())
+                            .cloned(),
+                    );
+                }
             }
             Expr::Member { object, .. } => {
                 self.collect_captured_names(object.as_ref().as_ref(), ctx, captures);
@@ -205,7 +299,7 @@ impl<'ctx> Compiler<'ctx> {
                 self.collect_captured_names(left.as_ref().as_ref(), ctx, captures);
                 self.collect_captured_names(right.as_ref().as_ref(), ctx, captures);
             }
-            Expr::Unary { expr, .. } => {
+            Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => {
                 self.collect_captured_names(expr.as_ref().as_ref(), ctx, captures);
             }
             Expr::If {
@@ -271,7 +365,7 @@ impl<'ctx> Compiler<'ctx> {
             }
             Expr::FString { parts } => {
                 for part in parts {
-                    if let FStringPart::Expr(expr) = part.as_ref() {
+                    if let FStringPart::Expr(expr, _) = part.as_ref() {
                         self.collect_captured_names(expr.as_ref(), ctx, captures);
                     }
                 }
@@ -284,10 +378,12 @@ impl<'ctx> Compiler<'ctx> {
             Expr::Await(inner) | Expr::Spawn(inner) => {
                 self.collect_captured_names(inner.as_ref().as_ref(), ctx, captures);
             }
+            // Anonymous functions cannot capture, so they need nothing from here
+            Expr::Lambda { .. } => {}
         }
     }
 
-    fn collect_captured_names_in_block(
+    pub(super) fn collect_captured_names_in_block(
         &self,
         block: &Block,
         ctx: &FunctionContext<'ctx>,
@@ -305,12 +401,25 @@ impl<'ctx> Compiler<'ctx> {
         captures: &mut BTreeSet<String>,
     ) {
         match stmt {
+            Statement::Assignment { name, expr } => {
+                if ctx.get(name.as_ref()).is_some() {
+                    captures.insert(name.as_ref().clone());
+                }
+                self.collect_captured_names(expr.as_ref(), ctx, captures);
+            }
             Statement::Expr(expr)
             | Statement::Let { expr, .. }
-            | Statement::Assignment { expr, .. }
-            | Statement::Return(Some(expr)) => {
+            | Statement::Const { expr, .. }
+            | Statement::Return(Some(expr))
+            | Statement::Defer(expr) => {
                 self.collect_captured_names(expr.as_ref(), ctx, captures);
             }
+            Statement::Assert { cond, message } => {
+                self.collect_captured_names(cond.as_ref(), ctx, captures);
+                if let Some(message) = message {
+                    self.collect_captured_names(message.as_ref(), ctx, captures);
+                }
+            }
             Statement::If {
                 cond,
                 then_block,
@@ -331,23 +440,30 @@ impl<'ctx> Compiler<'ctx> {
                 self.collect_captured_names(iterable.as_ref(), ctx, captures);
                 self.collect_captured_names_in_block(body.as_ref(), ctx, captures);
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, .. }
+            | Statement::With {
+                expr: cond, body, ..
+            } => {
                 self.collect_captured_names(cond.as_ref(), ctx, captures);
                 self.collect_captured_names_in_block(body.as_ref(), ctx, captures);
             }
             Statement::Block(block) => {
                 self.collect_captured_names_in_block(block.as_ref(), ctx, captures);
             }
+            Statement::Function(func) => {
+                self.collect_captured_names_in_block(func.as_ref().body.as_ref(), ctx, captures);
+            }
             Statement::Return(None)
-            | Statement::Break
-            | Statement::Continue
+            | Statement::Break(_)
+            | Statement::Continue(_)
             | Statement::Pass
             | Statement::Use { .. }
             | Statement::PubUse { .. }
             | Statement::Struct { .. }
             | Statement::Enum { .. }
             | Statement::TypeAlias { .. }
-            | Statement::Function(_) => {}
+            | Statement::Protocol { .. }
+            | Statement::Init(_) => {}
         }
     }
 
@@ -412,6 +528,8 @@ impl<'ctx> Compiler<'ctx> {
                             value: None,
                         })
                     }
+                } else if let Some((value, ty)) = self.constants.get(name).cloned() {
+                    self.eval_const_value(value, ty)
                 } else {
                     bail!("Variable {} not found", name);
                 }
@@ -420,7 +538,15 @@ impl<'ctx> Compiler<'ctx> {
                 self.eval_binary_expr(left.as_ref().as_ref(), op, right.as_ref().as_ref(), ctx)
             }
             Expr::Unary { op, expr } => self.eval_unary_expr(op, expr.as_ref().as_ref(), ctx),
-            Expr::Call { func: _, args: _ } => self.eval_call_expr(expr, ctx),
+            Expr::Cast { expr, ty } => {
+                self.eval_cast_expr(expr.as_ref().as_ref(), ty.as_ref(), ctx)
+            }
+            Expr::Call { func: _, args: _ } => {
+                match self.hoisted.get(&(expr as *const Expr as usize)) {
+                    Some(value) => Ok(value.clone()),
+                    None => self.eval_call_expr(expr, ctx),
+                }
+            }
             Expr::Member { object, field } => {
                 if let Some(value) =
                     self.try_build_enum_member(expr, object.as_ref().as_ref(), field, ctx)?
@@ -447,11 +573,9 @@ impl<'ctx> Compiler<'ctx> {
                         let idx = info.field_indices.get(field).copied().ok_or_else(|| {
                             anyhow!("struct '{}' has no field '{}'", info.name, field)
                         })?;
-                        let extracted = self
-                            .builder
-                            .build_extract_value(struct_value, idx as u32, field)
-                            .map_err(|e| anyhow!("failed to extract field '{}': {e}", field))?;
                         let field_ty = info.field_types[idx].clone();
+                        let extracted =
+                            self.build_struct_field_get(struct_id, struct_value, idx, field)?;
                         Ok(EvaluatedValue::with_value(extracted, field_ty))
                     } else {
                         bail!(
@@ -462,9 +586,15 @@ impl<'ctx> Compiler<'ctx> {
                 }
             }
             Expr::Struct { name, fields } => {
-                let (struct_id, _) = self
-                    .struct_info_by_name(name)
-                    .ok_or_else(|| anyhow!("unknown struct type '{}'", name))?;
+                if let Some((_, variant_name)) = name.split_once('.') {
+                    return self.eval_named_enum_variant(expr, variant_name, fields, ctx);
+                }
+                // A generic struct is built with the layout for its type arguments
+                let struct_id = match self.expr_type(expr) {
+                    Some(TypeInfo::Struct { args, .. }) => self.struct_id_for(name, args),
+                    _ => self.struct_id(name),
+                }
+                .ok_or_else(|| anyhow!("unknown struct type '{}'", name))?;
                 let (struct_name, struct_ty) = {
                     let info = self.struct_info(struct_id);
                     (info.name.clone(), info.ty)
@@ -488,10 +618,7 @@ impl<'ctx> Compiler<'ctx> {
                     let coerced =
                         self.coerce_type(raw_value, field_value.ty.clone(), expected_ty)?;
                     aggregate = self
-                        .builder
-                        .build_insert_value(aggregate, coerced, idx as u32, field_name)
-                        .map_err(|e| anyhow!("failed to insert field '{}': {e}", field_name))?
-                        .into_struct_value();
+                        .build_struct_field_set(struct_id, aggregate, idx, coerced, field_name)?;
                 }
 
                 Ok(EvaluatedValue::with_value(
@@ -541,6 +668,7 @@ impl<'ctx> Compiler<'ctx> {
             ),
             Expr::Await(expr) => self.eval_await_expr(expr.as_ref().as_ref(), ctx),
             Expr::Spawn(expr) => self.eval_spawn_expr(expr.as_ref().as_ref(), ctx),
+            Expr::Lambda { params, body } => self.eval_lambda_expr(params, body, ctx),
             _ => bail!("Expression type not implemented: {:?}", expr),
         }
     }
@@ -587,15 +715,29 @@ impl<'ctx> Compiler<'ctx> {
                 .context
                 .append_basic_block(function, &format!("match_arm_body_{}", i));
 
+            // A guarded arm falls through to the next check when the guard is false
+            let guard_bb = arm.as_ref().guard.as_ref().map(|_| {
+                self.context
+                    .append_basic_block(function, &format!("match_arm_guard_{}", i))
+            });
+
             self.compile_pattern_match(
                 &arm.as_ref().pattern,
                 &matched_val,
                 matched_type_info.clone(),
-                body_bb,
+                guard_bb.unwrap_or(body_bb),
                 next_check_bb,
                 ctx,
             )?;
 
+            if let (Some(guard), Some(guard_bb)) = (&arm.as_ref().guard, guard_bb) {
+                self.builder.position_at_end(guard_bb);
+                let guard_val = self.eval_expr(guard.as_ref(), ctx)?;
+                let guard_bool = self.to_bool_value(guard_val)?;
+                self.builder
+                    .build_conditional_branch(guard_bool, body_bb, next_check_bb)?;
+            }
+
             self.builder.position_at_end(body_bb);
             let body_val = self.lower_block_expression(&arm.as_ref().body, function, ctx)?;
 
@@ -697,39 +839,58 @@ impl<'ctx> Compiler<'ctx> {
                 Ok(())
             }
             Pattern::Literal(lit) => {
-                // Get type info from the pattern's expression if available
-                let type_info = None; // Patterns don't have type info in expr_types, use None
-                let lit_val = self.eval_literal(lit.as_ref(), type_info)?;
-                let is_equal = self.build_equality_check(matched_val, &lit_val)?;
+                let is_equal = if let Literal::String(text) = lit.as_ref() {
+                    self.build_string_pattern_check(matched_val, text)?
+                } else if let Literal::Symbol(_) = lit.as_ref() {
+                    let symbol = self.eval_literal(lit.as_ref(), None)?;
+                    match (matched_val.value, symbol.value) {
+                        (Some(matched), Some(symbol)) => self
+                            .call_ffi_returning_value(
+                                "symbol.equal",
+                                vec![matched, symbol],
+                                "sym_match",
+                            )?
+                            .into_int_value(),
+                        _ => self.context.bool_type().const_zero(),
+                    }
+                } else {
+                    // Get type info from the pattern's expression if available
+                    let type_info = None; // Patterns don't have type info in expr_types, use None
+                    let lit_val = self.eval_literal(lit.as_ref(), type_info)?;
+                    self.build_equality_check(matched_val, &lit_val)?
+                };
                 self.builder
                     .build_conditional_branch(is_equal, success_bb, fail_bb)?;
                 Ok(())
             }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let in_range = self.build_range_check(matched_val, start, end, *inclusive)?;
+                self.builder
+                    .build_conditional_branch(in_range, success_bb, fail_bb)?;
+                Ok(())
+            }
             Pattern::Identifier(name) => {
-                let function = self
-                    .builder
-                    .get_insert_block()
-                    .unwrap()
-                    .get_parent()
-                    .unwrap();
-                let matched_ty = matched_val.ty.clone();
-                let alloca = self.create_entry_block_alloca(function, name, matched_ty.clone())?;
-
-                if let Some(v) = matched_val.value {
-                    self.builder.build_store(alloca, v)?;
-                }
-
-                ctx.insert(
-                    name.clone(),
-                    crate::llvm::compiler::types::Variable {
-                        ptr: alloca,
-                        ty: matched_ty,
-                    },
-                );
-
+                self.bind_pattern_value(name, matched_val, ctx)?;
                 self.builder.build_unconditional_branch(success_bb)?;
                 Ok(())
             }
+            Pattern::Binding { name, pattern } => {
+                // The binding is only visible in the arm, which is entered
+                // exclusively through `success_bb`, so binding before testing is safe
+                self.bind_pattern_value(name, matched_val, ctx)?;
+                self.compile_pattern_match(
+                    pattern,
+                    matched_val,
+                    matched_type,
+                    success_bb,
+                    fail_bb,
+                    ctx,
+                )
+            }
             Pattern::EnumVariant {
                 enum_name,
                 variant,
@@ -801,35 +962,7 @@ impl<'ctx> Compiler<'ctx> {
                             })?
                             .clone();
 
-                        // Use type-specific getter based on field type
-                        let get_field_fn_name = match enum_field_kind(&field_type) {
-                            EnumFieldKind::Int => "runtime.enum.get_i64",
-                            EnumFieldKind::Float => "runtime.enum.get_f64",
-                            EnumFieldKind::Bool => "runtime.enum.get_bool",
-                            EnumFieldKind::Ptr => "runtime.enum.get_ptr",
-                        };
-                        let get_field_fn = self.get_or_declare_ffi_function(get_field_fn_name)?;
-                        let field_idx_val =
-                            self.context.i64_type().const_int(field_idx as u64, false);
-                        let field_val = self
-                            .builder
-                            .build_call(
-                                get_field_fn,
-                                &[handle.into(), field_idx_val.into()],
-                                "field",
-                            )?
-                            .try_as_basic_value()
-                            .left()
-                            .unwrap();
-
-                        // Convert to appropriate OtterType
-                        let field_otter_type = match enum_field_kind(&field_type) {
-                            EnumFieldKind::Int => OtterType::I64,
-                            EnumFieldKind::Float => OtterType::F64,
-                            EnumFieldKind::Bool => OtterType::Bool,
-                            EnumFieldKind::Ptr => OtterType::Opaque,
-                        };
-                        let field_eval = EvaluatedValue::with_value(field_val, field_otter_type);
+                        let field_eval = self.load_enum_field(handle, field_idx, &field_type)?;
 
                         let next_field_bb = if field_idx < fields.len() - 1 {
                             self.context.append_basic_block(
@@ -990,7 +1123,227 @@ impl<'ctx> Compiler<'ctx> {
 
                 Ok(())
             }
+            Pattern::Dict { entries } => {
+                let handle = matched_val
+                    .value
+                    .ok_or_else(|| anyhow!("Dict value is void"))?;
+                let function = self
+                    .builder
+                    .get_insert_block()
+                    .unwrap()
+                    .get_parent()
+                    .unwrap();
+
+                // Read values at the dict's value type so nested patterns compare concretely
+                let value_type = matched_type.as_ref().and_then(|ty| match ty {
+                    TypeInfo::Dict { value, .. } => Some((**value).clone()),
+                    _ => None,
+                });
+                let (get_fn_name, value_ty) = self.map_get_target(
+                    value_type
+                        .as_ref()
+                        .and_then(|ty| self.typeinfo_to_otter_type(ty)),
+                );
+
+                // Keys not named by the pattern are ignored
+                for (idx, (key, value_pattern)) in entries.iter().enumerate() {
+                    let key_val = self.eval_literal(key.as_ref(), None)?;
+                    let key_ptr = self.ensure_string_value(key_val)?;
+                    let has_key = self
+                        .call_ffi_returning_value(
+                            "map.has",
+                            vec![handle, key_ptr],
+                            &format!("dict_has_{}", idx),
+                        )?
+                        .into_int_value();
+
+                    let value_bb = self
+                        .context
+                        .append_basic_block(function, &format!("dict_value_{}", idx));
+                    self.builder
+                        .build_conditional_branch(has_key, value_bb, fail_bb)?;
+                    self.builder.position_at_end(value_bb);
+
+                    let value = self.call_ffi_returning_value(
+                        get_fn_name,
+                        vec![handle, key_ptr],
+                        &format!("dict_get_{}", idx),
+                    )?;
+                    let value_eval = EvaluatedValue::with_value(value, value_ty.clone());
+
+                    let next_bb = self
+                        .context
+                        .append_basic_block(function, &format!("dict_entry_{}", idx + 1));
+                    self.compile_pattern_match(
+                        value_pattern,
+                        &value_eval,
+                        value_type.clone(),
+                        next_bb,
+                        fail_bb,
+                        ctx,
+                    )?;
+                    self.builder.position_at_end(next_bb);
+                }
+
+                self.builder.build_unconditional_branch(success_bb)?;
+                Ok(())
+            }
+            Pattern::Or(alternatives) => {
+                let function = self
+                    .builder
+                    .get_insert_block()
+                    .unwrap()
+                    .get_parent()
+                    .unwrap();
+                let names: Vec<String> = pattern
+                    .as_ref()
+                    .bindings()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+
+                // Each alternative binds into its own slots; copy them into the
+                // first alternative's slots so the arm body sees one set of variables
+                let mut shared: Option<Vec<Variable<'ctx>>> = None;
+                for (idx, alternative) in alternatives.iter().enumerate() {
+                    let is_last = idx + 1 == alternatives.len();
+                    let next_bb = if is_last {
+                        fail_bb
+                    } else {
+                        self.context
+                            .append_basic_block(function, &format!("or_alt_{}", idx + 1))
+                    };
+                    let matched_bb = self
+                        .context
+                        .append_basic_block(function, &format!("or_matched_{}", idx));
+
+                    self.compile_pattern_match(
+                        alternative,
+                        matched_val,
+                        matched_type.clone(),
+                        matched_bb,
+                        next_bb,
+                        ctx,
+                    )?;
+
+                    self.builder.position_at_end(matched_bb);
+                    let bound = names
+                        .iter()
+                        .map(|name| {
+                            ctx.get(name).cloned().ok_or_else(|| {
+                                anyhow!("or-pattern alternative does not bind '{}'", name)
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    if let Some(shared) = &shared {
+                        for ((name, target), source) in names.iter().zip(shared).zip(&bound) {
+                            if target.ty != source.ty {
+                                bail!(
+                                    "or-pattern alternatives bind '{}' with different types",
+                                    name
+                                );
+                            }
+                            if let Some(basic_ty) = self.basic_type(source.ty.clone())? {
+                                let value = self.builder.build_load(basic_ty, source.ptr, name)?;
+                                self.builder.build_store(target.ptr, value)?;
+                            }
+                        }
+                    } else {
+                        shared = Some(bound);
+                    }
+                    self.builder.build_unconditional_branch(success_bb)?;
+
+                    if !is_last {
+                        self.builder.position_at_end(next_bb);
+                    }
+                }
+
+                if alternatives.is_empty() {
+                    self.builder.build_unconditional_branch(fail_bb)?;
+                }
+                for (name, var) in names.into_iter().zip(shared.unwrap_or_default()) {
+                    ctx.insert(name, var);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Stores the matched value in a fresh slot named `name`
+    pub(crate) fn bind_pattern_value(
+        &mut self,
+        name: &str,
+        matched_val: &EvaluatedValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<()> {
+        let function = self
+            .builder
+            .get_insert_block()
+            .unwrap()
+            .get_parent()
+            .unwrap();
+        let matched_ty = matched_val.ty.clone();
+        let alloca = self.create_entry_block_alloca(function, name, matched_ty.clone())?;
+
+        if let Some(v) = matched_val.value {
+            self.builder.build_store(alloca, v)?;
         }
+
+        ctx.insert(
+            name.to_string(),
+            Variable {
+                ptr: alloca,
+                ty: matched_ty,
+            },
+        );
+        Ok(())
+    }
+
+    /// Field `index` of the enum value `handle`, whose declared type is
+    /// `field_type`
+    pub(crate) fn load_enum_field(
+        &mut self,
+        handle: BasicValueEnum<'ctx>,
+        index: usize,
+        field_type: &TypeInfo,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        // Use type-specific getter based on field type
+        let get_field_fn_name = match enum_field_kind(field_type) {
+            EnumFieldKind::Int => "runtime.enum.get_i64",
+            EnumFieldKind::Float => "runtime.enum.get_f64",
+            EnumFieldKind::Bool => "runtime.enum.get_bool",
+            EnumFieldKind::Ptr => "runtime.enum.get_ptr",
+        };
+        let get_field_fn = self.get_or_declare_ffi_function(get_field_fn_name)?;
+        let field_idx_val = self.context.i64_type().const_int(index as u64, false);
+        let field_val = self
+            .builder
+            .build_call(
+                get_field_fn,
+                &[handle.into(), field_idx_val.into()],
+                "field",
+            )?
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+
+        // Convert to appropriate OtterType
+        let field_otter_type = match enum_field_kind(field_type) {
+            EnumFieldKind::Int => self
+                .typeinfo_to_otter_type(field_type)
+                .filter(OtterType::is_int)
+                .unwrap_or(OtterType::I64),
+            EnumFieldKind::Float => OtterType::F64,
+            EnumFieldKind::Bool => OtterType::Bool,
+            EnumFieldKind::Ptr => OtterType::Opaque,
+        };
+        // Integer fields are stored as i64 whatever their declared width
+        let field_val = if field_otter_type.is_int() {
+            self.coerce_type(field_val, OtterType::I64, field_otter_type.clone())?
+        } else {
+            field_val
+        };
+        Ok(EvaluatedValue::with_value(field_val, field_otter_type))
     }
 
     fn resolve_enum_variant_fields(
@@ -1017,7 +1370,7 @@ impl<'ctx> Compiler<'ctx> {
     ) -> Result<IntValue<'ctx>> {
         let lhs_ty = lhs.ty.clone();
         match lhs_ty {
-            OtterType::I64 | OtterType::Bool => {
+            _ if lhs_ty.is_int() || lhs_ty == OtterType::Bool => {
                 let l = lhs.value.unwrap().into_int_value();
                 let r = rhs.value.unwrap().into_int_value();
                 Ok(self
@@ -1061,6 +1414,106 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// Compares the scrutinee against a string literal pattern with the runtime
+    /// string compare. A scrutinee that is not a string never matches.
+    fn build_string_pattern_check(
+        &mut self,
+        value: &EvaluatedValue<'ctx>,
+        text: &str,
+    ) -> Result<IntValue<'ctx>> {
+        let scrutinee = match value.value {
+            Some(v) if value.ty == OtterType::Str || v.is_pointer_value() => v,
+            _ => return Ok(self.context.bool_type().const_zero()),
+        };
+        let pattern = self
+            .builder
+            .build_global_string_ptr(text, "str_pat")?
+            .as_pointer_value();
+        let result = self
+            .call_ffi_returning_value(
+                "std.strings.equal",
+                vec![scrutinee, pattern.into()],
+                "str_eq",
+            )?
+            .into_int_value();
+        let zero = result.get_type().const_zero();
+        Ok(self
+            .builder
+            .build_int_compare(IntPredicate::NE, result, zero, "str_match")?)
+    }
+
+    /// `start <= value < end`, or `start <= value <= end` when `inclusive`
+    fn build_range_check(
+        &mut self,
+        value: &EvaluatedValue<'ctx>,
+        start: &Node<Literal>,
+        end: &Node<Literal>,
+        inclusive: bool,
+    ) -> Result<IntValue<'ctx>> {
+        // Evaluate the bounds at the type of the matched value
+        let bound_type = match value.ty {
+            OtterType::F64 => TypeInfo::F64,
+            ref ty => int_typeinfo(ty).unwrap_or(TypeInfo::I64),
+        };
+        let lo = self.eval_literal(start.as_ref(), Some(&bound_type))?;
+        let hi = self.eval_literal(end.as_ref(), Some(&bound_type))?;
+        let (lo, hi) = match (lo.value, hi.value) {
+            (Some(lo), Some(hi)) => (lo, hi),
+            _ => bail!("Range pattern bounds must be numbers"),
+        };
+        let matched = value
+            .value
+            .ok_or_else(|| anyhow!("Cannot match a range against a void value"))?;
+
+        let (above, below) = match value.ty {
+            ref ty if ty.is_int() => {
+                let v = matched.into_int_value();
+                let signed = !matches!(ty.int_layout(), Some((_, false)));
+                let upper = match (inclusive, signed) {
+                    (true, true) => IntPredicate::SLE,
+                    (false, true) => IntPredicate::SLT,
+                    (true, false) => IntPredicate::ULE,
+                    (false, false) => IntPredicate::ULT,
+                };
+                let lower = if signed {
+                    IntPredicate::SGE
+                } else {
+                    IntPredicate::UGE
+                };
+                (
+                    self.builder
+                        .build_int_compare(lower, v, lo.into_int_value(), "range_lo")?,
+                    self.builder
+                        .build_int_compare(upper, v, hi.into_int_value(), "range_hi")?,
+                )
+            }
+            OtterType::F64 => {
+                let v = matched.into_float_value();
+                let upper = if inclusive {
+                    inkwell::FloatPredicate::OLE
+                } else {
+                    inkwell::FloatPredicate::OLT
+                };
+                (
+                    self.builder.build_float_compare(
+                        inkwell::FloatPredicate::OGE,
+                        v,
+                        lo.into_float_value(),
+                        "range_lo",
+                    )?,
+                    self.builder.build_float_compare(
+                        upper,
+                        v,
+                        hi.into_float_value(),
+                        "range_hi",
+                    )?,
+                )
+            }
+            ref other => bail!("Range patterns are not supported for type {:?}", other),
+        };
+        Ok(self.builder.build_and(above, below, "in_range")?)
+    }
+
     fn lower_block_expression(
         &mut self,
         block: &Node<Block>,
@@ -1068,6 +1521,9 @@ impl<'ctx> Compiler<'ctx> {
         ctx: &mut FunctionContext<'ctx>,
     ) -> Result<EvaluatedValue<'ctx>> {
         for stmt in &block.as_ref().statements {
+            if self.block_is_terminated() {
+                break;
+            }
             if let Statement::Expr(e) = stmt.as_ref() {
                 if std::ptr::eq(stmt, block.as_ref().statements.last().unwrap()) {
                     return self.eval_expr(e.as_ref(), ctx);
@@ -1101,7 +1557,11 @@ impl<'ctx> Compiler<'ctx> {
                 otterc_ast::nodes::FStringPart::Text(s) => {
                     self.eval_literal(&Literal::String(s.clone()), None)?
                 }
-                otterc_ast::nodes::FStringPart::Expr(e) => self.eval_expr(e.as_ref(), ctx)?,
+                otterc_ast::nodes::FStringPart::Expr(e, None) => self.eval_expr(e.as_ref(), ctx)?,
+                otterc_ast::nodes::FStringPart::Expr(e, Some(spec)) => {
+                    let value = self.eval_expr(e.as_ref(), ctx)?;
+                    self.build_formatted_value(value, spec.as_ref())?
+                }
             };
 
             result = self.build_string_concat(result, part_val)?;
@@ -1110,6 +1570,70 @@ impl<'ctx> Compiler<'ctx> {
         Ok(result)
     }
 
+    /// Formats `value` with the format specifier of an f-string interpolation,
+    /// which the type checker has matched against its type
+    fn build_formatted_value(
+        &mut self,
+        value: EvaluatedValue<'ctx>,
+        spec: &str,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let spec_ptr = self
+            .builder
+            .build_global_string_ptr(spec, "format_spec")?
+            .as_pointer_value();
+        let (name, argument) = match (value.ty.int_layout(), value.value) {
+            (Some((_, signed)), Some(raw)) => {
+                let (name, widest) = if signed {
+                    ("std.strings.format_int_spec", OtterType::I64)
+                } else {
+                    ("std.strings.format_uint_spec", OtterType::U64)
+                };
+                (name, self.coerce_type(raw, value.ty.clone(), widest)?)
+            }
+            (None, Some(raw)) if value.ty == OtterType::F64 => {
+                ("std.strings.format_float_spec", raw)
+            }
+            _ => (
+                "std.strings.format_str_spec",
+                self.ensure_string_value(value)?,
+            ),
+        };
+        let formatted =
+            self.call_ffi_returning_value(name, vec![argument, spec_ptr.into()], "formatted")?;
+        Ok(EvaluatedValue::with_value(formatted, OtterType::Str))
+    }
+
+    /// Materializes a module-level constant as an LLVM constant of type `ty`
+    fn eval_const_value(
+        &mut self,
+        value: ConstValue,
+        ty: OtterType,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        match value {
+            ConstValue::Int(value) if ty.is_int() => {
+                let (bits, signed) = ty.int_layout().unwrap_or((64, true));
+                let val = self
+                    .context
+                    .custom_width_int_type(bits)
+                    .const_int(value as u64, signed);
+                Ok(EvaluatedValue::with_value(val.into(), ty))
+            }
+            ConstValue::Int(value) => {
+                let val = self.context.f64_type().const_float(value as f64);
+                Ok(EvaluatedValue::with_value(val.into(), OtterType::F64))
+            }
+            ConstValue::Float(value) => {
+                let val = self.context.f64_type().const_float(value);
+                Ok(EvaluatedValue::with_value(val.into(), OtterType::F64))
+            }
+            ConstValue::Bool(value) => {
+                let val = self.context.bool_type().const_int(u64::from(value), false);
+                Ok(EvaluatedValue::with_value(val.into(), OtterType::Bool))
+            }
+            ConstValue::Str(text) => self.eval_literal(&Literal::String(text), None),
+        }
+    }
+
     fn eval_literal(
         &mut self,
         lit: &Literal,
@@ -1120,9 +1644,11 @@ impl<'ctx> Compiler<'ctx> {
                 // Use type checker's type information if available
                 let inferred_type = if let Some(type_info) = type_info {
                     match type_info {
-                        TypeInfo::I64 => OtterType::I64,
-                        TypeInfo::I32 => OtterType::I32,
                         TypeInfo::F64 => OtterType::F64,
+                        ty if ty.is_integer() => ty
+                            .int_layout()
+                            .and_then(|(bits, signed)| OtterType::int(bits, signed))
+                            .unwrap_or(OtterType::I64),
                         _ => {
                             // Fallback: use the literal's is_float_literal flag or check value
                             let is_float = n.is_float_literal || n.value.fract() != 0.0;
@@ -1144,18 +1670,20 @@ impl<'ctx> Compiler<'ctx> {
                 };
 
                 match inferred_type {
-                    OtterType::I64 => {
-                        // Convert f64 to i64, then to u64 for const_int
-                        let i64_val = n.value as i64;
-                        let val = self.context.i64_type().const_int(i64_val as u64, false);
-                        Ok(EvaluatedValue::with_value(val.into(), OtterType::I64))
-                    }
-                    OtterType::I32 => {
+                    ty if ty.is_int() => {
+                        let (bits, signed) = ty.int_layout().unwrap_or((64, true));
+                        // Signed values go through i64 so negative literals keep
+                        // their two's complement bits; const_int truncates to the width
+                        let raw = if signed {
+                            n.int_value() as i64 as u64
+                        } else {
+                            n.int_value() as u64
+                        };
                         let val = self
                             .context
-                            .i32_type()
-                            .const_int((n.value as i32) as u64, false);
-                        Ok(EvaluatedValue::with_value(val.into(), OtterType::I32))
+                            .custom_width_int_type(bits)
+                            .const_int(raw, signed);
+                        Ok(EvaluatedValue::with_value(val.into(), ty))
                     }
                     OtterType::F64 => {
                         let val = self.context.f64_type().const_float(n.value);
@@ -1168,13 +1696,42 @@ impl<'ctx> Compiler<'ctx> {
                     }
                 }
             }
-            Literal::String(s) => {
+            Literal::String(s) | Literal::Checked { value: s, .. } => {
                 let val = self.builder.build_global_string_ptr(s, "str_lit")?;
                 Ok(EvaluatedValue::with_value(
                     val.as_pointer_value().into(),
                     OtterType::Str,
                 ))
             }
+            Literal::Symbol(name) => {
+                // Interned at runtime, so every `:name` is the same pointer
+                let text = self.builder.build_global_string_ptr(name, "sym_name")?;
+                let symbol = self.call_ffi_returning_value(
+                    "sym",
+                    vec![text.as_pointer_value().into()],
+                    "symbol",
+                )?;
+                Ok(EvaluatedValue::with_value(symbol, OtterType::Str))
+            }
+            Literal::Bytes(bytes) => {
+                // Bytes literals are `list<int>` values holding one byte per
+                // element, built from a constant array in the binary's data
+                let data = self.context.const_string(bytes, false);
+                let global = self.module.add_global(data.get_type(), None, "bytes_lit");
+                global.set_initializer(&data);
+                global.set_constant(true);
+                global.set_linkage(inkwell::module::Linkage::Private);
+                let len = self.context.i64_type().const_int(bytes.len() as u64, false);
+                let handle = self.call_ffi_returning_value(
+                    "list.from_bytes",
+                    vec![global.as_pointer_value().into(), len.into()],
+                    "bytes_handle",
+                )?;
+                Ok(EvaluatedValue::with_value(
+                    handle,
+                    OtterType::list_of(OtterType::I64),
+                ))
+            }
             Literal::Bool(b) => {
                 let val = self.context.bool_type().const_int(*b as u64, false);
                 Ok(EvaluatedValue::with_value(val.into(), OtterType::Bool))
@@ -1194,15 +1751,65 @@ impl<'ctx> Compiler<'ctx> {
         right: &Expr,
         ctx: &mut FunctionContext<'ctx>,
     ) -> Result<EvaluatedValue<'ctx>> {
+        if matches!(op, BinaryOp::And | BinaryOp::Or) {
+            return self.eval_logical_expr(left, op, right, ctx);
+        }
+
         let lhs = self.eval_expr(left, ctx)?;
         let rhs = self.eval_expr(right, ctx)?;
         let lhs_ty = lhs.ty.clone();
         let rhs_ty = rhs.ty.clone();
 
+        // `==` on a type with an `eq` method, such as one from `@derive(eq)`
+        if matches!(op, BinaryOp::Eq | BinaryOp::Ne)
+            && let Some(equal) = self.build_eq_method_call(left, &lhs, &rhs)?
+        {
+            let result = if matches!(op, BinaryOp::Ne) {
+                self.builder.build_not(equal, "ne")?
+            } else {
+                equal
+            };
+            return Ok(EvaluatedValue::with_value(result.into(), OtterType::Bool));
+        }
+
         if matches!(op, BinaryOp::Add) && (lhs_ty == OtterType::Str || rhs_ty == OtterType::Str) {
             return self.build_string_concat(lhs, rhs);
         }
 
+        // Symbols are interned, so two symbols are equal when their pointers are
+        if matches!(op, BinaryOp::Eq | BinaryOp::Ne)
+            && matches!(self.expr_type(left), Some(TypeInfo::Symbol))
+            && matches!(self.expr_type(right), Some(TypeInfo::Symbol))
+            && let (Some(l), Some(r)) = (lhs.value, rhs.value)
+        {
+            let equal = self
+                .call_ffi_returning_value("symbol.equal", vec![l, r], "sym_eq")?
+                .into_int_value();
+            let result = if matches!(op, BinaryOp::Ne) {
+                self.builder.build_not(equal, "sym_ne")?
+            } else {
+                equal
+            };
+            return Ok(EvaluatedValue::with_value(result.into(), OtterType::Bool));
+        }
+
+        // Versions are handles, so they compare by the versions they hold
+        if [left, right].into_iter().all(|operand| {
+            self.expr_type(operand)
+                .is_some_and(TypeInfo::is_semver_version)
+        }) && let Some(predicate) = ordering_predicate(op)
+            && let (Some(l), Some(r)) = (lhs.value, rhs.value)
+        {
+            let order = self
+                .call_ffi_returning_value("semver.compare", vec![l, r], "semver_cmp")?
+                .into_int_value();
+            let zero = self.context.i64_type().const_zero();
+            let result = self
+                .builder
+                .build_int_compare(predicate, order, zero, "semver_order")?;
+            return Ok(EvaluatedValue::with_value(result.into(), OtterType::Bool));
+        }
+
         if lhs_ty == OtterType::Str && rhs_ty == OtterType::Str {
             return match op {
                 BinaryOp::Eq
@@ -1277,11 +1884,7 @@ impl<'ctx> Compiler<'ctx> {
             // Promote both to F64
             let l_f64 = if lhs_ty == OtterType::F64 {
                 lhs.value.unwrap().into_float_value()
-            } else if lhs_ty == OtterType::I64 {
-                let int_val = lhs.value.unwrap().into_int_value();
-                self.builder
-                    .build_signed_int_to_float(int_val, self.context.f64_type(), "itof")?
-            } else if lhs_ty == OtterType::Opaque {
+            } else if lhs_ty.is_int() || lhs_ty == OtterType::Opaque {
                 let coerced = self.coerce_type(lhs.value.unwrap(), lhs_ty, OtterType::F64)?;
                 coerced.into_float_value()
             } else {
@@ -1290,11 +1893,7 @@ impl<'ctx> Compiler<'ctx> {
 
             let r_f64 = if rhs_ty == OtterType::F64 {
                 rhs.value.unwrap().into_float_value()
-            } else if rhs_ty == OtterType::I64 {
-                let int_val = rhs.value.unwrap().into_int_value();
-                self.builder
-                    .build_signed_int_to_float(int_val, self.context.f64_type(), "itof")?
-            } else if rhs_ty == OtterType::Opaque {
+            } else if rhs_ty.is_int() || rhs_ty == OtterType::Opaque {
                 let coerced = self.coerce_type(rhs.value.unwrap(), rhs_ty, OtterType::F64)?;
                 coerced.into_float_value()
             } else {
@@ -1302,8 +1901,19 @@ impl<'ctx> Compiler<'ctx> {
             };
 
             (l_f64.into(), r_f64.into(), OtterType::F64)
-        } else if lhs_ty == OtterType::I64 && rhs_ty == OtterType::I64 {
-            (lhs.value.unwrap(), rhs.value.unwrap(), OtterType::I64)
+        } else if lhs_ty.is_int() && rhs_ty.is_int() {
+            // Shifts keep the type of the shifted value; other operations
+            // convert both sides to the type they both widen to
+            let result_ty = if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                lhs_ty.clone()
+            } else {
+                lhs_ty.common_numeric(&rhs_ty).ok_or_else(|| {
+                    anyhow!("Mismatched integer types {:?} and {:?}", lhs_ty, rhs_ty)
+                })?
+            };
+            let l = self.coerce_type(lhs.value.unwrap(), lhs_ty, result_ty.clone())?;
+            let r = self.coerce_type(rhs.value.unwrap(), rhs_ty, result_ty.clone())?;
+            (l, r, result_ty)
         } else if lhs_ty == OtterType::Bool && rhs_ty == OtterType::Bool {
             (lhs.value.unwrap(), rhs.value.unwrap(), OtterType::Bool)
         } else {
@@ -1316,63 +1926,71 @@ impl<'ctx> Compiler<'ctx> {
 
         // Perform the operation based on the result type
         match result_ty {
-            OtterType::I64 => {
+            ref int_ty if int_ty.is_int() => {
                 let l = lhs_val.into_int_value();
                 let r = rhs_val.into_int_value();
-                match op {
-                    BinaryOp::Add => Ok(EvaluatedValue::with_value(
-                        self.builder.build_int_add(l, r, "add")?.into(),
-                        OtterType::I64,
-                    )),
-                    BinaryOp::Sub => Ok(EvaluatedValue::with_value(
-                        self.builder.build_int_sub(l, r, "sub")?.into(),
-                        OtterType::I64,
-                    )),
-                    BinaryOp::Mul => Ok(EvaluatedValue::with_value(
-                        self.builder.build_int_mul(l, r, "mul")?.into(),
-                        OtterType::I64,
-                    )),
-                    BinaryOp::Div => Ok(EvaluatedValue::with_value(
-                        self.builder.build_int_signed_div(l, r, "div")?.into(),
-                        OtterType::I64,
-                    )),
-                    BinaryOp::Eq => Ok(EvaluatedValue::with_value(
-                        self.builder
-                            .build_int_compare(IntPredicate::EQ, l, r, "eq")?
-                            .into(),
-                        OtterType::Bool,
-                    )),
-                    BinaryOp::Ne => Ok(EvaluatedValue::with_value(
-                        self.builder
-                            .build_int_compare(IntPredicate::NE, l, r, "ne")?
-                            .into(),
-                        OtterType::Bool,
-                    )),
-                    BinaryOp::Lt => Ok(EvaluatedValue::with_value(
-                        self.builder
-                            .build_int_compare(IntPredicate::SLT, l, r, "lt")?
-                            .into(),
-                        OtterType::Bool,
-                    )),
-                    BinaryOp::Gt => Ok(EvaluatedValue::with_value(
-                        self.builder
-                            .build_int_compare(IntPredicate::SGT, l, r, "gt")?
-                            .into(),
-                        OtterType::Bool,
-                    )),
-                    BinaryOp::LtEq => Ok(EvaluatedValue::with_value(
-                        self.builder
-                            .build_int_compare(IntPredicate::SLE, l, r, "le")?
-                            .into(),
-                        OtterType::Bool,
-                    )),
-                    BinaryOp::GtEq => Ok(EvaluatedValue::with_value(
+                let (bits, signed) = int_ty.int_layout().unwrap_or((64, true));
+                let int_result = |value: IntValue<'ctx>| -> Result<EvaluatedValue<'ctx>> {
+                    Ok(EvaluatedValue::with_value(value.into(), int_ty.clone()))
+                };
+                let compare = |signed_pred, unsigned_pred| {
+                    if signed { signed_pred } else { unsigned_pred }
+                };
+                let predicate = match op {
+                    BinaryOp::Eq => Some(IntPredicate::EQ),
+                    BinaryOp::Ne => Some(IntPredicate::NE),
+                    BinaryOp::Lt => Some(compare(IntPredicate::SLT, IntPredicate::ULT)),
+                    BinaryOp::Gt => Some(compare(IntPredicate::SGT, IntPredicate::UGT)),
+                    BinaryOp::LtEq => Some(compare(IntPredicate::SLE, IntPredicate::ULE)),
+                    BinaryOp::GtEq => Some(compare(IntPredicate::SGE, IntPredicate::UGE)),
+                    _ => None,
+                };
+                if let Some(predicate) = predicate {
+                    return Ok(EvaluatedValue::with_value(
                         self.builder
-                            .build_int_compare(IntPredicate::SGE, l, r, "ge")?
+                            .build_int_compare(predicate, l, r, "cmp")?
                             .into(),
                         OtterType::Bool,
-                    )),
-                    _ => bail!("Unsupported binary op for I64"),
+                    ));
+                }
+                match op {
+                    BinaryOp::Add => int_result(self.builder.build_int_add(l, r, "add")?),
+                    BinaryOp::Sub => int_result(self.builder.build_int_sub(l, r, "sub")?),
+                    BinaryOp::Mul => int_result(self.builder.build_int_mul(l, r, "mul")?),
+                    BinaryOp::Div if signed => {
+                        int_result(self.builder.build_int_signed_div(l, r, "div")?)
+                    }
+                    BinaryOp::Div => int_result(self.builder.build_int_unsigned_div(l, r, "div")?),
+                    BinaryOp::Mod if signed => {
+                        int_result(self.builder.build_int_signed_rem(l, r, "rem")?)
+                    }
+                    BinaryOp::Mod => int_result(self.builder.build_int_unsigned_rem(l, r, "rem")?),
+                    BinaryOp::BitAnd => int_result(self.builder.build_and(l, r, "bitand")?),
+                    BinaryOp::BitOr => int_result(self.builder.build_or(l, r, "bitor")?),
+                    BinaryOp::BitXor => int_result(self.builder.build_xor(l, r, "bitxor")?),
+                    BinaryOp::Shl | BinaryOp::Shr => {
+                        // Shifting by the bit width or more is poison in LLVM, so
+                        // the amount wraps like it does on x86 and AArch64
+                        let amount = self.builder.build_int_cast_sign_flag(
+                            r,
+                            l.get_type(),
+                            false,
+                            "shamt_cast",
+                        )?;
+                        let amount = self.builder.build_and(
+                            amount,
+                            l.get_type().const_int(u64::from(bits - 1), false),
+                            "shamt",
+                        )?;
+                        // `>>` is arithmetic for signed types and logical for unsigned ones
+                        let shifted = if matches!(op, BinaryOp::Shl) {
+                            self.builder.build_left_shift(l, amount, "shl")?
+                        } else {
+                            self.builder.build_right_shift(l, amount, signed, "shr")?
+                        };
+                        int_result(shifted)
+                    }
+                    _ => bail!("Unsupported binary op for {:?}", int_ty),
                 }
             }
             OtterType::F64 => {
@@ -1438,6 +2056,77 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// `and`/`or` only evaluate the right operand when the left one does not
+    /// already decide the result
+    fn eval_logical_expr(
+        &mut self,
+        left: &Expr,
+        op: &BinaryOp,
+        right: &Expr,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let lhs = self.eval_expr(left, ctx)?;
+        let lhs = self.to_bool_value(lhs)?;
+        let lhs_bb = self.builder.get_insert_block().unwrap();
+        let function = lhs_bb.get_parent().unwrap();
+        let rhs_bb = self.context.append_basic_block(function, "logic_rhs");
+        let merge_bb = self.context.append_basic_block(function, "logic_merge");
+
+        if matches!(op, BinaryOp::And) {
+            self.builder
+                .build_conditional_branch(lhs, rhs_bb, merge_bb)?;
+        } else {
+            self.builder
+                .build_conditional_branch(lhs, merge_bb, rhs_bb)?;
+        }
+
+        self.builder.position_at_end(rhs_bb);
+        let rhs = self.eval_expr(right, ctx)?;
+        let rhs = self.to_bool_value(rhs)?;
+        let rhs_end_bb = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb)?;
+
+        self.builder.position_at_end(merge_bb);
+        let phi = self.builder.build_phi(self.context.bool_type(), "logic")?;
+        phi.add_incoming(&[(&lhs, lhs_bb), (&rhs, rhs_end_bb)]);
+        Ok(EvaluatedValue::with_value(
+            phi.as_basic_value(),
+            OtterType::Bool,
+        ))
+    }
+
+    /// Lowers `value as T`. Numeric casts truncate, extend or convert
+    /// between int and float; casts to `str` format the value.
+    fn eval_cast_expr(
+        &mut self,
+        expr: &Expr,
+        ty: &otterc_ast::nodes::Type,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let value = self.eval_expr(expr, ctx)?;
+        let target = self.otter_type_from_annotation(ty);
+        if value.ty == target {
+            return Ok(value);
+        }
+        if target == OtterType::Str {
+            let text = self.ensure_string_value(value)?;
+            return Ok(EvaluatedValue::with_value(text, OtterType::Str));
+        }
+        let raw = value
+            .value
+            .ok_or_else(|| anyhow!("cast of an expression without a value"))?;
+        let (raw, from) = if value.ty == OtterType::Bool && target == OtterType::F64 {
+            (
+                self.coerce_type(raw, OtterType::Bool, OtterType::I64)?,
+                OtterType::I64,
+            )
+        } else {
+            (raw, value.ty)
+        };
+        let converted = self.coerce_type(raw, from, target.clone())?;
+        Ok(EvaluatedValue::with_value(converted, target))
+    }
+
     fn eval_unary_expr(
         &mut self,
         op: &UnaryOp,
@@ -1448,11 +2137,11 @@ impl<'ctx> Compiler<'ctx> {
         match op {
             UnaryOp::Neg => {
                 let val_ty = val.ty.clone();
-                if val_ty == OtterType::I64 {
+                if val_ty.is_int() {
                     let v = val.value.unwrap().into_int_value();
                     Ok(EvaluatedValue::with_value(
                         self.builder.build_int_neg(v, "neg")?.into(),
-                        OtterType::I64,
+                        val_ty,
                     ))
                 } else if val_ty == OtterType::F64 {
                     let v = val.value.unwrap().into_float_value();
@@ -1476,6 +2165,17 @@ impl<'ctx> Compiler<'ctx> {
                     bail!("Unsupported type for not");
                 }
             }
+            UnaryOp::BitNot => {
+                if val.ty.is_int() {
+                    let v = val.value.unwrap().into_int_value();
+                    Ok(EvaluatedValue::with_value(
+                        self.builder.build_not(v, "bitnot")?.into(),
+                        val.ty,
+                    ))
+                } else {
+                    bail!("Unsupported type for bitwise not");
+                }
+            }
         }
     }
 
@@ -1483,10 +2183,14 @@ impl<'ctx> Compiler<'ctx> {
         match ty {
             OtterType::Unit => Ok(None),
             OtterType::Bool => Ok(Some(self.context.bool_type().into())),
-            OtterType::I32 => Ok(Some(self.context.i32_type().into())),
-            OtterType::I64 | OtterType::Opaque | OtterType::List(_) | OtterType::Map => {
-                Ok(Some(self.context.i64_type().into()))
-            }
+            OtterType::I8 | OtterType::U8 => Ok(Some(self.context.i8_type().into())),
+            OtterType::I16 | OtterType::U16 => Ok(Some(self.context.i16_type().into())),
+            OtterType::I32 | OtterType::U32 => Ok(Some(self.context.i32_type().into())),
+            OtterType::I64
+            | OtterType::U64
+            | OtterType::Opaque
+            | OtterType::List(_)
+            | OtterType::Map => Ok(Some(self.context.i64_type().into())),
             OtterType::F64 => Ok(Some(self.context.f64_type().into())),
             OtterType::Str => Ok(Some(self.string_ptr_type.into())),
             OtterType::Struct(id) => Ok(Some(self.struct_info(id).ty.into())),
@@ -1530,59 +2234,69 @@ impl<'ctx> Compiler<'ctx> {
 
         // Perform type coercion based on source and target types
         match (from_ty.clone(), to_ty.clone()) {
-            // Numeric conversions
-            (OtterType::I32, OtterType::I64) => {
+            // Integer conversions extend by the signedness of the source, or
+            // truncate. Equal widths only change how the bits are read.
+            (from, to) if from.is_int() && to.is_int() => {
+                let (from_bits, from_signed) = from.int_layout().unwrap_or((64, true));
+                let (to_bits, _) = to.int_layout().unwrap_or((64, true));
                 let int_val = value.into_int_value();
-                Ok(self
-                    .builder
-                    .build_int_s_extend(int_val, self.context.i64_type(), "i32_to_i64")?
-                    .into())
-            }
-            (OtterType::I64, OtterType::I32) => {
-                let int_val = value.into_int_value();
-                Ok(self
-                    .builder
-                    .build_int_truncate(int_val, self.context.i32_type(), "i64_to_i32")?
-                    .into())
+                let target = self.context.custom_width_int_type(to_bits);
+                Ok(match to_bits.cmp(&from_bits) {
+                    std::cmp::Ordering::Greater if from_signed => self
+                        .builder
+                        .build_int_s_extend(int_val, target, "int_sext")?
+                        .into(),
+                    std::cmp::Ordering::Greater => self
+                        .builder
+                        .build_int_z_extend(int_val, target, "int_zext")?
+                        .into(),
+                    std::cmp::Ordering::Less => self
+                        .builder
+                        .build_int_truncate(int_val, target, "int_trunc")?
+                        .into(),
+                    std::cmp::Ordering::Equal => value,
+                })
             }
-            (OtterType::I32, OtterType::F64) | (OtterType::I64, OtterType::F64) => {
+            (from, OtterType::F64) if from.is_int() => {
                 let int_val = value.into_int_value();
-                Ok(self
-                    .builder
-                    .build_signed_int_to_float(int_val, self.context.f64_type(), "int_to_f64")?
-                    .into())
-            }
-            (OtterType::F64, OtterType::I32) => {
-                let float_val = value.into_float_value();
-                Ok(self
-                    .builder
-                    .build_float_to_signed_int(float_val, self.context.i32_type(), "f64_to_i32")?
-                    .into())
+                let f64_type = self.context.f64_type();
+                Ok(if matches!(from.int_layout(), Some((_, false))) {
+                    self.builder
+                        .build_unsigned_int_to_float(int_val, f64_type, "uint_to_f64")?
+                } else {
+                    self.builder
+                        .build_signed_int_to_float(int_val, f64_type, "int_to_f64")?
+                }
+                .into())
             }
-            (OtterType::F64, OtterType::I64) => {
+            (OtterType::F64, to) if to.is_int() => {
                 let float_val = value.into_float_value();
-                Ok(self
-                    .builder
-                    .build_float_to_signed_int(float_val, self.context.i64_type(), "f64_to_i64")?
-                    .into())
+                let (bits, signed) = to.int_layout().unwrap_or((64, true));
+                let target = self.context.custom_width_int_type(bits);
+                Ok(if signed {
+                    self.builder
+                        .build_float_to_signed_int(float_val, target, "f64_to_int")?
+                } else {
+                    self.builder
+                        .build_float_to_unsigned_int(float_val, target, "f64_to_uint")?
+                }
+                .into())
             }
 
             // Bool conversions
-            (OtterType::Bool, OtterType::I32) => {
-                let bool_val = value.into_int_value();
-                Ok(self
-                    .builder
-                    .build_int_z_extend(bool_val, self.context.i32_type(), "bool_to_i32")?
-                    .into())
-            }
-            (OtterType::Bool, OtterType::I64) => {
+            (OtterType::Bool, to) if to.is_int() => {
                 let bool_val = value.into_int_value();
+                let (bits, _) = to.int_layout().unwrap_or((64, true));
                 Ok(self
                     .builder
-                    .build_int_z_extend(bool_val, self.context.i64_type(), "bool_to_i64")?
+                    .build_int_z_extend(
+                        bool_val,
+                        self.context.custom_width_int_type(bits),
+                        "bool_to_int",
+                    )?
                     .into())
             }
-            (OtterType::I32 | OtterType::I64, OtterType::Bool) => {
+            (from, OtterType::Bool) if from.is_int() => {
                 let int_val = value.into_int_value();
                 let zero = int_val.get_type().const_zero();
                 Ok(self
@@ -1592,7 +2306,8 @@ impl<'ctx> Compiler<'ctx> {
             }
 
             // Opaque type conversions (treat as i64)
-            (OtterType::Opaque, OtterType::I64) | (OtterType::I64, OtterType::Opaque)
+            (OtterType::Opaque, OtterType::I64 | OtterType::U64)
+            | (OtterType::I64 | OtterType::U64, OtterType::Opaque)
             // List/Map conversions (treat as opaque pointers)
             | (OtterType::List(_) | OtterType::Map, OtterType::Opaque)
             | (OtterType::Opaque, OtterType::List(_) | OtterType::Map) => {
@@ -1664,7 +2379,7 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
-    fn cast_argument_for_call(
+    pub(crate) fn cast_argument_for_call(
         &self,
         value: BasicValueEnum<'ctx>,
         from_ty: OtterType,
@@ -1703,7 +2418,35 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
-    fn eval_call_expr(
+    /// The address of the function a bare name passed to an opaque runtime
+    /// parameter refers to, which the runtime calls back through. Elsewhere a
+    /// function name is not a value.
+    fn callback_address(
+        &self,
+        arg: &Expr,
+        ctx: &FunctionContext<'ctx>,
+    ) -> Result<Option<EvaluatedValue<'ctx>>> {
+        let Expr::Identifier(name) = arg else {
+            return Ok(None);
+        };
+        if ctx.get(name).is_some() || self.constants.contains_key(name) {
+            return Ok(None);
+        }
+        let Some(function) = self.declared_functions.get(name).copied() else {
+            return Ok(None);
+        };
+        let address = self.builder.build_ptr_to_int(
+            function.as_global_value().as_pointer_value(),
+            self.context.i64_type(),
+            &format!("{name}_addr"),
+        )?;
+        Ok(Some(EvaluatedValue::with_value(
+            address.into(),
+            OtterType::Opaque,
+        )))
+    }
+
+    pub(crate) fn eval_call_expr(
         &mut self,
         expr: &Expr,
         ctx: &mut FunctionContext<'ctx>,
@@ -1715,14 +2458,81 @@ impl<'ctx> Compiler<'ctx> {
             {
                 return Ok(enum_value);
             }
+            if let Some(config) = self.try_build_config_load(func.as_ref().as_ref(), args, ctx)? {
+                return Ok(config);
+            }
+            if let Some(value) = self.try_build_reflect_call(func.as_ref().as_ref(), args, ctx)? {
+                return Ok(value);
+            }
+            if let Some(value) =
+                self.try_build_error_call(expr, func.as_ref().as_ref(), args, ctx)?
+            {
+                return Ok(value);
+            }
+            if let Some(value) =
+                self.try_build_fallible_method(expr, func.as_ref().as_ref(), args, ctx)?
+            {
+                return Ok(value);
+            }
+
+            // Pointers to the caller's variables, when calling a nested function
+            let mut captured_args = Vec::new();
+            let mut handle_return_type = None;
 
             // Evaluate function expression
             let func_name = match func.as_ref().as_ref() {
-                Expr::Identifier(name) => name.clone(),
+                Expr::Identifier(name)
+                    if self.protocol_functions.contains_key(name)
+                        && !self.nested_functions.contains_key(name) =>
+                {
+                    self.instantiate_protocol_function(name, args, ctx)?
+                }
+                Expr::Identifier(name) => match self.nested_functions.get(name) {
+                    Some(nested) => {
+                        for capture in &nested.captures {
+                            let var = ctx.get(capture).ok_or_else(|| {
+                                anyhow!("captured variable '{capture}' missing from scope")
+                            })?;
+                            captured_args.push(var.ptr);
+                        }
+                        nested.name.clone()
+                    }
+                    None => self
+                        .imported_items
+                        .get(name)
+                        .cloned()
+                        .unwrap_or_else(|| name.clone()),
+                },
                 Expr::Member { object, field } => {
-                    // First, try to evaluate the object to check its runtime type
+                    // Enum methods are found from the object's checked type. Otherwise,
+                    // try to evaluate the object to check its runtime type
                     // This handles cases like list.append() where the object is a variable
-                    if let Ok(evaluated) = self.eval_expr(object.as_ref().as_ref(), ctx) {
+                    if let Some(method_name) =
+                        self.resolve_enum_method_name(object.as_ref().as_ref(), field)
+                    {
+                        implicit_self = Some(self.eval_expr(object.as_ref().as_ref(), ctx)?);
+                        method_name
+                    } else if let Some(method) = self
+                        .expr_type(object.as_ref().as_ref())
+                        .and_then(|ty| ty.builtin_method(field))
+                        .filter(|method| method.function != "append")
+                    {
+                        // `"abc".upper()` calls the runtime's `str.upper` with
+                        // the receiver first
+                        implicit_self = Some(self.eval_expr(object.as_ref().as_ref(), ctx)?);
+                        // The runtime returns lists, dicts and frozen collections
+                        // as bare handles, so their type comes from the checker
+                        handle_return_type = self
+                            .expr_type(expr)
+                            .and_then(|ty| self.typeinfo_to_otter_type(ty))
+                            .filter(|ty| {
+                                matches!(
+                                    ty,
+                                    OtterType::List(_) | OtterType::Map | OtterType::Opaque
+                                )
+                            });
+                        method.function.to_string()
+                    } else if let Ok(evaluated) = self.eval_expr(object.as_ref().as_ref(), ctx) {
                         if evaluated.value.is_some() {
                             // Check if it's a list type and handle list methods
                             if matches!(evaluated.ty, OtterType::List(_)) {
@@ -1731,9 +2541,7 @@ impl<'ctx> Compiler<'ctx> {
                                     let arg_val = self.eval_expr(args[0].as_ref(), ctx)?;
                                     let method_name: String = match arg_val.ty {
                                         OtterType::Str => "append<list,string>".to_string(),
-                                        OtterType::I64 | OtterType::I32 => {
-                                            "append<list,int>".to_string()
-                                        }
+                                        _ if arg_val.ty.is_int() => "append<list,int>".to_string(),
                                         OtterType::F64 => "append<list,float>".to_string(),
                                         OtterType::Bool => "append<list,bool>".to_string(),
                                         OtterType::List(_) | OtterType::Opaque => {
@@ -1832,9 +2640,7 @@ impl<'ctx> Compiler<'ctx> {
                                 let arg_val = self.eval_expr(args[0].as_ref(), ctx)?;
                                 let method_name: String = match arg_val.ty {
                                     OtterType::Str => "append<list,string>".to_string(),
-                                    OtterType::I64 | OtterType::I32 => {
-                                        "append<list,int>".to_string()
-                                    }
+                                    _ if arg_val.ty.is_int() => "append<list,int>".to_string(),
                                     OtterType::F64 => "append<list,float>".to_string(),
                                     OtterType::Bool => "append<list,bool>".to_string(),
                                     OtterType::List(_) | OtterType::Opaque => {
@@ -1911,10 +2717,27 @@ impl<'ctx> Compiler<'ctx> {
                 .get_param_iter()
                 .map(|arg| arg.get_type())
                 .collect();
+            // Runtime functions taking a callback get it through an opaque parameter
+            let opaque_params: Vec<bool> =
+                if self.declared_functions.contains_key(&resolved_func_name) {
+                    Vec::new()
+                } else {
+                    self.symbol_registry
+                        .resolve(&resolved_func_name)
+                        .map(|ffi| {
+                            ffi.signature
+                                .params
+                                .iter()
+                                .map(|param| matches!(param, FfiType::Opaque))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
 
             // Evaluate arguments and convert types as needed
-            let mut arg_values: Vec<BasicMetadataValueEnum> = Vec::new();
-            let mut param_offset = 0;
+            let mut arg_values: Vec<BasicMetadataValueEnum> =
+                captured_args.iter().map(|ptr| (*ptr).into()).collect();
+            let mut param_offset = captured_args.len();
 
             if let Some(self_arg) = implicit_self {
                 let v = self_arg
@@ -1929,8 +2752,15 @@ impl<'ctx> Compiler<'ctx> {
             }
 
             for (i, arg) in args.iter().enumerate() {
+                let callback = if opaque_params.get(i).copied().unwrap_or(false) {
+                    self.callback_address(arg.as_ref(), ctx)?
+                } else {
+                    None
+                };
                 // Reuse first arg if it was already evaluated for len() dispatch
-                let arg_val = if i == 0 {
+                let arg_val = if let Some(address) = callback {
+                    address
+                } else if i == 0 {
                     if let Some(val) = first_arg_evaluated.as_ref() {
                         val.clone()
                     } else {
@@ -1980,9 +2810,32 @@ impl<'ctx> Compiler<'ctx> {
                 }
             }
 
+            // Calls that may block are bracketed so the runtime can warn when
+            // they stall a task worker
+            let blocking_site = if self.symbol_registry.may_block(&resolved_func_name) {
+                let site = format!(
+                    "`{resolved_func_name}` at {}",
+                    self.source_location(*func.span())
+                );
+                let site_ptr = self
+                    .builder
+                    .build_global_string_ptr(&site, "blocking_site")?
+                    .as_pointer_value();
+                let enter_fn = self.get_or_declare_ffi_function("__otter_blocking_enter")?;
+                self.builder.build_call(enter_fn, &[site_ptr.into()], "")?;
+                true
+            } else {
+                false
+            };
+
             // Call the function
             let call_site = self.builder.build_call(function, &arg_values, &func_name)?;
 
+            if blocking_site {
+                let exit_fn = self.get_or_declare_ffi_function("__otter_blocking_exit")?;
+                self.builder.build_call(exit_fn, &[], "")?;
+            }
+
             // Get return value
             if let Some(ret_val) = call_site.try_as_basic_value().left() {
                 // Use declared return type if available, otherwise infer from LLVM type
@@ -1990,6 +2843,7 @@ impl<'ctx> Compiler<'ctx> {
                     .function_return_types
                     .get(&resolved_func_name)
                     .cloned()
+                    .or(handle_return_type)
                     .unwrap_or_else(|| {
                         function
                             .get_type()
@@ -2169,7 +3023,7 @@ impl<'ctx> Compiler<'ctx> {
     fn list_append_target(&self, ty: &OtterType) -> Result<(&'static str, OtterType)> {
         match ty {
             OtterType::Str => Ok(("append<list,string>", OtterType::Str)),
-            OtterType::I32 | OtterType::I64 => Ok(("append<list,int>", OtterType::I64)),
+            int_ty if int_ty.is_int() => Ok(("append<list,int>", OtterType::I64)),
             OtterType::F64 => Ok(("append<list,float>", OtterType::F64)),
             OtterType::Bool => Ok(("append<list,bool>", OtterType::Bool)),
             OtterType::List(_) => Ok(("append<list,list>", ty.clone())),
@@ -2546,7 +3400,7 @@ impl<'ctx> Compiler<'ctx> {
     fn map_set_target(&self, ty: &OtterType) -> Result<(&'static str, OtterType)> {
         match ty {
             OtterType::Str => Ok(("map.set", OtterType::Str)),
-            OtterType::I32 | OtterType::I64 => Ok(("set<map,int>", OtterType::I64)),
+            int_ty if int_ty.is_int() => Ok(("set<map,int>", OtterType::I64)),
             OtterType::F64 => Ok(("set<map,float>", OtterType::F64)),
             OtterType::Bool => Ok(("set<map,bool>", OtterType::Bool)),
             OtterType::List(_) => Ok(("set<map,list>", ty.clone())),
@@ -2556,6 +3410,19 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// Getter used to read a map value of type `ty`; values of unknown type are
+    /// read as strings
+    fn map_get_target(&self, ty: Option<OtterType>) -> (&'static str, OtterType) {
+        match ty {
+            Some(ref int_ty) if int_ty.is_int() => ("map.get_int", OtterType::I64),
+            Some(OtterType::F64) => ("map.get_float", OtterType::F64),
+            Some(OtterType::Bool) => ("map.get_bool", OtterType::Bool),
+            Some(ty @ OtterType::List(_)) => ("map.get_list", ty),
+            Some(OtterType::Map) => ("map.get_map", OtterType::Map),
+            _ => ("map.get", OtterType::Str),
+        }
+    }
+
     fn comprehension_element_type(&self, expr: &Expr) -> Option<OtterType> {
         let id = expr as *const Expr as usize;
         let span = self.expr_spans.get(&id)?;
@@ -2585,9 +3452,10 @@ impl<'ctx> Compiler<'ctx> {
             Expr::Binary { left, right, .. } => self
                 .find_identifier_type_in_expr(left.as_ref().as_ref(), var)
                 .or_else(|| self.find_identifier_type_in_expr(right.as_ref().as_ref(), var)),
-            Expr::Unary { expr, .. } | Expr::Await(expr) | Expr::Spawn(expr) => {
-                self.find_identifier_type_in_expr(expr.as_ref().as_ref(), var)
-            }
+            Expr::Unary { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::Await(expr)
+            | Expr::Spawn(expr) => self.find_identifier_type_in_expr(expr.as_ref().as_ref(), var),
             Expr::Call { func, args } => self
                 .find_identifier_type_in_expr(func.as_ref().as_ref(), var)
                 .or_else(|| {
@@ -2674,12 +3542,14 @@ impl<'ctx> Compiler<'ctx> {
                 }
             }
             Expr::FString { parts } => parts.iter().find_map(|part| match part.as_ref() {
-                FStringPart::Expr(expr) => self.find_identifier_type_in_expr(expr.as_ref(), var),
+                FStringPart::Expr(expr, _) => self.find_identifier_type_in_expr(expr.as_ref(), var),
                 _ => None,
             }),
             Expr::Struct { fields, .. } => fields
                 .iter()
                 .find_map(|(_, expr)| self.find_identifier_type_in_expr(expr.as_ref(), var)),
+            // An anonymous function body only sees its own parameters
+            Expr::Lambda { .. } => None,
         }
     }
 
@@ -2694,20 +3564,24 @@ impl<'ctx> Compiler<'ctx> {
         match stmt {
             Statement::Expr(expr)
             | Statement::Return(Some(expr))
+            | Statement::Defer(expr)
             | Statement::Let { expr, .. }
+            | Statement::Const { expr, .. }
             | Statement::Assignment { expr, .. } => {
                 self.find_identifier_type_in_expr(expr.as_ref(), var)
             }
             Statement::Return(None)
-            | Statement::Break
-            | Statement::Continue
+            | Statement::Break(_)
+            | Statement::Continue(_)
             | Statement::Pass
             | Statement::Struct { .. }
             | Statement::Enum { .. }
             | Statement::TypeAlias { .. }
+            | Statement::Protocol { .. }
             | Statement::Use { .. }
             | Statement::PubUse { .. }
-            | Statement::Function(_) => None,
+            | Statement::Function(_)
+            | Statement::Init(_) => None,
             Statement::If {
                 cond,
                 then_block,
@@ -2727,17 +3601,30 @@ impl<'ctx> Compiler<'ctx> {
                         .as_ref()
                         .and_then(|block| self.find_identifier_type_in_block(block.as_ref(), var))
                 }),
-            Statement::While { cond, body } => self
+            Statement::While { cond, body, .. }
+            | Statement::With {
+                expr: cond, body, ..
+            } => self
                 .find_identifier_type_in_expr(cond.as_ref(), var)
                 .or_else(|| self.find_identifier_type_in_block(body.as_ref(), var)),
             Statement::For { iterable, body, .. } => self
                 .find_identifier_type_in_expr(iterable.as_ref(), var)
                 .or_else(|| self.find_identifier_type_in_block(body.as_ref(), var)),
+            Statement::Assert { cond, message } => self
+                .find_identifier_type_in_expr(cond.as_ref(), var)
+                .or_else(|| {
+                    message.as_ref().and_then(|message| {
+                        self.find_identifier_type_in_expr(message.as_ref(), var)
+                    })
+                }),
             Statement::Block(block) => self.find_identifier_type_in_block(block.as_ref(), var),
         }
     }
 
-    fn ensure_string_value(&mut self, value: EvaluatedValue<'ctx>) -> Result<BasicValueEnum<'ctx>> {
+    pub(crate) fn ensure_string_value(
+        &mut self,
+        value: EvaluatedValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
         let EvaluatedValue { ty, value } = value;
         let base_value = value.ok_or_else(|| anyhow!("expected value for string operation"))?;
 
@@ -2746,18 +3633,15 @@ impl<'ctx> Compiler<'ctx> {
             OtterType::I64 => {
                 self.call_ffi_returning_value("std.strings.format_int", vec![base_value], "fmt_int")
             }
-            OtterType::I32 => {
-                let int_val = base_value.into_int_value();
-                let widened = self.builder.build_int_s_extend(
-                    int_val,
-                    self.context.i64_type(),
-                    "i32_to_i64",
-                )?;
-                self.call_ffi_returning_value(
-                    "std.strings.format_int",
-                    vec![widened.into()],
-                    "fmt_int",
-                )
+            OtterType::U64 => self.call_ffi_returning_value(
+                "std.strings.format_uint",
+                vec![base_value],
+                "fmt_uint",
+            ),
+            ref int_ty if int_ty.is_int() => {
+                // Every narrower integer fits in an i64
+                let widened = self.coerce_type(base_value, int_ty.clone(), OtterType::I64)?;
+                self.call_ffi_returning_value("std.strings.format_int", vec![widened], "fmt_int")
             }
             OtterType::F64 => self.call_ffi_returning_value(
                 "std.strings.format_float",
@@ -2799,6 +3683,436 @@ impl<'ctx> Compiler<'ctx> {
             .ok_or_else(|| anyhow!("FFI function {name} returned void"))
     }
 
+    /// Lowers `config.load(path, Struct)`: the struct layout becomes a schema the
+    /// runtime validates the file against, then every field is read back with a
+    /// typed getter and inserted into the aggregate.
+    fn try_build_config_load(
+        &mut self,
+        func_expr: &Expr,
+        args: &[Node<Expr>],
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<Option<EvaluatedValue<'ctx>>> {
+        let Expr::Member { object, field } = func_expr else {
+            return Ok(None);
+        };
+        if field != "load"
+            || !matches!(object.as_ref().as_ref(), Expr::Identifier(module) if module == "config")
+            || ctx.get("config").is_some()
+        {
+            return Ok(None);
+        }
+        let [path, target] = args else {
+            return Ok(None);
+        };
+        let Expr::Identifier(struct_name) = target.as_ref() else {
+            return Ok(None);
+        };
+        let Some((struct_id, _)) = self.struct_info_by_name(struct_name) else {
+            return Ok(None);
+        };
+
+        let mut schema = Vec::new();
+        self.config_schema(struct_id, "", &mut schema)?;
+        let schema_ptr = self
+            .builder
+            .build_global_string_ptr(&schema.join(";"), "config_schema")?
+            .as_pointer_value();
+        let path_value = self.eval_expr(path.as_ref(), ctx)?;
+        let path_ptr = self.ensure_string_value(path_value)?;
+
+        let load_fn = self.get_or_declare_ffi_function("__otter_config_load")?;
+        let handle = self
+            .builder
+            .build_call(load_fn, &[path_ptr.into(), schema_ptr.into()], "config")?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| anyhow!("config.load did not return a handle"))?;
+        let value = self.build_config_struct(struct_id, "", handle)?;
+        let free_fn = self.get_or_declare_ffi_function("__otter_config_free")?;
+        self.builder
+            .build_call(free_fn, &[handle.into()], "config_free")?;
+
+        Ok(Some(EvaluatedValue::with_value(
+            value.into(),
+            OtterType::Struct(struct_id),
+        )))
+    }
+
+    /// Fields of a struct in layout order, keyed by their dotted path under `prefix`
+    fn ordered_struct_fields(
+        &self,
+        struct_id: u32,
+        prefix: &str,
+    ) -> Vec<(String, usize, OtterType)> {
+        let info = self.struct_info(struct_id);
+        let mut fields: Vec<_> = info
+            .field_indices
+            .iter()
+            .map(|(name, &idx)| {
+                (
+                    format!("{prefix}{name}"),
+                    idx,
+                    info.field_types[idx].clone(),
+                )
+            })
+            .collect();
+        fields.sort_by_key(|(_, idx, _)| *idx);
+        fields
+    }
+
+    /// Reads field `idx` of a struct value, through its pointer when the field
+    /// is boxed
+    fn build_struct_field_get(
+        &mut self,
+        struct_id: u32,
+        value: StructValue<'ctx>,
+        idx: usize,
+        name: &str,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let extracted = self
+            .builder
+            .build_extract_value(value, idx as u32, name)
+            .map_err(|e| anyhow!("failed to extract field '{name}': {e}"))?;
+        if !self.is_boxed_field(struct_id, idx) {
+            return Ok(extracted);
+        }
+        let field_ty = self
+            .basic_type(self.struct_info(struct_id).field_types[idx].clone())?
+            .ok_or_else(|| anyhow!("boxed field '{name}' has no value type"))?;
+        Ok(self
+            .builder
+            .build_load(field_ty, extracted.into_pointer_value(), name)?)
+    }
+
+    /// Sets field `idx` of a struct value; a boxed field gets a fresh heap
+    /// copy of `field_value`, so boxes are never written after creation
+    fn build_struct_field_set(
+        &mut self,
+        struct_id: u32,
+        aggregate: StructValue<'ctx>,
+        idx: usize,
+        field_value: BasicValueEnum<'ctx>,
+        name: &str,
+    ) -> Result<StructValue<'ctx>> {
+        let stored = if self.is_boxed_field(struct_id, idx) {
+            let ptr = self.builder.build_malloc(field_value.get_type(), name)?;
+            self.builder.build_store(ptr, field_value)?;
+            ptr.into()
+        } else {
+            field_value
+        };
+        Ok(self
+            .builder
+            .build_insert_value(aggregate, stored, idx as u32, name)
+            .map_err(|e| anyhow!("failed to insert field '{name}': {e}"))?
+            .into_struct_value())
+    }
+
+    fn is_boxed_field(&self, struct_id: u32, idx: usize) -> bool {
+        self.struct_info(struct_id)
+            .boxed_fields
+            .get(idx)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn config_schema(&self, struct_id: u32, prefix: &str, schema: &mut Vec<String>) -> Result<()> {
+        for (path, _, ty) in self.ordered_struct_fields(struct_id, prefix) {
+            let kind = match ty {
+                OtterType::I64 => "int",
+                OtterType::I8 => "i8",
+                OtterType::I16 => "i16",
+                OtterType::I32 => "i32",
+                OtterType::U8 => "u8",
+                OtterType::U16 => "u16",
+                OtterType::U32 => "u32",
+                OtterType::U64 => "u64",
+                OtterType::F64 => "float",
+                OtterType::Bool => "bool",
+                OtterType::Str => "str",
+                OtterType::Struct(nested) => {
+                    self.config_schema(nested, &format!("{path}."), schema)?;
+                    continue;
+                }
+                other => bail!("config field '{path}' has unsupported type {other:?}"),
+            };
+            schema.push(format!("{path}:{kind}"));
+        }
+        Ok(())
+    }
+
+    fn build_config_struct(
+        &mut self,
+        struct_id: u32,
+        prefix: &str,
+        handle: BasicValueEnum<'ctx>,
+    ) -> Result<StructValue<'ctx>> {
+        let mut aggregate = self.struct_info(struct_id).ty.get_undef();
+        for (path, idx, ty) in self.ordered_struct_fields(struct_id, prefix) {
+            let field_value = if let OtterType::Struct(nested) = ty {
+                self.build_config_struct(nested, &format!("{path}."), handle)?
+                    .into()
+            } else {
+                let getter = match ty {
+                    ref int_ty if int_ty.is_int() => "__otter_config_get_int",
+                    OtterType::F64 => "__otter_config_get_float",
+                    OtterType::Bool => "__otter_config_get_bool",
+                    _ => "__otter_config_get_str",
+                };
+                let getter_fn = self.get_or_declare_ffi_function(getter)?;
+                let key = self
+                    .builder
+                    .build_global_string_ptr(&path, "config_key")?
+                    .as_pointer_value();
+                let value = self
+                    .builder
+                    .build_call(getter_fn, &[handle.into(), key.into()], "config_field")?
+                    .try_as_basic_value()
+                    .left()
+                    .ok_or_else(|| anyhow!("config getter for '{path}' returned no value"))?;
+                if ty.is_int() {
+                    // The schema has already checked the value fits the field
+                    self.coerce_type(value, OtterType::I64, ty)?
+                } else {
+                    value
+                }
+            };
+            aggregate = self
+                .builder
+                .build_insert_value(aggregate, field_value, idx as u32, &path)
+                .map_err(|e| anyhow!("failed to insert config field '{path}': {e}"))?
+                .into_struct_value();
+        }
+        Ok(aggregate)
+    }
+
+    /// Lowers `deepcopy(value)` and `diff(old, new)`. Lists and maps go to the
+    /// runtime directly; structs are copied field by field, and diffed by first
+    /// reflecting them into maps keyed by field name.
+    fn try_build_reflect_call(
+        &mut self,
+        func_expr: &Expr,
+        args: &[Node<Expr>],
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<Option<EvaluatedValue<'ctx>>> {
+        let Expr::Identifier(name) = func_expr else {
+            return Ok(None);
+        };
+        if !matches!(name.as_str(), "deepcopy" | "diff")
+            || self.declared_functions.contains_key(name)
+            || ctx.get(name).is_some()
+        {
+            return Ok(None);
+        }
+
+        match (name.as_str(), args) {
+            ("deepcopy", [value]) => {
+                let value = self.eval_expr(value.as_ref(), ctx)?;
+                Ok(Some(self.build_deep_copy(value)?))
+            }
+            ("diff", [old, new]) => {
+                let old = self.eval_expr(old.as_ref(), ctx)?;
+                let old = self.build_reflected_handle(old)?;
+                let new = self.eval_expr(new.as_ref(), ctx)?;
+                let new = self.build_reflected_handle(new)?;
+                let changes =
+                    self.call_ffi_returning_value("__otter_diff", vec![old, new], "diff")?;
+                Ok(Some(EvaluatedValue::with_value(
+                    changes,
+                    OtterType::list_of(OtterType::Map),
+                )))
+            }
+            _ => bail!("{}() called with {} argument(s)", name, args.len()),
+        }
+    }
+
+    /// Lowers `error(message)`, `err.context(message)` and `panic(message)`,
+    /// which also pass the runtime where they are called, and `panic(err)`
+    fn try_build_error_call(
+        &mut self,
+        call: &Expr,
+        func_expr: &Expr,
+        args: &[Node<Expr>],
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<Option<EvaluatedValue<'ctx>>> {
+        let builtin = |name: &str| {
+            !self.declared_functions.contains_key(name)
+                && !self.nested_functions.contains_key(name)
+                && ctx.get(name).is_none()
+        };
+        let (function, receiver, message) = match (func_expr, args) {
+            (Expr::Identifier(name), [message]) if name == "error" && builtin(name) => {
+                ("__otter_error_new", None, Some(message))
+            }
+            (Expr::Member { object, field }, [message])
+                if field == "context"
+                    && self
+                        .expr_type(object.as_ref().as_ref())
+                        .is_some_and(TypeInfo::is_error_value) =>
+            {
+                ("error.context", Some(object.as_ref()), Some(message))
+            }
+            (Expr::Identifier(name), [error])
+                if name == "panic"
+                    && builtin(name)
+                    && self
+                        .expr_type(error.as_ref())
+                        .is_some_and(TypeInfo::is_error_value) =>
+            {
+                ("__otter_error_panic", Some(error), None)
+            }
+            (Expr::Identifier(name), [message]) if name == "panic" && builtin(name) => {
+                ("__otter_panic_at", None, Some(message))
+            }
+            _ => return Ok(None),
+        };
+
+        let mut call_args = Vec::new();
+        if let Some(receiver) = receiver {
+            let error = self.eval_expr(receiver.as_ref(), ctx)?;
+            call_args.push(
+                error
+                    .value
+                    .ok_or_else(|| anyhow!("error value has no runtime value"))?,
+            );
+        }
+        let Some(message) = message else {
+            let panic_fn = self.get_or_declare_ffi_function(function)?;
+            let call_args: Vec<BasicMetadataValueEnum> =
+                call_args.into_iter().map(Into::into).collect();
+            self.builder.build_call(panic_fn, &call_args, "")?;
+            return Ok(Some(EvaluatedValue {
+                ty: OtterType::Unit,
+                value: None,
+            }));
+        };
+        let message = self.eval_expr(message.as_ref(), ctx)?;
+        call_args.push(self.ensure_string_value(message)?);
+        let id = call as *const Expr as usize;
+        let location = match self.expr_spans.get(&id) {
+            Some(span) => self.source_location(*span),
+            None => "<unknown>".to_string(),
+        };
+        call_args.push(
+            self.builder
+                .build_global_string_ptr(&location, "error_loc")?
+                .as_pointer_value()
+                .into(),
+        );
+        if function == "__otter_panic_at" {
+            let panic_fn = self.get_or_declare_ffi_function(function)?;
+            let call_args: Vec<BasicMetadataValueEnum> =
+                call_args.into_iter().map(Into::into).collect();
+            self.builder.build_call(panic_fn, &call_args, "")?;
+            return Ok(Some(EvaluatedValue {
+                ty: OtterType::Unit,
+                value: None,
+            }));
+        }
+        let error = self.call_ffi_returning_value(function, call_args, "error")?;
+        Ok(Some(EvaluatedValue::with_value(error, OtterType::Opaque)))
+    }
+
+    fn build_deep_copy(&mut self, value: EvaluatedValue<'ctx>) -> Result<EvaluatedValue<'ctx>> {
+        let Some(raw) = value.value else {
+            return Ok(value);
+        };
+        let copied = match &value.ty {
+            OtterType::List(_) => {
+                self.call_ffi_returning_value("deepcopy<list>", vec![raw], "deepcopy_list")?
+            }
+            OtterType::Map => {
+                self.call_ffi_returning_value("deepcopy<map>", vec![raw], "deepcopy_map")?
+            }
+            OtterType::Struct(struct_id) => {
+                let mut aggregate = raw.into_struct_value();
+                for (field, idx, ty) in self.ordered_struct_fields(*struct_id, "") {
+                    // Boxes are never written after creation, so sharing
+                    // one is as good as copying it
+                    if self.is_boxed_field(*struct_id, idx) {
+                        continue;
+                    }
+                    let field_value = self
+                        .builder
+                        .build_extract_value(aggregate, idx as u32, &field)
+                        .map_err(|e| anyhow!("failed to extract field '{field}': {e}"))?;
+                    let copied =
+                        self.build_deep_copy(EvaluatedValue::with_value(field_value, ty))?;
+                    if let Some(copied) = copied.value {
+                        aggregate = self
+                            .builder
+                            .build_insert_value(aggregate, copied, idx as u32, &field)
+                            .map_err(|e| anyhow!("failed to insert field '{field}': {e}"))?
+                            .into_struct_value();
+                    }
+                }
+                aggregate.into()
+            }
+            // Scalars and strings are immutable, so sharing them is a copy
+            _ => raw,
+        };
+        Ok(EvaluatedValue::with_value(copied, value.ty))
+    }
+
+    /// A list or map handle the runtime can walk; structs become maps
+    fn build_reflected_handle(
+        &mut self,
+        value: EvaluatedValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let raw = value
+            .value
+            .ok_or_else(|| anyhow!("diff() argument has no value"))?;
+        match value.ty {
+            OtterType::List(_) | OtterType::Map => Ok(raw),
+            OtterType::Struct(struct_id) => {
+                self.build_struct_map(struct_id, raw.into_struct_value())
+            }
+            other => bail!("diff() expects lists, maps or structs, got {:?}", other),
+        }
+    }
+
+    fn build_struct_map(
+        &mut self,
+        struct_id: u32,
+        value: StructValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        let map = self.call_ffi_returning_value("map.new", vec![], "struct_map")?;
+        for (field, idx, ty) in self.ordered_struct_fields(struct_id, "") {
+            // Following a boxed field could go on forever
+            if self.is_boxed_field(struct_id, idx) {
+                continue;
+            }
+            let field_value = self
+                .builder
+                .build_extract_value(value, idx as u32, &field)
+                .map_err(|e| anyhow!("failed to extract field '{field}': {e}"))?;
+            let (setter, field_value) = match ty {
+                OtterType::Str => ("map.set", field_value),
+                OtterType::I64 => ("set<map,int>", field_value),
+                ref int_ty if int_ty.is_int() => (
+                    "set<map,int>",
+                    self.coerce_type(field_value, int_ty.clone(), OtterType::I64)?,
+                ),
+                OtterType::F64 => ("set<map,float>", field_value),
+                OtterType::Bool => ("set<map,bool>", field_value),
+                OtterType::List(_) => ("set<map,list>", field_value),
+                OtterType::Map => ("set<map,map>", field_value),
+                OtterType::Struct(nested) => (
+                    "set<map,map>",
+                    self.build_struct_map(nested, field_value.into_struct_value())?,
+                ),
+                // Handles and tuples have no runtime representation to compare
+                _ => continue,
+            };
+            let key = self
+                .builder
+                .build_global_string_ptr(&field, "field_name")?
+                .as_pointer_value();
+            self.call_ffi_returning_value(setter, vec![map, key.into(), field_value], "set_field")?;
+        }
+        Ok(map)
+    }
+
     fn try_build_enum_constructor(
         &mut self,
         call_expr: &Expr,
@@ -2862,11 +4176,10 @@ impl<'ctx> Compiler<'ctx> {
             let field_types: Vec<TypeInfo> = evaluated_args
                 .iter()
                 .map(|val| match val.ty.clone() {
-                    OtterType::I64 => TypeInfo::I64,
                     OtterType::F64 => TypeInfo::F64,
                     OtterType::Bool => TypeInfo::Bool,
                     OtterType::Str => TypeInfo::Str,
-                    _ => TypeInfo::Unknown,
+                    other => int_typeinfo(&other).unwrap_or(TypeInfo::Unknown),
                 })
                 .collect();
 
@@ -2904,6 +4217,35 @@ impl<'ctx> Compiler<'ctx> {
         Ok(None)
     }
 
+    /// Builds `Enum.Variant(name=value, ...)`, evaluating the fields in the
+    /// order the variant declares them
+    fn eval_named_enum_variant(
+        &mut self,
+        expr: &Expr,
+        variant_name: &str,
+        fields: &[(String, Node<Expr>)],
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let Some(enum_type) = self.expr_type(expr).cloned() else {
+            bail!("missing type for enum variant '{}'", variant_name);
+        };
+        let TypeInfo::Enum { name, variants, .. } = &enum_type else {
+            bail!("'{}' is not an enum variant", variant_name);
+        };
+        let variant = variants
+            .get(variant_name)
+            .ok_or_else(|| anyhow!("No variant named {variant_name} for enum {name}"))?;
+        let mut values = Vec::with_capacity(variant.field_names.len());
+        for field_name in &variant.field_names {
+            let (_, field_expr) = fields
+                .iter()
+                .find(|(name, _)| name == field_name)
+                .ok_or_else(|| anyhow!("missing field '{field_name}' for {name}.{variant_name}"))?;
+            values.push(self.eval_expr(field_expr.as_ref(), ctx)?);
+        }
+        self.build_enum_value_from_type(&enum_type, variant_name, values)
+    }
+
     fn module_path_from_expr(&self, expr: &Expr) -> Option<String> {
         self.expr_type(expr).and_then(|ty| match ty {
             TypeInfo::Module(name) => Some(name.clone()),
@@ -2930,6 +4272,63 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// Calls the `eq` method of the struct or enum `left` evaluated to, if it
+    /// has one
+    fn build_eq_method_call(
+        &mut self,
+        left: &Expr,
+        lhs: &EvaluatedValue<'ctx>,
+        rhs: &EvaluatedValue<'ctx>,
+    ) -> Result<Option<IntValue<'ctx>>> {
+        let method = match lhs.ty {
+            OtterType::Struct(struct_id) => self.resolve_struct_method_name(struct_id, "eq"),
+            _ => self.resolve_enum_method_name(left, "eq"),
+        };
+        let Some(function) = method.and_then(|name| self.declared_functions.get(&name).copied())
+        else {
+            return Ok(None);
+        };
+        let returns_bool = function
+            .get_type()
+            .get_return_type()
+            .is_some_and(|ty| ty.is_int_type() && ty.into_int_type().get_bit_width() == 1);
+        if function.count_params() != 2 || !returns_bool {
+            return Ok(None);
+        }
+        let param_types: Vec<BasicTypeEnum> = function
+            .get_param_iter()
+            .map(|param| param.get_type())
+            .collect();
+        let mut args: Vec<BasicMetadataValueEnum> = Vec::new();
+        for (operand, param_type) in [lhs, rhs].into_iter().zip(&param_types) {
+            let Some(value) = operand.value else {
+                return Ok(None);
+            };
+            args.push(
+                self.cast_argument_for_call(value, operand.ty.clone(), param_type)?
+                    .into(),
+            );
+        }
+        let equal = self
+            .builder
+            .build_call(function, &args, "eq")?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| anyhow!("eq method did not return a value"))?;
+        Ok(Some(equal.into_int_value()))
+    }
+
+    /// Compiled name of method `field` when `object` is an enum value
+    pub(crate) fn resolve_enum_method_name(&self, object: &Expr, field: &str) -> Option<String> {
+        let Some(TypeInfo::Enum { name, .. }) = self.expr_type(object) else {
+            return None;
+        };
+        let candidate = format!("{}_{}", name, field);
+        self.declared_functions
+            .contains_key(&candidate)
+            .then_some(candidate)
+    }
+
     fn flatten_member_chain(&self, expr: &Expr) -> Option<String> {
         match expr {
             Expr::Identifier(name) => Some(name.clone()),
@@ -2941,7 +4340,7 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
-    fn build_enum_value_from_type(
+    pub(crate) fn build_enum_value_from_type(
         &mut self,
         enum_type: &TypeInfo,
         variant_name: &str,
@@ -3062,11 +4461,9 @@ impl<'ctx> Compiler<'ctx> {
         let raw = value.ok_or_else(|| anyhow!("missing value for enum field"))?;
         let int_value = match ty {
             OtterType::I64 | OtterType::Opaque => raw.into_int_value(),
-            OtterType::I32 => self.builder.build_int_s_extend(
-                raw.into_int_value(),
-                self.context.i64_type(),
-                "i32_to_i64",
-            )?,
+            ref int_ty if int_ty.is_int() => self
+                .coerce_type(raw, int_ty.clone(), OtterType::I64)?
+                .into_int_value(),
             OtterType::Bool => self.builder.build_int_z_extend(
                 raw.into_int_value(),
                 self.context.i64_type(),
@@ -3089,16 +4486,9 @@ impl<'ctx> Compiler<'ctx> {
         let raw = value.ok_or_else(|| anyhow!("missing value for enum field"))?;
         let float_value = match ty {
             OtterType::F64 => raw.into_float_value(),
-            OtterType::I64 => self.builder.build_signed_int_to_float(
-                raw.into_int_value(),
-                self.context.f64_type(),
-                "i64_to_f64",
-            )?,
-            OtterType::I32 => self.builder.build_signed_int_to_float(
-                raw.into_int_value(),
-                self.context.f64_type(),
-                "i32_to_f64",
-            )?,
+            ref int_ty if int_ty.is_int() => self
+                .coerce_type(raw, int_ty.clone(), OtterType::F64)?
+                .into_float_value(),
             _ => {
                 bail!("cannot convert {:?} to f64 for enum field", ty);
             }
@@ -3128,9 +4518,38 @@ enum EnumFieldKind {
 fn enum_field_kind(field_type: &TypeInfo) -> EnumFieldKind {
     match field_type {
         TypeInfo::Bool => EnumFieldKind::Bool,
-        TypeInfo::I32 | TypeInfo::I64 => EnumFieldKind::Int,
+        ty if ty.is_integer() => EnumFieldKind::Int,
         TypeInfo::F64 => EnumFieldKind::Float,
         TypeInfo::Alias { underlying, .. } => enum_field_kind(underlying),
         _ => EnumFieldKind::Ptr,
     }
 }
+
+/// The checker's type for an integer `OtterType`
+fn int_typeinfo(ty: &OtterType) -> Option<TypeInfo> {
+    Some(match ty {
+        OtterType::I8 => TypeInfo::I8,
+        OtterType::I16 => TypeInfo::I16,
+        OtterType::I32 => TypeInfo::I32,
+        OtterType::I64 => TypeInfo::I64,
+        OtterType::U8 => TypeInfo::U8,
+        OtterType::U16 => TypeInfo::U16,
+        OtterType::U32 => TypeInfo::U32,
+        OtterType::U64 => TypeInfo::U64,
+        _ => return None,
+    })
+}
+
+/// The test of a `-1`/`0`/`1` ordering against zero that a comparison
+/// operator makes
+fn ordering_predicate(op: &BinaryOp) -> Option<IntPredicate> {
+    Some(match op {
+        BinaryOp::Eq => IntPredicate::EQ,
+        BinaryOp::Ne => IntPredicate::NE,
+        BinaryOp::Lt => IntPredicate::SLT,
+        BinaryOp::Gt => IntPredicate::SGT,
+        BinaryOp::LtEq => IntPredicate::SLE,
+        BinaryOp::GtEq => IntPredicate::SGE,
+        _ => return None,
+    })
+}
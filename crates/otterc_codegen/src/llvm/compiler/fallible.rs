@@ -0,0 +1,241 @@
+//! Combinator methods of `Result` and `Option`
+//!
+//! `value.map(f)`, `value.and_then(f)`, `value.unwrap_or(default)` and
+//! `value.expect(message)` branch on the variant of `value` where they are
+//! called. A lambda passed to `map` or `and_then` is compiled inline, with its
+//! parameter bound to the carried value, and a named function is called with
+//! it. Function values held in variables cannot be called yet.
+
+use anyhow::{Result, anyhow, bail};
+use inkwell::IntPredicate;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum};
+
+use crate::llvm::compiler::Compiler;
+use crate::llvm::compiler::types::{EvaluatedValue, FunctionContext, OtterType};
+use otterc_ast::nodes::{Expr, Node};
+use otterc_typecheck::{Fallible, TypeInfo};
+
+/// Local holding the carried value while a named function is called with it
+const CARRIED_VALUE: &str = "__otter_carried_value";
+
+impl<'ctx> Compiler<'ctx> {
+    pub(crate) fn try_build_fallible_method(
+        &mut self,
+        call: &Expr,
+        func_expr: &Expr,
+        args: &[Node<Expr>],
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<Option<EvaluatedValue<'ctx>>> {
+        let (Expr::Member { object, field }, [arg]) = (func_expr, args) else {
+            return Ok(None);
+        };
+        let object = object.as_ref().as_ref();
+        if !Fallible::METHODS.contains(&field.as_str())
+            || self.resolve_enum_method_name(object, field).is_some()
+        {
+            return Ok(None);
+        }
+        let Some(receiver_type) = self.expr_type(object).cloned() else {
+            return Ok(None);
+        };
+        let (Some(fallible), TypeInfo::Enum { name, .. }) =
+            (receiver_type.fallible(), &receiver_type)
+        else {
+            return Ok(None);
+        };
+        let success = fallible.success;
+        let value_type = fallible.value.clone();
+        let success_tag = self
+            .enum_layout(name)
+            .and_then(|layout| layout.tag_of(success))
+            .ok_or_else(|| anyhow!("Enum layout not found for {}", name))?;
+
+        let receiver = self.eval_expr(object, ctx)?;
+        let handle = receiver
+            .value
+            .ok_or_else(|| anyhow!("{} value has no runtime value", name))?;
+        // The default and the message are evaluated whichever the variant,
+        // like the arguments of any call
+        let argument = match field.as_str() {
+            "unwrap_or" | "expect" => Some(self.eval_expr(arg.as_ref(), ctx)?),
+            _ => None,
+        };
+
+        let function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or_else(|| anyhow!("{}() outside of a function", field))?;
+        let success_bb = self
+            .context
+            .append_basic_block(function, "fallible_success");
+        let failure_bb = self
+            .context
+            .append_basic_block(function, "fallible_failure");
+        let merge_bb = self.context.append_basic_block(function, "fallible_merge");
+
+        let get_tag_fn = self.get_or_declare_ffi_function("runtime.enum.get_tag")?;
+        let tag = self
+            .builder
+            .build_call(get_tag_fn, &[handle.into()], "tag")?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| anyhow!("runtime.enum.get_tag returned void"))?
+            .into_int_value();
+        let expected = self
+            .context
+            .i64_type()
+            .const_int(u64::from(success_tag), false);
+        let is_success =
+            self.builder
+                .build_int_compare(IntPredicate::EQ, tag, expected, "is_success")?;
+        self.builder
+            .build_conditional_branch(is_success, success_bb, failure_bb)?;
+
+        // `map` and `and_then` produce another enum value, the others the
+        // carried value
+        let slot_type = match field.as_str() {
+            "map" | "and_then" => OtterType::Opaque,
+            _ if matches!(value_type, TypeInfo::Str | TypeInfo::Symbol) => OtterType::Str,
+            _ => self
+                .typeinfo_to_otter_type(&value_type)
+                .filter(|ty| ty.is_int() || matches!(ty, OtterType::F64 | OtterType::Bool))
+                .unwrap_or(OtterType::Opaque),
+        };
+        let slot =
+            self.create_entry_block_alloca(function, "fallible_result", slot_type.clone())?;
+
+        self.builder.position_at_end(success_bb);
+        let carried = self.load_enum_field(handle, 0, &value_type)?;
+        let carried = match value_type {
+            TypeInfo::Str | TypeInfo::Symbol => self.convert_carried(carried, &OtterType::Str)?,
+            _ => carried,
+        };
+        let produced = match field.as_str() {
+            "map" => {
+                let mapped = self.apply_callback(field, arg, carried, ctx)?;
+                let result_type = self
+                    .expr_type(call)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("map() result type not inferred"))?;
+                self.build_enum_value_from_type(&result_type, success, vec![mapped])?
+            }
+            "and_then" => self.apply_callback(field, arg, carried, ctx)?,
+            _ => carried,
+        };
+        let produced = self.convert_carried(produced, &slot_type)?;
+        if let Some(value) = produced.value {
+            self.builder.build_store(slot, value)?;
+        }
+        self.builder.build_unconditional_branch(merge_bb)?;
+
+        self.builder.position_at_end(failure_bb);
+        match (field.as_str(), argument) {
+            ("unwrap_or", Some(default)) => {
+                let default = self.convert_carried(default, &slot_type)?;
+                if let Some(value) = default.value {
+                    self.builder.build_store(slot, value)?;
+                }
+                self.builder.build_unconditional_branch(merge_bb)?;
+            }
+            ("expect", Some(message)) => {
+                let message = self.ensure_string_value(message)?;
+                let location = match self.expr_spans.get(&(call as *const Expr as usize)) {
+                    Some(span) => self.source_location(*span),
+                    None => "<unknown>".to_string(),
+                };
+                let location = self
+                    .builder
+                    .build_global_string_ptr(&location, "expect_loc")?
+                    .as_pointer_value();
+                let panic_fn = self.get_or_declare_ffi_function("__otter_panic_at")?;
+                let panic_args: [BasicMetadataValueEnum; 2] = [message.into(), location.into()];
+                self.builder.build_call(panic_fn, &panic_args, "")?;
+                self.builder.build_unreachable()?;
+            }
+            // An `Err` or `None` carries over unchanged
+            _ => {
+                self.builder.build_store(slot, handle)?;
+                self.builder.build_unconditional_branch(merge_bb)?;
+            }
+        }
+
+        self.builder.position_at_end(merge_bb);
+        let Some(llvm_type) = self.basic_type(slot_type.clone())? else {
+            bail!("{}() result has no runtime representation", field);
+        };
+        let result = self.builder.build_load(llvm_type, slot, "fallible")?;
+        Ok(Some(EvaluatedValue::with_value(result, slot_type)))
+    }
+
+    /// Calls the callback of `map` or `and_then` with `value`
+    fn apply_callback(
+        &mut self,
+        method: &str,
+        callback: &Node<Expr>,
+        value: EvaluatedValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let (binding, body) = match callback.as_ref() {
+            Expr::Lambda { params, body } if params.len() == 1 => (
+                params[0].as_ref().name.as_ref().clone(),
+                Some(body.as_ref()),
+            ),
+            Expr::Identifier(name)
+                if self.declared_functions.contains_key(name)
+                    || self.nested_functions.contains_key(name) =>
+            {
+                (CARRIED_VALUE.to_string(), None)
+            }
+            _ => bail!("{}() takes a lambda or the name of a function", method),
+        };
+
+        let shadowed = ctx.remove(&binding);
+        self.bind_pattern_value(&binding, &value, ctx)?;
+        let result = match body {
+            Some(body) => self.eval_expr(body.as_ref(), ctx),
+            None => {
+                let span = *callback.span();
+                let call = Expr::Call {
+                    func: Box::new(callback.clone()),
+                    args: vec![Node::new(Expr::Identifier(binding.clone()), span)],
+                };
+                self.eval_call_expr(&call, ctx)
+            }
+        };
+        ctx.remove(&binding);
+        if let Some(variable) = shadowed {
+            ctx.insert(binding, variable);
+        }
+        result
+    }
+
+    /// `value` in the representation of the result slot, where strings read
+    /// back from an enum field are pointers again
+    fn convert_carried(
+        &mut self,
+        value: EvaluatedValue<'ctx>,
+        slot_type: &OtterType,
+    ) -> Result<EvaluatedValue<'ctx>> {
+        let Some(raw) = value.value else {
+            return Ok(value);
+        };
+        let converted: BasicValueEnum<'ctx> = match (&value.ty, slot_type) {
+            (from, to) if from == to => raw,
+            (OtterType::Opaque, OtterType::Str) => self
+                .builder
+                .build_int_to_ptr(raw.into_int_value(), self.string_ptr_type, "carried_str")?
+                .into(),
+            (OtterType::Str, OtterType::Opaque) => self
+                .builder
+                .build_ptr_to_int(
+                    raw.into_pointer_value(),
+                    self.context.i64_type(),
+                    "carried_ptr",
+                )?
+                .into(),
+            (from, to) => self.coerce_type(raw, from.clone(), to.clone())?,
+        };
+        Ok(EvaluatedValue::with_value(converted, slot_type.clone()))
+    }
+}
@@ -0,0 +1,437 @@
+//! Loop-invariant code motion
+//!
+//! A loop such as `while i < len(xs):` asks the runtime for the length of
+//! `xs` on every iteration. When nothing in the loop can change that length,
+//! the call is made once before the loop and its result is reused. A `str`
+//! never changes, and a list or dict only changes through calls, so a loop
+//! that rebinds neither and calls nothing but read-only builtins and methods
+//! sees the same length throughout. As with any unsynchronized access,
+//! another task changing the collection while the loop runs is not noticed.
+//!
+//! Only calls that every iteration makes are moved: not those behind an
+//! `if`, a short-circuiting `and`/`or`, a match arm, an inner loop's body or
+//! a statement that can leave the iteration, which may be guarded by a check
+//! the call relies on. The call is also made when the loop runs no
+//! iterations, which is harmless: the length of a `str`, list or dict is
+//! always defined.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::llvm::compiler::Compiler;
+use crate::llvm::compiler::types::{FunctionContext, OtterType};
+use otterc_ast::nodes::{BinaryOp, Block, Expr, FStringPart, Node, Statement};
+use otterc_typecheck::TypeInfo;
+
+/// Builtins that read their arguments without changing them
+const READ_ONLY_FUNCTIONS: &[&str] = &["len", "print", "println", "str"];
+
+/// Methods of lists and dicts that leave them unchanged
+const READ_ONLY_METHODS: &[&str] = &["len", "get", "has", "contains", "keys", "values"];
+
+/// Function called by an expression in a loop
+enum Callee<'a> {
+    Function(&'a str),
+    Method {
+        object: &'a Expr,
+        name: &'a str,
+    },
+    /// A lambda, a function value or anything else that could run any code
+    Other,
+}
+
+/// What a loop binds and calls
+#[derive(Default)]
+struct LoopScan<'a> {
+    /// Names bound or assigned anywhere in the loop
+    bound: HashSet<&'a str>,
+    calls: Vec<Callee<'a>>,
+    /// The loop awaits, spawns or leaves a `with` block, which can run other
+    /// code that changes collections
+    yields: bool,
+    /// `len()` calls measuring a variable, with that variable
+    lengths: Vec<(&'a str, &'a Node<Expr>)>,
+    /// Depth of `spawn` expressions being scanned, whose code runs in a
+    /// function of its own where nothing can be moved
+    spawned: usize,
+    /// Depth of code being scanned that an iteration may skip
+    conditional: usize,
+}
+
+impl<'a> LoopScan<'a> {
+    fn block(&mut self, block: &'a Block) {
+        let outer = self.conditional;
+        for statement in &block.statements {
+            self.statement(statement.as_ref());
+            // Whatever follows may be skipped by this statement
+            if leaves_iteration(statement.as_ref()) {
+                self.conditional = outer + 1;
+            }
+        }
+        self.conditional = outer;
+    }
+
+    /// Scans `block`, which an iteration may skip
+    fn conditional_block(&mut self, block: &'a Block) {
+        self.conditional += 1;
+        self.block(block);
+        self.conditional -= 1;
+    }
+
+    /// Scans `expr`, which an iteration may skip
+    fn conditional_expr(&mut self, expr: &'a Node<Expr>) {
+        self.conditional += 1;
+        self.expr(expr);
+        self.conditional -= 1;
+    }
+
+    fn statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::Let { name, expr, .. }
+            | Statement::Assignment { name, expr }
+            | Statement::Const { name, expr, .. } => {
+                self.bound.insert(name.as_ref());
+                self.expr(expr);
+            }
+            Statement::If {
+                cond,
+                then_block,
+                elif_blocks,
+                else_block,
+            } => {
+                self.expr(cond);
+                self.conditional_block(then_block.as_ref());
+                for (cond, block) in elif_blocks {
+                    self.conditional_expr(cond);
+                    self.conditional_block(block.as_ref());
+                }
+                if let Some(block) = else_block {
+                    self.conditional_block(block.as_ref());
+                }
+            }
+            Statement::For {
+                var,
+                iterable,
+                body,
+                is_await,
+                ..
+            } => {
+                self.bound.insert(var.as_ref());
+                self.yields |= *is_await;
+                self.expr(iterable);
+                self.conditional_block(body.as_ref());
+            }
+            Statement::While { cond, body, .. } => {
+                self.expr(cond);
+                self.conditional_block(body.as_ref());
+            }
+            Statement::With { expr, alias, body } => {
+                self.yields = true;
+                if let Some(alias) = alias {
+                    self.bound.insert(alias.as_ref());
+                }
+                self.expr(expr);
+                self.block(body.as_ref());
+            }
+            Statement::Return(Some(expr)) | Statement::Expr(expr) => self.expr(expr),
+            // Not evaluated at all when asserts are stripped
+            Statement::Assert { cond, message } => {
+                self.conditional_expr(cond);
+                if let Some(message) = message {
+                    self.conditional_expr(message);
+                }
+            }
+            // Compiled as a function of its own, so nothing in it is moved
+            Statement::Function(func) => {
+                self.bound.insert(func.as_ref().name.as_str());
+            }
+            Statement::Block(block) => self.block(block.as_ref()),
+            // Runs when the function exits rather than in the loop
+            Statement::Defer(_) => {}
+            Statement::Return(None)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Pass
+            | Statement::Struct { .. }
+            | Statement::Enum { .. }
+            | Statement::TypeAlias { .. }
+            | Statement::Protocol { .. }
+            | Statement::Init(_)
+            | Statement::Use { .. }
+            | Statement::PubUse { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Node<Expr>) {
+        match expr.as_ref() {
+            Expr::Literal(_) | Expr::Identifier(_) | Expr::Lambda { .. } => {}
+            Expr::Member { object, .. } => self.expr(object),
+            Expr::Call { func, args } => {
+                let measured = match (func.as_ref().as_ref(), args.as_slice()) {
+                    (Expr::Identifier(name), [arg]) if name == "len" => Some(arg.as_ref()),
+                    (Expr::Member { object, field }, []) if field == "len" => {
+                        Some(object.as_ref().as_ref())
+                    }
+                    _ => None,
+                };
+                if let (0, 0, Some(Expr::Identifier(variable))) =
+                    (self.spawned, self.conditional, measured)
+                {
+                    self.lengths.push((variable.as_str(), expr));
+                }
+                match func.as_ref().as_ref() {
+                    Expr::Identifier(name) => self.calls.push(Callee::Function(name)),
+                    Expr::Member { object, field } => {
+                        self.calls.push(Callee::Method {
+                            object: object.as_ref().as_ref(),
+                            name: field,
+                        });
+                        self.expr(object);
+                    }
+                    // A lambda or function value could run any code
+                    _ => {
+                        self.calls.push(Callee::Other);
+                        self.expr(func);
+                    }
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Binary {
+                op: BinaryOp::And | BinaryOp::Or,
+                left,
+                right,
+            } => {
+                self.expr(left);
+                self.conditional_expr(right);
+            }
+            Expr::Binary { left, right, .. }
+            | Expr::Range {
+                start: left,
+                end: right,
+            } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => self.expr(expr),
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.conditional_expr(then_branch);
+                if let Some(branch) = else_branch {
+                    self.conditional_expr(branch);
+                }
+            }
+            Expr::Match { value, arms } => {
+                self.expr(value);
+                for arm in arms {
+                    let arm = arm.as_ref();
+                    self.bound.extend(arm.pattern.as_ref().bindings());
+                    if let Some(guard) = &arm.guard {
+                        self.conditional_expr(guard);
+                    }
+                    self.conditional_block(arm.body.as_ref());
+                }
+            }
+            Expr::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Expr::Dict(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::ListComprehension {
+                element: value,
+                var,
+                iterable,
+                condition,
+            }
+            | Expr::DictComprehension {
+                value,
+                var,
+                iterable,
+                condition,
+                ..
+            } => {
+                self.bound.insert(var);
+                // Only the iterable is evaluated when it is empty
+                self.expr(iterable);
+                if let Expr::DictComprehension { key, .. } = expr.as_ref() {
+                    self.conditional_expr(key);
+                }
+                self.conditional_expr(value);
+                if let Some(condition) = condition {
+                    self.conditional_expr(condition);
+                }
+            }
+            Expr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr, _) = part.as_ref() {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::Struct { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::Await(expr) => {
+                self.yields = true;
+                self.expr(expr);
+            }
+            Expr::Spawn(expr) => {
+                self.yields = true;
+                self.spawned += 1;
+                self.expr(expr);
+                self.spawned -= 1;
+            }
+        }
+    }
+}
+
+/// Whether `statement` may end the iteration early, so that the statements
+/// after it are skipped
+fn leaves_iteration(statement: &Statement) -> bool {
+    let block_leaves = |block: &Block| {
+        block
+            .statements
+            .iter()
+            .any(|statement| leaves_iteration(statement.as_ref()))
+    };
+    match statement {
+        Statement::Break(_) | Statement::Continue(_) | Statement::Return(_) => true,
+        Statement::If {
+            then_block,
+            elif_blocks,
+            else_block,
+            ..
+        } => {
+            block_leaves(then_block.as_ref())
+                || elif_blocks
+                    .iter()
+                    .any(|(_, block)| block_leaves(block.as_ref()))
+                || else_block
+                    .as_ref()
+                    .is_some_and(|block| block_leaves(block.as_ref()))
+        }
+        // A labeled `break` or `continue` inside can target this loop
+        Statement::For { body, .. }
+        | Statement::While { body, .. }
+        | Statement::With { body, .. }
+        | Statement::Block(body) => block_leaves(body.as_ref()),
+        Statement::Expr(expr) => match expr.as_ref() {
+            Expr::Match { arms, .. } => arms
+                .iter()
+                .any(|arm| block_leaves(arm.as_ref().body.as_ref())),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Evaluates the invariant `len()` calls of a loop ahead of it. Returns
+    /// the calls that now reuse those values, for
+    /// [`release_hoisted`](Self::release_hoisted) once the loop is compiled.
+    pub(crate) fn hoist_loop_invariants(
+        &mut self,
+        keyword: &str,
+        cond: Option<&Node<Expr>>,
+        body: &Block,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<Vec<usize>> {
+        let mut scan = LoopScan::default();
+        if let Some(cond) = cond {
+            scan.expr(cond);
+        }
+        scan.block(body);
+        let collections_change = scan.yields
+            || scan
+                .calls
+                .iter()
+                .any(|callee| !self.is_read_only(callee, ctx));
+
+        let mut groups: Vec<(&str, Vec<&Node<Expr>>)> = Vec::new();
+        for (variable, call) in scan.lengths {
+            let id = call.as_ref() as *const Expr as usize;
+            if scan.bound.contains(variable) || self.hoisted.contains_key(&id) {
+                continue;
+            }
+            let invariant = match ctx.get(variable).map(|var| &var.ty) {
+                Some(OtterType::Str) => true,
+                Some(OtterType::List(_) | OtterType::Map) => !collections_change,
+                _ => false,
+            };
+            if !invariant {
+                continue;
+            }
+            match groups.iter_mut().find(|(name, _)| *name == variable) {
+                Some((_, calls)) => calls.push(call),
+                None => groups.push((variable, vec![call])),
+            }
+        }
+
+        let mut hoisted = Vec::new();
+        for (variable, calls) in groups {
+            let value = self.eval_call_expr(calls[0].as_ref(), ctx)?;
+            let uses = match calls.len() {
+                1 => String::new(),
+                uses => format!(" ({uses} uses)"),
+            };
+            self.opt_report.push(format!(
+                "{}: the length of `{variable}` is computed once before this `{keyword}` loop, \
+                 which does not change `{variable}`{uses}",
+                self.source_location(*calls[0].span()),
+            ));
+            for call in calls {
+                let id = call.as_ref() as *const Expr as usize;
+                self.hoisted.insert(id, value.clone());
+                hoisted.push(id);
+            }
+        }
+        Ok(hoisted)
+    }
+
+    pub(crate) fn release_hoisted(&mut self, hoisted: Vec<usize>) {
+        for id in hoisted {
+            self.hoisted.remove(&id);
+        }
+    }
+
+    /// Whether `callee` leaves every list and dict as it is
+    fn is_read_only(&self, callee: &Callee<'_>, ctx: &FunctionContext<'ctx>) -> bool {
+        match callee {
+            // Always the builtin, even where the module defines its own
+            Callee::Function("len") => true,
+            Callee::Function(name) => {
+                READ_ONLY_FUNCTIONS.contains(name)
+                    && ctx.get(name).is_none()
+                    && !self.nested_functions.contains_key(*name)
+                    && !self.function_return_types.contains_key(*name)
+            }
+            Callee::Method { object, name } => {
+                let local = match object {
+                    Expr::Identifier(variable) => ctx.get(variable).map(|var| &var.ty),
+                    _ => None,
+                };
+                match local {
+                    Some(OtterType::Str) => true,
+                    Some(OtterType::List(_) | OtterType::Map) => READ_ONLY_METHODS.contains(name),
+                    Some(_) => false,
+                    None => matches!(self.expr_type(object), Some(TypeInfo::Str)),
+                }
+            }
+            Callee::Other => false,
+        }
+    }
+}
@@ -1,5 +1,4 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::atomic::AtomicUsize;
 
 use anyhow::{Result, anyhow};
@@ -8,28 +7,65 @@ use inkwell::context::Context as InkwellContext;
 use inkwell::module::Module;
 use inkwell::passes::{PassBuilderOptions, PassManager};
 use inkwell::targets::TargetMachine;
-use inkwell::types::{BasicType, BasicTypeEnum, PointerType, StructType};
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, PointerType, StructType};
 use inkwell::values::{FunctionValue, PointerValue};
 
 use crate::llvm::bridges::prepare_rust_bridges;
-use otterc_ast::nodes::{Block, Expr, FStringPart, Function, Node, Program, Statement};
+use otterc_ast::nodes::{
+    Block, Expr, FStringPart, Function, InlineHint, Node, Param, Program, Statement, Type,
+};
 use otterc_config::CodegenOptLevel;
+use otterc_config::LtoMode;
+use otterc_config::PgoMode;
 use otterc_config::TargetTriple;
-use otterc_span::Span;
+use otterc_span::{SourceLines, Span};
 use otterc_symbol::registry::SymbolRegistry;
-use otterc_typecheck::{EnumLayout, TypeInfo};
+use otterc_typecheck::{ConstValue, EnumLayout, TypeInfo, eval_const_expr};
 
+mod debug;
 pub mod expr;
+mod fallible;
+mod invariant;
 pub mod stmt;
+#[cfg(test)]
+mod tests;
 pub mod types;
 
-use self::types::{FunctionContext, OtterType};
+use self::debug::DebugInfo;
+use self::types::{EvaluatedValue, FunctionContext, OtterType, Variable};
 
 struct StructInfo<'ctx> {
     name: String,
     ty: StructType<'ctx>,
     field_indices: HashMap<String, usize>,
     field_types: Vec<OtterType>,
+    /// Fields stored behind a pointer to a heap copy, see
+    /// [`recursive_struct_fields`]
+    boxed_fields: Vec<bool>,
+}
+
+/// A generic struct, laid out again for each set of type arguments it is
+/// used with
+#[derive(Clone)]
+struct GenericStruct {
+    generics: Vec<String>,
+    fields: Vec<(String, Node<Type>)>,
+    methods: Vec<Node<Function>>,
+}
+
+/// A `type` alias, with the aliases its target names already replaced
+struct TypeAlias {
+    generics: Vec<String>,
+    target: Node<Type>,
+}
+
+/// A function defined inside another, lifted to a top-level LLVM function.
+/// The enclosing function's variables it uses are passed by pointer ahead of
+/// its own arguments, so it reads and assigns the caller's storage.
+#[derive(Clone)]
+pub(crate) struct NestedFunction {
+    pub(crate) name: String,
+    pub(crate) captures: Vec<String>,
 }
 
 pub struct Compiler<'ctx> {
@@ -48,14 +84,49 @@ pub struct Compiler<'ctx> {
     expr_spans: HashMap<usize, Span>,
     pub(crate) enum_layouts: HashMap<String, EnumLayout>,
     pub(crate) function_defaults: HashMap<String, Vec<Option<Expr>>>,
-    #[expect(dead_code, reason = "Work in progress")]
+    /// Module-level constants, inlined wherever they are used
+    pub(crate) constants: HashMap<String, (ConstValue, OtterType)>,
     pub(crate) lambda_counter: AtomicUsize,
     next_spawn_id: u64,
     struct_ids: HashMap<String, u32>,
     struct_infos: Vec<StructInfo<'ctx>>,
+    generic_structs: HashMap<String, GenericStruct>,
+    /// Type aliases declared in the module, by name
+    type_aliases: HashMap<String, TypeAlias>,
+    /// `(struct, field)` pairs laid out as pointers
+    boxed_struct_fields: HashSet<(String, String)>,
+    /// Methods of generic struct instantiations and copies of protocol
+    /// functions, compiled after everything else
+    instance_methods: Vec<Function>,
+    /// Protocols declared in the module
+    protocols: HashSet<String>,
+    /// Functions taking a protocol, compiled once per set of argument types
+    /// they are called with
+    protocol_functions: HashMap<String, Function>,
+    /// Nested functions visible in the function being compiled, by Otter name
+    pub(crate) nested_functions: HashMap<String, NestedFunction>,
+    /// Names bound by `from module import name`, mapped to `module.name`
+    pub(crate) imported_items: HashMap<String, String>,
     pub cached_ir: Option<String>,
     /// Target triple for platform-specific ABI handling
     target_triple: Option<TargetTriple>,
+    /// Skip `assert` statements entirely
+    pub(crate) strip_asserts: bool,
+    /// Call the scheduler's preemption point at loop back-edges
+    pub(crate) preempt_loops: bool,
+    /// Report the entry and exit of every function to the runtime profiler
+    pub(crate) profile_functions: bool,
+    /// Instrument memory accesses for AddressSanitizer
+    pub(crate) sanitize_address: bool,
+    /// Line index of the compiled source, for locations in runtime messages
+    pub(crate) source_lines: Option<SourceLines>,
+    /// DWARF being emitted for the module, with `-g`
+    pub(crate) debug_info: Option<DebugInfo<'ctx>>,
+    /// Values of loop-invariant calls computed ahead of the loop being
+    /// compiled, by the address of the call expression
+    pub(crate) hoisted: HashMap<usize, EvaluatedValue<'ctx>>,
+    /// What was moved out of loops, one line each, for `--emit opt-report`
+    pub opt_report: Vec<String>,
 }
 
 impl<'ctx> Compiler<'ctx> {
@@ -73,17 +144,25 @@ impl<'ctx> Compiler<'ctx> {
         match stmt {
             Statement::Expr(expr)
             | Statement::Let { expr, .. }
+            | Statement::Const { expr, .. }
             | Statement::Assignment { expr, .. }
-            | Statement::Return(Some(expr)) => self.record_expr_spans(expr),
+            | Statement::Return(Some(expr))
+            | Statement::Defer(expr) => self.record_expr_spans(expr),
+            Statement::Assert { cond, message } => {
+                self.record_expr_spans(cond);
+                if let Some(message) = message {
+                    self.record_expr_spans(message);
+                }
+            }
             Statement::Return(None)
-            | Statement::Break
-            | Statement::Continue
+            | Statement::Break(_)
+            | Statement::Continue(_)
             | Statement::Pass
             | Statement::Use { .. }
             | Statement::PubUse { .. }
-            | Statement::Enum { .. }
-            | Statement::TypeAlias { .. } => {}
-            Statement::Struct { methods, .. } => {
+            | Statement::TypeAlias { .. }
+            | Statement::Protocol { .. } => {}
+            Statement::Struct { methods, .. } | Statement::Enum { methods, .. } => {
                 for method in methods {
                     self.record_function_spans(method.as_ref());
                 }
@@ -109,11 +188,16 @@ impl<'ctx> Compiler<'ctx> {
                 self.record_expr_spans(iterable);
                 self.record_block_spans(body.as_ref());
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, .. }
+            | Statement::With {
+                expr: cond, body, ..
+            } => {
                 self.record_expr_spans(cond);
                 self.record_block_spans(body.as_ref());
             }
-            Statement::Block(block) => self.record_block_spans(block.as_ref()),
+            Statement::Block(block) | Statement::Init(block) => {
+                self.record_block_spans(block.as_ref());
+            }
         }
     }
 
@@ -127,7 +211,11 @@ impl<'ctx> Compiler<'ctx> {
                 self.record_expr_spans(left);
                 self.record_expr_spans(right);
             }
-            Expr::Unary { expr, .. } | Expr::Await(expr) | Expr::Spawn(expr) => {
+            Expr::Unary { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::Await(expr)
+            | Expr::Spawn(expr)
+            | Expr::Lambda { body: expr, .. } => {
                 self.record_expr_spans(expr);
             }
             Expr::Call { func, args } => {
@@ -200,7 +288,7 @@ impl<'ctx> Compiler<'ctx> {
             }
             Expr::FString { parts } => {
                 for part in parts {
-                    if let FStringPart::Expr(expr) = part.as_ref() {
+                    if let FStringPart::Expr(expr, _) = part.as_ref() {
                         self.record_expr_spans(expr);
                     }
                 }
@@ -254,12 +342,29 @@ impl<'ctx> Compiler<'ctx> {
             expr_spans: HashMap::new(),
             enum_layouts,
             function_defaults: HashMap::new(),
+            constants: HashMap::new(),
             lambda_counter: AtomicUsize::new(0),
             next_spawn_id: 0,
             struct_ids: HashMap::new(),
             struct_infos: Vec::new(),
+            generic_structs: HashMap::new(),
+            type_aliases: HashMap::new(),
+            boxed_struct_fields: HashSet::new(),
+            instance_methods: Vec::new(),
+            protocols: HashSet::new(),
+            protocol_functions: HashMap::new(),
+            nested_functions: HashMap::new(),
+            imported_items: HashMap::new(),
             cached_ir: None,
             target_triple,
+            strip_asserts: false,
+            preempt_loops: false,
+            profile_functions: false,
+            sanitize_address: false,
+            source_lines: None,
+            debug_info: None,
+            hoisted: HashMap::new(),
+            opt_report: Vec::new(),
         }
     }
 
@@ -302,6 +407,7 @@ impl<'ctx> Compiler<'ctx> {
             ty: struct_type,
             field_indices: HashMap::new(),
             field_types: Vec::new(),
+            boxed_fields: Vec::new(),
         });
         (id, struct_type)
     }
@@ -320,12 +426,205 @@ impl<'ctx> Compiler<'ctx> {
     }
 
     pub(crate) fn struct_type_from_expr(&self, expr: &Expr) -> Option<OtterType> {
-        if let Some(TypeInfo::Struct { name, .. }) = self.expr_type(expr) {
-            return self.struct_id(name).map(OtterType::Struct);
+        if let Some(TypeInfo::Struct { name, args, .. }) = self.expr_type(expr) {
+            return self.struct_id_for(name, args).map(OtterType::Struct);
         }
         None
     }
 
+    /// Id of struct `name` with type arguments `args`. Generic structs used
+    /// with arguments that are not known fall back to the generic layout.
+    pub(crate) fn struct_id_for(&self, name: &str, args: &[TypeInfo]) -> Option<u32> {
+        self.struct_instance_name(name, args)
+            .and_then(|instance| self.struct_id(&instance))
+            .or_else(|| self.struct_id(name))
+    }
+
+    /// Name of the instantiation of generic struct `name`, e.g. `Stack<int>`
+    fn struct_instance_name(&self, name: &str, args: &[TypeInfo]) -> Option<String> {
+        let definition = self.generic_structs.get(name)?;
+        if args.len() != definition.generics.len() {
+            return None;
+        }
+        let args = args
+            .iter()
+            .map(|arg| self.type_argument_name(arg))
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!("{}<{}>", name, args.join(", ")))
+    }
+
+    /// Spelling of a concrete type argument; `None` for types still unknown
+    fn type_argument_name(&self, ty: &TypeInfo) -> Option<String> {
+        match ty {
+            TypeInfo::I64 => Some("int".to_string()),
+            TypeInfo::F64 => Some("float".to_string()),
+            TypeInfo::Str => Some("string".to_string()),
+            TypeInfo::Bool
+            | TypeInfo::Unit
+            | TypeInfo::Symbol
+            | TypeInfo::I8
+            | TypeInfo::I16
+            | TypeInfo::I32
+            | TypeInfo::U8
+            | TypeInfo::U16
+            | TypeInfo::U32
+            | TypeInfo::U64 => Some(ty.display_name()),
+            TypeInfo::List(inner) => Some(format!("list<{}>", self.type_argument_name(inner)?)),
+            TypeInfo::Dict { .. } => Some("map".to_string()),
+            TypeInfo::Alias { underlying, .. } => self.type_argument_name(underlying),
+            TypeInfo::Struct { name, args, .. } | TypeInfo::Generic { base: name, args }
+                if self.generic_structs.contains_key(name) =>
+            {
+                self.struct_instance_name(name, args)
+            }
+            TypeInfo::Struct { name, .. } | TypeInfo::Enum { name, .. } => Some(name.clone()),
+            TypeInfo::Generic { base, .. }
+                if self.struct_ids.contains_key(base) || self.enum_layouts.contains_key(base) =>
+            {
+                Some(base.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Annotation for a concrete type argument, used in the signatures of an
+    /// instantiation's methods. It has no place in the source, so nested
+    /// types get an empty span.
+    fn type_argument_annotation(&self, ty: &TypeInfo) -> Option<Type> {
+        match ty {
+            TypeInfo::List(inner) => Some(Type::Generic {
+                base: "list".to_string(),
+                args: vec![Node::new(
+                    self.type_argument_annotation(inner)?,
+                    Span::new(0, 0),
+                )],
+            }),
+            TypeInfo::Alias { underlying, .. } => self.type_argument_annotation(underlying),
+            other => self.type_argument_name(other).map(Type::Simple),
+        }
+    }
+
+    /// Lays out generic struct `name` for `args` and declares its methods
+    fn instantiate_struct(&mut self, name: &str, args: &[TypeInfo]) -> Result<()> {
+        let Some(instance) = self.struct_instance_name(name, args) else {
+            return Ok(());
+        };
+        if self.struct_ids.contains_key(&instance) {
+            return Ok(());
+        }
+        let Some(definition) = self.generic_structs.get(name).cloned() else {
+            return Ok(());
+        };
+
+        let mut substitutions = HashMap::new();
+        for (generic, arg) in definition.generics.iter().zip(args) {
+            let Some(annotation) = self.type_argument_annotation(arg) else {
+                return Ok(());
+            };
+            substitutions.insert(generic.clone(), annotation);
+        }
+        let fields: Vec<(String, Node<Type>)> = definition
+            .fields
+            .iter()
+            .map(|(field, ty)| (field.clone(), substitute_type_params(ty, &substitutions)))
+            .collect();
+        self.define_struct_layout(&instance, &fields)?;
+
+        for method in &definition.methods {
+            let mut method_func = method.as_ref().clone();
+            method_func.name = format!("{}_{}", instance, method_func.name);
+            for param in &mut method_func.params {
+                if let Some(ty) = &param.as_ref().ty {
+                    let substituted = substitute_type_params(ty, &substitutions);
+                    param.as_mut().ty = Some(substituted);
+                }
+            }
+            method_func.ret_ty = method_func
+                .ret_ty
+                .as_ref()
+                .map(|ty| substitute_type_params(ty, &substitutions));
+            self.rewrite_method_self_param(&mut method_func, &instance);
+            self.register_function_prototype(&method_func)?;
+            self.instance_methods.push(method_func);
+        }
+        Ok(())
+    }
+
+    /// Generic struct instantiations mentioned by `ty`, innermost first
+    fn collect_struct_instances(&self, ty: &TypeInfo, found: &mut Vec<(String, Vec<TypeInfo>)>) {
+        match ty {
+            TypeInfo::Struct { name, args, .. } | TypeInfo::Generic { base: name, args } => {
+                for arg in args {
+                    self.collect_struct_instances(arg, found);
+                }
+                if self.generic_structs.contains_key(name)
+                    && !args.is_empty()
+                    && !found.iter().any(|(n, a)| n == name && a == args)
+                {
+                    found.push((name.clone(), args.clone()));
+                }
+            }
+            TypeInfo::List(inner)
+            | TypeInfo::Alias {
+                underlying: inner, ..
+            } => self.collect_struct_instances(inner, found),
+            TypeInfo::Dict { key, value } => {
+                self.collect_struct_instances(key, found);
+                self.collect_struct_instances(value, found);
+            }
+            TypeInfo::Function {
+                params,
+                return_type,
+                ..
+            } => {
+                for param in params {
+                    self.collect_struct_instances(param, found);
+                }
+                self.collect_struct_instances(return_type, found);
+            }
+            TypeInfo::Enum { args, .. } => {
+                for arg in args {
+                    self.collect_struct_instances(arg, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn define_struct_layout(&mut self, name: &str, fields: &[(String, Node<Type>)]) -> Result<()> {
+        let (struct_id, struct_type) = self.ensure_struct_info(name);
+
+        let mut field_layout = Vec::new();
+        let mut field_indices = HashMap::new();
+        let mut field_types = Vec::new();
+        let mut boxed_fields = Vec::new();
+        for (idx, (field_name, ty)) in fields.iter().enumerate() {
+            let boxed = self
+                .boxed_struct_fields
+                .contains(&(name.to_string(), field_name.clone()));
+            if boxed {
+                field_layout.push(
+                    self.context
+                        .ptr_type(inkwell::AddressSpace::default())
+                        .into(),
+                );
+            } else {
+                field_layout.push(self.map_ast_type(ty.as_ref())?);
+            }
+            field_indices.insert(field_name.clone(), idx);
+            field_types.push(self.otter_type_from_annotation(ty.as_ref()));
+            boxed_fields.push(boxed);
+        }
+
+        struct_type.set_body(&field_layout, false);
+        if let Some(info) = self.struct_infos.get_mut(struct_id as usize) {
+            info.field_indices = field_indices;
+            info.field_types = field_types;
+            info.boxed_fields = boxed_fields;
+        }
+        Ok(())
+    }
+
     fn rewrite_method_self_param(
         &self,
         method_func: &mut otterc_ast::nodes::Function,
@@ -359,6 +658,80 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// Whether a parameter of `func` is annotated with a protocol
+    fn takes_protocol(&self, func: &Function) -> bool {
+        func.params.iter().any(|param| {
+            matches!(
+                param.as_ref().ty.as_ref().map(|ty| ty.as_ref()),
+                Some(Type::Simple(name)) if self.protocols.contains(name)
+            )
+        })
+    }
+
+    /// Name of the copy of protocol function `name` for the types of `args`,
+    /// e.g. `describe<File>`, declared the first time it is called
+    pub(crate) fn instantiate_protocol_function(
+        &mut self,
+        name: &str,
+        args: &[Node<Expr>],
+        ctx: &FunctionContext<'ctx>,
+    ) -> Result<String> {
+        let mut instance = self
+            .protocol_functions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("'{}' does not take a protocol", name))?;
+        let mut concrete = Vec::new();
+        for (param, arg) in instance.params.iter_mut().zip(args) {
+            let Some((protocol, span)) =
+                param.as_ref().ty.as_ref().and_then(|ty| match ty.as_ref() {
+                    Type::Simple(protocol) if self.protocols.contains(protocol) => {
+                        Some((protocol.clone(), *ty.span()))
+                    }
+                    _ => None,
+                })
+            else {
+                continue;
+            };
+            let arg_type = self
+                .protocol_argument_name(arg.as_ref(), ctx)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "cannot tell which type is passed as `{}` to '{}'",
+                        protocol,
+                        name
+                    )
+                })?;
+            param.as_mut().ty = Some(Node::new(Type::Simple(arg_type.clone()), span));
+            concrete.push(arg_type);
+        }
+        instance.name = format!("{}<{}>", name, concrete.join(", "));
+        if !self.declared_functions.contains_key(&instance.name) {
+            self.register_function_prototype(&instance)?;
+            let name = instance.name.clone();
+            self.instance_methods.push(instance);
+            return Ok(name);
+        }
+        Ok(instance.name)
+    }
+
+    /// Concrete type of an argument passed for a protocol parameter. Inside
+    /// another protocol function's copy the checked type is the protocol, so
+    /// the variable's struct type is used instead.
+    fn protocol_argument_name(&self, arg: &Expr, ctx: &FunctionContext<'ctx>) -> Option<String> {
+        match self.expr_type(arg) {
+            Some(TypeInfo::Protocol(_)) | None => {}
+            Some(ty) => return self.type_argument_name(ty),
+        }
+        let Expr::Identifier(name) = arg else {
+            return None;
+        };
+        match ctx.get(name).map(|var| &var.ty) {
+            Some(OtterType::Struct(id)) => Some(self.struct_info(*id).name.clone()),
+            _ => None,
+        }
+    }
+
     fn resolve_struct_method_name(&self, struct_id: u32, method: &str) -> Option<String> {
         let info = self.struct_info(struct_id);
         let candidate = format!("{}_{}", info.name, method);
@@ -369,6 +742,36 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    fn register_imported_items(&mut self, program: &Program) {
+        for statement in &program.statements {
+            let Statement::Use { imports } = statement.as_ref() else {
+                continue;
+            };
+            for import in imports {
+                let module = &import.as_ref().module;
+                let module = module.rsplit(':').next().unwrap_or(module);
+                let module = module.rsplit('.').next().unwrap_or(module);
+                if import.as_ref().glob {
+                    let prefix = format!("{}.", module);
+                    for function in self.symbol_registry.all() {
+                        if let Some(local) = function.name.strip_prefix(&prefix)
+                            && !local.contains('.')
+                        {
+                            self.imported_items
+                                .insert(local.to_string(), function.name.clone());
+                        }
+                    }
+                }
+                for item in &import.as_ref().items {
+                    self.imported_items.insert(
+                        item.as_ref().local_name().to_string(),
+                        format!("{}.{}", module, item.as_ref().name),
+                    );
+                }
+            }
+        }
+    }
+
     pub fn compile_module(&mut self, program: &Program) -> Result<()> {
         for statement in &program.statements {
             self.record_statement_spans(statement.as_ref());
@@ -377,35 +780,105 @@ impl<'ctx> Compiler<'ctx> {
         // Prepare Rust bridges
         let _libraries = prepare_rust_bridges(program, self.symbol_registry)?;
 
-        // First pass: register all functions and types
+        // An alias's target is expanded with the aliases declared before it,
+        // so expanding an annotation once replaces every alias it names
+        for statement in &program.statements {
+            if let Statement::TypeAlias {
+                name,
+                target,
+                generics,
+                ..
+            } = statement.as_ref()
+            {
+                let target = Node::new(self.expand_type_aliases(target.as_ref()), *target.span());
+                self.type_aliases.insert(
+                    name.clone(),
+                    TypeAlias {
+                        generics: generics.clone(),
+                        target,
+                    },
+                );
+            }
+        }
+
+        self.register_constants(program)?;
+        self.register_imported_items(program);
+
+        // Declare every struct before laying any out, so a field can name a
+        // struct declared after it or the struct it belongs to
+        for statement in &program.statements {
+            if let Statement::Struct { name, .. } = statement.as_ref() {
+                self.ensure_struct_info(name);
+            }
+        }
+        self.boxed_struct_fields = recursive_struct_fields(&program.statements);
+
+        // First pass: lay out types. A generic struct also keeps its
+        // definition so each instantiation the type checker saw gets its own
+        // layout and methods.
+        for statement in &program.statements {
+            if let Statement::Struct {
+                name,
+                fields,
+                methods,
+                generics,
+                ..
+            } = statement.as_ref()
+            {
+                self.define_struct_layout(name, fields)?;
+                if !generics.is_empty() {
+                    self.generic_structs.insert(
+                        name.clone(),
+                        GenericStruct {
+                            generics: generics.clone(),
+                            fields: fields.clone(),
+                            methods: methods.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        if !self.generic_structs.is_empty() {
+            let mut instances = Vec::new();
+            for ty in self
+                .expr_types
+                .values()
+                .chain(self.expr_types_by_span.values())
+            {
+                self.collect_struct_instances(ty, &mut instances);
+            }
+            for (name, args) in instances {
+                self.instantiate_struct(&name, &args)?;
+            }
+        }
+
+        // A function with a protocol parameter has no code of its own; each
+        // call instantiates a copy for the types it passes
+        for statement in &program.statements {
+            if let Statement::Protocol { name, .. } = statement.as_ref() {
+                self.protocols.insert(name.clone());
+            }
+        }
+        for statement in &program.statements {
+            if let Statement::Function(func) = statement.as_ref()
+                && self.takes_protocol(func.as_ref())
+            {
+                self.protocol_functions
+                    .insert(func.as_ref().name.clone(), func.as_ref().clone());
+            }
+        }
+
+        // Then register all functions
         for statement in &program.statements {
             match statement.as_ref() {
+                Statement::Function(func) if self.takes_protocol(func.as_ref()) => {}
                 Statement::Function(func) => {
                     self.register_function_prototype(func.as_ref())?;
                 }
-                Statement::Struct {
-                    name,
-                    fields,
-                    methods,
-                    ..
-                } => {
-                    let (struct_id, struct_type) = self.ensure_struct_info(name);
-
-                    let mut field_layout = Vec::new();
-                    let mut field_indices = HashMap::new();
-                    let mut field_types = Vec::new();
-                    for (idx, (field_name, ty)) in fields.iter().enumerate() {
-                        field_layout.push(self.map_ast_type(ty.as_ref())?);
-                        field_indices.insert(field_name.clone(), idx);
-                        field_types.push(self.otter_type_from_annotation(ty.as_ref()));
-                    }
-
-                    struct_type.set_body(&field_layout, false);
-                    if let Some(info) = self.struct_infos.get_mut(struct_id as usize) {
-                        info.field_indices = field_indices;
-                        info.field_types = field_types;
-                    }
-
+                Statement::Init(body) => {
+                    self.register_function_prototype(&init_function(body))?;
+                }
+                Statement::Struct { name, methods, .. } => {
                     // Register methods
                     for method in methods {
                         let mut method_func = method.as_ref().clone();
@@ -414,6 +887,14 @@ impl<'ctx> Compiler<'ctx> {
                         self.register_function_prototype(&method_func)?;
                     }
                 }
+                Statement::Enum { name, methods, .. } => {
+                    for method in methods {
+                        let mut method_func = method.as_ref().clone();
+                        method_func.name = format!("{}_{}", name, method_func.name);
+                        self.rewrite_method_self_param(&mut method_func, name);
+                        self.register_function_prototype(&method_func)?;
+                    }
+                }
                 _ => {}
             }
         }
@@ -421,11 +902,18 @@ impl<'ctx> Compiler<'ctx> {
         // Second pass: compile function bodies
         for statement in &program.statements {
             match statement.as_ref() {
+                Statement::Function(func) if self.takes_protocol(func.as_ref()) => {}
                 Statement::Function(func) => {
                     self.record_function_spans(func.as_ref());
                     self.compile_function(func.as_ref())?;
                 }
-                Statement::Struct { name, methods, .. } => {
+                Statement::Init(body) => {
+                    // The body is a copy, so its types are found through its spans
+                    let init = init_function(body);
+                    self.record_function_spans(&init);
+                    self.compile_function(&init)?;
+                }
+                Statement::Struct { name, methods, .. } | Statement::Enum { name, methods, .. } => {
                     for method in methods {
                         let mut method_func = method.as_ref().clone();
                         method_func.name = format!("{}_{}", name, method_func.name);
@@ -437,6 +925,13 @@ impl<'ctx> Compiler<'ctx> {
                 _ => {}
             }
         }
+        // Compiling a protocol function's copy can instantiate further copies
+        while !self.instance_methods.is_empty() {
+            for method_func in std::mem::take(&mut self.instance_methods) {
+                self.record_function_spans(&method_func);
+                self.compile_function(&method_func)?;
+            }
+        }
 
         // Verify module
         if let Err(e) = self.module.verify() {
@@ -447,6 +942,42 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
+    /// Computes every `const` in the program, in order, so later constants
+    /// and all function bodies can use them
+    fn register_constants(&mut self, program: &Program) -> Result<()> {
+        let mut values: HashMap<String, ConstValue> = HashMap::new();
+        for statement in &program.statements {
+            let Statement::Const { name, ty, expr, .. } = statement.as_ref() else {
+                continue;
+            };
+            let value = eval_const_expr(expr.as_ref(), &|path| values.get(path).cloned())
+                .map_err(|reason| anyhow!("invalid constant `{}`: {}", name, reason))?;
+            let ty = match ty {
+                Some(ty) => self.otter_type_from_annotation(ty.as_ref()),
+                None => match value {
+                    ConstValue::Int(_) => OtterType::I64,
+                    ConstValue::Float(_) => OtterType::F64,
+                    ConstValue::Bool(_) => OtterType::Bool,
+                    ConstValue::Str(_) => OtterType::Str,
+                },
+            };
+            values.insert(name.as_ref().clone(), value.clone());
+            self.constants.insert(name.as_ref().clone(), (value, ty));
+        }
+        Ok(())
+    }
+
+    /// The literal a default value computes to when it is built from
+    /// literals and constants, so calls leaving the parameter out do not
+    /// compute it again. Other defaults are evaluated at each such call.
+    fn folded_default(&self, default: &Node<Expr>) -> Expr {
+        let lookup = |path: &str| self.constants.get(path).map(|(value, _)| value.clone());
+        match eval_const_expr(default.as_ref(), &lookup) {
+            Ok(value) => Expr::Literal(Node::new(value.to_literal(), *default.span())),
+            Err(_) => default.as_ref().clone(),
+        }
+    }
+
     /// Declare an external function from the symbol registry
     fn declare_external_function(
         &mut self,
@@ -588,38 +1119,103 @@ impl<'ctx> Compiler<'ctx> {
         self.declare_external_function(name, &ffi_func)
     }
 
-    /// Map AST type to LLVM type
-    fn map_ast_type(&self, ty: &otterc_ast::nodes::Type) -> Result<BasicTypeEnum<'ctx>> {
+    /// Where `span` is in the source, for runtime messages
+    pub(crate) fn source_location(&self, span: Span) -> String {
+        match &self.source_lines {
+            Some(lines) => lines.location(span),
+            None => format!("byte {}", span.start()),
+        }
+    }
+
+    /// Map AST type to LLVM type. Unit, which has no value, is an `i8`
+    /// wherever one has to be stored.
+    fn map_ast_type(&self, ty: &Type) -> Result<BasicTypeEnum<'ctx>> {
+        Ok(self
+            .basic_type(self.otter_type_from_annotation(ty))?
+            .unwrap_or_else(|| self.context.i8_type().into()))
+    }
+
+    /// Type a parameter is passed as. The checker writes the types it infers
+    /// from call sites and defaults into the annotations, so a parameter
+    /// still without one is dynamic and passed as an `i64`.
+    fn param_type(&self, param: &Param) -> OtterType {
+        param.ty.as_ref().map_or(OtterType::I64, |ty| {
+            self.otter_type_from_annotation(ty.as_ref())
+        })
+    }
+
+    /// `ty` with every type alias it names replaced by its target
+    fn expand_type_aliases(&self, ty: &Type) -> Type {
         match ty {
-            otterc_ast::nodes::Type::Simple(name) => match name.as_str() {
-                // Lists and Maps are opaque handles
-                "int" | "i64" | "list" | "List" | "map" | "Map" => {
-                    Ok(self.context.i64_type().into())
-                }
-                "float" | "f64" => Ok(self.context.f64_type().into()),
-                "bool" => Ok(self.context.bool_type().into()),
-                "string" | "str" => Ok(self.string_ptr_type.into()),
-                "void" | "unit" => Ok(self.context.i8_type().into()), // Unit as i8 (or void for return)
-                other => {
-                    if let Some(id) = self.struct_id(other) {
-                        Ok(self.struct_info(id).ty.into())
-                    } else {
-                        Ok(self.context.i64_type().into())
+            Type::Simple(name) => match self.type_aliases.get(name) {
+                Some(alias) if alias.generics.is_empty() => alias.target.as_ref().clone(),
+                _ => ty.clone(),
+            },
+            Type::Generic { base, args } => {
+                let args: Vec<Node<Type>> = args
+                    .iter()
+                    .map(|arg| Node::new(self.expand_type_aliases(arg.as_ref()), *arg.span()))
+                    .collect();
+                match self.type_aliases.get(base) {
+                    Some(alias) if alias.generics.len() == args.len() => {
+                        let substitutions: HashMap<String, Type> = alias
+                            .generics
+                            .iter()
+                            .cloned()
+                            .zip(args.iter().map(|arg| arg.as_ref().clone()))
+                            .collect();
+                        substitute_type_params(&alias.target, &substitutions)
+                            .as_ref()
+                            .clone()
                     }
+                    _ => Type::Generic {
+                        base: base.clone(),
+                        args,
+                    },
                 }
-            },
-            otterc_ast::nodes::Type::Generic { .. } => Ok(self.context.i64_type().into()), // Treat generics as opaque handles
+            }
+            Type::Union(members) => Type::Union(
+                members
+                    .iter()
+                    .map(|member| {
+                        Node::new(self.expand_type_aliases(member.as_ref()), *member.span())
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Struct id for an annotation such as `Stack<int>`
+    fn generic_struct_annotation_id(&self, base: &str, ty: &Type) -> Option<u32> {
+        if !self.generic_structs.contains_key(base) {
+            return None;
+        }
+        match TypeInfo::from(ty) {
+            TypeInfo::Generic { args, .. } => self.struct_id_for(base, &args),
+            _ => None,
         }
     }
 
-    fn otter_type_from_annotation(&self, ty: &otterc_ast::nodes::Type) -> OtterType {
+    fn otter_type_from_annotation(&self, ty: &Type) -> OtterType {
+        self.otter_type_from_expanded(&self.expand_type_aliases(ty))
+    }
+
+    /// Type of values annotated with `ty`, in which no aliases are left
+    fn otter_type_from_expanded(&self, ty: &Type) -> OtterType {
         match ty {
             otterc_ast::nodes::Type::Simple(name) => match name.as_str() {
                 "int" | "i64" => OtterType::I64,
+                "i8" => OtterType::I8,
+                "i16" => OtterType::I16,
+                "i32" => OtterType::I32,
+                "u8" => OtterType::U8,
+                "u16" => OtterType::U16,
+                "u32" => OtterType::U32,
+                "u64" => OtterType::U64,
                 "float" | "f64" => OtterType::F64,
                 "bool" => OtterType::Bool,
-                "string" | "str" => OtterType::Str,
-                "unit" | "void" => OtterType::Unit,
+                "string" | "str" | "symbol" => OtterType::Str,
+                "unit" | "void" | "never" => OtterType::Unit,
                 "list" | "List" => OtterType::opaque_list(),
                 "map" | "Map" => OtterType::Map,
                 other => self
@@ -633,14 +1229,19 @@ impl<'ctx> Compiler<'ctx> {
                     "list" | "List" => {
                         let element = args
                             .first()
-                            .map(|arg| self.otter_type_from_annotation(arg.as_ref()))
+                            .map(|arg| self.otter_type_from_expanded(arg.as_ref()))
                             .unwrap_or(OtterType::Opaque);
                         OtterType::list_of(element)
                     }
                     "map" | "Map" => OtterType::Map,
-                    _ => OtterType::Opaque,
+                    _ => self
+                        .generic_struct_annotation_id(base, ty)
+                        .map(OtterType::Struct)
+                        .unwrap_or(OtterType::Opaque),
                 }
             }
+            // Which member a union holds is only known at runtime
+            otterc_ast::nodes::Type::Union(_) => OtterType::Opaque,
         }
     }
 
@@ -684,30 +1285,29 @@ impl<'ctx> Compiler<'ctx> {
     }
 
     fn register_function_prototype(&mut self, func: &otterc_ast::nodes::Function) -> Result<()> {
-        let ret_type: Option<BasicTypeEnum> = if let Some(ret_ty) = &func.ret_ty {
-            let mapped_ty = self.map_ast_type(ret_ty.as_ref())?;
-            // Check if it's effectively unit/void
-            if let otterc_ast::nodes::Type::Simple(name) = ret_ty.as_ref() {
-                if name == "void" || name == "unit" {
-                    None
-                } else {
-                    Some(mapped_ty)
-                }
-            } else {
-                Some(mapped_ty)
-            }
-        } else {
-            None
-        };
+        self.declare_function(func, &func.name, 0)?;
+        Ok(())
+    }
 
-        let mut param_types = Vec::new();
+    /// Declares `func` as `name`, taking `captures` pointers to the enclosing
+    /// function's variables before its own parameters
+    fn declare_function(
+        &mut self,
+        func: &otterc_ast::nodes::Function,
+        name: &str,
+        captures: usize,
+    ) -> Result<FunctionValue<'ctx>> {
+        let ret_otter_type = func.ret_ty.as_ref().map_or(OtterType::Unit, |ty| {
+            self.otter_type_from_annotation(ty.as_ref())
+        });
+        let ret_type = self.basic_type(ret_otter_type.clone())?;
+
+        let mut param_types = vec![BasicMetadataTypeEnum::from(self.string_ptr_type); captures];
         for param in &func.params {
-            if let Some(ty) = &param.as_ref().ty {
-                param_types.push(self.map_ast_type(ty.as_ref())?.into());
-            } else {
-                // Default to i64 if no type specified
-                param_types.push(self.context.i64_type().into());
-            }
+            let param_type = self
+                .basic_type(self.param_type(param.as_ref()))?
+                .unwrap_or_else(|| self.context.i8_type().into());
+            param_types.push(param_type.into());
         }
 
         let fn_type = if let Some(rt) = ret_type {
@@ -716,66 +1316,100 @@ impl<'ctx> Compiler<'ctx> {
             self.context.void_type().fn_type(&param_types, false)
         };
 
-        let llvm_name = if func.name == "main" {
-            "otter_entry"
-        } else {
-            &func.name
-        };
+        let llvm_name = if name == "main" { "otter_entry" } else { name };
         let function = self.module.add_function(llvm_name, fn_type, None);
-        self.declared_functions.insert(func.name.clone(), function);
+        if let Some(hint) = func.inline {
+            let attribute = match hint {
+                InlineHint::Always => "alwaysinline",
+                InlineHint::Never => "noinline",
+            };
+            function.add_attribute(
+                inkwell::attributes::AttributeLoc::Function,
+                self.context.create_enum_attribute(
+                    inkwell::attributes::Attribute::get_named_enum_kind_id(attribute),
+                    0,
+                ),
+            );
+        }
+        self.declared_functions.insert(name.to_string(), function);
 
         // Store return type for later use in eval_call_expr
-        let ret_otter_type = if let Some(ret_ty) = &func.ret_ty {
-            self.otter_type_from_annotation(ret_ty.as_ref())
-        } else {
-            OtterType::Unit
-        };
         self.function_return_types
-            .insert(func.name.clone(), ret_otter_type);
+            .insert(name.to_string(), ret_otter_type);
 
         // Store default values
-        let defaults: Vec<Option<Expr>> = func
-            .params
-            .iter()
-            .map(|p| p.as_ref().default.as_ref().map(|e| e.as_ref().clone()))
+        let defaults: Vec<Option<Expr>> = std::iter::repeat_n(None, captures)
+            .chain(
+                func.params
+                    .iter()
+                    .map(|p| p.as_ref().default.as_ref().map(|e| self.folded_default(e))),
+            )
             .collect();
-        self.function_defaults.insert(func.name.clone(), defaults);
+        self.function_defaults.insert(name.to_string(), defaults);
 
-        Ok(())
+        Ok(function)
     }
 
     fn compile_function(&mut self, func: &otterc_ast::nodes::Function) -> Result<()> {
-        let function = self
+        let function = *self
             .declared_functions
             .get(&func.name)
             .ok_or_else(|| anyhow!("Function {} not found", func.name))?;
+        self.compile_function_body(func, function, &[])
+    }
 
-        let entry = self.context.append_basic_block(*function, "entry");
+    /// Compiles the body of `func` into `function`, whose leading parameters
+    /// point at the `captures` of an enclosing function
+    fn compile_function_body(
+        &mut self,
+        func: &otterc_ast::nodes::Function,
+        function: FunctionValue<'ctx>,
+        captures: &[(String, OtterType)],
+    ) -> Result<()> {
+        let entry = self.context.append_basic_block(function, "entry");
         self.builder.position_at_end(entry);
+        let enclosing_location =
+            self.enter_debug_function(function, &func.name, Some(*func.body.span()));
 
         let mut ctx = FunctionContext::new();
+        // Nested functions defined in this body go out of scope with it
+        let enclosing_nested = self.nested_functions.clone();
+
+        for (i, (name, ty)) in captures.iter().enumerate() {
+            let ptr = function
+                .get_nth_param(i as u32)
+                .ok_or_else(|| anyhow!("missing captured variable '{name}'"))?
+                .into_pointer_value();
+            ctx.insert(
+                name.clone(),
+                Variable {
+                    ptr,
+                    ty: ty.clone(),
+                },
+            );
+        }
 
         // Bind arguments
         for (i, param) in func.params.iter().enumerate() {
-            let arg_val = function.get_nth_param(i as u32).unwrap();
+            let arg_val = function.get_nth_param((captures.len() + i) as u32).unwrap();
             let param_name = &param.as_ref().name;
 
-            // Determine type from AST or default to I64
-            let (_llvm_type, otter_type) = if let Some(ty) = &param.as_ref().ty {
-                let llvm_ty = self.map_ast_type(ty.as_ref())?;
-                let otter_ty = self.otter_type_from_annotation(ty.as_ref());
-                (llvm_ty, otter_ty)
-            } else {
-                (self.context.i64_type().into(), OtterType::I64)
-            };
+            let otter_type = self.param_type(param.as_ref());
 
             // Allocate stack space for parameter
             let alloca = self.create_entry_block_alloca(
-                *function,
+                function,
                 param_name.as_ref().as_str(),
                 otter_type.clone(),
             )?;
             self.builder.build_store(alloca, arg_val)?;
+            self.declare_debug_variable(
+                param_name.as_ref(),
+                alloca,
+                &otter_type,
+                *param.span(),
+                Some(i as u32 + 1),
+            );
 
             // Add to context
             ctx.insert(
@@ -787,8 +1421,27 @@ impl<'ctx> Compiler<'ctx> {
             );
         }
 
+        if self.profile_functions {
+            let name = self
+                .builder
+                .build_global_string_ptr(&func.name, "profile_name")?
+                .as_pointer_value();
+            let enter_fn = self.get_or_declare_ffi_function("__otter_profile_enter")?;
+            self.builder.build_call(enter_fn, &[name.into()], "")?;
+            ctx.profiled_name = Some(name);
+        }
+
+        // The module's `init:` block runs before anything in `main`
+        if function.get_name().to_bytes() == b"otter_entry"
+            && let Some(init) = self.declared_functions.get(INIT_FUNCTION).copied()
+        {
+            self.builder.build_call(init, &[], "")?;
+        }
+
         // Compile body
-        self.lower_block(func.body.as_ref(), *function, &mut ctx)?;
+        let lowered = self.lower_block(func.body.as_ref(), function, &mut ctx);
+        self.nested_functions = enclosing_nested;
+        lowered?;
 
         // Add implicit return if needed
         if self
@@ -797,14 +1450,25 @@ impl<'ctx> Compiler<'ctx> {
             .and_then(|b| b.get_terminator())
             .is_none()
         {
-            match func.ret_ty {
+            self.emit_function_exit(&mut ctx)?;
+            let ret_type = func.ret_ty.as_ref().map_or(OtterType::Unit, |ty| {
+                self.otter_type_from_annotation(ty.as_ref())
+            });
+            let never = func.ret_ty.as_ref().is_some_and(
+                |ty| matches!(ty.as_ref(), otterc_ast::nodes::Type::Simple(name) if name == "never"),
+            );
+            match self.basic_type(ret_type)? {
+                // The checker makes sure every path of a `never` function
+                // panics or loops forever
+                _ if never => {
+                    self.builder.build_unreachable()?;
+                }
+
                 None => {
                     self.builder.build_return(None)?;
                 }
 
-                Some(ref ret_ty) => {
-                    let llvm_ty = self.map_ast_type(ret_ty.as_ref())?;
-
+                Some(llvm_ty) => {
                     let default_val: inkwell::values::BasicValueEnum = match llvm_ty {
                         BasicTypeEnum::IntType(t) => t.const_zero().into(),
                         BasicTypeEnum::FloatType(t) => t.const_zero().into(),
@@ -824,9 +1488,54 @@ impl<'ctx> Compiler<'ctx> {
             }
         }
 
+        self.leave_debug_function(enclosing_location);
         Ok(())
     }
 
+    /// Lifts `func`, defined inside `enclosing`, into its own LLVM function
+    /// named `enclosing.func`, and makes it callable for the rest of the
+    /// enclosing body
+    pub(crate) fn lower_nested_function(
+        &mut self,
+        func: &otterc_ast::nodes::Function,
+        enclosing: FunctionValue<'ctx>,
+        ctx: &FunctionContext<'ctx>,
+    ) -> Result<()> {
+        let mut names = BTreeSet::new();
+        self.collect_captured_names_in_block(func.body.as_ref(), ctx, &mut names);
+        for param in &func.params {
+            names.remove(param.as_ref().name.as_ref());
+        }
+        let captures: Vec<(String, OtterType)> = names
+            .into_iter()
+            .filter_map(|name| ctx.get(&name).map(|var| (name, var.ty.clone())))
+            .collect();
+
+        let enclosing_name = enclosing.get_name().to_string_lossy();
+        let enclosing_name = if enclosing_name == "otter_entry" {
+            "main"
+        } else {
+            enclosing_name.as_ref()
+        };
+        let name = format!("{enclosing_name}.{}", func.name);
+        let function = self.declare_function(func, &name, captures.len())?;
+        // Registered before the body is compiled so the function can call itself
+        self.nested_functions.insert(
+            func.name.clone(),
+            NestedFunction {
+                name,
+                captures: captures.iter().map(|(name, _)| name.clone()).collect(),
+            },
+        );
+
+        let resume = self.builder.get_insert_block();
+        let compiled = self.compile_function_body(func, function, &captures);
+        if let Some(block) = resume {
+            self.builder.position_at_end(block);
+        }
+        compiled
+    }
+
     /// Creates a new stack allocation instruction in the entry block of the function.
     pub(super) fn create_entry_block_alloca(
         &self,
@@ -851,26 +1560,65 @@ impl<'ctx> Compiler<'ctx> {
         Ok(builder.build_alloca(llvm_type, name)?)
     }
 
+    /// Optimizes the module with the pipeline for `level`, or with `passes`,
+    /// a pipeline as `opt -passes=...` takes it
     pub(super) fn run_default_passes(
         &self,
         level: CodegenOptLevel,
-        _enable_pgo: bool,
-        _pgo_profile_file: Option<&Path>,
+        passes: Option<&str>,
+        pgo: &PgoMode,
         _inline_threshold: Option<u32>,
+        lto: LtoMode,
         target_machine: &TargetMachine,
-    ) {
-        if matches!(level, CodegenOptLevel::None) {
-            return;
+    ) -> Result<()> {
+        // AddressSanitizer only instruments functions that ask for it
+        if self.sanitize_address {
+            let attribute = self.context.create_enum_attribute(
+                inkwell::attributes::Attribute::get_named_enum_kind_id("sanitize_address"),
+                0,
+            );
+            for function in self.module.get_functions() {
+                if function.count_basic_blocks() > 0 {
+                    function.add_attribute(inkwell::attributes::AttributeLoc::Function, attribute);
+                }
+            }
         }
 
-        // Simplified pass running for now
-        let pass_options = PassBuilderOptions::create();
-        pass_options.set_loop_interleaving(true);
-        pass_options.set_loop_vectorization(true);
+        // Under PGO clang optimizes the bitcode when it links, so the
+        // instrumented and the profiled build start from the same IR and the
+        // profile's function hashes match. Its `-fsanitize` adds the
+        // instrumentation.
+        if pgo.is_enabled() {
+            return Ok(());
+        }
 
-        let _ = self
-            .module
-            .run_passes("default<O2>", target_machine, pass_options);
+        let pass_options = PassBuilderOptions::create();
+        let vectorize = matches!(
+            level,
+            CodegenOptLevel::Default | CodegenOptLevel::Aggressive
+        );
+        pass_options.set_loop_interleaving(vectorize);
+        pass_options.set_loop_vectorization(vectorize);
+        pass_options.set_loop_slp_vectorization(vectorize);
+        pass_options.set_loop_unrolling(level != CodegenOptLevel::Size);
+        pass_options.set_merge_functions(level == CodegenOptLevel::Size);
+
+        // A module linked with LTO gets the pre-link pipeline, which leaves
+        // the late inlining and cleanup to the link. Even `O0` runs, to
+        // inline `@inline` functions.
+        let mut pipeline = match (passes, lto) {
+            (Some(passes), _) => passes.to_string(),
+            (None, LtoMode::Off) => format!("default<{}>", level.pipeline_level()),
+            (None, LtoMode::Thin) => format!("thinlto-pre-link<{}>", level.pipeline_level()),
+            (None, LtoMode::Full) => format!("lto-pre-link<{}>", level.pipeline_level()),
+        };
+        // Instrumenting after optimization checks the accesses that remain
+        if self.sanitize_address {
+            pipeline.push_str(",asan");
+        }
+        self.module
+            .run_passes(&pipeline, target_machine, pass_options)
+            .map_err(|err| anyhow!("invalid pass pipeline '{pipeline}': {err}"))
     }
 
     /// Build a heap allocation using the GC
@@ -915,3 +1663,81 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 }
+
+/// Replaces the generic parameters named in `substitutions` within `ty`
+fn substitute_type_params(ty: &Node<Type>, substitutions: &HashMap<String, Type>) -> Node<Type> {
+    let substituted = match ty.as_ref() {
+        Type::Simple(name) => substitutions
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| Type::Simple(name.clone())),
+        Type::Generic { base, args } => Type::Generic {
+            base: base.clone(),
+            args: args
+                .iter()
+                .map(|arg| substitute_type_params(arg, substitutions))
+                .collect(),
+        },
+        Type::Union(members) => Type::Union(
+            members
+                .iter()
+                .map(|member| substitute_type_params(member, substitutions))
+                .collect(),
+        ),
+    };
+    Node::new(substituted, *ty.span())
+}
+
+/// Name of the function holding a module's `init:` block
+const INIT_FUNCTION: &str = "__otter_init";
+
+/// The `init:` block `body` as a function taking and returning nothing
+fn init_function(body: &Node<Block>) -> Function {
+    Function::new(INIT_FUNCTION, Vec::new(), None, body.clone())
+}
+
+/// Fields, as `(struct, field)` pairs, through which a struct contains
+/// itself. Stored by value they would give the struct an infinite size, so
+/// they are laid out as pointers to a heap copy instead.
+fn recursive_struct_fields(statements: &[Node<Statement>]) -> HashSet<(String, String)> {
+    let mut struct_fields: HashMap<&str, Vec<(&str, &str)>> = HashMap::new();
+    for statement in statements {
+        if let Statement::Struct { name, fields, .. } = statement.as_ref() {
+            let by_value = fields
+                .iter()
+                .filter_map(|(field, ty)| match ty.as_ref() {
+                    Type::Simple(target) => Some((field.as_str(), target.as_str())),
+                    _ => None,
+                })
+                .collect();
+            struct_fields.insert(name.as_str(), by_value);
+        }
+    }
+
+    let reaches = |from: &str, to: &str| {
+        let mut visited = HashSet::new();
+        let mut pending = vec![from];
+        while let Some(current) = pending.pop() {
+            if current == to {
+                return true;
+            }
+            if !visited.insert(current) {
+                continue;
+            }
+            if let Some(fields) = struct_fields.get(current) {
+                pending.extend(fields.iter().map(|(_, target)| *target));
+            }
+        }
+        false
+    };
+
+    let mut boxed = HashSet::new();
+    for (name, fields) in &struct_fields {
+        for (field, target) in fields {
+            if struct_fields.contains_key(target) && reaches(target, name) {
+                boxed.insert((name.to_string(), field.to_string()));
+            }
+        }
+    }
+    boxed
+}
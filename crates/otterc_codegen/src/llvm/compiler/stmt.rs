@@ -1,9 +1,11 @@
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use inkwell::values::{BasicValueEnum, FunctionValue};
 
 use crate::llvm::compiler::Compiler;
-use crate::llvm::compiler::types::{EvaluatedValue, FunctionContext, OtterType, Variable};
-use otterc_ast::nodes::{Block, Expr, Statement};
+use crate::llvm::compiler::types::{
+    CleanupAction, EvaluatedValue, FunctionContext, LoopContext, OtterType, Variable,
+};
+use otterc_ast::nodes::{Block, Expr, Node, Statement, with_exit_method};
 use otterc_typecheck::TypeInfo;
 
 struct IteratorRuntime<'ctx> {
@@ -14,6 +16,25 @@ struct IteratorRuntime<'ctx> {
     element_type: OtterType,
 }
 
+/// Resolves the loop a `break`/`continue` jumps to: the innermost loop, or
+/// the enclosing loop carrying `label`
+fn target_loop<'ctx>(
+    ctx: &FunctionContext<'ctx>,
+    label: Option<&Node<String>>,
+    keyword: &str,
+) -> Result<LoopContext<'ctx>> {
+    match label {
+        Some(label) => ctx
+            .labeled_loop(label.as_ref())
+            .cloned()
+            .ok_or_else(|| anyhow!("{keyword} to undeclared loop label '{}'", label.as_ref())),
+        None => ctx
+            .current_loop()
+            .cloned()
+            .ok_or_else(|| anyhow!("{keyword} statement outside of loop")),
+    }
+}
+
 impl<'ctx> Compiler<'ctx> {
     pub(crate) fn lower_block(
         &mut self,
@@ -22,11 +43,24 @@ impl<'ctx> Compiler<'ctx> {
         ctx: &mut FunctionContext<'ctx>,
     ) -> Result<()> {
         for stmt in &block.statements {
+            // Nothing after a `return`, `break` or `continue` can run, and
+            // emitting it would add instructions after the block's terminator
+            if self.block_is_terminated() {
+                break;
+            }
+            self.set_debug_location(*stmt.span());
             self.lower_statement(stmt.as_ref(), function, ctx)?;
         }
         Ok(())
     }
 
+    pub(crate) fn block_is_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .and_then(|b| b.get_terminator())
+            .is_some()
+    }
+
     pub(crate) fn lower_statement(
         &mut self,
         stmt: &Statement,
@@ -39,13 +73,14 @@ impl<'ctx> Compiler<'ctx> {
                 Ok(())
             }
             Statement::Return(expr) => {
-                if let Some(expr) = expr {
-                    let val = self.eval_expr(expr.as_ref(), ctx)?;
-                    if let Some(v) = val.value {
-                        self.builder.build_return(Some(&v))?;
-                    } else {
-                        self.builder.build_return(None)?;
-                    }
+                // The return value is computed before any cleanup runs
+                let value = match expr {
+                    Some(expr) => self.eval_expr(expr.as_ref(), ctx)?.value,
+                    None => None,
+                };
+                self.emit_function_exit(ctx)?;
+                if let Some(v) = value {
+                    self.builder.build_return(Some(&v))?;
                 } else {
                     self.builder.build_return(None)?;
                 }
@@ -82,6 +117,7 @@ impl<'ctx> Compiler<'ctx> {
                         .unwrap();
                     let alloca =
                         self.create_entry_block_alloca(function, name.as_ref(), var_ty.clone())?;
+                    self.declare_debug_variable(name.as_ref(), alloca, &var_ty, *name.span(), None);
 
                     if let Some(v) = val_value {
                         // Coerce value to variable type if needed
@@ -136,41 +172,84 @@ impl<'ctx> Compiler<'ctx> {
                 elif_blocks,
                 else_block.as_ref().map(|b| b.as_ref()),
             ),
-            Statement::While { cond, body } => {
-                self.lower_while_loop(function, ctx, cond.as_ref(), body.as_ref())
+            Statement::While { cond, body, label } => {
+                let hoisted = self.hoist_loop_invariants("while", Some(cond), body.as_ref(), ctx)?;
+                self.lower_while_loop(
+                    function,
+                    ctx,
+                    cond.as_ref(),
+                    body.as_ref(),
+                    label.as_ref().map(|label| label.as_ref().clone()),
+                )?;
+                self.release_hoisted(hoisted);
+                Ok(())
             }
-            Statement::Break => {
-                if let Some(loop_ctx) = ctx.current_loop() {
-                    self.builder.build_unconditional_branch(loop_ctx.exit_bb)?;
-                } else {
-                    bail!("break statement outside of loop");
-                }
+            Statement::Break(label) => {
+                let loop_ctx = target_loop(ctx, label.as_ref(), "break")?;
+                self.emit_cleanups(ctx, loop_ctx.cleanup_depth)?;
+                self.builder.build_unconditional_branch(loop_ctx.exit_bb)?;
                 Ok(())
             }
-            Statement::Continue => {
-                if let Some(loop_ctx) = ctx.current_loop() {
-                    self.builder.build_unconditional_branch(loop_ctx.cond_bb)?;
-                } else {
-                    bail!("continue statement outside of loop");
-                }
+            Statement::Continue(label) => {
+                let loop_ctx = target_loop(ctx, label.as_ref(), "continue")?;
+                self.emit_cleanups(ctx, loop_ctx.cleanup_depth)?;
+                self.builder.build_unconditional_branch(loop_ctx.cond_bb)?;
                 Ok(())
             }
+            Statement::With { expr, alias, body } => self.lower_with_statement(
+                function,
+                ctx,
+                expr.as_ref(),
+                alias.as_ref().map(|alias| alias.as_ref().as_str()),
+                body.as_ref(),
+            ),
+            Statement::Defer(expr) => self.lower_defer_statement(function, ctx, expr.as_ref()),
+            Statement::Assert { cond, message } => {
+                self.lower_assert_statement(function, ctx, cond, message.as_ref())
+            }
+            Statement::Function(func) => {
+                self.lower_nested_function(func.as_ref(), function, ctx)
+            }
             Statement::Pass
             | Statement::Struct { .. }
             // Handled at module level
+            | Statement::Const { .. }
             | Statement::Enum { .. }
             | Statement::TypeAlias { .. }
-            | Statement::Function(_)
+            | Statement::Protocol { .. }
+            | Statement::Init(_)
             | Statement::Use { .. }
             | Statement::PubUse { .. } => Ok(()),
             Statement::For {
                 var,
                 iterable,
                 body,
-            } => self.lower_for_loop(
+                label,
+                is_await: false,
+            } => {
+                let hoisted = self.hoist_loop_invariants("for", None, body.as_ref(), ctx)?;
+                self.lower_for_loop(
+                    var.as_ref(),
+                    iterable.as_ref(),
+                    body.as_ref(),
+                    label.as_ref().map(|label| label.as_ref().clone()),
+                    function,
+                    ctx,
+                )?;
+                self.release_hoisted(hoisted);
+                Ok(())
+            }
+            Statement::For {
+                var,
+                iterable,
+                body,
+                label,
+                is_await: true,
+            } => self.lower_for_await_loop(
                 var.as_ref(),
                 iterable.as_ref(),
                 body.as_ref(),
+                label.as_ref().map(|label| label.as_ref().clone()),
                 function,
                 ctx,
             ),
@@ -242,12 +321,271 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
+    fn lower_with_statement(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+        expr: &Expr,
+        alias: Option<&str>,
+        body: &Block,
+    ) -> Result<()> {
+        let resource = self.eval_expr(expr, ctx)?;
+        let OtterType::Struct(struct_id) = resource.ty else {
+            bail!("`with` resource must be a struct, got {:?}", resource.ty);
+        };
+        let value = resource
+            .value
+            .ok_or_else(|| anyhow::anyhow!("`with` resource has no value"))?;
+
+        // The same method the type checker accepted
+        let method_fn = |method: &str| {
+            self.resolve_struct_method_name(struct_id, method)
+                .and_then(|name| self.declared_functions.get(&name).copied())
+        };
+        let exit_fn = with_exit_method(|method| {
+            method_fn(method).map(|function| function.count_params() as usize)
+        })
+        .and_then(method_fn)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "struct '{}' has no __exit__ or close method for `with`",
+                self.struct_info(struct_id).name
+            )
+        })?;
+
+        let ptr = self.create_entry_block_alloca(
+            function,
+            alias.unwrap_or("with_resource"),
+            resource.ty.clone(),
+        )?;
+        self.builder.build_store(ptr, value)?;
+        let resource = Variable {
+            ptr,
+            ty: resource.ty,
+        };
+        // The alias shadows a variable of the same name until the block ends
+        let previous = alias.and_then(|alias| {
+            let previous = ctx.remove(alias);
+            ctx.insert(alias.to_string(), resource.clone());
+            previous
+        });
+
+        ctx.cleanup_stack.push(CleanupAction::CallMethod {
+            function: exit_fn,
+            resource,
+        });
+        self.lower_block(body, function, ctx)?;
+        let cleanup = ctx
+            .cleanup_stack
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("cleanup stack underflow after `with` block"))?;
+        if let Some(alias) = alias {
+            ctx.remove(alias);
+            if let Some(previous) = previous {
+                ctx.insert(alias.to_string(), previous);
+            }
+        }
+
+        // Early exits already ran the cleanup on their own path
+        if self
+            .builder
+            .get_insert_block()
+            .and_then(|b| b.get_terminator())
+            .is_none()
+        {
+            self.emit_cleanup(ctx, &cleanup)?;
+        }
+        Ok(())
+    }
+
+    /// Registers `expr` to run when the function exits. The expression is only
+    /// evaluated on exit paths that passed through this statement, which an
+    /// `armed` flag tracks at runtime.
+    fn lower_defer_statement(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+        expr: &Expr,
+    ) -> Result<()> {
+        // The flag lives in the entry block so every exit path sees it cleared
+        // until this statement runs
+        let entry_builder = self.context.create_builder();
+        let entry_block = function
+            .get_first_basic_block()
+            .ok_or_else(|| anyhow::anyhow!("function has no entry block"))?;
+        match entry_block.get_first_instruction() {
+            Some(first_instr) => entry_builder.position_before(&first_instr),
+            None => entry_builder.position_at_end(entry_block),
+        }
+        let bool_type = self.context.bool_type();
+        let armed = entry_builder.build_alloca(bool_type, "defer_armed")?;
+        entry_builder.build_store(armed, bool_type.const_zero())?;
+
+        self.builder
+            .build_store(armed, bool_type.const_all_ones())?;
+        ctx.deferred.push(CleanupAction::Deferred {
+            expr: expr.clone(),
+            armed,
+        });
+        Ok(())
+    }
+
+    /// Branches to a call to the runtime's assertion failure handler when `cond`
+    /// is false. Nothing is emitted (and `cond` is not evaluated) when asserts
+    /// are stripped.
+    fn lower_assert_statement(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+        cond: &Node<Expr>,
+        message: Option<&Node<Expr>>,
+    ) -> Result<()> {
+        if self.strip_asserts {
+            return Ok(());
+        }
+
+        let cond_val = self.eval_expr(cond.as_ref(), ctx)?;
+        let cond_int = cond_val
+            .value
+            .ok_or_else(|| anyhow::anyhow!("assert condition has no value"))?
+            .into_int_value();
+
+        let fail_bb = self.context.append_basic_block(function, "assert_fail");
+        let cont_bb = self.context.append_basic_block(function, "assert_cont");
+        self.builder
+            .build_conditional_branch(cond_int, cont_bb, fail_bb)?;
+
+        self.builder.position_at_end(fail_bb);
+        let message_ptr = match message {
+            Some(message) => {
+                let value = self.eval_expr(message.as_ref(), ctx)?;
+                self.ensure_string_value(value)?
+            }
+            None => self.string_ptr_type.const_null().into(),
+        };
+        let location = self.source_location(*cond.span());
+        let location_ptr = self
+            .builder
+            .build_global_string_ptr(&location, "assert_loc")?
+            .as_pointer_value();
+        let fail_fn = self.get_or_declare_ffi_function("__otter_assert_fail")?;
+        self.builder.build_call(
+            fail_fn,
+            &[message_ptr.into(), location_ptr.into()],
+            "assert_fail",
+        )?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(cont_bb);
+        Ok(())
+    }
+
+    /// Emits the code that runs when control leaves the function: every open
+    /// scope cleanup, then the `defer`red expressions in reverse order, then
+    /// the profiler's exit hook.
+    pub(crate) fn emit_function_exit(&mut self, ctx: &mut FunctionContext<'ctx>) -> Result<()> {
+        self.emit_cleanups(ctx, 0)?;
+        let deferred: Vec<_> = ctx.deferred.iter().rev().cloned().collect();
+        for cleanup in deferred {
+            self.emit_cleanup(ctx, &cleanup)?;
+        }
+        if let Some(name) = ctx.profiled_name {
+            let exit_fn = self.get_or_declare_ffi_function("__otter_profile_exit")?;
+            self.builder.build_call(exit_fn, &[name.into()], "")?;
+        }
+        Ok(())
+    }
+
+    /// Emits every cleanup registered above `depth`, innermost first, without
+    /// unregistering them (other exit paths still need them).
+    pub(crate) fn emit_cleanups(
+        &mut self,
+        ctx: &mut FunctionContext<'ctx>,
+        depth: usize,
+    ) -> Result<()> {
+        for cleanup in ctx.cleanups_above(depth) {
+            self.emit_cleanup(ctx, &cleanup)?;
+        }
+        Ok(())
+    }
+
+    fn emit_cleanup(
+        &mut self,
+        ctx: &mut FunctionContext<'ctx>,
+        cleanup: &CleanupAction<'ctx>,
+    ) -> Result<()> {
+        match cleanup {
+            CleanupAction::CallMethod { function, resource } => {
+                let basic_ty = self
+                    .basic_type(resource.ty.clone())?
+                    .ok_or_else(|| anyhow::anyhow!("`with` resource has no runtime value"))?;
+                let value = self
+                    .builder
+                    .build_load(basic_ty, resource.ptr, "with_resource")?;
+                let param_type = function
+                    .get_nth_param(0)
+                    .map(|param| param.get_type())
+                    .ok_or_else(|| anyhow::anyhow!("`with` exit method is missing self"))?;
+                let self_arg =
+                    self.cast_argument_for_call(value, resource.ty.clone(), &param_type)?;
+                self.builder
+                    .build_call(*function, &[self_arg.into()], "with_exit")?;
+            }
+            CleanupAction::FreeIterator { function, iterator } => {
+                self.builder
+                    .build_call(*function, &[(*iterator).into()], "")?;
+            }
+            CleanupAction::Deferred { expr, armed } => {
+                let function = self
+                    .builder
+                    .get_insert_block()
+                    .and_then(|b| b.get_parent())
+                    .ok_or_else(|| anyhow::anyhow!("`defer` emitted outside of a function"))?;
+                let run_bb = self.context.append_basic_block(function, "defer_run");
+                let done_bb = self.context.append_basic_block(function, "defer_done");
+
+                let is_armed = self
+                    .builder
+                    .build_load(self.context.bool_type(), *armed, "defer_armed")?
+                    .into_int_value();
+                self.builder
+                    .build_conditional_branch(is_armed, run_bb, done_bb)?;
+
+                self.builder.position_at_end(run_bb);
+                self.eval_expr(expr, ctx)?;
+                if self
+                    .builder
+                    .get_insert_block()
+                    .and_then(|b| b.get_terminator())
+                    .is_none()
+                {
+                    self.builder.build_unconditional_branch(done_bb)?;
+                }
+                self.builder.position_at_end(done_bb);
+            }
+        }
+        Ok(())
+    }
+
+    /// With `--preempt-loops`, lets the scheduler run queued tasks elsewhere
+    /// when this loop has held its worker for a whole time slice. Emitted at
+    /// the top of loop condition blocks, which every back-edge jumps to.
+    fn emit_preempt_point(&mut self) -> Result<()> {
+        if !self.preempt_loops {
+            return Ok(());
+        }
+        let preempt_fn = self.get_or_declare_ffi_function("__otter_preempt_point")?;
+        self.builder.build_call(preempt_fn, &[], "")?;
+        Ok(())
+    }
+
     fn lower_while_loop(
         &mut self,
         function: FunctionValue<'ctx>,
         ctx: &mut FunctionContext<'ctx>,
         cond: &Expr,
         body: &Block,
+        label: Option<String>,
     ) -> Result<()> {
         let cond_bb = self.context.append_basic_block(function, "while_cond");
         let body_bb = self.context.append_basic_block(function, "while_body");
@@ -257,6 +595,7 @@ impl<'ctx> Compiler<'ctx> {
 
         // Condition
         self.builder.position_at_end(cond_bb);
+        self.emit_preempt_point()?;
         let cond_val = self.eval_expr(cond, ctx)?;
         let cond_bool = self.to_bool_value(cond_val)?;
         self.builder
@@ -264,7 +603,7 @@ impl<'ctx> Compiler<'ctx> {
 
         // Body
         self.builder.position_at_end(body_bb);
-        ctx.push_loop(cond_bb, exit_bb);
+        ctx.push_loop(cond_bb, exit_bb, label);
         self.lower_block(body, function, ctx)?;
         ctx.pop_loop();
 
@@ -286,6 +625,7 @@ impl<'ctx> Compiler<'ctx> {
         var: &str,
         iterable: &Expr,
         body: &Block,
+        label: Option<String>,
         function: FunctionValue<'ctx>,
         ctx: &mut FunctionContext<'ctx>,
     ) -> Result<()> {
@@ -334,6 +674,7 @@ impl<'ctx> Compiler<'ctx> {
                 var,
                 EvaluatedValue::with_value(list_val, OtterType::list_of(start_ty.clone())),
                 body,
+                label,
                 function,
                 ctx,
                 IteratorRuntime {
@@ -365,6 +706,7 @@ impl<'ctx> Compiler<'ctx> {
                         var,
                         iterable_val,
                         body,
+                        label,
                         function,
                         ctx,
                         IteratorRuntime {
@@ -395,6 +737,7 @@ impl<'ctx> Compiler<'ctx> {
                         var,
                         iterable_val,
                         body,
+                        label,
                         function,
                         ctx,
                         IteratorRuntime {
@@ -418,11 +761,43 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 
+    /// `for await item in stream:` receives from a task channel until it is
+    /// closed and drained. Streams from `task.stream_*` are closed when their
+    /// producer returns.
+    fn lower_for_await_loop(
+        &mut self,
+        var: &str,
+        iterable: &Expr,
+        body: &Block,
+        label: Option<String>,
+        function: FunctionValue<'ctx>,
+        ctx: &mut FunctionContext<'ctx>,
+    ) -> Result<()> {
+        let element_type = self
+            .expr_type(iterable)
+            .and_then(|ty| ty.stream_element())
+            .and_then(|element| self.typeinfo_to_otter_type(&element))
+            .ok_or_else(|| {
+                anyhow!("`for await` needs a channel or stream with a known element type")
+            })?;
+        let stream = self.eval_expr(iterable, ctx)?;
+
+        let iter_runtime = IteratorRuntime {
+            create_fn: self.get_or_declare_ffi_function("__otter_stream_iter")?,
+            has_next_fn: self.get_or_declare_ffi_function("__otter_stream_has_next")?,
+            next_fn: self.get_or_declare_ffi_function("__otter_stream_next")?,
+            free_fn: self.get_or_declare_ffi_function("__otter_stream_free")?,
+            element_type,
+        };
+        self.lower_collection_for_loop(var, stream, body, label, function, ctx, iter_runtime)
+    }
+
     fn lower_collection_for_loop(
         &mut self,
         var: &str,
         iterable_val: crate::llvm::compiler::types::EvaluatedValue<'ctx>,
         body: &Block,
+        label: Option<String>,
         function: FunctionValue<'ctx>,
         ctx: &mut FunctionContext<'ctx>,
         iter_runtime: IteratorRuntime<'ctx>,
@@ -473,6 +848,7 @@ impl<'ctx> Compiler<'ctx> {
 
         // Condition check block
         self.builder.position_at_end(loop_cond_bb);
+        self.emit_preempt_point()?;
 
         // Check if iterator has next element
         let has_next_call = self
@@ -508,10 +884,20 @@ impl<'ctx> Compiler<'ctx> {
             self.builder.build_store(var_alloca, value)?;
         }
 
-        // Execute loop body
-        ctx.push_loop(loop_cond_bb, cleanup_bb);
+        // Execute loop body. Exits that skip the cleanup block, a `return` or
+        // a labeled `break` or `continue` to an outer loop, free the iterator
+        // on their own path.
+        ctx.cleanup_stack.push(CleanupAction::FreeIterator {
+            function: free_fn,
+            iterator: iter_val,
+        });
+        ctx.push_loop(loop_cond_bb, cleanup_bb, label);
         self.lower_block(body, function, ctx)?;
         ctx.pop_loop();
+        let cleanup = ctx
+            .cleanup_stack
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("cleanup stack underflow after `for` loop"))?;
 
         // Jump back to condition check
         if self.builder.get_insert_block().is_some()
@@ -527,7 +913,7 @@ impl<'ctx> Compiler<'ctx> {
 
         // Cleanup block - free iterator
         self.builder.position_at_end(cleanup_bb);
-        self.builder.build_call(free_fn, &[iter_val.into()], "")?;
+        self.emit_cleanup(ctx, &cleanup)?;
         self.builder.build_unconditional_branch(exit_bb)?;
 
         self.builder.position_at_end(exit_bb);
@@ -561,12 +947,19 @@ impl<'ctx> Compiler<'ctx> {
 
     pub(crate) fn typeinfo_to_otter_type(&self, ty: &TypeInfo) -> Option<OtterType> {
         match ty {
-            TypeInfo::Unit => Some(OtterType::Unit),
+            TypeInfo::Unit | TypeInfo::Never => Some(OtterType::Unit),
             TypeInfo::Bool => Some(OtterType::Bool),
+            TypeInfo::I8 => Some(OtterType::I8),
+            TypeInfo::I16 => Some(OtterType::I16),
             TypeInfo::I32 => Some(OtterType::I32),
             TypeInfo::I64 => Some(OtterType::I64),
+            TypeInfo::U8 => Some(OtterType::U8),
+            TypeInfo::U16 => Some(OtterType::U16),
+            TypeInfo::U32 => Some(OtterType::U32),
+            TypeInfo::U64 => Some(OtterType::U64),
             TypeInfo::F64 => Some(OtterType::F64),
-            TypeInfo::Str => Some(OtterType::Str),
+            // Symbols are interned strings
+            TypeInfo::Str | TypeInfo::Symbol => Some(OtterType::Str),
             TypeInfo::List(inner) => {
                 let element = self
                     .typeinfo_to_otter_type(inner)
@@ -574,8 +967,12 @@ impl<'ctx> Compiler<'ctx> {
                 Some(OtterType::list_of(element))
             }
             TypeInfo::Dict { .. } => Some(OtterType::Map),
-            TypeInfo::Struct { name, .. } => self.struct_id(name).map(OtterType::Struct),
+            TypeInfo::Struct { name, args, .. } => {
+                self.struct_id_for(name, args).map(OtterType::Struct)
+            }
             TypeInfo::Alias { underlying, .. } => self.typeinfo_to_otter_type(underlying),
+            // Errors and versions are runtime handles
+            ty if ty.is_error_value() || ty.is_semver_version() => Some(OtterType::Opaque),
             TypeInfo::Generic { base, args } => {
                 // Handle generic types
                 if args.is_empty() {
@@ -601,6 +998,8 @@ impl<'ctx> Compiler<'ctx> {
                             Some(OtterType::list_of(element))
                         }
                         "Dict" | "dict" => Some(OtterType::Map),
+                        // Channels and frozen collections are runtime handles
+                        "Channel" | "FrozenList" | "FrozenDict" => Some(OtterType::Opaque),
                         _ => {
                             #[expect(clippy::print_stderr, reason = "TODO: Use robust logging")]
                             {
@@ -656,7 +1055,7 @@ impl<'ctx> Compiler<'ctx> {
                 result.try_as_basic_value().left().unwrap()
             }
 
-            OtterType::I64 => {
+            OtterType::I64 | OtterType::U64 => {
                 // Decode as I64, but runtime might have stored as F64
                 // Try I64 first, but we may need to handle F64->I64 conversion
                 let decode_i64_fn =
@@ -815,8 +1214,15 @@ impl<'ctx> Compiler<'ctx> {
                 result.try_as_basic_value().left().unwrap()
             }
 
-            OtterType::I32 => {
+            OtterType::I8
+            | OtterType::I16
+            | OtterType::I32
+            | OtterType::U8
+            | OtterType::U16
+            | OtterType::U32 => {
                 // Decode as I64 then truncate
+                let (bits, signed) = expected_type.int_layout().unwrap_or((32, true));
+                let narrow_type = self.context.custom_width_int_type(bits);
                 let decode_i64_fn =
                     self.get_or_declare_ffi_function("__otter_decode_value_as_i64")?;
                 let decode_f64_fn =
@@ -846,16 +1252,16 @@ impl<'ctx> Compiler<'ctx> {
                     .unwrap();
                 let decode_i64_bb = self
                     .context
-                    .append_basic_block(function, "decode_i64_for_i32");
+                    .append_basic_block(function, "decode_i64_for_int");
                 let decode_f64_bb = self
                     .context
-                    .append_basic_block(function, "decode_f64_for_i32");
+                    .append_basic_block(function, "decode_f64_for_int");
                 let decode_other_bb = self
                     .context
-                    .append_basic_block(function, "decode_other_for_i32");
+                    .append_basic_block(function, "decode_other_for_int");
                 let merge_bb = self
                     .context
-                    .append_basic_block(function, "decode_i32_merge");
+                    .append_basic_block(function, "decode_int_merge");
 
                 // Branch based on type
                 self.builder
@@ -874,14 +1280,12 @@ impl<'ctx> Compiler<'ctx> {
                     .left()
                     .unwrap()
                     .into_int_value();
-                let truncated = self.builder.build_int_truncate(
-                    i64_val,
-                    self.context.i32_type(),
-                    "truncated_i32",
-                )?;
+                let truncated =
+                    self.builder
+                        .build_int_truncate(i64_val, narrow_type, "truncated_int")?;
                 self.builder.build_unconditional_branch(merge_bb)?;
 
-                // Decode as F64 then convert to I32
+                // Decode as F64 then convert to the narrow type
                 self.builder.position_at_end(decode_f64_bb);
                 let f64_result =
                     self.builder
@@ -891,19 +1295,19 @@ impl<'ctx> Compiler<'ctx> {
                     .left()
                     .unwrap()
                     .into_float_value();
-                let converted_i32 = self.builder.build_float_to_signed_int(
-                    f64_val,
-                    self.context.i32_type(),
-                    "f64_to_i32",
-                )?;
+                let converted = if signed {
+                    self.builder
+                        .build_float_to_signed_int(f64_val, narrow_type, "f64_to_int")?
+                } else {
+                    self.builder
+                        .build_float_to_unsigned_int(f64_val, narrow_type, "f64_to_uint")?
+                };
                 self.builder.build_unconditional_branch(merge_bb)?;
 
                 // Merge
                 self.builder.position_at_end(merge_bb);
-                let phi = self
-                    .builder
-                    .build_phi(self.context.i32_type(), "decoded_i32_phi")?;
-                phi.add_incoming(&[(&truncated, decode_i64_bb), (&converted_i32, decode_f64_bb)]);
+                let phi = self.builder.build_phi(narrow_type, "decoded_int_phi")?;
+                phi.add_incoming(&[(&truncated, decode_i64_bb), (&converted, decode_f64_bb)]);
                 phi.as_basic_value()
             }
 
@@ -930,15 +1334,26 @@ impl<'ctx> Compiler<'ctx> {
         let value = match element_type {
             OtterType::Unit => return Ok(None),
             OtterType::I64
+            | OtterType::U64
             | OtterType::Opaque
             | OtterType::List(_)
             | OtterType::Map
             | OtterType::Struct(_)
             | OtterType::Tuple(_) => raw_value,
-            OtterType::I32 => {
+            OtterType::I8
+            | OtterType::I16
+            | OtterType::I32
+            | OtterType::U8
+            | OtterType::U16
+            | OtterType::U32 => {
+                let (bits, _) = element_type.int_layout().unwrap_or((32, true));
                 let int_val = raw_value.into_int_value();
                 self.builder
-                    .build_int_truncate(int_val, self.context.i32_type(), "iter_i32")?
+                    .build_int_truncate(
+                        int_val,
+                        self.context.custom_width_int_type(bits),
+                        "iter_int",
+                    )?
                     .into()
             }
             OtterType::F64 => {
@@ -0,0 +1,352 @@
+//! Lowers small programs through the checker and compiler and inspects the
+//! LLVM IR they produce
+
+#![expect(clippy::panic, reason = "Panicking on test failures is acceptable")]
+
+use inkwell::context::Context;
+use otterc_typecheck::TypeChecker;
+// Links the runtime, whose symbols the compiler declares calls against
+use otterc_runtime as _;
+
+use super::Compiler;
+
+/// The verified IR of `source`
+fn lower_to_ir(source: &str) -> String {
+    lower(source).0
+}
+
+/// The verified IR of `source` and the optimization report of its lowering
+fn lower(source: &str) -> (String, Vec<String>) {
+    let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+    let mut program = otterc_parser::parse(&tokens).expect("parse");
+    let registry = otterc_ffi::bootstrap_stdlib();
+    let mut checker = TypeChecker::new().with_registry(registry);
+    checker.infer_parameter_types(&mut program);
+    if checker.check_program(&program).is_err() {
+        panic!("type errors: {:?}", checker.errors());
+    }
+    let enum_layouts = checker.enum_layouts();
+    let (expr_types, expr_types_by_span, comprehension_var_types) = checker.into_type_maps();
+
+    let context = Context::create();
+    let mut compiler = Compiler::new(
+        &context,
+        context.create_module("test"),
+        context.create_builder(),
+        registry,
+        expr_types,
+        expr_types_by_span,
+        comprehension_var_types,
+        enum_layouts,
+        None,
+    );
+    compiler.lower_program(&program, true).expect("lower");
+    compiler.module.verify().expect("verify");
+    (
+        compiler.module.print_to_string().to_string(),
+        compiler.opt_report,
+    )
+}
+
+/// The body of the function `name` in `ir`
+fn function_ir<'a>(ir: &'a str, name: &str) -> &'a str {
+    let start = ir
+        .lines()
+        .position(|line| line.starts_with("define ") && line.contains(&format!("@{name}(")))
+        .unwrap_or_else(|| panic!("no function {name} in:\n{ir}"));
+    let offset: usize = ir.lines().take(start).map(|line| line.len() + 1).sum();
+    let body = &ir[offset..];
+    &body[..body.find("\n}\n").map_or(body.len(), |end| end + 3)]
+}
+
+/// The LLVM types of a function's parameters, from the first line of its IR
+fn param_types(function: &str) -> Vec<&str> {
+    let signature = function.lines().next().unwrap_or_default();
+    let params = signature
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map_or("", |(params, _)| params);
+    params
+        .split(", ")
+        .filter_map(|param| param.split_whitespace().next())
+        .collect()
+}
+
+/// The blocks of a function's IR, as their label and instructions
+fn blocks(function: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut blocks: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in function.lines().skip(1) {
+        if let Some((label, _)) = line.split_once(':')
+            && !line.starts_with(' ')
+        {
+            blocks.push((label, Vec::new()));
+        } else if let Some((_, instructions)) = blocks.last_mut() {
+            instructions.push(line.trim());
+        }
+    }
+    blocks
+}
+
+/// The blocks of a function's IR that end in a `ret`
+fn returning_blocks(function: &str) -> Vec<(&str, Vec<&str>)> {
+    blocks(function)
+        .into_iter()
+        .filter(|(_, instructions)| instructions.iter().any(|line| line.starts_with("ret ")))
+        .collect()
+}
+
+#[test]
+fn deferred_expressions_run_on_every_return() {
+    let ir = lower_to_ir(
+        "fn note(code: int) -> int:\n    return code\n\
+         fn work(early: bool) -> int:\n    \
+         defer note(1)\n    \
+         if early:\n        return 1\n    \
+         return 2\n\
+         fn main():\n    work(true)\n",
+    );
+    let work = function_ir(&ir, "work");
+
+    // Each return, the early one included, leaves through the deferred call
+    let blocks = returning_blocks(work);
+    assert_eq!(blocks.len(), 2, "{work}");
+    assert!(
+        blocks
+            .iter()
+            .all(|(label, _)| label.starts_with("defer_done")),
+        "{work}"
+    );
+    assert_eq!(work.matches("@note(").count(), 2, "{work}");
+}
+
+#[test]
+fn with_resources_are_closed_on_every_return() {
+    let ir = lower_to_ir(
+        "struct Log:\n    \
+         path: str\n    \
+         fn close(self):\n        pass\n\
+         fn record(early: bool) -> int:\n    \
+         with Log(path=\"app.log\") as log:\n        \
+         if early:\n            return 1\n        \
+         let path: str = log.path\n    \
+         return 0\n\
+         fn main():\n    record(true)\n",
+    );
+    let record = function_ir(&ir, "record");
+
+    // The early return closes the log on its own path, the other one when
+    // control leaves the block
+    let returns = returning_blocks(record);
+    assert_eq!(returns.len(), 2, "{record}");
+    for (_, instructions) in returns {
+        let closes = instructions
+            .iter()
+            .filter(|line| line.contains("call ") && line.contains("close("))
+            .count();
+        assert_eq!(closes, 1, "{record}");
+    }
+}
+
+#[test]
+fn with_releases_through_the_method_the_checker_accepted() {
+    let ir = lower_to_ir(
+        "struct Log:\n    \
+         path: str\n    \
+         fn __exit__(self, code: int):\n        pass\n    \
+         fn close(self):\n        pass\n\
+         fn record() -> int:\n    \
+         let log = 7\n    \
+         with Log(path=\"app.log\") as log:\n        \
+         let path: str = log.path\n    \
+         return log\n\
+         fn main():\n    record()\n",
+    );
+    let record = function_ir(&ir, "record");
+
+    // `__exit__` takes more than `self`, so `close` releases the log
+    let calls: Vec<&str> = record
+        .lines()
+        .filter(|line| line.contains("call "))
+        .collect();
+    assert!(calls.iter().any(|line| line.contains("close(")), "{record}");
+    assert!(
+        !calls.iter().any(|line| line.contains("__exit__(")),
+        "{record}"
+    );
+    // The outer `log` is visible again after the block
+    assert!(
+        returning_blocks(record)
+            .iter()
+            .all(|(_, instructions)| instructions.iter().any(|line| line.starts_with("ret i64"))),
+        "{record}"
+    );
+}
+
+#[test]
+fn loop_iterators_are_freed_on_every_exit() {
+    let ir = lower_to_ir(
+        "fn scan(word: str) -> int:\n    \
+         outer: for i in 0..3:\n        \
+         for ch in word:\n            \
+         if ch == \"t\":\n                break outer\n            \
+         if ch == \"x\":\n                return i\n    \
+         return -1\n\
+         fn main():\n    scan(\"otter\")\n",
+    );
+    let scan = function_ir(&ir, "scan");
+    let frees = |symbol: &str| {
+        scan.lines()
+            .filter(|line| line.contains("call ") && line.contains(&format!("@{symbol}(")))
+            .count()
+    };
+
+    // The inner iterator is freed when its loop ends, by `break outer` and by
+    // `return`; the outer one when its loop ends and by `return`
+    assert_eq!(frees("otter_builtin_iter_free_string"), 3, "{scan}");
+    assert_eq!(frees("otter_builtin_iter_free_array"), 2, "{scan}");
+}
+
+#[test]
+fn parameters_are_passed_as_the_types_the_checker_gives_them() {
+    let ir = lower_to_ir(
+        "type Ratio = float\n\
+         type Flag = bool\n\
+         type Name = str\n\
+         fn label(ratio: Ratio, shown: Flag, name: Name):\n    \
+         if shown and ratio > 0.0:\n        print(name)\n\
+         fn scaled(value, factor = 2.5) -> float:\n    \
+         return value * factor\n\
+         fn main():\n    \
+         label(0.5, true, \"otter\")\n    \
+         let total: float = scaled(1.5)\n",
+    );
+
+    // Aliases are passed as their targets
+    let label = function_ir(&ir, "label");
+    assert_eq!(param_types(label), ["double", "i1", "ptr"], "{label}");
+    // Unannotated parameters take the types inferred from their call and
+    // default rather than the dynamic `i64`
+    let scaled = function_ir(&ir, "scaled");
+    assert_eq!(param_types(scaled), ["double", "double"], "{scaled}");
+}
+
+#[test]
+fn callbacks_are_passed_to_the_runtime_by_address() {
+    let ir = lower_to_ir(
+        "use runtime\n\
+         fn close_listeners():\n    pass\n\
+         fn main():\n    runtime.on_shutdown(close_listeners)\n",
+    );
+    let main = function_ir(&ir, "otter_entry");
+    // A constant address, so LLVM may fold the cast into the call
+    assert!(
+        main.lines()
+            .any(|line| line.contains("ptrtoint") && line.contains("@close_listeners")),
+        "{main}"
+    );
+}
+
+#[test]
+fn string_patterns_compare_the_text() {
+    let ir = lower_to_ir(
+        "fn route(method: string) -> string:\n    \
+         match method:\n        \
+         case \"GET\" | \"HEAD\":\n            return \"read\"\n        \
+         case \"POST\":\n            return \"write\"\n        \
+         case _:\n            return \"reject\"\n\
+         fn main():\n    \
+         let verb = \"PO\" + \"ST\"\n    \
+         println(route(verb))\n",
+    );
+    let route = function_ir(&ir, "route");
+
+    // Each literal is compared by its text, not by pointer
+    let compares = route
+        .lines()
+        .filter(|line| line.contains("call ") && line.contains("@otter_string_equal("))
+        .count();
+    assert_eq!(compares, 3, "{route}");
+
+    // A method no literal matches falls through to the wildcard arm
+    let reject = ir
+        .lines()
+        .find(|line| line.contains("c\"reject\\00\""))
+        .and_then(|line| line.split_once(" = "))
+        .map(|(name, _)| name)
+        .unwrap_or_else(|| panic!("no \"reject\" constant in:\n{ir}"));
+    let mut names = route.split(|c: char| !(c.is_alphanumeric() || matches!(c, '@' | '.' | '_')));
+    assert!(names.any(|name| name == reject), "{route}");
+}
+
+#[test]
+fn semver_versions_compare_by_value() {
+    let ir = lower_to_ir(
+        "use semver\n\
+         fn main():\n    \
+         let installed = semver.parse(\"1.4.2\")\n    \
+         if installed == 0:\n        return\n    \
+         if installed >= semver.parse(\"1.2.0\"):\n        print(\"supported\")\n",
+    );
+    let main = function_ir(&ir, "otter_entry");
+    // Comparing two versions goes through the runtime, checking the handle
+    // against `0` does not
+    let compares = main
+        .lines()
+        .filter(|line| line.contains("call ") && line.contains("@otter_std_semver_compare("))
+        .count();
+    assert_eq!(compares, 1, "{main}");
+}
+
+/// What the loop-invariant pass reported moving out of the loop in
+/// `body`, a `while` loop over the list `xs`
+fn hoisted_lengths(body: &str) -> Vec<String> {
+    let source = format!(
+        "fn tick() -> int:\n    return 1\n\
+         fn count(ready: bool) -> int:\n    \
+         let xs = [1, 2, 3]\n    \
+         let i = 0\n    \
+         let total = 0\n    \
+         {body}\n    \
+         return total\n\
+         fn main():\n    count(true)\n"
+    );
+    lower(&source).1
+}
+
+#[test]
+fn lengths_of_unchanged_collections_are_computed_before_the_loop() {
+    let report = hoisted_lengths("while i < len(xs):\n        i = i + 1");
+    assert_eq!(report.len(), 1, "{report:?}");
+    assert!(
+        report[0].ends_with(
+            "the length of `xs` is computed once before this `while` loop, \
+             which does not change `xs`"
+        ),
+        "{report:?}"
+    );
+}
+
+#[test]
+fn lengths_stay_in_loops_that_may_change_the_collection() {
+    for body in [
+        "while i < len(xs):\n        xs.append(i)\n        i = i + 1",
+        "while i < len(xs):\n        xs = [i]\n        i = i + 1",
+        "while i < len(xs):\n        let task = spawn tick()\n        i = i + 1",
+        "let task = spawn tick()\n    \
+         while i < len(xs):\n        total = total + await task\n        i = i + 1",
+    ] {
+        assert_eq!(hoisted_lengths(body), Vec::<String>::new(), "{body}");
+    }
+}
+
+#[test]
+fn lengths_an_iteration_may_skip_stay_in_the_loop() {
+    for body in [
+        "while i < 10 and i < len(xs):\n        i = i + 1",
+        "while i < 10:\n        if ready:\n            total = total + len(xs)\n        i = i + 1",
+        "while i < 10:\n        i = i + 1\n        if ready:\n            break\n        \
+         total = total + len(xs)",
+    ] {
+        assert_eq!(hoisted_lengths(body), Vec::<String>::new(), "{body}");
+    }
+}
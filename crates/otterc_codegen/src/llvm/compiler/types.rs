@@ -1,13 +1,21 @@
 use inkwell::basic_block::BasicBlock;
-use inkwell::values::{BasicValueEnum, PointerValue};
+use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
+use otterc_ast::nodes::Expr;
+use otterc_typecheck::Numeric;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OtterType {
     Unit,
     Bool,
+    I8,
+    I16,
     I32,
     I64,
+    U8,
+    U16,
+    U32,
+    U64,
     F64,
     Str,
     Opaque,               // For handles, pointers, etc.
@@ -35,6 +43,61 @@ impl OtterType {
             _ => None,
         }
     }
+
+    /// The integer type with the given bit width and signedness
+    pub fn int(bits: u32, signed: bool) -> Option<Self> {
+        Some(match (bits, signed) {
+            (8, true) => OtterType::I8,
+            (16, true) => OtterType::I16,
+            (32, true) => OtterType::I32,
+            (64, true) => OtterType::I64,
+            (8, false) => OtterType::U8,
+            (16, false) => OtterType::U16,
+            (32, false) => OtterType::U32,
+            (64, false) => OtterType::U64,
+            _ => return None,
+        })
+    }
+
+    /// Bit width and signedness of an integer type
+    pub fn int_layout(&self) -> Option<(u32, bool)> {
+        Some(match self {
+            OtterType::I8 => (8, true),
+            OtterType::I16 => (16, true),
+            OtterType::I32 => (32, true),
+            OtterType::I64 => (64, true),
+            OtterType::U8 => (8, false),
+            OtterType::U16 => (16, false),
+            OtterType::U32 => (32, false),
+            OtterType::U64 => (64, false),
+            _ => return None,
+        })
+    }
+
+    pub fn is_int(&self) -> bool {
+        self.int_layout().is_some()
+    }
+
+    /// The type as the type checker's widening rules see it
+    pub fn numeric(&self) -> Option<Numeric> {
+        match self {
+            OtterType::F64 => Some(Numeric::Float),
+            ty => ty
+                .int_layout()
+                .map(|(bits, signed)| Numeric::Int { bits, signed }),
+        }
+    }
+
+    /// The numeric type both operands convert to without loss, by the same
+    /// rules the type checker applies
+    pub fn common_numeric(&self, other: &OtterType) -> Option<OtterType> {
+        let common = self.numeric()?.common(other.numeric()?)?;
+        if self.numeric() == Some(common) {
+            Some(self.clone())
+        } else {
+            Some(other.clone())
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -62,13 +125,44 @@ pub struct Variable<'ctx> {
 pub struct LoopContext<'ctx> {
     pub cond_bb: BasicBlock<'ctx>,
     pub exit_bb: BasicBlock<'ctx>,
+    /// Height of the cleanup stack when the loop was entered
+    pub cleanup_depth: usize,
+    /// Label targeted by `break label` / `continue label`
+    pub label: Option<String>,
+}
+
+/// Code that must run whenever control leaves the scope that registered it
+#[derive(Debug, Clone)]
+pub enum CleanupAction<'ctx> {
+    /// Call `function` with the value stored in `resource` (a `with` exit method)
+    CallMethod {
+        function: FunctionValue<'ctx>,
+        resource: Variable<'ctx>,
+    },
+    /// Release the runtime iterator of a `for` loop by calling `function` with it
+    FreeIterator {
+        function: FunctionValue<'ctx>,
+        iterator: BasicValueEnum<'ctx>,
+    },
+    /// Evaluate a `defer`red expression if its statement was reached (`armed`
+    /// holds an i1 flag set when the `defer` executed)
+    Deferred {
+        expr: Expr,
+        armed: PointerValue<'ctx>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionContext<'ctx> {
     pub variables: HashMap<String, Variable<'ctx>>,
     pub loop_stack: Vec<LoopContext<'ctx>>,
+    pub cleanup_stack: Vec<CleanupAction<'ctx>>,
+    /// Function-scoped `defer` actions in registration order
+    pub deferred: Vec<CleanupAction<'ctx>>,
     pub exception_landingpad: Option<BasicBlock<'ctx>>,
+    /// Name passed to the runtime profiler when the function is entered,
+    /// which it is passed again on every exit
+    pub profiled_name: Option<PointerValue<'ctx>>,
 }
 
 impl<'ctx> FunctionContext<'ctx> {
@@ -76,7 +170,10 @@ impl<'ctx> FunctionContext<'ctx> {
         Self {
             variables: HashMap::new(),
             loop_stack: Vec::new(),
+            cleanup_stack: Vec::new(),
+            deferred: Vec::new(),
             exception_landingpad: None,
+            profiled_name: None,
         }
     }
 
@@ -92,8 +189,18 @@ impl<'ctx> FunctionContext<'ctx> {
         self.variables.remove(name)
     }
 
-    pub fn push_loop(&mut self, cond_bb: BasicBlock<'ctx>, exit_bb: BasicBlock<'ctx>) {
-        self.loop_stack.push(LoopContext { cond_bb, exit_bb });
+    pub fn push_loop(
+        &mut self,
+        cond_bb: BasicBlock<'ctx>,
+        exit_bb: BasicBlock<'ctx>,
+        label: Option<String>,
+    ) {
+        self.loop_stack.push(LoopContext {
+            cond_bb,
+            exit_bb,
+            cleanup_depth: self.cleanup_stack.len(),
+            label,
+        });
     }
 
     pub fn pop_loop(&mut self) -> Option<LoopContext<'ctx>> {
@@ -103,6 +210,19 @@ impl<'ctx> FunctionContext<'ctx> {
     pub fn current_loop(&self) -> Option<&LoopContext<'ctx>> {
         self.loop_stack.last()
     }
+
+    /// The innermost enclosing loop labeled `label`
+    pub fn labeled_loop(&self, label: &str) -> Option<&LoopContext<'ctx>> {
+        self.loop_stack
+            .iter()
+            .rev()
+            .find(|loop_ctx| loop_ctx.label.as_deref() == Some(label))
+    }
+
+    /// Cleanup actions registered above `depth`, innermost first
+    pub fn cleanups_above(&self, depth: usize) -> Vec<CleanupAction<'ctx>> {
+        self.cleanup_stack[depth..].iter().rev().cloned().collect()
+    }
 }
 
 impl<'ctx> Default for FunctionContext<'ctx> {
@@ -52,4 +52,8 @@ fn compiler_reports_clang(driver: &str) -> bool {
 pub struct BuildArtifact {
     pub binary: PathBuf,
     pub ir: Option<String>,
+    /// One line for each expression moved out of a loop, when requested
+    pub opt_report: Option<Vec<String>>,
+    /// The files `--emit` asked for, next to the binary
+    pub emitted: Vec<PathBuf>,
 }
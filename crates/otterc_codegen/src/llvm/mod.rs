@@ -3,5 +3,5 @@ pub mod build;
 pub mod compiler;
 pub mod config;
 
-pub use build::{build_executable, build_shared_library, current_llvm_version};
+pub use build::{build_executable, build_shared_library, current_llvm_version, merge_pgo_profiles};
 pub use config::BuildArtifact;
@@ -2,10 +2,12 @@
 #include <stdint.h>
 
 extern void otter_entry();
+extern void otter_runtime_init(void);
 
 int main(int argc, char** argv) {
     (void)argc;
     (void)argv;
+    otter_runtime_init();
     otter_entry();
     return 0;
 }
\ No newline at end of file
@@ -6,6 +6,11 @@
 
 #ifdef __wasi__
 #include <wasi/api.h>
+#include <dirent.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <time.h>
+#include <unistd.h>
 #else
 __attribute__((import_module("env"), import_name("otter_write_stdout")))
 void otter_env_write_stdout(const char* ptr, uint32_t len);
@@ -406,4 +411,165 @@ int otter_validate_utf8(const char* ptr) {
         } else return 0;
     }
     return 1;
-}
\ No newline at end of file
+}
+
+#ifdef __wasi__
+// ============================================================================
+// WASI commands: entry point, clock, filesystem and tasks
+// ============================================================================
+
+extern void otter_entry();
+
+int main(int argc, char** argv) {
+    (void)argc;
+    (void)argv;
+    otter_entry();
+    return otter_has_error_state ? 1 : 0;
+}
+
+static int64_t otter_clock_ns(__wasi_clockid_t clock) {
+    __wasi_timestamp_t timestamp = 0;
+    if (__wasi_clock_time_get(clock, 1, &timestamp) != __WASI_ERRNO_SUCCESS) {
+        return 0;
+    }
+    return (int64_t)timestamp;
+}
+
+int64_t otter_std_time_now_us() {
+    return otter_clock_ns(__WASI_CLOCKID_REALTIME) / 1000;
+}
+
+int64_t otter_std_time_now_ns() {
+    return otter_clock_ns(__WASI_CLOCKID_REALTIME);
+}
+
+int64_t otter_std_time_now_sec() {
+    return otter_clock_ns(__WASI_CLOCKID_REALTIME) / 1000000000;
+}
+
+void otter_std_time_sleep_ms(int64_t ms) {
+    if (ms <= 0) return;
+    struct timespec duration = { .tv_sec = ms / 1000, .tv_nsec = (ms % 1000) * 1000000 };
+    nanosleep(&duration, NULL);
+}
+
+// Paths resolve against the directories the host preopens, such as
+// `wasmtime run --dir .`
+
+char* otter_std_io_read(const char* path) {
+    if (!path) return NULL;
+    FILE* file = fopen(path, "rb");
+    if (!file) return NULL;
+    size_t capacity = 4096;
+    size_t len = 0;
+    char* buffer = (char*)malloc(capacity);
+    while (buffer) {
+        len += fread(buffer + len, 1, capacity - len - 1, file);
+        if (len + 1 < capacity) break;
+        capacity *= 2;
+        char* tmp = (char*)realloc(buffer, capacity);
+        if (!tmp) free(buffer);
+        buffer = tmp;
+    }
+    int failed = ferror(file);
+    fclose(file);
+    if (!buffer) return NULL;
+    if (failed) {
+        free(buffer);
+        return NULL;
+    }
+    buffer[len] = '\0';
+    return buffer;
+}
+
+int32_t otter_std_io_write(const char* path, const char* data) {
+    if (!path || !data) return 0;
+    FILE* file = fopen(path, "wb");
+    if (!file) return 0;
+    size_t len = strlen(data);
+    size_t written = fwrite(data, 1, len, file);
+    return fclose(file) == 0 && written == len ? 1 : 0;
+}
+
+int32_t otter_std_io_exists(const char* path) {
+    struct stat info;
+    return path && stat(path, &info) == 0 ? 1 : 0;
+}
+
+int32_t otter_std_io_is_file(const char* path) {
+    struct stat info;
+    return path && stat(path, &info) == 0 && S_ISREG(info.st_mode) ? 1 : 0;
+}
+
+int32_t otter_std_io_is_dir(const char* path) {
+    struct stat info;
+    return path && stat(path, &info) == 0 && S_ISDIR(info.st_mode) ? 1 : 0;
+}
+
+int64_t otter_std_io_file_size(const char* path) {
+    struct stat info;
+    if (!path || stat(path, &info) != 0) return -1;
+    return (int64_t)info.st_size;
+}
+
+int32_t otter_std_io_remove(const char* path) {
+    return path && unlink(path) == 0 ? 1 : 0;
+}
+
+// Creates the missing parents too, like the native runtime
+int32_t otter_std_io_mkdir(const char* path) {
+    if (!path || !*path) return 0;
+    char* partial = otter_dup_cstr(path);
+    if (!partial) return 0;
+    for (char* cursor = partial + 1; *cursor; cursor++) {
+        if (*cursor != '/') continue;
+        *cursor = '\0';
+        mkdir(partial, 0777);
+        *cursor = '/';
+    }
+    int created = mkdir(partial, 0777) == 0 || otter_std_io_is_dir(partial);
+    free(partial);
+    return created ? 1 : 0;
+}
+
+// Removes the directory and everything in it, like the native runtime
+int32_t otter_std_io_rmdir(const char* path) {
+    if (!path) return 0;
+    DIR* dir = opendir(path);
+    if (!dir) return 0;
+    size_t base = strlen(path);
+    struct dirent* entry;
+    while ((entry = readdir(dir)) != NULL) {
+        if (strcmp(entry->d_name, ".") == 0 || strcmp(entry->d_name, "..") == 0) continue;
+        size_t name_len = strlen(entry->d_name);
+        char* child = (char*)malloc(base + name_len + 2);
+        if (!child) break;
+        memcpy(child, path, base);
+        child[base] = '/';
+        memcpy(child + base + 1, entry->d_name, name_len + 1);
+        if (!otter_std_io_rmdir(child)) unlink(child);
+        free(child);
+    }
+    closedir(dir);
+    return rmdir(path) == 0 ? 1 : 0;
+}
+
+// There are no threads: a spawned task runs to completion before `spawn`
+// returns, so joining has nothing left to wait for
+typedef void (*otter_task_closure)(void*);
+
+static uint64_t otter_next_task_id = 1;
+
+uint64_t otter_task_spawn_closure(otter_task_closure callback, void* ctx) {
+    if (callback) callback(ctx);
+    return otter_next_task_id++;
+}
+
+void otter_task_join(uint64_t handle) {
+    (void)handle;
+}
+
+void otter_task_detach(uint64_t handle) {
+    (void)handle;
+}
+#endif
@@ -0,0 +1,114 @@
+//! Values `@cfg(...)` attributes are tested against
+//!
+//! A `key="value"` condition holds when the build's value for `key` equals
+//! `value`. `feature` may have any number of values, the others exactly one.
+
+use crate::target::TargetTriple;
+
+/// The target and feature set a program is being compiled for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CfgContext {
+    /// Operating system, using Rust's names (`linux`, `macos`, `windows`, ...)
+    pub os: String,
+    /// CPU architecture (`x86_64`, `aarch64`, `wasm32`, ...)
+    pub arch: String,
+    /// `unix`, `windows`, `wasm` or `none`
+    pub family: String,
+    /// Features enabled with `--cfg-feature`
+    pub features: Vec<String>,
+}
+
+impl CfgContext {
+    /// The machine the compiler runs on
+    pub fn host() -> Self {
+        let family = if std::env::consts::FAMILY.is_empty() {
+            "none"
+        } else {
+            std::env::consts::FAMILY
+        };
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            family: family.to_string(),
+            features: Vec::new(),
+        }
+    }
+
+    /// A cross-compilation target
+    pub fn for_target(target: &TargetTriple) -> Self {
+        let os = if target.os.starts_with("darwin") || target.os.starts_with("macos") {
+            "macos"
+        } else if target.is_embedded() {
+            "none"
+        } else {
+            target.os.as_str()
+        };
+        let family = if target.is_wasm() {
+            "wasm"
+        } else if target.is_windows() {
+            "windows"
+        } else if target.is_unix() || target.os.starts_with("darwin") {
+            "unix"
+        } else {
+            "none"
+        };
+        Self {
+            os: os.to_string(),
+            arch: target.arch.clone(),
+            family: family.to_string(),
+            features: Vec::new(),
+        }
+    }
+
+    pub fn with_features(mut self, features: impl IntoIterator<Item = String>) -> Self {
+        self.features.extend(features);
+        self
+    }
+
+    /// Whether `key="value"` holds. Unknown keys never hold.
+    pub fn matches(&self, key: &str, value: &str) -> bool {
+        match key {
+            "os" => self.os == value,
+            "arch" => self.arch == value,
+            "family" => self.family == value,
+            "feature" => self.features.iter().any(|feature| feature == value),
+            _ => false,
+        }
+    }
+}
+
+impl Default for CfgContext {
+    fn default() -> Self {
+        Self::host()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_targets_use_rust_os_names() {
+        let mac = CfgContext::for_target(&TargetTriple::new(
+            "aarch64",
+            "apple",
+            "darwin11.0",
+            None::<String>,
+        ));
+        assert!(mac.matches("os", "macos"));
+        assert!(mac.matches("family", "unix"));
+        assert!(mac.matches("arch", "aarch64"));
+
+        let wasm = CfgContext::for_target(&TargetTriple::wasm32_wasi());
+        assert!(wasm.matches("family", "wasm"));
+        assert!(!wasm.matches("os", "linux"));
+    }
+
+    #[test]
+    fn features_are_opt_in() {
+        let cfg = CfgContext::host().with_features(["gpu".to_string()]);
+        assert!(cfg.matches("feature", "gpu"));
+        assert!(!cfg.matches("feature", "simd"));
+        assert!(!cfg.matches("planet", "mars"));
+    }
+}
@@ -1,10 +1,15 @@
+pub mod cfg;
 pub mod target;
 pub mod tiered_compiler;
 
+pub use crate::cfg::CfgContext;
 pub use crate::target::TargetTriple;
 pub use crate::tiered_compiler::*;
 use inkwell::OptimizationLevel;
+use otterc_span::SourceLines;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -43,12 +48,211 @@ impl LanguageFeatureFlags {
     }
 }
 
-/// Codegen optimization level
+/// Codegen optimization level (`-O0/1/2/3/z`)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CodegenOptLevel {
     None,
+    /// `-O1`: the cheap optimizations, without vectorization
+    Less,
     Default,
     Aggressive,
+    /// `-Oz`: as small as possible
+    Size,
+}
+
+impl CodegenOptLevel {
+    /// The level in LLVM pipeline names, as in `default<O2>`
+    pub fn pipeline_level(self) -> &'static str {
+        match self {
+            CodegenOptLevel::None => "O0",
+            CodegenOptLevel::Less => "O1",
+            CodegenOptLevel::Default => "O2",
+            CodegenOptLevel::Aggressive => "O3",
+            CodegenOptLevel::Size => "Oz",
+        }
+    }
+
+    /// The flag that gives clang the same level
+    pub fn clang_flag(self) -> &'static str {
+        match self {
+            CodegenOptLevel::None => "-O0",
+            CodegenOptLevel::Less => "-O1",
+            CodegenOptLevel::Default => "-O2",
+            CodegenOptLevel::Aggressive => "-O3",
+            CodegenOptLevel::Size => "-Oz",
+        }
+    }
+}
+
+impl FromStr for CodegenOptLevel {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let level = text
+            .strip_prefix(['O', 'o'])
+            .unwrap_or(text)
+            .to_ascii_lowercase();
+        match level.as_str() {
+            "0" => Ok(CodegenOptLevel::None),
+            "1" => Ok(CodegenOptLevel::Less),
+            "2" => Ok(CodegenOptLevel::Default),
+            "3" => Ok(CodegenOptLevel::Aggressive),
+            "z" => Ok(CodegenOptLevel::Size),
+            _ => Err(format!(
+                "unknown optimization level '{text}' (expected 0, 1, 2, 3 or z)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CodegenOptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.pipeline_level())
+    }
+}
+
+/// An intermediate file a build keeps next to the binary (`--emit`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    /// The optimized LLVM IR
+    LlvmIr,
+    /// Assembly for the target
+    Asm,
+    /// The program's object file, before it is linked with the runtime
+    Obj,
+}
+
+impl EmitKind {
+    /// Extension of the file, which is named after the binary
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmitKind::LlvmIr => "ll",
+            EmitKind::Asm => "s",
+            EmitKind::Obj => "o",
+        }
+    }
+}
+
+/// Link-time optimization of the program together with its runtime (`--lto`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LtoMode {
+    #[default]
+    Off,
+    /// ThinLTO: per-module summaries and parallel backends, for most of the
+    /// inlining at a fraction of the link time
+    Thin,
+    /// Everything merged into one module before code generation
+    Full,
+}
+
+impl LtoMode {
+    pub fn is_enabled(self) -> bool {
+        self != LtoMode::Off
+    }
+
+    /// The flag that makes clang compile to bitcode and link with LTO
+    pub fn clang_flag(self) -> Option<&'static str> {
+        match self {
+            LtoMode::Off => None,
+            LtoMode::Thin => Some("-flto=thin"),
+            LtoMode::Full => Some("-flto=full"),
+        }
+    }
+}
+
+impl FromStr for LtoMode {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" | "false" => Ok(LtoMode::Off),
+            "thin" => Ok(LtoMode::Thin),
+            "full" | "fat" | "true" => Ok(LtoMode::Full),
+            other => Err(format!(
+                "unknown LTO mode '{other}' (expected off, thin or full)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LtoMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LtoMode::Off => "off",
+            LtoMode::Thin => "thin",
+            LtoMode::Full => "full",
+        })
+    }
+}
+
+/// Profile-guided optimization (`otter build --pgo-gen` and `--pgo-use`)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PgoMode {
+    #[default]
+    Off,
+    /// Instrument the program so that every run adds to the raw profiles
+    /// in `dir`
+    Generate { dir: PathBuf },
+    /// Optimize with a merged `.profdata` profile of the instrumented runs
+    Use { profile: PathBuf },
+}
+
+impl PgoMode {
+    pub fn is_enabled(&self) -> bool {
+        *self != PgoMode::Off
+    }
+}
+
+/// A sanitizer a build is instrumented with (`otter build --sanitize`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sanitizer {
+    /// AddressSanitizer: out-of-bounds and use-after-free accesses
+    Address,
+    /// UndefinedBehaviorSanitizer, for the C code linked into the program
+    Undefined,
+}
+
+impl Sanitizer {
+    /// The name clang's `-fsanitize=` takes
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+        }
+    }
+
+    /// The `-fsanitize=` flag for `sanitizers`, if there are any
+    pub fn clang_flag(sanitizers: &[Sanitizer]) -> Option<String> {
+        if sanitizers.is_empty() {
+            return None;
+        }
+        let names: Vec<_> = sanitizers
+            .iter()
+            .map(|sanitizer| sanitizer.as_str())
+            .collect();
+        Some(format!("-fsanitize={}", names.join(",")))
+    }
+}
+
+impl FromStr for Sanitizer {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "address" | "asan" => Ok(Sanitizer::Address),
+            "undefined" | "ubsan" => Ok(Sanitizer::Undefined),
+            other => Err(format!(
+                "unknown sanitizer '{other}' (expected address or undefined)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Sanitizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Codegen options
@@ -56,12 +260,43 @@ pub enum CodegenOptLevel {
 pub struct CodegenOptions {
     pub emit_ir: bool,
     pub opt_level: CodegenOptLevel,
-    pub enable_lto: bool,
-    pub enable_pgo: bool,
-    pub pgo_profile_file: Option<PathBuf>,
+    /// LLVM pass pipeline to run instead of the one for `opt_level`, as
+    /// `opt -passes=...` takes it
+    pub passes: Option<String>,
+    pub lto: LtoMode,
+    pub pgo: PgoMode,
+    /// Sanitizers to instrument the program with and link the runtimes of
+    pub sanitizers: Vec<Sanitizer>,
     pub inline_threshold: Option<u32>,
     /// Target triple for cross-compilation (defaults to native)
     pub target: Option<TargetTriple>,
+    /// Compile `assert` statements out entirely
+    pub strip_asserts: bool,
+    /// Emit a scheduler preemption point at every loop back-edge
+    pub preempt_loops: bool,
+    /// Source of the program being compiled, used to report file/line locations
+    pub source: Option<SourceLines>,
+    /// Values `@cfg(feature="...")` attributes test for
+    pub cfg_features: Vec<String>,
+    /// Describe what was moved out of loops in the build artifact
+    pub emit_opt_report: bool,
+    /// Report every function's entry and exit to the runtime profiler
+    pub profile_functions: bool,
+    /// Emit DWARF line tables and local variables (`-g`)
+    pub debug_info: bool,
+    /// Intermediate files to write next to the binary
+    pub emit: Vec<EmitKind>,
+}
+
+impl CodegenOptions {
+    /// What `@cfg(...)` attributes are evaluated against for this build
+    pub fn cfg(&self) -> CfgContext {
+        let cfg = match &self.target {
+            Some(target) => CfgContext::for_target(target),
+            None => CfgContext::host(),
+        };
+        cfg.with_features(self.cfg_features.iter().cloned())
+    }
 }
 
 impl Default for CodegenOptions {
@@ -69,11 +304,20 @@ impl Default for CodegenOptions {
         Self {
             emit_ir: false,
             opt_level: CodegenOptLevel::Default,
-            enable_lto: false,
-            enable_pgo: false,
-            pgo_profile_file: None,
+            passes: None,
+            lto: LtoMode::Off,
+            pgo: PgoMode::Off,
+            sanitizers: Vec::new(),
             inline_threshold: None,
             target: None,
+            strip_asserts: false,
+            preempt_loops: false,
+            source: None,
+            cfg_features: Vec::new(),
+            emit_opt_report: false,
+            profile_functions: false,
+            debug_info: false,
+            emit: Vec::new(),
         }
     }
 }
@@ -82,7 +326,9 @@ impl From<CodegenOptLevel> for OptimizationLevel {
     fn from(value: CodegenOptLevel) -> Self {
         match value {
             CodegenOptLevel::None => OptimizationLevel::None,
-            CodegenOptLevel::Default => OptimizationLevel::Default,
+            CodegenOptLevel::Less => OptimizationLevel::Less,
+            // Instruction selection has no size level; the passes shrink the code
+            CodegenOptLevel::Default | CodegenOptLevel::Size => OptimizationLevel::Default,
             CodegenOptLevel::Aggressive => OptimizationLevel::Aggressive,
         }
     }
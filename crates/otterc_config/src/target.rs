@@ -38,11 +38,19 @@ impl TargetTriple {
 
     /// Parse a target triple string (e.g., "x86_64-unknown-linux-gnu")
     pub fn parse(triple: &str) -> Result<Self, String> {
-        let parts: Vec<&str> = triple.split('-').collect();
+        let mut parts: Vec<&str> = triple.split('-').collect();
 
+        // `wasm32-wasi` and `wasm32-wasip1` leave out the vendor
+        if parts.len() == 2 && parts[0].starts_with("wasm") {
+            parts.insert(1, "unknown");
+        }
         if parts.len() < 3 {
             return Err(format!("Invalid target triple format: {}", triple));
         }
+        // WASI preview 1 is what LLVM calls `wasi`
+        if parts[2] == "wasip1" {
+            parts[2] = "wasi";
+        }
 
         let mut arch = parts[0].to_string();
         // Normalize arm64 to aarch64 for LLVM compatibility
@@ -127,6 +135,11 @@ impl TargetTriple {
         self.os == "windows"
     }
 
+    /// Check if this is a WebAssembly target with the WASI system interface
+    pub fn is_wasi(&self) -> bool {
+        self.is_wasm() && self.os == "wasi"
+    }
+
     /// Check if this is a Unix-like target
     pub fn is_unix(&self) -> bool {
         matches!(
@@ -147,7 +160,10 @@ impl TargetTriple {
 
     /// Get the appropriate linker driver for this target
     pub fn linker(&self) -> String {
-        if self.is_wasm() {
+        if self.is_wasi() {
+            // clang links against wasi-libc, which brings `_start` and malloc
+            "clang".to_string()
+        } else if self.is_wasm() {
             "wasm-ld".to_string()
         } else if self.is_windows() {
             // Use clang as the linker driver so we can keep passing POSIX-style flags
@@ -161,12 +177,13 @@ impl TargetTriple {
     pub fn linker_flags(&self) -> Vec<String> {
         let mut flags = Vec::new();
 
-        if self.is_wasm() {
+        if self.is_wasi() {
+            // A WASI command starts at `_start`; the default 64KiB stack is
+            // too small for recursive programs
+            flags.push("-Wl,-z,stack-size=1048576".to_string());
+        } else if self.is_wasm() {
             flags.push("--no-entry".to_string());
             flags.push("--export-dynamic".to_string());
-            if self.os == "wasi" {
-                flags.push("--allow-undefined".to_string());
-            }
         } else if self.is_windows() {
             // Pass subsystem settings through clang to the MSVC linker
             flags.push("-Wl,/SUBSYSTEM:CONSOLE".to_string());
@@ -186,6 +203,19 @@ impl TargetTriple {
         flags
     }
 
+    /// Whether code built for `self` runs on `other`: the same architecture,
+    /// OS and ABI, whatever the vendor or OS version
+    pub fn same_platform(&self, other: &TargetTriple) -> bool {
+        fn os_base(os: &str) -> &str {
+            os.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+        }
+        let env_matches = match (&self.env, &other.env) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        self.arch == other.arch && os_base(&self.os) == os_base(&other.os) && env_matches
+    }
+
     /// Check if this target needs position-independent code
     pub fn needs_pic(&self) -> bool {
         self.is_wasm() || matches!(self.os.as_str(), "linux" | "freebsd" | "openbsd" | "netbsd")
@@ -279,6 +309,28 @@ mod tests {
         assert_eq!(triple.to_llvm_triple(), "wasm32-unknown-unknown");
     }
 
+    #[test]
+    fn test_wasi_triple() {
+        for name in ["wasm32-wasi", "wasm32-wasip1", "wasm32-unknown-wasi"] {
+            let triple = TargetTriple::parse(name).unwrap();
+            assert!(triple.is_wasi(), "{name}");
+            assert_eq!(triple.to_llvm_triple(), "wasm32-unknown-wasi");
+        }
+        assert_eq!(TargetTriple::wasm32_wasi().linker(), "clang");
+        assert!(!TargetTriple::wasm32_unknown_unknown().is_wasi());
+        assert!(TargetTriple::parse("x86_64-linux").is_err());
+    }
+
+    #[test]
+    fn test_same_platform() {
+        let host = TargetTriple::parse("x86_64-pc-linux-gnu").unwrap();
+        assert!(host.same_platform(&TargetTriple::parse("x86_64-unknown-linux-gnu").unwrap()));
+        assert!(!host.same_platform(&TargetTriple::parse("aarch64-unknown-linux-gnu").unwrap()));
+        assert!(!host.same_platform(&TargetTriple::parse("x86_64-unknown-linux-musl").unwrap()));
+        let mac = TargetTriple::new("aarch64", "apple", "darwin11.0", None::<String>);
+        assert!(mac.same_platform(&TargetTriple::parse("arm64-apple-darwin").unwrap()));
+    }
+
     #[test]
     fn test_embedded_triple() {
         let triple = TargetTriple::thumbv7m_none_eabi();
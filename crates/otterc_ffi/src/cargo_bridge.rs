@@ -3,8 +3,9 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow, bail};
 use libloading::library_filename;
-use tracing::debug;
+use tracing::{debug, warn};
 
+use otterc_cache::SharedCache;
 use otterc_cache::path::cache_root;
 
 use super::rust_stubgen::RustStubGenerator;
@@ -20,10 +21,18 @@ pub struct BridgeArtifacts {
     pub library_path: PathBuf,
 }
 
+/// Directories of prebuilt bridges, separated like `PATH`
+pub const BRIDGE_PATH_ENV: &str = "OTTER_BRIDGE_PATH";
+
 /// Coordinates stub generation, cargo builds, and symbol registration.
 pub struct CargoBridge {
     root: PathBuf,
     registry: BridgeSymbolRegistry,
+    /// Compiled bridge libraries shared with other machines
+    shared: Option<SharedCache>,
+    /// Directories holding bridges built ahead of time, laid out as
+    /// `<arch>-<os>/<crate>-<hash>/` with the library and `crate-spec.json`
+    prebuilt: Vec<PathBuf>,
 }
 
 impl CargoBridge {
@@ -34,7 +43,58 @@ impl CargoBridge {
             Err(_) => return Err(anyhow!("Failed to get cache root")),
         };
         fs::create_dir_all(&root).context("failed to create ffi cache root")?;
-        Ok(Self { root, registry })
+        let prebuilt = std::env::var_os(BRIDGE_PATH_ENV)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+        Ok(Self {
+            root,
+            registry,
+            shared: SharedCache::from_env(),
+            prebuilt,
+        })
+    }
+
+    /// Also looks for prebuilt bridges in `dir`, before the ones from
+    /// `OTTER_BRIDGE_PATH`
+    pub fn with_prebuilt_dir(mut self, dir: PathBuf) -> Self {
+        self.prebuilt.insert(0, dir);
+        self
+    }
+
+    /// The prebuilt bridge for `metadata`'s crate, if one was shipped for this
+    /// platform with a matching hash. It needs neither rustdoc nor cargo.
+    fn find_prebuilt(&self, metadata: &BridgeMetadata) -> Option<PathBuf> {
+        let relative = Path::new(&platform_dir()).join(bridge_dir_name(metadata));
+        let library = library_filename(format!("otterffi_{}", metadata.crate_name));
+        self.prebuilt
+            .iter()
+            .map(|dir| dir.join(&relative))
+            .find(|dir| dir.join(&library).is_file())
+    }
+
+    /// Copies the compiled bridge and the crate spec for `crate_name` to
+    /// `out`, where `OTTER_BRIDGE_PATH` can point consumers of a package.
+    /// Returns the directory written.
+    pub fn export_prebuilt(&self, crate_name: &str, out: &Path) -> Result<PathBuf> {
+        let metadata = self.registry.ensure_metadata(crate_name)?;
+        let artifacts = self.ensure_bridge(crate_name)?;
+        let spec = self.crate_spec(&metadata);
+
+        let dest = out.join(platform_dir()).join(bridge_dir_name(&metadata));
+        fs::create_dir_all(&dest)
+            .with_context(|| format!("failed to create {}", dest.display()))?;
+        let library = artifacts
+            .library_path
+            .file_name()
+            .ok_or_else(|| anyhow!("bridge library has no file name"))?;
+        fs::copy(&artifacts.library_path, dest.join(library)).with_context(|| {
+            format!(
+                "failed to copy bridge library {}",
+                artifacts.library_path.display()
+            )
+        })?;
+        write_crate_spec(&dest.join(CRATE_SPEC_FILE), &spec)?;
+        Ok(dest)
     }
 
     /// Ensures a bridge crate exists and is compiled for the requested `crate_name`.
@@ -45,8 +105,18 @@ impl CargoBridge {
         let metadata = self.registry.ensure_metadata(crate_name)?;
         let cache_hash = metadata.dependency.cache_hash();
 
+        if let Some(prebuilt) = self.find_prebuilt(&metadata) {
+            debug!(crate = %crate_name, dir = %prebuilt.display(), "using prebuilt bridge crate");
+            let library_path = prebuilt.join(library_filename(format!("otterffi_{}", crate_name)));
+            return Ok(BridgeArtifacts {
+                manifest_path: prebuilt.join("Cargo.toml"),
+                crate_root: prebuilt,
+                library_path,
+            });
+        }
+
         // Use hash-based cache directory: <crate_name>-<hash>
-        let cache_dir_name = format!("{}-{}", crate_name, cache_hash);
+        let cache_dir_name = bridge_dir_name(&metadata);
         let crate_root = self.root.join(&cache_dir_name);
 
         // Check if library already exists in cache
@@ -57,6 +127,24 @@ impl CargoBridge {
             .join("release")
             .join(&library_filename);
 
+        // Bridge libraries are native code, so machines only share them with
+        // machines of the same platform
+        let shared_key = format!(
+            "ffi/{}/{}/{}",
+            platform_dir(),
+            cache_dir_name,
+            library_filename.to_string_lossy()
+        );
+        if !cached_library.exists()
+            && let Some(shared) = &self.shared
+        {
+            match shared.fetch(&shared_key, &cached_library) {
+                Ok(true) => debug!(crate = %crate_name, "fetched bridge crate from shared cache"),
+                Ok(false) => {}
+                Err(err) => warn!("failed to read bridge crate from shared cache: {err}"),
+            }
+        }
+
         if cached_library.exists() {
             debug!(
                 crate = %crate_name,
@@ -70,19 +158,7 @@ impl CargoBridge {
             });
         }
 
-        // Precompute transparent crate spec (rustdoc JSON) and synthesize auto functions
-        // If extraction fails (e.g., needs nightly Rust), fall back to bridge.yaml gracefully
-        let spec: CrateSpec = extract_crate_spec(&metadata.dependency).unwrap_or_else(|e| {
-            debug!(
-                "rustdoc extraction failed for {}: {}, falling back to bridge.yaml",
-                crate_name, e
-            );
-            CrateSpec {
-                name: crate_name.to_string(),
-                version: metadata.dependency.version.clone(),
-                items: Vec::new(),
-            }
-        });
+        let spec = self.crate_spec(&metadata);
         let generator = super::rust_stubgen::RustStubGenerator::new(
             metadata.crate_name.clone(),
             metadata.dependency.clone(),
@@ -122,6 +198,11 @@ impl CargoBridge {
         let library_path = self
             .build_bridge(crate_name, &crate_root)
             .context("failed to compile bridge crate")?;
+        if let Some(shared) = &self.shared
+            && let Err(err) = shared.publish(&shared_key, &library_path)
+        {
+            warn!("failed to upload bridge crate to shared cache: {err}");
+        }
 
         Ok(BridgeArtifacts {
             crate_root: crate_root.clone(),
@@ -130,6 +211,51 @@ impl CargoBridge {
         })
     }
 
+    /// The public API of the crate behind `metadata`, shipped with a prebuilt
+    /// bridge or extracted from rustdoc JSON and kept next to the bridge so
+    /// later builds can skip rustdoc. If
+    /// extraction fails (e.g., needs nightly Rust) the spec is empty and only
+    /// bridge.yaml functions are bridged.
+    pub fn crate_spec(&self, metadata: &BridgeMetadata) -> CrateSpec {
+        let spec_path = self
+            .root
+            .join(bridge_dir_name(metadata))
+            .join(CRATE_SPEC_FILE);
+        let prebuilt = self
+            .find_prebuilt(metadata)
+            .map(|dir| dir.join(CRATE_SPEC_FILE));
+        if let Some(spec) = prebuilt
+            .iter()
+            .chain([&spec_path])
+            .find_map(|path| serde_json::from_slice(&fs::read(path).ok()?).ok())
+        {
+            return spec;
+        }
+
+        match extract_crate_spec(&metadata.dependency) {
+            Ok(spec) => {
+                if let Err(err) = write_crate_spec(&spec_path, &spec) {
+                    debug!(
+                        "failed to cache crate spec for {}: {}",
+                        metadata.crate_name, err
+                    );
+                }
+                spec
+            }
+            Err(e) => {
+                debug!(
+                    "rustdoc extraction failed for {}: {}, falling back to bridge.yaml",
+                    metadata.crate_name, e
+                );
+                CrateSpec {
+                    name: metadata.crate_name.clone(),
+                    version: metadata.dependency.version.clone(),
+                    items: Vec::new(),
+                }
+            }
+        }
+    }
+
     fn write_bridge_with_functions(
         &self,
         metadata: &BridgeMetadata,
@@ -213,3 +339,74 @@ impl CargoBridge {
         }
     }
 }
+
+const CRATE_SPEC_FILE: &str = "crate-spec.json";
+
+/// `<crate>-<hash>`, which changes with the crate's version and features
+fn bridge_dir_name(metadata: &BridgeMetadata) -> String {
+    format!(
+        "{}-{}",
+        metadata.crate_name,
+        metadata.dependency.cache_hash()
+    )
+}
+
+/// `<arch>-<os>` of the machine the compiler runs on
+fn platform_dir() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+fn write_crate_spec(path: &Path, spec: &CrateSpec) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(spec)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DependencyConfig;
+
+    #[test]
+    fn prebuilt_bridges_with_a_matching_hash_are_preferred() {
+        let dir = std::env::temp_dir().join(format!("otter-prebuilt-{}", std::process::id()));
+        let metadata = BridgeMetadata {
+            crate_name: "geo".to_string(),
+            dependency: DependencyConfig {
+                name: "geo".to_string(),
+                version: Some("1.0.0".to_string()),
+                path: None,
+                features: Vec::new(),
+                default_features: true,
+            },
+            functions: Vec::new(),
+        };
+        let registry = BridgeSymbolRegistry::new();
+        registry.record(metadata.clone());
+        let bridge = CargoBridge::new(registry)
+            .unwrap()
+            .with_prebuilt_dir(dir.clone());
+
+        let shipped = dir.join(platform_dir()).join(bridge_dir_name(&metadata));
+        fs::create_dir_all(&shipped).unwrap();
+        let library = shipped.join(library_filename("otterffi_geo"));
+        fs::write(&library, b"not really a library").unwrap();
+        // A version rustdoc would never report, to tell the shipped spec apart
+        let spec = CrateSpec {
+            name: "geo".to_string(),
+            version: Some("shipped".to_string()),
+            items: Vec::new(),
+        };
+        write_crate_spec(&shipped.join(CRATE_SPEC_FILE), &spec).unwrap();
+
+        assert_eq!(bridge.ensure_bridge("geo").unwrap().library_path, library);
+        assert_eq!(
+            bridge.crate_spec(&metadata).version.as_deref(),
+            Some("shipped")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -29,7 +29,7 @@ pub fn register_dynamic_exports(library: &Library, registry: &SymbolRegistry) ->
             .context("ffi module missing otterlang_exports symbol")?;
         let set = exports();
         for function in set.functions.into_iter() {
-            registry.register(FfiFunction {
+            registry.register_plugin(FfiFunction {
                 name: function.name.into_string(),
                 symbol: function.symbol.into_string(),
                 signature: FfiSignature::new(function.params.into_vec(), function.result),
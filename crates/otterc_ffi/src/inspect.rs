@@ -0,0 +1,378 @@
+//! The part of a Rust crate's API that Otter code can use
+//!
+//! Backs `otter ffi inspect`: given the [`CrateSpec`] extracted from rustdoc,
+//! lists the functions the bridge exports, the public structs and methods
+//! with their types as Otter sees them, and warns about every Rust type that
+//! can only cross the bridge as an opaque handle.
+
+use std::fmt;
+
+use super::rust_stubgen::{RustStubGenerator, map_rust_type_to_spec};
+use super::types::{CrateSpec, DependencyConfig, FnSig, PublicItem, RustTypeRef, TypeSpec};
+
+/// A callable with its parameter and return types mapped for Otter
+#[derive(Clone, Debug)]
+pub struct InspectedFunction {
+    pub name: String,
+    pub params: Vec<TypeSpec>,
+    pub result: TypeSpec,
+}
+
+#[derive(Clone, Debug)]
+pub struct InspectedStruct {
+    pub name: String,
+    /// Public fields and their mapped types
+    pub fields: Vec<(String, TypeSpec)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CrateSurface {
+    pub crate_name: String,
+    pub version: Option<String>,
+    /// Functions the generated bridge exports, including `_try`/`_optjson`
+    /// helpers and async `_spawn`/`_await` pairs
+    pub functions: Vec<InspectedFunction>,
+    pub structs: Vec<InspectedStruct>,
+    pub methods: Vec<InspectedFunction>,
+    /// Functions Otter cannot call and types it only sees as opaque handles,
+    /// one message per occurrence
+    pub warnings: Vec<String>,
+    /// Public items with no Otter counterpart (traits, enums, consts, ...)
+    pub unsupported: usize,
+}
+
+impl CrateSurface {
+    pub fn from_spec(spec: &CrateSpec, dependency: &DependencyConfig) -> Self {
+        let generator = RustStubGenerator::new(spec.name.clone(), dependency.clone());
+        let functions = generator
+            .functions_from_crate_spec(spec)
+            .into_iter()
+            .map(|function| InspectedFunction {
+                name: function.name,
+                params: function.params,
+                result: function.result,
+            })
+            .collect();
+
+        let warnings = generator
+            .unmapped_functions(spec)
+            .into_iter()
+            .map(|function| {
+                format!(
+                    "{} is not callable: no Otter equivalent for {}",
+                    function.name,
+                    function.unmapped.join(", ")
+                )
+            })
+            .collect();
+
+        let mut surface = Self {
+            crate_name: spec.name.clone(),
+            version: spec.version.clone(),
+            functions,
+            structs: Vec::new(),
+            methods: Vec::new(),
+            warnings,
+            unsupported: 0,
+        };
+
+        for item in &spec.items {
+            match item {
+                PublicItem::Function { sig, path, .. } => {
+                    if let Some(result) = &sig.return_type {
+                        surface
+                            .warn_opaque(&format!("{} return value", path.display_dot()), result);
+                    }
+                }
+                PublicItem::Method { sig, path, .. }
+                | PublicItem::AssocFunction { sig, path, .. } => {
+                    let name = path.display_dot();
+                    surface.warn_opaque_signature(&name, sig);
+                    surface.methods.push(InspectedFunction {
+                        name,
+                        params: sig.params.iter().map(map_type).collect(),
+                        result: sig.return_type.as_ref().map_or(TypeSpec::Unit, map_type),
+                    });
+                }
+                PublicItem::Struct { path, fields, .. } => {
+                    let name = path.display_dot();
+                    let fields = fields
+                        .iter()
+                        .filter(|field| field.is_public)
+                        .map(|field| {
+                            surface.warn_opaque(&format!("{}.{}", name, field.name), &field.ty);
+                            (field.name.clone(), map_type(&field.ty))
+                        })
+                        .collect();
+                    surface.structs.push(InspectedStruct { name, fields });
+                }
+                PublicItem::Module { .. } => {}
+                _ => surface.unsupported += 1,
+            }
+        }
+        surface
+    }
+
+    fn warn_opaque_signature(&mut self, name: &str, sig: &FnSig) {
+        for (index, param) in sig.params.iter().enumerate() {
+            self.warn_opaque(&format!("{} parameter {}", name, index + 1), param);
+        }
+        if let Some(result) = &sig.return_type {
+            self.warn_opaque(&format!("{} return value", name), result);
+        }
+    }
+
+    fn warn_opaque(&mut self, what: &str, ty: &RustTypeRef) {
+        if matches!(map_type(ty), TypeSpec::Opaque) {
+            self.warnings.push(format!(
+                "{}: `{}` is only visible as an opaque handle",
+                what,
+                rust_type_name(ty)
+            ));
+        }
+    }
+}
+
+fn map_type(ty: &RustTypeRef) -> TypeSpec {
+    map_rust_type_to_spec(ty).unwrap_or(TypeSpec::Opaque)
+}
+
+fn signature(function: &InspectedFunction) -> String {
+    let params: Vec<&str> = function.params.iter().map(TypeSpec::otter_name).collect();
+    format!(
+        "{}({}) -> {}",
+        function.name,
+        params.join(", "),
+        function.result.otter_name()
+    )
+}
+
+impl fmt::Display for CrateSurface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => writeln!(f, "rust:{} {}", self.crate_name, version)?,
+            None => writeln!(f, "rust:{}", self.crate_name)?,
+        }
+
+        writeln!(f, "\nFunctions ({}):", self.functions.len())?;
+        for function in &self.functions {
+            writeln!(f, "  {}", signature(function))?;
+        }
+        writeln!(f, "\nStructs ({}):", self.structs.len())?;
+        for item in &self.structs {
+            writeln!(f, "  {}", item.name)?;
+            for (field, ty) in &item.fields {
+                writeln!(f, "    {}: {}", field, ty.otter_name())?;
+            }
+        }
+        writeln!(f, "\nMethods ({}):", self.methods.len())?;
+        for method in &self.methods {
+            writeln!(f, "  {}", signature(method))?;
+        }
+
+        if !self.warnings.is_empty() {
+            writeln!(f)?;
+            for warning in &self.warnings {
+                writeln!(f, "warning: {}", warning)?;
+            }
+        }
+        if self.unsupported > 0 {
+            writeln!(
+                f,
+                "\n{} other public item(s) (traits, enums, constants, statics, type aliases) are not visible to Otter",
+                self.unsupported
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Rust spelling of `ty`, shortened to the last path segment
+pub(crate) fn rust_type_name(ty: &RustTypeRef) -> String {
+    let wrap = |name: &str, args: &[&RustTypeRef]| {
+        let args: Vec<String> = args.iter().map(|ty| rust_type_name(ty)).collect();
+        format!("{}<{}>", name, args.join(", "))
+    };
+    match ty {
+        RustTypeRef::Unit => "()".to_string(),
+        RustTypeRef::Bool => "bool".to_string(),
+        RustTypeRef::I8 => "i8".to_string(),
+        RustTypeRef::I16 => "i16".to_string(),
+        RustTypeRef::I32 => "i32".to_string(),
+        RustTypeRef::I64 => "i64".to_string(),
+        RustTypeRef::I128 => "i128".to_string(),
+        RustTypeRef::U8 => "u8".to_string(),
+        RustTypeRef::U16 => "u16".to_string(),
+        RustTypeRef::U32 => "u32".to_string(),
+        RustTypeRef::U64 => "u64".to_string(),
+        RustTypeRef::U128 => "u128".to_string(),
+        RustTypeRef::Usize => "usize".to_string(),
+        RustTypeRef::Isize => "isize".to_string(),
+        RustTypeRef::F32 => "f32".to_string(),
+        RustTypeRef::F64 => "f64".to_string(),
+        RustTypeRef::Char => "char".to_string(),
+        RustTypeRef::Str => "str".to_string(),
+        RustTypeRef::String => "String".to_string(),
+        RustTypeRef::Path { path, args } => {
+            let name = path.segments.last().map_or("?", String::as_str);
+            if args.is_empty() {
+                name.to_string()
+            } else {
+                wrap(name, &args.iter().collect::<Vec<_>>())
+            }
+        }
+        RustTypeRef::Ref { mutable, inner, .. } => {
+            let prefix = if *mutable { "&mut " } else { "&" };
+            format!("{}{}", prefix, rust_type_name(inner))
+        }
+        RustTypeRef::Vec { elem } => wrap("Vec", &[elem.as_ref()]),
+        RustTypeRef::Slice { elem } => format!("[{}]", rust_type_name(elem)),
+        RustTypeRef::Array { elem, len } => format!("[{}; {}]", rust_type_name(elem), len),
+        RustTypeRef::Tuple { elems } => {
+            let elems: Vec<String> = elems.iter().map(rust_type_name).collect();
+            format!("({})", elems.join(", "))
+        }
+        RustTypeRef::Option { inner } => wrap("Option", &[inner.as_ref()]),
+        RustTypeRef::Result { ok, err } => wrap("Result", &[ok.as_ref(), err.as_ref()]),
+        RustTypeRef::Future { output } => {
+            format!("impl Future<Output = {}>", rust_type_name(output))
+        }
+        RustTypeRef::HashMap { key, value } => wrap("HashMap", &[key.as_ref(), value.as_ref()]),
+        RustTypeRef::HashSet { elem } => wrap("HashSet", &[elem.as_ref()]),
+        RustTypeRef::Box { inner } => wrap("Box", &[inner.as_ref()]),
+        RustTypeRef::Rc { inner } => wrap("Rc", &[inner.as_ref()]),
+        RustTypeRef::Arc { inner } => wrap("Arc", &[inner.as_ref()]),
+        RustTypeRef::Cow { inner, .. } => wrap("Cow", &[inner.as_ref()]),
+        RustTypeRef::Fn {
+            params,
+            return_type,
+        } => {
+            let params: Vec<String> = params.iter().map(rust_type_name).collect();
+            format!(
+                "fn({}) -> {}",
+                params.join(", "),
+                rust_type_name(return_type)
+            )
+        }
+        RustTypeRef::Generic { name } => name.clone(),
+        RustTypeRef::Opaque => "_".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RustPath, StructField};
+
+    fn path(segments: &[&str]) -> RustPath {
+        RustPath {
+            segments: segments.iter().map(|segment| segment.to_string()).collect(),
+        }
+    }
+
+    fn sig(name: &str, params: Vec<RustTypeRef>, return_type: Option<RustTypeRef>) -> FnSig {
+        FnSig {
+            name: name.to_string(),
+            params,
+            return_type,
+            is_async: false,
+            generics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn surface_maps_types_and_warns_about_opaque_ones() {
+        let spec = CrateSpec {
+            name: "geo".to_string(),
+            version: Some("1.0.0".to_string()),
+            items: vec![
+                PublicItem::Function {
+                    sig: sig(
+                        "distance",
+                        vec![RustTypeRef::F64, RustTypeRef::F64],
+                        Some(RustTypeRef::F64),
+                    ),
+                    path: path(&["geo", "distance"]),
+                    doc: None,
+                },
+                PublicItem::Function {
+                    sig: sig(
+                        "encode",
+                        vec![RustTypeRef::Ref {
+                            mutable: false,
+                            inner: Box::new(RustTypeRef::Str),
+                            lifetime: None,
+                        }],
+                        Some(RustTypeRef::Vec {
+                            elem: Box::new(RustTypeRef::U8),
+                        }),
+                    ),
+                    path: path(&["geo", "encode"]),
+                    doc: None,
+                },
+                PublicItem::Function {
+                    sig: sig(
+                        "centroid",
+                        vec![RustTypeRef::Vec {
+                            elem: Box::new(RustTypeRef::F64),
+                        }],
+                        Some(RustTypeRef::F64),
+                    ),
+                    path: path(&["geo", "centroid"]),
+                    doc: None,
+                },
+                PublicItem::Struct {
+                    name: "Point".to_string(),
+                    path: path(&["geo", "Point"]),
+                    doc: None,
+                    fields: vec![StructField {
+                        name: "x".to_string(),
+                        ty: RustTypeRef::F64,
+                        doc: None,
+                        is_public: true,
+                    }],
+                    is_tuple: false,
+                    generics: Vec::new(),
+                },
+                PublicItem::Trait {
+                    name: "Shape".to_string(),
+                    path: path(&["geo", "Shape"]),
+                    doc: None,
+                    methods: Vec::new(),
+                    associated_types: Vec::new(),
+                    generics: Vec::new(),
+                    is_unsafe: false,
+                },
+            ],
+        };
+        let dependency = DependencyConfig {
+            name: "geo".to_string(),
+            version: Some("1.0.0".to_string()),
+            path: None,
+            features: Vec::new(),
+            default_features: true,
+        };
+
+        let surface = CrateSurface::from_spec(&spec, &dependency);
+        let functions: Vec<String> = surface.functions.iter().map(signature).collect();
+        assert!(functions.contains(&"geo.distance(f64, f64) -> f64".to_string()));
+        assert!(functions.contains(&"geo.encode(str) -> opaque".to_string()));
+        assert_eq!(
+            surface.structs[0].fields,
+            [("x".to_string(), TypeSpec::F64)]
+        );
+        assert!(
+            !functions
+                .iter()
+                .any(|name| name.starts_with("geo.centroid"))
+        );
+        assert_eq!(
+            surface.warnings,
+            [
+                "geo.centroid is not callable: no Otter equivalent for parameter 1 type `Vec<f64>`",
+                "geo.encode return value: `Vec<u8>` is only visible as an opaque handle",
+            ]
+        );
+        assert_eq!(surface.unsupported, 1);
+    }
+}
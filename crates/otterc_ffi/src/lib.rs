@@ -8,6 +8,7 @@ pub mod cargo_bridge;
 pub mod dynamic;
 pub mod dynamic_loader;
 pub mod exports;
+pub mod inspect;
 pub mod metadata;
 pub mod providers;
 pub mod rust_stubgen;
@@ -19,6 +20,7 @@ pub use cargo_bridge::{BridgeArtifacts, CargoBridge};
 pub use dynamic::DynamicLibraryBackend;
 pub use dynamic_loader::{DynamicLibrary, DynamicLibraryLoader};
 pub use exports::{ExportFn, StableExportSet, StableFunction, register_dynamic_exports};
+pub use inspect::CrateSurface;
 pub use metadata::load_bridge_functions;
 pub use providers::{SymbolProvider, bootstrap_stdlib};
 
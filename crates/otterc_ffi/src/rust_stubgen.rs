@@ -1,8 +1,11 @@
 use std::fmt::Write as _;
 
+use otterc_symbol::registry::UnmappedFunction;
+
+use super::inspect::rust_type_name;
 use super::types::{
-    CallTemplate, CrateSpec, DependencyConfig, FunctionSpec, PublicItem, RustTypeRef, StubSource,
-    TypeSpec,
+    CallTemplate, CrateSpec, DependencyConfig, FnSig, FunctionSpec, PublicItem, RustPath,
+    RustTypeRef, StubSource, TypeSpec,
 };
 
 enum ArgContext<'a> {
@@ -39,6 +42,64 @@ impl RustStubGenerator {
         StubSource { manifest, source }
     }
 
+    /// The public functions of `spec` that `functions_from_crate_spec` leaves
+    /// out because a parameter or the return type has no FFI mapping
+    pub fn unmapped_functions(&self, spec: &CrateSpec) -> Vec<UnmappedFunction> {
+        let mut out = Vec::new();
+        for item in &spec.items {
+            let PublicItem::Function { sig, path, .. } = item else {
+                continue;
+            };
+            let mut unmapped: Vec<String> = sig
+                .params
+                .iter()
+                .enumerate()
+                .filter(|(_, param)| map_param_type(param).is_none())
+                .map(|(index, param)| {
+                    format!("parameter {} type `{}`", index + 1, rust_type_name(param))
+                })
+                .collect();
+            if let Some(result) = &sig.return_type
+                && map_rust_type_to_spec(result).is_none()
+            {
+                unmapped.push(format!("return type `{}`", rust_type_name(result)));
+            }
+            if unmapped.is_empty() {
+                continue;
+            }
+
+            let (name, rust_path) = self.export_names(sig, path);
+            let params: Vec<String> = sig.params.iter().map(rust_type_name).collect();
+            let mut rust_signature = format!(
+                "{}fn {}({})",
+                if sig.is_async { "async " } else { "" },
+                rust_path,
+                params.join(", ")
+            );
+            if let Some(result) = &sig.return_type {
+                let _ = write!(rust_signature, " -> {}", rust_type_name(result));
+            }
+            out.push(UnmappedFunction {
+                name,
+                rust_signature,
+                unmapped,
+            });
+        }
+        out
+    }
+
+    /// The Otter export name and Rust call path of a public function
+    fn export_names(&self, sig: &FnSig, path: &RustPath) -> (String, String) {
+        if path.segments.is_empty() {
+            (
+                sig.name.clone(),
+                format!("{}::{}", self.dependency.name, sig.name),
+            )
+        } else {
+            (path.segments.join("."), path.segments.join("::"))
+        }
+    }
+
     /// Convert a CrateSpec's public synchronous functions into bridge FunctionSpec entries.
     pub fn functions_from_crate_spec(&self, spec: &CrateSpec) -> Vec<FunctionSpec> {
         let mut out = Vec::new();
@@ -48,7 +109,7 @@ impl RustStubGenerator {
                 let mut params = Vec::new();
                 let mut skip = false;
                 for p in &sig.params {
-                    match map_rust_type_to_spec(p) {
+                    match map_param_type(p) {
                         Some(ts) => params.push(ts),
                         None => {
                             skip = true;
@@ -67,16 +128,8 @@ impl RustStubGenerator {
                     None => TypeSpec::Unit,
                 };
 
-                let (export_name, rust_path) = if path.segments.is_empty() {
-                    (
-                        sig.name.clone(),
-                        Some(format!("{}::{}", self.dependency.name, sig.name)),
-                    )
-                } else {
-                    let export = path.segments.join(".");
-                    let rust = path.segments.join("::");
-                    (export, Some(rust))
-                };
+                let (export_name, rust_path) = self.export_names(sig, path);
+                let rust_path = Some(rust_path);
 
                 if sig.is_async || matches!(sig.return_type, Some(RustTypeRef::Future { .. })) {
                     let spawn_name = format!("{}.{}_spawn", export_name, sig.name);
@@ -693,7 +746,7 @@ impl RustStubGenerator {
     }
 }
 
-fn map_rust_type_to_spec(ty: &RustTypeRef) -> Option<TypeSpec> {
+pub(crate) fn map_rust_type_to_spec(ty: &RustTypeRef) -> Option<TypeSpec> {
     match ty {
         RustTypeRef::Unit => Some(TypeSpec::Unit),
         RustTypeRef::Bool => Some(TypeSpec::Bool),
@@ -733,6 +786,12 @@ fn map_rust_type_to_spec(ty: &RustTypeRef) -> Option<TypeSpec> {
     }
 }
 
+/// Parameters arrive as plain values, so unlike return values they cannot
+/// fall back to an opaque handle
+fn map_param_type(ty: &RustTypeRef) -> Option<TypeSpec> {
+    map_rust_type_to_spec(ty).filter(|spec| *spec != TypeSpec::Opaque)
+}
+
 fn rust_value_ty(spec: &TypeSpec) -> &'static str {
     match spec {
         TypeSpec::Unit => "()",
@@ -36,7 +36,7 @@ impl FunctionSpec {
 }
 
 /// Supported primitive value categories for the generated stub.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TypeSpec {
     Unit,
     Bool,
@@ -59,6 +59,19 @@ impl TypeSpec {
         }
     }
 
+    /// How the type is named in `bridge.yaml` and shown to Otter users
+    pub fn otter_name(&self) -> &'static str {
+        match self {
+            TypeSpec::Unit => "unit",
+            TypeSpec::Bool => "bool",
+            TypeSpec::I32 => "i32",
+            TypeSpec::I64 => "i64",
+            TypeSpec::F64 => "f64",
+            TypeSpec::Str => "str",
+            TypeSpec::Opaque => "opaque",
+        }
+    }
+
     pub fn default_return(&self) -> &'static str {
         match self {
             TypeSpec::Unit => "()",
@@ -1,6 +1,6 @@
 use otterc_ast::nodes::{
-    BinaryOp, Block, Expr, FStringPart, Function, Literal, Node, Pattern, Program, Statement, Type,
-    UnaryOp,
+    BinaryOp, Block, CfgPredicate, Derive, Expr, FStringPart, Function, GenericBound, Literal,
+    Node, NumberLiteral, Param, Pattern, Program, Statement, Type, UnaryOp,
 };
 
 /// Formats OtterLang code
@@ -52,6 +52,26 @@ impl Formatter {
                     self.format_expr(expr, indent)
                 )
             }
+            Statement::Const {
+                name,
+                ty,
+                expr,
+                public,
+            } => {
+                let pub_str = if *public { "pub " } else { "" };
+                let ty_str = ty
+                    .as_ref()
+                    .map(|ty| format!(": {}", self.format_type(ty)))
+                    .unwrap_or_default();
+                format!(
+                    "{}{}const {}{} = {}\n",
+                    self.indent(indent),
+                    pub_str,
+                    name,
+                    ty_str,
+                    self.format_expr(expr, indent)
+                )
+            }
             Statement::Assignment { name, expr, .. } => {
                 format!(
                     "{}{} = {}\n",
@@ -71,24 +91,67 @@ impl Formatter {
                 var,
                 iterable,
                 body,
-                ..
+                label,
+                is_await,
             } => {
                 format!(
-                    "{}for {} in {}:\n{}",
+                    "{}{}for {}{} in {}:\n{}",
                     self.indent(indent),
+                    Self::format_loop_label(label.as_ref()),
+                    if *is_await { "await " } else { "" },
                     var,
                     self.format_expr(iterable, indent),
                     self.format_block(body, indent + 1)
                 )
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, label } => {
                 format!(
-                    "{}while {}:\n{}",
+                    "{}{}while {}:\n{}",
                     self.indent(indent),
+                    Self::format_loop_label(label.as_ref()),
                     self.format_expr(cond, indent),
                     self.format_block(body, indent + 1)
                 )
             }
+            Statement::With { expr, alias, body } => {
+                let alias_str = alias
+                    .as_ref()
+                    .map(|alias| format!(" as {}", alias))
+                    .unwrap_or_default();
+                format!(
+                    "{}with {}{}:\n{}",
+                    self.indent(indent),
+                    self.format_expr(expr, indent),
+                    alias_str,
+                    self.format_block(body, indent + 1)
+                )
+            }
+            Statement::Init(body) => {
+                format!(
+                    "{}init:\n{}",
+                    self.indent(indent),
+                    self.format_block(body, indent + 1)
+                )
+            }
+            Statement::Assert { cond, message } => {
+                let message_str = message
+                    .as_ref()
+                    .map(|message| format!(", {}", self.format_expr(message, indent)))
+                    .unwrap_or_default();
+                format!(
+                    "{}assert {}{}\n",
+                    self.indent(indent),
+                    self.format_expr(cond, indent),
+                    message_str
+                )
+            }
+            Statement::Defer(expr) => {
+                format!(
+                    "{}defer {}\n",
+                    self.indent(indent),
+                    self.format_expr(expr, indent)
+                )
+            }
             Statement::Return(expr) => {
                 if let Some(expr) = expr {
                     format!(
@@ -100,8 +163,14 @@ impl Formatter {
                     format!("{}return\n", self.indent(indent))
                 }
             }
-            Statement::Break => format!("{}break\n", self.indent(indent)),
-            Statement::Continue => format!("{}continue\n", self.indent(indent)),
+            Statement::Break(label) => match label {
+                Some(label) => format!("{}break {}\n", self.indent(indent), label),
+                None => format!("{}break\n", self.indent(indent)),
+            },
+            Statement::Continue(label) => match label {
+                Some(label) => format!("{}continue {}\n", self.indent(indent), label),
+                None => format!("{}continue\n", self.indent(indent)),
+            },
             Statement::Pass => format!("{}pass\n", self.indent(indent)),
             Statement::Expr(expr) => {
                 format!(
@@ -116,15 +185,14 @@ impl Formatter {
                 methods,
                 public,
                 generics,
+                bounds,
+                derives,
             } => {
                 let pub_str = if *public { "pub " } else { "" };
-                let gen_str = if generics.is_empty() {
-                    String::new()
-                } else {
-                    format!("<{}>", generics.join(", "))
-                };
+                let gen_str = self.format_generics(generics, bounds);
                 let mut result = format!(
-                    "{}{}struct {}{}:\n",
+                    "{}{}{}struct {}{}:\n",
+                    self.format_derives(derives, indent),
                     self.indent(indent),
                     pub_str,
                     name,
@@ -146,17 +214,17 @@ impl Formatter {
             Statement::Enum {
                 name,
                 variants,
+                methods,
                 public,
                 generics,
+                bounds,
+                derives,
             } => {
                 let pub_str = if *public { "pub " } else { "" };
-                let gen_str = if generics.is_empty() {
-                    String::new()
-                } else {
-                    format!("<{}>", generics.join(", "))
-                };
+                let gen_str = self.format_generics(generics, bounds);
                 let mut result = format!(
-                    "{}{}enum {}{}:\n",
+                    "{}{}{}enum {}{}:\n",
+                    self.format_derives(derives, indent),
                     self.indent(indent),
                     pub_str,
                     name,
@@ -170,11 +238,18 @@ impl Formatter {
                             variant.as_ref().name
                         ));
                     } else {
+                        let field_names = &variant.as_ref().field_names;
                         let fields = variant
                             .as_ref()
                             .fields
                             .iter()
-                            .map(|ty| self.format_type(ty))
+                            .enumerate()
+                            .map(|(idx, ty)| match field_names.get(idx) {
+                                Some(field_name) => {
+                                    format!("{}: {}", field_name, self.format_type(ty))
+                                }
+                                None => self.format_type(ty),
+                            })
                             .collect::<Vec<_>>()
                             .join(", ");
                         result.push_str(&format!(
@@ -185,6 +260,9 @@ impl Formatter {
                         ));
                     }
                 }
+                for method in methods {
+                    result.push_str(&self.format_function(method, indent + 1));
+                }
                 result
             }
             Statement::TypeAlias {
@@ -208,6 +286,59 @@ impl Formatter {
                     self.format_type(target)
                 )
             }
+            Statement::Protocol {
+                name,
+                methods,
+                public,
+            } => {
+                let pub_str = if *public { "pub " } else { "" };
+                let mut result = format!("{}{}protocol {}:\n", self.indent(indent), pub_str, name);
+                for method in methods {
+                    let method = method.as_ref();
+                    let mut params = vec!["self".to_string()];
+                    if !method.params.is_empty() {
+                        params.push(self.format_params(&method.params, indent + 1));
+                    }
+                    let ret_str = match &method.ret_ty {
+                        Some(ret_ty) => format!(" -> {}", self.format_type(ret_ty)),
+                        None => String::new(),
+                    };
+                    result.push_str(&format!(
+                        "{}    fn {}({}){}\n",
+                        self.indent(indent),
+                        method.name,
+                        params.join(", "),
+                        ret_str
+                    ));
+                }
+                result
+            }
+            Statement::Use { imports } if imports.iter().any(|i| i.as_ref().is_from()) => {
+                let mut lines = String::new();
+                for import in imports {
+                    let items: Vec<String> = if import.as_ref().glob {
+                        vec!["*".to_string()]
+                    } else {
+                        import
+                            .as_ref()
+                            .items
+                            .iter()
+                            .map(|item| match &item.as_ref().alias {
+                                Some(alias) => format!("{} as {}", item.as_ref().name, alias),
+                                None => item.as_ref().name.clone(),
+                            })
+                            .collect()
+                    };
+                    lines.push_str(&self.format_cfg(import.as_ref().cfg.as_ref(), indent));
+                    lines.push_str(&format!(
+                        "{}from {} import {}\n",
+                        self.indent(indent),
+                        import.as_ref().module,
+                        items.join(", ")
+                    ));
+                }
+                lines
+            }
             Statement::Use { imports } => {
                 let modules: Vec<String> = imports
                     .iter()
@@ -219,7 +350,15 @@ impl Formatter {
                         }
                     })
                     .collect();
-                format!("{}use {}\n", self.indent(indent), modules.join(", "))
+                let cfg = imports
+                    .first()
+                    .and_then(|import| import.as_ref().cfg.as_ref());
+                format!(
+                    "{}{}use {}\n",
+                    self.format_cfg(cfg, indent),
+                    self.indent(indent),
+                    modules.join(", ")
+                )
             }
             Statement::PubUse {
                 module,
@@ -239,11 +378,8 @@ impl Formatter {
         }
     }
 
-    fn format_function(&self, f: &Node<Function>, indent: usize) -> String {
-        let pub_str = if f.as_ref().public { "pub " } else { "" };
-        let params_str = f
-            .as_ref()
-            .params
+    fn format_params(&self, params: &[Node<Param>], indent: usize) -> String {
+        params
             .iter()
             .map(|p| {
                 let base = if let Some(ref ty) = p.as_ref().ty {
@@ -258,14 +394,26 @@ impl Formatter {
                 }
             })
             .collect::<Vec<_>>()
-            .join(", ");
+            .join(", ")
+    }
+
+    fn format_function(&self, f: &Node<Function>, indent: usize) -> String {
+        let pub_str = if f.as_ref().public { "pub " } else { "" };
+        let params_str = self.format_params(&f.as_ref().params, indent);
         let ret_str = if let Some(ref ret_ty) = f.as_ref().ret_ty {
             format!(" -> {}", self.format_type(ret_ty))
         } else {
             String::new()
         };
+        let inline_str = f
+            .as_ref()
+            .inline
+            .map(|hint| format!("{}{}\n", self.indent(indent), hint))
+            .unwrap_or_default();
         format!(
-            "{}{}fn {}({}){}:\n{}",
+            "{}{}{}{}fn {}({}){}:\n{}",
+            self.format_cfg(f.as_ref().cfg.as_ref(), indent),
+            inline_str,
             self.indent(indent),
             pub_str,
             f.as_ref().name,
@@ -275,6 +423,51 @@ impl Formatter {
         )
     }
 
+    fn format_cfg(&self, cfg: Option<&CfgPredicate>, indent: usize) -> String {
+        cfg.map(|cfg| format!("{}@cfg({})\n", self.indent(indent), cfg))
+            .unwrap_or_default()
+    }
+
+    fn format_generics(&self, generics: &[String], bounds: &[GenericBound]) -> String {
+        if generics.is_empty() {
+            return String::new();
+        }
+        let params: Vec<String> = generics
+            .iter()
+            .map(
+                |generic| match bounds.iter().find(|bound| &bound.param == generic) {
+                    Some(bound) => {
+                        let names: Vec<&str> = bound
+                            .bounds
+                            .iter()
+                            .map(|name| name.as_ref().as_str())
+                            .collect();
+                        format!("{}: {}", generic, names.join(" + "))
+                    }
+                    None => generic.clone(),
+                },
+            )
+            .collect();
+        format!("<{}>", params.join(", "))
+    }
+
+    fn format_derives(&self, derives: &[Node<Derive>], indent: usize) -> String {
+        if derives.is_empty() {
+            return String::new();
+        }
+        let names: Vec<&str> = derives
+            .iter()
+            .map(|derive| derive.as_ref().as_str())
+            .collect();
+        format!("{}@derive({})\n", self.indent(indent), names.join(", "))
+    }
+
+    fn format_loop_label(label: Option<&Node<String>>) -> String {
+        label
+            .map(|label| format!("{}: ", label))
+            .unwrap_or_default()
+    }
+
     fn format_block(&self, block: &Node<Block>, indent: usize) -> String {
         let mut result = String::new();
         for stmt in &block.as_ref().statements {
@@ -334,6 +527,13 @@ impl Formatter {
                     self.format_expr(expr, indent)
                 )
             }
+            Expr::Cast { expr, ty } => {
+                format!(
+                    "{} as {}",
+                    self.format_expr(expr, indent),
+                    self.format_type(ty)
+                )
+            }
             Expr::Call { func, args } => {
                 let args_str = args
                     .iter()
@@ -432,10 +632,17 @@ impl Formatter {
             Expr::Match { value, arms } => {
                 let mut result = format!("match {}:\n", self.format_expr(value, indent));
                 for arm in arms {
+                    let guard_str = arm
+                        .as_ref()
+                        .guard
+                        .as_ref()
+                        .map(|guard| format!(" if {}", self.format_expr(guard, indent)))
+                        .unwrap_or_default();
                     result.push_str(&format!(
-                        "{}    case {} => {}\n",
+                        "{}    case {}{} => {}\n",
                         self.indent(indent),
                         self.format_pattern(&arm.as_ref().pattern),
+                        guard_str,
                         self.format_block(&arm.as_ref().body, indent + 1)
                     ));
                 }
@@ -450,7 +657,11 @@ impl Formatter {
                     .join(", ");
                 format!("{}({})", name, fields_str)
             }
-            // Lambda expressions removed - use anonymous fn syntax instead
+            Expr::Lambda { params, body } => format!(
+                "fn({}) {}",
+                self.format_params(params, indent),
+                self.format_expr(body, indent)
+            ),
             Expr::Await(expr) => format!("await {}", self.format_expr(expr, indent)),
             Expr::Spawn(expr) => format!("spawn {}", self.format_expr(expr, indent)),
             Expr::FString { parts } => {
@@ -458,9 +669,12 @@ impl Formatter {
                     .iter()
                     .map(|part| match part.as_ref() {
                         FStringPart::Text(s) => s.clone(),
-                        FStringPart::Expr(e) => {
+                        FStringPart::Expr(e, None) => {
                             format!("{{{}}}", self.format_expr(e, indent))
                         }
+                        FStringPart::Expr(e, Some(spec)) => {
+                            format!("{{{}:{}}}", self.format_expr(e, indent), spec.as_ref())
+                        }
                     })
                     .collect::<Vec<_>>()
                     .join("");
@@ -517,20 +731,68 @@ impl Formatter {
                 };
                 format!("[{}{}]", patterns_str, rest_str)
             }
+            Pattern::Dict { entries } => {
+                let entries_str = entries
+                    .iter()
+                    .map(|(key, p)| {
+                        format!("{}: {}", self.format_literal(key), self.format_pattern(p))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{{{}}}", entries_str)
+            }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let op = if *inclusive { "..=" } else { ".." };
+                format!(
+                    "{}{}{}",
+                    self.format_literal(start),
+                    op,
+                    self.format_literal(end)
+                )
+            }
+            Pattern::Binding { name, pattern } => {
+                format!("{} @ {}", name, self.format_pattern(pattern))
+            }
+            Pattern::Or(alternatives) => alternatives
+                .iter()
+                .map(|p| self.format_pattern(p))
+                .collect::<Vec<_>>()
+                .join(" | "),
         }
     }
 
     fn format_literal(&self, lit: &Node<Literal>) -> String {
         match lit.as_ref() {
+            // Keeps the literal as written, `_` separators included
+            Literal::Number(NumberLiteral {
+                text: Some(text), ..
+            }) => text.clone(),
             Literal::Number(n) => {
-                if !n.is_float_literal && n.value.fract() == 0.0 {
-                    format!("{}", n.value as i64)
-                } else {
+                let digits = if n.is_float_literal || n.value.fract() != 0.0 {
                     n.value.to_string()
+                } else {
+                    let value = n.value as u128;
+                    match n.radix {
+                        16 => format!("0x{value:X}"),
+                        2 => format!("0b{value:b}"),
+                        8 => format!("0o{value:o}"),
+                        _ => format!("{}", n.value as i64),
+                    }
+                };
+                match n.suffix {
+                    Some(suffix) => format!("{digits}{suffix}"),
+                    None => digits,
                 }
             }
             Literal::Bool(b) => b.to_string(),
-            Literal::String(s) => format!("\"{}\"", s),
+            Literal::String(s) => format_string_literal(s),
+            Literal::Bytes(bytes) => format_bytes_literal(bytes),
+            Literal::Checked { tag, value } => format!("{tag}{}", format_string_literal(value)),
+            Literal::Symbol(name) => format!(":{name}"),
             Literal::None => "None".to_string(),
             Literal::Unit => "()".to_string(),
         }
@@ -551,6 +813,11 @@ impl Formatter {
                     format!("{}<{}>", base, args_str)
                 }
             }
+            Type::Union(members) => members
+                .iter()
+                .map(|member| self.format_type(member))
+                .collect::<Vec<_>>()
+                .join(" | "),
         }
     }
 
@@ -571,6 +838,11 @@ impl Formatter {
             BinaryOp::IsNot => "is not",
             BinaryOp::And => "and",
             BinaryOp::Or => "or",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
         }
     }
 
@@ -578,6 +850,7 @@ impl Formatter {
         match op {
             UnaryOp::Not => "not ",
             UnaryOp::Neg => "-",
+            UnaryOp::BitNot => "~",
         }
     }
 
@@ -591,3 +864,54 @@ impl Default for Formatter {
         Self::new()
     }
 }
+
+/// Writes a string literal back out. Strings with backslashes and nothing else
+/// that needs escaping come out raw (`r"..."`), which keeps regexes and Windows
+/// paths readable, and strings with line breaks come out triple-quoted.
+fn format_string_literal(text: &str) -> String {
+    let needs_escape = |c: char| c == '"' || c.is_control();
+    if text.contains('\\') && !text.chars().any(needs_escape) {
+        return format!("r\"{text}\"");
+    }
+    let multiline_safe = |c: char| c == '\n' || c == '\t' || !c.is_control();
+    if text.contains('\n')
+        && !text.contains("\"\"\"")
+        && !text.ends_with('"')
+        && text.chars().all(multiline_safe)
+    {
+        return format!("\"\"\"{}\"\"\"", text.replace('\\', "\\\\"));
+    }
+
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn format_bytes_literal(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 3);
+    out.push_str("b\"");
+    for &byte in bytes {
+        match byte {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\r' => out.push_str("\\r"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\x{byte:02x}")),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -8,9 +8,10 @@ use tempfile::TempDir;
 
 use otterc_ast::nodes::{Program, Statement};
 use otterc_codegen::build_shared_library;
-use otterc_config::{CodegenOptLevel, CodegenOptions};
+use otterc_config::{CodegenOptLevel, CodegenOptions, LtoMode, PgoMode};
 use otterc_metrics::profiler::{FunctionMetrics, GlobalProfiler, HotFunction};
-use otterc_symbol::registry::SymbolRegistry;
+use otterc_symbol::patch::PatchError;
+use otterc_symbol::registry::{FfiSignature, FfiType, SymbolRegistry};
 use otterc_typecheck::TypeChecker;
 
 use super::adaptive::{AdaptiveConcurrencyManager, AdaptiveMemoryManager};
@@ -27,6 +28,28 @@ pub enum FunctionPtr {
     VarArgs(unsafe extern "C" fn(*const u64, usize) -> u64),
 }
 
+impl FunctionPtr {
+    /// Address of the machine code, as tracked by the symbol registry
+    pub fn address(&self) -> usize {
+        match self {
+            FunctionPtr::NoArgs(f) => *f as usize,
+            FunctionPtr::OneArg(f) => *f as usize,
+            FunctionPtr::TwoArgs(f) => *f as usize,
+            FunctionPtr::ThreeArgs(f) => *f as usize,
+            FunctionPtr::VarArgs(f) => *f as usize,
+        }
+    }
+}
+
+/// How long re-optimization waits for calls into the old code to return
+const PATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Signature JIT functions are registered with: every argument and the
+/// result are passed as 64-bit words
+fn jit_signature(arg_count: usize) -> FfiSignature {
+    FfiSignature::new(vec![FfiType::I64; arg_count], FfiType::I64)
+}
+
 /// Compiled function with metadata
 struct CompiledFunction {
     library: Arc<Library>,
@@ -82,11 +105,12 @@ pub struct JitEngine {
     #[expect(dead_code, reason = "Work in progress")]
     memory_manager: AdaptiveMemoryManager,
     concurrency_manager: AdaptiveConcurrencyManager,
-    #[expect(dead_code, reason = "Work in progress")]
     symbol_registry: &'static SymbolRegistry,
     // Runtime state
     compiled_library: Arc<Mutex<Option<Arc<Library>>>>,
     compiled_functions: Arc<Mutex<HashMap<String, CompiledFunction>>>,
+    /// Libraries replaced while calls into them were still running
+    retired_libraries: Vec<Arc<Library>>,
     temp_dir: TempDir,
     program: Option<Program>,
     library_path: Arc<Mutex<Option<std::path::PathBuf>>>,
@@ -114,6 +138,7 @@ impl JitEngine {
             symbol_registry,
             compiled_library: Arc::new(Mutex::new(None)),
             compiled_functions: Arc::new(Mutex::new(HashMap::new())),
+            retired_libraries: Vec::new(),
             temp_dir,
             program: None,
             library_path: Arc::new(Mutex::new(None)),
@@ -140,10 +165,19 @@ impl JitEngine {
             target: None,
             emit_ir: false,
             opt_level: CodegenOptLevel::Default,
-            enable_lto: false,
-            enable_pgo: false,
-            pgo_profile_file: None,
+            passes: None,
+            lto: LtoMode::Off,
+            pgo: PgoMode::Off,
+            sanitizers: Vec::new(),
             inline_threshold: None,
+            strip_asserts: false,
+            preempt_loops: false,
+            source: None,
+            cfg_features: Vec::new(),
+            emit_opt_report: false,
+            profile_functions: false,
+            debug_info: false,
+            emit: Vec::new(),
         };
 
         let mut type_checker = TypeChecker::new().with_registry(SymbolRegistry::global());
@@ -208,6 +242,11 @@ impl JitEngine {
 
                 // Try to load function with different signatures
                 let func_ptr = self.load_function_symbol(&library, func_name, arg_count)?;
+                self.symbol_registry.register_patchable(
+                    func_name.clone(),
+                    jit_signature(arg_count),
+                    func_ptr.address(),
+                );
 
                 functions.insert(
                     func_name.clone(),
@@ -284,8 +323,12 @@ impl JitEngine {
                 .clone()
         };
 
-        // Execute the function
-        let result = compiled_func.execute(args)?;
+        // Execute the function, counted as in flight so re-optimization
+        // waits for it before dropping the old code
+        let call = self.symbol_registry.enter(function_name);
+        let result = compiled_func.execute(args);
+        drop(call);
+        let result = result?;
 
         // Record call for profiling
         let duration = start.elapsed();
@@ -321,10 +364,19 @@ impl JitEngine {
             target: None,
             emit_ir: false,
             opt_level: CodegenOptLevel::Aggressive,
-            enable_lto: true,
-            enable_pgo: false,
-            pgo_profile_file: None,
+            passes: None,
+            lto: LtoMode::Full,
+            pgo: PgoMode::Off,
+            sanitizers: Vec::new(),
             inline_threshold: None,
+            strip_asserts: false,
+            preempt_loops: false,
+            source: None,
+            cfg_features: Vec::new(),
+            emit_opt_report: false,
+            profile_functions: false,
+            debug_info: false,
+            emit: Vec::new(),
         };
 
         let mut type_checker = TypeChecker::new().with_registry(SymbolRegistry::global());
@@ -370,8 +422,8 @@ impl JitEngine {
                 let arg_count = func.as_ref().params.len();
                 if let Ok(func_ptr) = self.load_function_symbol(&library, &hot_func.name, arg_count)
                 {
-                    let mut functions = self.compiled_functions.lock().unwrap();
-                    functions.insert(
+                    let address = func_ptr.address();
+                    let previous = self.compiled_functions.lock().unwrap().insert(
                         hot_func.name.clone(),
                         CompiledFunction {
                             library: library.clone(),
@@ -379,6 +431,23 @@ impl JitEngine {
                             arg_count,
                         },
                     );
+                    match self.symbol_registry.patch(
+                        &hot_func.name,
+                        &jit_signature(arg_count),
+                        address,
+                        PATCH_TIMEOUT,
+                    ) {
+                        Ok(_) => {}
+                        // Calls still running the old code need its library
+                        Err(PatchError::Timeout { .. }) => {
+                            if let Some(previous) = previous {
+                                self.retired_libraries.push(previous.library);
+                            }
+                        }
+                        Err(err) => {
+                            return Err(anyhow!("Failed to patch '{}': {}", hot_func.name, err));
+                        }
+                    }
                 }
             }
         }
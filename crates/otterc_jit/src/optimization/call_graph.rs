@@ -33,7 +33,7 @@ impl CallGraph {
 
     fn extract_callees_from_stmt(&self, stmt: &Statement, callees: &mut Vec<String>) {
         match stmt {
-            Statement::Expr(expr) => {
+            Statement::Expr(expr) | Statement::Defer(expr) => {
                 if let Expr::Call { func, .. } = expr.as_ref()
                     && let Expr::Identifier(name) = func.as_ref().as_ref()
                 {
@@ -54,7 +54,9 @@ impl CallGraph {
                     self.extract_callees_from_block(block.as_ref(), callees);
                 }
             }
-            Statement::For { body, .. } | Statement::While { body, .. } => {
+            Statement::For { body, .. }
+            | Statement::While { body, .. }
+            | Statement::With { body, .. } => {
                 self.extract_callees_from_block(body.as_ref(), callees);
             }
             _ => {}
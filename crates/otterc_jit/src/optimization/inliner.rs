@@ -3,7 +3,8 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 use super::call_graph::CallGraph;
 use otterc_ast::nodes::{
-    Block, Expr, FStringPart, Function, Literal, MatchArm, Node, Pattern, Program, Statement,
+    Block, Expr, FStringPart, Function, InlineHint, Literal, MatchArm, Node, Pattern, Program,
+    Statement,
 };
 
 /// Configuration for the inliner.
@@ -37,6 +38,8 @@ pub struct InlineStats {
     pub skipped_cold: usize,
     pub skipped_recursive: usize,
     pub skipped_complex: usize,
+    /// Calls to `@noinline` functions
+    pub skipped_noinline: usize,
 }
 
 /// Inlines function calls for optimization
@@ -312,7 +315,11 @@ impl Inliner {
                     span,
                 ));
             }
-            Statement::While { mut cond, mut body } => {
+            Statement::While {
+                mut cond,
+                mut body,
+                label,
+            } => {
                 self.inline_expr(
                     &mut cond,
                     ctx,
@@ -331,12 +338,14 @@ impl Inliner {
                     current_hot,
                     current_name,
                 );
-                out.push(Node::new(Statement::While { cond, body }, span));
+                out.push(Node::new(Statement::While { cond, body, label }, span));
             }
             Statement::For {
                 var,
                 mut iterable,
                 mut body,
+                label,
+                is_await,
             } => {
                 self.inline_expr(
                     &mut iterable,
@@ -361,6 +370,8 @@ impl Inliner {
                         var,
                         iterable,
                         body,
+                        label,
+                        is_await,
                     },
                     span,
                 ));
@@ -407,7 +418,7 @@ impl Inliner {
                 self.inline_expr(left, ctx, stack, stats, depth, current_hot, current_name);
                 self.inline_expr(right, ctx, stack, stats, depth, current_hot, current_name);
             }
-            Expr::Unary { expr: inner, .. } => {
+            Expr::Unary { expr: inner, .. } | Expr::Cast { expr: inner, .. } => {
                 self.inline_expr(inner, ctx, stack, stats, depth, current_hot, current_name);
             }
             Expr::If {
@@ -509,12 +520,13 @@ impl Inliner {
             }
             Expr::FString { parts } => {
                 for part in parts {
-                    if let FStringPart::Expr(expr) = part.as_mut() {
+                    if let FStringPart::Expr(expr, _) = part.as_mut() {
                         self.inline_expr(expr, ctx, stack, stats, depth, current_hot, current_name);
                     }
                 }
             }
-            // Lambda expressions removed - use anonymous fn syntax instead
+            // Anonymous fn bodies are compiled as functions of their own, so
+            // calls inside them are left alone
             Expr::Spawn(expr) | Expr::Await(expr) => {
                 self.inline_expr(expr, ctx, stack, stats, depth, current_hot, current_name);
             }
@@ -593,13 +605,25 @@ impl Inliner {
             return None;
         }
 
-        if self.config.inline_hot_only && !current_hot && !ctx.hot_functions.contains(callee_name) {
+        // `@noinline` always wins; `@inline` skips the heat and size limits
+        let hint = callee.as_ref().inline;
+        if hint == Some(InlineHint::Never) {
+            stats.skipped_noinline += 1;
+            return None;
+        }
+        let forced = hint == Some(InlineHint::Always);
+
+        if !forced
+            && self.config.inline_hot_only
+            && !current_hot
+            && !ctx.hot_functions.contains(callee_name)
+        {
             stats.skipped_cold += 1;
             return None;
         }
 
         let size = callee.as_ref().body.as_ref().recursive_count();
-        if size > self.config.max_inline_size {
+        if !forced && size > self.config.max_inline_size {
             stats.skipped_size += 1;
             return None;
         }
@@ -664,6 +688,9 @@ impl Inliner {
                         return true;
                     }
                 }
+                // A deferred expression runs at function exit, which an inlined
+                // body no longer has
+                Statement::Defer(_) => return true,
                 Statement::If {
                     then_block,
                     elif_blocks,
@@ -686,6 +713,7 @@ impl Inliner {
                 }
                 Statement::While { body, .. }
                 | Statement::For { body, .. }
+                | Statement::With { body, .. }
                 | Statement::Block(body) => {
                     if Self::has_internal_return(body) {
                         return true;
@@ -844,14 +872,29 @@ impl InlineBuilder {
                 var,
                 iterable,
                 body,
+                label,
+                is_await,
             } => Statement::For {
                 var: var.map(|var| self.names.rename_local(&var)),
                 iterable: self.rewrite_expr(&iterable),
                 body: self.rewrite_nested_block(&body),
+                label,
+                is_await,
             },
-            Statement::While { cond, body } => Statement::While {
+            Statement::While { cond, body, label } => Statement::While {
                 cond: self.rewrite_expr(&cond),
                 body: self.rewrite_nested_block(&body),
+                label,
+            },
+            Statement::With { expr, alias, body } => Statement::With {
+                expr: self.rewrite_expr(&expr),
+                alias: alias.map(|alias| alias.map(|alias| self.names.rename_local(&alias))),
+                body: self.rewrite_nested_block(&body),
+            },
+            Statement::Defer(expr) => Statement::Defer(self.rewrite_expr(&expr)),
+            Statement::Assert { cond, message } => Statement::Assert {
+                cond: self.rewrite_expr(&cond),
+                message: message.map(|message| self.rewrite_expr(&message)),
             },
             Statement::Block(block) => Statement::Block(self.rewrite_nested_block(&block)),
             // Exception handling (try/except/finally/raise) removed
@@ -879,6 +922,10 @@ impl InlineBuilder {
                 op,
                 expr: Box::new(self.rewrite_expr(&expr)),
             },
+            Expr::Cast { expr, ty } => Expr::Cast {
+                expr: Box::new(self.rewrite_expr(&expr)),
+                ty,
+            },
             Expr::Call { func, args } => Expr::Call {
                 func: Box::new(self.rewrite_expr(&func)),
                 args: args.iter().map(|arg| self.rewrite_expr(arg)).collect(),
@@ -952,13 +999,14 @@ impl InlineBuilder {
                     .into_iter()
                     .map(|part| {
                         part.map(|part| match part {
-                            FStringPart::Expr(expr) => FStringPart::Expr(self.rewrite_expr(&expr)),
+                            FStringPart::Expr(expr, spec) => {
+                                FStringPart::Expr(self.rewrite_expr(&expr), spec.clone())
+                            }
                             FStringPart::Text(text) => FStringPart::Text(text.clone()),
                         })
                     })
                     .collect(),
             },
-            // Lambda expressions removed - use anonymous fn syntax instead
             Expr::Spawn(expr) => Expr::Spawn(Box::new(self.rewrite_expr(&expr))),
             Expr::Await(expr) => Expr::Await(Box::new(self.rewrite_expr(&expr))),
             Expr::Struct { name, fields } => Expr::Struct {
@@ -991,6 +1039,12 @@ impl InlineBuilder {
                     .collect(),
                 rest: rest.as_ref().map(|name| self.names.rename_local(name)),
             },
+            Pattern::Dict { entries } => Pattern::Dict {
+                entries: entries
+                    .into_iter()
+                    .map(|(key, pat)| (key, self.rewrite_pattern(&pat)))
+                    .collect(),
+            },
             Pattern::EnumVariant {
                 enum_name,
                 variant,
@@ -1003,9 +1057,73 @@ impl InlineBuilder {
                     .map(|pat| self.rewrite_pattern(&pat))
                     .collect(),
             },
+            Pattern::Binding { name, pattern } => Pattern::Binding {
+                name: self.names.rename_local(&name),
+                pattern: Box::new(self.rewrite_pattern(&pattern)),
+            },
+            Pattern::Or(alternatives) => {
+                let mut alternatives = alternatives.into_iter();
+                let Some(first) = alternatives.next() else {
+                    return Pattern::Or(Vec::new());
+                };
+                // The remaining alternatives must bind the names chosen for the first
+                let first = self.rewrite_pattern(&first);
+                let rest = alternatives.map(|pat| self.rebind_pattern(&pat));
+                Pattern::Or(std::iter::once(first).chain(rest).collect())
+            }
             _ => pattern.clone(),
         })
     }
+
+    /// Renames bindings to the names already chosen by `rewrite_pattern`
+    fn rebind_pattern(&self, pattern: &Node<Pattern>) -> Node<Pattern> {
+        pattern.clone().map(|pattern| match pattern {
+            Pattern::Identifier(name) => Pattern::Identifier(self.names.resolve_or_clone(&name)),
+            Pattern::Struct { name, fields } => Pattern::Struct {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(field, pat)| (field, pat.map(|inner| self.rebind_pattern(&inner))))
+                    .collect(),
+            },
+            Pattern::Array { patterns, rest } => Pattern::Array {
+                patterns: patterns
+                    .into_iter()
+                    .map(|pat| self.rebind_pattern(&pat))
+                    .collect(),
+                rest: rest.map(|name| self.names.resolve_or_clone(&name)),
+            },
+            Pattern::Dict { entries } => Pattern::Dict {
+                entries: entries
+                    .into_iter()
+                    .map(|(key, pat)| (key, self.rebind_pattern(&pat)))
+                    .collect(),
+            },
+            Pattern::EnumVariant {
+                enum_name,
+                variant,
+                fields,
+            } => Pattern::EnumVariant {
+                enum_name,
+                variant,
+                fields: fields
+                    .into_iter()
+                    .map(|pat| self.rebind_pattern(&pat))
+                    .collect(),
+            },
+            Pattern::Binding { name, pattern } => Pattern::Binding {
+                name: self.names.resolve_or_clone(&name),
+                pattern: Box::new(self.rebind_pattern(&pattern)),
+            },
+            Pattern::Or(alternatives) => Pattern::Or(
+                alternatives
+                    .into_iter()
+                    .map(|pat| self.rebind_pattern(&pat))
+                    .collect(),
+            ),
+            other => other,
+        })
+    }
 }
 
 struct InlineBody {
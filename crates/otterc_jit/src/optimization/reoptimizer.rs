@@ -3,10 +3,10 @@ use std::collections::HashSet;
 use super::call_graph::CallGraph;
 use super::inliner::{InlineConfig, Inliner};
 use otterc_ast::nodes::{
-    BinaryOp, Block, Expr, FStringPart, Function, Literal, Node, NumberLiteral, Program, Statement,
-    UnaryOp,
+    BinaryOp, Block, Expr, FStringPart, Function, Literal, Node, Program, Statement, UnaryOp,
 };
 use otterc_config::CodegenOptLevel;
+use otterc_typecheck::{ConstValue, eval_const_binary, eval_const_unary};
 
 /// Re-optimizes hot functions
 pub struct Reoptimizer {
@@ -23,12 +23,12 @@ impl Reoptimizer {
 
     pub fn with_opt_level(opt_level: CodegenOptLevel) -> Self {
         let inline_config = match opt_level {
-            CodegenOptLevel::None => InlineConfig {
+            CodegenOptLevel::None | CodegenOptLevel::Size => InlineConfig {
                 max_inline_size: 24,
                 max_depth: 1,
                 inline_hot_only: true,
             },
-            CodegenOptLevel::Default => InlineConfig {
+            CodegenOptLevel::Less | CodegenOptLevel::Default => InlineConfig {
                 max_inline_size: 48,
                 max_depth: 2,
                 inline_hot_only: true,
@@ -123,9 +123,11 @@ impl Reoptimizer {
     fn fold_constants_in_statement(&self, stmt: &mut Statement) {
         match stmt {
             Statement::Let { expr, .. }
+            | Statement::Const { expr, .. }
             | Statement::Assignment { expr, .. }
             | Statement::Expr(expr)
-            | Statement::Return(Some(expr)) => {
+            | Statement::Return(Some(expr))
+            | Statement::Defer(expr) => {
                 self.fold_constants_in_expr(expr.as_mut());
             }
             Statement::If {
@@ -143,7 +145,7 @@ impl Reoptimizer {
                     self.fold_constants_in_block(block.as_mut());
                 }
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, .. } => {
                 self.fold_constants_in_expr(cond.as_mut());
                 self.fold_constants_in_block(body.as_mut());
             }
@@ -265,14 +267,16 @@ impl Reoptimizer {
             }
             Expr::FString { parts } => {
                 for part in parts {
-                    if let FStringPart::Expr(expr) = part.as_mut() {
+                    if let FStringPart::Expr(expr, _) = part.as_mut() {
                         self.fold_constants_in_expr(expr.as_mut());
                     }
                 }
                 None
             }
-            // Lambda expressions removed - use anonymous fn syntax instead
-            Expr::Spawn(expr) | Expr::Await(expr) => {
+            Expr::Spawn(expr)
+            | Expr::Await(expr)
+            | Expr::Cast { expr, .. }
+            | Expr::Lambda { body: expr, .. } => {
                 self.fold_constants_in_expr(expr.as_mut().as_mut());
                 None
             }
@@ -287,85 +291,31 @@ impl Reoptimizer {
     }
 
     fn eval_unary(op: UnaryOp, literal: &Node<Literal>) -> Option<Node<Literal>> {
-        let (literal, span) = literal.clone().into_parts();
-        match (op, literal) {
-            (UnaryOp::Not, Literal::Bool(value)) => Some(Node::new(Literal::Bool(!value), span)),
-            (UnaryOp::Neg, Literal::Number(num)) => Some(Node::new(
-                Literal::Number(NumberLiteral::new(-num.value, num.is_float_literal)),
-                span,
-            )),
-            _ => None,
-        }
+        let value = Self::foldable_value(literal.as_ref())?;
+        let result = eval_const_unary(op, value).ok()?;
+        Some(Node::new(result.to_literal(), *literal.span()))
     }
 
+    /// Folds with the `const` evaluator; operations it rejects, such as
+    /// division by zero or overflow, are left for run time
     fn eval_binary(
         op: BinaryOp,
         left: &Node<Literal>,
         right: &Node<Literal>,
     ) -> Option<Node<Literal>> {
         let span = left.span().merge(right.span());
-        match op {
-            BinaryOp::Add => Self::eval_arithmetic(left.as_ref(), right.as_ref(), |a, b| a + b)
-                .map(|lit| Node::new(lit, span)),
-            BinaryOp::Sub => Self::eval_arithmetic(left.as_ref(), right.as_ref(), |a, b| a - b)
-                .map(|lit| Node::new(lit, span)),
-            BinaryOp::Mul => Self::eval_arithmetic(left.as_ref(), right.as_ref(), |a, b| a * b)
-                .map(|lit| Node::new(lit, span)),
-            BinaryOp::Div => {
-                if matches!(right.as_ref(), Literal::Number(n) if n.value == 0.0) {
-                    None
-                } else {
-                    Self::eval_arithmetic(left.as_ref(), right.as_ref(), |a, b| a / b)
-                        .map(|lit| Node::new(lit, span))
-                }
-            }
-            BinaryOp::Mod => Self::eval_arithmetic(left.as_ref(), right.as_ref(), |a, b| a % b)
-                .map(|lit| Node::new(lit, span)),
-            BinaryOp::And => match (left.as_ref(), right.as_ref()) {
-                (Literal::Bool(a), Literal::Bool(b)) => {
-                    Some(Node::new(Literal::Bool(*a && *b), span))
-                }
-                _ => None,
-            },
-            BinaryOp::Or => match (left.as_ref(), right.as_ref()) {
-                (Literal::Bool(a), Literal::Bool(b)) => {
-                    Some(Node::new(Literal::Bool(*a || *b), span))
-                }
-                _ => None,
-            },
-            BinaryOp::Eq => Some(Node::new(Literal::Bool(left == right), span)),
-            BinaryOp::Ne => Some(Node::new(Literal::Bool(left != right), span)),
-            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::LtEq | BinaryOp::GtEq => {
-                if let (Literal::Number(a), Literal::Number(b)) = (left.as_ref(), right.as_ref()) {
-                    let result = match op {
-                        BinaryOp::Lt => a.value < b.value,
-                        BinaryOp::Gt => a.value > b.value,
-                        BinaryOp::LtEq => a.value <= b.value,
-                        BinaryOp::GtEq => a.value >= b.value,
-                        _ => unreachable!(),
-                    };
-                    Some(Node::new(Literal::Bool(result), span))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        }
+        let left = Self::foldable_value(left.as_ref())?;
+        let right = Self::foldable_value(right.as_ref())?;
+        let result = eval_const_binary(op, left, right).ok()?;
+        Some(Node::new(result.to_literal(), span))
     }
 
-    fn eval_arithmetic<F>(left: &Literal, right: &Literal, op: F) -> Option<Literal>
-    where
-        F: Fn(f64, f64) -> f64,
-    {
-        if let (Literal::Number(a), Literal::Number(b)) = (left, right) {
-            let value = op(a.value, b.value);
-            Some(Literal::Number(NumberLiteral::new(
-                value,
-                a.is_float_literal || b.is_float_literal,
-            )))
-        } else {
-            None
+    /// Suffixed numbers keep their sized type, which a folded literal would lose
+    fn foldable_value(literal: &Literal) -> Option<ConstValue> {
+        if matches!(literal, Literal::Number(num) if num.suffix.is_some()) {
+            return None;
         }
+        ConstValue::from_literal(literal)?.ok()
     }
 
     fn simplify_statement(&self, stmt: Statement) -> StatementTransform {
@@ -435,7 +385,7 @@ impl Reoptimizer {
             }
             terminated = matches!(
                 stmt.as_ref(),
-                Statement::Return(_) | Statement::Break | Statement::Continue
+                Statement::Return(_) | Statement::Break(_) | Statement::Continue(_)
             );
             pruned.push(stmt);
         }
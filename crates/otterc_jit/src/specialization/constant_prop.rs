@@ -39,7 +39,9 @@ impl ConstantPropagator {
                     Some(RuntimeConstant::from_f64(n.value))
                 }
             }
-            Literal::String(s) => Some(RuntimeConstant::Str(s.clone())),
+            Literal::String(s) | Literal::Checked { value: s, .. } => {
+                Some(RuntimeConstant::Str(s.clone()))
+            }
             _ => None,
         }
     }
@@ -25,7 +25,7 @@ impl TypeTracker {
             Expr::Literal(lit) => match lit.as_ref() {
                 Literal::Bool(_) => RuntimeType::Bool,
                 Literal::Number(_) => RuntimeType::F64, // Default to float
-                Literal::String(_) => RuntimeType::Str,
+                Literal::String(_) | Literal::Checked { .. } => RuntimeType::Str,
                 _ => RuntimeType::Unknown,
             },
             // Would need symbol table lookup
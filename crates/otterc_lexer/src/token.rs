@@ -34,6 +34,9 @@ pub enum TokenKind {
     Enum,
     And,
     Or,
+    With,
+    Defer,
+    Assert,
 
     // Identifiers
     Identifier(String),
@@ -42,7 +45,19 @@ pub enum TokenKind {
     // Literals
     Number(String),
     StringLiteral(String),
-    FString(String), // Raw f-string content like "π ≈ {result}"
+    /// F-string content like `π ≈ {result}`, with escapes resolved
+    FString {
+        content: String,
+        /// Source offset of each char of `content`, so interpolated
+        /// expressions get spans in the file
+        offsets: Vec<usize>,
+    },
+    BytesLiteral(Vec<u8>),
+    /// A string prefixed with a validator tag, like `sql"SELECT 1"`
+    CheckedString {
+        tag: String,
+        value: String,
+    },
     Bool(bool),
 
     // Structural
@@ -58,6 +73,7 @@ pub enum TokenKind {
     RBracket,
     Comma,
     Dot,
+    At,
 
     // Operators
     Arrow,
@@ -75,6 +91,11 @@ pub enum TokenKind {
     Percent,
     Pipe,
     Amp,
+    Caret,
+    Tilde,
+    /// `<<`. There is no `>>` token so `List<List<int>>` still lexes as two
+    /// `>`; the parser joins adjacent `>` into a right shift.
+    Shl,
     Bang,
 
     // Assignment operators
@@ -122,6 +143,9 @@ impl Hash for TokenKind {
             TokenKind::Enum => 26u16.hash(state),
             TokenKind::And => 27u16.hash(state),
             TokenKind::Or => 28u16.hash(state),
+            TokenKind::With => 29u16.hash(state),
+            TokenKind::Defer => 30u16.hash(state),
+            TokenKind::Assert => 31u16.hash(state),
 
             // Identifiers
             TokenKind::Identifier(name) => {
@@ -142,10 +166,19 @@ impl Hash for TokenKind {
                 201u16.hash(state);
                 value.hash(state);
             }
-            TokenKind::FString(content) => {
+            TokenKind::FString { content, .. } => {
                 202u16.hash(state);
                 content.hash(state);
             }
+            TokenKind::BytesLiteral(bytes) => {
+                204u16.hash(state);
+                bytes.hash(state);
+            }
+            TokenKind::CheckedString { tag, value } => {
+                205u16.hash(state);
+                tag.hash(state);
+                value.hash(state);
+            }
             TokenKind::Bool(value) => {
                 203u16.hash(state);
                 value.hash(state);
@@ -164,6 +197,7 @@ impl Hash for TokenKind {
             TokenKind::RBracket => b']'.hash(state),
             TokenKind::Comma => b','.hash(state),
             TokenKind::Dot => b'.'.hash(state),
+            TokenKind::At => b'@'.hash(state),
 
             // Operators
             TokenKind::Arrow => 400u16.hash(state),
@@ -181,6 +215,9 @@ impl Hash for TokenKind {
             TokenKind::Percent => b'%'.hash(state),
             TokenKind::Pipe => b'|'.hash(state),
             TokenKind::Amp => b'&'.hash(state),
+            TokenKind::Caret => b'^'.hash(state),
+            TokenKind::Tilde => b'~'.hash(state),
+            TokenKind::Shl => 405u16.hash(state),
             TokenKind::Bang => b'!'.hash(state),
 
             // Assignment operators
@@ -232,6 +269,9 @@ impl TokenKind {
             TokenKind::Enum => "enum",
             TokenKind::And => "and",
             TokenKind::Or => "or",
+            TokenKind::With => "with",
+            TokenKind::Defer => "defer",
+            TokenKind::Assert => "assert",
 
             // Identifiers
             TokenKind::Identifier(_) => "identifier",
@@ -241,6 +281,8 @@ impl TokenKind {
             TokenKind::Number(_) => "number",
             TokenKind::StringLiteral(_) => "string",
             TokenKind::FString { .. } => "fstring",
+            TokenKind::BytesLiteral(_) => "bytes",
+            TokenKind::CheckedString { .. } => "checked_string",
             TokenKind::Bool(_) => "bool",
 
             // Structural
@@ -256,6 +298,7 @@ impl TokenKind {
             TokenKind::RBracket => "]",
             TokenKind::Comma => ",",
             TokenKind::Dot => ".",
+            TokenKind::At => "@",
 
             // Operators
             TokenKind::Arrow => "->",
@@ -273,6 +316,9 @@ impl TokenKind {
             TokenKind::Percent => "%",
             TokenKind::Pipe => "|",
             TokenKind::Amp => "&",
+            TokenKind::Caret => "^",
+            TokenKind::Tilde => "~",
+            TokenKind::Shl => "<<",
             TokenKind::Bang => "!",
 
             // Assignment operators
@@ -296,7 +342,11 @@ impl fmt::Debug for TokenKind {
             TokenKind::UnicodeIdentifier(name) => write!(f, "UnicodeIdentifier({name})"),
             TokenKind::Number(number) => write!(f, "Number({number})"),
             TokenKind::StringLiteral(value) => write!(f, "StringLiteral(\"{value}\")"),
-            TokenKind::FString(content) => write!(f, "FString(\"{}\")", content),
+            TokenKind::FString { content, .. } => write!(f, "FString(\"{}\")", content),
+            TokenKind::BytesLiteral(bytes) => write!(f, "BytesLiteral({bytes:?})"),
+            TokenKind::CheckedString { tag, value } => {
+                write!(f, "CheckedString({tag}, \"{value}\")")
+            }
             TokenKind::Bool(value) => write!(f, "Bool({value})"),
             kind => f.write_str(kind.name()),
         }
@@ -362,6 +412,9 @@ impl Token {
                 | TokenKind::Enum
                 | TokenKind::And
                 | TokenKind::Or
+                | TokenKind::With
+                | TokenKind::Defer
+                | TokenKind::Assert
         )
     }
 
@@ -370,7 +423,9 @@ impl Token {
             self.kind,
             TokenKind::Number(_)
                 | TokenKind::StringLiteral(_)
-                | TokenKind::FString(_)
+                | TokenKind::FString { .. }
+                | TokenKind::BytesLiteral(_)
+                | TokenKind::CheckedString { .. }
                 | TokenKind::Bool(_)
                 | TokenKind::None
         )
@@ -403,6 +458,9 @@ impl Token {
                 | TokenKind::Arrow
                 | TokenKind::Pipe
                 | TokenKind::Amp
+                | TokenKind::Caret
+                | TokenKind::Tilde
+                | TokenKind::Shl
                 | TokenKind::Bang
                 | TokenKind::PlusEq
                 | TokenKind::MinusEq
@@ -424,6 +482,7 @@ impl Token {
                 | TokenKind::Colon
                 | TokenKind::Comma
                 | TokenKind::Dot
+                | TokenKind::At
         )
     }
 }
@@ -25,6 +25,21 @@ pub enum LexerError {
         column: usize,
         span: Span,
     },
+    #[error("invalid escape `{escape}` in bytes literal (line {line}, column {column})")]
+    InvalidEscape {
+        escape: String,
+        line: usize,
+        column: usize,
+        span: Span,
+    },
+    #[error("invalid number literal `{literal}`: {reason} (line {line}, column {column})")]
+    InvalidNumber {
+        literal: String,
+        reason: String,
+        line: usize,
+        column: usize,
+        span: Span,
+    },
     #[error("unexpected character `{ch}` (line {line}, column {column})")]
     UnexpectedCharacter {
         ch: char,
@@ -68,6 +83,27 @@ impl LexerError {
             )
             .with_suggestion("Add a closing quote (\") to terminate the string")
             .with_help("String literals must be enclosed in double quotes."),
+            LexerError::InvalidEscape { span, .. } => Diagnostic::new(
+                DiagnosticSeverity::Error,
+                source_id,
+                *span,
+                self.to_string(),
+            )
+            .with_suggestion("Use \\xNN to write an arbitrary byte")
+            .with_help(
+                "Bytes literals accept \\n, \\t, \\r, \\0, \\\\, \\\", \\' and \\xNN escapes.",
+            ),
+            LexerError::InvalidNumber { span, .. } => Diagnostic::new(
+                DiagnosticSeverity::Error,
+                source_id,
+                *span,
+                self.to_string(),
+            )
+            .with_help(
+                "Numbers are decimal, or hex (0x), binary (0b) or octal (0o) integers, \
+                 optionally followed by a type suffix: i8, i16, i32, i64, u8, u16, u32, u64, \
+                 f32 or f64.",
+            ),
             LexerError::UnexpectedCharacter { span, ch, .. } => {
                 let mut diag = Diagnostic::new(
                     DiagnosticSeverity::Error,
@@ -83,12 +119,6 @@ impl LexerError {
                             "Did you mean a single quote (') or double quote (\")?",
                         );
                     }
-                    '~' => diag = diag.with_suggestion("Did you mean tilde (~) or negation (not)?"),
-                    '@' => {
-                        diag = diag.with_suggestion(
-                            "Did you mean the at symbol (@) or member access (.)?",
-                        );
-                    }
                     _ => {
                         if ch.is_ascii_punctuation() {
                             diag = diag.with_suggestion("Check for typos or invalid characters");
@@ -104,6 +134,11 @@ impl LexerError {
 
 pub type LexResult<T> = Result<T, Vec<LexerError>>;
 
+/// Type suffixes a number literal may end with
+const NUMBER_SUFFIXES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64",
+];
+
 // Optimized lexer state machine
 struct LexerState {
     tokens: Vec<Token>,
@@ -211,6 +246,11 @@ pub fn tokenize(source: &str) -> LexResult<Vec<Token>> {
     let estimated_tokens = source.len() / 4; // Rough estimate
     state.tokens.reserve(estimated_tokens);
 
+    // `#!/usr/bin/env otter` lets a script be executed directly
+    if source.starts_with("#!") {
+        state.skip_to_end_of_line();
+    }
+
     while !state.is_at_end() {
         state.process_line();
     }
@@ -402,6 +442,10 @@ impl LexerState {
                 self.emit_token(TokenKind::Colon, self.offset, 1);
                 self.advance(1);
             }
+            b'@' => {
+                self.emit_token(TokenKind::At, self.offset, 1);
+                self.advance(1);
+            }
             b'+' => {
                 if self.peek_char(1) == Some(b'=') {
                     self.emit_token(TokenKind::PlusEq, self.offset, 2);
@@ -455,6 +499,14 @@ impl LexerState {
                 self.emit_token(TokenKind::Amp, self.offset, 1);
                 self.advance(1);
             }
+            b'^' => {
+                self.emit_token(TokenKind::Caret, self.offset, 1);
+                self.advance(1);
+            }
+            b'~' => {
+                self.emit_token(TokenKind::Tilde, self.offset, 1);
+                self.advance(1);
+            }
             b'!' => {
                 if self.peek_char(1) == Some(b'=') {
                     self.emit_token(TokenKind::Neq, self.offset, 2);
@@ -473,15 +525,20 @@ impl LexerState {
                     self.advance(1);
                 }
             }
-            b'<' => {
-                if self.peek_char(1) == Some(b'=') {
+            b'<' => match self.peek_char(1) {
+                Some(b'=') => {
                     self.emit_token(TokenKind::LtEq, self.offset, 2);
                     self.advance(2);
-                } else {
+                }
+                Some(b'<') => {
+                    self.emit_token(TokenKind::Shl, self.offset, 2);
+                    self.advance(2);
+                }
+                _ => {
                     self.emit_token(TokenKind::Lt, self.offset, 1);
                     self.advance(1);
                 }
-            }
+            },
             b'>' => {
                 if self.peek_char(1) == Some(b'=') {
                     self.emit_token(TokenKind::GtEq, self.offset, 2);
@@ -493,15 +550,26 @@ impl LexerState {
             }
             b'"' => {
                 // Check for triple-quoted string (multi-line)
-                if self.peek_char(1) == Some(b'"') && self.peek_char(2) == Some(b'"') {
-                    self.tokenize_multiline_string();
+                if self.starts_triple_quote(0) {
+                    self.tokenize_multiline_string(None);
                 } else {
                     self.tokenize_string();
                 }
             }
+            b'r' if self.starts_triple_quote(1) => {
+                self.tokenize_multiline_string(Some(b'r'));
+            }
+            b'r' if self.peek_char(1) == Some(b'"') => {
+                self.tokenize_raw_string();
+            }
+            b'b' if self.peek_char(1) == Some(b'"') => {
+                self.tokenize_bytes();
+            }
             b'f' => {
                 // Check for f-string before treating as regular identifier
-                if self.peek_char(1) == Some(b'"') {
+                if self.starts_triple_quote(1) {
+                    self.tokenize_multiline_string(Some(b'f'));
+                } else if self.peek_char(1) == Some(b'"') {
                     self.tokenize_fstring();
                 } else {
                     self.tokenize_identifier_or_keyword();
@@ -588,60 +656,183 @@ impl LexerState {
         });
     }
 
-    fn tokenize_multiline_string(&mut self) {
+    fn starts_triple_quote(&self, ahead: usize) -> bool {
+        (ahead..ahead + 3).all(|i| self.peek_char(i) == Some(b'"'))
+    }
+
+    /// Lexes a `"""` string, optionally prefixed with `r` (raw) or `f`
+    /// (interpolated). Line breaks inside are kept as `\n` and never produce
+    /// NEWLINE, INDENT or DEDENT tokens. A backslash at the end of a line joins
+    /// it with the next one.
+    fn tokenize_multiline_string(&mut self, prefix: Option<u8>) {
         let start = self.offset;
-        self.advance(3); // Skip opening """
+        let (line, column) = (self.line, self.column);
+        let raw = prefix == Some(b'r');
+        let fstring = prefix == Some(b'f');
+        self.advance(3 + usize::from(prefix.is_some()));
 
-        let mut result = String::new();
+        let mut result = Vec::new();
+        // Source offset of each byte of `result`
+        let mut offsets = Vec::new();
 
         while let Some(ch) = self.current_char() {
             if self.current_newline_len().is_some() {
-                // Actual newline in multi-line string
-                result.push('\n');
+                // CRLF is normalized to a single \n
+                result.push(b'\n');
+                offsets.push(self.offset);
                 self.advance(1);
                 continue;
             }
 
             match ch {
-                b'"' => {
-                    // Check if this is the closing """
-                    if self.peek_char(1) == Some(b'"') && self.peek_char(2) == Some(b'"') {
-                        let span = Span::new(start, self.offset + 3);
-                        self.tokens
-                            .push(Token::new(TokenKind::StringLiteral(result), span));
-                        self.advance(3); // Skip closing """
-                        return;
+                b'"' if self.starts_triple_quote(0) => {
+                    let kind = if fstring {
+                        fstring_token(&result, &offsets)
                     } else {
-                        // Just a regular " in the string
-                        result.push('"');
-                        self.advance(1);
-                    }
+                        TokenKind::StringLiteral(String::from_utf8_lossy(&result).into_owned())
+                    };
+                    let span = Span::new(start, self.offset + 3);
+                    self.tokens.push(Token::new(kind, span));
+                    self.advance(3); // Skip closing """
+                    return;
                 }
-                b'\\' => {
-                    // Escape sequence (same as regular strings)
+                b'\\' if !raw => {
+                    let escape_start = self.offset;
                     self.advance(1);
+                    if self.current_newline_len().is_some() {
+                        // Line continuation
+                        self.advance(1);
+                        continue;
+                    }
                     if let Some(escaped) = self.current_char() {
-                        let escaped_char = match escaped {
-                            b'n' => '\n',
-                            b't' => '\t',
-                            b'r' => '\r',
-                            b'\\' => '\\',
-                            b'"' => '"',
-                            b'\'' => '\'',
-                            _ => escaped as char, // Unknown escape, keep as-is
+                        let escaped_byte = match escaped {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            b'r' => b'\r',
+                            // \\, \", \', \{, \} and unknown escapes keep the character
+                            _ => escaped,
                         };
-                        result.push(escaped_char);
+                        result.push(escaped_byte);
+                        offsets.push(escape_start);
                         self.advance(1);
                     }
                 }
                 _ => {
-                    result.push(ch as char);
+                    result.push(ch);
+                    offsets.push(self.offset);
+                    self.advance(1);
+                }
+            }
+        }
+
+        // Unterminated multi-line string at EOF: point at the opening quotes
+        let span = self.create_span(start, 3 + usize::from(prefix.is_some()));
+        self.emit_error(LexerError::UnterminatedString { line, column, span });
+    }
+
+    /// Lexes `r"..."`. Backslashes are kept as written, so a raw string cannot
+    /// contain a double quote.
+    fn tokenize_raw_string(&mut self) {
+        let start = self.offset;
+        self.advance(2); // Skip r"
+
+        let content_start = self.offset;
+        while let Some(ch) = self.current_char() {
+            if self.current_newline_len().is_some() {
+                break;
+            }
+            if ch == b'"' {
+                let result =
+                    String::from_utf8_lossy(&self.source[content_start..self.offset]).into_owned();
+                let span = Span::new(start, self.offset + 1);
+                self.tokens
+                    .push(Token::new(TokenKind::StringLiteral(result), span));
+                self.advance(1);
+                return;
+            }
+            self.advance(1);
+        }
+
+        let span = self.create_span(start, self.offset - start);
+        self.emit_error(LexerError::UnterminatedString {
+            line: self.line,
+            column: self.column,
+            span,
+        });
+    }
+
+    /// Lexes `b"..."` into raw bytes. Supports the regular escapes plus `\0`
+    /// and `\xNN`; other characters are taken as their UTF-8 bytes.
+    fn tokenize_bytes(&mut self) {
+        let start = self.offset;
+        self.advance(2); // Skip b"
+
+        let mut result = Vec::new();
+
+        while let Some(ch) = self.current_char() {
+            if self.current_newline_len().is_some() {
+                break;
+            }
+
+            match ch {
+                b'"' => {
+                    let span = Span::new(start, self.offset + 1);
+                    self.tokens
+                        .push(Token::new(TokenKind::BytesLiteral(result), span));
+                    self.advance(1);
+                    return;
+                }
+                b'\\' => {
+                    let escape_start = self.offset;
+                    self.advance(1);
+                    let Some(escaped) = self.current_char() else {
+                        break;
+                    };
+                    let byte = match escaped {
+                        b'n' => Some(b'\n'),
+                        b't' => Some(b'\t'),
+                        b'r' => Some(b'\r'),
+                        b'0' => Some(0),
+                        b'\\' | b'"' | b'\'' => Some(escaped),
+                        b'x' => {
+                            let hex = self
+                                .source
+                                .get(self.offset + 1..self.offset + 3)
+                                .and_then(|digits| std::str::from_utf8(digits).ok())
+                                .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+                            if hex.is_some() {
+                                self.advance(2);
+                            }
+                            hex
+                        }
+                        _ => None,
+                    };
+                    match byte {
+                        Some(byte) => result.push(byte),
+                        None => {
+                            let len = self.offset + 1 - escape_start;
+                            let escape = String::from_utf8_lossy(
+                                &self.source[escape_start..escape_start + len],
+                            )
+                            .into_owned();
+                            let span = self.create_span(escape_start, len);
+                            self.emit_error(LexerError::InvalidEscape {
+                                escape,
+                                line: self.line,
+                                column: self.column,
+                                span,
+                            });
+                        }
+                    }
+                    self.advance(1);
+                }
+                _ => {
+                    result.push(ch);
                     self.advance(1);
                 }
             }
         }
 
-        // Unterminated multi-line string at EOF
         let span = self.create_span(start, self.offset - start);
         self.emit_error(LexerError::UnterminatedString {
             line: self.line,
@@ -654,7 +845,9 @@ impl LexerState {
         let start = self.offset;
         self.advance(2); // Skip f"
 
-        let mut result = String::new();
+        let mut result = Vec::new();
+        // Source offset of each byte of `result`
+        let mut offsets = Vec::new();
 
         while let Some(ch) = self.current_char() {
             if self.current_newline_len().is_some() {
@@ -670,32 +863,31 @@ impl LexerState {
             match ch {
                 b'"' => {
                     let span = Span::new(start, self.offset + 1);
-                    self.tokens
-                        .push(Token::new(TokenKind::FString(result), span));
+                    let kind = fstring_token(&result, &offsets);
+                    self.tokens.push(Token::new(kind, span));
                     self.advance(1);
                     return;
                 }
                 b'\\' => {
                     // Escape sequence
+                    let escape_start = self.offset;
                     self.advance(1);
                     if let Some(escaped) = self.current_char() {
-                        let escaped_char = match escaped {
-                            b'n' => '\n',
-                            b't' => '\t',
-                            b'r' => '\r',
-                            b'\\' => '\\',
-                            b'"' => '"',
-                            b'\'' => '\'',
-                            b'{' => '{',
-                            b'}' => '}',
-                            _ => escaped as char, // Unknown escape, keep as-is
+                        let escaped_byte = match escaped {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            b'r' => b'\r',
+                            // \\, \", \', \{, \} and unknown escapes keep the character
+                            _ => escaped,
                         };
-                        result.push(escaped_char);
+                        result.push(escaped_byte);
+                        offsets.push(escape_start);
                         self.advance(1);
                     }
                 }
                 _ => {
-                    result.push(ch as char);
+                    result.push(ch);
+                    offsets.push(self.offset);
                     self.advance(1);
                 }
             }
@@ -712,38 +904,126 @@ impl LexerState {
 
     fn tokenize_number(&mut self) {
         let start = self.offset;
+        let (line, column) = (self.line, self.column);
+
+        let radix = match (self.current_char(), self.peek_char(1)) {
+            (Some(b'0'), Some(b'x' | b'X')) => 16,
+            (Some(b'0'), Some(b'b' | b'B')) => 2,
+            (Some(b'0'), Some(b'o' | b'O')) => 8,
+            _ => 10,
+        };
+        let mut is_float = false;
+        let mut error = None;
+
+        if radix == 10 {
+            self.skip_digits(|ch| ch.is_ascii_digit());
+
+            // Parse decimal part
+            if let Some(b'.') = self.current_char()
+                && let Some(next) = self.peek_char(1)
+                && next.is_ascii_digit()
+            {
+                self.advance(1); // Skip decimal point
+                self.skip_digits(|ch| ch.is_ascii_digit());
+                is_float = true;
+            }
 
-        // Parse integer part
+            // Exponent, e.g. `2.0e-3`
+            if let Some(b'e' | b'E') = self.current_char() {
+                let digit_at = match self.peek_char(1) {
+                    Some(b'+' | b'-') => 2,
+                    _ => 1,
+                };
+                if self
+                    .peek_char(digit_at)
+                    .is_some_and(|ch| ch.is_ascii_digit())
+                {
+                    self.advance(digit_at);
+                    self.skip_digits(|ch| ch.is_ascii_digit());
+                    is_float = true;
+                }
+            }
+        } else {
+            self.advance(2); // Skip the radix prefix
+            let digits_start = self.offset;
+            // Take every digit so `0b102` is reported rather than split
+            if radix == 16 {
+                self.skip_digits(|ch| ch.is_ascii_hexdigit());
+            } else {
+                self.skip_digits(|ch| ch.is_ascii_digit());
+            }
+            let digits = &self.source[digits_start..self.offset];
+            if let Some(bad) = digits
+                .iter()
+                .find(|ch| **ch != b'_' && !(**ch as char).is_digit(radix))
+            {
+                error = Some(format!("`{}` is not a base-{radix} digit", *bad as char));
+            } else if digits.iter().all(|ch| *ch == b'_') {
+                error = Some("missing digits after the base prefix".to_string());
+            }
+        }
+
+        // Type suffix
+        let suffix_start = self.offset;
         while let Some(ch) = self.current_char() {
-            if ch.is_ascii_digit() || ch == b'_' {
+            if ch.is_ascii_alphanumeric() || ch == b'_' {
                 self.advance(1);
             } else {
                 break;
             }
         }
+        let suffix = String::from_utf8_lossy(&self.source[suffix_start..self.offset]);
+        if error.is_none() && !suffix.is_empty() {
+            error = if !NUMBER_SUFFIXES.contains(&suffix.as_ref()) {
+                Some(format!("unknown suffix `{suffix}`"))
+            } else if suffix.starts_with('f') && radix != 10 {
+                Some(format!("a base-{radix} literal cannot have a float suffix"))
+            } else if is_float && !suffix.starts_with('f') {
+                Some(format!(
+                    "a float literal cannot have the integer suffix `{suffix}`"
+                ))
+            } else {
+                None
+            };
+        }
 
-        // Parse decimal part
-        if let Some(b'.') = self.current_char()
-            && let Some(next) = self.peek_char(1)
-            && next.is_ascii_digit()
-        {
-            self.advance(1); // Skip decimal point
-
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() || ch == b'_' {
-                    self.advance(1);
-                } else {
-                    break;
-                }
+        // u64 is the widest integer type, so nothing larger can be represented
+        if error.is_none() && !is_float && !suffix.starts_with('f') {
+            let digits_start = if radix == 10 { start } else { start + 2 };
+            let digits: String = self.source[digits_start..suffix_start]
+                .iter()
+                .filter(|ch| **ch != b'_')
+                .map(|ch| *ch as char)
+                .collect();
+            if u64::from_str_radix(&digits, radix).is_err() {
+                error = Some("integer literal out of range for u64".to_string());
             }
         }
 
-        let value = unsafe { std::str::from_utf8_unchecked(&self.source[start..self.offset]) };
-        self.emit_token(
-            TokenKind::Number(value.to_string()),
-            start,
-            self.offset - start,
-        );
+        let value = String::from_utf8_lossy(&self.source[start..self.offset]).into_owned();
+        if let Some(reason) = error {
+            let span = self.create_span(start, self.offset - start);
+            self.emit_error(LexerError::InvalidNumber {
+                literal: value,
+                reason,
+                line,
+                column,
+                span,
+            });
+            return;
+        }
+        self.emit_token(TokenKind::Number(value), start, self.offset - start);
+    }
+
+    /// Skips digits accepted by `is_digit` and `_` separators
+    fn skip_digits(&mut self, is_digit: impl Fn(u8) -> bool) {
+        while let Some(ch) = self.current_char() {
+            if is_digit(ch) || ch == b'_' {
+                self.advance(1);
+            } else {
+                break;
+            }
+        }
     }
 
     fn tokenize_identifier_or_keyword(&mut self) {
@@ -788,12 +1068,41 @@ impl LexerState {
             "enum" => TokenKind::Enum,
             "and" => TokenKind::And,
             "or" => TokenKind::Or,
+            "with" => TokenKind::With,
+            "defer" => TokenKind::Defer,
+            "assert" => TokenKind::Assert,
             _ => TokenKind::Identifier(value.to_string()),
         };
 
+        if let TokenKind::Identifier(tag) = &kind
+            && self.current_char() == Some(b'"')
+        {
+            self.tokenize_checked_string(tag.clone(), start);
+            return;
+        }
+
         self.emit_token(kind, start, self.offset - start);
     }
 
+    /// Lexes a string prefixed with a tag, like `sql"SELECT 1"`. The type
+    /// checker hands its value to the validator registered for the tag.
+    fn tokenize_checked_string(&mut self, tag: String, start: usize) {
+        if self.starts_triple_quote(0) {
+            self.tokenize_multiline_string(None);
+        } else {
+            self.tokenize_string();
+        }
+        // An unterminated string has already been reported and left no token
+        if let Some(token) = self.tokens.last_mut()
+            && token.span().start() == start + tag.len()
+            && let TokenKind::StringLiteral(value) = token.kind_mut()
+        {
+            let value = std::mem::take(value);
+            let span = Span::new(start, token.span().end());
+            *token = Token::new(TokenKind::CheckedString { tag, value }, span);
+        }
+    }
+
     fn tokenize_unicode_identifier(&mut self) {
         let start = self.offset;
 
@@ -844,6 +1153,19 @@ impl LexerState {
     }
 }
 
+/// An f-string token for the bytes of its content, `offsets` giving the
+/// source offset of each byte
+fn fstring_token(content: &[u8], offsets: &[usize]) -> TokenKind {
+    let content = String::from_utf8_lossy(content).into_owned();
+    // Replaced invalid UTF-8 leaves no offsets to map
+    let offsets = if content.len() == offsets.len() {
+        content.char_indices().map(|(i, _)| offsets[i]).collect()
+    } else {
+        Vec::new()
+    };
+    TokenKind::FString { content, offsets }
+}
+
 pub fn tokenize_legacy(source: &str) -> LexResult<Vec<Token>> {
     tokenize(source)
 }
@@ -861,6 +1183,16 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn leading_shebang_line_is_skipped() {
+        let source = "fn main():\n    pass\n";
+        let script = format!("#!/usr/bin/env otter\n{source}");
+
+        let mut expected = token_kinds(source);
+        expected.insert(0, TokenKind::Newline);
+        assert_eq!(token_kinds(&script), expected);
+    }
+
     #[test]
     fn crlf_and_lf_inputs_produce_same_token_stream() {
         let lf_source = "use otter:io\nfn main():\n    io.println(\"hi\")\n";
@@ -885,4 +1217,182 @@ mod tests {
 
         assert_eq!(newline_span, 2);
     }
+
+    #[test]
+    fn lexes_bitwise_operators() {
+        let kinds = token_kinds("a & b | c ^ ~d << 2 >> 1 <= e");
+        assert_eq!(
+            &kinds[..14],
+            &[
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::Amp,
+                TokenKind::Identifier("b".to_string()),
+                TokenKind::Pipe,
+                TokenKind::Identifier("c".to_string()),
+                TokenKind::Caret,
+                TokenKind::Tilde,
+                TokenKind::Identifier("d".to_string()),
+                TokenKind::Shl,
+                TokenKind::Number("2".to_string()),
+                TokenKind::Gt,
+                TokenKind::Gt,
+                TokenKind::Number("1".to_string()),
+                TokenKind::LtEq,
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_strings_keep_backslashes() {
+        let kinds = token_kinds(r#"r"C:\temp\new" r"\d+\.\d*""#);
+        assert_eq!(
+            &kinds[..2],
+            &[
+                TokenKind::StringLiteral(r"C:\temp\new".to_string()),
+                TokenKind::StringLiteral(r"\d+\.\d*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn bytes_literals_decode_escapes() {
+        let kinds = token_kinds(r#"b"GET\r\n\x00\xff""#);
+        assert_eq!(
+            kinds[0],
+            TokenKind::BytesLiteral(b"GET\r\n\x00\xff".to_vec())
+        );
+    }
+
+    #[test]
+    fn bytes_literals_reject_unknown_escapes() {
+        let errors = tokenize(r#"b"\q""#).expect_err("lexing should fail");
+        assert!(matches!(
+            &errors[0],
+            LexerError::InvalidEscape { escape, .. } if escape == "\\q"
+        ));
+    }
+
+    #[test]
+    fn identifiers_starting_with_r_and_b_are_unchanged() {
+        let kinds = token_kinds("result = buffer");
+        assert_eq!(kinds[0], TokenKind::Identifier("result".to_string()));
+        assert_eq!(kinds[2], TokenKind::Identifier("buffer".to_string()));
+    }
+
+    #[test]
+    fn tagged_strings_become_checked_strings() {
+        let tokens = tokenize("sql\"SELECT 1\" sql \"x\"").expect("lexing should succeed");
+        assert_eq!(
+            tokens[0].kind(),
+            &TokenKind::CheckedString {
+                tag: "sql".to_string(),
+                value: "SELECT 1".to_string(),
+            }
+        );
+        assert_eq!(tokens[0].span(), Span::new(0, 13));
+        // With a space in between the tag is an ordinary identifier
+        assert_eq!(tokens[1].kind(), &TokenKind::Identifier("sql".to_string()));
+    }
+
+    #[test]
+    fn triple_quoted_strings_span_lines_without_layout_tokens() {
+        let source =
+            "fn main():\n    let text = \"\"\"first\n  second\n\nthird\"\"\"\n    print(text)\n";
+        let kinds = token_kinds(source);
+        assert!(kinds.contains(&TokenKind::StringLiteral(
+            "first\n  second\n\nthird".to_string()
+        )));
+        let indents = kinds
+            .iter()
+            .filter(|kind| matches!(kind, TokenKind::Indent))
+            .count();
+        assert_eq!(indents, 1);
+
+        let crlf = token_kinds(&source.replace('\n', "\r\n"));
+        assert_eq!(crlf, kinds);
+    }
+
+    #[test]
+    fn triple_quoted_strings_support_prefixes_and_continuations() {
+        let kinds =
+            token_kinds("r\"\"\"C:\\temp\n\\d\"\"\" f\"\"\"{a}\n{b}\"\"\" \"\"\"one \\\ntwo\"\"\"");
+        assert_eq!(
+            &kinds[..3],
+            &[
+                TokenKind::StringLiteral("C:\\temp\n\\d".to_string()),
+                TokenKind::FString {
+                    content: "{a}\n{b}".to_string(),
+                    offsets: (22..29).collect(),
+                },
+                TokenKind::StringLiteral("one two".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_triple_quoted_string_points_at_opening_quotes() {
+        let errors = tokenize("let a = 1\nlet s = \"\"\"open\nstill open\n")
+            .expect_err("lexing should fail");
+        assert!(matches!(
+            errors[0],
+            LexerError::UnterminatedString {
+                line: 2,
+                column: 9,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn numbers_take_radix_prefixes_and_suffixes() {
+        let kinds = token_kinds("0xFF 0b1010_1010 0o777 1u8 3.0f32 0x1Fi64 2.0e-3 1e9");
+        let numbers: Vec<&str> = kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                TokenKind::Number(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                "0xFF",
+                "0b1010_1010",
+                "0o777",
+                "1u8",
+                "3.0f32",
+                "0x1Fi64",
+                "2.0e-3",
+                "1e9"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        for (source, reason) in [
+            ("0b102", "`2` is not a base-2 digit"),
+            ("0x", "missing digits after the base prefix"),
+            ("1u7", "unknown suffix `u7`"),
+            (
+                "2.5u8",
+                "a float literal cannot have the integer suffix `u8`",
+            ),
+            ("0o7f32", "a base-8 literal cannot have a float suffix"),
+            (
+                "18_446_744_073_709_551_616",
+                "integer literal out of range for u64",
+            ),
+            (
+                "0x1_0000_0000_0000_0000",
+                "integer literal out of range for u64",
+            ),
+        ] {
+            let errors = tokenize(source).expect_err("lexing should fail");
+            assert!(
+                matches!(&errors[0], LexerError::InvalidNumber { reason: found, .. } if found == reason),
+                "{source}: {errors:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,461 @@
+//! Compile-time file embedding
+//!
+//! `include_str("template.html")` and `include_bytes("logo.png")` are replaced
+//! by the contents of the named file before type checking. Paths are relative
+//! to the directory of the source file making the call. What is left is an
+//! ordinary string or bytes literal, so the contents are stored in the
+//! binary's read-only data and nothing is read at run time.
+//!
+//! Without filesystem access, as under `--sandbox`, a program can only embed
+//! files in its own directory: absolute paths, paths through `..` and
+//! symlinks leading out of the directory are rejected.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Component, Path};
+
+use otterc_ast::nodes::{Block, Expr, FStringPart, Function, Literal, Node, Program, Statement};
+use otterc_span::Span;
+
+/// Builtins replaced by the contents of a file
+pub const INCLUDE_BUILTINS: &[&str] = &["include_str", "include_bytes"];
+
+/// A call to an include builtin that could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeError {
+    pub span: Span,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl IncludeError {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// Replaces the include calls of `program` by the contents of the files they
+/// name, read with `read` relative to `source_dir`. Calls that cannot be
+/// resolved are left in place and reported. A function of the program with
+/// the same name as a builtin shadows it. When `confined`, only files inside
+/// `source_dir` can be included.
+pub fn resolve_includes(
+    program: &mut Program,
+    source_dir: &Path,
+    confined: bool,
+    read: impl Fn(&Path) -> io::Result<Vec<u8>>,
+) -> Vec<IncludeError> {
+    let shadowed = program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement.as_ref() {
+            Statement::Function(function) => Some(function.as_ref().name.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut resolver = Resolver {
+        source_dir,
+        confined,
+        read,
+        shadowed,
+        errors: Vec::new(),
+    };
+    for statement in &mut program.statements {
+        resolver.statement(statement.as_mut());
+    }
+    resolver.errors
+}
+
+struct Resolver<'a, R> {
+    source_dir: &'a Path,
+    confined: bool,
+    read: R,
+    shadowed: HashSet<String>,
+    errors: Vec<IncludeError>,
+}
+
+impl<R: Fn(&Path) -> io::Result<Vec<u8>>> Resolver<'_, R> {
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Let { expr, .. }
+            | Statement::Assignment { expr, .. }
+            | Statement::Const { expr, .. }
+            | Statement::Defer(expr)
+            | Statement::Expr(expr)
+            | Statement::Return(Some(expr)) => self.expr(expr),
+            Statement::If {
+                cond,
+                then_block,
+                elif_blocks,
+                else_block,
+            } => {
+                self.expr(cond);
+                self.block(then_block.as_mut());
+                for (cond, block) in elif_blocks {
+                    self.expr(cond);
+                    self.block(block.as_mut());
+                }
+                if let Some(block) = else_block {
+                    self.block(block.as_mut());
+                }
+            }
+            Statement::For { iterable, body, .. } => {
+                self.expr(iterable);
+                self.block(body.as_mut());
+            }
+            Statement::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body.as_mut());
+            }
+            Statement::With { expr, body, .. } => {
+                self.expr(expr);
+                self.block(body.as_mut());
+            }
+            Statement::Assert { cond, message } => {
+                self.expr(cond);
+                if let Some(message) = message {
+                    self.expr(message);
+                }
+            }
+            Statement::Function(function) => self.function(function.as_mut()),
+            Statement::Struct { methods, .. } | Statement::Enum { methods, .. } => {
+                for method in methods {
+                    self.function(method.as_mut());
+                }
+            }
+            Statement::Block(block) | Statement::Init(block) => self.block(block.as_mut()),
+            Statement::Return(None)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Pass
+            | Statement::TypeAlias { .. }
+            | Statement::Protocol { .. }
+            | Statement::Use { .. }
+            | Statement::PubUse { .. } => {}
+        }
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        for statement in &mut block.statements {
+            self.statement(statement.as_mut());
+        }
+    }
+
+    fn function(&mut self, function: &mut Function) {
+        for param in &mut function.params {
+            if let Some(default) = &mut param.as_mut().default {
+                self.expr(default);
+            }
+        }
+        self.block(function.body.as_mut());
+    }
+
+    fn expr(&mut self, expr: &mut Node<Expr>) {
+        let span = *expr.span();
+        let mut included = None;
+        match expr.as_mut() {
+            Expr::Literal(_) | Expr::Identifier(_) => {}
+            Expr::Member { object, .. } => self.expr(object),
+            Expr::Call { func, args } => {
+                self.expr(func);
+                for arg in args.iter_mut() {
+                    self.expr(arg);
+                }
+                if let Expr::Identifier(name) = func.as_ref().as_ref()
+                    && INCLUDE_BUILTINS.contains(&name.as_str())
+                    && !self.shadowed.contains(name)
+                {
+                    let name = name.clone();
+                    included = self.include(&name, args, span);
+                }
+            }
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::Await(expr)
+            | Expr::Spawn(expr) => self.expr(expr),
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.expr(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            Expr::Match { value, arms } => {
+                self.expr(value);
+                for arm in arms {
+                    let arm = arm.as_mut();
+                    if let Some(guard) = &mut arm.guard {
+                        self.expr(guard);
+                    }
+                    self.block(arm.body.as_mut());
+                }
+            }
+            Expr::Range { start, end } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::Array(elements) => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Dict(pairs) => {
+                for (key, value) in pairs {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::ListComprehension {
+                element,
+                iterable,
+                condition,
+                ..
+            } => {
+                self.expr(element);
+                self.expr(iterable);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+            }
+            Expr::DictComprehension {
+                key,
+                value,
+                iterable,
+                condition,
+                ..
+            } => {
+                self.expr(key);
+                self.expr(value);
+                self.expr(iterable);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+            }
+            Expr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr, _) = part.as_mut() {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::Struct { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::Lambda { params, body } => {
+                for param in params {
+                    if let Some(default) = &mut param.as_mut().default {
+                        self.expr(default);
+                    }
+                }
+                self.expr(body);
+            }
+        }
+        if let Some(literal) = included {
+            *expr = Node::new(Expr::Literal(Node::new(literal, span)), span);
+        }
+    }
+
+    /// The literal an include call stands for
+    fn include(&mut self, name: &str, args: &[Node<Expr>], span: Span) -> Option<Literal> {
+        let path = match args {
+            [arg] => match arg.as_ref() {
+                Expr::Literal(literal) => match literal.as_ref() {
+                    Literal::String(path) => Some(path),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(path) = path else {
+            self.errors.push(
+                IncludeError::new(span, format!("{name}() expects a string literal path"))
+                    .with_help("the file is read at compile time, so its path must be written out"),
+            );
+            return None;
+        };
+
+        let file = self.source_dir.join(path);
+        if self.confined && !stays_inside(self.source_dir, Path::new(path), &file) {
+            self.errors.push(
+                IncludeError::new(
+                    span,
+                    format!("cannot include `{path}`: filesystem access is disabled by --sandbox"),
+                )
+                .with_help("sandboxed programs can only include files in their own directory"),
+            );
+            return None;
+        }
+
+        let contents = match (self.read)(&file) {
+            Ok(contents) => contents,
+            Err(err) => {
+                self.errors.push(
+                    IncludeError::new(span, format!("cannot read `{path}`: {err}"))
+                        .with_help("paths are relative to the file containing the call"),
+                );
+                return None;
+            }
+        };
+        if name == "include_bytes" {
+            return Some(Literal::Bytes(contents));
+        }
+        match String::from_utf8(contents) {
+            Ok(text) => Some(Literal::String(text)),
+            Err(_) => {
+                self.errors.push(
+                    IncludeError::new(span, format!("`{path}` is not valid UTF-8"))
+                        .with_help("use include_bytes() for binary files"),
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Whether `path`, relative to `dir`, names a file inside it. `file` is the
+/// two joined, whose links are followed when it exists.
+fn stays_inside(dir: &Path, path: &Path, file: &Path) -> bool {
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return false;
+    }
+    match (dir.canonicalize(), file.canonicalize()) {
+        (Ok(dir), Ok(file)) => file.starts_with(dir),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otterc_lexer::tokenize;
+    use otterc_parser::parse;
+
+    fn resolve(source: &str) -> (Program, Vec<IncludeError>) {
+        resolve_with(source, false)
+    }
+
+    fn resolve_with(source: &str, confined: bool) -> (Program, Vec<IncludeError>) {
+        let tokens = tokenize(source).expect("tokenize");
+        let mut program = parse(&tokens).expect("parse");
+        let errors = resolve_includes(
+            &mut program,
+            Path::new("assets"),
+            confined,
+            |path| match path.to_str() {
+                Some("assets/page.html") => Ok(b"<h1>otter</h1>".to_vec()),
+                Some("assets/logo.png") => Ok(vec![0x89, b'P', b'N', b'G', 0xff]),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+            },
+        );
+        (program, errors)
+    }
+
+    fn let_value(program: &Program, index: usize) -> Option<Literal> {
+        let Statement::Function(main) = program.statements[0].as_ref() else {
+            return None;
+        };
+        match main.as_ref().body.as_ref().statements[index].as_ref() {
+            Statement::Let { expr, .. } => match expr.as_ref() {
+                Expr::Literal(literal) => Some(literal.as_ref().clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn replaces_calls_with_file_contents() {
+        let (program, errors) = resolve(
+            "fn main():\n    \
+             let page = include_str(\"page.html\")\n    \
+             let logo = include_bytes(\"logo.png\")\n",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            let_value(&program, 0),
+            Some(Literal::String("<h1>otter</h1>".to_string()))
+        );
+        assert_eq!(
+            let_value(&program, 1),
+            Some(Literal::Bytes(vec![0x89, b'P', b'N', b'G', 0xff]))
+        );
+    }
+
+    #[test]
+    fn reports_calls_that_cannot_be_resolved() {
+        let (_, errors) = resolve(
+            "fn main():\n    \
+             let name = \"page.html\"\n    \
+             let a = include_str(name)\n    \
+             let b = include_str(\"missing.txt\")\n    \
+             let c = include_str(\"logo.png\")\n",
+        );
+        let messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "include_str() expects a string literal path",
+                "cannot read `missing.txt`: not found",
+                "`logo.png` is not valid UTF-8",
+            ]
+        );
+    }
+
+    #[test]
+    fn confined_includes_stay_in_the_source_directory() {
+        let source = "fn main():\n    \
+                      let page = include_str(\"page.html\")\n    \
+                      let passwd = include_str(\"/etc/passwd\")\n    \
+                      let up = include_str(\"../secret.txt\")\n    \
+                      let down = include_bytes(\"img/../../logo.png\")\n";
+        let (program, errors) = resolve_with(source, true);
+        assert_eq!(
+            let_value(&program, 0),
+            Some(Literal::String("<h1>otter</h1>".to_string()))
+        );
+        let messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "cannot include `/etc/passwd`: filesystem access is disabled by --sandbox",
+                "cannot include `../secret.txt`: filesystem access is disabled by --sandbox",
+                "cannot include `img/../../logo.png`: filesystem access is disabled by --sandbox",
+            ]
+        );
+
+        // Unconfined, the same paths are read like any other
+        let (_, errors) = resolve_with(source, false);
+        let messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "cannot read `/etc/passwd`: not found",
+                "cannot read `../secret.txt`: not found",
+                "cannot read `img/../../logo.png`: not found",
+            ]
+        );
+    }
+}
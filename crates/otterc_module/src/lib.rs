@@ -2,10 +2,12 @@
 //!
 //! Handles module resolution, loading, and dependency tracking for .ot files
 
+pub mod includes;
 pub mod loader;
 pub mod processor;
 pub mod resolver;
 
+pub use includes::{INCLUDE_BUILTINS, IncludeError, resolve_includes};
 pub use loader::{Module, ModuleExports, ModuleLoader};
 pub use processor::ModuleProcessor;
 pub use resolver::{DependencyGraph, ModulePath, ModuleResolver};
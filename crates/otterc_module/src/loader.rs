@@ -3,8 +3,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::includes::resolve_includes;
 use crate::resolver::ModuleResolver;
 use otterc_ast::nodes::{Program, Statement};
+use otterc_config::CfgContext;
 use otterc_lexer::tokenize;
 use otterc_parser::parse;
 
@@ -30,15 +32,21 @@ impl ModuleExports {
     }
 
     pub fn add_function(&mut self, name: String) {
-        self.functions.push(name);
+        if !self.functions.contains(&name) {
+            self.functions.push(name);
+        }
     }
 
     pub fn add_constant(&mut self, name: String) {
-        self.constants.push(name);
+        if !self.constants.contains(&name) {
+            self.constants.push(name);
+        }
     }
 
     pub fn add_type(&mut self, name: String) {
-        self.types.push(name);
+        if !self.types.contains(&name) {
+            self.types.push(name);
+        }
     }
 
     pub fn is_exported(&self, name: &str) -> bool {
@@ -52,6 +60,13 @@ impl ModuleExports {
 pub struct ModuleLoader {
     cache: HashMap<PathBuf, Module>,
     resolver: ModuleResolver,
+    /// In-memory contents that replace files on disk, such as unsaved editor buffers
+    sources: HashMap<PathBuf, String>,
+    /// Target that `@cfg(...)` attributes are evaluated against, if any
+    cfg: Option<CfgContext>,
+    /// Whether `include_str` and `include_bytes` are kept to each module's
+    /// own directory
+    confined_includes: bool,
 }
 
 impl ModuleLoader {
@@ -59,9 +74,34 @@ impl ModuleLoader {
         Self {
             cache: HashMap::new(),
             resolver: ModuleResolver::new(source_dir, stdlib_dir),
+            sources: HashMap::new(),
+            cfg: None,
+            confined_includes: false,
         }
     }
 
+    /// Drops the functions and imports of loaded modules whose `@cfg(...)`
+    /// does not hold for `cfg`
+    pub fn set_cfg(&mut self, cfg: CfgContext) {
+        self.cache.clear();
+        self.cfg = Some(cfg);
+    }
+
+    /// Keeps the files modules include to their own directories, for programs
+    /// without filesystem access
+    pub fn set_confined_includes(&mut self, confined: bool) {
+        self.cache.clear();
+        self.confined_includes = confined;
+    }
+
+    /// Reads `path` from `source` instead of the file system. The file must
+    /// still exist on disk for imports to resolve to it.
+    pub fn set_source(&mut self, path: PathBuf, source: String) {
+        let path = path.canonicalize().unwrap_or(path);
+        self.cache.remove(&path);
+        self.sources.insert(path, source);
+    }
+
     /// Load a module from a path string
     pub fn load(&mut self, module: &str) -> Result<Module> {
         let resolved_path = self.resolver.resolve(module)?;
@@ -79,8 +119,11 @@ impl ModuleLoader {
 
     /// Load a module from a file path
     pub fn load_file(&mut self, path: &Path) -> Result<Module> {
-        let source = fs::read_to_string(path)
-            .with_context(|| format!("failed to read module file {}", path.display()))?;
+        let source = match self.sources.get(path) {
+            Some(source) => source.clone(),
+            None => fs::read_to_string(path)
+                .with_context(|| format!("failed to read module file {}", path.display()))?,
+        };
 
         let tokens = tokenize(&source).map_err(|errors| {
             anyhow::anyhow!(
@@ -90,13 +133,39 @@ impl ModuleLoader {
             )
         })?;
 
-        let program = parse(&tokens).map_err(|errors| {
+        let mut program = parse(&tokens).map_err(|errors| {
             anyhow::anyhow!(
                 "failed to parse module {}: {} errors",
                 path.display(),
                 errors.len()
             )
         })?;
+        if let Some(cfg) = &self.cfg {
+            program.retain_cfg(|key, value| cfg.matches(key, value));
+        }
+        let source_dir = path.parent().unwrap_or(Path::new("."));
+        let include_errors =
+            resolve_includes(&mut program, source_dir, self.confined_includes, |file| {
+                fs::read(file)
+            });
+        if let Some(error) = include_errors.into_iter().next() {
+            anyhow::bail!("{} in module {}", error.message, path.display());
+        }
+        if let Some(error) = program.expand_derives().into_iter().next() {
+            anyhow::bail!("{} in module {}", error.message, path.display());
+        }
+        // Imported modules are checked but not compiled into the program, so
+        // nothing would run their init block
+        if program
+            .statements
+            .iter()
+            .any(|statement| matches!(statement.as_ref(), Statement::Init(_)))
+        {
+            anyhow::bail!(
+                "`init:` block in imported module {}; only the program being run can have one",
+                path.display()
+            );
+        }
 
         let exports = self.extract_exports(&program);
 
@@ -118,14 +187,15 @@ impl ModuleLoader {
                         exports.add_function(function.as_ref().name.clone());
                     }
                 }
-                Statement::Let { name, public, .. } => {
+                Statement::Let { name, public, .. } | Statement::Const { name, public, .. } => {
                     if *public {
                         exports.add_constant(name.as_ref().clone());
                     }
                 }
                 Statement::Struct { name, public, .. }
                 | Statement::Enum { name, public, .. }
-                | Statement::TypeAlias { name, public, .. } => {
+                | Statement::TypeAlias { name, public, .. }
+                | Statement::Protocol { name, public, .. } => {
                     if *public {
                         exports.add_type(name.clone());
                     }
@@ -137,6 +207,20 @@ impl ModuleLoader {
         exports
     }
 
+    /// Paths of the modules `module` re-exports items from. Sources that do
+    /// not resolve are left for `resolve_re_exports` to report.
+    pub fn re_export_sources(&self, module: &Module) -> Vec<PathBuf> {
+        module
+            .program
+            .statements
+            .iter()
+            .filter_map(|statement| match statement.as_ref() {
+                Statement::PubUse { module, .. } => self.resolver.resolve(module).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Resolve re-exports for a module after all modules are loaded
     /// This processes `pub use` statements and adds re-exported items to the module's exports
     pub fn resolve_re_exports(
@@ -247,4 +331,57 @@ mod tests {
         assert_eq!(module.path, module_path);
         assert!(!module.program.statements.is_empty());
     }
+
+    #[test]
+    fn unsaved_sources_replace_files_on_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        let math_file = temp_dir.path().join("math.ot");
+        fs::write(&math_file, "pub fn sin(x: float) -> float:\n    return x\n").unwrap();
+
+        let mut loader = ModuleLoader::new(temp_dir.path().to_path_buf(), None);
+        loader.set_source(
+            math_file.clone(),
+            "pub fn cos(x: float) -> float:\n    return x\n".to_string(),
+        );
+        let module = loader.load("./math").unwrap();
+
+        assert_eq!(module.exports.functions, ["cos"]);
+    }
+
+    #[test]
+    fn cfg_attributes_filter_module_exports() {
+        let temp_dir = TempDir::new().unwrap();
+        let module_path = temp_dir.path().join("platform.ot");
+        fs::write(
+            &module_path,
+            "@cfg(os=\"linux\")\npub fn name() -> str:\n    return \"linux\"\n\n@cfg(not(os=\"linux\"))\npub fn name() -> str:\n    return \"other\"\n\n@cfg(feature=\"gpu\")\npub fn render():\n    pass\n",
+        )
+        .unwrap();
+
+        let mut loader = ModuleLoader::new(temp_dir.path().to_path_buf(), None);
+        let cfg = CfgContext::host();
+        loader.set_cfg(cfg.clone());
+        let module = loader.load_file(&module_path).unwrap();
+        assert_eq!(module.program.statements.len(), 1);
+        assert_eq!(module.exports.functions, ["name"]);
+
+        loader.set_cfg(cfg.with_features(["gpu".to_string()]));
+        let module = loader.load_file(&module_path).unwrap();
+        assert_eq!(module.exports.functions, ["name", "render"]);
+    }
+
+    #[test]
+    fn imported_modules_cannot_have_init_blocks() {
+        let temp_dir = TempDir::new().unwrap();
+        let module_path = temp_dir.path().join("plugins.ot");
+        fs::write(&module_path, "init:\n    print(\"loaded\")\n").unwrap();
+
+        let mut loader = ModuleLoader::new(temp_dir.path().to_path_buf(), None);
+        let error = loader.load_file(&module_path).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("`init:` block in imported module")
+        );
+    }
 }
@@ -1,9 +1,11 @@
-use anyhow::Result;
-use std::collections::HashMap;
+use anyhow::{Result, bail};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use crate::{Module, ModuleLoader, ModulePath, ModuleResolver};
-use otterc_ast::nodes::{Program, Statement};
+use otterc_ast::nodes::{Node, Program, Statement};
+use otterc_config::CfgContext;
+
 const DEFAULT_MODULES: &[&str] = &["otter:core"];
 
 const VIRTUAL_STDLIB_MODULES: &[&str] = &[
@@ -17,7 +19,12 @@ const VIRTUAL_STDLIB_MODULES: &[&str] = &[
     "fmt",
     "runtime",
     "task",
+    "tasklocal",
     "sys",
+    "env",
+    "semver",
+    "i18n",
+    "config",
     "sync",
     "time",
     "test",
@@ -47,6 +54,21 @@ impl ModuleProcessor {
         }
     }
 
+    /// Loads the module at `path` from `source` instead of the file system
+    pub fn set_source(&mut self, path: PathBuf, source: String) {
+        self.loader.set_source(path, source);
+    }
+
+    /// Evaluates `@cfg(...)` attributes in imported modules against `cfg`
+    pub fn set_cfg(&mut self, cfg: CfgContext) {
+        self.loader.set_cfg(cfg);
+    }
+
+    /// Keeps the files imported modules include to their own directories
+    pub fn set_confined_includes(&mut self, confined: bool) {
+        self.loader.set_confined_includes(confined);
+    }
+
     /// Process all `use` statements in a program and load dependencies
     pub fn process_imports(&mut self, program: &Program) -> Result<Vec<PathBuf>> {
         let mut dependencies = Vec::new();
@@ -54,50 +76,41 @@ impl ModuleProcessor {
 
         self.load_default_modules(&mut dependencies)?;
 
-        for statement in &program.statements {
-            if let Statement::Use { imports } = statement.as_ref() {
-                for import in imports {
-                    let module = &import.as_ref().module;
-                    if Self::is_virtual_module(module) {
-                        continue;
-                    }
-                    let module_path = ModulePath::from_string(module, &self.source_dir)?;
-
-                    match module_path {
-                        ModulePath::Rust(_) => {
-                            rust_imports.push(module.clone());
-                        }
-                        ModulePath::Stdlib(_) => {
-                            let resolved = {
-                                let resolver = self.loader.resolver();
-                                resolver.resolve(module)?
-                            };
-                            self.load_stdlib_dependency(resolved, &mut dependencies)?;
-                        }
-                        ModulePath::Relative(_) | ModulePath::Absolute(_) => {
-                            let source_dir = self.source_dir.clone();
-                            let resolved = {
-                                let resolver = self.loader.resolver();
-                                resolver.resolve(module)?
-                            };
-                            self.load_local_dependency(&source_dir, resolved, &mut dependencies)?;
-                        }
-                        ModulePath::Unqualified(_) => {
-                            let source_dir = self.source_dir.clone();
-                            let resolved = {
-                                let resolver = self.loader.resolver();
-                                resolver.resolve(module)?
-                            };
-                            if self.is_stdlib_path(&resolved) {
-                                self.load_stdlib_dependency(resolved, &mut dependencies)?;
-                            } else {
-                                self.load_local_dependency(
-                                    &source_dir,
-                                    resolved,
-                                    &mut dependencies,
-                                )?;
-                            }
-                        }
+        for module in Self::imported_modules(&program.statements) {
+            if Self::is_virtual_module(module) {
+                continue;
+            }
+            let module_path = ModulePath::from_string(module, &self.source_dir)?;
+
+            match module_path {
+                ModulePath::Rust(_) => {
+                    rust_imports.push(module.clone());
+                }
+                ModulePath::Stdlib(_) => {
+                    let resolved = {
+                        let resolver = self.loader.resolver();
+                        resolver.resolve(module)?
+                    };
+                    self.load_stdlib_dependency(resolved, &mut dependencies)?;
+                }
+                ModulePath::Relative(_) | ModulePath::Absolute(_) => {
+                    let source_dir = self.source_dir.clone();
+                    let resolved = {
+                        let resolver = self.loader.resolver();
+                        resolver.resolve(module)?
+                    };
+                    self.load_local_dependency(&source_dir, resolved, &mut dependencies)?;
+                }
+                ModulePath::Unqualified(_) => {
+                    let source_dir = self.source_dir.clone();
+                    let resolved = {
+                        let resolver = self.loader.resolver();
+                        resolver.resolve(module)?
+                    };
+                    if self.is_stdlib_path(&resolved) {
+                        self.load_stdlib_dependency(resolved, &mut dependencies)?;
+                    } else {
+                        self.load_local_dependency(&source_dir, resolved, &mut dependencies)?;
                     }
                 }
             }
@@ -122,50 +135,35 @@ impl ModuleProcessor {
 
         self.load_default_modules(&mut dependencies)?;
 
-        for statement in &module_statements {
-            if let Statement::Use { imports } = statement.as_ref() {
-                for import in imports {
-                    let module = &import.as_ref().module;
-                    if Self::is_virtual_module(module) {
-                        continue;
-                    }
-                    let module_dir = module_path.parent().unwrap_or(Path::new("."));
-                    let module_path_enum = ModulePath::from_string(module, module_dir)?;
-
-                    match module_path_enum {
-                        ModulePath::Rust(_) => {}
-                        ModulePath::Stdlib(_) => {
-                            let resolver = ModuleResolver::new(
-                                module_dir.to_path_buf(),
-                                self.stdlib_dir.clone(),
-                            );
-                            let resolved = resolver.resolve(module)?;
-                            self.load_stdlib_dependency(resolved, &mut dependencies)?;
-                        }
-                        ModulePath::Relative(_) | ModulePath::Absolute(_) => {
-                            let resolver = ModuleResolver::new(
-                                module_dir.to_path_buf(),
-                                self.stdlib_dir.clone(),
-                            );
-                            let resolved = resolver.resolve(module)?;
-                            self.load_local_dependency(module_path, resolved, &mut dependencies)?;
-                        }
-                        ModulePath::Unqualified(_) => {
-                            let resolver = ModuleResolver::new(
-                                module_dir.to_path_buf(),
-                                self.stdlib_dir.clone(),
-                            );
-                            let resolved = resolver.resolve(module)?;
-                            if self.is_stdlib_path(&resolved) {
-                                self.load_stdlib_dependency(resolved, &mut dependencies)?;
-                            } else {
-                                self.load_local_dependency(
-                                    module_path,
-                                    resolved,
-                                    &mut dependencies,
-                                )?;
-                            }
-                        }
+        for module in Self::imported_modules(&module_statements) {
+            if Self::is_virtual_module(module) {
+                continue;
+            }
+            let module_dir = module_path.parent().unwrap_or(Path::new("."));
+            let module_path_enum = ModulePath::from_string(module, module_dir)?;
+
+            match module_path_enum {
+                ModulePath::Rust(_) => {}
+                ModulePath::Stdlib(_) => {
+                    let resolver =
+                        ModuleResolver::new(module_dir.to_path_buf(), self.stdlib_dir.clone());
+                    let resolved = resolver.resolve(module)?;
+                    self.load_stdlib_dependency(resolved, &mut dependencies)?;
+                }
+                ModulePath::Relative(_) | ModulePath::Absolute(_) => {
+                    let resolver =
+                        ModuleResolver::new(module_dir.to_path_buf(), self.stdlib_dir.clone());
+                    let resolved = resolver.resolve(module)?;
+                    self.load_local_dependency(module_path, resolved, &mut dependencies)?;
+                }
+                ModulePath::Unqualified(_) => {
+                    let resolver =
+                        ModuleResolver::new(module_dir.to_path_buf(), self.stdlib_dir.clone());
+                    let resolved = resolver.resolve(module)?;
+                    if self.is_stdlib_path(&resolved) {
+                        self.load_stdlib_dependency(resolved, &mut dependencies)?;
+                    } else {
+                        self.load_local_dependency(module_path, resolved, &mut dependencies)?;
                     }
                 }
             }
@@ -201,15 +199,52 @@ impl ModuleProcessor {
     /// Resolve all re-exports after all modules are loaded
     pub fn resolve_all_re_exports(&mut self) -> Result<()> {
         let module_paths: Vec<PathBuf> = self.loaded_modules.keys().cloned().collect();
+        let mut resolved = HashSet::new();
 
         for module_path in module_paths {
-            let all_modules_snapshot: HashMap<PathBuf, Module> = self.loaded_modules.clone();
-            if let Some(module) = self.loaded_modules.get_mut(&module_path) {
-                self.loader
-                    .resolve_re_exports(module, &all_modules_snapshot)?;
-            }
+            self.resolve_re_exports_of(&module_path, &mut resolved, &mut Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the re-exports of every module `module_path` re-exports from
+    /// before its own, so re-exporting a module that itself re-exports others
+    /// picks up their items too
+    fn resolve_re_exports_of(
+        &mut self,
+        module_path: &PathBuf,
+        resolved: &mut HashSet<PathBuf>,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        if resolved.contains(module_path) {
+            return Ok(());
+        }
+        if stack.contains(module_path) {
+            let cycle: Vec<String> = stack
+                .iter()
+                .chain(std::iter::once(module_path))
+                .map(|path| path.display().to_string())
+                .collect();
+            bail!("circular re-export: {}", cycle.join(" -> "));
+        }
+        let Some(module) = self.loaded_modules.get(module_path) else {
+            return Ok(());
+        };
+
+        let sources = self.loader.re_export_sources(module);
+        stack.push(module_path.clone());
+        for source in &sources {
+            self.resolve_re_exports_of(source, resolved, stack)?;
         }
+        stack.pop();
 
+        let all_modules_snapshot: HashMap<PathBuf, Module> = self.loaded_modules.clone();
+        if let Some(module) = self.loaded_modules.get_mut(module_path) {
+            self.loader
+                .resolve_re_exports(module, &all_modules_snapshot)?;
+        }
+        resolved.insert(module_path.clone());
         Ok(())
     }
 }
@@ -279,6 +314,22 @@ impl ModuleProcessor {
             .unwrap_or(false)
     }
 
+    /// Modules a program depends on: everything it `use`s and every module
+    /// it re-exports from
+    fn imported_modules(statements: &[Node<Statement>]) -> Vec<&String> {
+        let mut modules = Vec::new();
+        for statement in statements {
+            match statement.as_ref() {
+                Statement::Use { imports } => {
+                    modules.extend(imports.iter().map(|import| &import.as_ref().module));
+                }
+                Statement::PubUse { module, .. } => modules.push(module),
+                _ => {}
+            }
+        }
+        modules
+    }
+
     fn is_virtual_module(module: &str) -> bool {
         let namespace_split = module.rsplit(':').next().unwrap_or(module);
         let candidate = namespace_split
@@ -461,4 +512,36 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("cannot re-export"));
     }
+
+    #[test]
+    fn re_exports_of_re_exports_are_resolved_transitively() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_dir = temp_dir.path().join("src");
+        fs::create_dir_all(&source_dir).unwrap();
+
+        let math_file = source_dir.join("math.ot");
+        let shapes_file = source_dir.join("shapes.ot");
+        let facade_file = source_dir.join("facade.ot");
+        fs::write(&math_file, "pub fn sin(x: float) -> float:\n    return x\n").unwrap();
+        fs::write(
+            &shapes_file,
+            "pub use ./math\npub struct Circle:\n    r: float\n",
+        )
+        .unwrap();
+        fs::write(&facade_file, "pub use ./shapes\n").unwrap();
+
+        let tokens = otterc_lexer::tokenize("from ./facade import *\n").unwrap();
+        let program = otterc_parser::parse(&tokens).unwrap();
+
+        let mut processor = ModuleProcessor::new(source_dir.clone(), None);
+        let deps = processor.process_imports(&program).unwrap();
+        assert_eq!(deps.len(), 3);
+        processor.resolve_all_re_exports().unwrap();
+
+        let facade = processor
+            .get_module(&facade_file.canonicalize().unwrap())
+            .unwrap();
+        assert_eq!(facade.exports.functions, ["sin"]);
+        assert_eq!(facade.exports.types, ["Circle"]);
+    }
 }
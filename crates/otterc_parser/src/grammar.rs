@@ -1,9 +1,11 @@
 use chumsky::Stream;
+use chumsky::error::SimpleReason;
 use chumsky::prelude::*;
 
 use otterc_ast::nodes::{
-    BinaryOp, Block, EnumVariant, Expr, FStringPart, Function, Literal, MatchArm, Node,
-    NumberLiteral, Param, Pattern, Program, Statement, Type, UnaryOp, UseImport,
+    BinaryOp, Block, CfgPredicate, Derive, EnumVariant, Expr, FStringPart, Function, GenericBound,
+    ImportItem, InlineHint, Literal, MatchArm, Node, NumberLiteral, Param, Pattern, Program,
+    ProtocolMethod, Statement, Type, UnaryOp, UseImport,
 };
 
 use otterc_lexer::token::{Token, TokenKind};
@@ -44,7 +46,9 @@ impl From<Simple<TokenKind>> for ParserError {
     fn from(value: Simple<TokenKind>) -> Self {
         let span_range = value.span();
         let span = Span::new(span_range.start, span_range.end);
-        let message = if let Some(found) = value.found() {
+        let message = if let SimpleReason::Custom(message) = value.reason() {
+            message.clone()
+        } else if let Some(found) = value.found() {
             format!("unexpected token: {:?}", found)
         } else {
             "unexpected end of input".to_string()
@@ -77,6 +81,105 @@ fn identifier_parser() -> impl Parser<TokenKind, String, Error = Simple<TokenKin
     select! { TokenKind::Identifier(name) => name }
 }
 
+/// `@cfg(predicate)` on its own line, where a predicate is `key="value"`,
+/// `not(p)`, `all(p, ...)` or `any(p, ...)`
+fn cfg_attribute_parser() -> impl Parser<TokenKind, CfgPredicate, Error = Simple<TokenKind>> {
+    let predicate = recursive(|predicate| {
+        let list = |name: &str| {
+            just(TokenKind::Identifier(name.to_string())).ignore_then(
+                predicate
+                    .clone()
+                    .separated_by(just(TokenKind::Comma))
+                    .allow_trailing()
+                    .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
+            )
+        };
+        let equals = identifier_parser()
+            .then_ignore(just(TokenKind::Equals))
+            .then(select! { TokenKind::StringLiteral(value) => value })
+            .try_map(|(key, value), span| {
+                if CfgPredicate::KEYS.contains(&key.as_str()) {
+                    Ok(CfgPredicate::Equals { key, value })
+                } else {
+                    Err(Simple::custom(
+                        span,
+                        format!(
+                            "unknown cfg key `{}`, expected one of: {}",
+                            key,
+                            CfgPredicate::KEYS.join(", ")
+                        ),
+                    ))
+                }
+            });
+
+        choice((
+            just(TokenKind::Not)
+                .ignore_then(
+                    predicate
+                        .clone()
+                        .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
+                )
+                .map(|inner| CfgPredicate::Not(Box::new(inner))),
+            list("all").map(CfgPredicate::All),
+            list("any").map(CfgPredicate::Any),
+            equals,
+        ))
+        .boxed()
+    });
+
+    just(TokenKind::At)
+        .ignore_then(just(TokenKind::Identifier("cfg".to_string())))
+        .ignore_then(predicate.delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)))
+        .then_ignore(just(TokenKind::Newline).repeated().at_least(1))
+}
+
+/// `@inline` or `@noinline` on its own line before a function
+fn inline_attribute_parser() -> impl Parser<TokenKind, InlineHint, Error = Simple<TokenKind>> {
+    just(TokenKind::At)
+        .ignore_then(choice((
+            just(TokenKind::Identifier("inline".to_string())).to(InlineHint::Always),
+            just(TokenKind::Identifier("noinline".to_string())).to(InlineHint::Never),
+        )))
+        .then_ignore(just(TokenKind::Newline).repeated().at_least(1))
+}
+
+/// An attribute on the lines before a top-level function or import
+#[derive(Debug, Clone)]
+enum ItemAttribute {
+    Cfg(CfgPredicate),
+    Inline(InlineHint),
+}
+
+/// `@derive(json, eq)` on its own line before a struct or enum
+fn derive_attribute_parser() -> impl Parser<TokenKind, Vec<Node<Derive>>, Error = Simple<TokenKind>>
+{
+    let derive = identifier_parser().try_map(|name, span: Range<usize>| {
+        Derive::parse(&name)
+            .map(|derive| Node::new(derive, span.clone()))
+            .ok_or_else(|| {
+                Simple::custom(
+                    span,
+                    format!(
+                        "unknown derive `{}`, expected one of: {}",
+                        name,
+                        Derive::ALL.map(Derive::as_str).join(", ")
+                    ),
+                )
+            })
+    });
+
+    just(TokenKind::At)
+        .ignore_then(just(TokenKind::Identifier("derive".to_string())))
+        .ignore_then(
+            derive
+                .separated_by(just(TokenKind::Comma))
+                .allow_trailing()
+                .at_least(1)
+                .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
+        )
+        .then_ignore(just(TokenKind::Newline).repeated().at_least(1))
+}
+
 fn identifier_or_keyword_parser() -> impl Parser<TokenKind, String, Error = Simple<TokenKind>> {
     select! {
         TokenKind::Identifier(name) => name,
@@ -103,12 +206,22 @@ fn identifier_or_keyword_parser() -> impl Parser<TokenKind, String, Error = Simp
         TokenKind::False => "false".to_string(),
         TokenKind::Print => "print".to_string(),
         TokenKind::None => "None".to_string(),
+        TokenKind::With => "with".to_string(),
+        TokenKind::Defer => "defer".to_string(),
+        TokenKind::Assert => "assert".to_string(),
     }
 }
 
+/// Type names allowed after `as`
+const CAST_TARGETS: &[&str] = &[
+    "int", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "float", "f64", "bool", "str",
+    "string",
+];
+
 fn type_parser() -> impl Parser<TokenKind, Node<Type>, Error = Simple<TokenKind>> {
     recursive(|ty| {
-        identifier_parser()
+        let member = identifier_parser()
+            .or(just(TokenKind::None).to("None".to_string()))
             .then(
                 ty.separated_by(just(TokenKind::Comma))
                     .allow_trailing()
@@ -124,116 +237,111 @@ fn type_parser() -> impl Parser<TokenKind, Node<Type>, Error = Simple<TokenKind>
                     span,
                 )
             })
+            .boxed();
+
+        // `int | str | None`
+        member
+            .clone()
+            .then(just(TokenKind::Pipe).ignore_then(member).repeated())
+            .map_with_span(|(first, rest), span| {
+                if rest.is_empty() {
+                    first
+                } else {
+                    let mut members = vec![first];
+                    members.extend(rest);
+                    Node::new(Type::Union(members), span)
+                }
+            })
     })
 }
 
-fn parse_fstring(content: String, span: impl Into<Span>) -> Node<Expr> {
-    use chumsky::Parser;
+/// Splits an f-string into text and interpolated expressions. `offsets` gives
+/// the source offset of each char of `content`, so the expressions, and any
+/// error in them, point into the literal.
+fn parse_fstring(
+    content: &str,
+    offsets: &[usize],
+    span: Span,
+) -> Result<Node<Expr>, Simple<TokenKind>> {
+    let chars: Vec<char> = content.chars().collect();
+    // Without offsets everything points at the start of the literal
+    let offset = |index: usize| match offsets.get(index) {
+        Some(offset) if offsets.len() == chars.len() => *offset,
+        _ if index >= chars.len() => span.end(),
+        _ => span.start(),
+    };
 
-    // Parse f-string by splitting on braces and parsing expressions
     let mut parts = Vec::new();
-    let mut current_text = String::new();
-    let mut chars = content.chars().enumerate().peekable();
-    let span: Span = span.into();
-    let span_start = span.start();
-
-    while let Some((i, ch)) = chars.next() {
-        let span_start = span_start + i;
-        match ch {
-            '{' => {
-                if let Some((_, '{')) = chars.peek() {
-                    // Escaped {{
-                    chars.next();
-                    current_text.push('{');
-                } else {
-                    // Expression start
-                    if !current_text.is_empty() {
-                        let s = Span::new(span_start, span_start + current_text.len());
-                        parts.push(Node::new(FStringPart::Text(current_text), s));
-                        current_text = String::new();
-                    }
-
-                    // Parse expression until }
-                    let mut expr_content = String::new();
-
-                    for (_, ch) in chars.by_ref() {
-                        if ch == '}' {
-                            break;
-                        }
-
-                        expr_content.push(ch);
-                    }
-
-                    if !expr_content.is_empty() {
-                        // Parse the expression content using the full expression parser
-                        let trimmed = expr_content.trim();
-                        if !trimmed.is_empty() {
-                            match otterc_lexer::tokenize(trimmed) {
-                                Ok(tokens) => {
-                                    // Create a stream from tokens for the parser
-                                    use chumsky::Stream;
-                                    let end_span =
-                                        tokens.last().map(|t| t.span().end()).unwrap_or(0);
-                                    let stream = Stream::from_iter(
-                                        end_span..end_span + 1,
-                                        tokens.iter().map(|token| {
-                                            (token.kind().clone(), token.span().into())
-                                        }),
-                                    );
-                                    match expr_parser().parse(stream) {
-                                        Ok(expr) => {
-                                            let s = Span::new(
-                                                span_start,
-                                                span_start + expr.span().end(),
-                                            );
-                                            parts.push(Node::new(FStringPart::Expr(expr), s));
-                                        }
-                                        Err(_) => {
-                                            // Fallback to simple identifier if parsing fails
-                                            let s =
-                                                Span::new(span_start, span_start + trimmed.len());
-                                            parts.push(Node::new(
-                                                FStringPart::Expr(Node::new(
-                                                    Expr::Identifier(trimmed.to_string()),
-                                                    s,
-                                                )),
-                                                s,
-                                            ));
-                                        }
-                                    }
-                                }
-                                Err(_) => {
-                                    // Fallback to simple identifier if parsing fails
-                                    let s = Span::new(span_start, span_start + trimmed.len());
-                                    parts.push(Node::new(
-                                        FStringPart::Expr(Node::new(
-                                            Expr::Identifier(trimmed.to_string()),
-                                            s,
-                                        )),
-                                        s,
-                                    ));
-                                }
-                            }
-                        }
-                    }
-                }
+    let mut text = String::new();
+    let mut text_start = 0;
+    let mut index = 0;
+    while index < chars.len() {
+        match (chars[index], chars.get(index + 1)) {
+            // Escaped `{{` and `}}`
+            ('{', Some('{')) | ('}', Some('}')) => {
+                text.push(chars[index]);
+                index += 2;
             }
-            '}' => {
-                if let Some((_, '}')) = chars.peek() {
-                    // Escaped }}
-                    chars.next();
-                    current_text.push('}');
-                } else {
-                    current_text.push('}');
+            ('{', _) => {
+                if !text.is_empty() {
+                    let text_span = Span::new(offset(text_start), offset(index));
+                    parts.push(Node::new(
+                        FStringPart::Text(std::mem::take(&mut text)),
+                        text_span,
+                    ));
                 }
+                let Some(close) = chars[index + 1..]
+                    .iter()
+                    .position(|&ch| ch == '}')
+                    .map(|len| index + 1 + len)
+                else {
+                    let unclosed = Span::new(offset(index), span.end());
+                    return Err(Simple::custom(unclosed.into(), "unclosed `{` in f-string"));
+                };
+                let hole = Span::new(offset(index), offset(close) + 1);
+                // `{expr:spec}`: the specifier is checked against the type of
+                // `expr` by the type checker
+                let (end, spec) = match format_spec_colon(&chars[index + 1..close]) {
+                    Some(colon) => {
+                        let colon = index + 1 + colon;
+                        let spec: String = chars[colon + 1..close].iter().collect();
+                        let spec_span = Span::new(offset(colon + 1), offset(close));
+                        (
+                            colon,
+                            (!spec.is_empty()).then(|| Node::new(spec, spec_span)),
+                        )
+                    }
+                    None => (close, None),
+                };
+                let leading = chars[index + 1..end]
+                    .iter()
+                    .take_while(|ch| ch.is_whitespace())
+                    .count();
+                let source: String = chars[index + 1 + leading..end].iter().collect();
+                let expr = parse_interpolation(source.trim_end(), offset(index + 1 + leading))
+                    .map_err(|error_span| {
+                        Simple::custom(
+                            error_span.unwrap_or(hole).into(),
+                            match source.trim_end() {
+                                "" => "empty expression in f-string".to_string(),
+                                source => format!("invalid expression `{source}` in f-string"),
+                            },
+                        )
+                    })?;
+                parts.push(Node::new(FStringPart::Expr(expr, spec), hole));
+                index = close + 1;
+                text_start = index;
+            }
+            (ch, _) => {
+                text.push(ch);
+                index += 1;
             }
-            _ => current_text.push(ch),
         }
     }
 
-    // Add remaining text
-    if !current_text.is_empty() {
-        parts.push(Node::new(FStringPart::Text(current_text), span));
+    if !text.is_empty() {
+        let text_span = Span::new(offset(text_start), offset(chars.len()));
+        parts.push(Node::new(FStringPart::Text(text), text_span));
     }
 
     // If no expressions found, treat as regular string
@@ -242,48 +350,87 @@ fn parse_fstring(content: String, span: impl Into<Span>) -> Node<Expr> {
         .all(|part| matches!(part.as_ref(), FStringPart::Text(_)))
         && let Some(FStringPart::Text(text)) = parts.first().map(|p| p.as_ref())
     {
-        return Node::new(
+        return Ok(Node::new(
             Expr::Literal(Node::new(Literal::String(text.clone()), span)),
             span,
-        );
+        ));
     }
 
-    Node::new(Expr::FString { parts }, span)
+    Ok(Node::new(Expr::FString { parts }, span))
 }
 
-fn literal_expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>> {
-    let string_lit = select! { TokenKind::StringLiteral(value) => Literal::String(value) }
-        .map_with_span(|lit, span: Range<usize>| {
-            let span: Span = span.into();
-            Node::new(Expr::Literal(Node::new(lit, span)), span)
-        })
-        .boxed();
-    let number_lit = select! { TokenKind::Number(value) => {
-        // Remove underscores from the number
-        let clean_value = value.replace('_', "");
-        let is_float_literal = value.contains('.') || value.contains('e') || value.contains('E');
-        // Check if it contains a decimal point or is an integer
-        if clean_value.contains('.') {
-            NumberLiteral::new(
-                clean_value.parse().unwrap_or_default(),
-                true,
-            )
-        } else {
-            // Parse as integer
-            match clean_value.parse::<i64>() {
-                Ok(int_val) => NumberLiteral::new(int_val as f64, is_float_literal),
-                Err(_) => NumberLiteral::new(0.0, is_float_literal),
-            }
+/// Position of the colon that starts the format specifier of an f-string
+/// interpolation: the first one outside brackets and string literals
+fn format_spec_colon(hole: &[char]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (index, &ch) in hole.iter().enumerate() {
+        match (quote, ch) {
+            (Some(open), ch) if ch == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth = depth.saturating_sub(1),
+            (None, ':') if depth == 0 => return Some(index),
+            _ => {}
         }
-    }}
-    .map_with_span(|num_lit, span: Range<usize>| {
+    }
+    None
+}
+
+/// Parses the expression between the braces of an f-string, which starts at
+/// `start` in the source. Fails with the span of the first error, if the
+/// parser found one.
+fn parse_interpolation(source: &str, start: usize) -> Result<Node<Expr>, Option<Span>> {
+    if source.is_empty() {
+        return Err(None);
+    }
+    let tokens = otterc_lexer::tokenize(source).map_err(|_| None)?;
+    let shift = |span: Span| start + span.start()..start + span.end();
+    let source_end = start + source.len();
+    let stream = Stream::from_iter(
+        source_end..source_end + 1,
+        tokens
+            .iter()
+            .filter(|token| {
+                !matches!(
+                    token.kind(),
+                    TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent | TokenKind::Eof
+                )
+            })
+            .map(|token| (token.kind().clone(), shift(token.span()))),
+    );
+    expr_parser()
+        .then_ignore(end())
+        .parse(stream)
+        .map_err(|errors| errors.first().map(|error| error.span().into()))
+}
+
+fn literal_expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>> {
+    let string_lit = select! {
+        TokenKind::StringLiteral(value) => Literal::String(value),
+        TokenKind::BytesLiteral(bytes) => Literal::Bytes(bytes),
+        TokenKind::CheckedString { tag, value } => Literal::Checked { tag, value },
+    }
+    .map_with_span(|lit, span: Range<usize>| {
         let span: Span = span.into();
-        Node::new(
-            Expr::Literal(Node::new(Literal::Number(num_lit), span)),
-            span,
-        )
+        Node::new(Expr::Literal(Node::new(lit, span)), span)
     })
     .boxed();
+    let number_lit = select! { TokenKind::Number(value) => value }
+        // The lexer has already rejected malformed and out of range numbers
+        .try_map(|value, span| {
+            NumberLiteral::parse(&value)
+                .ok_or_else(|| Simple::custom(span, format!("invalid number literal `{value}`")))
+        })
+        .map_with_span(|num_lit, span: Range<usize>| {
+            let span: Span = span.into();
+            Node::new(
+                Expr::Literal(Node::new(Literal::Number(num_lit), span)),
+                span,
+            )
+        })
+        .boxed();
     let bool_lit = select! {
         TokenKind::True => Literal::Bool(true),
         TokenKind::False => Literal::Bool(false),
@@ -300,8 +447,11 @@ fn literal_expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<To
             Node::new(Expr::Literal(Node::new(lit, span)), span)
         })
         .boxed();
-    let fstring_lit =
-        select! { |span| TokenKind::FString(content) => parse_fstring(content, span) }.boxed();
+    let fstring_lit = select! { TokenKind::FString { content, offsets } => (content, offsets) }
+        .try_map(|(content, offsets), span: Range<usize>| {
+            parse_fstring(&content, &offsets, span.into())
+        })
+        .boxed();
     let unit_lit = just(TokenKind::LParen)
         .then(just(TokenKind::RParen))
         .map_with_span(|_, span: Range<usize>| {
@@ -309,9 +459,30 @@ fn literal_expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<To
             Node::new(Expr::Literal(Node::new(Literal::Unit, span)), span)
         })
         .boxed();
+    // `:name` with nothing between the colon and the name, so the colon of a
+    // block or dict entry followed by a space is never read as a symbol
+    let symbol_lit = just(TokenKind::Colon)
+        .map_with_span(|_, span: Range<usize>| span.end)
+        .then(identifier_parser().map_with_span(|name, span: Range<usize>| (name, span.start)))
+        .try_map(|(colon_end, (name, name_start)), span: Range<usize>| {
+            if colon_end == name_start {
+                Ok(name)
+            } else {
+                Err(Simple::custom(
+                    span,
+                    "a symbol is written `:name`, with no space after the colon",
+                ))
+            }
+        })
+        .map_with_span(|name, span: Range<usize>| {
+            let span: Span = span.into();
+            Node::new(Expr::Literal(Node::new(Literal::Symbol(name), span)), span)
+        })
+        .boxed();
     choice((
         fstring_lit,
         string_lit,
+        symbol_lit,
         number_lit,
         bool_lit,
         none_lit,
@@ -321,10 +492,42 @@ fn literal_expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<To
 
 fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>> {
     recursive(|expr| {
-        // Lambda expressions removed - use anonymous fn syntax instead
-        // fn(<args>) expr or fn(<args>): <stmts>
+        // `fn(x: int) x * 2`: the body is a single expression. Callbacks that
+        // need statements are written as nested functions.
+        let lambda = just(TokenKind::Fn)
+            .ignore_then(
+                identifier_parser()
+                    .map_with_span(Node::new)
+                    .then(just(TokenKind::Colon).ignore_then(type_parser()).or_not())
+                    .map_with_span(|(name, ty), span| Node::new(Param::new(name, ty, None), span))
+                    .separated_by(just(TokenKind::Comma))
+                    .allow_trailing()
+                    .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
+            )
+            .then(expr.clone())
+            .map_with_span(|(params, body), span| {
+                Node::new(
+                    Expr::Lambda {
+                        params,
+                        body: Box::new(body),
+                    },
+                    span,
+                )
+            })
+            .boxed();
 
+        // `Point(x=1, y=2)`, or `Shape.Circle(radius=2.0)` for an enum variant
+        // with named fields
         let struct_init_pythonic = identifier_parser()
+            .then(
+                just(TokenKind::Dot)
+                    .ignore_then(identifier_parser())
+                    .or_not(),
+            )
+            .map(|(name, variant)| match variant {
+                Some(variant) => format!("{name}.{variant}"),
+                None => name,
+            })
             .then(
                 // Keyword argument: name=value
                 identifier_parser()
@@ -398,6 +601,7 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
             expr.clone()
                 .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
             list_comprehension,
+            lambda,
             // Array literal [expr, expr, ...]
             expr.clone()
                 .separated_by(just(TokenKind::Comma))
@@ -478,6 +682,7 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
             just(TokenKind::Minus).to(UnaryOp::Neg),
             just(TokenKind::Bang).to(UnaryOp::Not),
             just(TokenKind::Not).to(UnaryOp::Not),
+            just(TokenKind::Tilde).to(UnaryOp::BitNot),
         ))
         .then(choice((
             await_expr.clone(),
@@ -498,7 +703,34 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
         .or(call.clone())
         .boxed();
 
-        let product = unary
+        // Only builtin scalar types can be cast to, which keeps
+        // `with open(path) as file:` meaning a binding
+        let cast_target = identifier_parser()
+            .try_map(|name, span| {
+                if CAST_TARGETS.contains(&name.as_str()) {
+                    Ok(name)
+                } else {
+                    Err(Simple::custom(span, format!("cannot cast to `{name}`")))
+                }
+            })
+            .map_with_span(|name, span| Node::new(Type::Simple(name), span));
+
+        let cast = unary
+            .clone()
+            .then(just(TokenKind::As).ignore_then(cast_target).repeated())
+            .foldl(|expr, ty| {
+                let span = expr.span().merge(ty.span());
+                Node::new(
+                    Expr::Cast {
+                        expr: Box::new(expr),
+                        ty,
+                    },
+                    span,
+                )
+            })
+            .boxed();
+
+        let product = cast
             .clone()
             .then(
                 choice((
@@ -506,7 +738,7 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
                     just(TokenKind::Slash).to(BinaryOp::Div),
                     just(TokenKind::Percent).to(BinaryOp::Mod),
                 ))
-                .then(unary.clone())
+                .then(cast.clone())
                 .repeated(),
             )
             .foldl(|left, (op, right)| {
@@ -545,9 +777,69 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
             })
             .boxed();
 
-        let range = sum
+        // The lexer has no `>>` token so that closing nested generics still
+        // works; a right shift is two `>` with nothing between them
+        let shr_op = just(TokenKind::Gt)
+            .map_with_span(|_, span: std::ops::Range<usize>| span.end)
+            .then(just(TokenKind::Gt).map_with_span(|_, span: std::ops::Range<usize>| span.start))
+            .try_map(|(first_end, second_start), span| {
+                if first_end == second_start {
+                    Ok(BinaryOp::Shr)
+                } else {
+                    Err(Simple::custom(span, "expected `>>`"))
+                }
+            });
+
+        let shift = sum
+            .clone()
+            .then(
+                choice((just(TokenKind::Shl).to(BinaryOp::Shl), shr_op))
+                    .then(sum)
+                    .repeated(),
+            )
+            .foldl(fold_binary)
+            .boxed();
+
+        let bit_and = shift
+            .clone()
+            .then(
+                just(TokenKind::Amp)
+                    .to(BinaryOp::BitAnd)
+                    .then(shift)
+                    .repeated(),
+            )
+            .foldl(fold_binary)
+            .boxed();
+
+        let bit_xor = bit_and
+            .clone()
+            .then(
+                just(TokenKind::Caret)
+                    .to(BinaryOp::BitXor)
+                    .then(bit_and)
+                    .repeated(),
+            )
+            .foldl(fold_binary)
+            .boxed();
+
+        let bit_or = bit_xor
             .clone()
-            .then(just(TokenKind::DoubleDot).ignore_then(sum.clone()).or_not())
+            .then(
+                just(TokenKind::Pipe)
+                    .to(BinaryOp::BitOr)
+                    .then(bit_xor)
+                    .repeated(),
+            )
+            .foldl(fold_binary)
+            .boxed();
+
+        let range = bit_or
+            .clone()
+            .then(
+                just(TokenKind::DoubleDot)
+                    .ignore_then(bit_or.clone())
+                    .or_not(),
+            )
             .map_with_span(|(start, end), span| {
                 if let Some(end) = end {
                     Node::new(
@@ -658,6 +950,19 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
                 .map_with_span(|expr, span| Node::new(Statement::Return(expr), span))
                 .boxed();
 
+            let defer_stmt = just(TokenKind::Defer)
+                .ignore_then(expr.clone())
+                .map_with_span(|expr, span| Node::new(Statement::Defer(expr), span))
+                .boxed();
+
+            let assert_stmt = just(TokenKind::Assert)
+                .ignore_then(expr.clone())
+                .then(just(TokenKind::Comma).ignore_then(expr.clone()).or_not())
+                .map_with_span(|(cond, message), span| {
+                    Node::new(Statement::Assert { cond, message }, span)
+                })
+                .boxed();
+
             let let_stmt = just(TokenKind::Let)
                 .or_not()
                 .then(
@@ -723,16 +1028,20 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
                 .boxed();
 
             let break_stmt = just(TokenKind::Break)
-                .map_with_span(|_, span| Node::new(Statement::Break, span))
+                .ignore_then(identifier_parser().map_with_span(Node::new).or_not())
+                .map_with_span(|label, span| Node::new(Statement::Break(label), span))
                 .boxed();
 
             let continue_stmt = just(TokenKind::Continue)
-                .map_with_span(|_, span| Node::new(Statement::Continue, span))
+                .ignore_then(identifier_parser().map_with_span(Node::new).or_not())
+                .map_with_span(|label, span| Node::new(Statement::Continue(label), span))
                 .boxed();
 
             choice((
                 print_stmt,
                 return_stmt,
+                defer_stmt,
+                assert_stmt,
                 let_stmt,
                 assignment_stmt,
                 simple_assignment,
@@ -748,6 +1057,7 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
 
         let match_case = just(TokenKind::Case)
             .ignore_then(pattern_parser())
+            .then(just(TokenKind::If).ignore_then(logical.clone()).or_not())
             .then_ignore(just(TokenKind::Colon))
             .then_ignore(newline.clone())
             .then(
@@ -758,11 +1068,11 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
                     .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
                     .map_with_span(|block, span| Node::new(Block::new(block), span)),
             )
-            .map_with_span(|(pattern, body), span| {
+            .map_with_span(|((pattern, guard), body), span| {
                 Node::new(
                     MatchArm {
                         pattern,
-                        guard: None,
+                        guard,
                         body,
                     },
                     span,
@@ -794,6 +1104,18 @@ fn expr_parser() -> impl Parser<TokenKind, Node<Expr>, Error = Simple<TokenKind>
 }
 
 /// Pattern parser for match expressions
+fn fold_binary(left: Node<Expr>, (op, right): (BinaryOp, Node<Expr>)) -> Node<Expr> {
+    let span = left.span().merge(right.span());
+    Node::new(
+        Expr::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        },
+        span,
+    )
+}
+
 fn pattern_parser() -> impl Parser<TokenKind, Node<Pattern>, Error = Simple<TokenKind>> {
     recursive(|pattern| {
         let wildcard = just(TokenKind::Identifier("_".to_string()))
@@ -812,6 +1134,30 @@ fn pattern_parser() -> impl Parser<TokenKind, Node<Pattern>, Error = Simple<Toke
             })
             .boxed();
 
+        let range_bound = literal_expr_parser()
+            .try_map(|expr, span| match expr.into_inner() {
+                Expr::Literal(lit) => Ok(lit),
+                _ => Err(Simple::custom(span, "expected a literal range bound")),
+            })
+            .boxed();
+
+        let range_pattern = range_bound
+            .clone()
+            .then_ignore(just(TokenKind::DoubleDot))
+            .then(just(TokenKind::Equals).or_not())
+            .then(range_bound)
+            .map_with_span(|((start, inclusive), end), span| {
+                Node::new(
+                    Pattern::Range {
+                        start,
+                        end,
+                        inclusive: inclusive.is_some(),
+                    },
+                    span,
+                )
+            })
+            .boxed();
+
         let identifier_pattern = identifier_parser()
             .map_with_span(|ident, span| Node::new(Pattern::Identifier(ident), span))
             .boxed();
@@ -885,14 +1231,58 @@ fn pattern_parser() -> impl Parser<TokenKind, Node<Pattern>, Error = Simple<Toke
             })
             .boxed();
 
-        choice((
+        let dict_key = literal_expr_parser()
+            .try_map(|expr, span| match expr.into_inner() {
+                Expr::Literal(lit) => Ok(lit),
+                _ => Err(Simple::custom(span, "expected a literal dict key")),
+            })
+            .boxed();
+
+        let dict_pattern = dict_key
+            .then_ignore(just(TokenKind::Colon))
+            .then(pattern.clone())
+            .separated_by(just(TokenKind::Comma))
+            .allow_trailing()
+            .delimited_by(just(TokenKind::LBrace), just(TokenKind::RBrace))
+            .map_with_span(|entries, span| Node::new(Pattern::Dict { entries }, span))
+            .boxed();
+
+        let simple_pattern = choice((
             wildcard,
+            range_pattern,
             literal_pattern,
             enum_variant_pattern,
             struct_pattern,
             array_pattern,
+            dict_pattern,
             identifier_pattern,
         ))
+        .boxed();
+
+        let binding_pattern = identifier_parser()
+            .then_ignore(just(TokenKind::At))
+            .then(simple_pattern.clone())
+            .map_with_span(|(name, pattern), span| {
+                Node::new(
+                    Pattern::Binding {
+                        name,
+                        pattern: Box::new(pattern),
+                    },
+                    span,
+                )
+            })
+            .boxed();
+
+        choice((binding_pattern, simple_pattern))
+            .separated_by(just(TokenKind::Pipe))
+            .at_least(1)
+            .map_with_span(|mut alternatives, span| {
+                if alternatives.len() == 1 {
+                    alternatives.remove(0)
+                } else {
+                    Node::new(Pattern::Or(alternatives), span)
+                }
+            })
     })
 }
 
@@ -925,6 +1315,17 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .map_with_span(|expr, span| Node::new(Statement::Return(expr), span))
         .boxed();
 
+    let defer_stmt = just(TokenKind::Defer)
+        .ignore_then(expr.clone())
+        .map_with_span(|expr, span| Node::new(Statement::Defer(expr), span))
+        .boxed();
+
+    let assert_stmt = just(TokenKind::Assert)
+        .ignore_then(expr.clone())
+        .then(just(TokenKind::Comma).ignore_then(expr.clone()).or_not())
+        .map_with_span(|(cond, message), span| Node::new(Statement::Assert { cond, message }, span))
+        .boxed();
+
     let pub_keyword = just(TokenKind::Pub).or_not();
 
     let let_stmt = pub_keyword
@@ -1029,6 +1430,42 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .map_with_span(|imports, span| Node::new(Statement::Use { imports }, span))
         .boxed();
 
+    // from statement importing individual items
+    // Syntax: from module import name [as alias], ...
+    //         from module import *
+    // "from" and "import" are contextual so they stay usable as identifiers
+    let import_item = identifier_parser()
+        .then(
+            just(TokenKind::As)
+                .ignore_then(identifier_parser())
+                .or_not(),
+        )
+        .map_with_span(|(name, alias), span| Node::new(ImportItem::new(name, alias), span));
+
+    let from_stmt = just(TokenKind::Identifier("from".to_string()))
+        .ignore_then(module_path.clone())
+        .then_ignore(just(TokenKind::Identifier("import".to_string())))
+        .then(
+            just(TokenKind::Star).to(None).or(import_item
+                .separated_by(just(TokenKind::Comma))
+                .at_least(1)
+                .map(Some)),
+        )
+        .map_with_span(|(module, items), span| {
+            let import = match items {
+                Some(items) => UseImport::with_items(module, items),
+                None => UseImport::glob(module),
+            };
+            let import = Node::new(import, span.clone());
+            Node::new(
+                Statement::Use {
+                    imports: vec![import],
+                },
+                span,
+            )
+        })
+        .boxed();
+
     // pub use statement for re-exports
     // Syntax: pub use otterc_module.item [as alias]
     //         pub use otterc_module (re-export all)
@@ -1061,40 +1498,83 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .boxed();
 
     let break_stmt = just(TokenKind::Break)
-        .map_with_span(|_, span| Node::new(Statement::Break, span))
+        .ignore_then(identifier_parser().map_with_span(Node::new).or_not())
+        .map_with_span(|label, span| Node::new(Statement::Break(label), span))
         .boxed();
     let continue_stmt = just(TokenKind::Continue)
-        .map_with_span(|_, span| Node::new(Statement::Continue, span))
+        .ignore_then(identifier_parser().map_with_span(Node::new).or_not())
+        .map_with_span(|label, span| Node::new(Statement::Continue(label), span))
         .boxed();
     let pass_stmt = just(TokenKind::Pass)
         .map_with_span(|_, span| Node::new(Statement::Pass, span))
         .boxed();
 
-    // Create a recursive parser for statements
-    let statement = recursive(|stmt| {
-        let elif_block = just(TokenKind::Elif)
-            .ignore_then(expr.clone())
-            .then_ignore(just(TokenKind::Colon))
-            .then_ignore(newline.clone())
-            .then(
-                stmt.clone()
-                    .repeated()
-                    .at_least(1)
-                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
-                    .map_with_span(|block, span| Node::new(Block::new(block), span)),
-            )
-            .map(|(cond, block)| (cond, block))
-            .boxed();
-
-        let if_stmt = just(TokenKind::If)
-            .ignore_then(expr.clone())
-            .then_ignore(just(TokenKind::Colon))
-            .then_ignore(newline.clone())
-            .then(
-                stmt.clone()
-                    .repeated()
-                    .at_least(1)
-                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
+    let function_param = identifier_parser()
+        .map_with_span(Node::new)
+        .then(choice((
+            just(TokenKind::Colon).ignore_then(type_parser()).map(Some),
+            empty().to(None),
+        )))
+        .then(choice((
+            just(TokenKind::Equals).ignore_then(expr.clone()).map(Some),
+            empty().to(None),
+        )))
+        .map_with_span(|((name, ty), default), span| Node::new(Param::new(name, ty, default), span))
+        .boxed();
+
+    let function_params = function_param
+        .separated_by(just(TokenKind::Comma))
+        .allow_trailing()
+        .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen))
+        .or_not()
+        .map(|params| params.unwrap_or_default());
+
+    let function_ret_type = just(TokenKind::Arrow).ignore_then(type_parser()).or_not();
+
+    let function_keyword = just(TokenKind::Fn);
+
+    // `fn name(params) -> ret:` up to the body, shared by top-level and nested functions
+    let function_signature = function_keyword
+        .clone()
+        .ignore_then(identifier_parser())
+        .then(function_params)
+        .then(function_ret_type)
+        .then_ignore(just(TokenKind::Colon))
+        .then_ignore(newline.clone())
+        .boxed();
+
+    // Imports that may carry `@cfg(...)` attributes at the top level
+    let attributable_use = use_stmt
+        .clone()
+        .or(from_stmt.clone())
+        .then_ignore(newline.clone().or_not())
+        .boxed();
+
+    // Create a recursive parser for statements
+    let statement = recursive(|stmt| {
+        let elif_block = just(TokenKind::Elif)
+            .ignore_then(expr.clone())
+            .then_ignore(just(TokenKind::Colon))
+            .then_ignore(newline.clone())
+            .then(
+                stmt.clone()
+                    .repeated()
+                    .at_least(1)
+                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
+                    .map_with_span(|block, span| Node::new(Block::new(block), span)),
+            )
+            .map(|(cond, block)| (cond, block))
+            .boxed();
+
+        let if_stmt = just(TokenKind::If)
+            .ignore_then(expr.clone())
+            .then_ignore(just(TokenKind::Colon))
+            .then_ignore(newline.clone())
+            .then(
+                stmt.clone()
+                    .repeated()
+                    .at_least(1)
+                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
                     .map_with_span(|block, span| Node::new(Block::new(block), span)),
             )
             .then(elif_block.repeated())
@@ -1124,8 +1604,18 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
             })
             .boxed();
 
-        let for_stmt = just(TokenKind::For)
-            .ignore_then(identifier_parser().map_with_span(Node::new))
+        // Optional `name:` prefix targeted by `break name` / `continue name`
+        let loop_label = identifier_parser()
+            .map_with_span(Node::new)
+            .then_ignore(just(TokenKind::Colon))
+            .or_not()
+            .boxed();
+
+        let for_stmt = loop_label
+            .clone()
+            .then_ignore(just(TokenKind::For))
+            .then(just(TokenKind::Await).or_not())
+            .then(identifier_parser().map_with_span(Node::new))
             .then_ignore(just(TokenKind::In))
             .then(expr.clone())
             .then_ignore(just(TokenKind::Colon))
@@ -1137,20 +1627,44 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
                     .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
                     .map_with_span(|block, span| Node::new(Block::new(block), span)),
             )
-            .map_with_span(|((var, iterable), body), span| {
+            .map_with_span(|((((label, await_kw), var), iterable), body), span| {
                 Node::new(
                     Statement::For {
                         var,
                         iterable,
                         body,
+                        label,
+                        is_await: await_kw.is_some(),
                     },
                     span,
                 )
             })
             .boxed();
 
-        let while_stmt = just(TokenKind::While)
+        let while_stmt = loop_label
+            .then_ignore(just(TokenKind::While))
+            .then(expr.clone())
+            .then_ignore(just(TokenKind::Colon))
+            .then_ignore(newline.clone())
+            .then(
+                stmt.clone()
+                    .repeated()
+                    .at_least(1)
+                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
+                    .map_with_span(|block, span| Node::new(Block::new(block), span)),
+            )
+            .map_with_span(|((label, cond), body), span| {
+                Node::new(Statement::While { cond, body, label }, span)
+            })
+            .boxed();
+
+        let with_stmt = just(TokenKind::With)
             .ignore_then(expr.clone())
+            .then(
+                just(TokenKind::As)
+                    .ignore_then(identifier_parser().map_with_span(Node::new))
+                    .or_not(),
+            )
             .then_ignore(just(TokenKind::Colon))
             .then_ignore(newline.clone())
             .then(
@@ -1160,7 +1674,25 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
                     .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
                     .map_with_span(|block, span| Node::new(Block::new(block), span)),
             )
-            .map_with_span(|(cond, body), span| Node::new(Statement::While { cond, body }, span))
+            .map_with_span(|((expr, alias), body), span| {
+                Node::new(Statement::With { expr, alias, body }, span)
+            })
+            .boxed();
+
+        // Functions defined inside a function body are only visible within it
+        let nested_function = function_signature
+            .clone()
+            .then(
+                stmt.clone()
+                    .repeated()
+                    .at_least(1)
+                    .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent))
+                    .map_with_span(|block, span| Node::new(Block::new(block), span)),
+            )
+            .map_with_span(|(((name, params), ret_ty), body), span| {
+                Node::new(Function::new(name, params, ret_ty, body), span)
+            })
+            .map_with_span(|func, span| Node::new(Statement::Function(func), span))
             .boxed();
 
         // Exception handling (try/except/finally/raise) removed - use Result<T, E> pattern matching instead
@@ -1168,17 +1700,22 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         choice((
             print_stmt,
             return_stmt,
+            defer_stmt,
+            assert_stmt,
             let_stmt,
             compound_assignment_stmt,
             simple_assignment_stmt,
             use_stmt,
+            from_stmt,
             pub_use_stmt,
             if_stmt,
             for_stmt,
             while_stmt,
+            with_stmt,
             break_stmt,
             continue_stmt,
             pass_stmt,
+            nested_function,
             expr.clone()
                 .map_with_span(|expr, span| Node::new(Statement::Expr(expr), span)),
         ))
@@ -1194,40 +1731,11 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .map_with_span(|block, span| Node::new(Block::new(block), span))
         .boxed();
 
-    let function_param = identifier_parser()
-        .map_with_span(Node::new)
-        .then(choice((
-            just(TokenKind::Colon).ignore_then(type_parser()).map(Some),
-            empty().to(None),
-        )))
-        .then(choice((
-            just(TokenKind::Equals).ignore_then(expr.clone()).map(Some),
-            empty().to(None),
-        )))
-        .map_with_span(|((name, ty), default), span| Node::new(Param::new(name, ty, default), span))
-        .boxed();
-
-    let function_params = function_param
-        .separated_by(just(TokenKind::Comma))
-        .allow_trailing()
-        .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen))
-        .or_not()
-        .map(|params| params.unwrap_or_default());
-
-    let function_ret_type = just(TokenKind::Arrow).ignore_then(type_parser()).or_not();
-
-    let function_keyword = just(TokenKind::Fn);
-
     let function = pub_keyword
         .clone()
-        .then(function_keyword.clone())
-        .then(identifier_parser())
-        .then(function_params)
-        .then(function_ret_type)
-        .then_ignore(just(TokenKind::Colon))
-        .then_ignore(newline.clone())
+        .then(function_signature)
         .then(block.clone())
-        .map_with_span(|(((((pub_kw, _fn), name), params), ret_ty), body), span| {
+        .map_with_span(|((pub_kw, ((name, params), ret_ty)), body), span| {
             Node::new(
                 if pub_kw.is_some() {
                     Function::new_public(name, params, ret_ty, body)
@@ -1241,16 +1749,88 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .then_ignore(newline.clone().or_not())
         .boxed();
 
+    // Functions and imports guarded by `@cfg(...)`, where stacked attributes
+    // must all hold, and functions marked `@inline` or `@noinline`
+    let attributed_item = choice((
+        cfg_attribute_parser().map(ItemAttribute::Cfg),
+        inline_attribute_parser().map(ItemAttribute::Inline),
+    ))
+    .repeated()
+    .at_least(1)
+    .then(function.clone().or(attributable_use))
+    .try_map(|(attributes, mut statement), span: Range<usize>| {
+        let mut predicates = Vec::new();
+        let mut inline: Option<InlineHint> = None;
+        for attribute in attributes {
+            match attribute {
+                ItemAttribute::Cfg(predicate) => predicates.push(predicate),
+                ItemAttribute::Inline(hint) if inline.is_some_and(|other| other != hint) => {
+                    return Err(Simple::custom(
+                        span,
+                        "`@inline` and `@noinline` cannot both apply to a function",
+                    ));
+                }
+                ItemAttribute::Inline(hint) => inline = Some(hint),
+            }
+        }
+        let cfg = if predicates.len() > 1 {
+            Some(CfgPredicate::All(predicates))
+        } else {
+            predicates.pop()
+        };
+        match statement.as_mut() {
+            Statement::Function(function) => {
+                function.as_mut().cfg = cfg;
+                function.as_mut().inline = inline;
+            }
+            Statement::Use { .. } if inline.is_some() => {
+                return Err(Simple::custom(
+                    span,
+                    "`@inline` and `@noinline` only apply to functions",
+                ));
+            }
+            Statement::Use { imports } => {
+                for import in imports {
+                    import.as_mut().cfg = cfg.clone();
+                }
+            }
+            _ => {}
+        }
+        Ok(statement)
+    })
+    .boxed();
+
     //     field: Type
     //     fn method(self, ...) -> ReturnType:
     //         ...
+    // `<T, U: Numeric + Describable>`, split into the parameter names and the
+    // bounds of the parameters that have any
     let struct_generics = || {
+        let bound = identifier_parser().map_with_span(Node::new);
         identifier_parser()
+            .then(
+                just(TokenKind::Colon)
+                    .ignore_then(bound.separated_by(just(TokenKind::Plus)).at_least(1))
+                    .or_not(),
+            )
             .separated_by(just(TokenKind::Comma))
             .allow_trailing()
             .delimited_by(just(TokenKind::Lt), just(TokenKind::Gt))
             .or_not()
-            .map(|params| params.unwrap_or_default())
+            .map(|params| {
+                let mut generics = Vec::new();
+                let mut bounds = Vec::new();
+                for (param, param_bounds) in params.unwrap_or_default() {
+                    if let Some(param_bounds) = param_bounds {
+                        bounds.push(GenericBound {
+                            param: param.clone(),
+                            bounds: param_bounds,
+                        });
+                    }
+                    generics.push(param);
+                }
+                (generics, bounds)
+            })
     };
 
     let enum_variant_name = choice((
@@ -1258,11 +1838,18 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         just(TokenKind::None).to("None".to_string()),
     ));
 
+    // Variant fields are either all positional, `Circle: (float)`, or all
+    // named, `Circle: (radius: float)`
+    let enum_variant_field = identifier_parser()
+        .then_ignore(just(TokenKind::Colon))
+        .or_not()
+        .then(type_parser());
+
     let enum_variant = enum_variant_name
         .then(
             just(TokenKind::Colon)
                 .ignore_then(
-                    type_parser()
+                    enum_variant_field
                         .separated_by(just(TokenKind::Comma))
                         .allow_trailing()
                         .delimited_by(just(TokenKind::LParen), just(TokenKind::RParen)),
@@ -1270,17 +1857,24 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
                 .or_not(),
         )
         .then_ignore(newline.clone().or_not())
-        .map_with_span(|(name, fields), span| {
-            Node::new(EnumVariant::new(name, fields.unwrap_or_default()), span)
+        .try_map(|(name, fields), span: Range<usize>| {
+            let fields = fields.unwrap_or_default();
+            let named = fields.iter().filter(|(field, _)| field.is_some()).count();
+            if named != 0 && named != fields.len() {
+                return Err(Simple::custom(
+                    span,
+                    format!(
+                        "fields of enum variant '{name}' must all be named or all be positional"
+                    ),
+                ));
+            }
+            let (field_names, fields): (Vec<_>, Vec<_>) = fields.into_iter().unzip();
+            let variant = EnumVariant::new(name, fields)
+                .with_field_names(field_names.into_iter().flatten().collect());
+            Ok(Node::new(variant, span))
         })
         .boxed();
 
-    let enum_body = enum_variant
-        .repeated()
-        .at_least(1)
-        .then_ignore(newline.clone().or_not())
-        .boxed();
-
     let struct_field = identifier_parser()
         .then_ignore(just(TokenKind::Colon))
         .then(type_parser())
@@ -1323,6 +1917,32 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
 
     let method_function_ret_type = just(TokenKind::Arrow).ignore_then(type_parser()).or_not();
 
+    // Protocol methods are signatures without a body. `self` is implied, so
+    // it is dropped when written out.
+    let protocol_method = function_keyword
+        .clone()
+        .then(identifier_parser())
+        .then(method_function_params.clone())
+        .then(just(TokenKind::Arrow).ignore_then(type_parser()).or_not())
+        .then_ignore(newline.clone().or_not())
+        .map_with_span(|(((_kw, name), mut params), ret_ty), span| {
+            if params
+                .first()
+                .is_some_and(|param| param.as_ref().name.as_ref() == "self")
+            {
+                params.remove(0);
+            }
+            Node::new(
+                ProtocolMethod {
+                    name,
+                    params,
+                    ret_ty,
+                },
+                span,
+            )
+        })
+        .boxed();
+
     let struct_method_def = function_keyword
         .clone()
         .then(identifier_parser())
@@ -1355,6 +1975,25 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .then_ignore(newline.clone().or_not())
         .boxed();
 
+    // Enum bodies hold variants and methods
+    let enum_body = choice((
+        enum_variant.map(|variant| (Some(variant), None)),
+        struct_method_def.clone().map(|(_, method)| (None, method)),
+    ))
+    .repeated()
+    .at_least(1)
+    .then_ignore(newline.clone().or_not())
+    .map(|items| {
+        let mut variants = Vec::new();
+        let mut methods = Vec::new();
+        for (variant, method) in items {
+            variants.extend(variant);
+            methods.extend(method);
+        }
+        (variants, methods)
+    })
+    .boxed();
+
     let struct_body = choice((struct_field_def, struct_method_def))
         .repeated()
         .at_least(0)
@@ -1373,8 +2012,10 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
             (fields, methods)
         });
 
-    let struct_def = pub_keyword
-        .clone()
+    let struct_def = derive_attribute_parser()
+        .repeated()
+        .flatten()
+        .then(pub_keyword.clone())
         .then(just(TokenKind::Struct))
         .then(identifier_parser())
         .then(struct_generics())
@@ -1383,7 +2024,7 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .then(struct_body.delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent)))
         .then_ignore(newline.clone().or_not())
         .map_with_span(
-            |((((pub_kw, _), name), generics), (fields, methods)), span| {
+            |(((((derives, pub_kw), _), name), (generics, bounds)), (fields, methods)), span| {
                 Node::new(
                     Statement::Struct {
                         name,
@@ -1391,6 +2032,8 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
                         methods,
                         public: pub_kw.is_some(),
                         generics,
+                        bounds,
+                        derives,
                     },
                     span,
                 )
@@ -1398,8 +2041,10 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         )
         .boxed();
 
-    let enum_def = pub_keyword
-        .clone()
+    let enum_def = derive_attribute_parser()
+        .repeated()
+        .flatten()
+        .then(pub_keyword.clone())
         .then(just(TokenKind::Enum))
         .then(identifier_parser())
         .then(struct_generics())
@@ -1407,13 +2052,44 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         .then_ignore(newline.clone())
         .then(enum_body.delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent)))
         .then_ignore(newline.clone().or_not())
-        .map_with_span(|((((pub_kw, _), name), generics), variants), span| {
+        .map_with_span(
+            |(((((derives, pub_kw), _), name), (generics, bounds)), (variants, methods)), span| {
+                Node::new(
+                    Statement::Enum {
+                        name,
+                        variants,
+                        methods,
+                        public: pub_kw.is_some(),
+                        generics,
+                        bounds,
+                        derives,
+                    },
+                    span,
+                )
+            },
+        )
+        .boxed();
+
+    let protocol_def = pub_keyword
+        .clone()
+        .then(just(TokenKind::Identifier("protocol".to_string())))
+        .then(identifier_parser())
+        .then_ignore(just(TokenKind::Colon))
+        .then_ignore(newline.clone())
+        .then(
+            protocol_method
+                .repeated()
+                .at_least(1)
+                .then_ignore(newline.clone().or_not())
+                .delimited_by(just(TokenKind::Indent), just(TokenKind::Dedent)),
+        )
+        .then_ignore(newline.clone().or_not())
+        .map_with_span(|(((pub_kw, _), name), methods), span| {
             Node::new(
-                Statement::Enum {
+                Statement::Protocol {
                     name,
-                    variants,
+                    methods,
                     public: pub_kw.is_some(),
-                    generics,
                 },
                 span,
             )
@@ -1450,10 +2126,53 @@ fn program_parser() -> impl Parser<TokenKind, Program, Error = Simple<TokenKind>
         })
         .boxed();
 
+    let const_def = pub_keyword
+        .clone()
+        .then(just(TokenKind::Identifier("const".to_string())))
+        .then(identifier_parser().map_with_span(Node::new))
+        .then(just(TokenKind::Colon).ignore_then(type_parser()).or_not())
+        .then_ignore(just(TokenKind::Equals))
+        .then(expr.clone())
+        .then_ignore(newline.clone().or_not())
+        .map_with_span(|((((pub_kw, _), name), ty), expr), span| {
+            Node::new(
+                Statement::Const {
+                    name,
+                    ty,
+                    expr,
+                    public: pub_kw.is_some(),
+                },
+                span,
+            )
+        })
+        .boxed();
+
+    // `init` is only a keyword at the top level, followed by a block
+    let init_block = just(TokenKind::Identifier("init".to_string()))
+        .then_ignore(just(TokenKind::Colon))
+        .then_ignore(newline.clone())
+        .ignore_then(block)
+        .map_with_span(|body, span| Node::new(Statement::Init(body), span))
+        .then_ignore(newline.clone().or_not())
+        .boxed();
+
     newline
         .clone()
         .or_not()
-        .ignore_then(choice((struct_def, enum_def, type_alias_def, function, statement)).repeated())
+        .ignore_then(
+            choice((
+                struct_def,
+                enum_def,
+                protocol_def,
+                type_alias_def,
+                const_def,
+                attributed_item,
+                function,
+                init_block,
+                statement,
+            ))
+            .repeated(),
+        )
         .then_ignore(newline.repeated().or_not())
         .then_ignore(just(TokenKind::Eof))
         .map(Program::new)
@@ -1484,6 +2203,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_from_import_items() {
+        let source = "from math import sin, cos as cosine\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize from import");
+        let program = parse(&tokens).expect("parse from import");
+
+        assert_eq!(program.statements.len(), 1);
+        match &program.statements[0].as_ref() {
+            Statement::Use { imports } => {
+                assert_eq!(imports.len(), 1);
+                let import = imports[0].as_ref();
+                assert_eq!(import.module, "math");
+                let items: Vec<_> = import
+                    .items
+                    .iter()
+                    .map(|item| (item.as_ref().name.as_str(), item.as_ref().local_name()))
+                    .collect();
+                assert_eq!(items, [("sin", "sin"), ("cos", "cosine")]);
+            }
+            other => panic!("expected use statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_from_import_glob() {
+        let source = "from utils import *\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize glob import");
+        let program = parse(&tokens).expect("parse glob import");
+
+        match &program.statements[0].as_ref() {
+            Statement::Use { imports } => {
+                let import = imports[0].as_ref();
+                assert_eq!(import.module, "utils");
+                assert!(import.glob);
+                assert!(import.items.is_empty());
+            }
+            other => panic!("expected use statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_cfg_attributes() {
+        let source = "@cfg(os=\"linux\")\nuse sys\n\n@cfg(not(feature=\"gpu\"))\n@cfg(any(arch=\"x86_64\", arch=\"aarch64\"))\nfn render():\n    pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize cfg attributes");
+        let program = parse(&tokens).expect("parse cfg attributes");
+
+        assert_eq!(program.statements.len(), 2);
+        match &program.statements[0].as_ref() {
+            Statement::Use { imports } => {
+                let cfg = imports[0]
+                    .as_ref()
+                    .cfg
+                    .as_ref()
+                    .expect("use should carry cfg");
+                assert_eq!(cfg.to_string(), "os=\"linux\"");
+            }
+            other => panic!("expected use statement, got {:?}", other),
+        }
+        match &program.statements[1].as_ref() {
+            Statement::Function(function) => {
+                let cfg = function.as_ref().cfg.as_ref().expect("fn should carry cfg");
+                assert_eq!(
+                    cfg.to_string(),
+                    "all(not(feature=\"gpu\"), any(arch=\"x86_64\", arch=\"aarch64\"))"
+                );
+            }
+            other => panic!("expected function, got {:?}", other),
+        }
+
+        let unknown = otterc_lexer::tokenize("@cfg(planet=\"mars\")\nfn main():\n    pass\n")
+            .expect("tokenize unknown cfg key");
+        assert!(parse(&unknown).is_err());
+    }
+
+    #[test]
+    fn parses_inline_attributes() {
+        let source = "@inline\nfn square(x: int) -> int:\n    return x * x\n\n@cfg(os=\"linux\")\n@noinline\nfn cold():\n    pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize inline attributes");
+        let program = parse(&tokens).expect("parse inline attributes");
+
+        let hints: Vec<_> = program
+            .statements
+            .iter()
+            .map(|statement| match statement.as_ref() {
+                Statement::Function(function) => {
+                    (function.as_ref().inline, function.as_ref().cfg.is_some())
+                }
+                other => panic!("expected function, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                (Some(InlineHint::Always), false),
+                (Some(InlineHint::Never), true)
+            ]
+        );
+
+        for source in [
+            "@inline\n@noinline\nfn main():\n    pass\n",
+            "@inline\nuse math\n",
+        ] {
+            let tokens = otterc_lexer::tokenize(source).expect("tokenize misplaced inline");
+            assert!(parse(&tokens).is_err());
+        }
+    }
+
     #[test]
     fn parses_otter_namespace_use() {
         let source = "use otter:core\n";
@@ -1513,4 +2339,677 @@ mod tests {
         let tokens = otterc_lexer::tokenize(source).expect("tokenize enum demo");
         parse(&tokens).expect("parse enum demo");
     }
+
+    #[test]
+    fn parses_with_statement() {
+        let source =
+            "fn main():\n    with open_file(\"log.txt\") as fh:\n        fh.write(\"hi\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize with statement");
+        let program = parse(&tokens).expect("parse with statement");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        match func.as_ref().body.as_ref().statements[0].as_ref() {
+            Statement::With { alias, body, .. } => {
+                assert_eq!(
+                    alias.as_ref().map(|alias| alias.as_ref().as_str()),
+                    Some("fh")
+                );
+                assert_eq!(body.as_ref().statements.len(), 1);
+            }
+            other => panic!("expected with statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_defer_statement() {
+        let source = "fn main():\n    defer close(conn)\n    print(\"working\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize defer statement");
+        let program = parse(&tokens).expect("parse defer statement");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        match func.as_ref().body.as_ref().statements[0].as_ref() {
+            Statement::Defer(expr) => {
+                assert!(matches!(expr.as_ref(), Expr::Call { .. }));
+            }
+            other => panic!("expected defer statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_assert_statement() {
+        let source = "fn main():\n    assert x > 0, \"x must be positive\"\n    assert ready\n    test.assert(ready, \"still a call\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize assert statement");
+        let program = parse(&tokens).expect("parse assert statement");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let statements = &func.as_ref().body.as_ref().statements;
+        assert!(matches!(
+            statements[0].as_ref(),
+            Statement::Assert {
+                message: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            statements[1].as_ref(),
+            Statement::Assert { message: None, .. }
+        ));
+        assert!(matches!(statements[2].as_ref(), Statement::Expr(_)));
+    }
+
+    #[test]
+    fn parses_init_block() {
+        let source = "init:\n    print(\"ready\")\nlet init = 1\nfn main():\n    pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize init block");
+        let program = parse(&tokens).expect("parse init block");
+
+        assert_eq!(program.statements.len(), 3);
+        let Statement::Init(body) = program.statements[0].as_ref() else {
+            panic!("expected init block, got {:?}", program.statements[0]);
+        };
+        assert_eq!(body.as_ref().statements.len(), 1);
+        assert!(matches!(
+            program.statements[1].as_ref(),
+            Statement::Let { .. }
+        ));
+    }
+
+    #[test]
+    fn parses_labeled_loops() {
+        let source = "fn main():\n    outer: for i in 0..3:\n        while true:\n            if i == 1:\n                continue outer\n            break outer\n    break\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize labeled loops");
+        let program = parse(&tokens).expect("parse labeled loops");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let statements = &func.as_ref().body.as_ref().statements;
+        let Statement::For { label, body, .. } = statements[0].as_ref() else {
+            panic!("expected for loop, got {:?}", statements[0]);
+        };
+        assert_eq!(
+            label.as_ref().map(|label| label.as_ref().as_str()),
+            Some("outer")
+        );
+        let Statement::While { label, body, .. } = body.as_ref().statements[0].as_ref() else {
+            panic!("expected while loop, got {:?}", body.as_ref().statements[0]);
+        };
+        assert!(label.is_none());
+        assert!(matches!(
+            body.as_ref().statements[1].as_ref(),
+            Statement::Break(Some(label)) if label.as_ref() == "outer"
+        ));
+        assert!(matches!(statements[1].as_ref(), Statement::Break(None)));
+    }
+
+    #[test]
+    fn parses_match_guards() {
+        let source = "fn main():\n    match n:\n        case x if x > 0 and x < 10:\n            print(\"digit\")\n        case _:\n            print(\"other\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize match guards");
+        let program = parse(&tokens).expect("parse match guards");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let Statement::Expr(expr) = func.as_ref().body.as_ref().statements[0].as_ref() else {
+            panic!("expected match expression");
+        };
+        let Expr::Match { arms, .. } = expr.as_ref() else {
+            panic!("expected match, got {:?}", expr);
+        };
+        assert!(matches!(
+            arms[0].as_ref().guard.as_ref().map(|guard| guard.as_ref()),
+            Some(Expr::Binary {
+                op: BinaryOp::And,
+                ..
+            })
+        ));
+        assert!(arms[1].as_ref().guard.is_none());
+    }
+
+    #[test]
+    fn parses_or_patterns() {
+        let source = "fn main():\n    match n:\n        case 1 | 2 | 3:\n            print(\"small\")\n        case Color.Red | Color.Blue:\n            print(\"color\")\n        case _:\n            print(\"other\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize or-patterns");
+        let program = parse(&tokens).expect("parse or-patterns");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let Statement::Expr(expr) = func.as_ref().body.as_ref().statements[0].as_ref() else {
+            panic!("expected match expression");
+        };
+        let Expr::Match { arms, .. } = expr.as_ref() else {
+            panic!("expected match, got {:?}", expr);
+        };
+        let Pattern::Or(numbers) = arms[0].as_ref().pattern.as_ref() else {
+            panic!("expected or-pattern, got {:?}", arms[0].as_ref().pattern);
+        };
+        assert_eq!(numbers.len(), 3);
+        let Pattern::Or(colors) = arms[1].as_ref().pattern.as_ref() else {
+            panic!("expected or-pattern, got {:?}", arms[1].as_ref().pattern);
+        };
+        assert!(matches!(
+            colors[1].as_ref(),
+            Pattern::EnumVariant { variant, .. } if variant == "Blue"
+        ));
+        assert!(matches!(
+            arms[2].as_ref().pattern.as_ref(),
+            Pattern::Wildcard
+        ));
+    }
+
+    #[test]
+    fn parses_binding_patterns() {
+        let source = "fn main():\n    match shape:\n        case s @ Shape.Circle(r):\n            print(\"circle\")\n        case n @ 0:\n            print(\"zero\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize binding patterns");
+        let program = parse(&tokens).expect("parse binding patterns");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let Statement::Expr(expr) = func.as_ref().body.as_ref().statements[0].as_ref() else {
+            panic!("expected match expression");
+        };
+        let Expr::Match { arms, .. } = expr.as_ref() else {
+            panic!("expected match, got {:?}", expr);
+        };
+        let Pattern::Binding { name, pattern } = arms[0].as_ref().pattern.as_ref() else {
+            panic!(
+                "expected binding pattern, got {:?}",
+                arms[0].as_ref().pattern
+            );
+        };
+        assert_eq!(name, "s");
+        assert!(matches!(
+            pattern.as_ref().as_ref(),
+            Pattern::EnumVariant { variant, .. } if variant == "Circle"
+        ));
+        assert_eq!(arms[0].as_ref().pattern.as_ref().bindings(), ["s", "r"]);
+        assert!(matches!(
+            arms[1].as_ref().pattern.as_ref(),
+            Pattern::Binding { pattern, .. } if matches!(pattern.as_ref().as_ref(), Pattern::Literal(_))
+        ));
+    }
+
+    #[test]
+    fn parses_range_patterns() {
+        let source = "fn main():\n    match code:\n        case 200..300:\n            print(\"ok\")\n        case n @ 400..=499:\n            print(\"client\")\n        case 0.5..1.5:\n            print(\"float\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize range patterns");
+        let program = parse(&tokens).expect("parse range patterns");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let Statement::Expr(expr) = func.as_ref().body.as_ref().statements[0].as_ref() else {
+            panic!("expected match expression");
+        };
+        let Expr::Match { arms, .. } = expr.as_ref() else {
+            panic!("expected match, got {:?}", expr);
+        };
+        let Pattern::Range {
+            start,
+            end,
+            inclusive,
+        } = arms[0].as_ref().pattern.as_ref()
+        else {
+            panic!("expected range pattern, got {:?}", arms[0].as_ref().pattern);
+        };
+        assert!(matches!(start.as_ref(), Literal::Number(n) if n.value == 200.0));
+        assert!(matches!(end.as_ref(), Literal::Number(n) if n.value == 300.0));
+        assert!(!inclusive);
+        assert!(matches!(
+            arms[1].as_ref().pattern.as_ref(),
+            Pattern::Binding { pattern, .. }
+                if matches!(pattern.as_ref().as_ref(), Pattern::Range { inclusive: true, .. })
+        ));
+        assert!(matches!(
+            arms[2].as_ref().pattern.as_ref(),
+            Pattern::Range { start, .. } if matches!(start.as_ref(), Literal::Number(n) if n.is_float_literal)
+        ));
+    }
+
+    #[test]
+    fn parses_dict_patterns() {
+        let source = "fn main():\n    match response:\n        case {\"status\": s, \"code\": 200}:\n            print(s)\n        case {}:\n            print(\"any\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize dict patterns");
+        let program = parse(&tokens).expect("parse dict patterns");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let Statement::Expr(expr) = func.as_ref().body.as_ref().statements[0].as_ref() else {
+            panic!("expected match expression");
+        };
+        let Expr::Match { arms, .. } = expr.as_ref() else {
+            panic!("expected match, got {:?}", expr);
+        };
+        let Pattern::Dict { entries } = arms[0].as_ref().pattern.as_ref() else {
+            panic!("expected dict pattern, got {:?}", arms[0].as_ref().pattern);
+        };
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].0.as_ref(), Literal::String(key) if key == "status"));
+        assert!(matches!(entries[0].1.as_ref(), Pattern::Identifier(name) if name == "s"));
+        assert!(matches!(
+            entries[1].1.as_ref(),
+            Pattern::Literal(lit) if matches!(lit.as_ref(), Literal::Number(n) if n.value == 200.0)
+        ));
+        assert!(matches!(
+            arms[1].as_ref().pattern.as_ref(),
+            Pattern::Dict { entries } if entries.is_empty()
+        ));
+        assert_eq!(arms[0].as_ref().pattern.as_ref().bindings(), ["s"]);
+    }
+
+    #[test]
+    fn parses_bitwise_operators() {
+        let source =
+            "let x = a | b ^ c & d << 1 + 2\nlet y = ~a >> 3\nlet z: List<List<int>> = []\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize bitwise operators");
+        let program = parse(&tokens).expect("parse bitwise operators");
+
+        let Statement::Let { expr, .. } = program.statements[0].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[0]);
+        };
+        let Expr::Binary {
+            op: BinaryOp::BitOr,
+            right,
+            ..
+        } = expr.as_ref()
+        else {
+            panic!("expected `|` at the top, got {:?}", expr);
+        };
+        let Expr::Binary {
+            op: BinaryOp::BitXor,
+            right,
+            ..
+        } = right.as_ref().as_ref()
+        else {
+            panic!("expected `^` under `|`, got {:?}", right);
+        };
+        let Expr::Binary {
+            op: BinaryOp::BitAnd,
+            right,
+            ..
+        } = right.as_ref().as_ref()
+        else {
+            panic!("expected `&` under `^`, got {:?}", right);
+        };
+        assert!(matches!(
+            right.as_ref().as_ref(),
+            Expr::Binary {
+                op: BinaryOp::Shl,
+                ..
+            }
+        ));
+
+        let Statement::Let { expr, .. } = program.statements[1].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[1]);
+        };
+        let Expr::Binary {
+            op: BinaryOp::Shr,
+            left,
+            ..
+        } = expr.as_ref()
+        else {
+            panic!("expected `>>`, got {:?}", expr);
+        };
+        assert!(matches!(
+            left.as_ref().as_ref(),
+            Expr::Unary {
+                op: UnaryOp::BitNot,
+                ..
+            }
+        ));
+        assert_eq!(program.statements.len(), 3);
+    }
+
+    #[test]
+    fn parses_for_await_loops() {
+        let source = "for await line in lines:\n    print(line)\nfor x in xs:\n    print(x)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize for await");
+        let program = parse(&tokens).expect("parse for await");
+        let awaits: Vec<bool> = program
+            .statements
+            .iter()
+            .map(|statement| match statement.as_ref() {
+                Statement::For { is_await, .. } => *is_await,
+                other => panic!("expected for loop, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(awaits, vec![true, false]);
+    }
+
+    #[test]
+    fn parses_casts() {
+        let source = "let x = -n as f64 * 2.0\nwith open(path) as file:\n    print(file)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize casts");
+        let program = parse(&tokens).expect("parse casts");
+
+        let Statement::Let { expr, .. } = program.statements[0].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[0]);
+        };
+        let Expr::Binary {
+            op: BinaryOp::Mul,
+            left,
+            ..
+        } = expr.as_ref()
+        else {
+            panic!("expected `*` at the top, got {:?}", expr);
+        };
+        let Expr::Cast { expr: inner, ty } = left.as_ref().as_ref() else {
+            panic!("expected a cast, got {:?}", left);
+        };
+        assert!(matches!(ty.as_ref(), Type::Simple(name) if name == "f64"));
+        assert!(matches!(
+            inner.as_ref().as_ref(),
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                ..
+            }
+        ));
+
+        let Statement::With { alias, .. } = program.statements[1].as_ref() else {
+            panic!("expected with, got {:?}", program.statements[1]);
+        };
+        assert_eq!(
+            alias.as_ref().map(|alias| alias.as_ref().as_str()),
+            Some("file")
+        );
+    }
+
+    #[test]
+    fn parses_union_types() {
+        let source = "fn show(value: int | str | None) -> list<int | float>:\n    pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize unions");
+        let program = parse(&tokens).expect("parse unions");
+
+        let Statement::Function(func) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let param_ty = func.as_ref().params[0]
+            .as_ref()
+            .ty
+            .as_ref()
+            .expect("annotated");
+        let Type::Union(members) = param_ty.as_ref() else {
+            panic!("expected a union, got {:?}", param_ty);
+        };
+        let names: Vec<_> = members
+            .iter()
+            .map(|member| match member.as_ref() {
+                Type::Simple(name) => name.as_str(),
+                other => panic!("expected a simple type, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(names, ["int", "str", "None"]);
+
+        let ret_ty = func.as_ref().ret_ty.as_ref().expect("return type");
+        assert!(matches!(
+            ret_ty.as_ref(),
+            Type::Generic { base, args }
+                if base == "list" && matches!(args[0].as_ref(), Type::Union(members) if members.len() == 2)
+        ));
+    }
+
+    #[test]
+    fn parses_const_declarations() {
+        let source = "pub const LIMIT: u8 = 2 * 100\nconst NAME = \"otter\"\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize consts");
+        let program = parse(&tokens).expect("parse consts");
+
+        let Statement::Const {
+            name, ty, public, ..
+        } = program.statements[0].as_ref()
+        else {
+            panic!("expected const, got {:?}", program.statements[0]);
+        };
+        assert_eq!(name.as_ref(), "LIMIT");
+        assert!(*public);
+        assert!(matches!(
+            ty.as_ref().map(|ty| ty.as_ref()),
+            Some(Type::Simple(ty)) if ty == "u8"
+        ));
+
+        let Statement::Const {
+            name, ty, public, ..
+        } = program.statements[1].as_ref()
+        else {
+            panic!("expected const, got {:?}", program.statements[1]);
+        };
+        assert_eq!(name.as_ref(), "NAME");
+        assert!(ty.is_none());
+        assert!(!*public);
+    }
+
+    #[test]
+    fn parses_derive_attributes() {
+        let source = "@derive(json, eq)\n@derive(debug)\npub struct Point:\n    x: int\n\n\
+                      @derive(eq)\nenum Color:\n    Red\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize derive");
+        let program = parse(&tokens).expect("parse derive");
+
+        let Statement::Struct {
+            public, derives, ..
+        } = program.statements[0].as_ref()
+        else {
+            panic!("expected struct, got {:?}", program.statements[0]);
+        };
+        assert!(*public);
+        let derives: Vec<Derive> = derives.iter().map(|derive| *derive.as_ref()).collect();
+        assert_eq!(derives, [Derive::Json, Derive::Eq, Derive::Debug]);
+
+        let Statement::Enum { derives, .. } = program.statements[1].as_ref() else {
+            panic!("expected enum, got {:?}", program.statements[1]);
+        };
+        assert_eq!(derives.len(), 1);
+
+        let unknown = otterc_lexer::tokenize("@derive(hash)\nstruct Point:\n    x: int\n")
+            .expect("tokenize unknown derive");
+        assert!(parse(&unknown).is_err());
+    }
+
+    #[test]
+    fn parses_generic_bounds() {
+        let source =
+            "struct Stats<K, T: Numeric + Describable>:\n    key: K\n    values: list<T>\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize bounds");
+        let program = parse(&tokens).expect("parse bounds");
+
+        let Statement::Struct {
+            generics, bounds, ..
+        } = program.statements[0].as_ref()
+        else {
+            panic!("expected struct, got {:?}", program.statements[0]);
+        };
+        assert_eq!(generics, &["K".to_string(), "T".to_string()]);
+        assert_eq!(bounds.len(), 1);
+        assert_eq!(bounds[0].param, "T");
+        let names: Vec<&str> = bounds[0]
+            .bounds
+            .iter()
+            .map(|bound| bound.as_ref().as_str())
+            .collect();
+        assert_eq!(names, ["Numeric", "Describable"]);
+    }
+
+    #[test]
+    fn parses_enum_named_fields_and_methods() {
+        let source = "enum Shape:\n    Circle: (radius: float)\n    Point\n    fn area(self) -> float:\n        return 1.0\n\nlet shape = Shape.Circle(radius=2.0)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize enum");
+        let program = parse(&tokens).expect("parse enum");
+
+        let Statement::Enum {
+            variants, methods, ..
+        } = program.statements[0].as_ref()
+        else {
+            panic!("expected enum, got {:?}", program.statements[0]);
+        };
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].as_ref().field_names, vec!["radius".to_string()]);
+        assert!(variants[1].as_ref().field_names.is_empty());
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].as_ref().name, "area");
+
+        let Statement::Let { expr, .. } = program.statements[1].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[1]);
+        };
+        assert!(matches!(
+            expr.as_ref(),
+            Expr::Struct { name, fields } if name == "Shape.Circle" && fields[0].0 == "radius"
+        ));
+    }
+
+    #[test]
+    fn parses_protocol_signatures() {
+        let source = "pub protocol Reader:\n    fn read(self, n: int) -> str\n    fn close()\n\nlet protocol = 1\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize protocol");
+        let program = parse(&tokens).expect("parse protocol");
+
+        let Statement::Protocol {
+            name,
+            methods,
+            public,
+        } = program.statements[0].as_ref()
+        else {
+            panic!("expected protocol, got {:?}", program.statements[0]);
+        };
+        assert_eq!(name, "Reader");
+        assert!(*public);
+        assert_eq!(methods.len(), 2);
+        let read = methods[0].as_ref();
+        assert_eq!(read.name, "read");
+        assert_eq!(read.params.len(), 1);
+        assert_eq!(read.params[0].as_ref().name.as_ref(), "n");
+        assert!(read.ret_ty.is_some());
+        assert!(methods[1].as_ref().params.is_empty());
+        assert!(matches!(
+            program.statements[1].as_ref(),
+            Statement::Let { name, .. } if name.as_ref() == "protocol"
+        ));
+    }
+
+    #[test]
+    fn rejects_enum_variants_mixing_named_and_positional_fields() {
+        let source = "enum Bad:\n    Pair: (left: int, int)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize enum");
+        assert!(parse(&tokens).is_err());
+    }
+
+    #[test]
+    fn parses_functions_nested_in_function_bodies() {
+        let source = "fn outer(x: int) -> int:\n    fn double(n: int) -> int:\n        return n * 2\n    return double(x)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize nested fn");
+        let program = parse(&tokens).expect("parse nested fn");
+
+        let Statement::Function(outer) = program.statements[0].as_ref() else {
+            panic!("expected function, got {:?}", program.statements[0]);
+        };
+        let body = &outer.as_ref().body.as_ref().statements;
+        assert_eq!(body.len(), 2);
+        let Statement::Function(inner) = body[0].as_ref() else {
+            panic!("expected nested function, got {:?}", body[0]);
+        };
+        assert_eq!(inner.as_ref().name, "double");
+        assert!(!inner.as_ref().public);
+        assert!(matches!(body[1].as_ref(), Statement::Return(Some(_))));
+    }
+
+    #[test]
+    fn parses_anonymous_fn_as_call_argument() {
+        let source = "let doubled = map(xs, fn(x: int, y) x * 2)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize lambda");
+        let program = parse(&tokens).expect("parse lambda");
+
+        let Statement::Let { expr, .. } = program.statements[0].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[0]);
+        };
+        let Expr::Call { args, .. } = expr.as_ref() else {
+            panic!("expected call, got {:?}", expr);
+        };
+        assert_eq!(args.len(), 2);
+        let Expr::Lambda { params, body } = args[1].as_ref() else {
+            panic!("expected anonymous fn, got {:?}", args[1]);
+        };
+        assert_eq!(params.len(), 2);
+        assert!(params[0].as_ref().ty.is_some());
+        assert!(params[1].as_ref().ty.is_none());
+        assert!(matches!(body.as_ref().as_ref(), Expr::Binary { .. }));
+    }
+
+    #[test]
+    fn fstring_interpolations_point_into_the_source() {
+        let source = "let line = f\"{{total}}: {user.name} has {count + 1}\"\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize f-string");
+        let program = parse(&tokens).expect("parse f-string");
+
+        let Statement::Let { expr, .. } = program.statements[0].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[0]);
+        };
+        let Expr::FString { parts } = expr.as_ref() else {
+            panic!("expected f-string, got {:?}", expr);
+        };
+        assert!(matches!(parts[0].as_ref(), FStringPart::Text(text) if text == "{total}: "));
+        assert!(matches!(
+            parts[1].as_ref(),
+            FStringPart::Expr(expr, _) if matches!(expr.as_ref(), Expr::Member { .. })
+        ));
+        let expr_spans: Vec<_> = parts
+            .iter()
+            .filter_map(|part| match part.as_ref() {
+                FStringPart::Expr(expr, _) => {
+                    Some((part.span().start(), expr.span().start(), expr.span().end()))
+                }
+                FStringPart::Text(_) => None,
+            })
+            .collect();
+        assert_eq!(expr_spans, [(24, 25, 34), (40, 41, 50)]);
+
+        for (source, message) in [
+            (
+                "let line = f\"{count +}\"\n",
+                "invalid expression `count +` in f-string",
+            ),
+            ("let line = f\"{}\"\n", "empty expression in f-string"),
+            ("let line = f\"{count\"\n", "unclosed `{` in f-string"),
+        ] {
+            let tokens = otterc_lexer::tokenize(source).expect("tokenize f-string");
+            let errors = parse(&tokens).expect_err("invalid f-string");
+            assert!(
+                errors.iter().any(|error| error.message == message),
+                "{source}: {errors:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fstring_format_specs_are_split_off() {
+        let source = "let line = f\"{price:>8.2f} {pick(\"a:b\")} {n:}\"\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize f-string");
+        let program = parse(&tokens).expect("parse f-string");
+
+        let Statement::Let { expr, .. } = program.statements[0].as_ref() else {
+            panic!("expected let, got {:?}", program.statements[0]);
+        };
+        let Expr::FString { parts } = expr.as_ref() else {
+            panic!("expected f-string, got {:?}", expr);
+        };
+        let FStringPart::Expr(price, Some(spec)) = parts[0].as_ref() else {
+            panic!("expected a format spec, got {:?}", parts[0]);
+        };
+        assert!(matches!(price.as_ref(), Expr::Identifier(name) if name == "price"));
+        assert_eq!(spec.as_ref(), ">8.2f");
+        assert_eq!(&source[spec.span().start()..spec.span().end()], ">8.2f");
+        assert!(matches!(
+            parts[2].as_ref(),
+            FStringPart::Expr(expr, None) if matches!(expr.as_ref(), Expr::Call { .. })
+        ));
+        assert!(matches!(parts[4].as_ref(), FStringPart::Expr(_, None)));
+    }
 }
@@ -9,6 +9,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::memory::config::GcConfig;
+use crate::task::{CpuAffinity, StealPolicy};
 use otterc_config::TieredConfig;
 
 /// Complete runtime configuration
@@ -84,6 +85,20 @@ impl RuntimeConfig {
             self.profiling.enabled = env_config.profiling.enabled;
         }
 
+        // Merge scheduler placement
+        if std::env::var("OTTER_WORKER_THREADS").is_ok() {
+            self.scheduler.worker_threads = env_config.scheduler.worker_threads;
+        }
+        if std::env::var("OTTER_CPU_AFFINITY").is_ok() {
+            self.scheduler.cpu_affinity = env_config.scheduler.cpu_affinity;
+        }
+        if std::env::var("OTTER_STEAL_POLICY").is_ok() {
+            self.scheduler.steal_policy = env_config.scheduler.steal_policy;
+        }
+        if std::env::var("OTTER_BLOCKING_THREADS").is_ok() {
+            self.scheduler.blocking_threads = env_config.scheduler.blocking_threads;
+        }
+
         self
     }
 }
@@ -220,6 +235,18 @@ pub struct SchedulerConfig {
 
     /// Enable task metrics collection
     pub collect_metrics: bool,
+
+    /// Pin worker threads to cores (`off` or `pin`)
+    #[serde(default)]
+    pub cpu_affinity: CpuAffinity,
+
+    /// Order in which idle workers steal work (`any` or `numa`)
+    #[serde(default)]
+    pub steal_policy: StealPolicy,
+
+    /// Most threads the blocking pool may start (0 = default)
+    #[serde(default)]
+    pub blocking_threads: usize,
 }
 
 impl Default for SchedulerConfig {
@@ -229,6 +256,9 @@ impl Default for SchedulerConfig {
             work_stealing: true,
             queue_capacity: 10000,
             collect_metrics: false,
+            cpu_affinity: CpuAffinity::default(),
+            steal_policy: StealPolicy::default(),
+            blocking_threads: 0,
         }
     }
 }
@@ -249,6 +279,18 @@ impl SchedulerConfig {
             config.collect_metrics = val.parse().unwrap_or(false);
         }
 
+        if let Ok(val) = std::env::var("OTTER_CPU_AFFINITY") {
+            config.cpu_affinity = val.parse().unwrap_or_default();
+        }
+
+        if let Ok(val) = std::env::var("OTTER_STEAL_POLICY") {
+            config.steal_policy = val.parse().unwrap_or_default();
+        }
+
+        if let Ok(val) = std::env::var("OTTER_BLOCKING_THREADS") {
+            config.blocking_threads = val.parse().unwrap_or(0);
+        }
+
         config
     }
 }
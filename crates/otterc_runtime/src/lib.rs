@@ -1,11 +1,14 @@
 pub mod benchmark;
 pub mod config;
 pub mod error;
+pub mod limits;
 // NOTE: This currently depends on otterc_jit, which we don't want to pull into
 // pull into otterc_runtime at the moment. It's not currently being used so it's
 // safe to keep it commented out.
 // pub mod introspection;
 pub mod memory;
+pub mod profile;
+pub mod sandbox;
 pub mod stdlib;
 pub mod strings;
 pub mod task;
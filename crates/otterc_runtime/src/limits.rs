@@ -0,0 +1,273 @@
+//! Resource limits for sandboxed execution
+//!
+//! `--max-heap` caps the bytes tracked on the GC heap and `--max-cpu-seconds`
+//! caps the CPU time used by the process. Crossing a limit raises a catchable
+//! error with code [`RESOURCE_EXHAUSTED`] (see `runtime.error_has_error`), after
+//! which the program gets a grace allowance of 10% of the limit to clean up.
+//! Going past the grace allowance terminates the process with
+//! [`EXIT_RESOURCE_EXHAUSTED`].
+
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+use crate::error::{ErrorStack, OtError};
+
+/// Error code carried by resource-exhausted errors
+pub const RESOURCE_EXHAUSTED: i32 = 429;
+/// Exit status used when a program keeps running past its grace allowance
+pub const EXIT_RESOURCE_EXHAUSTED: i32 = 124;
+
+const WATCHDOG_POLL_INTERVAL: Duration = Duration::from_millis(25);
+const MIN_CPU_GRACE: Duration = Duration::from_secs(1);
+
+/// Limits applied to a running program
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum bytes tracked on the GC heap
+    pub max_heap_bytes: Option<usize>,
+    /// Maximum CPU time for the whole process
+    pub max_cpu: Option<Duration>,
+}
+
+impl ResourceLimits {
+    /// Reads `OTTER_MAX_HEAP` (e.g. `512M`) and `OTTER_MAX_CPU_SECONDS`
+    pub fn from_env() -> Self {
+        Self {
+            max_heap_bytes: std::env::var("OTTER_MAX_HEAP")
+                .ok()
+                .and_then(|value| parse_size(&value))
+                .filter(|bytes| *bytes > 0),
+            max_cpu: std::env::var("OTTER_MAX_CPU_SECONDS")
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+                .map(Duration::from_secs_f64),
+        }
+    }
+}
+
+/// Parses a byte size such as `4096`, `64K`, `512M`, `512MiB` or `2G`.
+/// Suffixes are binary (`K` = 1024 bytes).
+pub fn parse_size(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '_')
+        .unwrap_or(text.len());
+    let (digits, unit) = text.split_at(split);
+    let value: usize = digits.replace('_', "").parse().ok()?;
+    let multiplier: usize = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+    value.checked_mul(multiplier)
+}
+
+/// Parses a duration such as `500ms`, `5s`, `2m` or `1h`. A bare number is
+/// in seconds.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    let seconds = match unit.trim().to_ascii_lowercase().as_str() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" | "min" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Grace allowance past `limit` before the process is terminated
+fn grace_bytes(limit: usize) -> usize {
+    limit / 10
+}
+
+fn grace_cpu(limit: Duration) -> Duration {
+    (limit / 10).max(MIN_CPU_GRACE)
+}
+
+fn raise_exhausted(message: String) {
+    ErrorStack::raise(OtError::with_code(message, RESOURCE_EXHAUSTED));
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "The process is about to exit, stderr is the only channel left"
+)]
+#[expect(
+    clippy::exit,
+    reason = "Limits are enforced by ending the process once the grace allowance is spent"
+)]
+fn terminate(message: &str) -> ! {
+    eprintln!("error: resource exhausted: {message}");
+    std::process::exit(EXIT_RESOURCE_EXHAUSTED);
+}
+
+// ============================================================================
+// Heap
+// ============================================================================
+
+static HEAP_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Checks the tracked heap size against `limit` (0 = unlimited). Called by the
+/// GC after it has tried to make room.
+pub(crate) fn enforce_heap_limit(heap_bytes: usize, limit: usize) {
+    if limit == 0 {
+        return;
+    }
+    if heap_bytes <= limit {
+        // Allow the limit to be reported again if the program recovers
+        HEAP_REPORTED.store(false, Ordering::Relaxed);
+        return;
+    }
+    let message = format!("heap limit of {limit} bytes exceeded ({heap_bytes} bytes in use)");
+    if heap_bytes > limit.saturating_add(grace_bytes(limit)) {
+        terminate(&message);
+    }
+    if !HEAP_REPORTED.swap(true, Ordering::Relaxed) {
+        raise_exhausted(message);
+    }
+}
+
+// ============================================================================
+// CPU
+// ============================================================================
+
+static CPU_BUDGET_MS: AtomicU64 = AtomicU64::new(0);
+static CPU_EXCEEDED: AtomicBool = AtomicBool::new(false);
+static CPU_REPORTED: AtomicBool = AtomicBool::new(false);
+static STARTED: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// CPU time used by the whole process so far
+#[cfg(unix)]
+pub fn process_cpu_time() -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut ts) } != 0 {
+        return STARTED.elapsed();
+    }
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// CPU time used by the whole process so far. Without a process CPU clock this
+/// falls back to wall time, which only over-counts.
+#[cfg(not(unix))]
+pub fn process_cpu_time() -> Duration {
+    STARTED.elapsed()
+}
+
+fn spawn_watchdog(budget: Duration) {
+    static SPAWN: Once = Once::new();
+    SPAWN.call_once(|| {
+        Lazy::force(&STARTED);
+        let _ = std::thread::Builder::new()
+            .name("otter-watchdog".into())
+            .spawn(move || {
+                let hard_limit = budget + grace_cpu(budget);
+                loop {
+                    let used = process_cpu_time();
+                    if used > hard_limit {
+                        terminate(&format!(
+                            "CPU time budget of {:.3}s exceeded",
+                            budget.as_secs_f64()
+                        ));
+                    }
+                    if used > budget {
+                        CPU_EXCEEDED.store(true, Ordering::SeqCst);
+                    }
+                    std::thread::sleep(WATCHDOG_POLL_INTERVAL);
+                }
+            });
+    });
+}
+
+/// Raises the CPU budget error on the calling thread once the watchdog has seen
+/// the budget run out. Returns whether any limit has been exceeded.
+pub fn check_limits() -> bool {
+    if CPU_EXCEEDED.load(Ordering::SeqCst) && !CPU_REPORTED.swap(true, Ordering::SeqCst) {
+        let budget = Duration::from_millis(CPU_BUDGET_MS.load(Ordering::SeqCst));
+        raise_exhausted(format!(
+            "CPU time budget of {:.3}s exceeded",
+            budget.as_secs_f64()
+        ));
+    }
+    CPU_EXCEEDED.load(Ordering::SeqCst) || HEAP_REPORTED.load(Ordering::Relaxed)
+}
+
+/// Applies `limits` to the current process, starting the CPU watchdog if a CPU
+/// budget is set
+pub fn install(limits: ResourceLimits) {
+    if let Some(budget) = limits.max_cpu {
+        CPU_BUDGET_MS.store(
+            u64::try_from(budget.as_millis()).unwrap_or(u64::MAX),
+            Ordering::SeqCst,
+        );
+        spawn_watchdog(budget);
+    }
+    if let Some(max_heap) = limits.max_heap_bytes {
+        crate::memory::gc::get_gc().set_max_heap_bytes(max_heap);
+    }
+}
+
+/// Called by the program entry point before `main` runs
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_init() {
+    install(ResourceLimits::from_env());
+    crate::task::install_schedule_from_env();
+    crate::profile::install_from_env();
+    crate::memory::histogram::install_from_env();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("64K"), Some(64 * 1024));
+        assert_eq!(parse_size("512M"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_size("512MiB"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_size(" 2g "), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("1_000"), Some(1000));
+        assert_eq!(parse_size("12 parsecs"), None);
+        assert_eq!(parse_size("M"), None);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("5s"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration(" 2m "), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("5 fortnights"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn reports_heap_limit_once() {
+        ErrorStack::clear();
+        enforce_heap_limit(1050, 1000);
+        assert!(ErrorStack::has_error());
+        ErrorStack::clear();
+        enforce_heap_limit(1060, 1000);
+        assert!(!ErrorStack::has_error());
+        enforce_heap_limit(900, 1000);
+        enforce_heap_limit(1010, 1000);
+        assert!(ErrorStack::has_error());
+        ErrorStack::clear();
+    }
+}
@@ -45,6 +45,9 @@ pub struct GcConfig {
     pub max_heap_size: usize,
     /// Maximum bytes that may be allocated while GC is disabled (0 = unlimited)
     pub disabled_heap_limit: usize,
+    /// Print every GC pause to stderr
+    #[serde(default)]
+    pub log: bool,
 }
 
 impl Default for GcConfig {
@@ -56,6 +59,7 @@ impl Default for GcConfig {
             auto_gc: true,
             max_heap_size: 0,                      // Unlimited
             disabled_heap_limit: 64 * 1024 * 1024, // 64MB safeguard while GC disabled
+            log: false,
         }
     }
 }
@@ -97,6 +101,10 @@ impl GcConfig {
             config.disabled_heap_limit = limit_bytes;
         }
 
+        if let Ok(log) = std::env::var("OTTER_GC_LOG") {
+            config.log = matches!(log.trim(), "1" | "true" | "yes" | "on");
+        }
+
         config
     }
 }
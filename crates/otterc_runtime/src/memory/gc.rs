@@ -6,8 +6,12 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use parking_lot::RwLock;
 
+use crate::limits;
 use crate::memory::config::GcStrategy;
+use crate::memory::histogram::{HEAP, Histogram, OLD, YOUNG};
+use crate::memory::pin;
 use crate::memory::profiler::get_profiler;
+use crate::memory::telemetry;
 
 /// Trait for garbage collection strategies
 pub trait GcStrategyTrait: Send + Sync {
@@ -28,6 +32,9 @@ pub trait GcStrategyTrait: Send + Sync {
 
     /// Get the strategy name
     fn name(&self) -> &'static str;
+
+    /// Count the tracked objects into `histogram`
+    fn census(&self, _histogram: &mut Histogram) {}
 }
 
 /// Statistics from a garbage collection run
@@ -109,6 +116,26 @@ struct ObjectInfo {
     references: Vec<usize>, // Pointers to other objects
 }
 
+impl ObjectKind {
+    /// The runtime type a `memory.histogram()` bucket names
+    fn type_name(self) -> &'static str {
+        match self {
+            ObjectKind::Raw => "raw",
+            ObjectKind::CString => "str",
+        }
+    }
+}
+
+fn count_objects(
+    objects: &HashMap<usize, ObjectInfo>,
+    generation: &'static str,
+    histogram: &mut Histogram,
+) {
+    for info in objects.values() {
+        histogram.add(info.kind.type_name(), generation, info.size);
+    }
+}
+
 impl MarkSweepGC {
     pub fn new() -> Self {
         Self {
@@ -151,6 +178,7 @@ impl MarkSweepGC {
         let objects = self.objects.read().clone();
 
         let mut stack: Vec<usize> = roots.iter().copied().collect();
+        stack.extend(pin::pinned_pointers());
 
         while let Some(ptr) = stack.pop() {
             if marked.contains(&ptr) {
@@ -252,6 +280,10 @@ impl GcStrategyTrait for MarkSweepGC {
     fn name(&self) -> &'static str {
         "MarkSweep"
     }
+
+    fn census(&self, histogram: &mut Histogram) {
+        count_objects(&self.objects.read(), HEAP, histogram);
+    }
 }
 
 impl Default for MarkSweepGC {
@@ -316,6 +348,7 @@ impl GenerationalGC {
         // Find reachable objects in nursery
         let mut reachable = HashSet::new();
         let mut stack: Vec<usize> = roots.iter().copied().collect();
+        stack.extend(pin::pinned_pointers());
 
         while let Some(ptr) = stack.pop() {
             if reachable.contains(&ptr) {
@@ -418,6 +451,11 @@ impl GcStrategyTrait for GenerationalGC {
     fn name(&self) -> &'static str {
         "Generational"
     }
+
+    fn census(&self, histogram: &mut Histogram) {
+        count_objects(&self.nursery_objects.read(), YOUNG, histogram);
+        count_objects(&self.old_gen.objects.read(), OLD, histogram);
+    }
 }
 
 impl Default for GenerationalGC {
@@ -433,6 +471,7 @@ pub struct GcManager {
     gc_enabled: AtomicBool,
     disabled_bytes: AtomicUsize,
     disabled_bytes_limit: AtomicUsize,
+    max_heap_bytes: AtomicUsize,
     bytes_since_last_gc: AtomicUsize,
     gc_threshold: AtomicUsize,
 }
@@ -447,12 +486,14 @@ impl GcManager {
         };
 
         let disabled_limit = config.disabled_heap_limit;
+        let max_heap = config.max_heap_size;
         Self {
             strategy: Arc::new(RwLock::new(strategy)),
             config: Arc::new(RwLock::new(config)),
             gc_enabled: AtomicBool::new(true),
             disabled_bytes: AtomicUsize::new(0),
             disabled_bytes_limit: AtomicUsize::new(disabled_limit),
+            max_heap_bytes: AtomicUsize::new(max_heap),
             bytes_since_last_gc: AtomicUsize::new(0),
             gc_threshold: AtomicUsize::new(10 * 1024 * 1024), // 10MB default threshold
         }
@@ -462,7 +503,21 @@ impl GcManager {
         if !self.is_enabled() {
             return GcStats::default();
         }
-        self.strategy.read().collect()
+        let strategy = self.strategy.read();
+        let name = strategy.name();
+        let cycle = telemetry::pause_start(name);
+        let start = std::time::Instant::now();
+        let stats = strategy.collect();
+        let pause = start.elapsed();
+        drop(strategy);
+        telemetry::pause_end(
+            cycle,
+            name,
+            pause,
+            stats.objects_collected,
+            stats.bytes_freed,
+        );
+        stats
     }
 
     pub fn alloc(&self, size: usize) -> Option<*mut u8> {
@@ -483,12 +538,30 @@ impl GcManager {
         self.strategy.read().add_root(ptr);
     }
 
+    /// Count the objects the current strategy tracks into `histogram`
+    pub fn census(&self, histogram: &mut Histogram) {
+        self.strategy.read().census(histogram);
+    }
+
     pub fn remove_root(&self, ptr: usize) {
         self.strategy.read().remove_root(ptr);
     }
 
     pub fn register_object(&self, ptr: usize, size: usize, kind: ObjectKind) {
         self.strategy.read().register_object(ptr, size, kind);
+        telemetry::record_allocation(size);
+
+        let max_heap = self.max_heap_bytes.load(Ordering::Relaxed);
+        if max_heap > 0 {
+            if telemetry::heap_bytes() > max_heap {
+                // Give the collector a chance to make room before reporting
+                let _ = self.collect();
+            }
+            limits::enforce_heap_limit(telemetry::heap_bytes(), max_heap);
+        }
+        // Allocations are where a running program reliably re-enters the
+        // runtime, so surface an exhausted CPU budget here too
+        limits::check_limits();
 
         // Check memory threshold and trigger GC if needed
         if self.is_enabled() {
@@ -532,6 +605,12 @@ impl GcManager {
         self.config.clone()
     }
 
+    /// Caps the tracked heap size, see [`crate::limits`] (0 = unlimited)
+    pub fn set_max_heap_bytes(&self, bytes: usize) {
+        self.max_heap_bytes.store(bytes, Ordering::Relaxed);
+        self.config.write().max_heap_size = bytes;
+    }
+
     pub fn enable(&self) -> bool {
         let previous = self.gc_enabled.swap(true, Ordering::SeqCst);
         if !previous {
@@ -581,6 +660,9 @@ impl GcStrategyTrait for NoOpGC {
 /// Global GC manager
 static GLOBAL_GC: once_cell::sync::Lazy<GcManager> = once_cell::sync::Lazy::new(|| {
     let config = crate::memory::config::GcConfig::from_env();
+    if config.log {
+        telemetry::install_log_subscriber();
+    }
     GcManager::new(config)
 });
 
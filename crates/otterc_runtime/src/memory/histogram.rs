@@ -0,0 +1,156 @@
+//! Live heap objects counted by runtime type and GC generation
+//!
+//! `memory.histogram()` and `--heap-stats-interval` show which kinds of values
+//! hold the heap without taking a full snapshot. Objects the collector tracks
+//! are counted in the generation it keeps them in (`young` or `old`, or `heap`
+//! for a collector without generations). Lists, maps and the other values in
+//! the runtime's handle registries are not traced by the collector and are
+//! counted as `untracked`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::memory::gc::get_gc;
+
+/// Objects in the nursery of the generational collector
+pub const YOUNG: &str = "young";
+/// Objects that survived a collection, or were registered straight into the
+/// old generation
+pub const OLD: &str = "old";
+/// Objects of a collector without generations
+pub const HEAP: &str = "heap";
+/// Values held by the runtime's handle registries
+pub const UNTRACKED: &str = "untracked";
+
+/// The objects of one runtime type in one generation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistogramEntry {
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    pub generation: &'static str,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// Live objects by runtime type and generation
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    buckets: HashMap<(&'static str, &'static str), (usize, usize)>,
+}
+
+impl Histogram {
+    /// Counts one object of `type_name` taking `bytes`
+    pub fn add(&mut self, type_name: &'static str, generation: &'static str, bytes: usize) {
+        let bucket = self.buckets.entry((type_name, generation)).or_default();
+        bucket.0 += 1;
+        bucket.1 += bytes;
+    }
+
+    /// The buckets, largest first
+    pub fn entries(&self) -> Vec<HistogramEntry> {
+        let mut entries: Vec<_> = self
+            .buckets
+            .iter()
+            .map(
+                |(&(type_name, generation), &(count, bytes))| HistogramEntry {
+                    type_name,
+                    generation,
+                    count,
+                    bytes,
+                },
+            )
+            .collect();
+        entries.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then(b.count.cmp(&a.count))
+                .then(a.type_name.cmp(b.type_name))
+                .then(a.generation.cmp(b.generation))
+        });
+        entries
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.buckets.values().map(|&(_, bytes)| bytes).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.entries()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// One line per bucket, for the periodic heap log
+    pub fn render(&self) -> String {
+        let mut out = format!("[heap] {} bytes live\n", self.total_bytes());
+        for entry in self.entries() {
+            let _ = writeln!(
+                out,
+                "[heap]   {:<10} {:<9} {:>8} objects {:>12} bytes",
+                entry.type_name, entry.generation, entry.count, entry.bytes
+            );
+        }
+        out
+    }
+}
+
+/// Counts what is live right now
+pub fn take() -> Histogram {
+    let mut histogram = Histogram::default();
+    get_gc().census(&mut histogram);
+    crate::stdlib::builtins::census(&mut histogram);
+    histogram
+}
+
+/// Logs the histogram to stderr every `OTTER_HEAP_STATS_INTERVAL_MS`
+/// milliseconds (`--heap-stats-interval`), if set
+pub fn install_from_env() {
+    let Some(interval) = std::env::var("OTTER_HEAP_STATS_INTERVAL_MS")
+        .ok()
+        .and_then(|ms| ms.trim().parse::<u64>().ok())
+        .filter(|&ms| ms > 0)
+    else {
+        return;
+    };
+    let interval = Duration::from_millis(interval);
+    let _ = std::thread::Builder::new()
+        .name("otter-heap-stats".into())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(interval);
+                log(&take());
+            }
+        });
+}
+
+#[expect(clippy::print_stderr, reason = "The heap log is written to stderr")]
+fn log(histogram: &Histogram) {
+    eprint!("{}", histogram.render());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_are_listed_largest_first() {
+        let mut histogram = Histogram::default();
+        histogram.add("str", OLD, 10);
+        histogram.add("list", UNTRACKED, 100);
+        histogram.add("str", OLD, 30);
+        histogram.add("str", YOUNG, 5);
+
+        let entries = histogram.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].type_name, "list");
+        assert_eq!((entries[1].count, entries[1].bytes), (2, 40));
+        assert_eq!(entries[2].generation, YOUNG);
+        assert_eq!(histogram.total_bytes(), 145);
+        assert!(
+            histogram
+                .to_json()
+                .starts_with(r#"[{"type":"list","generation":"untracked","count":1,"bytes":100}"#)
+        );
+    }
+}
@@ -6,12 +6,16 @@ pub mod allocator;
 pub mod arena;
 pub mod config;
 pub mod gc;
+pub mod histogram;
 pub mod object;
+pub mod pin;
 pub mod profiler;
 pub mod rc;
+pub mod telemetry;
 
 pub use config::{GcConfig, GcStrategy};
 pub use gc::{GcStats, GcStrategyTrait, GenerationalGC, MarkSweepGC, RcGC, get_gc};
 pub use object::OtterObject;
 pub use profiler::{AllocationInfo, MemoryProfiler};
 pub use rc::{RcOtter, WeakOtter};
+pub use telemetry::{GcEvent, GcTelemetry};
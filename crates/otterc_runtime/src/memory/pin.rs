@@ -0,0 +1,99 @@
+//! Object pinning for FFI
+//!
+//! Pinning a GC-managed object keeps it alive (it is treated as a root) and
+//! guarantees its address does not change until it is unpinned. Every pin gets
+//! its own handle, so native code can hold the handle across calls and resolve
+//! it back to the pointer; a handle that was already unpinned resolves to
+//! nothing instead of a dangling pointer.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+/// Handle returned by [`pin`]. Handles are never reused, so a stale handle can
+/// always be told apart from a live one.
+pub type PinHandle = u64;
+
+#[derive(Default)]
+struct PinTable {
+    /// handle -> pinned pointer
+    handles: HashMap<PinHandle, usize>,
+    /// pointer -> number of live pins on it
+    counts: HashMap<usize, usize>,
+}
+
+static NEXT_PIN_HANDLE: AtomicU64 = AtomicU64::new(1);
+static PINS: Lazy<RwLock<PinTable>> = Lazy::new(|| RwLock::new(PinTable::default()));
+
+/// Pins `ptr` and returns a handle for it. The same pointer may be pinned
+/// several times; it stays pinned until every handle is unpinned.
+pub fn pin(ptr: usize) -> Option<PinHandle> {
+    if ptr == 0 {
+        return None;
+    }
+    let handle = NEXT_PIN_HANDLE.fetch_add(1, Ordering::SeqCst);
+    let mut pins = PINS.write();
+    pins.handles.insert(handle, ptr);
+    *pins.counts.entry(ptr).or_default() += 1;
+    Some(handle)
+}
+
+/// Releases the pin held by `handle`. Returns false if the handle is unknown
+/// or was already unpinned.
+pub fn unpin(handle: PinHandle) -> bool {
+    let mut pins = PINS.write();
+    let Some(ptr) = pins.handles.remove(&handle) else {
+        return false;
+    };
+    if let Some(count) = pins.counts.get_mut(&ptr) {
+        *count -= 1;
+        if *count == 0 {
+            pins.counts.remove(&ptr);
+        }
+    }
+    true
+}
+
+/// The pointer pinned by `handle`, or `None` if the handle is not live
+pub fn resolve(handle: PinHandle) -> Option<usize> {
+    PINS.read().handles.get(&handle).copied()
+}
+
+pub fn is_pinned(ptr: usize) -> bool {
+    PINS.read().counts.contains_key(&ptr)
+}
+
+/// Number of live pin handles
+pub fn pin_count() -> usize {
+    PINS.read().handles.len()
+}
+
+/// Every pinned pointer, for the collectors to treat as roots
+pub(crate) fn pinned_pointers() -> Vec<usize> {
+    PINS.read().counts.keys().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_are_counted_per_handle() {
+        let ptr = 0xdead_0000;
+        let first = pin(ptr).expect("pin");
+        let second = pin(ptr).expect("pin again");
+        assert_ne!(first, second);
+        assert_eq!(resolve(first), Some(ptr));
+
+        assert!(unpin(first));
+        assert!(is_pinned(ptr));
+        assert_eq!(resolve(first), None);
+        assert!(!unpin(first));
+
+        assert!(unpin(second));
+        assert!(!is_pinned(ptr));
+        assert_eq!(pin(0), None);
+    }
+}
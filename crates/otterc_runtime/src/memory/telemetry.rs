@@ -0,0 +1,310 @@
+//! GC telemetry
+//!
+//! Every collection emits a [`GcEvent::PauseStart`] / [`GcEvent::PauseEnd`] pair
+//! to the registered subscribers, and allocation/heap counters are kept so the
+//! totals can be exported without subscribing.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// A GC lifecycle event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GcEvent {
+    /// A collection is about to stop the world
+    PauseStart {
+        cycle: u64,
+        strategy: &'static str,
+        /// Tracked heap size when the collection started
+        heap_bytes: usize,
+        /// Bytes allocated since the previous collection
+        allocated_bytes: usize,
+    },
+    /// A collection finished
+    PauseEnd {
+        cycle: u64,
+        strategy: &'static str,
+        pause: Duration,
+        objects_reclaimed: usize,
+        bytes_reclaimed: usize,
+        /// Tracked heap size after the collection
+        heap_bytes: usize,
+    },
+}
+
+impl std::fmt::Display for GcEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GcEvent::PauseStart {
+                cycle,
+                strategy,
+                heap_bytes,
+                allocated_bytes,
+            } => write!(
+                f,
+                "[gc #{cycle}] {strategy} pause start: heap {}, {} allocated since last cycle",
+                format_bytes(*heap_bytes),
+                format_bytes(*allocated_bytes)
+            ),
+            GcEvent::PauseEnd {
+                cycle,
+                strategy,
+                pause,
+                objects_reclaimed,
+                bytes_reclaimed,
+                heap_bytes,
+            } => write!(
+                f,
+                "[gc #{cycle}] {strategy} pause end: {:.3}ms, reclaimed {} ({objects_reclaimed} objects), heap {}",
+                pause.as_secs_f64() * 1000.0,
+                format_bytes(*bytes_reclaimed),
+                format_bytes(*heap_bytes)
+            ),
+        }
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Identifies a subscription so it can be removed again
+pub type SubscriberId = u64;
+
+type Subscriber = Arc<dyn Fn(&GcEvent) + Send + Sync>;
+
+static SUBSCRIBERS: Lazy<RwLock<Vec<(SubscriberId, Subscriber)>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+static NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Registers `subscriber` to receive every GC event. Subscribers run on the
+/// collecting thread, so they should be quick and must not trigger a collection.
+pub fn subscribe(subscriber: impl Fn(&GcEvent) + Send + Sync + 'static) -> SubscriberId {
+    let id = NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::SeqCst);
+    SUBSCRIBERS.write().push((id, Arc::new(subscriber)));
+    id
+}
+
+/// Removes a subscription. Returns false if `id` was not subscribed.
+pub fn unsubscribe(id: SubscriberId) -> bool {
+    let mut subscribers = SUBSCRIBERS.write();
+    let before = subscribers.len();
+    subscribers.retain(|(existing, _)| *existing != id);
+    subscribers.len() != before
+}
+
+/// Prints every event to stderr (`--gc-log` / `OTTER_GC_LOG=1`)
+#[expect(clippy::print_stderr, reason = "The GC log is written to stderr")]
+pub(crate) fn install_log_subscriber() -> SubscriberId {
+    subscribe(|event| eprintln!("{event}"))
+}
+
+fn publish(event: &GcEvent) {
+    // Clone the list so subscribers may (un)subscribe without deadlocking
+    let subscribers: Vec<Subscriber> = SUBSCRIBERS
+        .read()
+        .iter()
+        .map(|(_, subscriber)| subscriber.clone())
+        .collect();
+    for subscriber in subscribers {
+        subscriber(event);
+    }
+}
+
+// ============================================================================
+// Counters
+// ============================================================================
+
+/// Cumulative GC and allocation counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcTelemetry {
+    pub cycles: u64,
+    pub total_pause_ns: u64,
+    pub max_pause_ns: u64,
+    pub objects_reclaimed: u64,
+    pub bytes_reclaimed: u64,
+    pub bytes_allocated: u64,
+    pub heap_bytes: u64,
+}
+
+impl GcTelemetry {
+    /// Renders the counters in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let seconds = |ns: u64| (ns as f64 / 1e9).to_string();
+        let metrics = [
+            (
+                "otter_gc_cycles_total",
+                "counter",
+                "Completed garbage collections",
+                self.cycles.to_string(),
+            ),
+            (
+                "otter_gc_pause_seconds_total",
+                "counter",
+                "Time spent in GC pauses",
+                seconds(self.total_pause_ns),
+            ),
+            (
+                "otter_gc_pause_max_seconds",
+                "gauge",
+                "Longest GC pause",
+                seconds(self.max_pause_ns),
+            ),
+            (
+                "otter_gc_reclaimed_objects_total",
+                "counter",
+                "Objects reclaimed by the GC",
+                self.objects_reclaimed.to_string(),
+            ),
+            (
+                "otter_gc_reclaimed_bytes_total",
+                "counter",
+                "Bytes reclaimed by the GC",
+                self.bytes_reclaimed.to_string(),
+            ),
+            (
+                "otter_gc_allocated_bytes_total",
+                "counter",
+                "Bytes allocated on the GC heap",
+                self.bytes_allocated.to_string(),
+            ),
+            (
+                "otter_gc_heap_bytes",
+                "gauge",
+                "Bytes currently tracked on the GC heap",
+                self.heap_bytes.to_string(),
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name} {value}");
+        }
+        out
+    }
+}
+
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_TOTAL: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_SINCE_CYCLE: AtomicUsize = AtomicUsize::new(0);
+static CYCLES: Lazy<Mutex<GcTelemetry>> = Lazy::new(|| Mutex::new(GcTelemetry::default()));
+
+/// Records an object registered with the GC
+pub(crate) fn record_allocation(size: usize) {
+    HEAP_BYTES.fetch_add(size, Ordering::Relaxed);
+    ALLOCATED_SINCE_CYCLE.fetch_add(size, Ordering::Relaxed);
+    ALLOCATED_TOTAL.fetch_add(size as u64, Ordering::Relaxed);
+}
+
+/// Bytes currently tracked on the GC heap
+pub(crate) fn heap_bytes() -> usize {
+    HEAP_BYTES.load(Ordering::Relaxed)
+}
+
+/// Starts a collection cycle and returns its number
+pub(crate) fn pause_start(strategy: &'static str) -> u64 {
+    let cycle = CYCLES.lock().cycles + 1;
+    publish(&GcEvent::PauseStart {
+        cycle,
+        strategy,
+        heap_bytes: HEAP_BYTES.load(Ordering::Relaxed),
+        allocated_bytes: ALLOCATED_SINCE_CYCLE.swap(0, Ordering::Relaxed),
+    });
+    cycle
+}
+
+/// Finishes the collection cycle started by [`pause_start`]
+pub(crate) fn pause_end(
+    cycle: u64,
+    strategy: &'static str,
+    pause: Duration,
+    objects_reclaimed: usize,
+    bytes_reclaimed: usize,
+) {
+    let heap_bytes = HEAP_BYTES
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |heap| {
+            Some(heap.saturating_sub(bytes_reclaimed))
+        })
+        .map_or(0, |previous| previous.saturating_sub(bytes_reclaimed));
+
+    {
+        let mut totals = CYCLES.lock();
+        let pause_ns = u64::try_from(pause.as_nanos()).unwrap_or(u64::MAX);
+        totals.cycles += 1;
+        totals.total_pause_ns = totals.total_pause_ns.saturating_add(pause_ns);
+        totals.max_pause_ns = totals.max_pause_ns.max(pause_ns);
+        totals.objects_reclaimed += objects_reclaimed as u64;
+        totals.bytes_reclaimed += bytes_reclaimed as u64;
+    }
+
+    publish(&GcEvent::PauseEnd {
+        cycle,
+        strategy,
+        pause,
+        objects_reclaimed,
+        bytes_reclaimed,
+        heap_bytes,
+    });
+}
+
+/// Current counters
+pub fn snapshot() -> GcTelemetry {
+    GcTelemetry {
+        bytes_allocated: ALLOCATED_TOTAL.load(Ordering::Relaxed),
+        heap_bytes: HEAP_BYTES.load(Ordering::Relaxed) as u64,
+        ..CYCLES.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_events_for_the_log() {
+        let event = GcEvent::PauseEnd {
+            cycle: 3,
+            strategy: "MarkSweep",
+            pause: Duration::from_micros(1500),
+            objects_reclaimed: 12,
+            bytes_reclaimed: 3 * 1024 * 1024,
+            heap_bytes: 512,
+        };
+        assert_eq!(
+            event.to_string(),
+            "[gc #3] MarkSweep pause end: 1.500ms, reclaimed 3.0 MiB (12 objects), heap 512 B"
+        );
+    }
+
+    #[test]
+    fn renders_prometheus_metrics() {
+        let telemetry = GcTelemetry {
+            cycles: 2,
+            total_pause_ns: 1_500_000_000,
+            heap_bytes: 4096,
+            ..GcTelemetry::default()
+        };
+        let text = telemetry.to_prometheus();
+        assert!(text.contains("# TYPE otter_gc_cycles_total counter\notter_gc_cycles_total 2\n"));
+        assert!(text.contains("otter_gc_pause_seconds_total 1.5\n"));
+        assert!(text.contains("otter_gc_heap_bytes 4096\n"));
+    }
+}
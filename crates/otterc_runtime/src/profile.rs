@@ -0,0 +1,326 @@
+//! Run profiles for `otter profile report`
+//!
+//! Programs compiled for profiling call [`otter_profile_enter`] and
+//! [`otter_profile_exit`] around every function body. When
+//! `OTTER_PROFILE_REPORT` names a file, the runtime also records every GC
+//! pause, and writes a [`RunProfile`] of the run to that file as JSON when
+//! the program exits.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::memory::telemetry::{self, GcEvent, GcTelemetry};
+
+/// Everything measured during one run of a program
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunProfile {
+    /// Time from the runtime starting to the program exiting
+    pub wall_ns: u64,
+    /// Profiled functions, most total time first
+    pub functions: Vec<FunctionProfile>,
+    pub gc: GcTelemetry,
+    pub gc_pauses: Vec<GcPause>,
+    pub tasks_spawned: u64,
+    pub tasks_completed: u64,
+    /// Empty when the program never started the task runtime
+    pub workers: Vec<WorkerProfile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Time between entering and leaving the function, counting recursive
+    /// calls once
+    pub total_ns: u64,
+    /// Time not spent in other profiled functions
+    pub self_ns: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcPause {
+    pub cycle: u64,
+    pub strategy: String,
+    /// When the pause ended, from the runtime starting
+    pub at_ns: u64,
+    pub pause_ns: u64,
+    pub bytes_reclaimed: u64,
+    /// Tracked heap size after the collection
+    pub heap_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerProfile {
+    pub id: usize,
+    pub tasks_processed: u64,
+    /// Time spent running tasks
+    pub busy_ns: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Totals {
+    calls: u64,
+    total_ns: u64,
+    self_ns: u64,
+}
+
+/// A function call that has not returned yet
+struct Frame {
+    name: &'static str,
+    entered: Instant,
+    /// Time spent in profiled functions it called
+    callees_ns: u64,
+}
+
+type ThreadTotals = Arc<Mutex<HashMap<&'static str, Totals>>>;
+
+/// The totals of every thread that has entered a profiled function. Each
+/// thread only locks its own, so calls on different threads do not contend.
+static THREADS: Lazy<Mutex<Vec<ThreadTotals>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+thread_local! {
+    static STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    static TOTALS: ThreadTotals = {
+        let totals = ThreadTotals::default();
+        THREADS.lock().push(Arc::clone(&totals));
+        totals
+    };
+}
+
+static START: Lazy<Instant> = Lazy::new(Instant::now);
+static REPORT_PATH: OnceCell<PathBuf> = OnceCell::new();
+static PAUSES: Lazy<Mutex<Vec<GcPause>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn nanos_since(instant: Instant) -> u64 {
+    u64::try_from(instant.elapsed().as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Codegen passes a string constant, which lives as long as the program
+unsafe fn function_name(name: *const c_char) -> Option<&'static str> {
+    if name.is_null() {
+        return None;
+    }
+    let name: &'static CStr = unsafe { CStr::from_ptr(name) };
+    name.to_str().ok()
+}
+
+/// Called as a profiled function is entered
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_profile_enter(name: *const c_char) {
+    let Some(name) = (unsafe { function_name(name) }) else {
+        return;
+    };
+    STACK.with_borrow_mut(|stack| {
+        stack.push(Frame {
+            name,
+            entered: Instant::now(),
+            callees_ns: 0,
+        });
+    });
+}
+
+/// Called as a profiled function returns
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_profile_exit(name: *const c_char) {
+    let Some(name) = (unsafe { function_name(name) }) else {
+        return;
+    };
+    STACK.with_borrow_mut(|stack| {
+        // Frames above it were left by an exception without returning
+        let Some(position) = stack.iter().rposition(|frame| frame.name == name) else {
+            return;
+        };
+        let frame = stack.swap_remove(position);
+        stack.truncate(position);
+        let elapsed = nanos_since(frame.entered);
+        if let Some(caller) = stack.last_mut() {
+            caller.callees_ns = caller.callees_ns.saturating_add(elapsed);
+        }
+        let outermost = stack.iter().all(|frame| frame.name != name);
+
+        TOTALS.with(|totals| {
+            let mut totals = totals.lock();
+            let totals = totals.entry(name).or_default();
+            totals.calls += 1;
+            totals.self_ns = totals
+                .self_ns
+                .saturating_add(elapsed.saturating_sub(frame.callees_ns));
+            if outermost {
+                totals.total_ns = totals.total_ns.saturating_add(elapsed);
+            }
+        });
+    });
+}
+
+/// What has been measured so far
+pub fn snapshot() -> RunProfile {
+    let mut merged: HashMap<&'static str, Totals> = HashMap::new();
+    for totals in THREADS.lock().iter() {
+        for (name, thread) in totals.lock().iter() {
+            let merged = merged.entry(*name).or_default();
+            merged.calls += thread.calls;
+            merged.total_ns = merged.total_ns.saturating_add(thread.total_ns);
+            merged.self_ns = merged.self_ns.saturating_add(thread.self_ns);
+        }
+    }
+    let mut functions: Vec<FunctionProfile> = merged
+        .into_iter()
+        .map(|(name, totals)| FunctionProfile {
+            name: name.to_string(),
+            calls: totals.calls,
+            total_ns: totals.total_ns,
+            self_ns: totals.self_ns,
+        })
+        .collect();
+    functions.sort_by(|a, b| b.total_ns.cmp(&a.total_ns).then(a.name.cmp(&b.name)));
+
+    let mut profile = RunProfile {
+        wall_ns: nanos_since(*START),
+        functions,
+        gc: telemetry::snapshot(),
+        gc_pauses: PAUSES.lock().clone(),
+        ..RunProfile::default()
+    };
+    // Reading the metrics must not start a scheduler the program never used
+    if let Some(runtime) = crate::task::started_runtime() {
+        let tasks = runtime.scheduler().metrics().snapshot();
+        profile.tasks_spawned = tasks.tasks_spawned;
+        profile.tasks_completed = tasks.tasks_completed;
+        profile.workers = tasks
+            .worker_infos
+            .iter()
+            .map(|worker| WorkerProfile {
+                id: worker.id,
+                tasks_processed: worker.tasks_processed,
+                busy_ns: worker.busy_ns,
+            })
+            .collect();
+    }
+    profile
+}
+
+/// Starts collecting a run profile if `OTTER_PROFILE_REPORT` names a file to
+/// write it to
+pub fn install_from_env() {
+    let Some(path) = std::env::var_os("OTTER_PROFILE_REPORT") else {
+        return;
+    };
+    if REPORT_PATH.set(PathBuf::from(path)).is_err() {
+        return;
+    }
+    Lazy::force(&START);
+    telemetry::subscribe(|event| {
+        if let GcEvent::PauseEnd {
+            cycle,
+            strategy,
+            pause,
+            bytes_reclaimed,
+            heap_bytes,
+            ..
+        } = event
+        {
+            PAUSES.lock().push(GcPause {
+                cycle: *cycle,
+                strategy: strategy.to_string(),
+                at_ns: nanos_since(*START),
+                pause_ns: u64::try_from(pause.as_nanos()).unwrap_or(u64::MAX),
+                bytes_reclaimed: *bytes_reclaimed as u64,
+                heap_bytes: *heap_bytes as u64,
+            });
+        }
+    });
+    unsafe {
+        libc::atexit(write_report_at_exit);
+    }
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "The program is exiting, stderr is the only channel left"
+)]
+extern "C" fn write_report_at_exit() {
+    let Some(path) = REPORT_PATH.get() else {
+        return;
+    };
+    let written = serde_json::to_string(&snapshot())
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(path, json));
+    if let Err(err) = written {
+        eprintln!(
+            "warning: failed to write profile to {}: {err}",
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recursive_calls_count_their_time_once() {
+        let outer = c"profile_test_outer";
+        let inner = c"profile_test_inner";
+        unsafe {
+            otter_profile_enter(outer.as_ptr());
+            otter_profile_enter(outer.as_ptr());
+            otter_profile_enter(inner.as_ptr());
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            otter_profile_exit(inner.as_ptr());
+            otter_profile_exit(outer.as_ptr());
+            otter_profile_exit(outer.as_ptr());
+        }
+        let profile = snapshot();
+        let find = |name: &str| {
+            profile
+                .functions
+                .iter()
+                .find(|function| function.name == name)
+                .cloned()
+                .expect("function was profiled")
+        };
+        let (outer, inner) = (find("profile_test_outer"), find("profile_test_inner"));
+        assert_eq!(outer.calls, 2);
+        assert_eq!(inner.calls, 1);
+        assert!(inner.total_ns >= 2_000_000);
+        assert!(outer.total_ns >= inner.total_ns);
+        assert!(outer.total_ns < 2 * inner.total_ns);
+        assert!(outer.self_ns < inner.self_ns);
+    }
+
+    #[test]
+    fn frames_left_by_an_exception_are_discarded() {
+        let caller = c"profile_test_caller";
+        let thrower = c"profile_test_thrower";
+        unsafe {
+            otter_profile_enter(caller.as_ptr());
+            otter_profile_enter(thrower.as_ptr());
+            otter_profile_exit(caller.as_ptr());
+        }
+        STACK.with_borrow(|stack| assert!(stack.is_empty()));
+        let profile = snapshot();
+        assert!(
+            profile
+                .functions
+                .iter()
+                .all(|function| function.name != "profile_test_thrower")
+        );
+    }
+}
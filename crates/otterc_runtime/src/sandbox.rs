@@ -0,0 +1,65 @@
+//! Sandbox stubs
+//!
+//! Under `--sandbox` the symbol registry resolves functions that need
+//! filesystem, network, process or FFI plugin access to one of these stubs,
+//! picked by return type. Each stub raises a catchable permission error with
+//! code [`PERMISSION_DENIED`] and returns an empty value, so a sandboxed
+//! program keeps running and can check `runtime.error_has_error()`.
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use crate::error::{ErrorStack, OtError};
+
+/// Error code carried by sandbox permission errors
+pub const PERMISSION_DENIED: i32 = 403;
+
+fn deny() {
+    ErrorStack::raise(OtError::with_code(
+        "permission denied: filesystem, network, process and FFI plugin access are disabled in sandbox mode",
+        PERMISSION_DENIED,
+    ));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_unit() {
+    deny();
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_bool() -> bool {
+    deny();
+    false
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_i32() -> i32 {
+    deny();
+    0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_i64() -> i64 {
+    deny();
+    0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_f64() -> f64 {
+    deny();
+    0.0
+}
+
+/// Returns a fresh empty string so callers can use and free it as usual
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_str() -> *mut c_char {
+    deny();
+    CString::default().into_raw()
+}
+
+/// Returns the null handle for lists, maps and opaque values
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_sandbox_denied_handle() -> u64 {
+    deny();
+    0
+}
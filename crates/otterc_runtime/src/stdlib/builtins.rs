@@ -6,6 +6,8 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 
+use crate::memory::histogram::{Histogram, UNTRACKED};
+use crate::stdlib::panic_hook::run_panic_hook;
 use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
 
 // ============================================================================
@@ -13,10 +15,10 @@ use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry
 // For lists and maps, we'll use opaque handles
 // ============================================================================
 
-type HandleId = u64;
+pub(crate) type HandleId = u64;
 static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
 
-fn next_handle_id() -> HandleId {
+pub(crate) fn next_handle_id() -> HandleId {
     NEXT_HANDLE_ID.fetch_add(1, Ordering::SeqCst)
 }
 
@@ -130,11 +132,11 @@ pub struct List {
 pub static LISTS: Lazy<RwLock<std::collections::HashMap<HandleId, List>>> =
     Lazy::new(|| RwLock::new(std::collections::HashMap::new()));
 
-struct Map {
-    items: std::collections::HashMap<String, Value>,
+pub(crate) struct Map {
+    pub(crate) items: std::collections::HashMap<String, Value>,
 }
 
-static MAPS: Lazy<RwLock<std::collections::HashMap<HandleId, Map>>> =
+pub(crate) static MAPS: Lazy<RwLock<std::collections::HashMap<HandleId, Map>>> =
     Lazy::new(|| RwLock::new(std::collections::HashMap::new()));
 
 struct ArrayIterator {
@@ -153,7 +155,56 @@ struct StringIterator {
 static STRING_ITERATORS: Lazy<RwLock<std::collections::HashMap<HandleId, StringIterator>>> =
     Lazy::new(|| RwLock::new(std::collections::HashMap::new()));
 
-fn value_to_string(value: &Value) -> String {
+/// Counts the values held by the handle registries into `histogram`. The
+/// sizes are what the registry entry owns: the element storage and the text
+/// of inline strings, not the lists and maps it refers to.
+pub(crate) fn census(histogram: &mut Histogram) {
+    fn value_bytes(value: &Value) -> usize {
+        match value {
+            Value::String(s) => s.capacity(),
+            _ => 0,
+        }
+    }
+
+    for list in LISTS.read().values() {
+        let bytes = std::mem::size_of::<List>()
+            + list.items.capacity() * std::mem::size_of::<Value>()
+            + list.items.iter().map(value_bytes).sum::<usize>();
+        histogram.add("list", UNTRACKED, bytes);
+    }
+    for map in MAPS.read().values() {
+        let bytes = std::mem::size_of::<Map>()
+            + map.items.capacity() * std::mem::size_of::<(String, Value)>()
+            + map
+                .items
+                .iter()
+                .map(|(key, value)| key.capacity() + value_bytes(value))
+                .sum::<usize>();
+        histogram.add("map", UNTRACKED, bytes);
+    }
+    for value in RUNTIME_VALUES.read().values() {
+        let type_name = match value.value {
+            Value::F64(_) => "float",
+            _ => "int",
+        };
+        histogram.add(type_name, UNTRACKED, std::mem::size_of::<RuntimeValue>());
+    }
+    for error in ERRORS.read().values() {
+        let bytes = std::mem::size_of::<Error>()
+            + error.message.capacity()
+            + error.location.as_ref().map_or(0, String::capacity);
+        histogram.add("error", UNTRACKED, bytes);
+    }
+    for _ in ARRAY_ITERATORS.read().values() {
+        histogram.add("iterator", UNTRACKED, std::mem::size_of::<ArrayIterator>());
+    }
+    for iterator in STRING_ITERATORS.read().values() {
+        let bytes = std::mem::size_of::<StringIterator>() + iterator.string.capacity();
+        histogram.add("iterator", UNTRACKED, bytes);
+    }
+}
+
+pub(crate) fn value_to_string(value: &Value) -> String {
     match value {
         Value::Unit => "None".to_string(),
         Value::Bool(b) => b.to_string(),
@@ -230,6 +281,20 @@ fn stringify_map_handle(handle: HandleId) -> String {
 // Error type representation
 struct Error {
     message: String,
+    /// The error this one adds context to
+    cause: Option<HandleId>,
+    /// `file:line:column` of the `error()` or `.context()` call that made it
+    location: Option<String>,
+}
+
+impl Error {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            cause: None,
+            location: None,
+        }
+    }
 }
 
 static ERRORS: Lazy<RwLock<std::collections::HashMap<HandleId, Error>>> =
@@ -238,11 +303,10 @@ static ERRORS: Lazy<RwLock<std::collections::HashMap<HandleId, Error>>> =
 // Thread-local panic state
 thread_local! {
     static PANIC_STATE: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
-}
-
-// Thread-local defer stack
-thread_local! {
-    static DEFER_STACK: std::cell::RefCell<Vec<extern "C" fn()>> = std::cell::RefCell::new(Vec::new());
+    /// The error value being raised by `panic(err)`, handed to `try` as is
+    static PANIC_ERROR: std::cell::Cell<Option<HandleId>> = const { std::cell::Cell::new(None) };
+    /// Number of `try` calls running on this thread
+    static TRY_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
 }
 
 // ============================================================================
@@ -352,6 +416,195 @@ pub unsafe extern "C" fn otter_builtin_str_contains(
     }
 }
 
+// ============================================================================
+// Methods on builtin types ("abc".upper(), m.keys(), ...)
+// ============================================================================
+
+/// Borrows a string passed in from Otter code
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string that outlives the
+/// returned reference
+unsafe fn borrow_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s).to_str().ok() }
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s)
+        .ok()
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+fn new_list(items: Vec<Value>) -> u64 {
+    let id = next_handle_id();
+    LISTS.write().insert(id, List { items });
+    id
+}
+
+/// `s.upper()`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_upper(s: *const c_char) -> *mut c_char {
+    into_c_string(unsafe { borrow_str(s) }.unwrap_or("").to_uppercase())
+}
+
+/// `s.lower()`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_lower(s: *const c_char) -> *mut c_char {
+    into_c_string(unsafe { borrow_str(s) }.unwrap_or("").to_lowercase())
+}
+
+/// `s.strip()`, without leading and trailing whitespace
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_strip(s: *const c_char) -> *mut c_char {
+    into_c_string(unsafe { borrow_str(s) }.unwrap_or("").trim().to_string())
+}
+
+/// `s.to_json()`, the string as a quoted and escaped JSON string
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_to_json(s: *const c_char) -> *mut c_char {
+    let s = unsafe { borrow_str(s) }.unwrap_or("");
+    into_c_string(serde_json::to_string(s).unwrap_or_default())
+}
+
+/// `s.starts_with(prefix)`
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_starts_with(
+    s: *const c_char,
+    prefix: *const c_char,
+) -> bool {
+    match unsafe { (borrow_str(s), borrow_str(prefix)) } {
+        (Some(s), Some(prefix)) => s.starts_with(prefix),
+        _ => false,
+    }
+}
+
+/// `s.ends_with(suffix)`
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_ends_with(
+    s: *const c_char,
+    suffix: *const c_char,
+) -> bool {
+    match unsafe { (borrow_str(s), borrow_str(suffix)) } {
+        (Some(s), Some(suffix)) => s.ends_with(suffix),
+        _ => false,
+    }
+}
+
+/// `s.replace(from, to)`, replacing every occurrence
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_replace(
+    s: *const c_char,
+    from: *const c_char,
+    to: *const c_char,
+) -> *mut c_char {
+    let (s, from, to) = unsafe { (borrow_str(s), borrow_str(from), borrow_str(to)) };
+    let s = s.unwrap_or("");
+    match (from, to) {
+        (Some(from), Some(to)) if !from.is_empty() => into_c_string(s.replace(from, to)),
+        _ => into_c_string(s.to_string()),
+    }
+}
+
+/// `s.split(sep)`, a list of the pieces between occurrences of `sep`. An
+/// empty `sep` splits on runs of whitespace.
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_str_split(s: *const c_char, sep: *const c_char) -> u64 {
+    let (s, sep) = unsafe { (borrow_str(s).unwrap_or(""), borrow_str(sep).unwrap_or("")) };
+    let pieces: Vec<Value> = if sep.is_empty() {
+        s.split_whitespace()
+            .map(|piece| Value::String(piece.to_string()))
+            .collect()
+    } else {
+        s.split(sep)
+            .map(|piece| Value::String(piece.to_string()))
+            .collect()
+    };
+    new_list(pieces)
+}
+
+/// Entries of a map ordered by key, so `keys()` and `values()` line up and
+/// iterate the same way on every run
+fn sorted_map_entries(handle: u64) -> Vec<(String, Value)> {
+    let maps = MAPS.read();
+    let mut entries: Vec<(String, Value)> = maps
+        .get(&handle)
+        .map(|map| {
+            map.items
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+/// `m.keys()`, in sorted order
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_map_keys(handle: u64) -> u64 {
+    new_list(
+        sorted_map_entries(handle)
+            .into_iter()
+            .map(|(key, _)| Value::String(key))
+            .collect(),
+    )
+}
+
+/// `m.values()`, in the order of `m.keys()`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_map_values(handle: u64) -> u64 {
+    new_list(
+        sorted_map_entries(handle)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect(),
+    )
+}
+
+/// `n.abs()`. The absolute value of the smallest `int` does not fit, so it
+/// stays as is.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_int_abs(value: i64) -> i64 {
+    value.wrapping_abs()
+}
+
 // ============================================================================
 // append(x, val) - Append to a list
 // ============================================================================
@@ -543,6 +796,28 @@ pub extern "C" fn otter_builtin_list_new() -> u64 {
     id
 }
 
+/// build a list holding one int per byte of `data`, for bytes literals
+/// stored in the binary's data
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_list_from_bytes(data: *const u8, len: i64) -> u64 {
+    let items = if data.is_null() || len <= 0 {
+        Vec::new()
+    } else {
+        let bytes = unsafe { std::slice::from_raw_parts(data, len as usize) };
+        bytes
+            .iter()
+            .map(|byte| Value::I64(i64::from(*byte)))
+            .collect()
+    };
+    let id = next_handle_id();
+    LISTS.write().insert(id, List { items });
+    id
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_builtin_map_new() -> u64 {
     let id = next_handle_id();
@@ -586,6 +861,21 @@ pub unsafe extern "C" fn otter_builtin_map_get(handle: u64, key: *const c_char)
     }
 }
 
+/// see if the map `handle` has an entry for `key`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_map_has(handle: u64, key: *const c_char) -> bool {
+    if key.is_null() {
+        return false;
+    }
+
+    let key_str = unsafe { CStr::from_ptr(key).to_str().unwrap_or("") };
+    map_value(handle, key_str).is_some()
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_builtin_list_get_int(handle: u64, index: i64) -> i64 {
     match list_value(handle, index) {
@@ -1024,6 +1314,17 @@ pub extern "C" fn otter_builtin_iter_free_string(iter_handle: u64) {
 /// this function dereferences a raw pointer
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn otter_builtin_panic(msg: *const c_char) {
+    unsafe { otter_builtin_panic_at(msg, std::ptr::null()) }
+}
+
+/// `panic(message)`, with the `file:line:column` of the call for the panic
+/// hook. `location` may be null.
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_panic_at(msg: *const c_char, location: *const c_char) {
     let message = if msg.is_null() {
         "panic: unknown error".to_string()
     } else {
@@ -1035,6 +1336,12 @@ pub unsafe extern "C" fn otter_builtin_panic(msg: *const c_char) {
         }
     };
 
+    if TRY_DEPTH.get() == 0 {
+        let location =
+            (!location.is_null()).then(|| unsafe { CStr::from_ptr(location) }.to_string_lossy());
+        run_panic_hook(&message, location.as_deref());
+    }
+
     // Set panic state in thread-local storage
     PANIC_STATE.with(|state| {
         *state.borrow_mut() = Some(message.clone());
@@ -1051,6 +1358,33 @@ pub unsafe extern "C" fn otter_builtin_panic(msg: *const c_char) {
     }
 }
 
+/// Reports a failed `assert` statement and panics. `msg` may be null when the
+/// assert has no message; `location` is the `file:line:column` of the condition.
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_assert_fail(msg: *const c_char, location: *const c_char) {
+    let read = |ptr: *const c_char| {
+        (!ptr.is_null()).then(|| {
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+    let location = read(location).unwrap_or_else(|| "<unknown>".to_string());
+    let message = match read(msg) {
+        Some(msg) => format!("assertion failed at {location}: {msg}"),
+        None => format!("assertion failed at {location}"),
+    };
+    let message = CString::new(message).unwrap_or_default();
+    let location = CString::new(location).unwrap_or_default();
+    unsafe {
+        otter_builtin_panic_at(message.as_ptr(), location.as_ptr());
+    }
+}
+
 // ============================================================================
 // recover() -> any - Recover from panic
 // Returns the panic message if recovering, null otherwise
@@ -1095,9 +1429,12 @@ pub extern "C" fn otter_builtin_try(callback: TryFn) -> u64 {
     PANIC_STATE.with(|state| {
         *state.borrow_mut() = None;
     });
+    PANIC_ERROR.set(None);
 
     // Execute function in a catch_unwind block
+    TRY_DEPTH.set(TRY_DEPTH.get() + 1);
     let result = catch_unwind(AssertUnwindSafe(|| callback()));
+    TRY_DEPTH.set(TRY_DEPTH.get() - 1);
 
     match result {
         Ok(ptr) => {
@@ -1109,7 +1446,7 @@ pub extern "C" fn otter_builtin_try(callback: TryFn) -> u64 {
                 if let Some(msg) = panic_msg {
                     // Create error handle
                     let error_id = next_handle_id();
-                    ERRORS.write().insert(error_id, Error { message: msg });
+                    ERRORS.write().insert(error_id, Error::new(msg));
 
                     TRY_RESULTS.write().insert(
                         id,
@@ -1153,10 +1490,11 @@ pub extern "C" fn otter_builtin_try(callback: TryFn) -> u64 {
                     .unwrap_or_else(|| "panic: unknown error".to_string())
             });
 
-            let error_id = next_handle_id();
-            ERRORS
-                .write()
-                .insert(error_id, Error { message: panic_msg });
+            let error_id = PANIC_ERROR.take().unwrap_or_else(|| {
+                let error_id = next_handle_id();
+                ERRORS.write().insert(error_id, Error::new(panic_msg));
+                error_id
+            });
 
             TRY_RESULTS.write().insert(
                 id,
@@ -1211,29 +1549,126 @@ pub extern "C" fn otter_builtin_error_message(error_handle: u64) -> *mut c_char
     }
 }
 
-// ============================================================================
-// defer(fn) - Defer function execution until scope exit
-// ============================================================================
+/// Creates an error value for `error(message)`, `location` being where the
+/// call is
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_error_new(
+    message: *const c_char,
+    location: *const c_char,
+) -> u64 {
+    unsafe { new_error(message, None, location) }
+}
+
+/// `err.context(message)`: a new error with `message` whose cause is `error`
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_builtin_error_context(
+    error: u64,
+    message: *const c_char,
+    location: *const c_char,
+) -> u64 {
+    let cause = ERRORS.read().contains_key(&error).then_some(error);
+    unsafe { new_error(message, cause, location) }
+}
+
+unsafe fn new_error(
+    message: *const c_char,
+    cause: Option<HandleId>,
+    location: *const c_char,
+) -> u64 {
+    let read = |ptr: *const c_char| {
+        (!ptr.is_null()).then(|| {
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+    let id = next_handle_id();
+    ERRORS.write().insert(
+        id,
+        Error {
+            message: read(message).unwrap_or_default(),
+            cause,
+            location: read(location),
+        },
+    );
+    id
+}
 
-type DeferFn = extern "C" fn();
+/// `error` and its causes, one per line with where each was made:
+///
+/// ```text
+/// error: failed to start
+///   at main.ot:12:11
+/// caused by: while reading config
+///   at main.ot:7:15
+/// caused by: file not found
+///   at main.ot:3:12
+/// ```
+fn render_error(error: HandleId) -> String {
+    let errors = ERRORS.read();
+    let mut out = String::new();
+    let mut next = errors.get(&error);
+    let mut label = "error";
+    while let Some(error) = next {
+        out.push_str(&format!("{label}: {}\n", error.message));
+        if let Some(location) = &error.location {
+            out.push_str(&format!("  at {location}\n"));
+        }
+        label = "caused by";
+        next = error.cause.and_then(|cause| errors.get(&cause));
+    }
+    out
+}
 
 #[unsafe(no_mangle)]
-pub extern "C" fn otter_builtin_defer(callback: DeferFn) {
-    DEFER_STACK.with(|stack| {
-        stack.borrow_mut().push(callback);
-    });
+pub extern "C" fn otter_builtin_error_to_string(error: u64) -> *mut c_char {
+    let rendered = render_error(error);
+    CString::new(rendered.trim_end())
+        .ok()
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
 }
 
-/// Execute all deferred functions (called at scope exit)
-/// This should be called by the compiler-generated code
-#[unsafe(no_mangle)]
-pub extern "C" fn otter_builtin_run_defers() {
-    DEFER_STACK.with(|stack| {
-        let mut stack_ref = stack.borrow_mut();
-        while let Some(callback) = stack_ref.pop() {
-            callback();
+/// `panic(err)`. An error no `try` catches ends the program with its report,
+/// unless it was raised in a task, whose panic report names the task.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_error_panic(error: u64) {
+    let rendered = render_error(error);
+    let location = ERRORS
+        .read()
+        .get(&error)
+        .and_then(|error| error.location.clone());
+    if TRY_DEPTH.get() == 0 && crate::task::current_task() == 0 {
+        run_panic_hook(rendered.trim_end(), location.as_deref());
+        #[expect(
+            clippy::print_stderr,
+            reason = "The uncaught error is the last thing the program reports"
+        )]
+        {
+            eprint!("{rendered}");
         }
-    });
+        #[expect(clippy::exit, reason = "An uncaught error ends the program")]
+        std::process::exit(1);
+    }
+    PANIC_ERROR.set(Some(error));
+    let message = CString::new(rendered.trim_end()).unwrap_or_default();
+    let location = location.and_then(|location| CString::new(location).ok());
+    unsafe {
+        otter_builtin_panic_at(
+            message.as_ptr(),
+            location
+                .as_ref()
+                .map_or(std::ptr::null(), |location| location.as_ptr()),
+        );
+    }
 }
 
 // ============================================================================
@@ -1543,6 +1978,107 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Bool),
     });
 
+    // Methods on builtin types, resolved through `TypeInfo::builtin_method`
+    for (name, symbol, params, result) in [
+        (
+            "str.upper",
+            "otter_builtin_str_upper",
+            vec![FfiType::Str],
+            FfiType::Str,
+        ),
+        (
+            "str.lower",
+            "otter_builtin_str_lower",
+            vec![FfiType::Str],
+            FfiType::Str,
+        ),
+        (
+            "str.strip",
+            "otter_builtin_str_strip",
+            vec![FfiType::Str],
+            FfiType::Str,
+        ),
+        (
+            "str.to_json",
+            "otter_builtin_str_to_json",
+            vec![FfiType::Str],
+            FfiType::Str,
+        ),
+        (
+            "str.starts_with",
+            "otter_builtin_str_starts_with",
+            vec![FfiType::Str, FfiType::Str],
+            FfiType::Bool,
+        ),
+        (
+            "str.ends_with",
+            "otter_builtin_str_ends_with",
+            vec![FfiType::Str, FfiType::Str],
+            FfiType::Bool,
+        ),
+        (
+            "str.replace",
+            "otter_builtin_str_replace",
+            vec![FfiType::Str, FfiType::Str, FfiType::Str],
+            FfiType::Str,
+        ),
+        (
+            "str.split",
+            "otter_builtin_str_split",
+            vec![FfiType::Str, FfiType::Str],
+            FfiType::List,
+        ),
+        (
+            "map.keys",
+            "otter_builtin_map_keys",
+            vec![FfiType::Map],
+            FfiType::List,
+        ),
+        (
+            "map.values",
+            "otter_builtin_map_values",
+            vec![FfiType::Map],
+            FfiType::List,
+        ),
+        (
+            "int.abs",
+            "otter_builtin_int_abs",
+            vec![FfiType::I64],
+            FfiType::I64,
+        ),
+        // The math module's functions, available without `use math`
+        (
+            "float.abs",
+            "otter_std_math_abs",
+            vec![FfiType::F64],
+            FfiType::F64,
+        ),
+        (
+            "float.floor",
+            "otter_std_math_floor",
+            vec![FfiType::F64],
+            FfiType::F64,
+        ),
+        (
+            "float.ceil",
+            "otter_std_math_ceil",
+            vec![FfiType::F64],
+            FfiType::F64,
+        ),
+        (
+            "float.round",
+            "otter_std_math_round",
+            vec![FfiType::F64],
+            FfiType::F64,
+        ),
+    ] {
+        registry.register(FfiFunction {
+            name: name.into(),
+            symbol: symbol.into(),
+            signature: FfiSignature::new(params, result),
+        });
+    }
+
     // append() functions
     registry.register(FfiFunction {
         name: "append<list,string>".into(),
@@ -1614,6 +2150,12 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![], FfiType::List),
     });
 
+    registry.register(FfiFunction {
+        name: "list.from_bytes".into(),
+        symbol: "otter_builtin_list_from_bytes".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::I64], FfiType::List),
+    });
+
     registry.register(FfiFunction {
         name: "runtime.list.length".into(),
         symbol: "otter_runtime_list_length".into(),
@@ -1668,6 +2210,12 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::List, FfiType::I64], FfiType::Map),
     });
 
+    registry.register(FfiFunction {
+        name: "map.has".into(),
+        symbol: "otter_builtin_map_has".into(),
+        signature: FfiSignature::new(vec![FfiType::Map, FfiType::Str], FfiType::Bool),
+    });
+
     registry.register(FfiFunction {
         name: "map.get".into(),
         symbol: "otter_builtin_map_get".into(),
@@ -1789,6 +2337,18 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::Str], FfiType::Unit),
     });
 
+    registry.register(FfiFunction {
+        name: "__otter_panic_at".into(),
+        symbol: "otter_builtin_panic_at".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_assert_fail".into(),
+        symbol: "otter_builtin_assert_fail".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Unit),
+    });
+
     registry.register(FfiFunction {
         name: "recover".into(),
         symbol: "otter_builtin_recover".into(),
@@ -1819,10 +2379,31 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Str),
     });
 
-    // defer() function
+    // `error(message)` and `err.context(message)`, with where they are called
+    registry.register(FfiFunction {
+        name: "__otter_error_new".into(),
+        symbol: "otter_builtin_error_new".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "error.context".into(),
+        symbol: "otter_builtin_error_context".into(),
+        signature: FfiSignature::new(
+            vec![FfiType::Opaque, FfiType::Str, FfiType::Str],
+            FfiType::Opaque,
+        ),
+    });
+
+    registry.register(FfiFunction {
+        name: "error.to_string".into(),
+        symbol: "otter_builtin_error_to_string".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Str),
+    });
+
     registry.register(FfiFunction {
-        name: "defer".into(),
-        symbol: "otter_builtin_defer".into(),
+        name: "__otter_error_panic".into(),
+        symbol: "otter_builtin_error_panic".into(),
         signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
     });
 
@@ -1959,6 +2540,30 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         },
     });
 
+    registry.register(FfiFunction {
+        name: "gc.pin".into(),
+        symbol: "otter_gc_pin".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.unpin".into(),
+        symbol: "otter_gc_unpin".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.pinned".into(),
+        symbol: "otter_gc_pinned".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.pin_count".into(),
+        symbol: "otter_gc_pin_count".into(),
+        signature: FfiSignature::new(vec![], FfiType::I64),
+    });
+
     registry.register(FfiFunction {
         name: "gc.enable".into(),
         symbol: "otter_gc_enable".into(),
@@ -1977,6 +2582,36 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![], FfiType::Bool),
     });
 
+    registry.register(FfiFunction {
+        name: "gc.on_collect".into(),
+        symbol: "otter_gc_on_collect".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.off_collect".into(),
+        symbol: "otter_gc_off_collect".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.stats".into(),
+        symbol: "otter_gc_stats".into(),
+        signature: FfiSignature::new(vec![], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "memory.histogram".into(),
+        symbol: "otter_memory_histogram".into(),
+        signature: FfiSignature::new(vec![], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "gc.metrics".into(),
+        symbol: "otter_gc_metrics".into(),
+        signature: FfiSignature::new(vec![], FfiType::Str),
+    });
+
     registry.register(FfiFunction {
         name: "arena.create".into(),
         symbol: "otter_arena_create".into(),
@@ -2004,6 +2639,64 @@ fn register_builtin_symbols(registry: &SymbolRegistry) {
         ),
     });
 
+    // Preemption point emitted at loop back-edges by `--preempt-loops`
+    registry.register(FfiFunction {
+        name: "__otter_preempt_point".into(),
+        symbol: "otter_task_preempt_point".into(),
+        signature: FfiSignature::new(vec![], FfiType::Unit),
+    });
+
+    // Brackets calls that may block a task worker
+    registry.register(FfiFunction {
+        name: "__otter_blocking_enter".into(),
+        symbol: "otter_blocking_enter".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_blocking_exit".into(),
+        symbol: "otter_blocking_exit".into(),
+        signature: FfiSignature::new(vec![], FfiType::Unit),
+    });
+
+    // Brackets every function body compiled for `otter profile report`
+    registry.register(FfiFunction {
+        name: "__otter_profile_enter".into(),
+        symbol: "otter_profile_enter".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_profile_exit".into(),
+        symbol: "otter_profile_exit".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Unit),
+    });
+
+    // `for await` loops over channels and streams
+    registry.register(FfiFunction {
+        name: "__otter_stream_iter".into(),
+        symbol: "otter_stream_iter".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_stream_has_next".into(),
+        symbol: "otter_stream_has_next".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_stream_next".into(),
+        symbol: "otter_stream_next".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_stream_free".into(),
+        symbol: "otter_stream_free".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+
     // Array Iterator functions
     registry.register(FfiFunction {
         name: "__otter_iter_array".into(),
@@ -0,0 +1,489 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use crate::stdlib::builtins::otter_builtin_panic;
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Typed Configuration Loading
+// ============================================================================
+//
+// `config.load("app.toml", AppConfig)` is lowered by the compiler into a call to
+// `__otter_config_load` with a schema derived from the struct layout, followed
+// by one typed getter per field. The schema is a `;`-separated list of
+// `path:kind` entries where nested struct fields use dotted paths, e.g.
+// `name:str;server.port:int;server.tls:bool`.
+
+static CONFIGS: Lazy<RwLock<HashMap<u64, Value>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Int,
+    /// A sized integer such as `u8`, with the smallest and largest value it holds
+    Sized(&'static str, i128, i128),
+    Float,
+    Bool,
+    Str,
+}
+
+impl FieldKind {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "int" => Some(Self::Int),
+            "i8" => Some(Self::Sized("i8", i8::MIN.into(), i8::MAX.into())),
+            "i16" => Some(Self::Sized("i16", i16::MIN.into(), i16::MAX.into())),
+            "i32" => Some(Self::Sized("i32", i32::MIN.into(), i32::MAX.into())),
+            "u8" => Some(Self::Sized("u8", 0, u8::MAX.into())),
+            "u16" => Some(Self::Sized("u16", 0, u16::MAX.into())),
+            "u32" => Some(Self::Sized("u32", 0, u32::MAX.into())),
+            "u64" => Some(Self::Sized("u64", 0, u64::MAX.into())),
+            "float" => Some(Self::Float),
+            "bool" => Some(Self::Bool),
+            "str" => Some(Self::Str),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Int | Self::Sized(..) => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::Str => "string",
+        }
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            Self::Int => value.as_i64().is_some(),
+            Self::Sized(_, min, max) => value
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| value.as_u64().map(i128::from))
+                .is_some_and(|v| (min..=max).contains(&v)),
+            Self::Float => value.is_number(),
+            Self::Bool => value.is_boolean(),
+            Self::Str => value.is_string(),
+        }
+    }
+}
+
+fn parse_schema(schema: &str) -> Vec<(Vec<String>, FieldKind)> {
+    schema
+        .split(';')
+        .filter_map(|entry| {
+            let (path, kind) = entry.split_once(':')?;
+            let kind = FieldKind::parse(kind.trim())?;
+            let path = path.split('.').map(str::to_string).collect();
+            Some((path, kind))
+        })
+        .collect()
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "table",
+    }
+}
+
+/// 1-based line and column of byte `offset` in `source`
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let prefix = &source[..offset.min(source.len())];
+    let line = prefix.matches('\n').count() + 1;
+    let line_start = prefix.rfind('\n').map_or(0, |idx| idx + 1);
+    (line, prefix[line_start..].chars().count() + 1)
+}
+
+/// Finds where the key for `path` is written in `source`, searching for each
+/// segment after the previous one. Keys are recognised in all supported
+/// formats: `key = ...` and `[table.key]` in TOML, `"key": ...` in JSON and
+/// `key: ...` in YAML.
+fn locate_key(source: &str, path: &[String]) -> Option<usize> {
+    let mut from = 0;
+    let mut found = None;
+    for segment in path {
+        let offset = find_key(source, segment, from)?;
+        found = Some(offset);
+        from = offset + segment.len();
+    }
+    found
+}
+
+fn find_key(source: &str, key: &str, from: usize) -> Option<usize> {
+    let is_key_char = |ch: char| ch.is_alphanumeric() || ch == '_' || ch == '-';
+    let mut search = from;
+    while let Some(idx) = source.get(search..)?.find(key) {
+        let start = search + idx;
+        let end = start + key.len();
+        search = end;
+
+        let before = source[..start].trim_end_matches(['"', '\'']);
+        let after = source[end..].trim_start_matches(['"', '\'']);
+        if before.chars().next_back().is_some_and(is_key_char)
+            || after.chars().next().is_some_and(is_key_char)
+        {
+            continue;
+        }
+
+        let preceded = before
+            .chars()
+            .next_back()
+            .is_none_or(|ch| matches!(ch, '\n' | ' ' | '\t' | '[' | '.' | '{' | ','));
+        let followed = after
+            .trim_start_matches([' ', '\t'])
+            .chars()
+            .next()
+            .is_some_and(|ch| matches!(ch, ':' | '=' | ']' | '.'));
+        if preceded && followed {
+            return Some(start);
+        }
+    }
+    None
+}
+
+fn parse_document(format: Format, source: &str, file: &str) -> Result<Value, String> {
+    let located = |offset: Option<usize>, message: String| match offset {
+        Some(offset) => {
+            let (line, col) = line_col(source, offset);
+            format!("{file}:{line}:{col}: {message}")
+        }
+        None => format!("{file}: {message}"),
+    };
+
+    match format {
+        Format::Json => serde_json::from_str(source).map_err(|err| {
+            format!(
+                "{file}:{}:{}: invalid JSON: {err}",
+                err.line(),
+                err.column()
+            )
+        }),
+        Format::Yaml => serde_yaml::from_str(source).map_err(|err| {
+            let offset = err.location().map(|location| location.index());
+            located(offset, format!("invalid YAML: {err}"))
+        }),
+        Format::Toml => parse_toml(source, file, located),
+    }
+}
+
+#[cfg(feature = "toml-config")]
+fn parse_toml(
+    source: &str,
+    _file: &str,
+    located: impl Fn(Option<usize>, String) -> String,
+) -> Result<Value, String> {
+    let value: toml::Value = toml::from_str(source).map_err(|err| {
+        let offset = err.span().map(|span| span.start);
+        located(offset, format!("invalid TOML: {}", err.message()))
+    })?;
+    serde_json::to_value(value).map_err(|err| located(None, err.to_string()))
+}
+
+#[cfg(not(feature = "toml-config"))]
+fn parse_toml(
+    _source: &str,
+    file: &str,
+    _located: impl Fn(Option<usize>, String) -> String,
+) -> Result<Value, String> {
+    Err(format!(
+        "{file}: TOML support not enabled. Enable the 'toml-config' feature."
+    ))
+}
+
+/// Checks `root` against the schema and returns one message per missing or
+/// mistyped field, located in `source`.
+fn validate(root: &Value, schema: &str, source: &str, file: &str) -> Vec<String> {
+    let location = |path: &[String]| {
+        let offset = locate_key(source, path).unwrap_or(0);
+        let (line, col) = line_col(source, offset);
+        format!("{file}:{line}:{col}")
+    };
+
+    let mut errors = Vec::new();
+    let mut reported = HashSet::new();
+    'fields: for (path, kind) in parse_schema(schema) {
+        let mut current = root;
+        for (depth, segment) in path.iter().enumerate() {
+            let prefix = &path[..=depth];
+            let parent = &path[..depth];
+            let Some(table) = current.as_object() else {
+                if reported.insert(parent.join(".")) {
+                    errors.push(format!(
+                        "{}: field '{}' expected table, found {}",
+                        location(parent),
+                        parent.join("."),
+                        value_kind(current)
+                    ));
+                }
+                continue 'fields;
+            };
+            let Some(next) = table.get(segment) else {
+                if reported.insert(prefix.join(".")) {
+                    errors.push(format!(
+                        "{}: missing field '{}'",
+                        location(parent),
+                        prefix.join(".")
+                    ));
+                }
+                continue 'fields;
+            };
+            current = next;
+        }
+
+        if !kind.accepts(current) {
+            let found = match (kind, current) {
+                (FieldKind::Sized(name, ..), Value::Number(n)) if n.is_i64() || n.is_u64() => {
+                    format!("int out of {name} range")
+                }
+                _ => value_kind(current).to_string(),
+            };
+            errors.push(format!(
+                "{}: field '{}' expected {}, found {}",
+                location(&path),
+                path.join("."),
+                kind.name(),
+                found
+            ));
+        }
+    }
+    errors
+}
+
+fn load(path: &str, schema: &str) -> Result<Value, String> {
+    let format = Format::from_path(path).ok_or_else(|| {
+        format!("{path}: unsupported config format (expected .toml, .json, .yaml or .yml)")
+    })?;
+    let source =
+        std::fs::read_to_string(path).map_err(|err| format!("{path}: cannot read file: {err}"))?;
+    let root = parse_document(format, &source, path)?;
+    let errors = validate(&root, schema, &source, path);
+    if errors.is_empty() {
+        Ok(root)
+    } else {
+        Err(errors.join("\n  "))
+    }
+}
+
+fn read_c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn with_field<T>(
+    handle: u64,
+    path: *const c_char,
+    f: impl FnOnce(&Value) -> Option<T>,
+) -> Option<T> {
+    let path = read_c_str(path);
+    let configs = CONFIGS.read();
+    let value = path
+        .split('.')
+        .try_fold(configs.get(&handle)?, |value, segment| value.get(segment))?;
+    f(value)
+}
+
+/// load the config file at `path` and check it against `schema`, panicking with
+/// every missing or mistyped field if it does not match
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_config_load(path: *const c_char, schema: *const c_char) -> u64 {
+    let path = read_c_str(path);
+    match load(&path, &read_c_str(schema)) {
+        Ok(root) => {
+            let id = NEXT_HANDLE_ID.fetch_add(1, Ordering::SeqCst);
+            CONFIGS.write().insert(id, root);
+            id
+        }
+        Err(message) => {
+            let message =
+                CString::new(format!("failed to load config:\n  {message}")).unwrap_or_default();
+            unsafe {
+                otter_builtin_panic(message.as_ptr());
+            }
+            0
+        }
+    }
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_config_get_int(handle: u64, path: *const c_char) -> i64 {
+    with_field(handle, path, Value::as_i64).unwrap_or_default()
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_config_get_float(handle: u64, path: *const c_char) -> f64 {
+    with_field(handle, path, Value::as_f64).unwrap_or_default()
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_config_get_bool(handle: u64, path: *const c_char) -> bool {
+    with_field(handle, path, Value::as_bool).unwrap_or_default()
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_config_get_str(handle: u64, path: *const c_char) -> *mut c_char {
+    let value =
+        with_field(handle, path, |value| value.as_str().map(str::to_string)).unwrap_or_default();
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or_else(|_| std::ptr::null_mut())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_config_free(handle: u64) {
+    CONFIGS.write().remove(&handle);
+}
+
+fn register_std_config_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "__otter_config_load".into(),
+        symbol: "otter_std_config_load".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_config_get_int".into(),
+        symbol: "otter_std_config_get_int".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Str], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_config_get_float".into(),
+        symbol: "otter_std_config_get_float".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Str], FfiType::F64),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_config_get_bool".into(),
+        symbol: "otter_std_config_get_bool".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Str], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_config_get_str".into(),
+        symbol: "otter_std_config_get_str".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_config_free".into(),
+        symbol: "otter_std_config_free".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "config",
+        autoload: false,
+        register: register_std_config_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "name:str;debug:bool;server.port:i32;server.ratio:float";
+
+    #[test]
+    fn locates_keys_in_each_format() {
+        let toml = "name = \"app\"\n\n[server]\nport = 80\n";
+        assert_eq!(
+            locate_key(toml, &["server".into(), "port".into()]).map(|o| line_col(toml, o)),
+            Some((4, 1))
+        );
+
+        let json = "{\n  \"server\": {\n    \"port\": 80\n  }\n}";
+        assert_eq!(
+            locate_key(json, &["server".into(), "port".into()]).map(|o| line_col(json, o)),
+            Some((3, 6))
+        );
+
+        let yaml = "import: x\nserver:\n  ports: []\n  port: 80\n";
+        assert_eq!(
+            locate_key(yaml, &["server".into(), "port".into()]).map(|o| line_col(yaml, o)),
+            Some((4, 3))
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_mistyped_fields() {
+        let source = "name: 42\nserver:\n  port: 99999999999\n";
+        let root = parse_document(Format::Yaml, source, "app.yaml").expect("valid yaml");
+        let errors = validate(&root, SCHEMA, source, "app.yaml");
+        assert_eq!(
+            errors,
+            [
+                "app.yaml:1:1: field 'name' expected string, found int",
+                "app.yaml:1:1: missing field 'debug'",
+                "app.yaml:3:3: field 'server.port' expected int, found int out of i32 range",
+                "app.yaml:2:1: missing field 'server.ratio'",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_matching_document() {
+        let source = r#"{"name": "app", "debug": true, "server": {"port": 8080, "ratio": 1}}"#;
+        let root = parse_document(Format::Json, source, "app.json").expect("valid json");
+        assert!(validate(&root, SCHEMA, source, "app.json").is_empty());
+        assert!(
+            parse_document(Format::Json, "{\n  \"name\": }", "bad.json")
+                .unwrap_err()
+                .starts_with("bad.json:2:")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::os::raw::c_char;
+
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+fn read_c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn lookup(name: *const c_char) -> Option<String> {
+    let name = read_c_str(name)?;
+    std::env::var(name).ok()
+}
+
+/// Parses the contents of a `.env` file into key/value pairs.
+///
+/// Supports `KEY=value`, an optional `export ` prefix, `#` comments, single-quoted
+/// (literal) values and double-quoted values with `\n`, `\t`, `\"` and `\\` escapes.
+fn parse_dotenv(contents: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, raw_value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim();
+        if key.is_empty() || key.chars().any(char::is_whitespace) {
+            continue;
+        }
+
+        entries.push((key.to_string(), parse_dotenv_value(raw_value.trim())));
+    }
+
+    entries
+}
+
+fn parse_dotenv_value(raw: &str) -> String {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let end = rest.find('\'').unwrap_or(rest.len());
+        return rest[..end].to_string();
+    }
+
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some(other) => value.push(other),
+                    None => value.push('\\'),
+                },
+                other => value.push(other),
+            }
+        }
+        return value;
+    }
+
+    // Unquoted values end at an inline comment
+    let value = match raw.find(" #") {
+        Some(idx) => &raw[..idx],
+        None => raw,
+    };
+    value.trim_end().to_string()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Loads variables from the `.env` file at `path` into the process environment.
+///
+/// Variables that are already set are left untouched. Returns the number of
+/// variables that were set, or -1 if the file could not be read.
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer and mutates the process environment,
+/// so it must not race with other threads reading or writing environment variables
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_load_dotenv(path: *const c_char) -> i64 {
+    let path = read_c_str(path).unwrap_or_else(|| ".env".to_string());
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return -1;
+    };
+
+    let mut loaded = 0;
+    for (key, value) in parse_dotenv(&contents) {
+        if std::env::var_os(&key).is_some() {
+            continue;
+        }
+        // SAFETY: the caller guarantees no concurrent environment access
+        unsafe {
+            std::env::set_var(&key, &value);
+        }
+        loaded += 1;
+    }
+    loaded
+}
+
+/// returns whether the env variable `name` is set
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_has(name: *const c_char) -> bool {
+    lookup(name).is_some()
+}
+
+/// get the env variable `name`, or `default` if it is unset
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_get(
+    name: *const c_char,
+    default: *const c_char,
+) -> *mut c_char {
+    let value = lookup(name)
+        .or_else(|| read_c_str(default))
+        .unwrap_or_default();
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or_else(|_| std::ptr::null_mut())
+}
+
+/// get the env variable `name` as an integer, or `default` if it is unset or malformed
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_int(name: *const c_char, default: i64) -> i64 {
+    lookup(name)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// get the env variable `name` as a float, or `default` if it is unset or malformed
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_float(name: *const c_char, default: f64) -> f64 {
+    lookup(name)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// get the env variable `name` as a boolean, or `default` if it is unset or malformed
+///
+/// Accepts `1/0`, `true/false`, `yes/no` and `on/off` (case-insensitive).
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_env_bool(name: *const c_char, default: bool) -> bool {
+    lookup(name)
+        .and_then(|value| parse_bool(&value))
+        .unwrap_or(default)
+}
+
+fn register_std_env_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "env.load_dotenv".into(),
+        symbol: "otter_std_env_load_dotenv".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "env.has".into(),
+        symbol: "otter_std_env_has".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "env.get".into(),
+        symbol: "otter_std_env_get".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "env.int".into(),
+        symbol: "otter_std_env_int".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::I64], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "env.float".into(),
+        symbol: "otter_std_env_float".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::F64], FfiType::F64),
+    });
+
+    registry.register(FfiFunction {
+        name: "env.bool".into(),
+        symbol: "otter_std_env_bool".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Bool], FfiType::Bool),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "env",
+        autoload: false,
+        register: register_std_env_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotenv_entries() {
+        let contents = r#"
+# database settings
+export DB_HOST=localhost
+DB_PORT = 5432 # default port
+GREETING="hello\nworld"
+RAW='no $expansion\n'
+not a pair
+"#;
+        let entries = parse_dotenv(contents);
+        assert_eq!(
+            entries,
+            vec![
+                ("DB_HOST".to_string(), "localhost".to_string()),
+                ("DB_PORT".to_string(), "5432".to_string()),
+                ("GREETING".to_string(), "hello\nworld".to_string()),
+                ("RAW".to_string(), "no $expansion\\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_bool_values() {
+        assert_eq!(parse_bool("Yes"), Some(true));
+        assert_eq!(parse_bool("off"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
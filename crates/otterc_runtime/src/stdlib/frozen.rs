@@ -0,0 +1,796 @@
+//! Frozen lists and dicts, made with `list.freeze()` and `dict.freeze()`
+//!
+//! A frozen collection never changes. `set`, `append` and `remove` return a
+//! new collection and leave the one they were called on as it was, so a frozen
+//! value can be handed to other tasks without a lock and kept as a snapshot
+//! for undo. Copies share structure: a frozen list is a spine of fixed-size
+//! chunks and a frozen dict a fixed number of shards picked by key hash, and a
+//! change copies the spine and the one chunk or shard it touches. Nested lists
+//! and dicts are shared by handle, not frozen along with the collection.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::{CStr, CString};
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_char;
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use crate::stdlib::builtins::{
+    HandleId, LISTS, List, MAPS, Map, Value, next_handle_id, value_to_string,
+};
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+/// Elements per chunk of a frozen list
+const CHUNK_LEN: usize = 32;
+/// Shards of a frozen dict
+const SHARDS: usize = 32;
+
+/// An immutable list. Every chunk but the last is full, so an index finds
+/// its chunk by division.
+#[derive(Clone, Default)]
+pub struct FrozenList {
+    chunks: Vec<Arc<Vec<Value>>>,
+    len: usize,
+}
+
+impl FrozenList {
+    pub fn from_items(items: &[Value]) -> Self {
+        Self {
+            chunks: items
+                .chunks(CHUNK_LEN)
+                .map(|chunk| Arc::new(chunk.to_vec()))
+                .collect(),
+            len: items.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.chunks
+            .get(index / CHUNK_LEN)
+            .and_then(|chunk| chunk.get(index % CHUNK_LEN))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// A copy with the element at `index` replaced, or `None` when `index` is
+    /// out of range
+    pub fn with(&self, index: usize, value: Value) -> Option<Self> {
+        if index >= self.len {
+            return None;
+        }
+        let mut copy = self.clone();
+        Arc::make_mut(&mut copy.chunks[index / CHUNK_LEN])[index % CHUNK_LEN] = value;
+        Some(copy)
+    }
+
+    /// A copy with `value` added at the end
+    pub fn appended(&self, value: Value) -> Self {
+        let mut copy = self.clone();
+        match copy.chunks.last_mut() {
+            Some(last) if last.len() < CHUNK_LEN => Arc::make_mut(last).push(value),
+            _ => copy.chunks.push(Arc::new(vec![value])),
+        }
+        copy.len += 1;
+        copy
+    }
+}
+
+/// An immutable dict
+#[derive(Clone)]
+pub struct FrozenDict {
+    shards: Vec<Arc<HashMap<String, Value>>>,
+    len: usize,
+}
+
+impl Default for FrozenDict {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Arc::default()).collect(),
+            len: 0,
+        }
+    }
+}
+
+impl FrozenDict {
+    pub fn from_items(items: &HashMap<String, Value>) -> Self {
+        let mut shards: Vec<HashMap<String, Value>> = (0..SHARDS).map(|_| HashMap::new()).collect();
+        for (key, value) in items {
+            shards[shard_of(key)].insert(key.clone(), value.clone());
+        }
+        Self {
+            shards: shards.into_iter().map(Arc::new).collect(),
+            len: items.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.shards[shard_of(key)].get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.shards.iter().flat_map(|shard| shard.iter())
+    }
+
+    /// A copy with `key` set to `value`
+    pub fn with(&self, key: &str, value: Value) -> Self {
+        let mut copy = self.clone();
+        if Arc::make_mut(&mut copy.shards[shard_of(key)])
+            .insert(key.to_string(), value)
+            .is_none()
+        {
+            copy.len += 1;
+        }
+        copy
+    }
+
+    /// A copy without `key`
+    pub fn without(&self, key: &str) -> Self {
+        if self.get(key).is_none() {
+            return self.clone();
+        }
+        let mut copy = self.clone();
+        Arc::make_mut(&mut copy.shards[shard_of(key)]).remove(key);
+        copy.len -= 1;
+        copy
+    }
+}
+
+fn shard_of(key: &str) -> usize {
+    // `DefaultHasher::new` has fixed keys, so a key lands in the same shard
+    // in every copy
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % SHARDS as u64) as usize
+}
+
+static FROZEN_LISTS: Lazy<RwLock<HashMap<HandleId, FrozenList>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+static FROZEN_DICTS: Lazy<RwLock<HashMap<HandleId, FrozenDict>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn frozen_list(handle: u64) -> FrozenList {
+    FROZEN_LISTS
+        .read()
+        .get(&handle)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn frozen_dict(handle: u64) -> FrozenDict {
+    FROZEN_DICTS
+        .read()
+        .get(&handle)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn insert_list(list: FrozenList) -> u64 {
+    let id = next_handle_id();
+    FROZEN_LISTS.write().insert(id, list);
+    id
+}
+
+fn insert_dict(dict: FrozenDict) -> u64 {
+    let id = next_handle_id();
+    FROZEN_DICTS.write().insert(id, dict);
+    id
+}
+
+fn list_value(handle: u64, index: i64) -> Option<Value> {
+    let index = usize::try_from(index).ok()?;
+    FROZEN_LISTS.read().get(&handle)?.get(index).cloned()
+}
+
+fn dict_value(handle: u64, key: *const c_char) -> Option<Value> {
+    let key = string_arg(key);
+    FROZEN_DICTS.read().get(&handle)?.get(&key).cloned()
+}
+
+fn string_arg(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn into_c_string(value: Option<Value>) -> *mut c_char {
+    value
+        .and_then(|value| CString::new(value_to_string(&value)).ok())
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+fn as_int(value: Option<Value>) -> i64 {
+    match value {
+        Some(Value::I64(i)) => i,
+        Some(Value::F64(f)) => f as i64,
+        Some(Value::Bool(b)) => i64::from(b),
+        _ => 0,
+    }
+}
+
+fn as_float(value: Option<Value>) -> f64 {
+    match value {
+        Some(Value::F64(f)) => f,
+        Some(Value::I64(i)) => i as f64,
+        Some(Value::Bool(b)) => f64::from(u8::from(b)),
+        _ => 0.0,
+    }
+}
+
+fn as_bool(value: Option<Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => b,
+        Some(Value::I64(i)) => i != 0,
+        Some(Value::F64(f)) => f != 0.0,
+        _ => false,
+    }
+}
+
+fn as_list(value: Option<Value>) -> u64 {
+    match value {
+        Some(Value::List(handle)) => handle,
+        _ => 0,
+    }
+}
+
+fn as_map(value: Option<Value>) -> u64 {
+    match value {
+        Some(Value::Map(handle)) => handle,
+        _ => 0,
+    }
+}
+
+/// The list with `value` at `index`, or the list itself when `index` is out
+/// of range
+fn list_with(handle: u64, index: i64, value: Value) -> u64 {
+    let updated = usize::try_from(index)
+        .ok()
+        .and_then(|index| frozen_list(handle).with(index, value));
+    match updated {
+        Some(list) => insert_list(list),
+        None => handle,
+    }
+}
+
+fn list_appended(handle: u64, value: Value) -> u64 {
+    insert_list(frozen_list(handle).appended(value))
+}
+
+fn dict_with(handle: u64, key: *const c_char, value: Value) -> u64 {
+    insert_dict(frozen_dict(handle).with(&string_arg(key), value))
+}
+
+// ============================================================================
+// Freezing and thawing
+// ============================================================================
+
+/// `list.freeze()`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_freeze(handle: u64) -> u64 {
+    let list = LISTS
+        .read()
+        .get(&handle)
+        .map(|list| FrozenList::from_items(&list.items))
+        .unwrap_or_default();
+    insert_list(list)
+}
+
+/// `frozen.thaw()`, a mutable list with the frozen list's elements
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_thaw(handle: u64) -> u64 {
+    let items = frozen_list(handle).iter().cloned().collect();
+    let id = next_handle_id();
+    LISTS.write().insert(id, List { items });
+    id
+}
+
+/// `dict.freeze()`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_dict_freeze(handle: u64) -> u64 {
+    let dict = MAPS
+        .read()
+        .get(&handle)
+        .map(|map| FrozenDict::from_items(&map.items))
+        .unwrap_or_default();
+    insert_dict(dict)
+}
+
+/// `frozen.thaw()`, a mutable dict with the frozen dict's entries
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_dict_thaw(handle: u64) -> u64 {
+    let items = frozen_dict(handle)
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let id = next_handle_id();
+    MAPS.write().insert(id, Map { items });
+    id
+}
+
+// ============================================================================
+// Frozen lists
+// ============================================================================
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_len(handle: u64) -> i64 {
+    FROZEN_LISTS
+        .read()
+        .get(&handle)
+        .map_or(0, |list| list.len() as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get(handle: u64, index: i64) -> *mut c_char {
+    into_c_string(list_value(handle, index))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get_int(handle: u64, index: i64) -> i64 {
+    as_int(list_value(handle, index))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get_float(handle: u64, index: i64) -> f64 {
+    as_float(list_value(handle, index))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get_bool(handle: u64, index: i64) -> bool {
+    as_bool(list_value(handle, index))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get_list(handle: u64, index: i64) -> u64 {
+    as_list(list_value(handle, index))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_get_map(handle: u64, index: i64) -> u64 {
+    as_map(list_value(handle, index))
+}
+
+/// returns a copy of the frozen list with a string at `index`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_list_set_string(
+    handle: u64,
+    index: i64,
+    value: *const c_char,
+) -> u64 {
+    list_with(handle, index, Value::String(string_arg(value)))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_set_int(handle: u64, index: i64, value: i64) -> u64 {
+    list_with(handle, index, Value::I64(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_set_float(handle: u64, index: i64, value: f64) -> u64 {
+    list_with(handle, index, Value::F64(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_set_bool(handle: u64, index: i64, value: bool) -> u64 {
+    list_with(handle, index, Value::Bool(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_set_list(handle: u64, index: i64, value: u64) -> u64 {
+    list_with(handle, index, Value::List(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_set_map(handle: u64, index: i64, value: u64) -> u64 {
+    list_with(handle, index, Value::Map(value))
+}
+
+/// returns a copy of the frozen list with a string added at the end
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_list_append_string(handle: u64, value: *const c_char) -> u64 {
+    list_appended(handle, Value::String(string_arg(value)))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_append_int(handle: u64, value: i64) -> u64 {
+    list_appended(handle, Value::I64(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_append_float(handle: u64, value: f64) -> u64 {
+    list_appended(handle, Value::F64(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_append_bool(handle: u64, value: bool) -> u64 {
+    list_appended(handle, Value::Bool(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_append_list(handle: u64, value: u64) -> u64 {
+    list_appended(handle, Value::List(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_list_append_map(handle: u64, value: u64) -> u64 {
+    list_appended(handle, Value::Map(value))
+}
+
+// ============================================================================
+// Frozen dicts
+// ============================================================================
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_dict_len(handle: u64) -> i64 {
+    FROZEN_DICTS
+        .read()
+        .get(&handle)
+        .map_or(0, |dict| dict.len() as i64)
+}
+
+/// `frozen.keys()`, in sorted order like `m.keys()`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_frozen_dict_keys(handle: u64) -> u64 {
+    let mut keys: Vec<String> = frozen_dict(handle)
+        .iter()
+        .map(|(key, _)| key.clone())
+        .collect();
+    keys.sort();
+    let id = next_handle_id();
+    LISTS.write().insert(
+        id,
+        List {
+            items: keys.into_iter().map(Value::String).collect(),
+        },
+    );
+    id
+}
+
+/// see if the frozen dict `handle` has an entry for `key`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_has(handle: u64, key: *const c_char) -> bool {
+    dict_value(handle, key).is_some()
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get(handle: u64, key: *const c_char) -> *mut c_char {
+    into_c_string(dict_value(handle, key))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get_int(handle: u64, key: *const c_char) -> i64 {
+    as_int(dict_value(handle, key))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get_float(handle: u64, key: *const c_char) -> f64 {
+    as_float(dict_value(handle, key))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get_bool(handle: u64, key: *const c_char) -> bool {
+    as_bool(dict_value(handle, key))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get_list(handle: u64, key: *const c_char) -> u64 {
+    as_list(dict_value(handle, key))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_get_map(handle: u64, key: *const c_char) -> u64 {
+    as_map(dict_value(handle, key))
+}
+
+/// returns a copy of the frozen dict with `key` set to a string
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_string(
+    handle: u64,
+    key: *const c_char,
+    value: *const c_char,
+) -> u64 {
+    dict_with(handle, key, Value::String(string_arg(value)))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_int(
+    handle: u64,
+    key: *const c_char,
+    value: i64,
+) -> u64 {
+    dict_with(handle, key, Value::I64(value))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_float(
+    handle: u64,
+    key: *const c_char,
+    value: f64,
+) -> u64 {
+    dict_with(handle, key, Value::F64(value))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_bool(
+    handle: u64,
+    key: *const c_char,
+    value: bool,
+) -> u64 {
+    dict_with(handle, key, Value::Bool(value))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_list(
+    handle: u64,
+    key: *const c_char,
+    value: u64,
+) -> u64 {
+    dict_with(handle, key, Value::List(value))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_set_map(
+    handle: u64,
+    key: *const c_char,
+    value: u64,
+) -> u64 {
+    dict_with(handle, key, Value::Map(value))
+}
+
+/// returns a copy of the frozen dict without `key`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_frozen_dict_remove(handle: u64, key: *const c_char) -> u64 {
+    insert_dict(frozen_dict(handle).without(&string_arg(key)))
+}
+
+fn register_frozen_symbols(registry: &SymbolRegistry) {
+    let frozen = || FfiType::Opaque;
+    for (name, symbol, params, result) in [
+        (
+            "list.freeze",
+            "otter_frozen_list_freeze",
+            vec![FfiType::List],
+            frozen(),
+        ),
+        (
+            "frozen_list.thaw",
+            "otter_frozen_list_thaw",
+            vec![frozen()],
+            FfiType::List,
+        ),
+        (
+            "frozen_list.len",
+            "otter_frozen_list_len",
+            vec![frozen()],
+            FfiType::I64,
+        ),
+        (
+            "map.freeze",
+            "otter_frozen_dict_freeze",
+            vec![FfiType::Map],
+            frozen(),
+        ),
+        (
+            "frozen_dict.thaw",
+            "otter_frozen_dict_thaw",
+            vec![frozen()],
+            FfiType::Map,
+        ),
+        (
+            "frozen_dict.len",
+            "otter_frozen_dict_len",
+            vec![frozen()],
+            FfiType::I64,
+        ),
+        (
+            "frozen_dict.keys",
+            "otter_frozen_dict_keys",
+            vec![frozen()],
+            FfiType::List,
+        ),
+        (
+            "frozen_dict.has",
+            "otter_frozen_dict_has",
+            vec![frozen(), FfiType::Str],
+            FfiType::Bool,
+        ),
+        (
+            "frozen_dict.remove",
+            "otter_frozen_dict_remove",
+            vec![frozen(), FfiType::Str],
+            frozen(),
+        ),
+    ] {
+        registry.register(FfiFunction {
+            name: name.into(),
+            symbol: symbol.into(),
+            signature: FfiSignature::new(params, result),
+        });
+    }
+
+    // Element access and copying updates, one function per element type
+    // like `list.get_int` and `append<list,int>`
+    for (suffix, element) in [
+        ("string", FfiType::Str),
+        ("int", FfiType::I64),
+        ("float", FfiType::F64),
+        ("bool", FfiType::Bool),
+        ("list", FfiType::List),
+        ("map", FfiType::Map),
+    ] {
+        let getter = if suffix == "string" {
+            String::new()
+        } else {
+            format!("_{suffix}")
+        };
+        registry.register(FfiFunction {
+            name: format!("frozen_list.get{getter}"),
+            symbol: format!("otter_frozen_list_get{getter}"),
+            signature: FfiSignature::new(vec![frozen(), FfiType::I64], element.clone()),
+        });
+        registry.register(FfiFunction {
+            name: format!("frozen_list.set<{suffix}>"),
+            symbol: format!("otter_frozen_list_set_{suffix}"),
+            signature: FfiSignature::new(vec![frozen(), FfiType::I64, element.clone()], frozen()),
+        });
+        registry.register(FfiFunction {
+            name: format!("frozen_list.append<{suffix}>"),
+            symbol: format!("otter_frozen_list_append_{suffix}"),
+            signature: FfiSignature::new(vec![frozen(), element.clone()], frozen()),
+        });
+        registry.register(FfiFunction {
+            name: format!("frozen_dict.get{getter}"),
+            symbol: format!("otter_frozen_dict_get{getter}"),
+            signature: FfiSignature::new(vec![frozen(), FfiType::Str], element.clone()),
+        });
+        registry.register(FfiFunction {
+            name: format!("frozen_dict.set<{suffix}>"),
+            symbol: format!("otter_frozen_dict_set_{suffix}"),
+            signature: FfiSignature::new(vec![frozen(), FfiType::Str, element], frozen()),
+        });
+    }
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "frozen",
+        autoload: true,
+        register: register_frozen_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_copy_only_what_they_touch() {
+        let items: Vec<Value> = (0..100).map(Value::I64).collect();
+        let original = FrozenList::from_items(&items);
+        let changed = original.with(40, Value::I64(-1)).unwrap();
+        let longer = original.appended(Value::I64(100));
+
+        assert!(matches!(original.get(40), Some(Value::I64(40))));
+        assert!(matches!(changed.get(40), Some(Value::I64(-1))));
+        assert!(matches!(longer.get(100), Some(Value::I64(100))));
+        assert_eq!((original.len(), longer.len()), (100, 101));
+        assert!(original.with(100, Value::Unit).is_none());
+        // Only the chunk holding index 40 was copied
+        assert!(Arc::ptr_eq(&original.chunks[0], &changed.chunks[0]));
+        assert!(!Arc::ptr_eq(&original.chunks[1], &changed.chunks[1]));
+        assert!(Arc::ptr_eq(&original.chunks[2], &longer.chunks[2]));
+
+        let dict = FrozenDict::default()
+            .with("a", Value::I64(1))
+            .with("b", Value::I64(2));
+        let without = dict.without("a");
+        let replaced = dict.with("b", Value::I64(3));
+        assert_eq!((dict.len(), without.len(), replaced.len()), (2, 1, 2));
+        assert!(dict.get("a").is_some() && without.get("a").is_none());
+        assert!(matches!(dict.get("b"), Some(Value::I64(2))));
+        assert!(matches!(replaced.get("b"), Some(Value::I64(3))));
+    }
+
+    #[test]
+    fn freezing_snapshots_the_list() {
+        let list = next_handle_id();
+        LISTS.write().insert(
+            list,
+            List {
+                items: vec![Value::I64(1)],
+            },
+        );
+        let frozen = otter_frozen_list_freeze(list);
+        LISTS
+            .write()
+            .get_mut(&list)
+            .unwrap()
+            .items
+            .push(Value::I64(2));
+
+        let appended = otter_frozen_list_append_int(frozen, 3);
+        assert_eq!(otter_frozen_list_len(frozen), 1);
+        assert_eq!(otter_frozen_list_get_int(appended, 1), 3);
+        assert_eq!(otter_frozen_list_set_int(frozen, 5, 0), frozen);
+
+        let thawed = otter_frozen_list_thaw(appended);
+        assert_eq!(LISTS.read()[&thawed].items.len(), 2);
+    }
+}
@@ -1,6 +1,14 @@
 //! Garbage Collection FFI bindings
 
-use crate::memory::{arena, get_gc};
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use crate::memory::telemetry::{self, GcEvent};
+use crate::memory::{arena, get_gc, histogram, pin};
+
+/// Otter callback invoked after each collection with
+/// `(pause_us, bytes_reclaimed, heap_bytes)`
+type CollectHandler = extern "C" fn(i64, i64, i64);
 
 /// Allocate memory on the heap managed by the GC
 ///
@@ -37,6 +45,36 @@ pub unsafe extern "C" fn otter_gc_remove_root(ptr: *mut u8) {
     get_gc().remove_root(ptr as usize);
 }
 
+/// Pin a GC-managed object so it is neither collected nor moved, and return a
+/// handle for it (0 if `ptr` is null). Native code should hold the handle and
+/// resolve it with `otter_gc_pinned` instead of caching the raw pointer.
+///
+/// # Safety
+/// Caller must ensure `ptr` points to a valid GC-managed object.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_gc_pin(ptr: *mut u8) -> u64 {
+    pin::pin(ptr as usize).unwrap_or(0)
+}
+
+/// Release a pin. Returns false if the handle was already unpinned.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_unpin(handle: u64) -> bool {
+    pin::unpin(handle)
+}
+
+/// Resolve a pin handle to the pinned pointer, or null if the handle is no
+/// longer live.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_pinned(handle: u64) -> *mut u8 {
+    pin::resolve(handle).map_or(std::ptr::null_mut(), |ptr| ptr as *mut u8)
+}
+
+/// Number of live pin handles, useful for spotting leaked pins.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_pin_count() -> i64 {
+    pin::pin_count() as i64
+}
+
 /// Enable garbage collection. Returns previous GC state.
 ///
 /// # Safety
@@ -64,6 +102,67 @@ pub unsafe extern "C" fn otter_gc_is_enabled() -> bool {
     get_gc().is_enabled()
 }
 
+/// Registers `handler` to be called after every collection with the pause in
+/// microseconds, the bytes reclaimed and the heap size afterwards. Returns a
+/// subscription id for `gc.off_collect`.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_on_collect(handler: i64) -> i64 {
+    if handler == 0 {
+        return 0;
+    }
+    // SAFETY: the compiler passes the address of an Otter function taking three ints
+    let handler: CollectHandler = unsafe { std::mem::transmute(handler as usize) };
+    let id = telemetry::subscribe(move |event| {
+        if let GcEvent::PauseEnd {
+            pause,
+            bytes_reclaimed,
+            heap_bytes,
+            ..
+        } = event
+        {
+            handler(
+                i64::try_from(pause.as_micros()).unwrap_or(i64::MAX),
+                *bytes_reclaimed as i64,
+                *heap_bytes as i64,
+            );
+        }
+    });
+    id as i64
+}
+
+/// Removes a handler registered with `gc.on_collect`.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_off_collect(id: i64) -> bool {
+    telemetry::unsubscribe(id as u64)
+}
+
+/// Cumulative GC counters as a JSON object.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_stats() -> *mut c_char {
+    let json = serde_json::to_string(&telemetry::snapshot()).unwrap_or_default();
+    CString::new(json)
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Cumulative GC counters in the Prometheus text format, ready to serve from a
+/// metrics endpoint.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_gc_metrics() -> *mut c_char {
+    CString::new(telemetry::snapshot().to_prometheus())
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Live objects by runtime type and GC generation as a JSON array of
+/// `{type, generation, count, bytes}`, largest first.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_memory_histogram() -> *mut c_char {
+    CString::new(histogram::take().to_json())
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
 /// Create a dedicated arena allocator and return its handle.
 ///
 /// # Safety
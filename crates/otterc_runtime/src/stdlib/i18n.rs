@@ -0,0 +1,387 @@
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+use icu_normalizer::{ComposingNormalizerBorrowed, DecomposingNormalizerBorrowed};
+
+use crate::stdlib::builtins::{LISTS, Value};
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Normalization and Case Folding
+// ============================================================================
+
+pub fn nfc(text: &str) -> String {
+    ComposingNormalizerBorrowed::new_nfc()
+        .normalize(text)
+        .into_owned()
+}
+
+pub fn nfd(text: &str) -> String {
+    DecomposingNormalizerBorrowed::new_nfd()
+        .normalize(text)
+        .into_owned()
+}
+
+/// Simple case folding, for caseless matching (`"Straße"` and `"STRASSE"` fold
+/// to the same string).
+///
+/// This is `str::to_lowercase` plus the folding of `ß`, final sigma and the
+/// Latin ligatures, not the full mapping of Unicode's `CaseFolding.txt`.
+pub fn casefold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ŉ' => folded.push_str("ʼn"),
+            'ﬀ' => folded.push_str("ff"),
+            'ﬁ' => folded.push_str("fi"),
+            'ﬂ' => folded.push_str("fl"),
+            'ﬃ' => folded.push_str("ffi"),
+            'ﬄ' => folded.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => folded.push_str("st"),
+            _ => folded.extend(ch.to_lowercase()),
+        }
+    }
+    folded
+}
+
+// ============================================================================
+// Collation
+// ============================================================================
+
+/// Whether `ch` is a combining diacritical mark, as produced by NFD for
+/// accented Latin, Greek and Cyrillic letters
+fn is_combining_mark(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// Letters a locale sorts as separate letters, in order, placed right after
+/// `after`
+struct Tailoring {
+    after: char,
+    letters: &'static [&'static str],
+}
+
+fn tailorings(locale: &str) -> &'static [Tailoring] {
+    let language = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match language.as_str() {
+        "sv" | "fi" => &[Tailoring {
+            after: 'z',
+            letters: &["å", "ä", "ö"],
+        }],
+        "da" | "nb" | "nn" | "no" => &[Tailoring {
+            after: 'z',
+            letters: &["æ", "ø", "å"],
+        }],
+        "es" => &[Tailoring {
+            after: 'n',
+            letters: &["ñ"],
+        }],
+        _ => &[],
+    }
+}
+
+/// One collation element: a base letter, its accents and its case
+#[derive(Debug, PartialEq, Eq)]
+struct CollationElement {
+    primary: (u32, u8),
+    accents: Vec<char>,
+    upper: bool,
+}
+
+fn collation_elements(text: &str, tailorings: &[Tailoring]) -> Vec<CollationElement> {
+    let decomposed = nfd(text);
+    let mut elements = Vec::new();
+    let mut chars = decomposed.chars().peekable();
+
+    while let Some(base) = chars.next() {
+        let mut accents = Vec::new();
+        while let Some(&mark) = chars.peek().filter(|&&c| is_combining_mark(c)) {
+            accents.push(mark);
+            chars.next();
+        }
+
+        let folded_base = casefold(&base.to_string());
+        let mut primary = (
+            folded_base.chars().next().map_or(base as u32, |c| c as u32),
+            0,
+        );
+
+        if !tailorings.is_empty() {
+            let mut cluster = String::from(base);
+            cluster.extend(&accents);
+            let cluster = casefold(&nfc(&cluster));
+            for tailoring in tailorings {
+                if let Some(idx) = tailoring.letters.iter().position(|l| *l == cluster) {
+                    primary = (tailoring.after as u32, idx as u8 + 1);
+                    // The tailored letter already accounts for its accents
+                    accents.clear();
+                }
+            }
+        }
+
+        elements.push(CollationElement {
+            primary,
+            accents,
+            upper: base.is_uppercase(),
+        });
+    }
+    elements
+}
+
+/// Compares two strings for display ordering in `locale` (a BCP 47 tag such as
+/// `"en"`, `"sv-SE"` or `"es"`; empty for the root order).
+///
+/// Strings are compared first by base letters ignoring case and accents, then
+/// by accents, then lowercase before uppercase. Locales that treat accented
+/// letters as separate letters (Swedish `ä`, Spanish `ñ`, ...) sort them in
+/// their alphabet position.
+pub fn compare(a: &str, b: &str, locale: &str) -> Ordering {
+    let tailorings = tailorings(locale);
+    let left = collation_elements(a, tailorings);
+    let right = collation_elements(b, tailorings);
+
+    let primaries = |elements: &[CollationElement]| -> Vec<(u32, u8)> {
+        elements.iter().map(|e| e.primary).collect()
+    };
+    primaries(&left)
+        .cmp(&primaries(&right))
+        .then_with(|| {
+            let accents = |elements: &[CollationElement]| -> Vec<Vec<char>> {
+                elements.iter().map(|e| e.accents.clone()).collect()
+            };
+            accents(&left).cmp(&accents(&right))
+        })
+        .then_with(|| {
+            let cases = |elements: &[CollationElement]| -> Vec<bool> {
+                elements.iter().map(|e| e.upper).collect()
+            };
+            cases(&left).cmp(&cases(&right))
+        })
+        .then_with(|| a.cmp(b))
+}
+
+// ============================================================================
+// FFI
+// ============================================================================
+
+fn read_c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn to_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or_else(|_| std::ptr::null_mut())
+}
+
+/// compare `a` and `b` using the collation rules of `locale`, returning -1, 0 or 1
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_compare(
+    a: *const c_char,
+    b: *const c_char,
+    locale: *const c_char,
+) -> i64 {
+    match compare(&read_c_str(a), &read_c_str(b), &read_c_str(locale)) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// sort `items` by the collation rules of `locale`. Values that are not strings
+/// go after the strings, in their original order, so the comparison stays a
+/// total order.
+fn sort_values(items: &mut [Value], locale: &str) {
+    items.sort_by(|a, b| match (a, b) {
+        (Value::String(a), Value::String(b)) => compare(a, b, locale),
+        (Value::String(_), _) => Ordering::Less,
+        (_, Value::String(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    });
+}
+
+/// sort a list of strings in place using the collation rules of `locale`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_sort(list: u64, locale: *const c_char) {
+    let locale = read_c_str(locale);
+    if let Some(list) = LISTS.write().get_mut(&list) {
+        sort_values(&mut list.items, &locale);
+    }
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_casefold(text: *const c_char) -> *mut c_char {
+    to_c_string(casefold(&read_c_str(text)))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_nfc(text: *const c_char) -> *mut c_char {
+    to_c_string(nfc(&read_c_str(text)))
+}
+
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_nfd(text: *const c_char) -> *mut c_char {
+    to_c_string(nfd(&read_c_str(text)))
+}
+
+/// returns whether `a` and `b` are equal after normalization and case folding
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_i18n_equals_ignore_case(
+    a: *const c_char,
+    b: *const c_char,
+) -> bool {
+    let fold = |ptr| nfc(&casefold(&nfd(&read_c_str(ptr))));
+    fold(a) == fold(b)
+}
+
+fn register_std_i18n_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "i18n.compare".into(),
+        symbol: "otter_std_i18n_compare".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str, FfiType::Str], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "i18n.sort".into(),
+        symbol: "otter_std_i18n_sort".into(),
+        signature: FfiSignature::new(vec![FfiType::List, FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "i18n.casefold".into(),
+        symbol: "otter_std_i18n_casefold".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "i18n.nfc".into(),
+        symbol: "otter_std_i18n_nfc".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "i18n.nfd".into(),
+        symbol: "otter_std_i18n_nfd".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "i18n.equals_ignore_case".into(),
+        symbol: "otter_std_i18n_equals_ignore_case".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Bool),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "i18n",
+        autoload: false,
+        register: register_std_i18n_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: &[&str], locale: &str) -> Vec<String> {
+        let mut words: Vec<String> = words.iter().map(ToString::to_string).collect();
+        words.sort_by(|a, b| compare(a, b, locale));
+        words
+    }
+
+    #[test]
+    fn normalizes_and_folds() {
+        assert_eq!(nfc("e\u{0301}"), "é");
+        assert_eq!(nfd("é"), "e\u{0301}");
+        assert_eq!(casefold("Straße"), casefold("STRASSE"));
+        assert_eq!(casefold("ΌΣΟΣ"), casefold("όσος"));
+    }
+
+    #[test]
+    fn collates_accents_and_case() {
+        assert_eq!(
+            sorted(
+                &["zebra", "Äpfel", "apple", "Apple", "éclair", "eclair"],
+                "en"
+            ),
+            ["Äpfel", "apple", "Apple", "eclair", "éclair", "zebra"]
+        );
+    }
+
+    #[test]
+    fn applies_locale_tailorings() {
+        assert_eq!(
+            sorted(&["öl", "zon", "ål", "apa"], "sv-SE"),
+            ["apa", "zon", "ål", "öl"]
+        );
+        assert_eq!(sorted(&["ñu", "nz", "oa"], "es"), ["nz", "ñu", "oa"]);
+        assert_eq!(
+            sorted(&["öl", "zon", "ål", "apa"], "de"),
+            ["ål", "apa", "öl", "zon"]
+        );
+    }
+
+    #[test]
+    fn sorts_values_that_are_not_strings_last() {
+        let mut items = vec![
+            Value::I64(2),
+            Value::String("zebra".to_string()),
+            Value::Bool(true),
+            Value::String("apple".to_string()),
+            Value::I64(1),
+        ];
+        sort_values(&mut items, "en");
+        let rendered: Vec<_> = items
+            .iter()
+            .map(|value| match value {
+                Value::String(text) => text.clone(),
+                other => format!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            ["apple", "zebra", "I64(2)", "Bool(true)", "I64(1)"]
+        );
+    }
+}
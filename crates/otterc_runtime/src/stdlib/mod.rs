@@ -1,18 +1,28 @@
 pub mod builtins;
+pub mod config;
 pub mod enums;
+pub mod env;
 pub mod exceptions;
 pub mod fmt;
+pub mod frozen;
 pub mod gc;
 pub mod http;
+pub mod i18n;
 pub mod io;
 pub mod json;
 pub mod math;
 pub mod net;
+pub mod panic_hook;
 pub mod rand;
+pub mod reflect;
 pub mod runtime;
+pub mod semver;
+pub mod shutdown;
+pub mod symbol;
 pub mod sync;
 pub mod sys;
 pub mod task;
+pub mod tasklocal;
 pub mod test;
 pub mod time;
 pub mod yaml;
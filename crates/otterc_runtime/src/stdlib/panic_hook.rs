@@ -0,0 +1,117 @@
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::task::otter_frames;
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Panic Hook
+// ============================================================================
+
+/// Receives the message, the `file:line:column` of the panic (empty when
+/// unknown) and the Otter functions on the stack, innermost first, one per line
+type PanicHook = extern "C" fn(*mut c_char, *mut c_char, *mut c_char);
+
+static HOOK: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Set while the hook runs, so a panic in the hook does not call it again
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs `handler` as the panic hook, replacing any previous one. 0
+/// removes it.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_set_panic_hook(handler: i64) {
+    HOOK.store(handler as usize, Ordering::SeqCst);
+}
+
+/// Hands a panic no `try` catches to the hook, if one is installed, before the
+/// runtime reports it. Runs on the thread that panicked.
+pub(crate) fn run_panic_hook(message: &str, location: Option<&str>) {
+    let handler = HOOK.load(Ordering::SeqCst);
+    if handler == 0 || RUNNING.replace(true) {
+        return;
+    }
+    // SAFETY: the type checker only accepts a function taking three strings for
+    // `runtime.set_panic_hook`, and the compiler passes its address
+    let handler: PanicHook = unsafe { std::mem::transmute(handler) };
+    let backtrace = otter_frames(&Backtrace::force_capture().to_string()).join("\n");
+    // The handler owns the strings, like any string the runtime returns
+    let [message, location, backtrace] =
+        [message, location.unwrap_or_default(), backtrace.as_str()].map(|text| {
+            CString::new(text.replace('\0', ""))
+                .unwrap_or_default()
+                .into_raw()
+        });
+    handler(message, location, backtrace);
+    RUNNING.set(false);
+}
+
+fn register_std_panic_hook_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "runtime.set_panic_hook".into(),
+        symbol: "otter_runtime_set_panic_hook".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "runtime",
+        autoload: false,
+        register: register_std_panic_hook_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    static RECEIVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    extern "C" fn record(message: *mut c_char, location: *mut c_char, backtrace: *mut c_char) {
+        let texts = [message, location, backtrace].map(|text| {
+            // SAFETY: the runtime hands the hook strings it gives up
+            unsafe { CString::from_raw(text) }
+                .into_string()
+                .unwrap_or_default()
+        });
+        RECEIVED
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .extend(texts);
+    }
+
+    /// Stands in for an Otter function on the stack
+    #[unsafe(no_mangle)]
+    #[inline(never)]
+    extern "C" fn checkout_order() {
+        run_panic_hook("out of stock", Some("shop.ot:12:5"));
+    }
+
+    #[test]
+    fn hook_receives_message_location_and_otter_frames() {
+        otter_runtime_set_panic_hook(record as extern "C" fn(_, _, _) as usize as i64);
+        checkout_order();
+        otter_runtime_set_panic_hook(0);
+
+        let received = RECEIVED.lock().unwrap_or_else(|err| err.into_inner());
+        assert_eq!(received.len(), 3, "{received:?}");
+        let (message, location, backtrace) = (&received[0], &received[1], &received[2]);
+        assert_eq!(message, "out of stock");
+        assert_eq!(location, "shop.ot:12:5");
+        assert!(
+            backtrace
+                .lines()
+                .next()
+                .is_some_and(|frame| frame.starts_with("checkout_order")),
+            "{backtrace}"
+        );
+    }
+}
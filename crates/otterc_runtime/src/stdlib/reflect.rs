@@ -0,0 +1,390 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::stdlib::builtins::{
+    HandleId, LISTS, List, MAPS, Map, Value, next_handle_id, value_to_string,
+};
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Deep Copy
+// ============================================================================
+
+/// Recursively copies nested lists and maps so the result shares no handles
+/// with `value`. Aliasing and cycles inside `value` are preserved in the copy.
+pub fn deep_copy(value: &Value) -> Value {
+    deep_copy_with(value, &mut HashMap::new())
+}
+
+fn deep_copy_with(value: &Value, copies: &mut HashMap<HandleId, HandleId>) -> Value {
+    match value {
+        Value::List(handle) => Value::List(deep_copy_list(*handle, copies)),
+        Value::Map(handle) => Value::Map(deep_copy_map(*handle, copies)),
+        scalar => scalar.clone(),
+    }
+}
+
+fn deep_copy_list(handle: HandleId, copies: &mut HashMap<HandleId, HandleId>) -> HandleId {
+    if let Some(copy) = copies.get(&handle) {
+        return *copy;
+    }
+    let copy = next_handle_id();
+    copies.insert(handle, copy);
+    // Register the copy before recursing so self-references resolve to it
+    LISTS.write().insert(copy, List { items: Vec::new() });
+
+    let items = LISTS
+        .read()
+        .get(&handle)
+        .map(|list| list.items.clone())
+        .unwrap_or_default();
+    let items = items
+        .iter()
+        .map(|item| deep_copy_with(item, copies))
+        .collect();
+    if let Some(list) = LISTS.write().get_mut(&copy) {
+        list.items = items;
+    }
+    copy
+}
+
+fn deep_copy_map(handle: HandleId, copies: &mut HashMap<HandleId, HandleId>) -> HandleId {
+    if let Some(copy) = copies.get(&handle) {
+        return *copy;
+    }
+    let copy = next_handle_id();
+    copies.insert(handle, copy);
+    MAPS.write().insert(
+        copy,
+        Map {
+            items: HashMap::new(),
+        },
+    );
+
+    let items = MAPS
+        .read()
+        .get(&handle)
+        .map(|map| map.items.clone())
+        .unwrap_or_default();
+    let items = items
+        .iter()
+        .map(|(key, item)| (key.clone(), deep_copy_with(item, copies)))
+        .collect();
+    if let Some(map) = MAPS.write().get_mut(&copy) {
+        map.items = items;
+    }
+    copy
+}
+
+// ============================================================================
+// Structural Diff
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// One difference between two values. `path` locates it from the root, e.g.
+/// `users[2].name`; it is empty when the roots themselves differ.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Compares `old` and `new` structurally, descending into lists by index and
+/// into maps by key. Map keys are visited in sorted order so the result is
+/// deterministic.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), old, new, &mut changes, &mut HashSet::new());
+    changes
+}
+
+fn diff_into(
+    path: String,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<Change>,
+    visiting: &mut HashSet<(HandleId, HandleId)>,
+) {
+    match (old, new) {
+        (Value::List(a), Value::List(b)) | (Value::Map(a), Value::Map(b)) if a == b => {}
+        (Value::List(a), Value::List(b)) => {
+            if !visiting.insert((*a, *b)) {
+                return;
+            }
+            let lists = LISTS.read();
+            let old_items = lists.get(a).map(|list| list.items.clone());
+            let new_items = lists.get(b).map(|list| list.items.clone());
+            drop(lists);
+            let (old_items, new_items) =
+                (old_items.unwrap_or_default(), new_items.unwrap_or_default());
+
+            for idx in 0..old_items.len().max(new_items.len()) {
+                let item_path = format!("{path}[{idx}]");
+                match (old_items.get(idx), new_items.get(idx)) {
+                    (Some(a), Some(b)) => diff_into(item_path, a, b, changes, visiting),
+                    (Some(a), None) => changes.push(Change {
+                        path: item_path,
+                        kind: ChangeKind::Removed,
+                        old: Some(a.clone()),
+                        new: None,
+                    }),
+                    (None, Some(b)) => changes.push(Change {
+                        path: item_path,
+                        kind: ChangeKind::Added,
+                        old: None,
+                        new: Some(b.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+            visiting.remove(&(*a, *b));
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            if !visiting.insert((*a, *b)) {
+                return;
+            }
+            let maps = MAPS.read();
+            let old_items = maps.get(a).map(|map| map.items.clone());
+            let new_items = maps.get(b).map(|map| map.items.clone());
+            drop(maps);
+            let (old_items, new_items) =
+                (old_items.unwrap_or_default(), new_items.unwrap_or_default());
+
+            let keys: BTreeSet<&String> = old_items.keys().chain(new_items.keys()).collect();
+            for key in keys {
+                let item_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (old_items.get(key), new_items.get(key)) {
+                    (Some(a), Some(b)) => diff_into(item_path, a, b, changes, visiting),
+                    (Some(a), None) => changes.push(Change {
+                        path: item_path,
+                        kind: ChangeKind::Removed,
+                        old: Some(a.clone()),
+                        new: None,
+                    }),
+                    (None, Some(b)) => changes.push(Change {
+                        path: item_path,
+                        kind: ChangeKind::Added,
+                        old: None,
+                        new: Some(b.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+            visiting.remove(&(*a, *b));
+        }
+        (a, b) if scalar_eq(a, b) => {}
+        (a, b) => changes.push(Change {
+            path,
+            kind: ChangeKind::Changed,
+            old: Some(a.clone()),
+            new: Some(b.clone()),
+        }),
+    }
+}
+
+fn scalar_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Unit, Value::Unit) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::I64(a), Value::I64(b)) => a == b,
+        (Value::F64(a), Value::F64(b)) => a == b,
+        (Value::I64(a), Value::F64(b)) | (Value::F64(b), Value::I64(a)) => *a as f64 == *b,
+        (Value::String(a), Value::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Renders a value for a change record, quoting strings so `"1"` and `1` differ
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{s}\""),
+        other => value_to_string(other),
+    }
+}
+
+/// Looks up whether `handle` is a list or a map
+fn handle_value(handle: u64) -> Value {
+    if LISTS.read().contains_key(&handle) {
+        Value::List(handle)
+    } else if MAPS.read().contains_key(&handle) {
+        Value::Map(handle)
+    } else {
+        Value::Unit
+    }
+}
+
+// ============================================================================
+// FFI
+// ============================================================================
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_deepcopy_list(handle: u64) -> u64 {
+    deep_copy_list(handle, &mut HashMap::new())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_deepcopy_map(handle: u64) -> u64 {
+    deep_copy_map(handle, &mut HashMap::new())
+}
+
+/// diffs two list or map handles, returning a list of change maps with the
+/// string keys `path`, `op` (`added`, `removed` or `changed`), `old` and `new`.
+/// `old`/`new` are empty when the value is missing on that side.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_builtin_diff(old: u64, new: u64) -> u64 {
+    let changes = diff(&handle_value(old), &handle_value(new));
+
+    let mut maps = MAPS.write();
+    let records = changes
+        .into_iter()
+        .map(|change| {
+            let id = next_handle_id();
+            let field = |value: Option<Value>| {
+                Value::String(value.as_ref().map(display_value).unwrap_or_default())
+            };
+            let items = HashMap::from([
+                ("path".to_string(), Value::String(change.path)),
+                (
+                    "op".to_string(),
+                    Value::String(change.kind.as_str().to_string()),
+                ),
+                ("old".to_string(), field(change.old)),
+                ("new".to_string(), field(change.new)),
+            ]);
+            maps.insert(id, Map { items });
+            Value::Map(id)
+        })
+        .collect();
+    drop(maps);
+
+    let id = next_handle_id();
+    LISTS.write().insert(id, List { items: records });
+    id
+}
+
+fn register_std_reflect_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "deepcopy<list>".into(),
+        symbol: "otter_builtin_deepcopy_list".into(),
+        signature: FfiSignature::new(vec![FfiType::List], FfiType::List),
+    });
+
+    registry.register(FfiFunction {
+        name: "deepcopy<map>".into(),
+        symbol: "otter_builtin_deepcopy_map".into(),
+        signature: FfiSignature::new(vec![FfiType::Map], FfiType::Map),
+    });
+
+    registry.register(FfiFunction {
+        name: "__otter_diff".into(),
+        symbol: "otter_builtin_diff".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Opaque], FfiType::List),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "reflect",
+        autoload: true,
+        register: register_std_reflect_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::panic, reason = "Panicking on test failures is acceptable")]
+
+    use super::*;
+
+    fn list(items: Vec<Value>) -> Value {
+        let id = next_handle_id();
+        LISTS.write().insert(id, List { items });
+        Value::List(id)
+    }
+
+    fn map(items: Vec<(&str, Value)>) -> Value {
+        let id = next_handle_id();
+        let items = items
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        MAPS.write().insert(id, Map { items });
+        Value::Map(id)
+    }
+
+    #[test]
+    fn deep_copy_detaches_nested_collections() {
+        let inner = list(vec![Value::I64(1)]);
+        let original = map(vec![("items", inner.clone())]);
+        let Value::Map(copy) = deep_copy(&original) else {
+            panic!("expected a map copy");
+        };
+        let Some(Value::List(copied_inner)) = MAPS.read()[&copy].items.get("items").cloned() else {
+            panic!("expected a nested list");
+        };
+
+        let Value::List(inner) = inner else {
+            panic!("expected a list");
+        };
+        assert_ne!(copied_inner, inner);
+        LISTS
+            .write()
+            .get_mut(&inner)
+            .unwrap()
+            .items
+            .push(Value::I64(2));
+        assert_eq!(LISTS.read()[&copied_inner].items.len(), 1);
+    }
+
+    #[test]
+    fn diffs_nested_values_by_path() {
+        let old = map(vec![
+            ("name", Value::String("otter".into())),
+            ("tags", list(vec![Value::I64(1), Value::I64(2)])),
+            ("gone", Value::Bool(true)),
+        ]);
+        let new = map(vec![
+            ("name", Value::String("sea otter".into())),
+            (
+                "tags",
+                list(vec![Value::I64(1), Value::I64(3), Value::I64(4)]),
+            ),
+        ]);
+
+        let summary: Vec<(String, ChangeKind)> = diff(&old, &new)
+            .into_iter()
+            .map(|change| (change.path, change.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("gone".to_string(), ChangeKind::Removed),
+                ("name".to_string(), ChangeKind::Changed),
+                ("tags[1]".to_string(), ChangeKind::Changed),
+                ("tags[2]".to_string(), ChangeKind::Added),
+            ]
+        );
+        assert!(diff(&old, &deep_copy(&old)).is_empty());
+    }
+}
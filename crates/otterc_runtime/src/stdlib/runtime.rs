@@ -9,6 +9,7 @@ use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use sysinfo::System;
 
+use crate::limits;
 use crate::memory::config::GcStrategy;
 use crate::memory::gc::get_gc;
 use crate::memory::profiler::get_profiler;
@@ -98,6 +99,14 @@ pub extern "C" fn otter_runtime_collect_garbage() -> i64 {
     stats.bytes_freed as i64
 }
 
+/// Check the `--max-heap`/`--max-cpu-seconds` limits, raising the
+/// resource-exhausted error if the CPU budget has run out since the last check.
+/// Returns true once any limit has been exceeded.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_check_limits() -> bool {
+    limits::check_limits()
+}
+
 /// Start memory profiling
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_runtime_memory_profiler_start() {
@@ -275,6 +284,16 @@ pub extern "C" fn otter_runtime_tasks() -> *mut c_char {
     }
 }
 
+/// Print every live task with its state, spawn site and what it is waiting for
+#[expect(
+    clippy::print_stderr,
+    reason = "Task dumps are diagnostics and go to stderr"
+)]
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_dump_tasks() {
+    eprint!("{}", crate::task::dump_tasks());
+}
+
 /// Get OtterLang runtime version
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_runtime_version() -> *mut c_char {
@@ -313,6 +332,11 @@ pub fn decrement_active_tasks() {
     ACTIVE_GOROUTINES.fetch_sub(1, Ordering::SeqCst);
 }
 
+/// Current active task count
+pub fn active_tasks() -> u64 {
+    ACTIVE_GOROUTINES.load(Ordering::SeqCst)
+}
+
 #[cfg(feature = "task-runtime")]
 pub fn register_task_metrics(metrics: Arc<TaskRuntimeMetrics>) {
     *TASK_METRICS.write() = Some(metrics);
@@ -448,6 +472,12 @@ fn register_std_runtime_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![], FfiType::Str),
     });
 
+    registry.register(FfiFunction {
+        name: "runtime.dump_tasks".into(),
+        symbol: "otter_runtime_dump_tasks".into(),
+        signature: FfiSignature::new(vec![], FfiType::Unit),
+    });
+
     // Error handling runtime functions
     registry.register(FfiFunction {
         name: "runtime.error_push_context".into(),
@@ -478,6 +508,12 @@ fn register_std_runtime_symbols(registry: &SymbolRegistry) {
         symbol: "otter_error_rethrow".into(),
         signature: FfiSignature::new(vec![], FfiType::Unit),
     });
+
+    registry.register(FfiFunction {
+        name: "runtime.check_limits".into(),
+        symbol: "otter_runtime_check_limits".into(),
+        signature: FfiSignature::new(vec![], FfiType::Bool),
+    });
 }
 
 inventory::submit! {
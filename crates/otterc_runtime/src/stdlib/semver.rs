@@ -0,0 +1,683 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Versions
+// ============================================================================
+
+/// A semantic version (`MAJOR.MINOR.PATCH[-PRE][+BUILD]`).
+///
+/// Build metadata is kept for display but ignored when comparing, as the
+/// semver spec requires, by equality as well as ordering.
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: String,
+}
+
+/// A dot-separated pre-release identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+            // Numeric identifiers always have lower precedence
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{n}"),
+            Identifier::AlphaNumeric(s) => f.write_str(s),
+        }
+    }
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: String::new(),
+        }
+    }
+
+    /// Parses a full `MAJOR.MINOR.PATCH` version. A leading `v` is accepted.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+
+        let (text, build) = match text.split_once('+') {
+            Some((rest, build)) => {
+                if !is_valid_dotted(build, false) {
+                    return Err(format!("invalid build metadata '{build}'"));
+                }
+                (rest, build.to_string())
+            }
+            None => (text, String::new()),
+        };
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, parse_prerelease(pre)?),
+            None => (text, Vec::new()),
+        };
+
+        let parts: Vec<&str> = core.split('.').collect();
+        let [major, minor, patch] = parts.as_slice() else {
+            return Err(format!("expected MAJOR.MINOR.PATCH, got '{core}'"));
+        };
+
+        Ok(Self {
+            major: parse_number(major)?,
+            minor: parse_number(minor)?,
+            patch: parse_number(patch)?,
+            pre,
+            build,
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A pre-release sorts before its release
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(ToString::to_string).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid version number '{text}'"));
+    }
+    if text.len() > 1 && text.starts_with('0') {
+        return Err(format!("version number '{text}' has a leading zero"));
+    }
+    text.parse()
+        .map_err(|err| format!("version number '{text}' is too large: {err}"))
+}
+
+fn is_valid_dotted(text: &str, strict_numbers: bool) -> bool {
+    text.split('.').all(|part| {
+        !part.is_empty()
+            && part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !(strict_numbers
+                && part.len() > 1
+                && part.starts_with('0')
+                && part.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+fn parse_prerelease(text: &str) -> Result<Vec<Identifier>, String> {
+    if !is_valid_dotted(text, true) {
+        return Err(format!("invalid pre-release '{text}'"));
+    }
+    Ok(text
+        .split('.')
+        .map(|part| match part.parse() {
+            Ok(n) if part.bytes().all(|b| b.is_ascii_digit()) => Identifier::Numeric(n),
+            _ => Identifier::AlphaNumeric(part.to_string()),
+        })
+        .collect())
+}
+
+// ============================================================================
+// Version Requirements
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+    Wildcard,
+}
+
+/// A single comparator such as `>=1.2` or `^0.3.1`. Missing components are
+/// `None` (e.g. `1.2` or `1.2.x`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Identifier>,
+}
+
+/// A version range: comparators separated by `,` must all match, and
+/// alternatives separated by `||` may match.
+///
+/// A bare version like `1.2.3` is treated as `^1.2.3`, following Cargo.
+/// Pre-release versions only satisfy a range when one of its comparators
+/// names a pre-release of the same `MAJOR.MINOR.PATCH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    alternatives: Vec<Vec<Comparator>>,
+}
+
+impl VersionReq {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut alternatives = Vec::new();
+        for alternative in text.split("||") {
+            let comparators = alternative
+                .split(',')
+                .map(parse_comparator)
+                .collect::<Result<Vec<_>, _>>()?;
+            alternatives.push(comparators);
+        }
+        Ok(Self { alternatives })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives.iter().any(|comparators| {
+            comparators.iter().all(|c| c.matches(version))
+                && (!version.is_prerelease()
+                    || comparators.iter().any(|c| c.allows_prerelease_of(version)))
+        })
+    }
+}
+
+fn parse_comparator(text: &str) -> Result<Comparator, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("empty version requirement".to_string());
+    }
+
+    let (op, rest) = [
+        (">=", Op::GreaterEq),
+        ("<=", Op::LessEq),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Exact),
+        ("~", Op::Tilde),
+        ("^", Op::Caret),
+    ]
+    .iter()
+    .find_map(|(prefix, op)| {
+        text.strip_prefix(prefix)
+            .map(|rest| (Some(*op), rest.trim()))
+    })
+    .unwrap_or((None, text));
+    let rest = rest.strip_prefix('v').unwrap_or(rest);
+
+    let (core, pre) = match rest.split_once('-') {
+        Some((core, pre)) => (core, parse_prerelease(pre)?),
+        None => (rest, Vec::new()),
+    };
+
+    let mut parts = [None; 3];
+    let mut wildcard = false;
+    let segments: Vec<&str> = core.split('.').collect();
+    if segments.len() > 3 {
+        return Err(format!("invalid version requirement '{text}'"));
+    }
+    for (slot, segment) in parts.iter_mut().zip(&segments) {
+        if matches!(*segment, "*" | "x" | "X") {
+            wildcard = true;
+        } else if wildcard {
+            return Err(format!("unexpected version after wildcard in '{text}'"));
+        } else {
+            *slot = Some(parse_number(segment)?);
+        }
+    }
+
+    if !pre.is_empty() && parts.iter().any(Option::is_none) {
+        return Err(format!("pre-release requires a full version in '{text}'"));
+    }
+
+    // Bare versions are caret requirements, bare wildcards pin what is given
+    let op = op.unwrap_or(if wildcard { Op::Wildcard } else { Op::Caret });
+
+    Ok(Comparator {
+        op,
+        major: parts[0],
+        minor: parts[1],
+        patch: parts[2],
+        pre,
+    })
+}
+
+impl Comparator {
+    fn lower_bound(&self) -> Version {
+        Version {
+            pre: self.pre.clone(),
+            ..Version::new(
+                self.major.unwrap_or(0),
+                self.minor.unwrap_or(0),
+                self.patch.unwrap_or(0),
+            )
+        }
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        let Some(major) = self.major else {
+            // `*` matches everything
+            return true;
+        };
+        let lower = self.lower_bound();
+
+        match self.op {
+            Op::Exact | Op::Wildcard => match (self.minor, self.patch) {
+                (Some(_), Some(_)) => version.cmp(&lower) == Ordering::Equal,
+                (Some(minor), None) => version.major == major && version.minor == minor,
+                _ => version.major == major,
+            },
+            Op::Greater => match (self.minor, self.patch) {
+                (Some(_), Some(_)) => *version > lower,
+                (Some(minor), None) => (version.major, version.minor) > (major, minor),
+                _ => version.major > major,
+            },
+            Op::GreaterEq => *version >= lower,
+            Op::Less => *version < lower,
+            Op::LessEq => match (self.minor, self.patch) {
+                (Some(_), Some(_)) => *version <= lower,
+                (Some(minor), None) => (version.major, version.minor) <= (major, minor),
+                _ => version.major <= major,
+            },
+            Op::Tilde => {
+                *version >= lower
+                    && version.major == major
+                    && self.minor.is_none_or(|minor| version.minor == minor)
+            }
+            Op::Caret => {
+                if *version < lower || version.major != major {
+                    return false;
+                }
+                // The left-most non-zero component may not change
+                match (major, self.minor, self.patch) {
+                    (0, Some(0), Some(patch)) => version.minor == 0 && version.patch == patch,
+                    (0, Some(minor), _) => version.minor == minor,
+                    _ => true,
+                }
+            }
+        }
+    }
+
+    fn allows_prerelease_of(&self, version: &Version) -> bool {
+        !self.pre.is_empty()
+            && self.major == Some(version.major)
+            && self.minor == Some(version.minor)
+            && self.patch == Some(version.patch)
+    }
+}
+
+// ============================================================================
+// FFI
+// ============================================================================
+
+type HandleId = u64;
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+static VERSIONS: Lazy<RwLock<HashMap<HandleId, Version>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn read_c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn to_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or_else(|_| std::ptr::null_mut())
+}
+
+fn with_version<T>(handle: HandleId, default: T, f: impl FnOnce(&Version) -> T) -> T {
+    VERSIONS.read().get(&handle).map(f).unwrap_or(default)
+}
+
+fn compare_handles(a: HandleId, b: HandleId) -> Option<Ordering> {
+    let versions = VERSIONS.read();
+    Some(versions.get(&a)?.cmp(versions.get(&b)?))
+}
+
+/// parses `text` into a version handle, returning 0 if it is not a valid version
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_semver_parse(text: *const c_char) -> u64 {
+    let Some(version) = read_c_str(text).and_then(|text| Version::parse(&text).ok()) else {
+        return 0;
+    };
+    let id = NEXT_HANDLE_ID.fetch_add(1, AtomicOrdering::SeqCst);
+    VERSIONS.write().insert(id, version);
+    id
+}
+
+/// returns whether `text` is a valid version
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_semver_valid(text: *const c_char) -> bool {
+    read_c_str(text).is_some_and(|text| Version::parse(&text).is_ok())
+}
+
+/// returns whether `text` is a valid version range
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_semver_valid_range(text: *const c_char) -> bool {
+    read_c_str(text).is_some_and(|text| VersionReq::parse(&text).is_ok())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_major(handle: u64) -> i64 {
+    with_version(handle, -1, |v| v.major as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_minor(handle: u64) -> i64 {
+    with_version(handle, -1, |v| v.minor as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_patch(handle: u64) -> i64 {
+    with_version(handle, -1, |v| v.patch as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_prerelease(handle: u64) -> *mut c_char {
+    let pre = with_version(handle, String::new(), |v| {
+        v.pre
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".")
+    });
+    to_c_string(pre)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_to_string(handle: u64) -> *mut c_char {
+    to_c_string(with_version(handle, String::new(), ToString::to_string))
+}
+
+/// Returns -1, 0 or 1 as `a` is lower than, equal to or greater than `b`.
+/// Invalid handles compare as equal.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_compare(a: u64, b: u64) -> i64 {
+    match compare_handles(a, b) {
+        Some(Ordering::Less) => -1,
+        Some(Ordering::Greater) => 1,
+        Some(Ordering::Equal) | None => 0,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_eq(a: u64, b: u64) -> bool {
+    compare_handles(a, b).is_some_and(Ordering::is_eq)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_lt(a: u64, b: u64) -> bool {
+    compare_handles(a, b).is_some_and(Ordering::is_lt)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_le(a: u64, b: u64) -> bool {
+    compare_handles(a, b).is_some_and(Ordering::is_le)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_gt(a: u64, b: u64) -> bool {
+    compare_handles(a, b).is_some_and(Ordering::is_gt)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_ge(a: u64, b: u64) -> bool {
+    compare_handles(a, b).is_some_and(Ordering::is_ge)
+}
+
+/// returns whether the version `handle` satisfies the range `range`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_std_semver_satisfies(handle: u64, range: *const c_char) -> bool {
+    let Some(req) = read_c_str(range).and_then(|range| VersionReq::parse(&range).ok()) else {
+        return false;
+    };
+    with_version(handle, false, |v| req.matches(v))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_std_semver_free(handle: u64) {
+    VERSIONS.write().remove(&handle);
+}
+
+fn register_std_semver_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "semver.parse".into(),
+        symbol: "otter_std_semver_parse".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "semver.valid".into(),
+        symbol: "otter_std_semver_valid".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "semver.valid_range".into(),
+        symbol: "otter_std_semver_valid_range".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Bool),
+    });
+
+    for (name, symbol) in [
+        ("semver.major", "otter_std_semver_major"),
+        ("semver.minor", "otter_std_semver_minor"),
+        ("semver.patch", "otter_std_semver_patch"),
+    ] {
+        registry.register(FfiFunction {
+            name: name.into(),
+            symbol: symbol.into(),
+            signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+        });
+    }
+
+    registry.register(FfiFunction {
+        name: "semver.prerelease".into(),
+        symbol: "otter_std_semver_prerelease".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "semver.to_string".into(),
+        symbol: "otter_std_semver_to_string".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "semver.compare".into(),
+        symbol: "otter_std_semver_compare".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Opaque], FfiType::I64),
+    });
+
+    for (name, symbol) in [
+        ("semver.eq", "otter_std_semver_eq"),
+        ("semver.lt", "otter_std_semver_lt"),
+        ("semver.le", "otter_std_semver_le"),
+        ("semver.gt", "otter_std_semver_gt"),
+        ("semver.ge", "otter_std_semver_ge"),
+    ] {
+        registry.register(FfiFunction {
+            name: name.into(),
+            symbol: symbol.into(),
+            signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Opaque], FfiType::Bool),
+        });
+    }
+
+    registry.register(FfiFunction {
+        name: "semver.satisfies".into(),
+        symbol: "otter_std_semver_satisfies".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Str], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "semver.free".into(),
+        symbol: "otter_std_semver_free".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "semver",
+        autoload: false,
+        register: register_std_semver_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).expect("valid version")
+    }
+
+    fn req(text: &str) -> VersionReq {
+        VersionReq::parse(text).expect("valid requirement")
+    }
+
+    #[test]
+    fn parses_and_orders_versions() {
+        assert_eq!(v("v1.2.3-rc.1+build.5").to_string(), "1.2.3-rc.1+build.5");
+        assert!(Version::parse("1.2").is_err());
+        assert!(Version::parse("01.2.3").is_err());
+
+        let mut versions = [
+            v("1.0.0"),
+            v("1.0.0-rc.1"),
+            v("1.0.0-alpha.beta"),
+            v("1.0.0-alpha"),
+            v("1.0.0-alpha.1"),
+            v("1.0.0-beta.11"),
+            v("1.0.0-beta.2"),
+            v("1.0.0-beta"),
+        ];
+        versions.sort();
+        let sorted: Vec<String> = versions.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            sorted,
+            [
+                "1.0.0-alpha",
+                "1.0.0-alpha.1",
+                "1.0.0-alpha.beta",
+                "1.0.0-beta",
+                "1.0.0-beta.2",
+                "1.0.0-beta.11",
+                "1.0.0-rc.1",
+                "1.0.0",
+            ]
+        );
+        assert_eq!(v("1.2.3+a").cmp(&v("1.2.3+b")), Ordering::Equal);
+        assert_eq!(v("1.2.3+a"), v("1.2.3+b"));
+        assert_ne!(v("1.2.3-rc.1"), v("1.2.3"));
+    }
+
+    #[test]
+    fn matches_ranges() {
+        assert!(req("^1.2").matches(&v("1.9.0")));
+        assert!(!req("^1.2").matches(&v("2.0.0")));
+        assert!(!req("^0.2.3").matches(&v("0.3.0")));
+        assert!(req("~1.2.3").matches(&v("1.2.9")));
+        assert!(!req("~1.2.3").matches(&v("1.3.0")));
+        assert!(req(">=2, <3").matches(&v("2.5.1")));
+        assert!(!req(">=2, <3").matches(&v("3.0.0")));
+        assert!(req("1.x").matches(&v("1.4.0")));
+        assert!(req("<1 || >=3").matches(&v("3.1.0")));
+        assert!(req("*").matches(&v("42.0.0")));
+    }
+
+    #[test]
+    fn prereleases_need_an_explicit_comparator() {
+        assert!(!req(">=1.0.0").matches(&v("2.0.0-beta")));
+        assert!(req(">=2.0.0-alpha").matches(&v("2.0.0-beta")));
+        assert!(!req(">=2.0.0-alpha").matches(&v("2.1.0-beta")));
+    }
+}
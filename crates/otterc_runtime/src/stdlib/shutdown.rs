@@ -0,0 +1,194 @@
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::stdlib::runtime::active_tasks;
+use crate::stdlib::task::cancel_all_tasks;
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Graceful Shutdown
+// ============================================================================
+
+type ShutdownHandler = extern "C" fn();
+
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 5_000;
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static HANDLERS: Lazy<Mutex<Vec<ShutdownHandler>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+static DRAIN_TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_DRAIN_TIMEOUT_MS);
+/// Last signal received, 0 if none. Written from the signal handler, so it must
+/// stay a plain atomic.
+static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn record_signal(signal: libc::c_int) {
+    PENDING_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+/// Installs SIGINT/SIGTERM handlers and a watcher thread that runs the shutdown
+/// sequence outside of signal context. Without the thread the signals keep
+/// their default action, so they still end the process.
+#[expect(
+    clippy::print_stderr,
+    reason = "There is no other channel to report a runtime setup failure on"
+)]
+fn install_signal_watcher() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let watcher = std::thread::Builder::new()
+            .name("otter-shutdown".into())
+            .spawn(|| {
+                loop {
+                    let signal = PENDING_SIGNAL.load(Ordering::SeqCst);
+                    if signal != 0 {
+                        run_shutdown();
+                        exit_after_shutdown(128 + signal);
+                    }
+                    std::thread::sleep(SIGNAL_POLL_INTERVAL);
+                }
+            });
+        if let Err(err) = watcher {
+            eprintln!(
+                "warning: shutdown handlers will not run on SIGINT/SIGTERM: failed to start the signal watcher: {err}"
+            );
+            return;
+        }
+
+        let handler = record_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        unsafe {
+            libc::signal(libc::SIGINT, handler);
+            libc::signal(libc::SIGTERM, handler);
+        }
+    });
+}
+
+/// Broadcasts cancellation, runs the registered handlers and waits for in-flight
+/// tasks to finish or the drain timeout to elapse. Only the first call does work.
+fn run_shutdown() {
+    if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    cancel_all_tasks();
+
+    let handlers = HANDLERS.lock().clone();
+    for handler in handlers {
+        handler();
+    }
+
+    let timeout = Duration::from_millis(DRAIN_TIMEOUT_MS.load(Ordering::SeqCst));
+    let deadline = Instant::now() + timeout;
+    while active_tasks() > 0 && Instant::now() < deadline {
+        std::thread::sleep(DRAIN_POLL_INTERVAL);
+    }
+}
+
+#[expect(
+    clippy::exit,
+    reason = "Shutdown ends the process once draining completes"
+)]
+fn exit_after_shutdown(code: i32) -> ! {
+    std::process::exit(code);
+}
+
+/// Registers `handler` to run when the process receives SIGINT/SIGTERM or when
+/// `runtime.shutdown()` is called. Handlers run in registration order.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_on_shutdown(handler: i64) {
+    if handler == 0 {
+        return;
+    }
+    // SAFETY: the type checker only accepts a function without parameters
+    // for `runtime.on_shutdown`, and the compiler passes its address
+    let handler: ShutdownHandler = unsafe { std::mem::transmute(handler as usize) };
+    HANDLERS.lock().push(handler);
+    install_signal_watcher();
+}
+
+/// Sets how long the shutdown sequence waits for in-flight tasks, in milliseconds.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_set_shutdown_timeout(ms: i64) {
+    DRAIN_TIMEOUT_MS.store(ms.max(0) as u64, Ordering::SeqCst);
+}
+
+/// Returns true once shutdown has started, so long-running tasks can stop
+/// accepting new work.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Runs the shutdown sequence and exits with `code`.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_runtime_shutdown(code: i64) {
+    run_shutdown();
+    exit_after_shutdown(code as i32);
+}
+
+fn register_std_shutdown_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "runtime.on_shutdown".into(),
+        symbol: "otter_runtime_on_shutdown".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "runtime.set_shutdown_timeout".into(),
+        symbol: "otter_runtime_set_shutdown_timeout".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "runtime.shutdown_requested".into(),
+        symbol: "otter_runtime_shutdown_requested".into(),
+        signature: FfiSignature::new(vec![], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "runtime.shutdown".into(),
+        symbol: "otter_runtime_shutdown".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Unit),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "runtime",
+        autoload: false,
+        register: register_std_shutdown_symbols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CALLS: Lazy<Mutex<Vec<u8>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+    extern "C" fn first() {
+        CALLS.lock().push(1);
+    }
+
+    extern "C" fn second() {
+        CALLS.lock().push(2);
+    }
+
+    #[test]
+    fn shutdown_runs_handlers_once_in_registration_order() {
+        otter_runtime_set_shutdown_timeout(0);
+        otter_runtime_on_shutdown(0);
+        otter_runtime_on_shutdown(first as extern "C" fn() as usize as i64);
+        otter_runtime_on_shutdown(second as extern "C" fn() as usize as i64);
+        assert!(!otter_runtime_shutdown_requested());
+
+        run_shutdown();
+        run_shutdown();
+        assert!(otter_runtime_shutdown_requested());
+        assert_eq!(*CALLS.lock(), [1, 2]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Interned symbols for `:name` literals and `sym(name)`
+//!
+//! A symbol is the C string of its name, interned once per process and never
+//! freed, so every `:ok` in every task is the same pointer and two symbols are
+//! equal exactly when their pointers are. The collector never sees the
+//! strings, and code that reads a symbol as a `str` gets its name.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+#[derive(Default)]
+struct SymbolTable {
+    by_name: HashMap<Box<str>, usize>,
+    /// Addresses of the interned names, to tell a symbol from a copy of its
+    /// name
+    addresses: HashSet<usize>,
+}
+
+static SYMBOLS: Lazy<RwLock<SymbolTable>> = Lazy::new(|| RwLock::new(SymbolTable::default()));
+
+/// The interned C string for `name`
+pub fn intern(name: &str) -> *const c_char {
+    // A name with a NUL is cut at it, as the C string it becomes would be
+    let name = name.split('\0').next().unwrap_or_default();
+    if let Some(&address) = SYMBOLS.read().by_name.get(name) {
+        return address as *const c_char;
+    }
+    let mut table = SYMBOLS.write();
+    // Another thread may have interned it between the two locks
+    if let Some(&address) = table.by_name.get(name) {
+        return address as *const c_char;
+    }
+    let address = CString::new(name).unwrap_or_default().into_raw() as usize;
+    table.by_name.insert(name.into(), address);
+    table.addresses.insert(address);
+    address as *const c_char
+}
+
+/// Whether `ptr` is an interned symbol rather than a string that happens to
+/// hold a symbol's name
+pub fn is_interned(ptr: *const c_char) -> bool {
+    SYMBOLS.read().addresses.contains(&(ptr as usize))
+}
+
+/// returns the symbol named `name`
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_symbol_intern(name: *const c_char) -> *const c_char {
+    if name.is_null() {
+        return intern("");
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    intern(&name)
+}
+
+/// returns whether two symbols are the same. Symbols compare by address; a
+/// symbol copied out of a list or dict is a plain string again, and is
+/// compared by name.
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_symbol_equal(a: *const c_char, b: *const c_char) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.is_null() || b.is_null() {
+        return false;
+    }
+    {
+        let table = SYMBOLS.read();
+        if table.addresses.contains(&(a as usize)) && table.addresses.contains(&(b as usize)) {
+            return false;
+        }
+    }
+    unsafe { CStr::from_ptr(a) == CStr::from_ptr(b) }
+}
+
+fn register_symbol_functions(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "sym".into(),
+        symbol: "otter_symbol_intern".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "symbol.equal".into(),
+        symbol: "otter_symbol_equal".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Bool),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "symbol",
+        autoload: true,
+        register: register_symbol_functions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_names_intern_to_one_pointer() {
+        let ok = intern("ok");
+        assert_eq!(intern("ok"), ok);
+        assert_ne!(intern("error"), ok);
+        assert!(is_interned(ok));
+
+        let copy = CString::new("ok").unwrap();
+        assert!(!is_interned(copy.as_ptr()));
+        unsafe {
+            assert_eq!(CStr::from_ptr(ok).to_str(), Ok("ok"));
+            assert!(otter_symbol_equal(ok, intern("ok")));
+            assert!(!otter_symbol_equal(ok, intern("error")));
+            assert!(otter_symbol_equal(ok, copy.as_ptr()));
+            assert_eq!(otter_symbol_intern(copy.as_ptr()), ok);
+        }
+    }
+}
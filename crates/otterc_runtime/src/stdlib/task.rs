@@ -12,10 +12,14 @@ use once_cell::sync::Lazy;
 use parking_lot::Condvar;
 use parking_lot::Mutex;
 
+use crate::stdlib::builtins::{Value, encode_runtime_value};
 #[cfg(feature = "task-runtime")]
 use crate::stdlib::runtime::task_metrics_clone;
 use crate::stdlib::runtime::{decrement_active_tasks, increment_active_tasks};
-use crate::task::{JoinHandle, TaskChannel, TaskRuntimeMetrics, runtime};
+use crate::task::{
+    BroadcastChannel, JoinHandle, Restart, RestartPolicy, Supervisor, TaskChannel,
+    TaskRuntimeMetrics, blocking_enter, blocking_exit, runtime, set_spawn_site, symbol_name,
+};
 use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
 
 type HandleId = u64;
@@ -26,6 +30,9 @@ type TaskClosure = extern "C" fn(*mut c_void);
 static TASK_HANDLES: Lazy<Mutex<HashMap<HandleId, JoinHandle>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+static SUPERVISORS: Lazy<Mutex<HashMap<HandleId, Supervisor>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 struct SpawnContextGuard {
     ptr: *mut c_void,
 }
@@ -60,6 +67,13 @@ fn next_handle_id() -> HandleId {
     NEXT_ID.fetch_add(1, Ordering::SeqCst)
 }
 
+/// Names the function a task runs so task dumps can show where it came from
+fn record_spawn_site(task_id: u64, callback: usize) {
+    if let Some(site) = symbol_name(callback) {
+        set_spawn_site(task_id, site);
+    }
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_spawn(callback: TaskCallback) -> u64 {
     increment_active_tasks();
@@ -69,6 +83,23 @@ pub extern "C" fn otter_task_spawn(callback: TaskCallback) -> u64 {
         decrement_active_tasks();
     });
     let task_id = join.task_id().raw();
+    record_spawn_site(task_id, callback as usize);
+    TASK_HANDLES.lock().insert(task_id, join);
+    task_id
+}
+
+/// Runs `callback` on the blocking pool, for work that would otherwise hold a
+/// task worker. The handle is awaited like any other task.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_spawn_blocking(callback: TaskCallback) -> u64 {
+    increment_active_tasks();
+    let scheduler = runtime().scheduler().clone();
+    let join = scheduler.spawn_blocking_fn(Some("task.spawn_blocking".into()), move || {
+        callback();
+        decrement_active_tasks();
+    });
+    let task_id = join.task_id().raw();
+    record_spawn_site(task_id, callback as usize);
     TASK_HANDLES.lock().insert(task_id, join);
     task_id
 }
@@ -84,6 +115,7 @@ pub extern "C" fn otter_task_spawn_closure(callback: TaskClosure, ctx: *mut c_vo
         decrement_active_tasks();
     });
     let task_id = join.task_id().raw();
+    record_spawn_site(task_id, callback as usize);
     TASK_HANDLES.lock().insert(task_id, join);
     task_id
 }
@@ -100,6 +132,123 @@ pub extern "C" fn otter_task_detach(handle: u64) {
     TASK_HANDLES.lock().remove(&handle);
 }
 
+fn supervisor(handle: u64) -> Option<Supervisor> {
+    SUPERVISORS.lock().get(&handle).cloned()
+}
+
+/// Creates a supervisor that gives up once its children need more than
+/// `max_restarts` restarts within `window_ms`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervisor(max_restarts: i64, window_ms: i64) -> u64 {
+    let policy = RestartPolicy {
+        max_restarts: max_restarts.clamp(0, u32::MAX as i64) as u32,
+        window: Duration::from_millis(window_ms.max(0) as u64),
+        ..RestartPolicy::default()
+    };
+    let id = next_handle_id();
+    let supervisor = Supervisor::new(
+        format!("supervisor-{id}"),
+        policy,
+        runtime().scheduler().clone(),
+    );
+    SUPERVISORS.lock().insert(id, supervisor);
+    id
+}
+
+/// Sets the delay before the first restart and the most it may grow to
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervisor_backoff(handle: u64, initial_ms: i64, max_ms: i64) {
+    if let Some(supervisor) = supervisor(handle) {
+        let initial_backoff = Duration::from_millis(initial_ms.max(0) as u64);
+        supervisor.set_policy(RestartPolicy {
+            initial_backoff,
+            max_backoff: Duration::from_millis(max_ms.max(0) as u64).max(initial_backoff),
+            ..supervisor.policy()
+        });
+    }
+}
+
+/// Runs `callback` under the supervisor `handle`, starting it again whenever
+/// it returns. A panic in Otter code ends the program, so a service that hits
+/// an error should return and let the supervisor restart it.
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervise(handle: u64, callback: TaskCallback) {
+    if let Some(supervisor) = supervisor(handle) {
+        let name = symbol_name(callback as usize).unwrap_or_else(|| "task.supervise".into());
+        supervisor.supervise(name, Restart::Permanent, move || callback());
+    }
+}
+
+/// Makes the supervisor `child` a child of `parent`, which restarts it when it
+/// gives up
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervise_child(parent: u64, child: u64) {
+    if let (Some(parent), Some(child)) = (supervisor(parent), supervisor(child)) {
+        parent.supervise_supervisor(&child);
+    }
+}
+
+/// Restarts the supervisor `handle` has made so far, or -1 for an unknown
+/// handle
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervisor_restarts(handle: u64) -> i64 {
+    supervisor(handle).map_or(-1, |supervisor| supervisor.restarts() as i64)
+}
+
+/// Stops the supervisor `handle`; its children are not restarted again
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervisor_stop(handle: u64) {
+    if let Some(supervisor) = SUPERVISORS.lock().remove(&handle) {
+        supervisor.stop();
+    }
+}
+
+/// Waits for the supervisor `handle` to finish, returning false if it gave up
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_supervisor_wait(handle: u64) -> bool {
+    let Some(supervisor) = supervisor(handle) else {
+        return false;
+    };
+    supervisor.wait();
+    SUPERVISORS.lock().remove(&handle);
+    supervisor.failure().is_none()
+}
+
+/// Loop back-edge hook emitted by `--preempt-loops`
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_preempt_point() {
+    runtime().scheduler().preempt_point();
+}
+
+/// Marks the start of a call that may block, `site` naming it and its location
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_blocking_enter(site: *const c_char) {
+    if site.is_null() {
+        return;
+    }
+    // Codegen passes a string constant, which lives as long as the program
+    let site: &'static CStr = unsafe { CStr::from_ptr(site) };
+    if let Ok(site) = site.to_str() {
+        blocking_enter(site);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_blocking_exit() {
+    blocking_exit();
+}
+
+/// Signals cancellation to every task that still has a live handle.
+pub(crate) fn cancel_all_tasks() {
+    for join in TASK_HANDLES.lock().values() {
+        join.cancel();
+    }
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_sleep(ms: i64) {
     if ms <= 0 {
@@ -238,6 +387,69 @@ pub extern "C" fn otter_task_channel_float() -> u64 {
     id
 }
 
+// Bounded channels hold at most `capacity` values: `send` waits while one is
+// full and `try_send` returns 0 instead of waiting
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_bounded_channel_string(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    STRING_CHANNELS.lock().insert(
+        id,
+        ChannelWrapper {
+            channel: TaskChannel::bounded(capacity.max(0) as usize, obtain_metrics()),
+        },
+    );
+    id
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_bounded_channel_int(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    INT_CHANNELS.lock().insert(
+        id,
+        ChannelWrapper {
+            channel: TaskChannel::bounded(capacity.max(0) as usize, obtain_metrics()),
+        },
+    );
+    id
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_bounded_channel_float(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    FLOAT_CHANNELS.lock().insert(
+        id,
+        ChannelWrapper {
+            channel: TaskChannel::bounded(capacity.max(0) as usize, obtain_metrics()),
+        },
+    );
+    id
+}
+
+// Sends and receives clone the channel out of the registry first: holding the
+// registry lock while waiting would stop every other task from reaching it.
+
+fn string_channel(handle: u64) -> Option<TaskChannel<String>> {
+    STRING_CHANNELS
+        .lock()
+        .get(&handle)
+        .map(|wrapper| wrapper.channel.clone())
+}
+
+fn int_channel(handle: u64) -> Option<TaskChannel<i64>> {
+    INT_CHANNELS
+        .lock()
+        .get(&handle)
+        .map(|wrapper| wrapper.channel.clone())
+}
+
+fn float_channel(handle: u64) -> Option<TaskChannel<f64>> {
+    FLOAT_CHANNELS
+        .lock()
+        .get(&handle)
+        .map(|wrapper| wrapper.channel.clone())
+}
+
 /// send a string `value` to the channel pointed to by `handle`
 ///
 /// # Safety
@@ -249,39 +461,187 @@ pub unsafe extern "C" fn otter_task_send_string(handle: u64, value: *const c_cha
         return 0;
     }
     let value = unsafe { CStr::from_ptr(value).to_str().unwrap_or("").to_string() };
-    if let Some(wrapper) = STRING_CHANNELS.lock().get(&handle) {
-        wrapper.channel.send(value);
-        1
-    } else {
-        0
+    match string_channel(handle) {
+        Some(channel) => channel.send(value) as i32,
+        None => string_broadcast(handle).is_some_and(|broadcast| broadcast.send(value)) as i32,
     }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_send_int(handle: u64, value: i64) -> i32 {
-    if let Some(wrapper) = INT_CHANNELS.lock().get(&handle) {
-        wrapper.channel.send(value);
-        1
-    } else {
-        0
+    match int_channel(handle) {
+        Some(channel) => channel.send(value) as i32,
+        None => int_broadcast(handle).is_some_and(|broadcast| broadcast.send(value)) as i32,
     }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_send_float(handle: u64, value: f64) -> i32 {
-    if let Some(wrapper) = FLOAT_CHANNELS.lock().get(&handle) {
-        wrapper.channel.send(value);
-        1
-    } else {
-        0
+    match float_channel(handle) {
+        Some(channel) => channel.send(value) as i32,
+        None => float_broadcast(handle).is_some_and(|broadcast| broadcast.send(value)) as i32,
+    }
+}
+
+/// send a string `value` without waiting, returning 0 if the channel pointed
+/// to by `handle` is full or closed
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_task_try_send_string(handle: u64, value: *const c_char) -> i32 {
+    if value.is_null() {
+        return 0;
+    }
+    let value = unsafe { CStr::from_ptr(value).to_str().unwrap_or("").to_string() };
+    string_channel(handle).is_some_and(|channel| channel.try_send(value).is_ok()) as i32
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_try_send_int(handle: u64, value: i64) -> i32 {
+    int_channel(handle).is_some_and(|channel| channel.try_send(value).is_ok()) as i32
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_try_send_float(handle: u64, value: f64) -> i32 {
+    float_channel(handle).is_some_and(|channel| channel.try_send(value).is_ok()) as i32
+}
+
+/// Capacity and current length of any channel
+fn channel_occupancy(handle: u64) -> Option<(Option<usize>, usize)> {
+    string_channel(handle)
+        .map(|channel| (channel.capacity(), channel.len()))
+        .or_else(|| int_channel(handle).map(|channel| (channel.capacity(), channel.len())))
+        .or_else(|| float_channel(handle).map(|channel| (channel.capacity(), channel.len())))
+}
+
+/// Whether a send to `handle` would go through without waiting
+fn channel_has_room(handle: u64) -> bool {
+    channel_occupancy(handle)
+        .is_none_or(|(capacity, len)| capacity.is_none_or(|capacity| len < capacity))
+}
+
+/// Most values the channel holds, 0 for an unbounded channel and -1 for an
+/// unknown handle
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_channel_capacity(handle: u64) -> i64 {
+    match channel_occupancy(handle) {
+        Some((capacity, _)) => capacity.unwrap_or(0) as i64,
+        None => -1,
+    }
+}
+
+/// Values waiting in the channel, or -1 for an unknown handle
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_channel_len(handle: u64) -> i64 {
+    channel_occupancy(handle).map_or(-1, |(_, len)| len as i64)
+}
+
+// ============================================================================
+// Broadcast Channels
+// ============================================================================
+
+// `task.send` on a broadcast handle delivers the value to every subscriber.
+// `task.subscribe` returns an ordinary bounded channel handle, so subscribers
+// use `task.recv`, `select` and `for await` as with any other channel, and
+// leave with `task.close`.
+
+macro_rules! broadcast_registry {
+    ($name:ident, $ty:ty) => {
+        static $name: Lazy<Mutex<HashMap<HandleId, BroadcastChannel<$ty>>>> =
+            Lazy::new(|| Mutex::new(HashMap::new()));
+    };
+}
+
+broadcast_registry!(STRING_BROADCASTS, String);
+broadcast_registry!(INT_BROADCASTS, i64);
+broadcast_registry!(FLOAT_BROADCASTS, f64);
+
+fn string_broadcast(handle: u64) -> Option<BroadcastChannel<String>> {
+    STRING_BROADCASTS.lock().get(&handle).cloned()
+}
+
+fn int_broadcast(handle: u64) -> Option<BroadcastChannel<i64>> {
+    INT_BROADCASTS.lock().get(&handle).cloned()
+}
+
+fn float_broadcast(handle: u64) -> Option<BroadcastChannel<f64>> {
+    FLOAT_BROADCASTS.lock().get(&handle).cloned()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_broadcast_string(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    STRING_BROADCASTS.lock().insert(
+        id,
+        BroadcastChannel::new(capacity.max(0) as usize, obtain_metrics()),
+    );
+    id
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_broadcast_int(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    INT_BROADCASTS.lock().insert(
+        id,
+        BroadcastChannel::new(capacity.max(0) as usize, obtain_metrics()),
+    );
+    id
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_broadcast_float(capacity: i64) -> u64 {
+    let id = next_handle_id();
+    FLOAT_BROADCASTS.lock().insert(
+        id,
+        BroadcastChannel::new(capacity.max(0) as usize, obtain_metrics()),
+    );
+    id
+}
+
+/// Subscribes to the broadcast `handle`, returning a channel handle that
+/// receives every message sent from now on, or 0 for an unknown handle
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_subscribe(handle: u64) -> u64 {
+    if let Some(broadcast) = string_broadcast(handle) {
+        let id = next_handle_id();
+        let channel = broadcast.subscribe();
+        STRING_CHANNELS
+            .lock()
+            .insert(id, ChannelWrapper { channel });
+        return id;
+    }
+    if let Some(broadcast) = int_broadcast(handle) {
+        let id = next_handle_id();
+        let channel = broadcast.subscribe();
+        INT_CHANNELS.lock().insert(id, ChannelWrapper { channel });
+        return id;
     }
+    if let Some(broadcast) = float_broadcast(handle) {
+        let id = next_handle_id();
+        let channel = broadcast.subscribe();
+        FLOAT_CHANNELS.lock().insert(id, ChannelWrapper { channel });
+        return id;
+    }
+    0
+}
+
+/// Subscribers still listening to the broadcast `handle`, or -1 for an
+/// unknown handle
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_subscriber_count(handle: u64) -> i64 {
+    string_broadcast(handle)
+        .map(|broadcast| broadcast.subscriber_count())
+        .or_else(|| int_broadcast(handle).map(|broadcast| broadcast.subscriber_count()))
+        .or_else(|| float_broadcast(handle).map(|broadcast| broadcast.subscriber_count()))
+        .map_or(-1, |count| count as i64)
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_recv_string(handle: u64) -> *mut c_char {
-    if let Some(wrapper) = STRING_CHANNELS.lock().get(&handle)
-        && let Some(value) = wrapper.channel.recv()
-    {
+    let channel = string_channel(handle);
+    if let Some(value) = channel.and_then(|channel| channel.recv()) {
         return CString::new(value)
             .ok()
             .map(CString::into_raw)
@@ -292,18 +652,16 @@ pub extern "C" fn otter_task_recv_string(handle: u64) -> *mut c_char {
 
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_recv_int(handle: u64) -> i64 {
-    if let Some(wrapper) = INT_CHANNELS.lock().get(&handle) {
-        return wrapper.channel.recv().unwrap_or(0);
-    }
-    0
+    int_channel(handle)
+        .and_then(|channel| channel.recv())
+        .unwrap_or(0)
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_task_recv_float(handle: u64) -> f64 {
-    if let Some(wrapper) = FLOAT_CHANNELS.lock().get(&handle) {
-        return wrapper.channel.recv().unwrap_or(0.0);
-    }
-    0.0
+    float_channel(handle)
+        .and_then(|channel| channel.recv())
+        .unwrap_or(0.0)
 }
 
 #[unsafe(no_mangle)]
@@ -323,6 +681,177 @@ pub extern "C" fn otter_task_close_channel(handle: u64) {
     STRING_CHANNELS.lock().remove(&handle);
     INT_CHANNELS.lock().remove(&handle);
     FLOAT_CHANNELS.lock().remove(&handle);
+
+    // Closing a broadcast closes its subscribers' channels too
+    if let Some(broadcast) = STRING_BROADCASTS.lock().remove(&handle) {
+        broadcast.close();
+    }
+    if let Some(broadcast) = INT_BROADCASTS.lock().remove(&handle) {
+        broadcast.close();
+    }
+    if let Some(broadcast) = FLOAT_BROADCASTS.lock().remove(&handle) {
+        broadcast.close();
+    }
+}
+
+// ============================================================================
+// Streams
+// ============================================================================
+
+// A stream is a channel that its producer closes when it has no more items.
+// `for await item in stream:` iterates with the `otter_stream_*` functions
+// below, and `task.stream_*` spawns a producer task and closes its channel
+// when the producer returns. A closed stream stays registered until a loop
+// drains it, so a producer that finishes first does not lose its items.
+
+type StreamProducer = extern "C" fn(u64);
+
+/// The channel a `for await` loop receives from. It is cloned out of the
+/// registry so the loop can drain it after `task.close` drops the handle.
+#[derive(Debug)]
+enum StreamSource {
+    String(TaskChannel<String>),
+    Int(TaskChannel<i64>),
+    Float(TaskChannel<f64>),
+}
+
+impl StreamSource {
+    fn lookup(handle: u64) -> Option<Self> {
+        if let Some(wrapper) = STRING_CHANNELS.lock().get(&handle) {
+            return Some(Self::String(wrapper.channel.clone()));
+        }
+        if let Some(wrapper) = INT_CHANNELS.lock().get(&handle) {
+            return Some(Self::Int(wrapper.channel.clone()));
+        }
+        FLOAT_CHANNELS
+            .lock()
+            .get(&handle)
+            .map(|wrapper| Self::Float(wrapper.channel.clone()))
+    }
+
+    /// Waits for the next item, or `None` once the channel is closed and empty
+    fn recv(&self) -> Option<Value> {
+        match self {
+            Self::String(channel) => channel.recv().map(Value::String),
+            Self::Int(channel) => channel.recv().map(Value::I64),
+            Self::Float(channel) => channel.recv().map(Value::F64),
+        }
+    }
+
+    fn close(&self) {
+        match self {
+            Self::String(channel) => channel.close(),
+            Self::Int(channel) => channel.close(),
+            Self::Float(channel) => channel.close(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OtterStreamIterator {
+    handle: u64,
+    source: Option<StreamSource>,
+    /// Item received by `otter_stream_has_next` and not yet taken
+    pending: Option<Value>,
+    exhausted: bool,
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_stream_iter(handle: u64) -> *mut OtterStreamIterator {
+    Box::into_raw(Box::new(OtterStreamIterator {
+        handle,
+        source: StreamSource::lookup(handle),
+        pending: None,
+        exhausted: false,
+    }))
+}
+
+/// Waits until the stream has another item, returning false once it is closed
+/// and drained
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_stream_has_next(iter: *mut OtterStreamIterator) -> bool {
+    if iter.is_null() {
+        return false;
+    }
+    let iter = unsafe { &mut *iter };
+    if iter.pending.is_none() && !iter.exhausted {
+        iter.pending = iter.source.as_ref().and_then(StreamSource::recv);
+        iter.exhausted = iter.pending.is_none();
+    }
+    iter.pending.is_some()
+}
+
+/// Takes the item found by [`otter_stream_has_next`] as a tagged runtime value
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_stream_next(iter: *mut OtterStreamIterator) -> u64 {
+    if iter.is_null() {
+        return 0;
+    }
+    let iter = unsafe { &mut *iter };
+    iter.pending
+        .take()
+        .map(|value| encode_runtime_value(&value))
+        .unwrap_or(0)
+}
+
+/// Releases a stream iterator, unregistering the channel if the loop drained it
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_stream_free(iter: *mut OtterStreamIterator) {
+    if iter.is_null() {
+        return;
+    }
+    let iter = unsafe { Box::from_raw(iter) };
+    if iter.exhausted {
+        otter_task_close_channel(iter.handle);
+    }
+}
+
+/// Runs `producer` as a task that fills the channel `handle`, closing it when
+/// the producer returns
+fn spawn_stream_producer(handle: u64, producer: StreamProducer) {
+    increment_active_tasks();
+    let scheduler = runtime().scheduler().clone();
+    let join = scheduler.spawn_fn(Some("task.stream".into()), move || {
+        producer(handle);
+        if let Some(source) = StreamSource::lookup(handle) {
+            source.close();
+        }
+        decrement_active_tasks();
+    });
+    record_spawn_site(join.task_id().raw(), producer as usize);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_stream_string(producer: StreamProducer) -> u64 {
+    let handle = otter_task_channel_string();
+    spawn_stream_producer(handle, producer);
+    handle
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_stream_int(producer: StreamProducer) -> u64 {
+    let handle = otter_task_channel_int();
+    spawn_stream_producer(handle, producer);
+    handle
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_task_stream_float(producer: StreamProducer) -> u64 {
+    let handle = otter_task_channel_float();
+    spawn_stream_producer(handle, producer);
+    handle
 }
 
 // ============================================================================
@@ -381,8 +910,10 @@ pub unsafe extern "C" fn otter_builtin_select(
     // First pass: check for immediate readiness
     for (idx, case) in cases_slice.iter().enumerate() {
         if case.is_send {
-            // Send is always ready in unbounded channels
-            return idx as i64;
+            // Sends are ready unless a bounded channel is full
+            if channel_has_room(case.channel) {
+                return idx as i64;
+            }
         } else {
             // Check if any channel has data
             if STRING_CHANNELS
@@ -429,6 +960,9 @@ pub unsafe extern "C" fn otter_builtin_select(
 
             // Check again before sleeping to avoid race
             for (idx, case) in cases_slice.iter().enumerate() {
+                if case.is_send && channel_has_room(case.channel) {
+                    return idx as i64;
+                }
                 if !case.is_send {
                     if STRING_CHANNELS
                         .lock()
@@ -449,9 +983,17 @@ pub unsafe extern "C" fn otter_builtin_select(
             }
 
             // Wait
+            // Full channels do not wake senders through the waker, so poll
+            // while a send case is waiting for room
             let mut ready = condvar_pair.0.lock();
             if !*ready {
-                condvar_pair.1.wait(&mut ready);
+                if cases_slice.iter().any(|case| case.is_send) {
+                    condvar_pair
+                        .1
+                        .wait_for(&mut ready, Duration::from_millis(1));
+                } else {
+                    condvar_pair.1.wait(&mut ready);
+                }
             }
             *ready = false; // Reset for next iteration
         }
@@ -473,6 +1015,12 @@ fn register_std_task_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
     });
 
+    registry.register(FfiFunction {
+        name: "task.spawn_blocking".into(),
+        symbol: "otter_task_spawn_blocking".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
+    });
+
     registry.register(FfiFunction {
         name: "task.join".into(),
         symbol: "otter_task_join".into(),
@@ -485,6 +1033,51 @@ fn register_std_task_symbols(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
     });
 
+    registry.register(FfiFunction {
+        name: "task.supervisor".into(),
+        symbol: "otter_task_supervisor".into(),
+        signature: FfiSignature::new(vec![FfiType::I64, FfiType::I64], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervisor_backoff".into(),
+        symbol: "otter_task_supervisor_backoff".into(),
+        signature: FfiSignature::new(
+            vec![FfiType::Opaque, FfiType::I64, FfiType::I64],
+            FfiType::Unit,
+        ),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervise".into(),
+        symbol: "otter_task_supervise".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Opaque], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervise_child".into(),
+        symbol: "otter_task_supervise_child".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque, FfiType::Opaque], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervisor_restarts".into(),
+        symbol: "otter_task_supervisor_restarts".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervisor_stop".into(),
+        symbol: "otter_task_supervisor_stop".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.supervisor_wait".into(),
+        symbol: "otter_task_supervisor_wait".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Bool),
+    });
+
     registry.register(FfiFunction {
         name: "task.sleep".into(),
         symbol: "otter_task_sleep".into(),
@@ -621,6 +1214,123 @@ fn register_std_task_symbols(registry: &SymbolRegistry) {
         symbol: "otter_task_recv_string".into(),
         signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Str),
     });
+
+    // Bounded channels and non-waiting sends
+    for (name, bounded_symbol, try_send_symbol, value_type) in [
+        (
+            "string",
+            "otter_task_bounded_channel_string",
+            "otter_task_try_send_string",
+            FfiType::Str,
+        ),
+        (
+            "int",
+            "otter_task_bounded_channel_int",
+            "otter_task_try_send_int",
+            FfiType::I64,
+        ),
+        (
+            "float",
+            "otter_task_bounded_channel_float",
+            "otter_task_try_send_float",
+            FfiType::F64,
+        ),
+    ] {
+        for alias in [
+            format!("task.bounded_channel<{name}>"),
+            format!("task.bounded_channel_{name}"),
+        ] {
+            registry.register(FfiFunction {
+                name: alias,
+                symbol: bounded_symbol.into(),
+                signature: FfiSignature::new(vec![FfiType::I64], FfiType::Opaque),
+            });
+        }
+        for alias in [
+            format!("task.try_send<{name}>"),
+            format!("task.try_send_{name}"),
+        ] {
+            registry.register(FfiFunction {
+                name: alias,
+                symbol: try_send_symbol.into(),
+                signature: FfiSignature::new(
+                    vec![FfiType::Opaque, value_type.clone()],
+                    FfiType::I32,
+                ),
+            });
+        }
+    }
+
+    registry.register(FfiFunction {
+        name: "task.capacity".into(),
+        symbol: "otter_task_channel_capacity".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.len".into(),
+        symbol: "otter_task_channel_len".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    // Broadcasts: `task.send` reaches every subscriber
+    for (name, symbol) in [
+        ("string", "otter_task_broadcast_string"),
+        ("int", "otter_task_broadcast_int"),
+        ("float", "otter_task_broadcast_float"),
+    ] {
+        for alias in [
+            format!("task.broadcast<{name}>"),
+            format!("task.broadcast_{name}"),
+        ] {
+            registry.register(FfiFunction {
+                name: alias,
+                symbol: symbol.into(),
+                signature: FfiSignature::new(vec![FfiType::I64], FfiType::Opaque),
+            });
+        }
+        // Typed names let the type checker know the subscription's element type
+        for alias in [
+            format!("task.subscribe<{name}>"),
+            format!("task.subscribe_{name}"),
+        ] {
+            registry.register(FfiFunction {
+                name: alias,
+                symbol: "otter_task_subscribe".into(),
+                signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
+            });
+        }
+    }
+
+    registry.register(FfiFunction {
+        name: "task.subscribe".into(),
+        symbol: "otter_task_subscribe".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
+    });
+
+    registry.register(FfiFunction {
+        name: "task.subscribers".into(),
+        symbol: "otter_task_subscriber_count".into(),
+        signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::I64),
+    });
+
+    // Streams: a producer function fills a channel that is closed when it returns
+    for (name, symbol) in [
+        ("string", "otter_task_stream_string"),
+        ("int", "otter_task_stream_int"),
+        ("float", "otter_task_stream_float"),
+    ] {
+        for alias in [
+            format!("task.stream<{name}>"),
+            format!("task.stream_{name}"),
+        ] {
+            registry.register(FfiFunction {
+                name: alias,
+                symbol: symbol.into(),
+                signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Opaque),
+            });
+        }
+    }
 }
 
 inventory::submit! {
@@ -630,3 +1340,93 @@ inventory::submit! {
         register: register_std_task_symbols,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::builtins::otter_decode_value_as_i64;
+
+    extern "C" fn count_to_three(handle: u64) {
+        for value in 1..=3 {
+            otter_task_send_int(handle, value);
+        }
+    }
+
+    #[test]
+    fn stream_loop_drains_items_sent_before_the_producer_finished() {
+        let handle = otter_task_stream_int(count_to_three);
+        let iter = otter_stream_iter(handle);
+        let mut items = Vec::new();
+        unsafe {
+            while otter_stream_has_next(iter) {
+                items.push(otter_decode_value_as_i64(otter_stream_next(iter)));
+            }
+            assert!(!otter_stream_has_next(iter));
+            otter_stream_free(iter);
+        }
+        assert_eq!(items, vec![1, 2, 3]);
+        // A drained stream is unregistered
+        assert!(INT_CHANNELS.lock().get(&handle).is_none());
+    }
+
+    #[test]
+    fn bounded_channels_report_capacity_and_refuse_try_send_when_full() {
+        let handle = otter_task_bounded_channel_int(2);
+        assert_eq!(otter_task_channel_capacity(handle), 2);
+        assert_eq!(otter_task_send_int(handle, 1), 1);
+        assert_eq!(otter_task_try_send_int(handle, 2), 1);
+        assert_eq!(otter_task_try_send_int(handle, 3), 0);
+        assert_eq!(otter_task_channel_len(handle), 2);
+        assert!(!channel_has_room(handle));
+
+        assert_eq!(otter_task_recv_int(handle), 1);
+        assert!(channel_has_room(handle));
+        otter_task_close_channel(handle);
+        assert_eq!(otter_task_channel_capacity(handle), -1);
+
+        let unbounded = otter_task_channel_int();
+        assert_eq!(otter_task_channel_capacity(unbounded), 0);
+        otter_task_close_channel(unbounded);
+    }
+
+    #[test]
+    fn broadcast_sends_reach_every_subscription() {
+        let broadcast = otter_task_broadcast_int(2);
+        let first = otter_task_subscribe(broadcast);
+        let second = otter_task_subscribe(broadcast);
+        assert_eq!(otter_task_subscriber_count(broadcast), 2);
+        assert_eq!(otter_task_channel_capacity(first), 2);
+
+        assert_eq!(otter_task_send_int(broadcast, 7), 1);
+        assert_eq!(otter_task_recv_int(first), 7);
+        assert_eq!(otter_task_recv_int(second), 7);
+
+        otter_task_close_channel(second);
+        assert_eq!(otter_task_send_int(broadcast, 8), 1);
+        assert_eq!(otter_task_subscriber_count(broadcast), 1);
+
+        otter_task_close_channel(broadcast);
+        assert_eq!(otter_task_send_int(broadcast, 9), 0);
+        assert_eq!(otter_task_subscribe(broadcast), 0);
+        // The remaining subscriber drains what was sent before the close
+        assert_eq!(otter_task_recv_int(first), 8);
+        assert_eq!(otter_task_channel_len(first), 0);
+        otter_task_close_channel(first);
+    }
+
+    static SERVICE_RUNS: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+    extern "C" fn short_lived_service() {
+        SERVICE_RUNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    #[test]
+    fn supervised_callbacks_restart_until_the_supervisor_gives_up() {
+        let supervisor = otter_task_supervisor(2, 10_000);
+        otter_task_supervisor_backoff(supervisor, 1, 5);
+        otter_task_supervise(supervisor, short_lived_service);
+        assert!(!otter_task_supervisor_wait(supervisor));
+        assert_eq!(SERVICE_RUNS.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(otter_task_supervisor_restarts(supervisor), -1);
+    }
+}
@@ -0,0 +1,141 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+use crate::task::{task_local_get, task_local_remove, task_local_set, with_task_local};
+use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+
+// ============================================================================
+// Task-local Values
+// ============================================================================
+
+type ScopeCallback = extern "C" fn();
+
+fn read_c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn to_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or_else(|_| std::ptr::null_mut())
+}
+
+/// returns the current task's value for `key`, or an empty string if it is unset
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_tasklocal_get(key: *const c_char) -> *mut c_char {
+    let value = read_c_str(key)
+        .and_then(|key| task_local_get(&key))
+        .unwrap_or_default();
+    to_c_string(value)
+}
+
+/// returns whether `key` is set for the current task
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_tasklocal_has(key: *const c_char) -> bool {
+    read_c_str(key).is_some_and(|key| task_local_get(&key).is_some())
+}
+
+/// sets `key` for the current task and every task it spawns afterwards
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_tasklocal_set(key: *const c_char, value: *const c_char) {
+    if let (Some(key), Some(value)) = (read_c_str(key), read_c_str(value)) {
+        task_local_set(&key, &value);
+    }
+}
+
+/// removes `key` from the current task
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_tasklocal_remove(key: *const c_char) {
+    if let Some(key) = read_c_str(key) {
+        task_local_remove(&key);
+    }
+}
+
+/// runs `callback` with `key` set to `value`, restoring the previous value
+/// afterwards. Tasks spawned by `callback` keep the value.
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_tasklocal_scope(
+    key: *const c_char,
+    value: *const c_char,
+    callback: i64,
+) {
+    if callback == 0 {
+        return;
+    }
+    // SAFETY: the compiler passes the address of a zero-argument Otter function
+    let callback: ScopeCallback = unsafe { std::mem::transmute(callback as usize) };
+    match (read_c_str(key), read_c_str(value)) {
+        (Some(key), Some(value)) => with_task_local(&key, &value, || callback()),
+        _ => callback(),
+    }
+}
+
+fn register_std_tasklocal_symbols(registry: &SymbolRegistry) {
+    registry.register(FfiFunction {
+        name: "tasklocal.get".into(),
+        symbol: "otter_tasklocal_get".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Str),
+    });
+
+    registry.register(FfiFunction {
+        name: "tasklocal.has".into(),
+        symbol: "otter_tasklocal_has".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Bool),
+    });
+
+    registry.register(FfiFunction {
+        name: "tasklocal.set".into(),
+        symbol: "otter_tasklocal_set".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "tasklocal.remove".into(),
+        symbol: "otter_tasklocal_remove".into(),
+        signature: FfiSignature::new(vec![FfiType::Str], FfiType::Unit),
+    });
+
+    registry.register(FfiFunction {
+        name: "tasklocal.scope".into(),
+        symbol: "otter_tasklocal_scope".into(),
+        signature: FfiSignature::new(
+            vec![FfiType::Str, FfiType::Str, FfiType::Opaque],
+            FfiType::Unit,
+        ),
+    });
+}
+
+inventory::submit! {
+    otterc_ffi::SymbolProvider {
+        namespace: "tasklocal",
+        autoload: false,
+        register: register_std_tasklocal_symbols,
+    }
+}
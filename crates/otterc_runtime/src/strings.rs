@@ -3,6 +3,7 @@ use std::os::raw::c_char;
 
 use crate::memory::gc::{ObjectKind, get_gc};
 use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType, SymbolRegistry};
+use otterc_utils::format_spec::FormatSpec;
 
 /// Format a float value to string
 #[unsafe(no_mangle)]
@@ -27,7 +28,16 @@ pub extern "C" fn otter_format_float(value: f64) -> *mut c_char {
 /// Format an integer value to string
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_format_int(value: i64) -> *mut c_char {
-    let formatted = format!("{}", value);
+    gc_string(value.to_string())
+}
+
+/// Format an unsigned integer value to string
+#[unsafe(no_mangle)]
+pub extern "C" fn otter_format_uint(value: u64) -> *mut c_char {
+    gc_string(value.to_string())
+}
+
+fn gc_string(formatted: String) -> *mut c_char {
     let s = CString::new(formatted)
         .map(CString::into_raw)
         .unwrap_or_else(|_| std::ptr::null_mut());
@@ -41,6 +51,67 @@ pub extern "C" fn otter_format_int(value: i64) -> *mut c_char {
     s
 }
 
+/// The format specifier codegen passes for an f-string interpolation. The
+/// type checker has already rejected specifiers that do not parse.
+unsafe fn format_spec(spec: *const c_char) -> FormatSpec {
+    if spec.is_null() {
+        return FormatSpec::default();
+    }
+    unsafe { CStr::from_ptr(spec) }
+        .to_str()
+        .ok()
+        .and_then(|spec| FormatSpec::parse(spec).ok())
+        .unwrap_or_default()
+}
+
+/// Format an integer value with an f-string format specifier
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_format_int_spec(value: i64, spec: *const c_char) -> *mut c_char {
+    gc_string(unsafe { format_spec(spec) }.format_int(value))
+}
+
+/// Format an unsigned integer value with an f-string format specifier
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_format_uint_spec(value: u64, spec: *const c_char) -> *mut c_char {
+    gc_string(unsafe { format_spec(spec) }.format_uint(value))
+}
+
+/// Format a float value with an f-string format specifier
+///
+/// # Safety
+///
+/// this function dereferences a raw pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_format_float_spec(value: f64, spec: *const c_char) -> *mut c_char {
+    gc_string(unsafe { format_spec(spec) }.format_float(value))
+}
+
+/// Format a string with an f-string format specifier
+///
+/// # Safety
+///
+/// this function dereferences raw pointers
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn otter_format_str_spec(
+    value: *const c_char,
+    spec: *const c_char,
+) -> *mut c_char {
+    let value = if value.is_null() {
+        ""
+    } else {
+        unsafe { CStr::from_ptr(value) }.to_str().unwrap_or("")
+    };
+    gc_string(unsafe { format_spec(spec) }.format_str(value))
+}
+
 /// Format a boolean value to string
 #[unsafe(no_mangle)]
 pub extern "C" fn otter_format_bool(value: bool) -> *mut c_char {
@@ -190,12 +261,47 @@ fn register_string_functions(registry: &SymbolRegistry) {
         signature: FfiSignature::new(vec![FfiType::I64], FfiType::Str),
     });
 
+    registry.register(FfiFunction {
+        name: "std.strings.format_uint".into(),
+        symbol: "otter_format_uint".into(),
+        signature: FfiSignature::new(vec![FfiType::I64], FfiType::Str),
+    });
+
     registry.register(FfiFunction {
         name: "std.strings.format_bool".into(),
         symbol: "otter_format_bool".into(),
         signature: FfiSignature::new(vec![FfiType::Bool], FfiType::Str),
     });
 
+    for (name, symbol, value) in [
+        (
+            "std.strings.format_int_spec",
+            "otter_format_int_spec",
+            FfiType::I64,
+        ),
+        (
+            "std.strings.format_uint_spec",
+            "otter_format_uint_spec",
+            FfiType::I64,
+        ),
+        (
+            "std.strings.format_float_spec",
+            "otter_format_float_spec",
+            FfiType::F64,
+        ),
+        (
+            "std.strings.format_str_spec",
+            "otter_format_str_spec",
+            FfiType::Str,
+        ),
+    ] {
+        registry.register(FfiFunction {
+            name: name.into(),
+            symbol: symbol.into(),
+            signature: FfiSignature::new(vec![value, FfiType::Str], FfiType::Str),
+        });
+    }
+
     registry.register(FfiFunction {
         name: "std.strings.concat".into(),
         symbol: "otter_str_concat".into(),
@@ -263,6 +369,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_format_uint() {
+        let result = otter_format_uint(u64::MAX);
+        unsafe {
+            let s = CStr::from_ptr(result).to_str().unwrap();
+            assert_eq!(s, "18446744073709551615");
+            otter_free_string(result);
+        }
+    }
+
     #[test]
     fn test_concat_strings() {
         let s1 = CString::new("Hello ").unwrap();
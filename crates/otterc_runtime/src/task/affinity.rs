@@ -0,0 +1,323 @@
+//! CPU placement for scheduler workers
+//!
+//! [`CpuAffinity::Pinned`] pins each worker thread to one core. With
+//! [`StealPolicy::Numa`] each worker is kept on the CPUs of one NUMA node
+//! and idle workers steal from workers on the same node before crossing to
+//! another, so task data stays in node-local memory and caches.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether worker threads are pinned to cores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuAffinity {
+    /// Let the OS place worker threads
+    #[default]
+    Off,
+    /// Pin worker `i` to the `i`-th CPU the process may run on
+    Pinned,
+}
+
+impl FromStr for CpuAffinity {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" | "0" | "false" => Ok(Self::Off),
+            "pin" | "pinned" | "1" | "true" => Ok(Self::Pinned),
+            other => Err(format!(
+                "unknown CPU affinity '{other}' (expected off or pin)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CpuAffinity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Pinned => "pin",
+        })
+    }
+}
+
+/// Order in which an idle worker visits other workers when stealing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StealPolicy {
+    /// Steal from any worker, in index order
+    #[default]
+    Any,
+    /// Steal from workers on the same NUMA node first
+    Numa,
+}
+
+impl FromStr for StealPolicy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "any" | "global" => Ok(Self::Any),
+            "numa" | "numa-local" => Ok(Self::Numa),
+            other => Err(format!(
+                "unknown steal policy '{other}' (expected any or numa)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StealPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Any => "any",
+            Self::Numa => "numa",
+        })
+    }
+}
+
+/// CPUs available to the process, grouped by NUMA node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topology {
+    nodes: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    pub fn new(nodes: Vec<Vec<usize>>) -> Self {
+        let nodes: Vec<Vec<usize>> = nodes.into_iter().filter(|cpus| !cpus.is_empty()).collect();
+        if nodes.is_empty() {
+            return Self::single_node(1);
+        }
+        Self { nodes }
+    }
+
+    /// A machine with one node holding `cpus` CPUs
+    pub fn single_node(cpus: usize) -> Self {
+        Self {
+            nodes: vec![(0..cpus.max(1)).collect()],
+        }
+    }
+
+    /// Reads the NUMA layout from sysfs, keeping only the CPUs this process may
+    /// run on. Falls back to a single node elsewhere.
+    pub fn detect() -> Self {
+        let allowed = allowed_cpus();
+        let nodes = read_numa_nodes()
+            .map(|nodes| {
+                nodes
+                    .into_iter()
+                    .map(|cpus| {
+                        cpus.into_iter()
+                            .filter(|cpu| allowed.as_ref().is_none_or(|set| set.contains(cpu)))
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<usize>>>()
+            })
+            .unwrap_or_default();
+        if nodes.iter().any(|cpus| !cpus.is_empty()) {
+            return Self::new(nodes);
+        }
+        match allowed {
+            Some(cpus) if !cpus.is_empty() => Self::new(vec![cpus]),
+            _ => Self::single_node(
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1),
+            ),
+        }
+    }
+
+    pub fn nodes(&self) -> &[Vec<usize>] {
+        &self.nodes
+    }
+
+    /// All CPUs, node by node
+    pub fn cpus(&self) -> Vec<usize> {
+        self.nodes.iter().flatten().copied().collect()
+    }
+
+    /// The CPU worker `index` is placed on
+    pub fn cpu_for_worker(&self, index: usize) -> usize {
+        let cpus = self.cpus();
+        cpus[index % cpus.len()]
+    }
+
+    /// The NUMA node worker `index` is placed on
+    pub fn node_for_worker(&self, index: usize) -> usize {
+        let cpu = self.cpu_for_worker(index);
+        self.nodes
+            .iter()
+            .position(|cpus| cpus.contains(&cpu))
+            .unwrap_or(0)
+    }
+
+    /// The CPUs worker `index` may run on, or `None` to leave it unpinned
+    pub fn placement(
+        &self,
+        index: usize,
+        affinity: CpuAffinity,
+        policy: StealPolicy,
+    ) -> Option<Vec<usize>> {
+        match (affinity, policy) {
+            (CpuAffinity::Pinned, _) => Some(vec![self.cpu_for_worker(index)]),
+            (CpuAffinity::Off, StealPolicy::Numa) if self.nodes.len() > 1 => {
+                Some(self.nodes[self.node_for_worker(index)].clone())
+            }
+            (CpuAffinity::Off, _) => None,
+        }
+    }
+
+    /// Workers that worker `index` steals from, in the order it tries them
+    pub fn steal_order(&self, index: usize, workers: usize, policy: StealPolicy) -> Vec<usize> {
+        let others = (0..workers).filter(|&other| other != index);
+        match policy {
+            StealPolicy::Any => others.collect(),
+            StealPolicy::Numa => {
+                let node = self.node_for_worker(index);
+                let (mut local, remote): (Vec<usize>, Vec<usize>) =
+                    others.partition(|&other| self.node_for_worker(other) == node);
+                local.extend(remote);
+                local
+            }
+        }
+    }
+}
+
+/// Parses a kernel CPU list such as `0-3,8-11`
+pub fn parse_cpu_list(text: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in text.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                if end < start {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.trim().parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(target_os = "linux")]
+fn read_numa_nodes() -> Option<Vec<Vec<usize>>> {
+    let mut nodes: Vec<(usize, Vec<usize>)> = std::fs::read_dir("/sys/devices/system/node")
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let id: usize = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse()
+                .ok()?;
+            let list = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((id, parse_cpu_list(&list)?))
+        })
+        .collect();
+    nodes.sort_by_key(|(id, _)| *id);
+    Some(nodes.into_iter().map(|(_, cpus)| cpus).collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_numa_nodes() -> Option<Vec<Vec<usize>>> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn allowed_cpus() -> Option<Vec<usize>> {
+    // SAFETY: cpu_set_t is plain data and sched_getaffinity fills it in
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn allowed_cpus() -> Option<Vec<usize>> {
+    None
+}
+
+/// Restricts the calling thread to `cpus`. Returns false if the OS refused or
+/// does not support thread affinity.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpus: &[usize]) -> bool {
+    // SAFETY: cpu_set_t is plain data, and CPU_SET only writes inside it for
+    // CPUs below CPU_SETSIZE
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpus: &[usize]) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8-9\n"), Some(vec![0, 1, 2, 3, 8, 9]));
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a-b"), None);
+    }
+
+    #[test]
+    fn numa_policy_steals_from_the_same_node_first() {
+        let topology = Topology::new(vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(topology.steal_order(2, 4, StealPolicy::Any), vec![0, 1, 3]);
+        assert_eq!(topology.steal_order(2, 4, StealPolicy::Numa), vec![3, 0, 1]);
+        // Workers past the CPU count wrap around onto the same CPUs
+        assert_eq!(topology.node_for_worker(5), 0);
+    }
+
+    #[test]
+    fn places_workers() {
+        let topology = Topology::new(vec![vec![0, 1], vec![4, 5]]);
+        assert_eq!(
+            topology.placement(2, CpuAffinity::Pinned, StealPolicy::Any),
+            Some(vec![4])
+        );
+        assert_eq!(
+            topology.placement(3, CpuAffinity::Off, StealPolicy::Numa),
+            Some(vec![4, 5])
+        );
+        assert_eq!(
+            topology.placement(3, CpuAffinity::Off, StealPolicy::Any),
+            None
+        );
+        assert_eq!(
+            Topology::single_node(4).placement(1, CpuAffinity::Off, StealPolicy::Numa),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_flag_values() {
+        assert_eq!("pin".parse(), Ok(CpuAffinity::Pinned));
+        assert_eq!("OFF".parse(), Ok(CpuAffinity::Off));
+        assert_eq!("numa".parse(), Ok(StealPolicy::Numa));
+        assert!("fastest".parse::<StealPolicy>().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Warnings for blocking calls made on task workers
+//!
+//! Codegen brackets every call to a function the symbol registry flags as
+//! possibly blocking (file, network and process access, FFI plugins, sleeps,
+//! locks) with [`blocking_enter`] and [`blocking_exit`]. When such a call runs
+//! inside a task and holds the worker for longer than `OTTER_BLOCKING_WARN_MS`
+//! (50ms by default, 0 to disable), the runtime prints a warning naming the
+//! call site and the task. Each call site is reported once. Calls made on the
+//! blocking pool (`task.spawn_blocking`) are never reported.
+
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::{monitor, replay};
+
+const DEFAULT_THRESHOLD: Duration = Duration::from_millis(50);
+
+static THRESHOLD: Lazy<Option<Duration>> =
+    Lazy::new(|| match std::env::var("OTTER_BLOCKING_WARN_MS") {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(ms) => Some(Duration::from_millis(ms)),
+            Err(_) => Some(DEFAULT_THRESHOLD),
+        },
+        Err(_) => Some(DEFAULT_THRESHOLD),
+    });
+
+/// Call sites already warned about
+static REPORTED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+thread_local! {
+    /// The blocking call in progress on this thread and when it started
+    static CURRENT: Cell<Option<(&'static str, Instant)>> = const { Cell::new(None) };
+    /// Whether this thread belongs to the blocking pool, where blocking is fine
+    static ON_BLOCKING_POOL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Called once by each blocking pool thread
+pub(super) fn mark_blocking_pool_thread() {
+    ON_BLOCKING_POOL.with(|flag| flag.set(true));
+}
+
+/// Notes that the current thread is about to make the call described by
+/// `site`, e.g. "`io.read` at main.ot:4:12"
+pub fn blocking_enter(site: &'static str) {
+    if replay::current_task() == 0 || THRESHOLD.is_none() || ON_BLOCKING_POOL.with(Cell::get) {
+        return;
+    }
+    CURRENT.with(|current| current.set(Some((site, Instant::now()))));
+}
+
+/// Ends the call started by [`blocking_enter`], warning if it held the worker
+/// for too long
+pub fn blocking_exit() {
+    let Some((site, started)) = CURRENT.with(Cell::take) else {
+        return;
+    };
+    let Some(threshold) = *THRESHOLD else {
+        return;
+    };
+    let elapsed = started.elapsed();
+    if elapsed >= threshold && REPORTED.lock().insert(site) {
+        report(site, elapsed);
+    }
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "Blocking-call warnings go to stderr while the program runs"
+)]
+fn report(site: &str, elapsed: Duration) {
+    eprint!(
+        "{}",
+        warning(
+            site,
+            elapsed,
+            &monitor::describe_task(replay::current_task())
+        )
+    );
+}
+
+fn warning(site: &str, elapsed: Duration, task: &str) -> String {
+    format!(
+        "warning: blocking call {site} held a task worker for {:.2}s\n{task}\
+         hint: other tasks cannot use this worker until the call returns; \
+         run it with task.spawn_blocking so it gets its own thread\n",
+        elapsed.as_secs_f64()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning_names_the_call_site_and_task() {
+        let text = warning(
+            "`io.read` at main.ot:4:12",
+            Duration::from_millis(250),
+            "  task 3 (spawned by task 0 at load): running for 0.3s\n",
+        );
+        assert!(text.starts_with(
+            "warning: blocking call `io.read` at main.ot:4:12 held a task worker for 0.25s\n"
+        ));
+        assert!(text.contains("  task 3 (spawned by task 0 at load)"));
+    }
+
+    #[test]
+    fn calls_outside_tasks_are_ignored() {
+        blocking_enter("`time.sleep` at main.ot:1:1");
+        assert!(CURRENT.with(Cell::get).is_none());
+        blocking_exit();
+    }
+}
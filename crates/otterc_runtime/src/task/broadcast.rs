@@ -0,0 +1,142 @@
+//! Broadcast channels, which deliver every message to every subscriber
+//!
+//! Each subscriber gets its own bounded [`TaskChannel`], so one that falls
+//! behind holds back the sender instead of missing messages. A subscriber
+//! leaves by closing its channel; the broadcast drops it on the next send.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use super::channel::TaskChannel;
+use super::metrics::TaskRuntimeMetrics;
+
+#[derive(Debug)]
+pub struct BroadcastChannel<T> {
+    inner: Arc<BroadcastInner<T>>,
+}
+
+#[derive(Debug)]
+struct BroadcastInner<T> {
+    /// Most unread messages each subscriber holds before `send` waits
+    capacity: usize,
+    subscribers: Mutex<Vec<TaskChannel<T>>>,
+    closed: Mutex<bool>,
+    metrics: Option<Arc<TaskRuntimeMetrics>>,
+}
+
+impl<T: Clone> BroadcastChannel<T> {
+    /// A broadcast whose subscribers each buffer up to `capacity` messages.
+    /// A capacity of 0 is treated as 1.
+    pub fn new(capacity: usize, metrics: Option<Arc<TaskRuntimeMetrics>>) -> Self {
+        Self {
+            inner: Arc::new(BroadcastInner {
+                capacity: capacity.max(1),
+                subscribers: Mutex::new(Vec::new()),
+                closed: Mutex::new(false),
+                metrics,
+            }),
+        }
+    }
+
+    /// A channel receiving every message sent from now on. Subscribing to a
+    /// closed broadcast gives a channel that is already closed.
+    pub fn subscribe(&self) -> TaskChannel<T> {
+        let channel = TaskChannel::bounded(self.inner.capacity, self.inner.metrics.clone());
+        if *self.inner.closed.lock() {
+            channel.close();
+        } else {
+            self.inner.subscribers.lock().push(channel.clone());
+        }
+        channel
+    }
+
+    /// Sends a copy of `value` to every subscriber, waiting for room in any
+    /// that are full. Returns false if the broadcast is closed.
+    pub fn send(&self, value: T) -> bool {
+        if *self.inner.closed.lock() {
+            return false;
+        }
+        // Clone the list out so subscribing doesn't wait on a slow subscriber
+        let subscribers = self.inner.subscribers.lock().clone();
+        for subscriber in &subscribers {
+            if !subscriber.is_closed() {
+                subscriber.send(value.clone());
+            }
+        }
+        self.inner
+            .subscribers
+            .lock()
+            .retain(|subscriber| !subscriber.is_closed());
+        true
+    }
+
+    /// Number of subscribers that have not closed their channel
+    pub fn subscriber_count(&self) -> usize {
+        self.inner
+            .subscribers
+            .lock()
+            .iter()
+            .filter(|subscriber| !subscriber.is_closed())
+            .count()
+    }
+
+    /// Closes the broadcast and every subscriber channel. Subscribers still
+    /// receive the messages already queued for them.
+    pub fn close(&self) {
+        *self.inner.closed.lock() = true;
+        for subscriber in self.inner.subscribers.lock().drain(..) {
+            subscriber.close();
+        }
+    }
+}
+
+impl<T> Clone for BroadcastChannel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn every_subscriber_receives_each_message() {
+        let broadcast = BroadcastChannel::new(4, None);
+        let first = broadcast.subscribe();
+        let second = broadcast.subscribe();
+        assert!(broadcast.send(1));
+        assert!(broadcast.send(2));
+        broadcast.close();
+
+        for subscriber in [first, second] {
+            assert_eq!(subscriber.recv(), Some(1));
+            assert_eq!(subscriber.recv(), Some(2));
+            assert_eq!(subscriber.recv(), None);
+        }
+        assert!(!broadcast.send(3));
+        assert!(broadcast.subscribe().is_closed());
+    }
+
+    #[test]
+    fn slow_subscribers_hold_back_the_sender_until_they_leave() {
+        let broadcast = BroadcastChannel::new(1, None);
+        let slow = broadcast.subscribe();
+        let late = broadcast.subscribe();
+        late.close();
+        assert!(broadcast.send("a"));
+        assert_eq!(broadcast.subscriber_count(), 1);
+
+        let sender = broadcast.clone();
+        let handle = thread::spawn(move || sender.send("b"));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow.recv(), Some("a"));
+        assert!(handle.join().unwrap());
+        assert_eq!(slow.recv(), Some("b"));
+    }
+}
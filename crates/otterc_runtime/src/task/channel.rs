@@ -2,9 +2,14 @@ use anyhow::{Result, bail};
 use parking_lot::{Condvar, Mutex};
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::Waker;
 
 use super::metrics::TaskRuntimeMetrics;
+use super::monitor::{self, TaskWait};
+use super::replay::{self, ScheduleEvent};
+
+static NEXT_CHANNEL_ID: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Debug)]
 pub struct TaskChannel<T> {
@@ -13,11 +18,17 @@ pub struct TaskChannel<T> {
 
 #[derive(Debug)]
 struct ChannelInner<T> {
+    /// Identifies the channel in recorded schedules
+    id: u64,
     queue: Mutex<VecDeque<T>>,
+    /// Most values the queue holds before `send` waits, or `None` if unbounded
+    capacity: Option<usize>,
     receiver_wakers: Mutex<Vec<Waker>>,
     metrics: Option<Arc<TaskRuntimeMetrics>>,
     closed: Mutex<bool>,
     condvar: Condvar,
+    /// Wakes senders waiting for room in a bounded channel
+    space: Condvar,
 }
 
 impl<T> Default for TaskChannel<T> {
@@ -32,27 +43,101 @@ impl<T> TaskChannel<T> {
     }
 
     pub fn with_metrics(metrics: Option<Arc<TaskRuntimeMetrics>>) -> Self {
+        Self::create(None, metrics)
+    }
+
+    /// A channel holding at most `capacity` values. Once it is full, `send`
+    /// waits until a receiver takes a value, so a fast producer cannot run
+    /// ahead of a slow consumer. A capacity of 0 is treated as 1.
+    pub fn bounded(capacity: usize, metrics: Option<Arc<TaskRuntimeMetrics>>) -> Self {
+        Self::create(Some(capacity.max(1)), metrics)
+    }
+
+    fn create(capacity: Option<usize>, metrics: Option<Arc<TaskRuntimeMetrics>>) -> Self {
         if let Some(metrics) = &metrics {
             metrics.register_channel();
         }
+        let task = replay::current_task();
+        replay::wait_turn(&ScheduleEvent::Channel { task, channel: 0 });
+        let id = NEXT_CHANNEL_ID.fetch_add(1, Ordering::Relaxed);
+        replay::record(ScheduleEvent::Channel { task, channel: id });
+        monitor::used_channel(id);
         Self {
             inner: Arc::new(ChannelInner {
+                id,
                 queue: Mutex::new(VecDeque::new()),
+                capacity,
                 receiver_wakers: Mutex::new(Vec::new()),
                 metrics,
                 closed: Mutex::new(false),
                 condvar: Condvar::new(),
+                space: Condvar::new(),
             }),
         }
     }
 
-    /// Send a value to the channel, waking any waiting receivers.
-    pub fn send(&self, value: T) {
+    /// Send a value to the channel, waking any waiting receivers. On a full
+    /// bounded channel this waits for room first. Returns false, dropping the
+    /// value, if the channel is closed while waiting.
+    pub fn send(&self, value: T) -> bool {
+        monitor::used_channel(self.inner.id);
+        let event = ScheduleEvent::Send {
+            task: replay::current_task(),
+            channel: self.inner.id,
+        };
+        replay::wait_turn(&event);
+        {
+            let mut queue = self.inner.queue.lock();
+            let mut blocked = None;
+            while self.is_full(&queue) {
+                if *self.inner.closed.lock() {
+                    replay::record(event);
+                    return false;
+                }
+                blocked.get_or_insert_with(|| monitor::block(TaskWait::ChannelSend(self.inner.id)));
+                self.inner.space.wait(&mut queue);
+            }
+            queue.push_back(value);
+            replay::record(event);
+        }
+        self.after_send();
+        true
+    }
+
+    /// Send a value without waiting. Gives the value back if a bounded
+    /// channel is full or the channel is closed.
+    pub fn try_send(&self, value: T) -> Result<(), T> {
+        monitor::used_channel(self.inner.id);
+        let event = ScheduleEvent::Send {
+            task: replay::current_task(),
+            channel: self.inner.id,
+        };
+        replay::wait_turn(&event);
         {
             let mut queue = self.inner.queue.lock();
+            if self.is_full(&queue) || *self.inner.closed.lock() {
+                replay::record(event);
+                return Err(value);
+            }
             queue.push_back(value);
+            replay::record(event);
         }
+        self.after_send();
+        Ok(())
+    }
+
+    /// Most values the channel holds, or `None` if it is unbounded
+    pub fn capacity(&self) -> Option<usize> {
+        self.inner.capacity
+    }
+
+    fn is_full(&self, queue: &VecDeque<T>) -> bool {
+        self.inner
+            .capacity
+            .is_some_and(|capacity| queue.len() >= capacity)
+    }
 
+    fn after_send(&self) {
         if let Some(metrics) = &self.inner.metrics {
             metrics.record_channel_backlog(1);
         }
@@ -72,11 +157,22 @@ impl<T> TaskChannel<T> {
     /// Receive a value, blocking if none is available.
     /// This is a legacy blocking API. For task-aware code, use `recv_async` instead.
     pub fn recv(&self) -> Option<T> {
+        monitor::used_channel(self.inner.id);
+        let event = ScheduleEvent::Recv {
+            task: replay::current_task(),
+            channel: self.inner.id,
+        };
+        replay::wait_turn(&event);
+
+        // Reported to the deadlock watchdog only once the receive actually waits
+        let mut blocked = None;
         // For blocking recv, use condvar to wait for data
         let mut queue = self.inner.queue.lock();
 
         loop {
             if let Some(value) = queue.pop_front() {
+                replay::record(event);
+                self.inner.space.notify_one();
                 if let Some(metrics) = &self.inner.metrics {
                     metrics.record_channel_backlog(-1);
                 }
@@ -84,21 +180,28 @@ impl<T> TaskChannel<T> {
             }
 
             if *self.inner.closed.lock() {
+                replay::record(event);
                 return None;
             }
 
+            blocked.get_or_insert_with(|| monitor::block(TaskWait::Channel(self.inner.id)));
             self.inner.condvar.wait(&mut queue);
         }
     }
 
     /// Try to receive a value without blocking. Returns None if no value is available.
     pub fn try_recv(&self) -> Option<T> {
+        monitor::used_channel(self.inner.id);
+        let event = self.poll_event();
+        replay::wait_turn(&event);
         let mut queue = self.inner.queue.lock();
         let value = queue.pop_front();
-        if value.is_some()
-            && let Some(metrics) = &self.inner.metrics
-        {
-            metrics.record_channel_backlog(-1);
+        replay::record(event);
+        if value.is_some() {
+            self.inner.space.notify_one();
+            if let Some(metrics) = &self.inner.metrics {
+                metrics.record_channel_backlog(-1);
+            }
         }
         value
     }
@@ -130,7 +233,18 @@ impl<T> TaskChannel<T> {
 
     /// Close the channel, waking all waiting receivers.
     pub fn close(&self) {
+        monitor::used_channel(self.inner.id);
+        let event = ScheduleEvent::Close {
+            task: replay::current_task(),
+            channel: self.inner.id,
+        };
+        replay::wait_turn(&event);
+        // Lock the queue first, like `recv`, so the close is recorded in order
+        // with receives that observe it
+        let queue = self.inner.queue.lock();
         let mut closed = self.inner.closed.lock();
+        replay::record(event);
+        drop(queue);
         if *closed {
             return;
         }
@@ -148,8 +262,9 @@ impl<T> TaskChannel<T> {
             metrics.record_channel_waiters(-pending);
         }
 
-        // Wake blocking receivers
+        // Wake blocking receivers, and senders waiting for room
         self.inner.condvar.notify_all();
+        self.inner.space.notify_all();
     }
 
     /// Get the current queue length.
@@ -186,8 +301,23 @@ impl<T> TaskChannel<T> {
     }
 
     fn take_next_value(&self) -> Option<T> {
+        monitor::used_channel(self.inner.id);
+        let event = self.poll_event();
+        replay::wait_turn(&event);
         let mut queue = self.inner.queue.lock();
-        queue.pop_front()
+        let value = queue.pop_front();
+        replay::record(event);
+        if value.is_some() {
+            self.inner.space.notify_one();
+        }
+        value
+    }
+
+    fn poll_event(&self) -> ScheduleEvent {
+        ScheduleEvent::Poll {
+            task: replay::current_task(),
+            channel: self.inner.id,
+        }
     }
 
     fn take_next_waker(&self) -> Option<Waker> {
@@ -317,6 +447,60 @@ mod tests {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn bounded_send_waits_for_room() {
+        let channel = TaskChannel::bounded(2, None);
+        assert!(channel.send(1));
+        assert_eq!(channel.try_send(2), Ok(()));
+        assert_eq!(channel.try_send(3), Err(3));
+        assert_eq!(channel.capacity(), Some(2));
+
+        let producer = channel.clone();
+        let handle = thread::spawn(move || producer.send(3));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(channel.len(), 2);
+
+        assert_eq!(channel.recv(), Some(1));
+        assert!(handle.join().unwrap());
+        assert_eq!(channel.recv(), Some(2));
+        assert_eq!(channel.recv(), Some(3));
+    }
+
+    #[test]
+    fn closing_a_full_channel_releases_waiting_senders() {
+        let channel = TaskChannel::bounded(1, None);
+        assert!(channel.send(1));
+        let producer = channel.clone();
+        let handle = thread::spawn(move || producer.send(2));
+        thread::sleep(Duration::from_millis(20));
+        channel.close();
+        assert!(!handle.join().unwrap());
+        assert_eq!(channel.recv(), Some(1));
+        assert_eq!(channel.recv(), None);
+    }
+
+    #[test]
+    fn receivers_on_clones_share_the_work() {
+        let channel = TaskChannel::bounded(4, None);
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let queue = channel.clone();
+                thread::spawn(move || std::iter::from_fn(|| queue.recv()).collect::<Vec<_>>())
+            })
+            .collect();
+        for job in 0..100 {
+            assert!(channel.send(job));
+        }
+        channel.close();
+
+        let mut done: Vec<i32> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        done.sort_unstable();
+        assert_eq!(done, (0..100).collect::<Vec<_>>());
+    }
+
     #[test]
     fn waker_registration_deduplicated_and_drained_on_close() {
         let channel = TaskChannel::<i32>::new();
@@ -2,6 +2,7 @@ use parking_lot::RwLock;
 use std::cmp::max;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorkerState {
@@ -16,6 +17,8 @@ pub struct WorkerInfo {
     pub state: WorkerState,
     pub queue_depth: usize,
     pub tasks_processed: u64,
+    /// Time spent running tasks
+    pub busy_ns: u64,
 }
 
 #[derive(Debug, Default)]
@@ -98,6 +101,7 @@ impl TaskRuntimeMetrics {
                     state: WorkerState::Idle,
                     queue_depth: 0,
                     tasks_processed: 0,
+                    busy_ns: 0,
                 });
             }
             infos[worker_id].state = state;
@@ -105,10 +109,13 @@ impl TaskRuntimeMetrics {
         }
     }
 
-    pub fn record_worker_task(&self, worker_id: usize) {
+    pub fn record_worker_task(&self, worker_id: usize, busy: Duration) {
         let mut infos = self.worker_infos.write();
-        if worker_id < infos.len() {
-            infos[worker_id].tasks_processed += 1;
+        if let Some(info) = infos.get_mut(worker_id) {
+            info.tasks_processed += 1;
+            info.busy_ns = info
+                .busy_ns
+                .saturating_add(u64::try_from(busy.as_nanos()).unwrap_or(u64::MAX));
         }
     }
 
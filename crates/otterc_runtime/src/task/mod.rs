@@ -3,21 +3,43 @@
 //! Provides a lightweight task scheduler, join handles, and runtime metrics
 //! used by the standard library FFI bindings.
 
+mod affinity;
+mod blocking;
+mod broadcast;
 mod channel;
 mod metrics;
+mod monitor;
+mod replay;
 mod scheduler;
+mod supervisor;
 mod task_impl;
 mod timer;
 mod tls;
+mod trace;
 
+pub use affinity::{CpuAffinity, StealPolicy, Topology, parse_cpu_list, pin_current_thread};
+pub use blocking::{blocking_enter, blocking_exit};
+pub use broadcast::BroadcastChannel;
 pub use channel::{SelectResult, TaskChannel, TaskMailBox, select2, select2_async};
 pub use metrics::{TaskMetricsSnapshot, TaskRuntimeMetrics, WorkerInfo, WorkerState};
+pub use monitor::{TaskStatus, TaskWait, dump_tasks, find_deadlock};
+pub(crate) use monitor::{set_spawn_site, symbol_name};
+pub(crate) use replay::current_task;
+pub use replay::{
+    ScheduleEvent, TURN_TIMEOUT, format_schedule, install_schedule_from_env, is_replaying,
+    parse_schedule, start_recording, start_replay, take_recording,
+};
 pub use scheduler::{SchedulerConfig, TaskScheduler};
-pub use task_impl::{CancellationToken, JoinFuture, JoinHandle, Task, TaskFn, TaskId, TaskState};
+pub use supervisor::{Restart, RestartPolicy, Supervisor};
+pub use task_impl::{
+    CancellationToken, JoinFuture, JoinHandle, Task, TaskFn, TaskId, TaskKind, TaskState,
+};
 pub use timer::TimerWheel;
 pub use tls::{
     TaskLocalRegistry, TaskLocalStorage, cleanup_task_local_storage, get_task_local_storage,
+    task_local_get, task_local_remove, task_local_set, with_task_local,
 };
+pub(crate) use trace::otter_frames;
 
 use std::sync::Once;
 
@@ -29,7 +51,11 @@ pub struct TaskRuntime {
 impl TaskRuntime {
     fn new() -> Self {
         register_exit_hook();
-        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        monitor::start_watchdog();
+        trace::install_panic_hook();
+        let scheduler = TaskScheduler::new(SchedulerConfig::from(
+            &crate::config::get_config().scheduler(),
+        ));
         // Register metrics with runtime for FFI access
         #[cfg(feature = "task-runtime")]
         crate::stdlib::runtime::register_task_metrics(scheduler.metrics());
@@ -47,6 +73,11 @@ pub fn runtime() -> &'static TaskRuntime {
     &RUNTIME
 }
 
+/// The task runtime if the program has started it, without starting it
+pub fn started_runtime() -> Option<&'static TaskRuntime> {
+    once_cell::sync::Lazy::get(&RUNTIME)
+}
+
 /// Initializes the task runtime and returns a scheduler handle.
 pub fn init_runtime() -> TaskScheduler {
     runtime().scheduler().clone()
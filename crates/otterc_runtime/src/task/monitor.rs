@@ -0,0 +1,626 @@
+//! Task dumps, deadlock detection and starvation warnings
+//!
+//! The scheduler reports every task's lifecycle here, and blocking channel
+//! receives and joins report what they wait on. [`dump_tasks`] renders the
+//! table (`runtime.dump_tasks()`), and a watchdog thread looks for blocked
+//! tasks that can only be woken by each other.
+//!
+//! The same watchdog warns about tasks that have sat in the run queue for
+//! longer than `OTTER_STARVATION_MS` (500ms by default, 0 to disable), listing
+//! the tasks that have held a worker without blocking for that long as the
+//! likely cause. Tasks on the blocking pool are left out, since they do not
+//! use the task workers.
+//!
+//! A task blocked on a join can only be woken by the joined task. A task
+//! blocked on a channel receive can be woken by any other live task that has
+//! used the channel. When every possible waker of every task in a set is itself
+//! blocked in that set, none of them can make progress and the set is reported
+//! as a deadlock. If every live task is blocked the whole program is reported,
+//! even when the wakers are unknown.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::replay;
+use super::task_impl::TaskKind;
+
+/// How long tasks must stay blocked before the watchdog reports them
+const DEADLOCK_GRACE: Duration = Duration::from_secs(1);
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a task may wait for a worker before the watchdog warns about it
+const DEFAULT_STARVATION: Duration = Duration::from_millis(500);
+
+/// The main thread is tracked as task 0
+const MAIN_TASK: u64 = 0;
+
+/// What a blocked task is waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskWait {
+    /// A blocking receive on a channel
+    Channel(u64),
+    /// A send waiting for room in a full bounded channel
+    ChannelSend(u64),
+    /// Completion of another task
+    Join(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Blocked(TaskWait),
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskStatus::Queued => write!(f, "queued"),
+            TaskStatus::Running => write!(f, "running"),
+            TaskStatus::Blocked(TaskWait::Channel(channel)) => {
+                write!(f, "blocked receiving from channel {channel}")
+            }
+            TaskStatus::Blocked(TaskWait::ChannelSend(channel)) => {
+                write!(f, "blocked sending to full channel {channel}")
+            }
+            TaskStatus::Blocked(TaskWait::Join(task)) => write!(f, "blocked joining task {task}"),
+        }
+    }
+}
+
+/// A task that spawned another, kept by its descendants so the chain can be
+/// shown after the spawner has finished
+#[derive(Debug, Clone)]
+struct SpawnFrame {
+    task: u64,
+    name: Option<String>,
+    site: Option<String>,
+}
+
+impl std::fmt::Display for SpawnFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task {}", self.task)?;
+        if let Some(name) = &self.name {
+            write!(f, " \"{name}\"")?;
+        }
+        if let Some(site) = &self.site {
+            write!(f, " running {site}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct TaskRecord {
+    name: Option<String>,
+    parent: Option<u64>,
+    spawn_site: Option<String>,
+    /// The tasks that spawned this one, nearest first
+    ancestry: Vec<SpawnFrame>,
+    kind: TaskKind,
+    status: TaskStatus,
+    since: Instant,
+    /// Channels this task has used, so it can be dropped from them when it ends
+    channels: HashSet<u64>,
+}
+
+impl TaskRecord {
+    fn new(name: Option<String>, parent: Option<u64>, status: TaskStatus) -> Self {
+        Self {
+            name,
+            parent,
+            spawn_site: None,
+            ancestry: Vec::new(),
+            kind: TaskKind::Async,
+            status,
+            since: Instant::now(),
+            channels: HashSet::new(),
+        }
+    }
+
+    fn set_status(&mut self, status: TaskStatus) {
+        self.status = status;
+        self.since = Instant::now();
+    }
+
+    fn frame(&self, task: u64) -> SpawnFrame {
+        SpawnFrame {
+            task,
+            name: self.name.clone(),
+            site: self.spawn_site.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TaskMonitor {
+    tasks: HashMap<u64, TaskRecord>,
+    /// Live tasks that have used each channel
+    channel_users: HashMap<u64, HashSet<u64>>,
+    /// Last deadlock reported by the watchdog, so it is printed once
+    reported: Vec<u64>,
+    /// Starving tasks already warned about
+    starvation_reported: HashSet<u64>,
+}
+
+impl TaskMonitor {
+    fn new() -> Self {
+        let main = TaskRecord::new(Some("main".into()), None, TaskStatus::Running);
+        Self {
+            tasks: HashMap::from([(MAIN_TASK, main)]),
+            channel_users: HashMap::new(),
+            reported: Vec::new(),
+            starvation_reported: HashSet::new(),
+        }
+    }
+
+    fn record_mut(&mut self, task: u64) -> &mut TaskRecord {
+        self.tasks
+            .entry(task)
+            .or_insert_with(|| TaskRecord::new(None, None, TaskStatus::Running))
+    }
+
+    fn spawned(&mut self, task: u64, name: Option<String>, parent: u64, kind: TaskKind) {
+        let mut record = TaskRecord::new(name, Some(parent), TaskStatus::Queued);
+        record.kind = kind;
+        if let Some(spawner) = self.tasks.get(&parent) {
+            record.ancestry.push(spawner.frame(parent));
+            record.ancestry.extend(spawner.ancestry.iter().cloned());
+        }
+        self.tasks.insert(task, record);
+    }
+
+    fn finished(&mut self, task: u64) {
+        self.starvation_reported.remove(&task);
+        let Some(record) = self.tasks.remove(&task) else {
+            return;
+        };
+        for channel in record.channels {
+            if let Some(users) = self.channel_users.get_mut(&channel) {
+                users.remove(&task);
+                if users.is_empty() {
+                    self.channel_users.remove(&channel);
+                }
+            }
+        }
+    }
+
+    fn used_channel(&mut self, task: u64, channel: u64) {
+        if self.record_mut(task).channels.insert(channel) {
+            self.channel_users.entry(channel).or_default().insert(task);
+        }
+    }
+
+    /// Live tasks other than `task` that could end its wait
+    fn wakers(&self, task: u64, wait: TaskWait) -> Vec<u64> {
+        match wait {
+            TaskWait::Join(target) if self.tasks.contains_key(&target) => vec![target],
+            TaskWait::Join(_) => Vec::new(),
+            TaskWait::Channel(channel) | TaskWait::ChannelSend(channel) => self
+                .channel_users
+                .get(&channel)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|user| *user != task && self.tasks.contains_key(user))
+                .collect(),
+        }
+    }
+
+    /// Tasks blocked for at least `grace` that can only be woken by each other
+    fn deadlocked(&self, grace: Duration) -> Vec<u64> {
+        let mut stuck: HashMap<u64, TaskWait> = self
+            .tasks
+            .iter()
+            .filter_map(|(id, record)| match record.status {
+                TaskStatus::Blocked(wait) if record.since.elapsed() >= grace => Some((*id, wait)),
+                _ => None,
+            })
+            .collect();
+
+        let mut ids: Vec<u64> = if !stuck.is_empty() && stuck.len() == self.tasks.len() {
+            // Nothing is left running that could wake anyone
+            stuck.into_keys().collect()
+        } else {
+            loop {
+                let free: Vec<u64> = stuck
+                    .iter()
+                    .filter(|(id, wait)| {
+                        let wakers = self.wakers(**id, **wait);
+                        wakers.is_empty() || wakers.iter().any(|w| !stuck.contains_key(w))
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                if free.is_empty() {
+                    break;
+                }
+                for id in free {
+                    stuck.remove(&id);
+                }
+            }
+            stuck.into_keys().collect()
+        };
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Spawned tasks on the task workers in `status` for at least `threshold`
+    fn in_status_for(&self, status: TaskStatus, threshold: Duration) -> Vec<u64> {
+        let mut ids: Vec<u64> = self
+            .tasks
+            .iter()
+            .filter(|(id, record)| {
+                **id != MAIN_TASK
+                    && record.kind == TaskKind::Async
+                    && record.status == status
+                    && record.since.elapsed() >= threshold
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Tasks that have waited at least `threshold` for a worker
+    fn starving(&self, threshold: Duration) -> Vec<u64> {
+        self.in_status_for(TaskStatus::Queued, threshold)
+    }
+
+    /// Tasks that have run at least `threshold` without blocking
+    fn monopolizing(&self, threshold: Duration) -> Vec<u64> {
+        self.in_status_for(TaskStatus::Running, threshold)
+    }
+
+    /// Warning for tasks starving for at least `threshold` that have not been
+    /// reported yet
+    fn starvation_warning(&mut self, threshold: Duration) -> Option<String> {
+        let starved: Vec<u64> = self
+            .starving(threshold)
+            .into_iter()
+            .filter(|id| !self.starvation_reported.contains(id))
+            .collect();
+        if starved.is_empty() {
+            return None;
+        }
+        let mut out = format!(
+            "warning: {} task(s) have waited over {:.1}s for a worker\n{}",
+            starved.len(),
+            threshold.as_secs_f64(),
+            self.render(&starved)
+        );
+        let hogs = self.monopolizing(threshold);
+        if !hogs.is_empty() {
+            let _ = write!(
+                out,
+                "these tasks have held a worker without blocking:\n{}",
+                self.render(&hogs)
+            );
+        }
+        out.push_str(
+            "hint: split long loops into smaller tasks, or build with --preempt-loops so \
+             long-running tasks hand their queued work to another thread\n",
+        );
+        self.starvation_reported.extend(starved);
+        Some(out)
+    }
+
+    fn describe(&self, out: &mut String, id: u64, record: &TaskRecord) {
+        let _ = write!(out, "  task {id}");
+        if let Some(name) = &record.name {
+            let _ = write!(out, " \"{name}\"");
+        }
+        match (record.parent, &record.spawn_site) {
+            (Some(parent), Some(site)) => {
+                let _ = write!(out, " (spawned by task {parent} at {site})");
+            }
+            (Some(parent), None) => {
+                let _ = write!(out, " (spawned by task {parent})");
+            }
+            _ => {}
+        }
+        let _ = writeln!(
+            out,
+            ": {} for {:.1}s",
+            record.status,
+            record.since.elapsed().as_secs_f64()
+        );
+    }
+
+    fn render(&self, ids: &[u64]) -> String {
+        let mut out = String::new();
+        for id in ids {
+            if let Some(record) = self.tasks.get(id) {
+                self.describe(&mut out, *id, record);
+            }
+        }
+        out
+    }
+
+    /// `task`, then each task that led to it being spawned, one per line
+    fn spawn_chain(&self, task: u64) -> Vec<String> {
+        let Some(record) = self.tasks.get(&task) else {
+            return vec![format!("task {task}")];
+        };
+        std::iter::once(record.frame(task))
+            .chain(record.ancestry.iter().cloned())
+            .map(|frame| frame.to_string())
+            .collect()
+    }
+
+    fn dump(&self) -> String {
+        let mut ids: Vec<u64> = self.tasks.keys().copied().collect();
+        ids.sort_unstable();
+        format!("task dump ({} tasks):\n{}", ids.len(), self.render(&ids))
+    }
+}
+
+static MONITOR: Lazy<Mutex<TaskMonitor>> = Lazy::new(|| Mutex::new(TaskMonitor::new()));
+
+pub(crate) fn task_spawned(task: u64, name: Option<String>, kind: TaskKind) {
+    MONITOR
+        .lock()
+        .spawned(task, name, replay::current_task(), kind);
+}
+
+/// Records where a task was spawned, e.g. the name of its entry function
+pub(crate) fn set_spawn_site(task: u64, site: String) {
+    if let Some(record) = MONITOR.lock().tasks.get_mut(&task) {
+        record.spawn_site = Some(site);
+    }
+}
+
+pub(crate) fn task_started(task: u64) {
+    MONITOR
+        .lock()
+        .record_mut(task)
+        .set_status(TaskStatus::Running);
+}
+
+pub(crate) fn task_finished(task: u64) {
+    MONITOR.lock().finished(task);
+}
+
+/// Notes that the current task used `channel`, making it a possible waker of
+/// tasks blocked on it
+pub(crate) fn used_channel(channel: u64) {
+    MONITOR.lock().used_channel(replay::current_task(), channel);
+}
+
+/// Marks the current task blocked until the guard is dropped
+pub(crate) fn block(wait: TaskWait) -> BlockedGuard {
+    let task = replay::current_task();
+    MONITOR
+        .lock()
+        .record_mut(task)
+        .set_status(TaskStatus::Blocked(wait));
+    BlockedGuard { task }
+}
+
+pub(crate) struct BlockedGuard {
+    task: u64,
+}
+
+impl Drop for BlockedGuard {
+    fn drop(&mut self) {
+        if let Some(record) = MONITOR.lock().tasks.get_mut(&self.task) {
+            record.set_status(TaskStatus::Running);
+        }
+    }
+}
+
+/// Every live task with its state, spawn site and what it is waiting for
+pub fn dump_tasks() -> String {
+    MONITOR.lock().dump()
+}
+
+/// One dump line for `task`, or nothing if it is not tracked
+pub(crate) fn describe_task(task: u64) -> String {
+    MONITOR.lock().render(&[task])
+}
+
+/// The task and the tasks that spawned it, nearest first
+pub(crate) fn spawn_chain(task: u64) -> Vec<String> {
+    MONITOR.lock().spawn_chain(task)
+}
+
+/// Whether any spawned task is waiting for a task worker
+pub(crate) fn has_queued_tasks() -> bool {
+    MONITOR
+        .lock()
+        .tasks
+        .values()
+        .any(|record| record.kind == TaskKind::Async && record.status == TaskStatus::Queued)
+}
+
+/// Tasks that have been blocked on each other for at least `grace`
+pub fn find_deadlock(grace: Duration) -> Vec<u64> {
+    MONITOR.lock().deadlocked(grace)
+}
+
+/// Name of the function containing `addr`, used as a spawn site
+#[cfg(unix)]
+pub(crate) fn symbol_name(addr: usize) -> Option<String> {
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::zeroed();
+    let found = unsafe { libc::dladdr(addr as *const libc::c_void, info.as_mut_ptr()) };
+    let info = unsafe { info.assume_init() };
+    if found == 0 || info.dli_sname.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(info.dli_sname) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn symbol_name(_addr: usize) -> Option<String> {
+    None
+}
+
+/// Starvation threshold from `OTTER_STARVATION_MS`, `None` when disabled
+fn starvation_threshold() -> Option<Duration> {
+    match std::env::var("OTTER_STARVATION_MS") {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(ms) => Some(Duration::from_millis(ms)),
+            Err(_) => Some(DEFAULT_STARVATION),
+        },
+        Err(_) => Some(DEFAULT_STARVATION),
+    }
+}
+
+/// Starts the deadlock watchdog unless `OTTER_DEADLOCK_WATCHDOG=0`
+pub(crate) fn start_watchdog() {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        if std::env::var("OTTER_DEADLOCK_WATCHDOG").is_ok_and(|value| value == "0") {
+            return;
+        }
+        let starvation = starvation_threshold();
+        let _ = std::thread::Builder::new()
+            .name("otter-deadlock-watchdog".into())
+            .spawn(move || watchdog_loop(starvation));
+    });
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "Deadlock reports go to stderr while the program is hung"
+)]
+fn watchdog_loop(starvation: Option<Duration>) {
+    loop {
+        std::thread::sleep(WATCHDOG_INTERVAL);
+        let mut monitor = MONITOR.lock();
+        if let Some(warning) =
+            starvation.and_then(|threshold| monitor.starvation_warning(threshold))
+        {
+            eprint!("{warning}");
+        }
+        let stuck = monitor.deadlocked(DEADLOCK_GRACE);
+        if stuck.is_empty() || stuck == monitor.reported {
+            continue;
+        }
+        eprint!(
+            "deadlock detected: {} task(s) are waiting on each other\n{}",
+            stuck.len(),
+            monitor.render(&stuck)
+        );
+        monitor.reported = stuck;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor_with(tasks: &[(u64, TaskStatus)]) -> TaskMonitor {
+        let mut monitor = TaskMonitor::new();
+        for (id, status) in tasks {
+            monitor.spawned(*id, None, MAIN_TASK, TaskKind::Async);
+            monitor.record_mut(*id).set_status(*status);
+        }
+        monitor
+    }
+
+    #[test]
+    fn reports_join_cycles() {
+        let monitor = monitor_with(&[
+            (1, TaskStatus::Blocked(TaskWait::Join(2))),
+            (2, TaskStatus::Blocked(TaskWait::Join(1))),
+            (3, TaskStatus::Blocked(TaskWait::Join(1))),
+            (4, TaskStatus::Running),
+        ]);
+        // Task 3 is stuck behind the cycle, so it cannot make progress either
+        assert_eq!(monitor.deadlocked(Duration::ZERO), [1, 2, 3]);
+    }
+
+    #[test]
+    fn channel_waits_need_every_user_blocked() {
+        let mut monitor = monitor_with(&[
+            (1, TaskStatus::Blocked(TaskWait::Channel(7))),
+            (2, TaskStatus::Blocked(TaskWait::Channel(8))),
+            (3, TaskStatus::Running),
+        ]);
+        monitor.used_channel(1, 7);
+        monitor.used_channel(2, 7);
+        monitor.used_channel(1, 8);
+        monitor.used_channel(2, 8);
+        assert_eq!(monitor.deadlocked(Duration::ZERO), [1, 2]);
+
+        // Another live user of channel 7 could still send to task 1
+        monitor.used_channel(3, 7);
+        assert!(monitor.deadlocked(Duration::ZERO).is_empty());
+
+        monitor.finished(3);
+        assert_eq!(monitor.deadlocked(Duration::ZERO), [1, 2]);
+        assert!(
+            monitor
+                .dump()
+                .contains("task 1 (spawned by task 0): blocked receiving from channel 7")
+        );
+    }
+
+    #[test]
+    fn reports_programs_with_every_task_blocked() {
+        let mut monitor = monitor_with(&[(1, TaskStatus::Blocked(TaskWait::Channel(3)))]);
+        assert!(monitor.deadlocked(Duration::ZERO).is_empty());
+
+        monitor
+            .record_mut(MAIN_TASK)
+            .set_status(TaskStatus::Blocked(TaskWait::Join(1)));
+        assert_eq!(monitor.deadlocked(Duration::ZERO), [0, 1]);
+    }
+
+    #[test]
+    fn warns_once_about_starving_tasks() {
+        let mut monitor = monitor_with(&[
+            (1, TaskStatus::Running),
+            (2, TaskStatus::Queued),
+            (3, TaskStatus::Blocked(TaskWait::Join(1))),
+        ]);
+        monitor.record_mut(1).spawn_site = Some("crunch".into());
+        assert_eq!(monitor.starving(Duration::ZERO), [2]);
+        assert_eq!(monitor.monopolizing(Duration::ZERO), [1]);
+
+        let warning = monitor
+            .starvation_warning(Duration::ZERO)
+            .unwrap_or_default();
+        assert!(warning.contains("1 task(s) have waited"));
+        assert!(warning.contains("task 2 (spawned by task 0): queued"));
+        assert!(warning.contains("task 1 (spawned by task 0 at crunch): running"));
+        assert!(monitor.starvation_warning(Duration::ZERO).is_none());
+
+        assert!(monitor.starving(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn spawn_chain_outlives_finished_spawners() {
+        let mut monitor = TaskMonitor::new();
+        monitor.spawned(1, Some("task.spawn".into()), MAIN_TASK, TaskKind::Async);
+        monitor.record_mut(1).spawn_site = Some("serve".into());
+        monitor.spawned(2, Some("task.spawn".into()), 1, TaskKind::Async);
+        monitor.record_mut(2).spawn_site = Some("handle_request".into());
+        monitor.finished(1);
+
+        assert_eq!(
+            monitor.spawn_chain(2),
+            [
+                "task 2 \"task.spawn\" running handle_request",
+                "task 1 \"task.spawn\" running serve",
+                "task 0 \"main\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn blocking_pool_tasks_do_not_count_as_starving() {
+        let mut monitor = monitor_with(&[(1, TaskStatus::Running)]);
+        monitor.spawned(2, None, MAIN_TASK, TaskKind::Blocking);
+        monitor.spawned(3, None, MAIN_TASK, TaskKind::Blocking);
+        monitor.record_mut(3).set_status(TaskStatus::Running);
+        assert!(monitor.starving(Duration::ZERO).is_empty());
+        assert_eq!(monitor.monopolizing(Duration::ZERO), [1]);
+    }
+}
@@ -0,0 +1,452 @@
+//! Deterministic schedule record/replay
+//!
+//! In record mode (`OTTER_RECORD_SCHEDULE=<path>`) the scheduler logs every
+//! task spawn, every task start and every channel operation in the order they
+//! happened, and writes the log to `<path>` when the program exits. In replay
+//! mode (`OTTER_REPLAY_SCHEDULE=<path>`) each of those points waits until it is
+//! next in the recorded log, so a race that showed up once can be reproduced by
+//! replaying the schedule that triggered it.
+//!
+//! Replay only orders the recorded points; code between them still runs
+//! freely. If the program stops following the log (a point does not get its
+//! turn within [`TURN_TIMEOUT`], or the log runs out) replay reports the
+//! divergence on stderr and the rest of the program runs unordered.
+
+use std::cell::Cell;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::{Condvar, Mutex};
+
+/// How long a point waits for its turn before replay gives up
+pub const TURN_TIMEOUT: Duration = Duration::from_secs(5);
+
+const LOG_HEADER: &str = "# otter schedule v1";
+
+/// One scheduling decision or channel operation. Task 0 is the main thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleEvent {
+    /// `parent` spawned `child`
+    Spawn { parent: u64, child: u64 },
+    /// A worker started running `task`
+    Run { task: u64 },
+    /// `task` created `channel`
+    Channel { task: u64, channel: u64 },
+    /// `task` sent a value on `channel`
+    Send { task: u64, channel: u64 },
+    /// A blocking receive by `task` on `channel` returned
+    Recv { task: u64, channel: u64 },
+    /// `task` polled `channel` without blocking, whether or not a value was ready
+    Poll { task: u64, channel: u64 },
+    /// `task` closed `channel`
+    Close { task: u64, channel: u64 },
+}
+
+impl ScheduleEvent {
+    /// Whether `other` is the same point in the program. Spawns and channel
+    /// creations are matched before their id is allocated, so the new id is
+    /// ignored here and checked once it is recorded.
+    fn same_point(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Spawn { parent: a, .. }, Self::Spawn { parent: b, .. })
+            | (Self::Channel { task: a, .. }, Self::Channel { task: b, .. }) => a == b,
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn { parent, child } => write!(f, "spawn {parent} {child}"),
+            Self::Run { task } => write!(f, "run {task}"),
+            Self::Channel { task, channel } => write!(f, "channel {task} {channel}"),
+            Self::Send { task, channel } => write!(f, "send {task} {channel}"),
+            Self::Recv { task, channel } => write!(f, "recv {task} {channel}"),
+            Self::Poll { task, channel } => write!(f, "poll {task} {channel}"),
+            Self::Close { task, channel } => write!(f, "close {task} {channel}"),
+        }
+    }
+}
+
+impl FromStr for ScheduleEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut parts = line.split_whitespace();
+        let kind = parts.next().ok_or_else(|| anyhow!("empty event"))?;
+        let ids = parts
+            .map(|part| {
+                part.parse::<u64>()
+                    .with_context(|| format!("bad id '{part}'"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let event = match (kind, ids.as_slice()) {
+            ("spawn", [parent, child]) => Self::Spawn {
+                parent: *parent,
+                child: *child,
+            },
+            ("run", [task]) => Self::Run { task: *task },
+            ("channel", [task, channel]) => Self::Channel {
+                task: *task,
+                channel: *channel,
+            },
+            ("send", [task, channel]) => Self::Send {
+                task: *task,
+                channel: *channel,
+            },
+            ("recv", [task, channel]) => Self::Recv {
+                task: *task,
+                channel: *channel,
+            },
+            ("poll", [task, channel]) => Self::Poll {
+                task: *task,
+                channel: *channel,
+            },
+            ("close", [task, channel]) => Self::Close {
+                task: *task,
+                channel: *channel,
+            },
+            _ => bail!("unrecognized event '{line}'"),
+        };
+        Ok(event)
+    }
+}
+
+/// Renders a schedule in the text format read by [`parse_schedule`]
+pub fn format_schedule(events: &[ScheduleEvent]) -> String {
+    let mut out = String::from(LOG_HEADER);
+    out.push('\n');
+    for event in events {
+        out.push_str(&event.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+/// Parses a schedule written by [`format_schedule`]. Blank lines and `#`
+/// comments are skipped.
+pub fn parse_schedule(text: &str) -> Result<Vec<ScheduleEvent>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(index, line)| {
+            line.parse()
+                .with_context(|| format!("schedule line {}", index + 1))
+        })
+        .collect()
+}
+
+// ============================================================================
+// Controller
+// ============================================================================
+
+#[derive(Debug)]
+enum Mode {
+    Off,
+    Record(Vec<ScheduleEvent>),
+    Replay {
+        events: Vec<ScheduleEvent>,
+        cursor: usize,
+    },
+}
+
+/// Records or enforces the order of schedule points
+#[derive(Debug)]
+struct ScheduleController {
+    mode: Mutex<Mode>,
+    turn: Condvar,
+    /// Fast path so the scheduler does not lock anything when replay is off
+    active: AtomicBool,
+    timeout: Duration,
+}
+
+impl ScheduleController {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            mode: Mutex::new(Mode::Off),
+            turn: Condvar::new(),
+            active: AtomicBool::new(false),
+            timeout,
+        }
+    }
+
+    fn set_mode(&self, mode: Mode) -> Mode {
+        let mut current = self.mode.lock();
+        self.active
+            .store(!matches!(mode, Mode::Off), Ordering::SeqCst);
+        let previous = std::mem::replace(&mut *current, mode);
+        self.turn.notify_all();
+        previous
+    }
+
+    /// Blocks until `event` is the next point in the replayed schedule
+    fn wait_turn(&self, event: &ScheduleEvent) {
+        if !self.active.load(Ordering::SeqCst) {
+            return;
+        }
+        let deadline = Instant::now() + self.timeout;
+        let mut mode = self.mode.lock();
+        loop {
+            let Mode::Replay { events, cursor } = &*mode else {
+                return;
+            };
+            let next = match events.get(*cursor) {
+                Some(next) if next.same_point(event) => return,
+                Some(next) => *next,
+                None => {
+                    let message =
+                        format!("the recorded schedule ended, but the program reached `{event}`");
+                    self.diverge(&mut mode, message);
+                    return;
+                }
+            };
+            if self.turn.wait_until(&mut mode, deadline).timed_out() {
+                let message = format!(
+                    "`{event}` waited {:.1}s for its turn behind `{next}`",
+                    self.timeout.as_secs_f64()
+                );
+                self.diverge(&mut mode, message);
+                return;
+            }
+        }
+    }
+
+    /// Logs `event` while recording, or hands the turn to the next point while
+    /// replaying
+    fn record(&self, event: ScheduleEvent) {
+        if !self.active.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut mode = self.mode.lock();
+        match &mut *mode {
+            Mode::Off => {}
+            Mode::Record(events) => events.push(event),
+            Mode::Replay { events, cursor } => match events.get(*cursor) {
+                Some(expected) if *expected == event => {
+                    *cursor += 1;
+                    self.turn.notify_all();
+                }
+                Some(expected) => {
+                    let message = format!("expected `{expected}`, but the program did `{event}`");
+                    self.diverge(&mut mode, message);
+                }
+                None => {
+                    let message =
+                        format!("the recorded schedule ended, but the program did `{event}`");
+                    self.diverge(&mut mode, message);
+                }
+            },
+        }
+    }
+
+    #[expect(
+        clippy::print_stderr,
+        reason = "Divergence is reported on stderr so the program can keep running"
+    )]
+    fn diverge(&self, mode: &mut Mode, message: String) {
+        if let Mode::Replay { cursor, .. } = mode {
+            eprintln!(
+                "warning: schedule replay diverged at event {}: {message}; continuing unordered",
+                *cursor + 1
+            );
+        }
+        *mode = Mode::Off;
+        self.active.store(false, Ordering::SeqCst);
+        self.turn.notify_all();
+    }
+}
+
+static CONTROLLER: Lazy<ScheduleController> = Lazy::new(|| ScheduleController::new(TURN_TIMEOUT));
+static RECORD_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+thread_local! {
+    static CURRENT_TASK: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The task running on this thread, or 0 outside the scheduler's workers
+pub(crate) fn current_task() -> u64 {
+    CURRENT_TASK.with(Cell::get)
+}
+
+pub(crate) fn set_current_task(task: u64) {
+    CURRENT_TASK.with(|current| current.set(task));
+}
+
+/// Blocks until `event` is next in the replayed schedule. No-op unless replaying.
+pub(crate) fn wait_turn(event: &ScheduleEvent) {
+    CONTROLLER.wait_turn(event);
+}
+
+/// Logs `event` while recording and advances the replay cursor while replaying.
+/// Every [`wait_turn`] must be followed by a `record` of the same point.
+pub(crate) fn record(event: ScheduleEvent) {
+    CONTROLLER.record(event);
+}
+
+/// Starts logging schedule points, discarding any previous recording
+pub fn start_recording() {
+    CONTROLLER.set_mode(Mode::Record(Vec::new()));
+}
+
+/// Stops recording and returns the points logged so far
+pub fn take_recording() -> Vec<ScheduleEvent> {
+    match CONTROLLER.set_mode(Mode::Off) {
+        Mode::Record(events) => events,
+        _ => Vec::new(),
+    }
+}
+
+/// Makes every schedule point wait for its turn in `events`
+pub fn start_replay(events: Vec<ScheduleEvent>) {
+    CONTROLLER.set_mode(Mode::Replay { events, cursor: 0 });
+}
+
+/// Whether a replay is in progress and has not diverged
+pub fn is_replaying() -> bool {
+    matches!(*CONTROLLER.mode.lock(), Mode::Replay { .. })
+}
+
+extern "C" fn write_recording_at_exit() {
+    if let Some(path) = RECORD_PATH.get() {
+        write_recording(path);
+    }
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "The program is exiting, stderr is the only channel left"
+)]
+fn write_recording(path: &PathBuf) {
+    let events = take_recording();
+    if let Err(err) = std::fs::write(path, format_schedule(&events)) {
+        eprintln!(
+            "warning: failed to write schedule to {}: {err}",
+            path.display()
+        );
+    }
+}
+
+/// Starts recording or replaying according to `OTTER_RECORD_SCHEDULE` /
+/// `OTTER_REPLAY_SCHEDULE`. Replay wins if both are set.
+#[expect(
+    clippy::print_stderr,
+    reason = "A schedule that cannot be loaded is reported and the program runs unordered"
+)]
+pub fn install_schedule_from_env() {
+    if let Some(path) = std::env::var_os("OTTER_REPLAY_SCHEDULE") {
+        let path = PathBuf::from(path);
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| parse_schedule(&text));
+        match loaded {
+            Ok(events) => start_replay(events),
+            Err(err) => eprintln!(
+                "warning: failed to load schedule {}: {err:#}",
+                path.display()
+            ),
+        }
+    } else if let Some(path) = std::env::var_os("OTTER_RECORD_SCHEDULE")
+        && RECORD_PATH.set(PathBuf::from(path)).is_ok()
+    {
+        start_recording();
+        unsafe {
+            libc::atexit(write_recording_at_exit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn schedule_round_trips_through_text() {
+        let events = vec![
+            ScheduleEvent::Spawn {
+                parent: 0,
+                child: 1,
+            },
+            ScheduleEvent::Channel {
+                task: 0,
+                channel: 4,
+            },
+            ScheduleEvent::Run { task: 1 },
+            ScheduleEvent::Send {
+                task: 1,
+                channel: 4,
+            },
+            ScheduleEvent::Recv {
+                task: 0,
+                channel: 4,
+            },
+        ];
+        let text = format_schedule(&events);
+        assert!(text.starts_with(LOG_HEADER));
+        assert_eq!(parse_schedule(&text).unwrap(), events);
+        assert!(parse_schedule("run one").is_err());
+        assert!(parse_schedule("send 1").is_err());
+    }
+
+    #[test]
+    fn replay_orders_points_across_threads() {
+        let controller = Arc::new(ScheduleController::new(Duration::from_secs(5)));
+        let schedule: Vec<_> = (0..6)
+            .map(|i| ScheduleEvent::Run { task: i % 2 + 1 })
+            .collect();
+        controller.set_mode(Mode::Replay {
+            events: schedule.clone(),
+            cursor: 0,
+        });
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let workers: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|task| {
+                let controller = Arc::clone(&controller);
+                let order = Arc::clone(&order);
+                thread::spawn(move || {
+                    for _ in 0..3 {
+                        let event = ScheduleEvent::Run { task };
+                        controller.wait_turn(&event);
+                        order.lock().push(event);
+                        controller.record(event);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(*order.lock(), schedule);
+        assert!(matches!(
+            *controller.mode.lock(),
+            Mode::Replay { cursor: 6, .. }
+        ));
+    }
+
+    #[test]
+    fn replay_stops_when_the_program_diverges() {
+        let controller = ScheduleController::new(Duration::from_millis(10));
+        controller.set_mode(Mode::Replay {
+            events: vec![ScheduleEvent::Run { task: 1 }],
+            cursor: 0,
+        });
+
+        // Task 2 never gets a turn, so replay gives up instead of hanging
+        controller.wait_turn(&ScheduleEvent::Run { task: 2 });
+        assert!(matches!(*controller.mode.lock(), Mode::Off));
+        assert!(!controller.active.load(Ordering::SeqCst));
+    }
+}
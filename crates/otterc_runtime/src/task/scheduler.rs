@@ -1,18 +1,45 @@
 use crossbeam_deque::{Injector, Steal, Stealer, Worker};
 use crossbeam_utils::Backoff;
+use parking_lot::{Condvar, Mutex};
+use std::cell::Cell;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use super::affinity::{CpuAffinity, StealPolicy, Topology, pin_current_thread};
+use super::blocking::mark_blocking_pool_thread;
 use super::metrics::{TaskRuntimeMetrics, WorkerState};
-use super::task_impl::{JoinHandle, Task, TaskFn};
+use super::monitor;
+use super::replay::{self, ScheduleEvent};
+use super::task_impl::{JoinHandle, Task, TaskFn, TaskId, TaskKind};
 use super::timer::TimerWheel;
-use super::tls::cleanup_task_local_storage;
+use super::tls::{cleanup_task_local_storage, inherit_task_locals};
+
+/// How long a task may run before a preemption point hands off queued work
+const PREEMPT_SLICE: Duration = Duration::from_millis(10);
+/// Preemption points skipped between clock reads
+const PREEMPT_CHECK_INTERVAL: u32 = 1024;
+/// Blocking pool size when the config leaves it at 0
+const DEFAULT_BLOCKING_THREADS: usize = 64;
+/// How long an idle blocking pool thread waits for work before exiting
+const BLOCKING_KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+thread_local! {
+    /// When the task running on this worker started its current time slice
+    static SLICE_START: Cell<Option<Instant>> = const { Cell::new(None) };
+    static PREEMPT_COUNTER: Cell<u32> = const { Cell::new(0) };
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct SchedulerConfig {
     pub max_workers: usize,
+    /// Pin each worker thread to a core
+    pub affinity: CpuAffinity,
+    /// Order in which idle workers steal from each other
+    pub steal_policy: StealPolicy,
+    /// Most threads the blocking pool starts, independent of `max_workers`
+    pub max_blocking_threads: usize,
 }
 
 impl Default for SchedulerConfig {
@@ -22,6 +49,29 @@ impl Default for SchedulerConfig {
             .unwrap_or(4);
         Self {
             max_workers: workers,
+            affinity: CpuAffinity::default(),
+            steal_policy: StealPolicy::default(),
+            max_blocking_threads: DEFAULT_BLOCKING_THREADS,
+        }
+    }
+}
+
+impl From<&crate::config::SchedulerConfig> for SchedulerConfig {
+    fn from(config: &crate::config::SchedulerConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_workers: if config.worker_threads == 0 {
+                defaults.max_workers
+            } else {
+                config.worker_threads
+            },
+            affinity: config.cpu_affinity,
+            steal_policy: config.steal_policy,
+            max_blocking_threads: if config.blocking_threads == 0 {
+                defaults.max_blocking_threads
+            } else {
+                config.blocking_threads
+            },
         }
     }
 }
@@ -29,12 +79,33 @@ impl Default for SchedulerConfig {
 #[derive(Debug)]
 struct SchedulerCore {
     injector: Injector<Task>,
-    _stealers: Arc<Vec<Stealer<Task>>>,
+    stealers: Arc<Vec<Stealer<Task>>>,
     metrics: Arc<TaskRuntimeMetrics>,
     shutdown: AtomicBool,
     timer_wheel: Arc<TimerWheel>,
     worker_count: AtomicUsize,
-    _config: SchedulerConfig,
+    /// Helper threads started by preemption points that are still draining
+    helpers: AtomicUsize,
+    blocking: BlockingPool,
+    config: SchedulerConfig,
+}
+
+/// Threads for [`TaskKind::Blocking`] tasks. Threads start when a task arrives
+/// and none is idle, up to `max_blocking_threads`, and exit after
+/// [`BLOCKING_KEEP_ALIVE`] without work.
+#[derive(Debug, Default)]
+struct BlockingPool {
+    queue: Injector<Task>,
+    state: Mutex<BlockingPoolState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct BlockingPoolState {
+    threads: usize,
+    idle: usize,
+    /// Idle threads already woken for a queued task
+    notified: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -60,12 +131,14 @@ impl TaskScheduler {
 
         let core = Arc::new(SchedulerCore {
             injector,
-            _stealers: Arc::clone(&stealers),
+            stealers: Arc::clone(&stealers),
             metrics: Arc::clone(&metrics),
             shutdown: AtomicBool::new(false),
             timer_wheel: Arc::clone(&timer_wheel),
             worker_count: AtomicUsize::new(config.max_workers),
-            _config: config,
+            helpers: AtomicUsize::new(0),
+            blocking: BlockingPool::default(),
+            config,
         });
 
         metrics.set_total_workers(config.max_workers);
@@ -85,12 +158,23 @@ impl TaskScheduler {
             .spawn(move || timer_processor_loop(timer_core))
             .expect("failed to spawn timer processor");
 
+        let topology = Topology::detect();
         for (index, worker) in workers.into_iter().enumerate() {
             let core = Arc::clone(&core);
-            let stealers = Arc::clone(&stealers);
+            let victims: Vec<Stealer<Task>> = topology
+                .steal_order(index, config.max_workers, config.steal_policy)
+                .into_iter()
+                .map(|victim| stealers[victim].clone())
+                .collect();
+            let placement = topology.placement(index, config.affinity, config.steal_policy);
             thread::Builder::new()
                 .name(format!("otter-task-worker-{}", index))
-                .spawn(move || worker_loop(core, stealers, worker, index))
+                .spawn(move || {
+                    if let Some(cpus) = placement {
+                        pin_current_thread(&cpus);
+                    }
+                    worker_loop(core, victims, worker, index);
+                })
                 .expect("failed to spawn task worker");
         }
 
@@ -109,14 +193,66 @@ impl TaskScheduler {
     where
         F: FnOnce() + Send + 'static,
     {
-        let task = Task::new(name, Box::new(func) as TaskFn);
+        self.spawn_with_kind(TaskKind::Async, name, func)
+    }
+
+    /// Runs `func` on the blocking pool, leaving the task workers free
+    pub fn spawn_blocking_fn<F>(&self, name: Option<String>, func: F) -> JoinHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.spawn_with_kind(TaskKind::Blocking, name, func)
+    }
+
+    pub fn spawn_with_kind<F>(&self, kind: TaskKind, name: Option<String>, func: F) -> JoinHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let parent = replay::current_task();
+        replay::wait_turn(&ScheduleEvent::Spawn { parent, child: 0 });
+        let task = Task::new(name, Box::new(func) as TaskFn).with_kind(kind);
+        replay::record(ScheduleEvent::Spawn {
+            parent,
+            child: task.id().raw(),
+        });
+        monitor::task_spawned(task.id().raw(), task.name().map(str::to_string), kind);
+        inherit_task_locals(TaskId::new(parent), task.id());
         let cancellation_token = task.cancellation_token().clone();
         let join = JoinHandle::new(task.id(), task.join_state(), cancellation_token);
         self.core.metrics.record_spawn();
-        self.core.injector.push(task);
+        match kind {
+            TaskKind::Async => self.core.injector.push(task),
+            TaskKind::Blocking => submit_blocking(&self.core, task),
+        }
         join
     }
 
+    /// Called at loop back-edges of programs built with `--preempt-loops`.
+    ///
+    /// Workers cannot suspend a running task, so once a task has run for a
+    /// full time slice while other tasks wait, this starts a helper thread that
+    /// runs the queued tasks and exits when the queues are empty.
+    pub fn preempt_point(&self) {
+        let checked = PREEMPT_COUNTER.with(|counter| {
+            let next = counter.get().wrapping_add(1);
+            counter.set(next);
+            next % PREEMPT_CHECK_INTERVAL == 0
+        });
+        if !checked {
+            return;
+        }
+        let expired = SLICE_START.with(|start| match start.get() {
+            Some(started) if started.elapsed() >= PREEMPT_SLICE => {
+                start.set(Some(Instant::now()));
+                true
+            }
+            _ => false,
+        });
+        if expired && monitor::has_queued_tasks() {
+            spawn_helper(&self.core);
+        }
+    }
+
     pub fn get_worker_count(&self) -> usize {
         self.core.worker_count.load(Ordering::Relaxed)
     }
@@ -129,23 +265,13 @@ impl TaskScheduler {
     }
 }
 
+/// `stealers` lists the other workers in the order this worker steals from them
 fn worker_loop(
     core: Arc<SchedulerCore>,
-    stealers: Arc<Vec<Stealer<Task>>>,
+    stealers: Vec<Stealer<Task>>,
     local: Worker<Task>,
     index: usize,
 ) {
-    let stealers: Vec<_> = stealers
-        .iter()
-        .enumerate()
-        .filter_map(|(i, stealer)| {
-            if i != index {
-                Some(stealer.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
     let backoff = Backoff::new();
     let mut consecutive_idle = 0;
 
@@ -161,17 +287,7 @@ fn worker_loop(
         if let Some(task) = local.pop() {
             backoff.reset();
             consecutive_idle = 0;
-            // Skip cancelled tasks
-            let task_id = task.id();
-            if task.is_cancelled() {
-                core.metrics.record_completion();
-                cleanup_task_local_storage(task_id);
-                continue;
-            }
-            task.run();
-            core.metrics.record_completion();
-            core.metrics.record_worker_task(index);
-            cleanup_task_local_storage(task_id);
+            run_task(&core, task, index);
             continue;
         }
 
@@ -179,17 +295,7 @@ fn worker_loop(
             Steal::Success(task) => {
                 backoff.reset();
                 consecutive_idle = 0;
-                // Skip cancelled tasks
-                let task_id = task.id();
-                if task.is_cancelled() {
-                    core.metrics.record_completion();
-                    cleanup_task_local_storage(task_id);
-                    continue;
-                }
-                task.run();
-                core.metrics.record_completion();
-                core.metrics.record_worker_task(index);
-                cleanup_task_local_storage(task_id);
+                run_task(&core, task, index);
                 continue;
             }
             Steal::Retry => {
@@ -217,17 +323,7 @@ fn worker_loop(
         if let Some(task) = stolen {
             backoff.reset();
             consecutive_idle = 0;
-            // Skip cancelled tasks
-            let task_id = task.id();
-            if task.is_cancelled() {
-                core.metrics.record_completion();
-                cleanup_task_local_storage(task_id);
-                continue;
-            }
-            task.run();
-            core.metrics.record_completion();
-            core.metrics.record_worker_task(index);
-            cleanup_task_local_storage(task_id);
+            run_task(&core, task, index);
             continue;
         }
 
@@ -253,6 +349,135 @@ fn worker_loop(
     }
 }
 
+fn run_task(core: &SchedulerCore, task: Task, index: usize) {
+    // Skip cancelled tasks
+    let task_id = task.id();
+    if task.is_cancelled() {
+        core.metrics.record_completion();
+        monitor::task_finished(task_id.raw());
+        cleanup_task_local_storage(task_id);
+        return;
+    }
+    let run = ScheduleEvent::Run {
+        task: task_id.raw(),
+    };
+    replay::wait_turn(&run);
+    replay::record(run);
+    replay::set_current_task(task_id.raw());
+    monitor::task_started(task_id.raw());
+    // Blocking tasks have their own threads, so they never need preempting
+    if task.kind() == TaskKind::Async {
+        SLICE_START.with(|start| start.set(Some(Instant::now())));
+    }
+    let started = Instant::now();
+    task.run();
+    let busy = started.elapsed();
+    SLICE_START.with(|start| start.set(None));
+    monitor::task_finished(task_id.raw());
+    replay::set_current_task(0);
+    core.metrics.record_completion();
+    core.metrics.record_worker_task(index, busy);
+    cleanup_task_local_storage(task_id);
+}
+
+/// Starts a helper thread unless there are already as many helpers as workers
+fn spawn_helper(core: &Arc<SchedulerCore>) {
+    if core.helpers.fetch_add(1, Ordering::SeqCst) >= core.config.max_workers {
+        core.helpers.fetch_sub(1, Ordering::SeqCst);
+        return;
+    }
+    let helper_core = Arc::clone(core);
+    let spawned = thread::Builder::new()
+        .name("otter-task-helper".into())
+        .spawn(move || {
+            helper_loop(&helper_core);
+            helper_core.helpers.fetch_sub(1, Ordering::SeqCst);
+        });
+    if spawned.is_err() {
+        core.helpers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Runs queued and stealable tasks until there are none left
+fn helper_loop(core: &SchedulerCore) {
+    let local = Worker::new_fifo();
+    while !core.shutdown.load(Ordering::SeqCst) {
+        let task = local.pop().or_else(|| {
+            std::iter::repeat_with(|| {
+                core.injector
+                    .steal_batch_and_pop(&local)
+                    .or_else(|| core.stealers.iter().map(Stealer::steal).collect())
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(Steal::success)
+        });
+        match task {
+            // Helpers have no worker slot in the metrics
+            Some(task) => run_task(core, task, usize::MAX),
+            None => break,
+        }
+    }
+}
+
+/// Queues a blocking task, waking an idle pool thread or starting a new one
+fn submit_blocking(core: &Arc<SchedulerCore>, task: Task) {
+    let pool = &core.blocking;
+    pool.queue.push(task);
+    let mut state = pool.state.lock();
+    if state.idle > state.notified {
+        state.notified += 1;
+        pool.available.notify_one();
+        return;
+    }
+    if state.threads >= core.config.max_blocking_threads {
+        // Every thread is busy; the task runs when one finishes
+        return;
+    }
+    state.threads += 1;
+    drop(state);
+    let pool_core = Arc::clone(core);
+    let spawned = thread::Builder::new()
+        .name("otter-blocking".into())
+        .spawn(move || blocking_loop(&pool_core));
+    if spawned.is_err() {
+        core.blocking.state.lock().threads -= 1;
+    }
+}
+
+fn blocking_loop(core: &SchedulerCore) {
+    mark_blocking_pool_thread();
+    let pool = &core.blocking;
+    loop {
+        match pool.queue.steal() {
+            // Blocking pool threads have no worker slot in the metrics
+            Steal::Success(task) => {
+                run_task(core, task, usize::MAX);
+                continue;
+            }
+            Steal::Retry => continue,
+            Steal::Empty => {}
+        }
+
+        let mut state = pool.state.lock();
+        // A task may have been queued while this thread was not yet idle
+        if !pool.queue.is_empty() {
+            continue;
+        }
+        state.idle += 1;
+        let timed_out = pool
+            .available
+            .wait_for(&mut state, BLOCKING_KEEP_ALIVE)
+            .timed_out();
+        state.idle -= 1;
+        if state.notified > 0 {
+            state.notified -= 1;
+        } else if timed_out && pool.queue.is_empty() {
+            state.threads -= 1;
+            return;
+        }
+    }
+}
+
 fn autoscaler_loop(core: Arc<SchedulerCore>) {
     loop {
         if core.shutdown.load(Ordering::SeqCst) {
@@ -299,3 +524,49 @@ fn timer_processor_loop(core: Arc<SchedulerCore>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking_tasks_run_side_by_side_off_the_workers() {
+        let scheduler = TaskScheduler::new(SchedulerConfig {
+            max_workers: 1,
+            max_blocking_threads: 3,
+            ..SchedulerConfig::default()
+        });
+        let arrived = Arc::new(AtomicUsize::new(0));
+        let threads = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<JoinHandle> = (0..3)
+            .map(|_| {
+                let arrived = Arc::clone(&arrived);
+                let threads = Arc::clone(&threads);
+                scheduler.spawn_blocking_fn(None, move || {
+                    threads
+                        .lock()
+                        .push(thread::current().name().map(str::to_string));
+                    arrived.fetch_add(1, Ordering::SeqCst);
+                    // Only returns early if all three tasks are blocked here at once
+                    let deadline = Instant::now() + Duration::from_secs(5);
+                    while arrived.load(Ordering::SeqCst) < 3 && Instant::now() < deadline {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                })
+            })
+            .collect();
+        let started = Instant::now();
+        for handle in &handles {
+            handle.join();
+        }
+        assert_eq!(arrived.load(Ordering::SeqCst), 3);
+        assert!(
+            threads
+                .lock()
+                .iter()
+                .all(|name| name.as_deref() == Some("otter-blocking"))
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(scheduler.core.blocking.state.lock().threads <= 3);
+    }
+}
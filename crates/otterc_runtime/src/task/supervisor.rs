@@ -0,0 +1,466 @@
+//! Supervision trees for long-lived tasks
+//!
+//! A [`Supervisor`] runs child tasks and starts them again when they stop.
+//! Restarts back off exponentially, from the policy's `initial_backoff` up to
+//! `max_backoff`. When a child needs more than `max_restarts` restarts within
+//! `window`, the supervisor gives up: it stops its other children and reports
+//! the failure to its parent supervisor, which restarts it like any other
+//! child. A supervisor without a parent prints the failure and finishes.
+
+use std::collections::VecDeque;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use super::scheduler::TaskScheduler;
+use super::task_impl::{JoinHandle, JoinState, panic_message};
+
+/// When a supervisor restarts a child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    /// Whenever it stops, for services that should always be running
+    Permanent,
+    /// Only when it panics
+    Transient,
+}
+
+/// How often and how quickly a supervisor restarts its children
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    /// Most restarts allowed within `window` before the supervisor gives up
+    pub max_restarts: u32,
+    pub window: Duration,
+    /// Delay before a restart, doubled for each restart already in the window
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            window: Duration::from_secs(5),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before the next restart when `recent` restarts happened within
+    /// the window
+    pub fn backoff(&self, recent: u32) -> Duration {
+        let factor = 1u32.checked_shl(recent).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+type ChildFn = Arc<dyn Fn() + Send + Sync>;
+
+enum ChildKind {
+    Task(ChildFn),
+    Supervisor(Supervisor),
+}
+
+struct Child {
+    name: String,
+    restart: Restart,
+    kind: ChildKind,
+    /// Bumped on every start so that exits of replaced runs are ignored
+    generation: u64,
+    handle: Option<JoinHandle>,
+    finished: bool,
+}
+
+#[derive(Clone)]
+pub struct Supervisor {
+    inner: Arc<SupervisorInner>,
+}
+
+struct SupervisorInner {
+    name: String,
+    scheduler: TaskScheduler,
+    state: Mutex<SupervisorState>,
+    done: Arc<JoinState>,
+}
+
+struct SupervisorState {
+    policy: RestartPolicy,
+    children: Vec<Child>,
+    /// When the restarts within the policy window happened
+    recent_restarts: VecDeque<Instant>,
+    total_restarts: u64,
+    stopped: bool,
+    /// The supervisor it reports to, and its index among that one's children
+    parent: Option<(Weak<SupervisorInner>, usize)>,
+}
+
+impl std::fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("Supervisor")
+            .field("name", &self.inner.name)
+            .field("children", &state.children.len())
+            .field("restarts", &state.total_restarts)
+            .field("stopped", &state.stopped)
+            .finish()
+    }
+}
+
+impl Supervisor {
+    pub fn new(name: impl Into<String>, policy: RestartPolicy, scheduler: TaskScheduler) -> Self {
+        Self {
+            inner: Arc::new(SupervisorInner {
+                name: name.into(),
+                scheduler,
+                state: Mutex::new(SupervisorState {
+                    policy,
+                    children: Vec::new(),
+                    recent_restarts: VecDeque::new(),
+                    total_restarts: 0,
+                    stopped: false,
+                    parent: None,
+                }),
+                done: JoinState::new(),
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    pub fn policy(&self) -> RestartPolicy {
+        self.inner.state.lock().policy
+    }
+
+    pub fn set_policy(&self, policy: RestartPolicy) {
+        self.inner.state.lock().policy = policy;
+    }
+
+    /// Starts `func` as a child task, restarted according to `restart`
+    pub fn supervise<F>(&self, name: impl Into<String>, restart: Restart, func: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let index = self.add_child(name.into(), restart, ChildKind::Task(Arc::new(func)));
+        self.start_child(index, None);
+    }
+
+    /// Makes `child` report to this supervisor, which restarts it with all of
+    /// its children when it gives up
+    pub fn supervise_supervisor(&self, child: &Supervisor) {
+        let index = self.add_child(
+            child.name().to_string(),
+            Restart::Transient,
+            ChildKind::Supervisor(child.clone()),
+        );
+        child.inner.state.lock().parent = Some((Arc::downgrade(&self.inner), index));
+    }
+
+    /// Restarts made so far, over the supervisor's whole life
+    pub fn restarts(&self) -> u64 {
+        self.inner.state.lock().total_restarts
+    }
+
+    /// Why the supervisor gave up, if it did
+    pub fn failure(&self) -> Option<String> {
+        self.inner.done.failure()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.inner.done.is_complete()
+    }
+
+    /// Blocks until the supervisor finishes: every child stopped for good,
+    /// the supervisor was stopped, or it gave up
+    pub fn wait(&self) {
+        self.inner.done.wait_blocking();
+    }
+
+    /// Stops restarting children and asks the running ones to cancel
+    pub fn stop(&self) {
+        let nested = self.halt();
+        for child in nested {
+            child.stop();
+        }
+        self.inner.done.mark_complete();
+    }
+
+    fn add_child(&self, name: String, restart: Restart, kind: ChildKind) -> usize {
+        let mut state = self.inner.state.lock();
+        state.children.push(Child {
+            name,
+            restart,
+            kind,
+            generation: 0,
+            handle: None,
+            finished: false,
+        });
+        state.children.len() - 1
+    }
+
+    /// Marks the supervisor stopped and cancels its child tasks, returning
+    /// its child supervisors
+    fn halt(&self) -> Vec<Supervisor> {
+        let mut state = self.inner.state.lock();
+        state.stopped = true;
+        let mut nested = Vec::new();
+        for child in &mut state.children {
+            child.generation += 1;
+            match &child.kind {
+                ChildKind::Task(_) => {
+                    if let Some(handle) = child.handle.take() {
+                        handle.cancel();
+                    }
+                }
+                ChildKind::Supervisor(supervisor) => nested.push(supervisor.clone()),
+            }
+        }
+        nested
+    }
+
+    /// Starts child `index`, unless a newer run replaced the one `expected`
+    /// names or the supervisor stopped
+    fn start_child(&self, index: usize, expected: Option<u64>) {
+        let (kind, generation, task_name) = {
+            let mut state = self.inner.state.lock();
+            if state.stopped {
+                return;
+            }
+            let child = &mut state.children[index];
+            if expected.is_some_and(|generation| generation != child.generation) {
+                return;
+            }
+            child.generation += 1;
+            child.finished = false;
+            let kind = match &child.kind {
+                ChildKind::Task(func) => ChildKind::Task(Arc::clone(func)),
+                ChildKind::Supervisor(supervisor) => ChildKind::Supervisor(supervisor.clone()),
+            };
+            let task_name = format!("{}/{}", self.inner.name, child.name);
+            (kind, child.generation, task_name)
+        };
+
+        match kind {
+            ChildKind::Task(func) => {
+                let supervisor = Arc::downgrade(&self.inner);
+                let handle = self.inner.scheduler.spawn_fn(Some(task_name), move || {
+                    let outcome = catch_unwind(AssertUnwindSafe(|| func()))
+                        .map_err(|payload| panic_message(payload.as_ref()));
+                    if let Some(inner) = supervisor.upgrade() {
+                        Supervisor { inner }.child_exited(index, Some(generation), outcome);
+                    }
+                });
+                let mut state = self.inner.state.lock();
+                let child = &mut state.children[index];
+                if child.generation == generation && !child.finished {
+                    child.handle = Some(handle);
+                }
+            }
+            ChildKind::Supervisor(supervisor) => supervisor.restart_all(),
+        }
+    }
+
+    /// Starts every child again, used when the parent restarts this supervisor
+    fn restart_all(&self) {
+        let count = {
+            let mut state = self.inner.state.lock();
+            state.stopped = false;
+            state.recent_restarts.clear();
+            state.children.len()
+        };
+        for index in 0..count {
+            self.start_child(index, None);
+        }
+    }
+
+    /// Handles the end of a run of child `index`. `generation` is the run's
+    /// generation, or `None` for a child supervisor's current run.
+    fn child_exited(&self, index: usize, generation: Option<u64>, outcome: Result<(), String>) {
+        let mut state = self.inner.state.lock();
+        let child = &state.children[index];
+        if state.stopped || generation.is_some_and(|generation| generation != child.generation) {
+            return;
+        }
+        let generation = child.generation;
+        let restart = match (child.restart, &outcome) {
+            (Restart::Permanent, _) | (Restart::Transient, Err(_)) => true,
+            (Restart::Transient, Ok(())) => false,
+        };
+        if !restart {
+            state.children[index].finished = true;
+            let all_finished = state.children.iter().all(|child| child.finished);
+            drop(state);
+            if all_finished {
+                self.finish(Ok(()));
+            }
+            return;
+        }
+
+        let now = Instant::now();
+        let policy = state.policy;
+        while state
+            .recent_restarts
+            .front()
+            .is_some_and(|started| now.duration_since(*started) > policy.window)
+        {
+            state.recent_restarts.pop_front();
+        }
+        if state.recent_restarts.len() >= policy.max_restarts as usize {
+            let how = match &outcome {
+                Ok(()) => "stopped".to_string(),
+                Err(message) => format!("failed ({message})"),
+            };
+            let reason = format!(
+                "supervisor '{}' gave up: child '{}' {} after {} restarts within {:.1}s",
+                self.inner.name,
+                state.children[index].name,
+                how,
+                policy.max_restarts,
+                policy.window.as_secs_f64()
+            );
+            drop(state);
+            let nested = self.halt();
+            for child in nested {
+                child.stop();
+            }
+            self.finish(Err(reason));
+            return;
+        }
+
+        let delay = policy.backoff(state.recent_restarts.len() as u32);
+        state.recent_restarts.push_back(now);
+        state.total_restarts += 1;
+        drop(state);
+
+        if delay.is_zero() {
+            self.start_child(index, Some(generation));
+        } else {
+            // Wait on the blocking pool so the backoff never holds a task worker
+            let supervisor = Arc::downgrade(&self.inner);
+            self.inner.scheduler.spawn_blocking_fn(None, move || {
+                std::thread::sleep(delay);
+                if let Some(inner) = supervisor.upgrade() {
+                    Supervisor { inner }.start_child(index, Some(generation));
+                }
+            });
+        }
+    }
+
+    /// Reports the supervisor's end to its parent, or finishes it when it
+    /// has none
+    fn finish(&self, outcome: Result<(), String>) {
+        let parent = self
+            .inner
+            .state
+            .lock()
+            .parent
+            .as_ref()
+            .and_then(|(parent, index)| Some((parent.upgrade()?, *index)));
+        match (parent, outcome) {
+            (Some((inner, index)), outcome) => {
+                if outcome.is_ok() {
+                    self.inner.done.mark_complete();
+                }
+                Supervisor { inner }.child_exited(index, None, outcome);
+            }
+            (None, Ok(())) => self.inner.done.mark_complete(),
+            (None, Err(reason)) => {
+                report(&reason);
+                self.inner.done.mark_failed(reason);
+            }
+        }
+    }
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "A supervisor without a parent reports giving up on stderr"
+)]
+fn report(reason: &str) {
+    eprintln!("error: {reason}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::SchedulerConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn quick_policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            window: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(10));
+        assert_eq!(policy.backoff(3), Duration::from_millis(80));
+        assert_eq!(policy.backoff(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn failing_child_is_restarted_until_the_limit() {
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let supervisor = Supervisor::new("root", quick_policy(2), scheduler);
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&runs);
+        supervisor.supervise("worker", Restart::Permanent, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        supervisor.wait();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(supervisor.restarts(), 2);
+        assert!(
+            supervisor
+                .failure()
+                .is_some_and(|reason| reason.contains("child 'worker' stopped"))
+        );
+    }
+
+    #[test]
+    fn transient_child_that_returns_is_not_restarted() {
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let supervisor = Supervisor::new("root", quick_policy(5), scheduler);
+        supervisor.supervise("once", Restart::Transient, || {});
+        supervisor.wait();
+
+        assert_eq!(supervisor.restarts(), 0);
+        assert!(supervisor.failure().is_none());
+    }
+
+    #[test]
+    fn child_supervisor_escalates_to_its_parent() {
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let parent = Supervisor::new("parent", quick_policy(1), scheduler.clone());
+        let child = Supervisor::new("child", quick_policy(0), scheduler);
+        parent.supervise_supervisor(&child);
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&runs);
+        child.supervise("worker", Restart::Permanent, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        parent.wait();
+
+        // The child gives up at once, the parent restarts it once, then gives up too
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(parent.restarts(), 1);
+        assert!(
+            parent
+                .failure()
+                .is_some_and(|reason| reason.contains("child 'child' failed"))
+        );
+        assert!(child.is_finished());
+    }
+}
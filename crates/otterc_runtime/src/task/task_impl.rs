@@ -1,4 +1,6 @@
 use parking_lot::{Condvar, Mutex};
+use std::any::Any;
+use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::Waker;
@@ -31,6 +33,19 @@ pub enum TaskState {
     Running,
     Completed,
     Cancelled,
+    /// The task panicked
+    Failed,
+}
+
+/// Which pool a task runs on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TaskKind {
+    /// Short-running work on the work-stealing task workers
+    #[default]
+    Async,
+    /// Work that may block or hog a CPU, run on the blocking pool so it does
+    /// not hold a task worker
+    Blocking,
 }
 
 /// Cancellation token shared between task and join handle.
@@ -72,6 +87,8 @@ pub struct JoinState {
 #[derive(Debug)]
 struct JoinInner {
     completed: bool,
+    /// Panic message of a task that failed
+    failure: Option<String>,
     waiters: Vec<Waker>,
 }
 
@@ -80,6 +97,7 @@ impl JoinState {
         Arc::new(Self {
             inner: Mutex::new(JoinInner {
                 completed: false,
+                failure: None,
                 waiters: Vec::new(),
             }),
             condvar: Condvar::new(),
@@ -98,10 +116,21 @@ impl JoinState {
         self.condvar.notify_all();
     }
 
+    /// Completes the task as failed with the panic `message`
+    pub fn mark_failed(&self, message: String) {
+        self.inner.lock().failure.get_or_insert(message);
+        self.mark_complete();
+    }
+
     pub fn is_complete(&self) -> bool {
         self.inner.lock().completed
     }
 
+    /// Panic message of the task, if it failed
+    pub fn failure(&self) -> Option<String> {
+        self.inner.lock().failure.clone()
+    }
+
     pub fn wait_blocking(&self) {
         let mut inner = self.inner.lock();
         while !inner.completed {
@@ -123,6 +152,7 @@ impl JoinState {
 pub struct Task {
     id: TaskId,
     name: Option<String>,
+    kind: TaskKind,
     state: TaskState,
     func: Option<TaskFn>,
     join: Arc<JoinState>,
@@ -134,6 +164,7 @@ impl Task {
         Self {
             id: next_task_id(),
             name,
+            kind: TaskKind::Async,
             state: TaskState::Ready,
             func: Some(func),
             join: JoinState::new(),
@@ -149,6 +180,15 @@ impl Task {
         self.name.as_deref()
     }
 
+    pub fn with_kind(mut self, kind: TaskKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn kind(&self) -> TaskKind {
+        self.kind
+    }
+
     pub fn state(&self) -> TaskState {
         self.state
     }
@@ -185,8 +225,13 @@ impl Task {
 
         // Run the function, but check for cancellation periodically
         // Note: For cooperative cancellation, tasks should check cancellation_token themselves
-        if let Some(func) = self.func.take() {
-            func();
+        // A panic fails this task instead of taking down the worker thread
+        if let Some(func) = self.func.take()
+            && let Err(payload) = catch_unwind(AssertUnwindSafe(func))
+        {
+            self.state = TaskState::Failed;
+            self.join.mark_failed(panic_message(payload.as_ref()));
+            return;
         }
 
         // Check if cancelled after running
@@ -226,7 +271,13 @@ impl JoinHandle {
         self.state.is_complete()
     }
 
+    /// Panic message of the task, if it failed
+    pub fn failure(&self) -> Option<String> {
+        self.state.failure()
+    }
+
     pub fn join(&self) {
+        let _blocked = super::monitor::block(super::monitor::TaskWait::Join(self.task_id.raw()));
         self.state.wait_blocking();
     }
 
@@ -243,6 +294,15 @@ impl JoinHandle {
     }
 }
 
+/// Text of a panic payload, which is usually a `&str` or a `String`
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic: unknown error".to_string())
+}
+
 pub struct JoinFuture {
     state: Arc<JoinState>,
 }
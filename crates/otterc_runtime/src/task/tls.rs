@@ -8,6 +8,7 @@ use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::replay::current_task;
 use super::task_impl::TaskId;
 
 /// Task-local storage container.
@@ -144,3 +145,110 @@ pub fn get_task_local_storage(task_id: TaskId) -> TaskLocalStorage {
 pub fn cleanup_task_local_storage(task_id: TaskId) {
     TASK_LOCAL_REGISTRY.remove(task_id);
 }
+
+// ============================================================================
+// String values (`tasklocal` module)
+// ============================================================================
+
+/// String values set through the `tasklocal` stdlib module, such as request IDs
+/// or tracing context. A spawned task starts with a copy of its parent's values.
+#[derive(Debug, Clone, Default)]
+struct TaskLocalValues(HashMap<String, String>);
+
+/// Storage of the task running on this thread. Code outside the scheduler's
+/// tasks, including `main`, shares the storage of task 0.
+fn current_storage() -> TaskLocalStorage {
+    get_task_local_storage(TaskId::new(current_task()))
+}
+
+/// Returns the current task's value for `key`
+pub fn task_local_get(key: &str) -> Option<String> {
+    let storage = current_storage();
+    let values = storage.get_ref::<TaskLocalValues>()?;
+    values.0.get(key).cloned()
+}
+
+/// Sets `key` for the current task and the tasks it spawns from now on,
+/// returning the previous value
+pub fn task_local_set(key: &str, value: &str) -> Option<String> {
+    let storage = current_storage();
+    if !storage.contains::<TaskLocalValues>() {
+        storage.set(TaskLocalValues::default());
+    }
+    let mut values = storage.get_ref::<TaskLocalValues>()?;
+    values.0.insert(key.to_string(), value.to_string())
+}
+
+/// Removes `key` from the current task, returning the previous value
+pub fn task_local_remove(key: &str) -> Option<String> {
+    let storage = current_storage();
+    let mut values = storage.get_ref::<TaskLocalValues>()?;
+    values.0.remove(key)
+}
+
+/// Runs `f` with `key` set to `value`, then restores the previous value
+pub fn with_task_local<R>(key: &str, value: &str, f: impl FnOnce() -> R) -> R {
+    let previous = task_local_set(key, value);
+    let result = f();
+    match previous {
+        Some(previous) => task_local_set(key, &previous),
+        None => task_local_remove(key),
+    };
+    result
+}
+
+/// Copies the values of `parent` into the storage of a newly spawned `child`
+pub(crate) fn inherit_task_locals(parent: TaskId, child: TaskId) {
+    let Some(storage) = TASK_LOCAL_REGISTRY.get(parent) else {
+        return;
+    };
+    let Some(values) = storage.get::<TaskLocalValues>() else {
+        return;
+    };
+    if !values.0.is_empty() {
+        TASK_LOCAL_REGISTRY.get_or_create(child).set(values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_values_are_restored() {
+        task_local_set("tls-test-scope", "outer");
+        let inner = with_task_local("tls-test-scope", "inner", || {
+            task_local_get("tls-test-scope")
+        });
+        assert_eq!(inner.as_deref(), Some("inner"));
+        assert_eq!(task_local_get("tls-test-scope").as_deref(), Some("outer"));
+
+        with_task_local("tls-test-unset", "value", || {});
+        assert_eq!(task_local_get("tls-test-unset"), None);
+        task_local_remove("tls-test-scope");
+    }
+
+    #[test]
+    fn spawned_tasks_copy_parent_values() {
+        let parent = TaskId::new(u64::MAX - 1);
+        let child = TaskId::new(u64::MAX - 2);
+        let mut values = TaskLocalValues::default();
+        values.0.insert("request_id".into(), "abc-123".into());
+        get_task_local_storage(parent).set(values);
+
+        inherit_task_locals(parent, child);
+        let copied = get_task_local_storage(child)
+            .get::<TaskLocalValues>()
+            .map(|values| values.0);
+        assert_eq!(
+            copied.and_then(|values| values.get("request_id").cloned()),
+            Some("abc-123".to_string())
+        );
+
+        // The child's copy is independent of later changes in the parent
+        get_task_local_storage(parent).clear();
+        assert!(get_task_local_storage(child).contains::<TaskLocalValues>());
+        cleanup_task_local_storage(parent);
+        cleanup_task_local_storage(child);
+    }
+}
@@ -0,0 +1,145 @@
+//! Panic reports for spawned tasks
+//!
+//! The native backtrace of a worker thread mostly shows the scheduler, and it
+//! says nothing about who started the task. When a task panics, the hook
+//! installed here prints the Otter functions on its stack, innermost first,
+//! followed by the chain of tasks that spawned it. Panics outside tasks go to
+//! the previous hook.
+
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::sync::Once;
+
+use super::monitor;
+use super::replay;
+use super::task_impl::panic_message;
+
+pub(crate) fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let task = replay::current_task();
+            if task == 0 {
+                previous(info);
+                return;
+            }
+            let trace = Backtrace::force_capture().to_string();
+            report(&panic_report(
+                &panic_message(info.payload()),
+                &otter_frames(&trace),
+                &monitor::spawn_chain(task),
+            ));
+        }));
+    });
+}
+
+#[expect(
+    clippy::print_stderr,
+    reason = "Otter panics abort the process, so the report must be printed from the hook"
+)]
+fn report(text: &str) {
+    eprint!("{text}");
+}
+
+/// `chain` starts with the panicking task and continues with its spawners
+fn panic_report(message: &str, frames: &[String], chain: &[String]) -> String {
+    let mut out = String::new();
+    let task = chain.first().map_or("task", String::as_str);
+    let _ = writeln!(out, "error: {task} panicked: {message}");
+    for frame in frames {
+        let _ = writeln!(out, "  at {frame}");
+    }
+    for spawner in chain.iter().skip(1) {
+        let _ = writeln!(out, "  spawned by {spawner}");
+    }
+    out
+}
+
+/// Otter functions in a rendered backtrace, innermost first, with their
+/// source locations when debug info has them
+pub(crate) fn otter_frames(trace: &str) -> Vec<String> {
+    let mut frames = Vec::new();
+    let mut lines = trace.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((index, symbol)) = line.trim_start().split_once(": ") else {
+            continue;
+        };
+        if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let location = lines
+            .next_if(|next| next.trim_start().starts_with("at "))
+            .map(|next| next.trim_start()["at ".len()..].to_string());
+        let Some(name) = otter_function(symbol.trim()) else {
+            continue;
+        };
+        frames.push(match location {
+            Some(location) => format!("{name} ({location})"),
+            None => name,
+        });
+    }
+    frames
+}
+
+/// The Otter name of `symbol`, or `None` for runtime, Rust and libc frames
+fn otter_function(symbol: &str) -> Option<String> {
+    // Codegen renames the program's `main`, leaving `main` to the C entry point
+    if symbol == "otter_entry" {
+        return Some("main".to_string());
+    }
+    let native = symbol.contains("::")
+        || symbol.starts_with(['_', '<'])
+        || symbol.starts_with("otter_")
+        || symbol.starts_with("rust_")
+        || matches!(
+            symbol,
+            "main" | "start_thread" | "clone" | "clone3" | "thread_start"
+        );
+    (!native).then(|| symbol.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: &str = "\
+   0: std::backtrace::Backtrace::force_capture
+             at /rustc/library/std/src/backtrace.rs:312:13
+   1: otter_builtin_panic
+             at crates/otterc_runtime/src/stdlib/builtins.rs:1066:9
+   2: parse_header
+   3: Request_handle
+             at server.ot:14:5
+   4: <F as core::ops::function::FnOnce<()>>::call_once
+   5: start_thread
+   6: clone3
+";
+
+    #[test]
+    fn keeps_only_otter_frames() {
+        assert_eq!(
+            otter_frames(TRACE),
+            ["parse_header", "Request_handle (server.ot:14:5)"]
+        );
+        assert_eq!(otter_function("otter_entry").as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn report_lists_frames_then_spawners() {
+        let report = panic_report(
+            "index out of range",
+            &["parse_header".to_string()],
+            &[
+                "task 2 running handle".to_string(),
+                "task 0 \"main\"".to_string(),
+            ],
+        );
+        assert_eq!(
+            report,
+            "error: task 2 running handle panicked: index out of range\n  \
+             at parse_header\n  \
+             spawned by task 0 \"main\"\n"
+        );
+    }
+}
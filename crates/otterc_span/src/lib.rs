@@ -85,3 +85,50 @@ impl From<Range<usize>> for Span {
         Self::new(range.start, range.end)
     }
 }
+
+/// Maps byte offsets in a source file to 1-based line and column numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLines {
+    /// The name used when rendering locations, usually the file path.
+    name: String,
+    /// Byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl SourceLines {
+    /// Indexes the line starts of `source`
+    #[must_use]
+    pub fn new(name: impl Into<String>, source: &str) -> Self {
+        let line_starts = core::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self {
+            name: name.into(),
+            line_starts,
+        }
+    }
+
+    /// The name used when rendering locations
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The 1-based line and column of `offset`
+    #[must_use]
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= offset)
+            .max(1);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+
+    /// Renders the start of `span` as `name:line:column`
+    #[must_use]
+    pub fn location(&self, span: Span) -> String {
+        let (line, column) = self.line_col(span.start());
+        format!("{}:{}:{}", self.name, line, column)
+    }
+}
@@ -0,0 +1,194 @@
+//! Replacing a function's implementation while the program runs
+//!
+//! Hot reload and the tiered JIT swap in new code for a function without
+//! stopping its callers. Calls go through a [`PatchSlot`]: [`PatchSlot::enter`]
+//! reads the current address and counts the call as in flight until the
+//! returned [`ActiveCall`] is dropped. [`PatchSlot::replace`] swaps the address
+//! atomically, so calls made afterwards run the new code straight away, then
+//! waits for the calls that may still be running the old code to return. Only
+//! after that is it safe to unload the old code.
+//!
+//! In-flight calls are counted per epoch. A patch stores the new address,
+//! starts a new epoch and waits for the count of the previous one to drain.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// How often a patch checks whether the old code is still running
+const QUIESCE_POLL: Duration = Duration::from_micros(50);
+
+/// The current implementation of a patchable function
+#[derive(Debug)]
+pub struct PatchSlot {
+    address: AtomicUsize,
+    epoch: AtomicUsize,
+    /// Calls in flight, indexed by the parity of the epoch they started in
+    in_flight: [AtomicUsize; 2],
+    /// Held while a patch waits, so each patch drains only its own epoch
+    patching: Mutex<()>,
+}
+
+impl PatchSlot {
+    pub fn new(address: usize) -> Self {
+        Self {
+            address: AtomicUsize::new(address),
+            epoch: AtomicUsize::new(0),
+            in_flight: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            patching: Mutex::new(()),
+        }
+    }
+
+    pub fn address(&self) -> usize {
+        self.address.load(Ordering::SeqCst)
+    }
+
+    /// Starts a call. The address of the returned guard stays valid until the
+    /// guard is dropped.
+    pub fn enter(self: &Arc<Self>) -> ActiveCall {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let parity = epoch % 2;
+            self.in_flight[parity].fetch_add(1, Ordering::SeqCst);
+            // A patch that started the next epoch in between may already be
+            // waiting on a different counter, so retry in the new epoch
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                return ActiveCall {
+                    slot: Arc::clone(self),
+                    parity,
+                    address: self.address.load(Ordering::SeqCst),
+                };
+            }
+            self.in_flight[parity].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Makes new calls use `address` and waits up to `timeout` for calls
+    /// still running the previous implementation. Returns the previous
+    /// address. On a timeout the new address stays installed, but the old
+    /// code must be kept loaded.
+    pub fn replace(&self, address: usize, timeout: Duration) -> Result<usize, PatchError> {
+        let _patching = self.patching.lock();
+        let previous = self.address.swap(address, Ordering::SeqCst);
+        let old_epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        let draining = &self.in_flight[old_epoch % 2];
+        let deadline = Instant::now() + timeout;
+        loop {
+            let in_flight = draining.load(Ordering::SeqCst);
+            if in_flight == 0 {
+                return Ok(previous);
+            }
+            if Instant::now() >= deadline {
+                return Err(PatchError::Timeout {
+                    previous,
+                    in_flight,
+                });
+            }
+            std::thread::sleep(QUIESCE_POLL);
+        }
+    }
+}
+
+/// A call in progress. The implementation it started with is not unloaded
+/// by a patch until it is dropped.
+#[derive(Debug)]
+pub struct ActiveCall {
+    slot: Arc<PatchSlot>,
+    parity: usize,
+    address: usize,
+}
+
+impl ActiveCall {
+    pub fn address(&self) -> usize {
+        self.address
+    }
+}
+
+impl Drop for ActiveCall {
+    fn drop(&mut self) {
+        self.slot.in_flight[self.parity].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// No patchable function has this name
+    UnknownFunction(String),
+    /// The replacement's signature differs from the registered one
+    SignatureMismatch {
+        name: String,
+        expected: String,
+        found: String,
+    },
+    NullAddress(String),
+    /// Calls into the previous implementation were still running when the
+    /// timeout elapsed. The patch is installed but `previous` must stay
+    /// loaded.
+    Timeout {
+        previous: usize,
+        in_flight: usize,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::UnknownFunction(name) => {
+                write!(f, "'{name}' is not registered as patchable")
+            }
+            PatchError::SignatureMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "cannot patch '{name}': expected signature {expected}, found {found}"
+            ),
+            PatchError::NullAddress(name) => write!(f, "cannot patch '{name}' with a null address"),
+            PatchError::Timeout {
+                previous,
+                in_flight,
+            } => write!(
+                f,
+                "{in_flight} call(s) into the previous implementation at {previous:#x} did not finish in time"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_wait_for_calls_into_the_old_code() {
+        let slot = Arc::new(PatchSlot::new(0x1000));
+        let call = slot.enter();
+        assert_eq!(call.address(), 0x1000);
+
+        assert_eq!(
+            slot.replace(0x2000, Duration::from_millis(5)),
+            Err(PatchError::Timeout {
+                previous: 0x1000,
+                in_flight: 1
+            })
+        );
+        // New calls see the new code even while old ones are running
+        let next = slot.enter();
+        assert_eq!(next.address(), 0x2000);
+        drop(call);
+
+        let finisher = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(next);
+        });
+        assert_eq!(slot.replace(0x3000, Duration::from_secs(5)), Ok(0x2000));
+        finisher.join().unwrap();
+        assert_eq!(slot.address(), 0x3000);
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 use abi_stable::StableAbi;
 use abi_stable::std_types::RVec;
@@ -7,6 +9,8 @@ use ahash::AHashMap;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 
+use crate::patch::{ActiveCall, PatchError, PatchSlot};
+
 #[repr(u8)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, StableAbi)]
 pub enum FfiType {
@@ -60,7 +64,7 @@ impl fmt::Display for FfiType {
 }
 
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FfiSignature {
     pub params: Vec<FfiType>,
     pub result: FfiType,
@@ -92,12 +96,102 @@ pub struct FfiFunction {
     pub signature: FfiSignature,
 }
 
+/// A Rust function Otter cannot call because part of its signature has no
+/// FFI mapping
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnmappedFunction {
+    pub name: String,
+    /// The signature as written in Rust, e.g. `fn geo::encode(Vec<u8>) -> String`
+    pub rust_signature: String,
+    /// The positions that cannot be mapped, e.g. "parameter 1 type `Vec<u8>`"
+    pub unmapped: Vec<String>,
+}
+
+/// Access a function needs beyond pure computation. Denied capabilities make
+/// the registry resolve the function to a stub that raises a permission error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Filesystem,
+    Network,
+    Process,
+    FfiPlugin,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::Filesystem,
+        Capability::Network,
+        Capability::Process,
+        Capability::FfiPlugin,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Filesystem => "filesystem",
+            Capability::Network => "network",
+            Capability::Process => "process",
+            Capability::FfiPlugin => "ffi plugin",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Stdlib functions that need a capability, by exact name or `namespace.*`
+const CAPABILITY_TABLE: &[(&str, Capability)] = &[
+    ("fs.*", Capability::Filesystem),
+    ("io.read", Capability::Filesystem),
+    ("io.write", Capability::Filesystem),
+    ("io.copy", Capability::Filesystem),
+    ("io.lines", Capability::Filesystem),
+    ("env.load_dotenv", Capability::Filesystem),
+    ("__otter_config_load", Capability::Filesystem),
+    ("net.*", Capability::Network),
+    ("http.*", Capability::Network),
+    ("std.http.*", Capability::Network),
+    ("env.*", Capability::Process),
+    ("sys.getenv", Capability::Process),
+    ("sys.exit", Capability::Process),
+];
+
+/// Stdlib functions without a capability that can still block the calling
+/// thread for a long time
+const BLOCKING_TABLE: &[&str] = &[
+    "std.io.read_line",
+    "time.sleep",
+    "sync.lock",
+    "sync.waitgroup_wait",
+];
+
+/// Runtime stub standing in for a denied function returning `result`. Every
+/// stub raises a permission error and returns an empty value of that type.
+fn denial_symbol(result: &FfiType) -> Option<&'static str> {
+    Some(match result {
+        FfiType::Unit => "otter_sandbox_denied_unit",
+        FfiType::Bool => "otter_sandbox_denied_bool",
+        FfiType::I32 => "otter_sandbox_denied_i32",
+        FfiType::I64 => "otter_sandbox_denied_i64",
+        FfiType::F64 => "otter_sandbox_denied_f64",
+        FfiType::Str => "otter_sandbox_denied_str",
+        FfiType::Opaque | FfiType::List | FfiType::Map => "otter_sandbox_denied_handle",
+        FfiType::Struct { .. } | FfiType::Tuple(_) => return None,
+    })
+}
+
 type ModuleRegistrar = fn(&SymbolRegistry);
 
 pub struct SymbolRegistry {
     functions: RwLock<AHashMap<String, FfiFunction>>,
     lazy_modules: RwLock<AHashMap<String, Vec<ModuleRegistrar>>>,
     active_modules: RwLock<HashSet<String>>,
+    plugin_functions: RwLock<HashSet<String>>,
+    unmapped: RwLock<AHashMap<String, UnmappedFunction>>,
+    denied: RwLock<HashSet<Capability>>,
+    patchable: RwLock<AHashMap<String, (FfiSignature, Arc<PatchSlot>)>>,
 }
 
 pub static GLOBAL_SYMBOL_REGISTRY: Lazy<SymbolRegistry> = Lazy::new(SymbolRegistry::default);
@@ -108,6 +202,10 @@ impl Default for SymbolRegistry {
             functions: RwLock::new(AHashMap::new()),
             lazy_modules: RwLock::new(AHashMap::new()),
             active_modules: RwLock::new(HashSet::new()),
+            plugin_functions: RwLock::new(HashSet::new()),
+            unmapped: RwLock::new(AHashMap::new()),
+            denied: RwLock::new(HashSet::new()),
+            patchable: RwLock::new(AHashMap::new()),
         }
     }
 }
@@ -127,6 +225,24 @@ impl SymbolRegistry {
             .insert(function.name.clone(), function);
     }
 
+    /// Registers a function exported by a dynamically loaded FFI plugin
+    pub fn register_plugin(&self, function: FfiFunction) {
+        self.plugin_functions.write().insert(function.name.clone());
+        self.register(function);
+    }
+
+    /// Records a Rust function that was left out of a bridge so calls to it
+    /// can be reported
+    pub fn register_unmapped(&self, function: UnmappedFunction) {
+        self.unmapped
+            .write()
+            .insert(function.name.clone(), function);
+    }
+
+    pub fn unmapped(&self, name: &str) -> Option<UnmappedFunction> {
+        self.unmapped.read().get(name).cloned()
+    }
+
     pub fn register_many<I>(&self, functions: I)
     where
         I: IntoIterator<Item = FfiFunction>,
@@ -182,11 +298,199 @@ impl SymbolRegistry {
         self.functions.read().contains_key(name)
     }
 
+    /// Looks up `name`. Functions needing a denied capability resolve to a
+    /// permission-error stub with the same signature, or to `None` when no stub
+    /// fits their return type.
     pub fn resolve(&self, name: &str) -> Option<FfiFunction> {
-        self.functions.read().get(name).cloned()
+        let mut function = self.functions.read().get(name).cloned()?;
+        if self.denied_capability(name).is_some() {
+            function.symbol = denial_symbol(&function.signature.result)?.to_string();
+        }
+        Some(function)
+    }
+
+    /// The capability `name` needs, if any
+    pub fn required_capability(&self, name: &str) -> Option<Capability> {
+        if self.plugin_functions.read().contains(name) {
+            return Some(Capability::FfiPlugin);
+        }
+        CAPABILITY_TABLE
+            .iter()
+            .find(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => *pattern == name,
+            })
+            .map(|(_, capability)| *capability)
+    }
+
+    /// Whether calling `name` can block the thread, e.g. on I/O or a lock
+    pub fn may_block(&self, name: &str) -> bool {
+        self.required_capability(name).is_some() || BLOCKING_TABLE.contains(&name)
+    }
+
+    /// The capability `name` needs if it has been denied
+    pub fn denied_capability(&self, name: &str) -> Option<Capability> {
+        self.required_capability(name)
+            .filter(|capability| self.is_denied(*capability))
+    }
+
+    pub fn deny(&self, capability: Capability) {
+        self.denied.write().insert(capability);
+    }
+
+    pub fn is_denied(&self, capability: Capability) -> bool {
+        self.denied.read().contains(&capability)
+    }
+
+    /// Denies every capability (`--sandbox`)
+    pub fn enable_sandbox(&self) {
+        self.denied.write().extend(Capability::ALL);
     }
 
     pub fn all(&self) -> Vec<FfiFunction> {
         self.functions.read().values().cloned().collect()
     }
+
+    /// Makes `name` callable through [`SymbolRegistry::enter`] so its
+    /// implementation can later be replaced with [`SymbolRegistry::patch`].
+    /// Registering again starts over with `address`.
+    pub fn register_patchable(
+        &self,
+        name: impl Into<String>,
+        signature: FfiSignature,
+        address: usize,
+    ) {
+        self.patchable
+            .write()
+            .insert(name.into(), (signature, Arc::new(PatchSlot::new(address))));
+    }
+
+    pub fn is_patchable(&self, name: &str) -> bool {
+        self.patchable.read().contains_key(name)
+    }
+
+    /// Starts a call to the current implementation of `name`
+    pub fn enter(&self, name: &str) -> Option<ActiveCall> {
+        let slot = Arc::clone(&self.patchable.read().get(name)?.1);
+        Some(slot.enter())
+    }
+
+    /// Atomically replaces the implementation of `name` with the code at
+    /// `address`, then waits up to `timeout` for calls into the old code to
+    /// return. Returns the old address, which may be unloaded once this
+    /// succeeds.
+    pub fn patch(
+        &self,
+        name: &str,
+        signature: &FfiSignature,
+        address: usize,
+        timeout: Duration,
+    ) -> Result<usize, PatchError> {
+        if address == 0 {
+            return Err(PatchError::NullAddress(name.to_string()));
+        }
+        let slot = {
+            let patchable = self.patchable.read();
+            let (expected, slot) = patchable
+                .get(name)
+                .ok_or_else(|| PatchError::UnknownFunction(name.to_string()))?;
+            if expected != signature {
+                return Err(PatchError::SignatureMismatch {
+                    name: name.to_string(),
+                    expected: expected.to_string(),
+                    found: signature.to_string(),
+                });
+            }
+            Arc::clone(slot)
+        };
+        // The map lock is released so other functions can be entered and
+        // patched while this one drains
+        slot.replace(address, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str, result: FfiType) -> FfiFunction {
+        FfiFunction {
+            name: name.into(),
+            symbol: format!("sym_{name}"),
+            signature: FfiSignature::new(vec![FfiType::Str], result),
+        }
+    }
+
+    #[test]
+    fn sandbox_swaps_denied_functions_for_stubs() {
+        let registry = SymbolRegistry::new();
+        registry.register(function("fs.exists", FfiType::Bool));
+        registry.register(function("io.read", FfiType::Str));
+        registry.register(function("io.eprintln", FfiType::Unit));
+        registry.register_plugin(function("serde:to_string", FfiType::Str));
+
+        assert_eq!(
+            registry.resolve("fs.exists").map(|f| f.symbol),
+            Some("sym_fs.exists".to_string())
+        );
+
+        registry.enable_sandbox();
+        assert_eq!(
+            registry.resolve("fs.exists").map(|f| f.symbol),
+            Some("otter_sandbox_denied_bool".to_string())
+        );
+        assert_eq!(
+            registry.denied_capability("serde:to_string"),
+            Some(Capability::FfiPlugin)
+        );
+        assert_eq!(
+            registry.resolve("io.read").map(|f| f.symbol),
+            Some("otter_sandbox_denied_str".to_string())
+        );
+        assert_eq!(
+            registry.resolve("io.eprintln").map(|f| f.symbol),
+            Some("sym_io.eprintln".to_string())
+        );
+    }
+
+    #[test]
+    fn flags_functions_that_may_block() {
+        let registry = SymbolRegistry::new();
+        registry.register_plugin(function("serde:to_string", FfiType::Str));
+        assert!(registry.may_block("net.dial"));
+        assert!(registry.may_block("time.sleep"));
+        assert!(registry.may_block("serde:to_string"));
+        assert!(!registry.may_block("io.eprintln"));
+        assert!(!registry.may_block("len"));
+    }
+
+    #[test]
+    fn patches_are_checked_against_the_registered_signature() {
+        let registry = SymbolRegistry::new();
+        let signature = FfiSignature::new(vec![FfiType::I64], FfiType::I64);
+        registry.register_patchable("fib", signature.clone(), 0x1000);
+
+        let wrong = FfiSignature::new(vec![FfiType::F64], FfiType::I64);
+        assert!(matches!(
+            registry.patch("fib", &wrong, 0x2000, Duration::ZERO),
+            Err(PatchError::SignatureMismatch { .. })
+        ));
+        assert_eq!(
+            registry.patch("fib", &signature, 0, Duration::ZERO),
+            Err(PatchError::NullAddress("fib".to_string()))
+        );
+        assert_eq!(
+            registry.patch("main", &signature, 0x2000, Duration::ZERO),
+            Err(PatchError::UnknownFunction("main".to_string()))
+        );
+
+        assert_eq!(
+            registry.patch("fib", &signature, 0x2000, Duration::ZERO),
+            Ok(0x1000)
+        );
+        assert_eq!(
+            registry.enter("fib").map(|call| call.address()),
+            Some(0x2000)
+        );
+    }
 }
@@ -0,0 +1,344 @@
+//! Compile-time validation of tagged string literals
+//!
+//! A string prefixed with a tag, like `sql"SELECT name FROM users"`, is
+//! handed to the [`LiteralValidator`] registered for that tag while type
+//! checking. Problems are reported at the literal; at run time the value is an
+//! ordinary `str`. `sql` is registered by default and embedders add their own
+//! tags with [`TypeChecker::with_literal_validator`].
+//!
+//! [`TypeChecker::with_literal_validator`]: crate::TypeChecker::with_literal_validator
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Checks the contents of literals with one tag
+pub trait LiteralValidator: Send + Sync {
+    /// Returns the first problem found in `value`
+    fn validate(&self, value: &str) -> Result<(), LiteralError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiteralError {
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl LiteralError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// The validators a type checker knows, by tag
+#[derive(Clone)]
+pub struct LiteralValidators {
+    validators: HashMap<String, Arc<dyn LiteralValidator>>,
+}
+
+impl LiteralValidators {
+    /// No validators, so every tagged literal is rejected
+    pub fn empty() -> Self {
+        Self {
+            validators: HashMap::new(),
+        }
+    }
+
+    /// Registers `validator` for `tag`, replacing any earlier one
+    pub fn register(&mut self, tag: impl Into<String>, validator: Arc<dyn LiteralValidator>) {
+        self.validators.insert(tag.into(), validator);
+    }
+
+    pub fn get(&self, tag: &str) -> Option<&Arc<dyn LiteralValidator>> {
+        self.validators.get(tag)
+    }
+
+    /// The registered tags, sorted
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.validators.keys().map(String::as_str).collect();
+        tags.sort_unstable();
+        tags
+    }
+}
+
+impl Default for LiteralValidators {
+    fn default() -> Self {
+        let mut validators = Self::empty();
+        validators.register("sql", Arc::new(SqlValidator));
+        validators
+    }
+}
+
+/// Basic syntax checking for SQL statements: quoting and parentheses must
+/// balance, each statement must start with a known keyword and the clauses a
+/// statement cannot do without must be present. It does not know about any
+/// schema or dialect.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqlValidator;
+
+const SQL_STATEMENTS: &[&str] = &[
+    "SELECT",
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "WITH",
+    "CREATE",
+    "DROP",
+    "ALTER",
+    "REPLACE",
+    "VALUES",
+    "PRAGMA",
+    "EXPLAIN",
+    "BEGIN",
+    "COMMIT",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "RELEASE",
+];
+
+/// Keywords that may not directly follow a comma
+const SQL_CLAUSES: &[&str] = &[
+    "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "VALUES", "SET", "UNION",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum SqlToken {
+    /// A keyword or identifier, uppercased
+    Word(String),
+    Punct(char),
+    /// Strings, numbers, quoted identifiers and parameters
+    Operand,
+}
+
+impl LiteralValidator for SqlValidator {
+    fn validate(&self, value: &str) -> Result<(), LiteralError> {
+        let tokens = sql_tokens(value)?;
+        let statements: Vec<&[SqlToken]> = tokens
+            .split(|token| *token == SqlToken::Punct(';'))
+            .collect();
+        let last = statements.len() - 1;
+        for (index, statement) in statements.into_iter().enumerate() {
+            // A trailing semicolon leaves an empty last statement
+            if statement.is_empty() && index == last && index > 0 {
+                continue;
+            }
+            check_sql_statement(statement)?;
+        }
+        Ok(())
+    }
+}
+
+fn sql_tokens(sql: &str) -> Result<Vec<SqlToken>, LiteralError> {
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = sql.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        closed = true;
+                        break;
+                    }
+                    previous = c;
+                }
+                if !closed {
+                    return Err(LiteralError::new("unterminated `/*` comment"));
+                }
+            }
+            '\'' | '"' | '`' => {
+                let close = ch;
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == close {
+                        // A doubled quote stands for the quote itself
+                        if chars.peek() == Some(&close) {
+                            chars.next();
+                            continue;
+                        }
+                        closed = true;
+                        break;
+                    }
+                }
+                if !closed {
+                    let what = if ch == '\'' {
+                        "string"
+                    } else {
+                        "quoted identifier"
+                    };
+                    return Err(LiteralError::new(format!("unterminated {what}"))
+                        .with_help(format!("close it with {close}")));
+                }
+                tokens.push(SqlToken::Operand);
+            }
+            '(' => {
+                depth += 1;
+                tokens.push(SqlToken::Punct(ch));
+            }
+            ')' => {
+                if depth == 0 {
+                    return Err(LiteralError::new("unmatched `)`"));
+                }
+                depth -= 1;
+                tokens.push(SqlToken::Punct(ch));
+            }
+            c if c.is_ascii_digit() => {
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '.')
+                {
+                    chars.next();
+                }
+                tokens.push(SqlToken::Operand);
+            }
+            '?' | '$' | ':' | '@'
+                if ch == '?'
+                    || chars
+                        .peek()
+                        .is_some_and(|c| c.is_alphanumeric() || *c == '_') =>
+            {
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+                {
+                    chars.next();
+                }
+                tokens.push(SqlToken::Operand);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_uppercase().to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    word.extend(c.to_uppercase());
+                    chars.next();
+                }
+                tokens.push(SqlToken::Word(word));
+            }
+            c => tokens.push(SqlToken::Punct(c)),
+        }
+    }
+    if depth > 0 {
+        return Err(LiteralError::new("unclosed `(`"));
+    }
+    Ok(tokens)
+}
+
+fn check_sql_statement(tokens: &[SqlToken]) -> Result<(), LiteralError> {
+    let Some(SqlToken::Word(keyword)) = tokens.first() else {
+        return Err(match tokens.first() {
+            None => LiteralError::new("empty SQL statement"),
+            Some(_) => LiteralError::new("expected a SQL statement").with_help(
+                "statements start with a keyword such as SELECT, INSERT, UPDATE or DELETE",
+            ),
+        });
+    };
+    if !SQL_STATEMENTS.contains(&keyword.as_str()) {
+        return Err(
+            LiteralError::new(format!("`{keyword}` does not start a SQL statement")).with_help(
+                "statements start with a keyword such as SELECT, INSERT, UPDATE or DELETE",
+            ),
+        );
+    }
+
+    for pair in tokens.windows(2) {
+        if pair[0] != SqlToken::Punct(',') {
+            continue;
+        }
+        match &pair[1] {
+            SqlToken::Word(word) if SQL_CLAUSES.contains(&word.as_str()) => {
+                return Err(LiteralError::new(format!("unexpected `,` before {word}")));
+            }
+            SqlToken::Punct(')') => return Err(LiteralError::new("unexpected `,` before `)`")),
+            _ => {}
+        }
+    }
+    if tokens.last() == Some(&SqlToken::Punct(',')) {
+        return Err(LiteralError::new("SQL statement ends with `,`"));
+    }
+
+    let has = |word: &str| {
+        tokens
+            .iter()
+            .any(|token| matches!(token, SqlToken::Word(w) if w == word))
+    };
+    match keyword.as_str() {
+        "SELECT" => match tokens.get(1) {
+            None => Err(LiteralError::new("SELECT needs a list of columns")),
+            Some(SqlToken::Word(word)) if SQL_CLAUSES.contains(&word.as_str()) => Err(
+                LiteralError::new(format!("SELECT needs a list of columns before {word}")),
+            ),
+            Some(_) => Ok(()),
+        },
+        "INSERT" if !has("INTO") => Err(LiteralError::new("INSERT needs INTO")),
+        "UPDATE" if !has("SET") => Err(LiteralError::new("UPDATE needs SET")),
+        "DELETE" if !has("FROM") => Err(LiteralError::new("DELETE needs FROM")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(sql: &str) -> Result<(), String> {
+        SqlValidator.validate(sql).map_err(|err| err.message)
+    }
+
+    #[test]
+    fn accepts_common_statements() {
+        for sql in [
+            "SELECT id, name FROM users WHERE name = 'O''Brien' AND id > ?",
+            "insert into logs (level, message) values ($1, $2);",
+            "UPDATE users SET name = :name WHERE id = @id -- rename\n",
+            "DELETE FROM sessions WHERE expires < 1700000000; COMMIT /* done */",
+            "WITH recent AS (SELECT * FROM events LIMIT 10) SELECT count(*) FROM recent",
+        ] {
+            assert_eq!(check(sql), Ok(()), "{sql}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_statements() {
+        assert_eq!(
+            check("SELECT name FROM users WHERE name = 'bob"),
+            Err("unterminated string".to_string())
+        );
+        assert_eq!(
+            check("SELECT count(id FROM users"),
+            Err("unclosed `(`".to_string())
+        );
+        assert_eq!(
+            check("SELECT id, FROM users"),
+            Err("unexpected `,` before FROM".to_string())
+        );
+        assert_eq!(
+            check("SELEC id FROM users"),
+            Err("`SELEC` does not start a SQL statement".to_string())
+        );
+        assert_eq!(
+            check("UPDATE users name = 'x'"),
+            Err("UPDATE needs SET".to_string())
+        );
+        assert_eq!(check("  "), Err("empty SQL statement".to_string()));
+    }
+}
@@ -1,20 +1,37 @@
 use anyhow::{Result, bail};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::checked_literals::{LiteralValidator, LiteralValidators};
+use crate::consts::{CONST_DIVISION_BY_ZERO, CONST_OVERFLOW, ConstValue, eval_const_expr};
+use crate::incremental::FunctionCache;
+use crate::params::{CallSites, annotation_for};
+use crate::unreachable::{loops_forever, unreachable_statements};
+use crate::unused::unused_bindings;
 
 use crate::types::{
-    EnumDefinition, EnumLayout, StructDefinition, TypeContext, TypeError, TypeInfo,
+    BUILTIN_BOUNDS, EnumDefinition, EnumLayout, Fallible, ProtocolDefinition, StructDefinition,
+    TypeContext, TypeError, TypeInfo,
 };
 use otterc_ast::nodes::{
-    BinaryOp, Block, Expr, FStringPart, Function, Literal, Node, Pattern, Program, Statement, Type,
-    UnaryOp, UseImport,
+    BinaryOp, Block, Expr, FStringPart, Function, GenericBound, ImportItem, Literal, MatchArm,
+    Node, NumberLiteral, NumberSuffix, Param, Pattern, Program, Statement, Type, UnaryOp,
+    UseImport, with_exit_method,
 };
 use otterc_config::LanguageFeatureFlags;
 use otterc_span::Span;
-use otterc_symbol::registry::{FfiType, SymbolRegistry};
+use otterc_symbol::registry::{FfiType, SymbolRegistry, UnmappedFunction};
+use otterc_utils::format_spec::{FormatArg, FormatSpec};
+
+/// Reported for an unsuffixed integer literal that is not an i64, unless it is
+/// used where a u64 is expected
+const INT_LITERAL_OUT_OF_RANGE: &str = "integer literal out of range for i64";
 
 /// Type checker that validates and infers types in OtterLang programs
 pub struct TypeChecker {
     errors: Vec<TypeError>,
+    /// Problems that do not stop compilation, such as unused variables
+    warnings: Vec<TypeError>,
     context: TypeContext,
     registry: Option<&'static SymbolRegistry>,
     expr_types: HashMap<usize, TypeInfo>,
@@ -26,6 +43,19 @@ pub struct TypeChecker {
     features: LanguageFeatureFlags,
     /// Current function's return type (if inside a function)
     current_function_return_type: Option<TypeInfo>,
+    /// Labels of the enclosing loops, innermost last (`None` for unlabeled loops)
+    loop_labels: Vec<Option<String>>,
+    /// Number literal being negated, which may reach the signed minimum
+    negated_literal: Option<Span>,
+    /// Values of the module's constants and of imported ones (`module.NAME`)
+    const_values: HashMap<String, ConstValue>,
+    /// Validators for tagged string literals such as `sql"..."`
+    literal_validators: LiteralValidators,
+    /// Argument types of direct calls, recorded while inferring parameter
+    /// types
+    call_sites: Option<CallSites>,
+    /// Bounds of the generic parameters of structs and enums, by type name
+    generic_bounds: HashMap<String, Vec<GenericBound>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -33,9 +63,12 @@ pub struct ModuleExports {
     pub module: String,
     pub functions: HashMap<String, TypeInfo>,
     pub variables: HashMap<String, TypeInfo>,
+    /// Values of public constants, whose types are also in `variables`
+    pub constants: HashMap<String, ConstValue>,
     pub structs: HashMap<String, StructDefinition>,
     pub enums: HashMap<String, EnumDefinition>,
     pub type_aliases: HashMap<String, TypeInfo>,
+    pub protocols: HashMap<String, ProtocolDefinition>,
 }
 
 impl ModuleExports {
@@ -52,6 +85,59 @@ impl ModuleExports {
             && self.structs.is_empty()
             && self.enums.is_empty()
             && self.type_aliases.is_empty()
+            && self.protocols.is_empty()
+    }
+
+    /// Adds what `pub use` re-exports from `source`: the single `item`
+    /// (renamed to `alias`), or everything `source` exports. Returns false
+    /// when `source` does not export `item`.
+    pub fn re_export(
+        &mut self,
+        source: &ModuleExports,
+        item: Option<&str>,
+        alias: Option<&str>,
+    ) -> bool {
+        let Some(item) = item else {
+            self.functions.extend(source.functions.clone());
+            self.variables.extend(source.variables.clone());
+            self.constants.extend(source.constants.clone());
+            self.structs.extend(source.structs.clone());
+            self.enums.extend(source.enums.clone());
+            self.type_aliases.extend(source.type_aliases.clone());
+            self.protocols.extend(source.protocols.clone());
+            return true;
+        };
+
+        let name = alias.unwrap_or(item).to_string();
+        let mut found = false;
+        if let Some(ty) = source.functions.get(item) {
+            self.functions.insert(name.clone(), ty.clone());
+            found = true;
+        }
+        if let Some(ty) = source.variables.get(item) {
+            self.variables.insert(name.clone(), ty.clone());
+            found = true;
+        }
+        if let Some(value) = source.constants.get(item) {
+            self.constants.insert(name.clone(), value.clone());
+        }
+        if let Some(def) = source.structs.get(item) {
+            self.structs.insert(item.to_string(), def.clone());
+            found = true;
+        }
+        if let Some(def) = source.enums.get(item) {
+            self.enums.insert(item.to_string(), def.clone());
+            found = true;
+        }
+        if let Some(def) = source.protocols.get(item) {
+            self.protocols.insert(item.to_string(), def.clone());
+            found = true;
+        }
+        if let Some(ty) = source.type_aliases.get(item) {
+            self.type_aliases.insert(name, ty.clone());
+            found = true;
+        }
+        found
     }
 }
 
@@ -84,13 +170,173 @@ impl TypeChecker {
                 }
                 self.collect_generic_usages(return_type, used);
             }
-            // Structs and Enums in TypeInfo are usually fully resolved or have their generics in args (if we added args to them)
-            // Current TypeInfo::Struct doesn't store args, which is a limitation if we want to track usage inside it.
-            // But usually `TypeInfo::Generic` covers the usage *of* a struct with generics.
-            // TypeInfo::Struct { .. } | TypeInfo::Enum { .. } => ..
+            // Instantiated generic structs and enums carry their arguments
+            TypeInfo::Struct { args, .. } | TypeInfo::Enum { args, .. } => {
+                for arg in args {
+                    self.collect_generic_usages(arg, used);
+                }
+            }
             _ => {}
         }
     }
+    /// What `cond` tells about the types of dynamically typed variables when
+    /// it is true and when it is false
+    fn narrowing_of(&self, cond: &Node<Expr>) -> Narrowing {
+        match cond.as_ref() {
+            Expr::Unary {
+                op: UnaryOp::Not,
+                expr,
+            } => {
+                let inner = self.narrowing_of(expr);
+                Narrowing {
+                    then: inner.otherwise,
+                    otherwise: inner.then,
+                }
+            }
+            Expr::Binary {
+                op: BinaryOp::And,
+                left,
+                right,
+            } => {
+                let mut then = self.narrowing_of(left).then;
+                then.extend(self.narrowing_of(right).then);
+                Narrowing {
+                    then,
+                    otherwise: Vec::new(),
+                }
+            }
+            Expr::Binary {
+                op: BinaryOp::Or,
+                left,
+                right,
+            } => {
+                let mut otherwise = self.narrowing_of(left).otherwise;
+                otherwise.extend(self.narrowing_of(right).otherwise);
+                Narrowing {
+                    then: Vec::new(),
+                    otherwise,
+                }
+            }
+            Expr::Binary { op, left, right } => {
+                let test = match op {
+                    BinaryOp::Eq | BinaryOp::Ne => {
+                        Self::type_of_test(left, right).or_else(|| Self::type_of_test(right, left))
+                    }
+                    BinaryOp::Is | BinaryOp::IsNot => {
+                        Self::none_test(left, right).or_else(|| Self::none_test(right, left))
+                    }
+                    _ => None,
+                };
+                let Some((name, ty)) = test else {
+                    return Narrowing::default();
+                };
+                let Some((matching, rest)) = self
+                    .context
+                    .get_variable(&name)
+                    .and_then(|current| Self::split_by_test(current, &ty))
+                else {
+                    return Narrowing::default();
+                };
+                let matching: Vec<_> = matching.map(|ty| (name.clone(), ty)).into_iter().collect();
+                let rest: Vec<_> = rest.map(|ty| (name, ty)).into_iter().collect();
+                if matches!(op, BinaryOp::Eq | BinaryOp::Is) {
+                    Narrowing {
+                        then: matching,
+                        otherwise: rest,
+                    }
+                } else {
+                    Narrowing {
+                        then: rest,
+                        otherwise: matching,
+                    }
+                }
+            }
+            _ => Narrowing::default(),
+        }
+    }
+
+    /// The types a variable of type `current` has when a `type_of` or `None`
+    /// test for `tested` holds and when it does not. A dynamic variable only
+    /// learns something when the test holds; a union is split between the
+    /// members the test picks and the rest.
+    fn split_by_test(
+        current: &TypeInfo,
+        tested: &TypeInfo,
+    ) -> Option<(Option<TypeInfo>, Option<TypeInfo>)> {
+        if Self::is_unknown_like(current) {
+            return Some((Some(tested.clone()), None));
+        }
+        let TypeInfo::Union(members) = current else {
+            return None;
+        };
+        let (matching, rest): (Vec<_>, Vec<_>) = members
+            .iter()
+            .cloned()
+            .partition(|member| type_of_matches(member, tested));
+        let narrowed =
+            |members: Vec<TypeInfo>| (!members.is_empty()).then(|| TypeInfo::union(members));
+        Some((narrowed(matching), narrowed(rest)))
+    }
+
+    /// `type_of(x)` compared with a type name
+    fn type_of_test(call: &Node<Expr>, name: &Node<Expr>) -> Option<(String, TypeInfo)> {
+        let Expr::Call { func, args } = call.as_ref() else {
+            return None;
+        };
+        let (Expr::Identifier(func), [arg]) = (func.as_ref().as_ref(), args.as_slice()) else {
+            return None;
+        };
+        let (Expr::Identifier(var), Expr::Literal(literal)) = (arg.as_ref(), name.as_ref()) else {
+            return None;
+        };
+        let Literal::String(type_name) = literal.as_ref() else {
+            return None;
+        };
+        if func != "type_of" {
+            return None;
+        }
+        Some((var.clone(), type_of_name(type_name)?))
+    }
+
+    /// `x is None`
+    fn none_test(var: &Node<Expr>, none: &Node<Expr>) -> Option<(String, TypeInfo)> {
+        match (var.as_ref(), none.as_ref()) {
+            (Expr::Identifier(var), Expr::Literal(literal))
+                if matches!(literal.as_ref(), Literal::None) =>
+            {
+                Some((var.clone(), TypeInfo::Unit))
+            }
+            _ => None,
+        }
+    }
+
+    /// Runs `check` with the variables in `narrowed` given their narrowed
+    /// types, restoring their previous types afterwards
+    fn with_narrowing<T>(
+        &mut self,
+        narrowed: &[(String, TypeInfo)],
+        check: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let previous: Vec<_> = narrowed
+            .iter()
+            .map(|(name, ty)| {
+                let previous = self.context.get_variable(name).cloned();
+                self.context.insert_variable(name.clone(), ty.clone());
+                (name, previous)
+            })
+            .collect();
+        let result = check(self);
+        for (name, previous) in previous.into_iter().rev() {
+            match previous {
+                Some(ty) => self.context.insert_variable(name.clone(), ty),
+                None => {
+                    self.context.remove_variable(name);
+                }
+            }
+        }
+        result
+    }
+
     fn is_unknown_like(ty: &TypeInfo) -> bool {
         matches!(ty, TypeInfo::Unknown)
             || matches!(ty, TypeInfo::Generic { args, .. } if args.is_empty())
@@ -148,11 +394,12 @@ impl TypeChecker {
             },
             true,
         );
-        context.define_type_alias("Error".to_string(), TypeInfo::Error, true);
+        context.define_type_alias("Error".to_string(), TypeInfo::error_value(), true);
         context.define_type_alias("string".to_string(), TypeInfo::Str, true);
 
         Self {
             errors: Vec::new(),
+            warnings: Vec::new(),
             context,
             registry: None,
             expr_types: HashMap::new(),
@@ -163,6 +410,12 @@ impl TypeChecker {
             method_expr_ids: HashMap::new(),
             features,
             current_function_return_type: None,
+            loop_labels: Vec::new(),
+            negated_literal: None,
+            const_values: HashMap::new(),
+            literal_validators: LiteralValidators::default(),
+            call_sites: None,
+            generic_bounds: HashMap::new(),
         }
     }
 
@@ -171,6 +424,47 @@ impl TypeChecker {
         self
     }
 
+    /// Checks string literals tagged `tag` with `validator`, replacing the
+    /// built-in validator for that tag if there is one
+    pub fn with_literal_validator(
+        mut self,
+        tag: impl Into<String>,
+        validator: Arc<dyn LiteralValidator>,
+    ) -> Self {
+        self.literal_validators.register(tag, validator);
+        self
+    }
+
+    /// Reports problems the validator registered for `tag` finds in a
+    /// tagged string literal
+    fn check_tagged_literal(&mut self, tag: &str, value: &str, span: Span) {
+        let Some(validator) = self.literal_validators.get(tag) else {
+            let known = self.literal_validators.tags();
+            let mut error = TypeError::new(format!("unknown string prefix '{tag}'"))
+                .with_span(span)
+                .with_help(format!(
+                    "no validator is registered for '{tag}'; known prefixes: {}",
+                    known.join(", ")
+                ));
+            let candidates = known.iter().map(|known| known.to_string());
+            if let Some(closest) = otterc_utils::suggest::find_best_match(tag, candidates) {
+                error = error
+                    .with_hint(format!("did you mean `{closest}`?"))
+                    .with_suggestion(closest);
+            }
+            self.errors.push(error);
+            return;
+        };
+        if let Err(problem) = validator.validate(value) {
+            let mut error = TypeError::new(format!("invalid {tag} literal: {}", problem.message))
+                .with_span(span);
+            if let Some(help) = problem.help {
+                error = error.with_help(help);
+            }
+            self.errors.push(error);
+        }
+    }
+
     /// Register all built-in functions in the type context
     fn register_builtins(context: &mut TypeContext) {
         // print function
@@ -202,6 +496,16 @@ impl TypeChecker {
             },
         );
 
+        // sym function, the symbol named by a string
+        context.functions.insert(
+            "sym".to_string(),
+            TypeInfo::Function {
+                params: vec![TypeInfo::Str],
+                param_defaults: vec![false],
+                return_type: Box::new(TypeInfo::Symbol),
+            },
+        );
+
         // len functions (accepts string, list, map, etc.)
         context.functions.insert(
             "len".to_string(),
@@ -222,22 +526,140 @@ impl TypeChecker {
             },
         );
 
-        // panic function
+        // panic function, which also raises error values
         context.functions.insert(
             "panic".to_string(),
+            TypeInfo::Function {
+                params: vec![TypeInfo::union(vec![
+                    TypeInfo::Str,
+                    TypeInfo::error_value(),
+                ])],
+                param_defaults: vec![false],
+                return_type: Box::new(TypeInfo::Never),
+            },
+        );
+
+        // error values, carrying a message, a cause and where they were made
+        context.functions.insert(
+            "error".to_string(),
             TypeInfo::Function {
                 params: vec![TypeInfo::Str],
                 param_defaults: vec![false],
-                return_type: Box::new(TypeInfo::Unit),
+                return_type: Box::new(TypeInfo::error_value()),
+            },
+        );
+
+        // Include builtins are replaced by the file contents before checking;
+        // calls left over are ones that could not be resolved
+        context.functions.insert(
+            "include_str".to_string(),
+            TypeInfo::Function {
+                params: vec![TypeInfo::Str],
+                param_defaults: vec![false],
+                return_type: Box::new(TypeInfo::Str),
+            },
+        );
+        context.functions.insert(
+            "include_bytes".to_string(),
+            TypeInfo::Function {
+                params: vec![TypeInfo::Str],
+                param_defaults: vec![false],
+                return_type: Box::new(TypeInfo::List(Box::new(TypeInfo::I64))),
             },
         );
     }
 
     /// Type check a program
     pub fn check_program(&mut self, program: &Program) -> Result<()> {
+        self.check_program_with(program, None)
+    }
+
+    /// Checks `program`, reusing the errors `cache` holds for functions whose
+    /// text has not changed since the previous check of `source`. Reused
+    /// functions record no expression types, so the result is only good for
+    /// diagnostics.
+    pub fn check_program_incremental(
+        &mut self,
+        program: &Program,
+        source: &str,
+        cache: &mut FunctionCache,
+    ) -> Result<()> {
+        cache.begin(program, source);
+        self.check_program_with(program, Some((source, cache)))
+    }
+
+    /// Annotates the parameters of `program`'s functions that have neither a
+    /// type nor a default with the type of the arguments passed to them, so
+    /// they are checked and compiled as if it had been written. The program
+    /// is checked once per round without keeping any results, and rounds go
+    /// on while they annotate something, since an inferred parameter passed
+    /// on to another function gives that one's parameter a type too. Call it
+    /// before [`TypeChecker::check_program`].
+    pub fn infer_parameter_types(&mut self, program: &mut Program) {
+        loop {
+            let errors = self.errors.len();
+            let context = self.context.clone();
+            let const_values = self.const_values.clone();
+            self.call_sites = Some(CallSites::default());
+            let _ = self.check_program_with(program, None);
+            let call_sites = self.call_sites.take().unwrap_or_default();
+            self.errors.truncate(errors);
+            self.warnings.clear();
+            self.context = context;
+            self.const_values = const_values;
+            self.expr_types.clear();
+            self.expr_types_by_span.clear();
+            self.expr_spans.clear();
+            self.comprehension_var_types.clear();
+            self.method_comprehension_spans.clear();
+            self.method_expr_ids.clear();
+
+            let mut annotated = false;
+            for statement in &mut program.statements {
+                let Statement::Function(function) = statement.as_mut() else {
+                    continue;
+                };
+                let function = function.as_mut();
+                if function.public {
+                    continue;
+                }
+                for (index, param) in function.params.iter_mut().enumerate() {
+                    let span = *param.span();
+                    let param = param.as_mut();
+                    if param.ty.is_some() {
+                        continue;
+                    }
+                    if let Some(ty) = call_sites.unified(&function.name, index) {
+                        param.ty = annotation_for(&ty, span);
+                        annotated |= param.ty.is_some();
+                    }
+                }
+            }
+            if !annotated {
+                break;
+            }
+        }
+    }
+
+    fn check_program_with(
+        &mut self,
+        program: &Program,
+        mut cache: Option<(&str, &mut FunctionCache)>,
+    ) -> Result<()> {
+        self.warnings = unused_bindings(program);
+        self.warnings.extend(unreachable_statements(program));
         self.register_module_imports(&program.statements);
         // First pass: collect struct definitions, enums, and type aliases
         self.register_type_definitions(&program.statements);
+        self.check_bound_declarations(&program.statements);
+
+        // Constants are computed before any signature or body is checked, so
+        // default values and functions can use constants declared below them
+        for statement in &program.statements {
+            if let Statement::Const { .. } = statement.as_ref() {
+                self.check_statement(statement)?;
+            }
+        }
 
         // Second pass: collect function signatures
         for statement in &program.statements {
@@ -246,25 +668,42 @@ impl TypeChecker {
                 self.context
                     .functions
                     .insert(function.as_ref().name.clone(), sig);
+                self.record_default_arguments(function.as_ref());
             }
         }
 
         // Third pass: type check function bodies and top-level statements
+        let mut init_blocks = 0;
         for statement in &program.statements {
             let span = statement.span();
             match statement.as_ref() {
-                Statement::Function(function) => {
-                    self.check_function(function)?;
+                Statement::Init(body) => {
+                    init_blocks += 1;
+                    if init_blocks > 1 {
+                        self.errors.push(
+                            TypeError::new("a module can have only one `init:` block".to_string())
+                                .with_hint("move its statements into the first one".to_string())
+                                .with_span(*span),
+                        );
+                    }
+                    // Not a name a function can have, so no call is taken
+                    // for a recursive one
+                    self.check_body("<init>", &[], TypeInfo::Unit, body)?;
                 }
-                Statement::Struct { name, methods, .. } => {
-                    self.check_struct_methods(name, methods)?;
+                Statement::Function(function) => match cache.as_mut() {
+                    Some((source, cache)) => self.check_function_cached(function, source, cache)?,
+                    None => self.check_function(function)?,
+                },
+                Statement::Struct { name, methods, .. } | Statement::Enum { name, methods, .. } => {
+                    self.check_methods(name, methods)?;
                 }
                 Statement::Let { .. } | Statement::Expr(_) => {
                     // Top-level let and expressions are allowed
                     self.check_statement(statement)?;
                 }
-                Statement::Enum { .. }
+                Statement::Const { .. }
                 | Statement::TypeAlias { .. }
+                | Statement::Protocol { .. }
                 | Statement::Use { .. }
                 | Statement::PubUse { .. } => {}
                 _ => {
@@ -288,15 +727,34 @@ impl TypeChecker {
         Ok(())
     }
 
-    fn check_struct_methods(
+    fn check_function_cached(
         &mut self,
-        struct_name: &str,
-        methods: &[Node<Function>],
+        function: &Node<Function>,
+        source: &str,
+        cache: &mut FunctionCache,
     ) -> Result<()> {
+        let name = &function.as_ref().name;
+        if let Some(errors) = cache.lookup(name, source, *function.span()) {
+            self.errors.extend(errors);
+            return Ok(());
+        }
+        let before = self.errors.len();
+        self.check_function(function)?;
+        cache.store(
+            name,
+            source,
+            *function.span(),
+            self.errors[before..].to_vec(),
+        );
+        Ok(())
+    }
+
+    /// Checks the methods of a struct or enum
+    fn check_methods(&mut self, type_name: &str, methods: &[Node<Function>]) -> Result<()> {
         for method in methods {
             let mut method_clone = method.as_ref().clone();
-            method_clone.name = format!("{}.{}", struct_name, method_clone.name);
-            self.rewrite_method_self_param(&mut method_clone, struct_name);
+            method_clone.name = format!("{}.{}", type_name, method_clone.name);
+            self.rewrite_method_self_param(&mut method_clone, type_name);
             let node = Node::new(method_clone, *method.span());
             self.record_method_metadata(&node.as_ref().name, node.as_ref().body.as_ref());
             self.check_function(&node)?;
@@ -342,20 +800,30 @@ impl TypeChecker {
         match stmt {
             Statement::Expr(expr)
             | Statement::Let { expr, .. }
-            | Statement::Assignment { expr, .. } => {
+            | Statement::Const { expr, .. }
+            | Statement::Assignment { expr, .. }
+            | Statement::Return(Some(expr))
+            | Statement::Defer(expr) => {
                 self.collect_metadata_in_expr(expr, spans, expr_ids);
             }
-            Statement::Return(Some(expr)) => self.collect_metadata_in_expr(expr, spans, expr_ids),
+            Statement::Assert { cond, message } => {
+                self.collect_metadata_in_expr(cond, spans, expr_ids);
+                if let Some(message) = message {
+                    self.collect_metadata_in_expr(message, spans, expr_ids);
+                }
+            }
             Statement::Return(None)
-            | Statement::Break
-            | Statement::Continue
+            | Statement::Break(_)
+            | Statement::Continue(_)
             | Statement::Pass
             | Statement::Use { .. }
             | Statement::PubUse { .. }
             | Statement::Struct { .. }
             | Statement::Enum { .. }
             | Statement::TypeAlias { .. }
-            | Statement::Function(_) => {}
+            | Statement::Protocol { .. }
+            | Statement::Function(_)
+            | Statement::Init(_) => {}
             Statement::If {
                 cond,
                 then_block,
@@ -376,7 +844,10 @@ impl TypeChecker {
                 self.collect_metadata_in_expr(iterable, spans, expr_ids);
                 self.collect_metadata_in_block(body.as_ref(), spans, expr_ids);
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, .. }
+            | Statement::With {
+                expr: cond, body, ..
+            } => {
                 self.collect_metadata_in_expr(cond, spans, expr_ids);
                 self.collect_metadata_in_block(body.as_ref(), spans, expr_ids);
             }
@@ -428,9 +899,11 @@ impl TypeChecker {
                 self.collect_metadata_in_expr(right, spans, expr_ids);
             }
             Expr::Unary { expr, .. }
+            | Expr::Cast { expr, .. }
             | Expr::Await(expr)
             | Expr::Spawn(expr)
-            | Expr::Member { object: expr, .. } => {
+            | Expr::Member { object: expr, .. }
+            | Expr::Lambda { body: expr, .. } => {
                 self.collect_metadata_in_expr(expr, spans, expr_ids);
             }
             Expr::Call { func, args } => {
@@ -476,7 +949,7 @@ impl TypeChecker {
             }
             Expr::FString { parts } => {
                 for part in parts {
-                    if let FStringPart::Expr(expr) = part.as_ref() {
+                    if let FStringPart::Expr(expr, _) = part.as_ref() {
                         self.collect_metadata_in_expr(expr, spans, expr_ids);
                     }
                 }
@@ -490,6 +963,26 @@ impl TypeChecker {
         }
     }
 
+    /// Generic arguments of the struct a method is called on, from its type
+    /// arguments when they are known and otherwise from its field types
+    fn method_receiver_generics(&mut self, func: &Node<Expr>) -> Result<HashMap<String, TypeInfo>> {
+        let Expr::Member { object, .. } = func.as_ref() else {
+            return Ok(HashMap::new());
+        };
+        let TypeInfo::Struct { name, fields, args } = self.infer_expr_type(object)? else {
+            return Ok(HashMap::new());
+        };
+        let mut inferred = self.infer_struct_generics_from_instance(&name, &fields);
+        if let Some(struct_def) = self.context.get_struct(&name) {
+            for (generic, arg) in struct_def.generics.iter().zip(args) {
+                if !Self::is_unknown_like(&arg) {
+                    inferred.insert(generic.clone(), arg);
+                }
+            }
+        }
+        Ok(inferred)
+    }
+
     fn infer_struct_generics_from_instance(
         &mut self,
         struct_name: &str,
@@ -560,6 +1053,28 @@ impl TypeChecker {
     }
 
     /// Infer function signature from declaration
+    /// Counts the default value of each unannotated parameter as one more
+    /// argument passed to it, so an inferred type also fits the calls that
+    /// leave the argument out
+    fn record_default_arguments(&mut self, function: &Function) {
+        if self.call_sites.is_none() {
+            return;
+        }
+        for (index, param) in function.params.iter().enumerate() {
+            let param = param.as_ref();
+            let (None, Some(default)) = (&param.ty, &param.default) else {
+                continue;
+            };
+            let Ok(ty) = self.infer_expr_type(default) else {
+                continue;
+            };
+            let int_literal = Self::unsuffixed_int_literal(default).is_some();
+            if let Some(call_sites) = &mut self.call_sites {
+                call_sites.record(&function.name, index, &ty, int_literal);
+            }
+        }
+    }
+
     fn infer_function_signature(&mut self, function: &Node<Function>) -> TypeInfo {
         let mut param_types = Vec::new();
         let mut param_defaults = Vec::new();
@@ -596,7 +1111,7 @@ impl TypeChecker {
                 param_defaults.push(true);
                 if let Ok(default_type) = self.infer_expr_type(default_expr)
                     && let Some(expected) = &explicit_type
-                    && !default_type.is_compatible_with(expected)
+                    && !self.accepts(&default_type, expected)
                 {
                     self.errors.push(
                         TypeError::new(format!(
@@ -655,19 +1170,30 @@ impl TypeChecker {
     }
 
     fn try_register_module(&mut self, import: &Node<UseImport>) {
-        let Some(registry) = self.registry else {
-            return;
-        };
-
         let Some(module_name) = Self::canonical_module_name(&import.as_ref().module) else {
             return;
         };
+        let registry = self
+            .registry
+            .filter(|registry| registry.has_module(&module_name));
+        if let Some(registry) = registry {
+            registry.activate_module(&module_name);
+        }
 
-        if !registry.has_module(&module_name) {
+        if import.as_ref().glob {
+            self.register_glob_import(&module_name);
+            return;
+        }
+        if !import.as_ref().items.is_empty() {
+            for item in &import.as_ref().items {
+                self.register_imported_item(&module_name, item, registry.is_some());
+            }
             return;
         }
 
-        registry.activate_module(&module_name);
+        if registry.is_none() {
+            return;
+        }
         let alias = import
             .as_ref()
             .alias
@@ -677,6 +1203,94 @@ impl TypeChecker {
             .insert_variable(alias, TypeInfo::Module(module_name));
     }
 
+    /// Binds the local name of `from module import name` to what `module.name`
+    /// refers to: a registry function, or a function, variable or constant the
+    /// module exports. Items of modules the checker knows nothing about are
+    /// bound as functions without a signature, like their `module.name` calls.
+    fn register_imported_item(&mut self, module: &str, item: &Node<ImportItem>, known: bool) {
+        let qualified = format!("{}.{}", module, item.as_ref().name);
+        let local = item.as_ref().local_name().to_string();
+
+        if self.bind_imported_name(&qualified, local.clone()) {
+            return;
+        }
+        if known || matches!(self.context.get_variable(module), Some(TypeInfo::Module(_))) {
+            self.errors.push(
+                TypeError::new(format!(
+                    "module '{}' has no item '{}'",
+                    module,
+                    item.as_ref().name
+                ))
+                .with_span(*item.span()),
+            );
+        } else {
+            self.context.insert_function(
+                local,
+                TypeInfo::Function {
+                    params: vec![],
+                    param_defaults: vec![],
+                    return_type: Box::new(TypeInfo::Unknown),
+                },
+            );
+        }
+    }
+
+    /// Binds every item `module` exports under its own name, for
+    /// `from module import *`. Nested modules of registry modules stay behind
+    /// their dotted path.
+    fn register_glob_import(&mut self, module: &str) {
+        let prefix = format!("{}.", module);
+        let mut names = BTreeSet::new();
+        if let Some(registry) = self.registry {
+            names.extend(registry.all().into_iter().map(|symbol| symbol.name));
+        }
+        names.extend(self.context.functions.keys().cloned());
+        names.extend(self.context.variables.keys().cloned());
+        names.extend(self.const_values.keys().cloned());
+
+        for qualified in names {
+            let Some(local) = qualified.strip_prefix(&prefix) else {
+                continue;
+            };
+            if local.is_empty() || local.contains('.') {
+                continue;
+            }
+            let local = local.to_string();
+            self.bind_imported_name(&qualified, local);
+        }
+    }
+
+    /// Makes `qualified` (`module.name`) available as `local`. Returns false
+    /// when nothing by that name is known.
+    fn bind_imported_name(&mut self, qualified: &str, local: String) -> bool {
+        if let Some(symbol) = self
+            .registry
+            .and_then(|registry| registry.resolve(qualified))
+        {
+            let signature = TypeInfo::Function {
+                params: symbol
+                    .signature
+                    .params
+                    .iter()
+                    .map(ffi_type_to_typeinfo)
+                    .collect(),
+                param_defaults: vec![false; symbol.signature.params.len()],
+                return_type: Box::new(ffi_type_to_typeinfo(&symbol.signature.result)),
+            };
+            self.context.insert_function(local, signature);
+        } else if let Some(function) = self.context.get_function(qualified).cloned() {
+            self.context.insert_function(local, function);
+        } else if let Some(variable) = self.context.get_variable(qualified).cloned() {
+            if let Some(value) = self.const_values.get(qualified).cloned() {
+                self.const_values.insert(local.clone(), value);
+            }
+            self.context.insert_variable(local, variable);
+        } else {
+            return false;
+        }
+        true
+    }
+
     fn canonical_module_name(module: &str) -> Option<String> {
         if module.starts_with("rust:") {
             return None;
@@ -705,6 +1319,56 @@ impl TypeChecker {
     }
 
     fn register_type_definitions(&mut self, statements: &[Node<Statement>]) {
+        // Declare every struct and enum before resolving any field types, so
+        // fields can name the type they belong to or one declared later
+        for statement in statements {
+            match statement.as_ref() {
+                Statement::Struct {
+                    name,
+                    generics,
+                    bounds,
+                    public,
+                    ..
+                } => {
+                    self.context.define_struct(StructDefinition {
+                        name: name.clone(),
+                        generics: generics.clone(),
+                        fields: HashMap::new(),
+                        public: *public,
+                    });
+                    if !bounds.is_empty() {
+                        self.generic_bounds.insert(name.clone(), bounds.clone());
+                    }
+                }
+                Statement::Enum {
+                    name,
+                    variants,
+                    generics,
+                    bounds,
+                    public,
+                    ..
+                } => {
+                    self.context.define_enum(EnumDefinition {
+                        name: name.clone(),
+                        generics: generics.clone(),
+                        variants: variants.iter().map(|v| v.as_ref()).cloned().collect(),
+                        public: *public,
+                    });
+                    if !bounds.is_empty() {
+                        self.generic_bounds.insert(name.clone(), bounds.clone());
+                    }
+                }
+                Statement::Protocol { name, public, .. } => {
+                    self.context.define_protocol(ProtocolDefinition {
+                        name: name.clone(),
+                        methods: Vec::new(),
+                        public: *public,
+                    })
+                }
+                _ => {}
+            }
+        }
+
         for statement in statements {
             match statement.as_ref() {
                 Statement::Struct {
@@ -713,6 +1377,7 @@ impl TypeChecker {
                     methods,
                     generics,
                     public,
+                    ..
                 } => {
                     let mut field_types = HashMap::new();
                     for (field_name, field_ty) in fields {
@@ -797,11 +1462,61 @@ impl TypeChecker {
                     let ty = self.context.type_from_annotation(target);
                     self.context.define_type_alias(name.clone(), ty, *public);
                 }
+                Statement::Protocol {
+                    name,
+                    methods,
+                    public,
+                } => {
+                    let mut signatures: Vec<(String, TypeInfo)> = Vec::new();
+                    for method in methods {
+                        let method = method.as_ref();
+                        if signatures
+                            .iter()
+                            .any(|(existing, _)| *existing == method.name)
+                        {
+                            self.errors.push(
+                                TypeError::new(format!(
+                                    "protocol '{}' lists method '{}' more than once",
+                                    name, method.name
+                                ))
+                                .with_span(*statement.span()),
+                            );
+                            continue;
+                        }
+                        let params = method
+                            .params
+                            .iter()
+                            .map(|param| match &param.as_ref().ty {
+                                Some(ty) => self.context.type_from_annotation(ty),
+                                None => TypeInfo::Unknown,
+                            })
+                            .collect();
+                        let return_type = match &method.ret_ty {
+                            Some(ty) => self.context.type_from_annotation(ty),
+                            None => TypeInfo::Unit,
+                        };
+                        signatures.push((
+                            method.name.clone(),
+                            TypeInfo::Function {
+                                params,
+                                param_defaults: vec![false; method.params.len()],
+                                return_type: Box::new(return_type),
+                            },
+                        ));
+                    }
+                    self.context.define_protocol(ProtocolDefinition {
+                        name: name.clone(),
+                        methods: signatures,
+                        public: *public,
+                    });
+                }
                 Statement::Enum {
                     name,
                     variants,
+                    methods,
                     generics,
                     public,
+                    ..
                 } => {
                     let definition = EnumDefinition {
                         name: name.clone(),
@@ -810,26 +1525,90 @@ impl TypeChecker {
                         public: *public,
                     };
                     self.context.define_enum(definition);
-                }
-                _ => {}
-            }
-        }
-    }
 
-    /// Type check a function
-    fn check_function(&mut self, function: &Node<Function>) -> Result<()> {
-        // Determine function return type
-        let return_type = if let Some(ret_ty) = &function.as_ref().ret_ty {
+                    for generic in generics {
+                        self.context.push_generic(generic.clone());
+                    }
+
+                    // Methods are registered like struct methods, as `Enum.method`
+                    for method in methods {
+                        if variants
+                            .iter()
+                            .any(|variant| variant.as_ref().name == method.as_ref().name)
+                        {
+                            self.errors.push(
+                                TypeError::new(format!(
+                                    "enum '{}' has both a variant and a method named '{}'",
+                                    name,
+                                    method.as_ref().name
+                                ))
+                                .with_hint("rename the method".to_string())
+                                .with_span(*method.span()),
+                            );
+                        }
+                        let mut method_clone = method.as_ref().clone();
+                        self.rewrite_method_self_param(&mut method_clone, name);
+                        let method_name = format!("{}.{}", name, method_clone.name);
+                        let method_node = Node::new(method_clone, *method.span());
+                        self.record_method_metadata(
+                            &method_name,
+                            method_node.as_ref().body.as_ref(),
+                        );
+                        let sig = self.infer_function_signature(&method_node);
+                        self.context.insert_function(method_name, sig);
+                    }
+
+                    for _ in generics {
+                        self.context.pop_generic();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Type check a function
+    fn check_function(&mut self, function: &Node<Function>) -> Result<()> {
+        // Determine function return type
+        let return_type = if let Some(ret_ty) = &function.as_ref().ret_ty {
             self.context.type_from_annotation(ret_ty)
         } else {
             TypeInfo::Unit
         };
 
+        let (body_type, return_type) = self.check_body(
+            &function.as_ref().name,
+            &function.as_ref().params,
+            return_type,
+            &function.as_ref().body,
+        )?;
+        if !matches!(body_type, TypeInfo::Never)
+            && let Some(return_type) = return_type
+        {
+            self.check_missing_return(function, &return_type);
+        }
+
+        Ok(())
+    }
+
+    /// Checks `body` in a scope of its own holding `params`, where `return`
+    /// takes a `return_type`. Returns the type of the body and the return
+    /// type it was checked against.
+    fn check_body(
+        &mut self,
+        name: &str,
+        params: &[Node<Param>],
+        return_type: TypeInfo,
+        body: &Node<Block>,
+    ) -> Result<(TypeInfo, Option<TypeInfo>)> {
         let mut fn_context = TypeContext::with_features(self.features.clone());
         fn_context.variables = self.context.variables.clone();
 
         // Add function parameters to context, overriding any globals/imports
-        for param in &function.as_ref().params {
+        for param in params {
+            if let Some(default) = &param.as_ref().default {
+                self.check_default_value(param.as_ref().name.as_ref(), default);
+            }
             let param_type = if let Some(ty) = &param.as_ref().ty {
                 self.context.type_from_annotation(ty)
             } else {
@@ -846,15 +1625,50 @@ impl TypeChecker {
         fn_context.structs = self.context.structs.clone();
         fn_context.type_aliases = self.context.type_aliases.clone();
         fn_context.enums = self.context.enums.clone();
+        fn_context.protocols = self.context.protocols.clone();
 
         // Type check function body with return type tracking
         let old_context = std::mem::replace(&mut self.context, fn_context);
         let old_return_type = self.current_function_return_type.replace(return_type);
-        let _ = self.check_block(&function.as_ref().body)?;
+        let old_loop_labels = std::mem::take(&mut self.loop_labels);
+        let old_caller = self
+            .call_sites
+            .as_mut()
+            .map(|call_sites| call_sites.enter(name));
+        let result = self.check_block(body);
         self.context = old_context;
-        self.current_function_return_type = old_return_type;
+        let return_type =
+            std::mem::replace(&mut self.current_function_return_type, old_return_type);
+        self.loop_labels = old_loop_labels;
+        if let (Some(call_sites), Some(caller)) = (&mut self.call_sites, old_caller) {
+            call_sites.leave(caller);
+        }
+        Ok((result?, return_type))
+    }
 
-        Ok(())
+    /// Reports a function declared to return a value, or to never return,
+    /// whose body can run to its end
+    fn check_missing_return(&mut self, function: &Node<Function>, return_type: &TypeInfo) {
+        let name = &function.as_ref().name;
+        let error = match return_type {
+            TypeInfo::Unit | TypeInfo::Unknown | TypeInfo::Error => return,
+            TypeInfo::Never => TypeError::new(format!(
+                "function `{name}` is declared `never` but can return"
+            ))
+            .with_hint("end every path with `panic(...)` or a loop that never exits".to_string()),
+            TypeInfo::Alias { underlying, .. } if matches!(**underlying, TypeInfo::Unit) => return,
+            _ => TypeError::new(format!(
+                "missing return in function `{name}`, which returns {}",
+                return_type.display_name()
+            ))
+            .with_hint("end every path with `return` or `panic(...)`".to_string()),
+        };
+        let span = function
+            .as_ref()
+            .ret_ty
+            .as_ref()
+            .map_or(*function.span(), |ret_ty| *ret_ty.span());
+        self.errors.push(error.with_span(span));
     }
 
     /// Check function with generic type parameters
@@ -917,6 +1731,386 @@ impl TypeChecker {
                     self.extract_generic_params(arg, params);
                 }
             }
+            Type::Union(members) => {
+                for member in members {
+                    self.extract_generic_params(member, params);
+                }
+            }
+        }
+    }
+
+    /// `config.load(path, Struct)` deserializes a config file into `Struct`, so
+    /// its second argument is a type name rather than a value
+    fn try_eval_config_load(
+        &mut self,
+        func: &Node<Expr>,
+        args: &[Node<Expr>],
+    ) -> Result<Option<TypeInfo>> {
+        let Expr::Member { object, field } = func.as_ref() else {
+            return Ok(None);
+        };
+        if field != "load"
+            || !matches!(object.as_ref().as_ref(), Expr::Identifier(module) if module == "config")
+            || self.context.get_variable("config").is_some()
+        {
+            return Ok(None);
+        }
+
+        let [path, target] = args else {
+            self.errors.push(
+                TypeError::new(format!(
+                    "config.load expects 2 arguments (path, struct type), got {}",
+                    args.len()
+                ))
+                .with_span(*func.span()),
+            );
+            return Ok(Some(TypeInfo::Error));
+        };
+
+        let path_type = self.infer_expr_type(path)?;
+        if !path_type.is_compatible_with(&TypeInfo::Str) {
+            self.errors.push(
+                TypeError::new(format!(
+                    "config.load path must be a string, got {}",
+                    path_type.display_name()
+                ))
+                .with_span(*path.span()),
+            );
+        }
+
+        let Some(definition) = (match target.as_ref() {
+            Expr::Identifier(name) => self.context.get_struct(name).cloned(),
+            _ => None,
+        }) else {
+            self.errors.push(
+                TypeError::new(
+                    "config.load expects a struct type as its second argument".to_string(),
+                )
+                .with_span(*target.span())
+                .with_hint(
+                    "pass the struct name, e.g. `config.load(\"app.toml\", AppConfig)`".to_string(),
+                ),
+            );
+            return Ok(Some(TypeInfo::Error));
+        };
+
+        let mut unsupported = Vec::new();
+        self.collect_unsupported_config_fields(
+            &definition,
+            &definition.name,
+            &mut vec![definition.name.clone()],
+            &mut unsupported,
+        );
+        for (field_path, field_type) in unsupported {
+            self.errors.push(
+                TypeError::new(format!(
+                    "config field '{}' has unsupported type {}",
+                    field_path,
+                    field_type.display_name()
+                ))
+                .with_span(*target.span())
+                .with_hint(
+                    "config structs may only contain int, float, bool, string and nested struct fields"
+                        .to_string(),
+                ),
+            );
+        }
+
+        Ok(Some(TypeInfo::Struct {
+            name: definition.name,
+            fields: definition.fields,
+            args: Vec::new(),
+        }))
+    }
+
+    fn collect_unsupported_config_fields(
+        &self,
+        definition: &StructDefinition,
+        prefix: &str,
+        enclosing: &mut Vec<String>,
+        unsupported: &mut Vec<(String, TypeInfo)>,
+    ) {
+        let mut fields: Vec<_> = definition.fields.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, ty) in fields {
+            let path = format!("{prefix}.{name}");
+            match self.context.normalize_type(ty.clone()) {
+                TypeInfo::I32 | TypeInfo::I64 | TypeInfo::F64 | TypeInfo::Bool | TypeInfo::Str => {}
+                // A struct that contains itself, directly or not, has no finite config
+                TypeInfo::Struct { name: nested, .. } if !enclosing.contains(&nested) => {
+                    if let Some(nested) = self.context.get_struct(&nested) {
+                        enclosing.push(nested.name.clone());
+                        self.collect_unsupported_config_fields(
+                            nested,
+                            &path,
+                            enclosing,
+                            unsupported,
+                        );
+                        enclosing.pop();
+                    }
+                }
+                other => unsupported.push((path, other)),
+            }
+        }
+    }
+
+    /// `deepcopy(value)` and `diff(old, new)` work on any list, map or struct,
+    /// so they are checked structurally instead of through a fixed signature
+    fn try_eval_reflect_call(
+        &mut self,
+        func: &Node<Expr>,
+        args: &[Node<Expr>],
+    ) -> Result<Option<TypeInfo>> {
+        let Expr::Identifier(name) = func.as_ref() else {
+            return Ok(None);
+        };
+        if !matches!(name.as_str(), "deepcopy" | "diff")
+            || self.context.get_function(name).is_some()
+            || self.context.get_variable(name).is_some()
+        {
+            return Ok(None);
+        }
+
+        let expected = if name == "deepcopy" { 1 } else { 2 };
+        if args.len() != expected {
+            self.errors.push(
+                TypeError::new(format!(
+                    "{}() expects {} argument(s), got {}",
+                    name,
+                    expected,
+                    args.len()
+                ))
+                .with_span(*func.span()),
+            );
+            return Ok(Some(TypeInfo::Error));
+        }
+
+        let mut arg_types = Vec::with_capacity(args.len());
+        for arg in args {
+            let ty = self.infer_expr_type(arg)?;
+            arg_types.push(self.context.normalize_type(ty));
+        }
+
+        if name == "deepcopy" {
+            return Ok(Some(arg_types.remove(0)));
+        }
+
+        let structural = |ty: &TypeInfo| {
+            matches!(
+                ty,
+                TypeInfo::List(_) | TypeInfo::Dict { .. } | TypeInfo::Struct { .. }
+            )
+        };
+        let same_shape = match (&arg_types[0], &arg_types[1]) {
+            (TypeInfo::Struct { name: a, .. }, TypeInfo::Struct { name: b, .. }) => a == b,
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        };
+        if arg_types.iter().any(|ty| matches!(ty, TypeInfo::Error)) {
+            return Ok(Some(TypeInfo::Error));
+        }
+        if !arg_types.iter().all(structural) || !same_shape {
+            self.errors.push(
+                TypeError::new(format!(
+                    "diff() expects two lists, maps or structs of the same type, got {} and {}",
+                    arg_types[0].display_name(),
+                    arg_types[1].display_name()
+                ))
+                .with_span(*func.span()),
+            );
+            return Ok(Some(TypeInfo::Error));
+        }
+
+        // One record per change: {"path", "op", "old", "new"}
+        Ok(Some(TypeInfo::List(Box::new(TypeInfo::Dict {
+            key: Box::new(TypeInfo::Str),
+            value: Box::new(TypeInfo::Str),
+        }))))
+    }
+
+    /// `map`, `and_then`, `unwrap_or` and `expect` on a `Result` or `Option`
+    /// whose enum does not define a method of that name
+    fn try_eval_fallible_method(
+        &mut self,
+        func: &Node<Expr>,
+        args: &[Node<Expr>],
+    ) -> Result<Option<TypeInfo>> {
+        let Expr::Member { object, field } = func.as_ref() else {
+            return Ok(None);
+        };
+        if !Fallible::METHODS.contains(&field.as_str()) {
+            return Ok(None);
+        }
+        // The receiver is checked again as an ordinary call when this is not
+        // one of these methods, so its errors are only kept here if it is
+        let reported = self.errors.len();
+        let receiver = self.infer_expr_type(object)?;
+        let receiver = self.context.normalize_type(receiver);
+        let fallible = match &receiver {
+            TypeInfo::Enum { name, .. }
+                if self
+                    .context
+                    .get_function(&format!("{}.{}", name, field))
+                    .is_none() =>
+            {
+                receiver.fallible()
+            }
+            _ => None,
+        };
+        let Some(fallible) = fallible else {
+            self.errors.truncate(reported);
+            return Ok(None);
+        };
+        let TypeInfo::Enum {
+            name,
+            args: type_args,
+            ..
+        } = &receiver
+        else {
+            return Ok(None);
+        };
+
+        let span = *func.span();
+        let [arg] = args else {
+            self.errors.push(
+                TypeError::new(format!(
+                    "{}.{}() expects 1 argument, got {}",
+                    name,
+                    field,
+                    args.len()
+                ))
+                .with_span(span),
+            );
+            return Ok(Some(TypeInfo::Error));
+        };
+
+        let value = fallible.value.clone();
+        let result = match field.as_str() {
+            "map" => {
+                let mapped = self.infer_callback(arg, &value, field)?;
+                let mut mapped_args = type_args.clone();
+                mapped_args[0] = mapped;
+                self.context
+                    .build_enum_type(name, mapped_args)
+                    .unwrap_or(TypeInfo::Error)
+            }
+            "and_then" => {
+                let chained = self.infer_callback(arg, &value, field)?;
+                let chained = self.context.normalize_type(chained);
+                match &chained {
+                    TypeInfo::Enum {
+                        name: chained_name, ..
+                    } if chained_name == name => chained,
+                    TypeInfo::Error | TypeInfo::Unknown => chained,
+                    _ => {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "and_then() expects a function returning {}, got one returning {}",
+                                name,
+                                chained.display_name()
+                            ))
+                            .with_hint("use map() to transform the value itself".to_string())
+                            .with_span(*arg.span()),
+                        );
+                        TypeInfo::Error
+                    }
+                }
+            }
+            "unwrap_or" => {
+                let default = self.infer_expr_type(arg)?;
+                let default = self.adapt_int_literal(arg, default, &value);
+                if !self.accepts(&default, &value) {
+                    self.errors.push(
+                        TypeError::new("unwrap_or() default has the wrong type".to_string())
+                            .with_mismatch(&value, &default)
+                            .with_span(*arg.span()),
+                    );
+                }
+                value
+            }
+            _ => {
+                let message = self.infer_expr_type(arg)?;
+                if !message.is_compatible_with(&TypeInfo::Str) {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "expect() message must be str, got {}",
+                            message.display_name()
+                        ))
+                        .with_span(*arg.span()),
+                    );
+                }
+                value
+            }
+        };
+        Ok(Some(result))
+    }
+
+    /// The return type of `callback` when it is called with a `param`. A
+    /// lambda's unannotated parameter takes the type `param`.
+    fn infer_callback(
+        &mut self,
+        callback: &Node<Expr>,
+        param: &TypeInfo,
+        method: &str,
+    ) -> Result<TypeInfo> {
+        if let Expr::Lambda { params, body } = callback.as_ref()
+            && let [lambda_param] = params.as_slice()
+        {
+            let name = lambda_param.as_ref().name.as_ref().clone();
+            let ty = match &lambda_param.as_ref().ty {
+                Some(annotation) => {
+                    let annotated = self.context.type_from_annotation(annotation);
+                    if !self.accepts(param, &annotated) {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "{}() callback parameter type mismatch",
+                                method
+                            ))
+                            .with_mismatch(&annotated, param)
+                            .with_span(*lambda_param.span()),
+                        );
+                    }
+                    annotated
+                }
+                None => param.clone(),
+            };
+            let previous = self.context.variables.insert(name.clone(), ty);
+            let return_type = self.infer_expr_type(body);
+            match previous {
+                Some(ty) => self.context.insert_variable(name, ty),
+                None => {
+                    self.context.remove_variable(&name);
+                }
+            }
+            return return_type;
+        }
+
+        match self.infer_expr_type(callback)? {
+            TypeInfo::Function {
+                params,
+                return_type,
+                ..
+            } if params.len() == 1 => {
+                if !self.accepts(param, &params[0]) {
+                    self.errors.push(
+                        TypeError::new(format!("{}() callback parameter type mismatch", method))
+                            .with_mismatch(&params[0], param)
+                            .with_span(*callback.span()),
+                    );
+                }
+                Ok(*return_type)
+            }
+            TypeInfo::Error | TypeInfo::Unknown => Ok(TypeInfo::Unknown),
+            other => {
+                self.errors.push(
+                    TypeError::new(format!(
+                        "{}() expects a function of one argument, got {}",
+                        method,
+                        other.display_name()
+                    ))
+                    .with_span(*callback.span()),
+                );
+                Ok(TypeInfo::Error)
+            }
         }
     }
 
@@ -929,73 +2123,188 @@ impl TypeChecker {
             && let Expr::Identifier(enum_name) = object.as_ref().as_ref()
             && let Some(definition) = self.context.get_enum(enum_name).cloned()
         {
-            let Some(variant) = definition
-                .variants
-                .iter()
-                .find(|variant| variant.name == *field)
-            else {
-                self.errors.push(
-                    TypeError::new(format!("enum '{}' has no variant '{}'", enum_name, field))
-                        .with_span(*object.span()),
-                );
-                return Ok(Some(TypeInfo::Error));
-            };
+            let args: Vec<&Node<Expr>> = args.iter().collect();
+            return self
+                .check_enum_variant_args(&definition, field, &args, *object.span())
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Checks the values given to `variant` of `definition`, in field order,
+    /// and returns the constructed enum type
+    fn check_enum_variant_args(
+        &mut self,
+        definition: &EnumDefinition,
+        field: &str,
+        args: &[&Node<Expr>],
+        span: Span,
+    ) -> Result<TypeInfo> {
+        let enum_name = &definition.name;
+        let Some(variant) = definition
+            .variants
+            .iter()
+            .find(|variant| variant.name == *field)
+        else {
+            self.errors.push(
+                TypeError::new(format!("enum '{}' has no variant '{}'", enum_name, field))
+                    .with_span(span),
+            );
+            return Ok(TypeInfo::Error);
+        };
+
+        let expected_len = variant.fields.len();
+        if expected_len != args.len() {
+            self.errors.push(
+                TypeError::new(format!(
+                    "enum variant '{}.{}' expects {} argument(s), got {}",
+                    enum_name,
+                    field,
+                    expected_len,
+                    args.len()
+                ))
+                .with_span(span),
+            );
+        }
+
+        let mut arg_types = Vec::new();
+        for arg in args {
+            arg_types.push(self.infer_expr_type(arg)?);
+        }
 
-            let expected_len = variant.fields.len();
-            if expected_len != args.len() {
+        for (field_ty, actual_ty) in variant.fields.iter().zip(arg_types.iter()) {
+            let expected_type = self.context.type_from_annotation(field_ty);
+            if !self.type_contains_enum_generic(field_ty.as_ref(), &definition.generics)
+                && !actual_ty.is_compatible_with(&expected_type)
+            {
                 self.errors.push(
                     TypeError::new(format!(
-                        "enum variant '{}.{}' expects {} argument(s), got {}",
+                        "argument for '{}.{}' expects type {}, got {}",
                         enum_name,
                         field,
-                        expected_len,
-                        args.len()
+                        expected_type.display_name(),
+                        actual_ty.display_name()
                     ))
-                    .with_span(*object.span()),
+                    .with_span(*field_ty.span()),
                 );
             }
+        }
 
-            let mut arg_types = Vec::new();
-            for arg in args {
-                arg_types.push(self.infer_expr_type(arg)?);
-            }
+        let mut inferred = HashMap::new();
+        for (field_ty, actual_ty) in variant.fields.iter().zip(arg_types.iter()) {
+            self.infer_enum_generics_from_type(field_ty, actual_ty, definition, &mut inferred);
+        }
 
-            for (field_ty, actual_ty) in variant.fields.iter().zip(arg_types.iter()) {
-                let expected_type = self.context.type_from_annotation(field_ty);
-                if !self.type_contains_enum_generic(field_ty.as_ref(), &definition.generics)
-                    && !actual_ty.is_compatible_with(&expected_type)
-                {
-                    self.errors.push(
-                        TypeError::new(format!(
-                            "argument for '{}.{}' expects type {}, got {}",
-                            enum_name,
-                            field,
-                            expected_type.display_name(),
-                            actual_ty.display_name()
-                        ))
-                        .with_span(*field_ty.span()),
-                    );
-                }
-            }
+        let resolved_args = definition
+            .generics
+            .iter()
+            .map(|name| inferred.get(name).cloned().unwrap_or(TypeInfo::Unknown))
+            .collect::<Vec<_>>();
 
-            let mut inferred = HashMap::new();
-            for (field_ty, actual_ty) in variant.fields.iter().zip(arg_types.iter()) {
-                self.infer_enum_generics_from_type(field_ty, actual_ty, &definition, &mut inferred);
-            }
+        Ok(self
+            .context
+            .build_enum_type(enum_name, resolved_args)
+            .unwrap_or(TypeInfo::Error))
+    }
 
-            let resolved_args = definition
-                .generics
-                .iter()
-                .map(|name| inferred.get(name).cloned().unwrap_or(TypeInfo::Unknown))
-                .collect::<Vec<_>>();
+    /// Checks `Enum.Variant(name=value, ...)` for a variant with named fields
+    fn check_named_enum_variant(
+        &mut self,
+        enum_name: &str,
+        variant_name: &str,
+        fields: &[(String, Node<Expr>)],
+        span: Span,
+    ) -> Result<TypeInfo> {
+        let Some(definition) = self.context.get_enum(enum_name).cloned() else {
+            self.errors.push(
+                TypeError::new(format!("unknown enum type: {}", enum_name))
+                    .with_hint(
+                        "`Name.Variant(field=value)` constructs an enum variant with named fields"
+                            .to_string(),
+                    )
+                    .with_span(span),
+            );
+            return Ok(TypeInfo::Error);
+        };
+        let Some(variant) = definition
+            .variants
+            .iter()
+            .find(|variant| variant.name == variant_name)
+        else {
+            self.errors.push(
+                TypeError::new(format!(
+                    "enum '{}' has no variant '{}'",
+                    enum_name, variant_name
+                ))
+                .with_span(span),
+            );
+            return Ok(TypeInfo::Error);
+        };
+        if variant.field_names.is_empty() {
+            self.errors.push(
+                TypeError::new(format!(
+                    "enum variant '{}.{}' has no named fields",
+                    enum_name, variant_name
+                ))
+                .with_hint(format!(
+                    "pass the values in order: {}.{}(...)",
+                    enum_name, variant_name
+                ))
+                .with_span(span),
+            );
+            return Ok(TypeInfo::Error);
+        }
 
-            if let Some(enum_type) = self.context.build_enum_type(enum_name, resolved_args) {
-                return Ok(Some(enum_type));
+        let mut ordered: Vec<Option<&Node<Expr>>> = vec![None; variant.field_names.len()];
+        for (field_name, field_expr) in fields {
+            match variant
+                .field_names
+                .iter()
+                .position(|name| name == field_name)
+            {
+                Some(idx) if ordered[idx].is_some() => self.errors.push(
+                    TypeError::new(format!(
+                        "duplicate field '{}' in enum variant initialization",
+                        field_name
+                    ))
+                    .with_span(span),
+                ),
+                Some(idx) => ordered[idx] = Some(field_expr),
+                None => self.errors.push(
+                    TypeError::new(format!(
+                        "enum variant '{}.{}' has no field '{}'",
+                        enum_name, variant_name, field_name
+                    ))
+                    .with_hint(format!(
+                        "Available fields: {}",
+                        variant.field_names.join(", ")
+                    ))
+                    .with_span(span),
+                ),
             }
-
-            return Ok(Some(TypeInfo::Error));
         }
-        Ok(None)
+        let missing: Vec<&str> = variant
+            .field_names
+            .iter()
+            .zip(&ordered)
+            .filter(|(_, value)| value.is_none())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            self.errors.push(
+                TypeError::new(format!(
+                    "missing field(s) {} in '{}.{}' initialization",
+                    missing.join(", "),
+                    enum_name,
+                    variant_name
+                ))
+                .with_span(span),
+            );
+            return Ok(TypeInfo::Error);
+        }
+
+        let args: Vec<&Node<Expr>> = ordered.into_iter().flatten().collect();
+        self.check_enum_variant_args(&definition, variant_name, &args, span)
     }
 
     fn type_contains_enum_generic(&self, ty: &Type, generics: &[String]) -> bool {
@@ -1007,6 +2316,9 @@ impl TypeChecker {
                         .iter()
                         .any(|arg| self.type_contains_enum_generic(arg.as_ref(), generics))
             }
+            Type::Union(members) => members
+                .iter()
+                .any(|member| self.type_contains_enum_generic(member.as_ref(), generics)),
         }
     }
 
@@ -1083,6 +2395,8 @@ impl TypeChecker {
                     }
                 }
             }
+            // Which member a value belongs to is only known at runtime
+            Type::Union(_) => {}
         }
     }
 
@@ -1277,37 +2591,282 @@ impl TypeChecker {
                     }
                 }
             }
-            Pattern::Wildcard | Pattern::Literal(_) => {
+            Pattern::Dict { entries } => {
+                if let TypeInfo::Dict { value, .. } = ty {
+                    for (_, pattern) in entries {
+                        self.bind_pattern_variables(pattern, value);
+                    }
+                }
+            }
+            Pattern::Binding { name, pattern } => {
+                self.context.insert_variable(name.clone(), ty.clone());
+                self.bind_pattern_variables(pattern, ty);
+            }
+            Pattern::Or(alternatives) => {
+                // Alternatives are checked to bind the same names, so the first
+                // one determines the bindings
+                if let Some(first) = alternatives.first() {
+                    self.bind_pattern_variables(first, ty);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {
                 // No variables to bind
             }
         }
     }
 
-    fn validate_pattern_against_type(&mut self, pattern: &Node<Pattern>, ty: &TypeInfo) {
-        match pattern.as_ref() {
-            Pattern::Wildcard | Pattern::Identifier(_) => {
-                // Wildcard and Identifier binds any type
+    /// Type of a literal used in a pattern. Whole numbers that fit in 32 bits
+    /// are `i32`, which is compatible with every numeric type.
+    fn literal_pattern_type(lit: &Literal) -> TypeInfo {
+        match lit {
+            Literal::String(_) | Literal::Checked { .. } => TypeInfo::Str,
+            Literal::Bytes(_) => TypeInfo::List(Box::new(TypeInfo::I64)),
+            Literal::Symbol(_) => TypeInfo::Symbol,
+            Literal::Number(n) => {
+                if n.value.fract() == 0.0
+                    && n.value >= i32::MIN as f64
+                    && n.value <= i32::MAX as f64
+                {
+                    TypeInfo::I32
+                } else {
+                    TypeInfo::F64
+                }
             }
-            Pattern::Literal(lit) => {
-                // Check literal type matches expected type
-                let lit_type = match lit.as_ref() {
-                    Literal::String(_) => TypeInfo::Str,
-                    Literal::Number(n) => {
-                        if n.value.fract() == 0.0
-                            && n.value >= i32::MIN as f64
-                            && n.value <= i32::MAX as f64
-                        {
-                            TypeInfo::I32
-                        } else {
-                            TypeInfo::F64
-                        }
-                    }
-                    Literal::Bool(_) => TypeInfo::Bool,
-                    Literal::None | Literal::Unit => TypeInfo::Unit,
-                };
+            Literal::Bool(_) => TypeInfo::Bool,
+            Literal::None | Literal::Unit => TypeInfo::Unit,
+        }
+    }
 
-                if !lit_type.is_compatible_with(ty) {
-                    self.errors.push(
+    /// Reports the variants of an enum `match` that no arm handles. Arms with a
+    /// guard never count, since the guard may be false.
+    fn check_match_exhaustiveness(
+        &mut self,
+        value_type: &TypeInfo,
+        arms: &[Node<MatchArm>],
+        span: Span,
+    ) {
+        let enum_name = match value_type {
+            TypeInfo::Enum { name, .. } => name,
+            TypeInfo::Generic { base, .. } => base,
+            _ => return,
+        };
+        let Some(definition) = self.context.get_enum(enum_name) else {
+            return;
+        };
+
+        let mut covered = BTreeSet::new();
+        for arm in arms.iter().filter(|arm| arm.as_ref().guard.is_none()) {
+            if Self::covers_variants(arm.as_ref().pattern.as_ref(), enum_name, &mut covered) {
+                return;
+            }
+        }
+
+        let missing: Vec<_> = definition
+            .variants
+            .iter()
+            .filter(|variant| !covered.contains(variant.name.as_str()))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = missing
+            .iter()
+            .map(|variant| format!("{}.{}", enum_name, variant.name))
+            .collect();
+        let arms: Vec<String> = missing
+            .iter()
+            .map(|variant| {
+                if variant.fields.is_empty() {
+                    format!("case {}.{}:", enum_name, variant.name)
+                } else {
+                    let fields = vec!["_"; variant.fields.len()];
+                    format!(
+                        "case {}.{}({}):",
+                        enum_name,
+                        variant.name,
+                        fields.join(", ")
+                    )
+                }
+            })
+            .collect();
+        self.errors.push(
+            TypeError::new(format!(
+                "non-exhaustive match on '{}': {} not covered",
+                enum_name,
+                names.join(", ")
+            ))
+            .with_span(span)
+            .with_hint(format!(
+                "add an arm for each missing variant or a `case _:` catch-all, e.g. `{}`",
+                arms[0]
+            ))
+            .with_suggestion(arms.join("\n")),
+        );
+    }
+
+    /// Reports the members of a union that no arm of `match type_of(var):`
+    /// handles
+    fn check_type_match_exhaustiveness(
+        &mut self,
+        var: &str,
+        members: &[TypeInfo],
+        arms: &[Node<MatchArm>],
+        span: Span,
+    ) {
+        let mut named = Vec::new();
+        for arm in arms.iter().filter(|arm| arm.as_ref().guard.is_none()) {
+            let pattern = arm.as_ref().pattern.as_ref();
+            if Self::is_irrefutable(pattern) {
+                return;
+            }
+            named.extend(Self::type_pattern_names(pattern));
+        }
+
+        let missing: Vec<&TypeInfo> = members
+            .iter()
+            .filter(|member| {
+                !named.iter().any(|name| {
+                    type_of_name(name).is_some_and(|tested| type_of_matches(member, &tested))
+                })
+            })
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = missing.iter().map(|member| member.display_name()).collect();
+        let mut arms: Vec<String> = Vec::new();
+        for member in &missing {
+            let arm = match type_of_label(member) {
+                Some(label) => format!("case \"{label}\":"),
+                None => "case _:".to_string(),
+            };
+            if !arms.contains(&arm) {
+                arms.push(arm);
+            }
+        }
+        self.errors.push(
+            TypeError::new(format!(
+                "non-exhaustive match on type_of({}): {} not covered",
+                var,
+                names.join(", ")
+            ))
+            .with_span(span)
+            .with_hint(format!(
+                "add an arm for each missing type or a `case _:` catch-all, e.g. `{}`",
+                arms[0]
+            ))
+            .with_suggestion(arms.join("\n")),
+        );
+    }
+
+    /// `x` when `value` is `type_of(x)`
+    fn type_of_subject(value: &Node<Expr>) -> Option<String> {
+        let Expr::Call { func, args } = value.as_ref() else {
+            return None;
+        };
+        match (func.as_ref().as_ref(), args.as_slice()) {
+            (Expr::Identifier(func), [arg]) if func == "type_of" => match arg.as_ref() {
+                Expr::Identifier(var) => Some(var.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type names a `match type_of(x):` arm tests for
+    fn type_pattern_names(pattern: &Pattern) -> Vec<&str> {
+        match pattern {
+            Pattern::Literal(literal) => match literal.as_ref() {
+                Literal::String(name) => vec![name.as_str()],
+                _ => Vec::new(),
+            },
+            Pattern::Or(alternatives) => alternatives
+                .iter()
+                .flat_map(|alternative| Self::type_pattern_names(alternative.as_ref()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The type of a variable of type `current` inside a `match type_of(x):`
+    /// arm with `pattern`
+    fn narrow_by_type_pattern(current: &TypeInfo, pattern: &Pattern) -> Option<TypeInfo> {
+        let names = Self::type_pattern_names(pattern);
+        if names.is_empty() {
+            return None;
+        }
+        let mut narrowed = Vec::new();
+        for name in names {
+            let (matching, _) = Self::split_by_test(current, &type_of_name(name)?)?;
+            narrowed.extend(matching);
+        }
+        (!narrowed.is_empty()).then(|| TypeInfo::union(narrowed))
+    }
+
+    /// Adds the variants of `enum_name` that `pattern` always matches to
+    /// `covered`. Returns `true` when the pattern matches every value.
+    fn covers_variants<'a>(
+        pattern: &'a Pattern,
+        enum_name: &str,
+        covered: &mut BTreeSet<&'a str>,
+    ) -> bool {
+        match pattern {
+            Pattern::Wildcard | Pattern::Identifier(_) => true,
+            Pattern::Binding { pattern, .. } => {
+                Self::covers_variants(pattern.as_ref().as_ref(), enum_name, covered)
+            }
+            Pattern::Or(alternatives) => alternatives.iter().fold(false, |all, alternative| {
+                Self::covers_variants(alternative.as_ref(), enum_name, covered) || all
+            }),
+            Pattern::EnumVariant {
+                enum_name: pattern_enum,
+                variant,
+                fields,
+            } => {
+                if pattern_enum == enum_name
+                    && fields
+                        .iter()
+                        .all(|field| Self::is_irrefutable(field.as_ref()))
+                {
+                    covered.insert(variant.as_str());
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn is_irrefutable(pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Wildcard | Pattern::Identifier(_) => true,
+            Pattern::Binding { pattern, .. } => Self::is_irrefutable(pattern.as_ref().as_ref()),
+            Pattern::Or(alternatives) => alternatives
+                .iter()
+                .any(|alternative| Self::is_irrefutable(alternative.as_ref())),
+            _ => false,
+        }
+    }
+
+    fn validate_pattern_against_type(&mut self, pattern: &Node<Pattern>, ty: &TypeInfo) {
+        match pattern.as_ref() {
+            Pattern::Wildcard | Pattern::Identifier(_) => {
+                // Wildcard and Identifier binds any type
+            }
+            Pattern::Literal(lit) if matches!(lit.as_ref(), Literal::Bytes(_)) => {
+                self.errors.push(
+                    TypeError::new("bytes literals cannot be used as patterns".to_string())
+                        .with_hint("compare the value with `==` in a guard instead".to_string())
+                        .with_span(*pattern.span()),
+                );
+            }
+            Pattern::Literal(lit) => {
+                // Check literal type matches expected type
+                let lit_type = Self::literal_pattern_type(lit.as_ref());
+
+                if !lit_type.is_compatible_with(ty) {
+                    self.errors.push(
                         TypeError::new(format!(
                             "literal pattern type {} does not match expected type {}",
                             lit_type.display_name(),
@@ -1399,6 +2958,7 @@ impl TypeChecker {
                     TypeInfo::Struct {
                         name: struct_name,
                         fields: struct_fields,
+                        args,
                     } => {
                         if name != struct_name {
                             self.errors.push(
@@ -1413,9 +2973,14 @@ impl TypeChecker {
 
                         // Check each field pattern
                         for (field_name, field_pattern) in fields {
-                            if let Some(field_type) = struct_fields.get(field_name) {
+                            if let Some(field_type) = self.context.struct_field_type(
+                                struct_name,
+                                args,
+                                struct_fields,
+                                field_name,
+                            ) {
                                 if let Some(pattern) = field_pattern {
-                                    self.validate_pattern_against_type(pattern, field_type);
+                                    self.validate_pattern_against_type(pattern, &field_type);
                                 }
                                 // If no pattern, just bind the field - no validation needed
                             } else {
@@ -1441,6 +3006,42 @@ impl TypeChecker {
                     }
                 }
             }
+            Pattern::Dict { entries } => {
+                let TypeInfo::Dict { key, value } = ty else {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "cannot match dict pattern against non-dict type {}",
+                            ty.display_name()
+                        ))
+                        .with_span(*pattern.span()),
+                    );
+                    return;
+                };
+
+                for (index, (key_lit, value_pattern)) in entries.iter().enumerate() {
+                    let key_type = Self::literal_pattern_type(key_lit.as_ref());
+                    if !key_type.is_compatible_with(key) {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "dict pattern key of type {} does not match key type {}",
+                                key_type.display_name(),
+                                key.display_name()
+                            ))
+                            .with_span(*key_lit.span()),
+                        );
+                    }
+                    if entries[..index]
+                        .iter()
+                        .any(|(earlier, _)| earlier.as_ref() == key_lit.as_ref())
+                    {
+                        self.errors.push(
+                            TypeError::new("duplicate key in dict pattern".to_string())
+                                .with_span(*key_lit.span()),
+                        );
+                    }
+                    self.validate_pattern_against_type(value_pattern, value);
+                }
+            }
             Pattern::Array { patterns, rest } => {
                 // Check that value type is a list/array
                 match ty {
@@ -1473,16 +3074,146 @@ impl TypeChecker {
                     );
                 }
             }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let (Literal::Number(start), Literal::Number(end)) = (start.as_ref(), end.as_ref())
+                else {
+                    self.errors.push(
+                        TypeError::new("range pattern bounds must be numbers".to_string())
+                            .with_span(*pattern.span()),
+                    );
+                    return;
+                };
+
+                let bound_type = if start.is_float_literal || end.is_float_literal {
+                    TypeInfo::F64
+                } else {
+                    TypeInfo::I32
+                };
+                if !bound_type.is_compatible_with(ty) {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "range pattern of type {} does not match expected type {}",
+                            bound_type.display_name(),
+                            ty.display_name()
+                        ))
+                        .with_span(*pattern.span()),
+                    );
+                }
+
+                let is_empty = if *inclusive {
+                    start.value > end.value
+                } else {
+                    start.value >= end.value
+                };
+                if is_empty {
+                    let op = if *inclusive { "..=" } else { ".." };
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "range pattern {}{}{} can never match",
+                            start.value, op, end.value
+                        ))
+                        .with_span(*pattern.span())
+                        .with_hint(if *inclusive {
+                            "an inclusive range needs `start <= end`".to_string()
+                        } else {
+                            "an exclusive range needs `start < end`; use `..=` to include the end"
+                                .to_string()
+                        }),
+                    );
+                }
+            }
+            Pattern::Binding { pattern: inner, .. } => {
+                self.validate_pattern_against_type(inner, ty);
+            }
+            Pattern::Or(alternatives) => {
+                let mut expected: Option<Vec<&str>> = None;
+                for alternative in alternatives {
+                    self.validate_pattern_against_type(alternative, ty);
+
+                    let mut names = alternative.as_ref().bindings();
+                    names.sort_unstable();
+                    match &expected {
+                        None => expected = Some(names),
+                        Some(expected) if *expected != names => {
+                            self.errors.push(
+                                TypeError::new(
+                                    "all alternatives of an or-pattern must bind the same names"
+                                        .to_string(),
+                                )
+                                .with_span(*alternative.span())
+                                .with_hint(format!(
+                                    "the first alternative binds [{}], this one binds [{}]",
+                                    expected.join(", "),
+                                    names.join(", ")
+                                )),
+                            );
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ensures a `with` resource exposes a zero-argument exit method
+    fn check_with_resource(&mut self, resource: &TypeInfo, span: Span) {
+        let struct_name = match resource {
+            TypeInfo::Unknown | TypeInfo::Error => return,
+            TypeInfo::Alias { underlying, .. } => {
+                self.check_with_resource(underlying, span);
+                return;
+            }
+            TypeInfo::Struct { name, .. } => Some(name),
+            _ => None,
+        };
+
+        let has_exit_method = struct_name.is_some_and(|name| {
+            with_exit_method(|method| {
+                match self.context.get_function(&format!("{}.{}", name, method)) {
+                    Some(TypeInfo::Function { params, .. }) => Some(params.len()),
+                    _ => None,
+                }
+            })
+            .is_some()
+        });
+
+        if !has_exit_method {
+            self.errors.push(
+                TypeError::new(format!(
+                    "type {} cannot be used in a `with` statement",
+                    resource.display_name()
+                ))
+                .with_hint(
+                    "`with` resources must define `fn __exit__(self)` or `fn close(self)`"
+                        .to_string(),
+                )
+                .with_span(span),
+            );
         }
     }
 
-    /// Type check a block
+    fn check_loop_body(&mut self, label: Option<&Node<String>>, body: &Node<Block>) -> Result<()> {
+        self.loop_labels
+            .push(label.map(|label| label.as_ref().clone()));
+        let result = self.check_block(body);
+        self.loop_labels.pop();
+        result.map(|_| ())
+    }
+
+    /// The type of the last statement of `block`, or `never` when one of its
+    /// statements never completes
     fn check_block(&mut self, block: &Node<Block>) -> Result<TypeInfo> {
         let mut last_type = TypeInfo::Unit;
+        let mut diverged = false;
         for statement in &block.as_ref().statements {
             last_type = self.check_statement(statement)?;
+            diverged |= matches!(last_type, TypeInfo::Never);
         }
-        Ok(last_type)
+        Ok(if diverged { TypeInfo::Never } else { last_type })
     }
 
     /// Type check a statement
@@ -1493,13 +3224,11 @@ impl TypeChecker {
                 let expr_type = self.infer_expr_type(expr)?;
                 if let Some(annotation) = ty {
                     let annotated_type = self.context.type_from_annotation(annotation);
-                    if !expr_type.is_compatible_with(&annotated_type) {
+                    let expr_type = self.adapt_int_literal(expr, expr_type, &annotated_type);
+                    if !self.accepts(&expr_type, &annotated_type) {
                         self.errors.push(
-                            TypeError::new(format!(
-                                "type mismatch: expected {}, got {}",
-                                annotated_type.display_name(),
-                                expr_type.display_name()
-                            ))
+                            TypeError::new("type mismatch".to_string())
+                            .with_mismatch(&annotated_type, &expr_type)
                             .with_hint(format!(
                                 "The variable `{}` is declared as `{}`, but the initializer has type `{}`",
                                 name,
@@ -1521,7 +3250,18 @@ impl TypeChecker {
                 }
                 Ok(TypeInfo::Unit)
             }
+            Statement::Const { name, ty, expr, .. } => {
+                self.check_const(name, ty.as_ref(), expr, *span)?;
+                Ok(TypeInfo::Unit)
+            }
             Statement::Assignment { name, expr } => {
+                if self.const_values.contains_key(name.as_ref()) {
+                    self.errors.push(
+                        TypeError::new(format!("cannot assign to constant `{}`", name))
+                            .with_hint("declare it with `let` if it needs to change".to_string())
+                            .with_span(*span),
+                    );
+                }
                 let var_type = self
                     .context
                     .get_variable(name.as_ref())
@@ -1537,13 +3277,10 @@ impl TypeChecker {
                     .clone();
 
                 let expr_type = self.infer_expr_type(expr)?;
-                if !expr_type.is_compatible_with(&var_type) {
-                    self.errors.push(TypeError::new(format!(
-                        "cannot assign {} to {} (expected {})",
-                        expr_type.display_name(),
-                        name,
-                        var_type.display_name()
-                    ))
+                let expr_type = self.adapt_int_literal(expr, expr_type, &var_type);
+                if !self.accepts(&expr_type, &var_type) {
+                    self.errors.push(TypeError::new(format!("cannot assign to `{}`: type mismatch", name))
+                    .with_mismatch(&var_type, &expr_type)
                     .with_hint(format!("The variable `{}` is declared as `{}`, but you're trying to assign a value of type `{}`", name, var_type.display_name(), expr_type.display_name()))
                     .with_help("Make sure the types match or are compatible (e.g., i32 can be promoted to i64 or f64)".to_string())
                     .with_span(*span));
@@ -1567,23 +3304,59 @@ impl TypeChecker {
                     );
                 }
 
-                self.check_block(then_block)?;
-                for (_, block) in elif_blocks {
-                    self.check_block(block)?;
-                }
-                if let Some(block) = else_block {
-                    self.check_block(block)?;
+                // Each branch sees what its condition, and the falsity of the
+                // conditions before it, says about dynamically typed variables
+                let narrowing = self.narrowing_of(cond);
+                let then_type = self
+                    .with_narrowing(&narrowing.then, |checker| checker.check_block(then_block))?;
+                let mut diverges = matches!(then_type, TypeInfo::Never);
+                let mut ruled_out = narrowing.otherwise;
+                for (elif_cond, block) in elif_blocks {
+                    let (otherwise, block_type) = self.with_narrowing(&ruled_out, |checker| {
+                        // Narrowing a union builds on what is already ruled out
+                        let narrowing = checker.narrowing_of(elif_cond);
+                        let cond_type = checker.infer_expr_type(elif_cond)?;
+                        if !cond_type.is_compatible_with(&TypeInfo::Bool) {
+                            checker.errors.push(
+                                TypeError::new(format!(
+                                    "elif condition must be bool, got {}",
+                                    cond_type.display_name()
+                                ))
+                                .with_span(*elif_cond.span()),
+                            );
+                        }
+                        let block_type = checker.with_narrowing(&narrowing.then, |checker| {
+                            checker.check_block(block)
+                        })?;
+                        Ok((narrowing.otherwise, block_type))
+                    })?;
+                    diverges &= matches!(block_type, TypeInfo::Never);
+                    ruled_out.extend(otherwise);
                 }
-                Ok(TypeInfo::Unit)
+                // Without an `else` the statement completes when no condition holds
+                let else_type = match else_block {
+                    Some(block) => {
+                        self.with_narrowing(&ruled_out, |checker| checker.check_block(block))?
+                    }
+                    None => TypeInfo::Unit,
+                };
+                diverges &= matches!(else_type, TypeInfo::Never);
+                Ok(if diverges {
+                    TypeInfo::Never
+                } else {
+                    TypeInfo::Unit
+                })
             }
             Statement::For {
                 var,
                 iterable,
                 body,
-                ..
+                label,
+                is_await,
             } => {
                 let iter_type = self.infer_expr_type(iterable)?;
                 let element_type = match &iter_type {
+                    _ if *is_await => self.stream_element_type(&iter_type, *span),
                     TypeInfo::List(elem) => elem.as_ref().clone(),
                     TypeInfo::Dict { value, .. } => value.as_ref().clone(),
                     TypeInfo::Str => TypeInfo::Str,
@@ -1597,13 +3370,17 @@ impl TypeChecker {
                         }
                     }
                     _ => {
-                        self.errors.push(
-                            TypeError::new(format!(
-                                "cannot iterate over type {}",
-                                iter_type.display_name()
-                            ))
-                            .with_span(*span),
-                        );
+                        let mut error = TypeError::new(format!(
+                            "cannot iterate over type {}",
+                            iter_type.display_name()
+                        ))
+                        .with_span(*span);
+                        if iter_type.stream_element().is_some() {
+                            error = error.with_hint(
+                                "use `for await` to receive from a channel or stream".to_string(),
+                            );
+                        }
+                        self.errors.push(error);
                         TypeInfo::Unknown
                     }
                 };
@@ -1611,7 +3388,7 @@ impl TypeChecker {
                 let previous = self.context.remove_variable(var.as_ref());
                 self.context
                     .insert_variable(var.as_ref().clone(), element_type);
-                self.check_block(body)?;
+                self.check_loop_body(label.as_ref(), body)?;
                 match previous {
                     Some(prev) => {
                         self.context.insert_variable(var.as_ref().clone(), prev);
@@ -1622,7 +3399,7 @@ impl TypeChecker {
                 }
                 Ok(TypeInfo::Unit)
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, label } => {
                 let cond_type = self.infer_expr_type(cond)?;
                 if !cond_type.is_compatible_with(&TypeInfo::Bool) {
                     self.errors.push(
@@ -1633,7 +3410,70 @@ impl TypeChecker {
                         .with_span(*span),
                     );
                 }
+                self.check_loop_body(label.as_ref(), body)?;
+                let label = label.as_ref().map(|label| label.as_ref().as_str());
+                if loops_forever(cond.as_ref(), body.as_ref(), label) {
+                    Ok(TypeInfo::Never)
+                } else {
+                    Ok(TypeInfo::Unit)
+                }
+            }
+            Statement::With { expr, alias, body } => {
+                let resource_type = self.infer_expr_type(expr)?;
+                self.check_with_resource(&resource_type, *span);
+
+                let Some(alias) = alias else {
+                    self.check_block(body)?;
+                    return Ok(TypeInfo::Unit);
+                };
+
+                let previous = self.context.remove_variable(alias.as_ref());
+                self.context
+                    .insert_variable(alias.as_ref().clone(), resource_type);
                 self.check_block(body)?;
+                match previous {
+                    Some(prev) => {
+                        self.context.insert_variable(alias.as_ref().clone(), prev);
+                    }
+                    None => {
+                        self.context.remove_variable(alias.as_ref());
+                    }
+                }
+                Ok(TypeInfo::Unit)
+            }
+            Statement::Assert { cond, message } => {
+                let cond_type = self.infer_expr_type(cond)?;
+                if !cond_type.is_compatible_with(&TypeInfo::Bool) {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "assert condition must be bool, got {}",
+                            cond_type.display_name()
+                        ))
+                        .with_span(*cond.span()),
+                    );
+                }
+                if let Some(message) = message {
+                    let message_type = self.infer_expr_type(message)?;
+                    if !message_type.is_compatible_with(&TypeInfo::Str) {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "assert message must be string, got {}",
+                                message_type.display_name()
+                            ))
+                            .with_span(*message.span()),
+                        );
+                    }
+                }
+                Ok(TypeInfo::Unit)
+            }
+            Statement::Defer(expr) => {
+                self.infer_expr_type(expr)?;
+                if self.current_function_return_type.is_none() {
+                    self.errors.push(
+                        TypeError::new("defer statement outside of function".to_string())
+                            .with_span(*span),
+                    );
+                }
                 Ok(TypeInfo::Unit)
             }
             Statement::Return(expr) => {
@@ -1641,15 +3481,14 @@ impl TypeChecker {
                     let expr_type = self.infer_expr_type(expr)?;
 
                     // Check return type matches function signature
-                    if let Some(expected_return_type) = &self.current_function_return_type {
-                        if !expr_type.is_compatible_with(expected_return_type) {
+                    if let Some(expected_return_type) = self.current_function_return_type.clone() {
+                        let expr_type =
+                            self.adapt_int_literal(expr, expr_type, &expected_return_type);
+                        if !self.accepts(&expr_type, &expected_return_type) {
                             self.errors.push(
-                                TypeError::new(format!(
-                                    "return type mismatch: expected {}, got {}",
-                                    expected_return_type.display_name(),
-                                    expr_type.display_name()
-                                ))
-                                .with_span(*span),
+                                TypeError::new("return type mismatch".to_string())
+                                    .with_mismatch(&expected_return_type, &expr_type)
+                                    .with_span(*span),
                             );
                         }
                     } else {
@@ -1662,7 +3501,9 @@ impl TypeChecker {
                 } else {
                     // Bare return - check if function expects unit
                     if let Some(expected_return_type) = &self.current_function_return_type {
-                        if !expected_return_type.is_compatible_with(&TypeInfo::Unit) {
+                        if !expected_return_type.is_compatible_with(&TypeInfo::Unit)
+                            || matches!(expected_return_type, TypeInfo::Never)
+                        {
                             self.errors.push(
                                 TypeError::new(format!(
                                     "bare return in function that expects return type {}",
@@ -1679,10 +3520,17 @@ impl TypeChecker {
                         );
                     }
                 }
-                Ok(TypeInfo::Unit)
+                Ok(TypeInfo::Never)
             }
-            Statement::Function(_) => {
-                // Functions are handled separately
+            Statement::Function(function) => {
+                // Top-level functions are handled by `check_program`. A nested
+                // function is visible to itself and to the rest of the enclosing
+                // function, and sees the enclosing function's variables.
+                let sig = self.infer_function_signature(function);
+                self.context
+                    .functions
+                    .insert(function.as_ref().name.clone(), sig);
+                self.check_function(function)?;
                 Ok(TypeInfo::Unit)
             }
             Statement::Expr(expr) => {
@@ -1690,9 +3538,27 @@ impl TypeChecker {
                 // Expression statements are allowed (e.g., function calls)
                 Ok(expr_type)
             }
-            Statement::Break | Statement::Continue => {
-                // These are handled by loop context
-                Ok(TypeInfo::Unit)
+            Statement::Break(label) | Statement::Continue(label) => {
+                if let Some(label) = label
+                    && !self
+                        .loop_labels
+                        .iter()
+                        .any(|l| l.as_deref() == Some(label.as_ref().as_str()))
+                {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "use of undeclared loop label '{}'",
+                            label.as_ref()
+                        ))
+                        .with_span(*label.span())
+                        .with_hint(format!(
+                            "label an enclosing loop with `{}: for ...` or `{}: while ...`",
+                            label.as_ref(),
+                            label.as_ref()
+                        )),
+                    );
+                }
+                Ok(TypeInfo::Never)
             }
             Statement::Pass => {
                 // No-op
@@ -1711,36 +3577,305 @@ impl TypeChecker {
                 // Enums are handled during the module pass
                 Ok(TypeInfo::Unit)
             }
-            Statement::TypeAlias { .. } => {
-                // Type aliases are handled at the module level
+            Statement::TypeAlias { .. } | Statement::Protocol { .. } | Statement::Init(_) => {
+                // Type aliases, protocols and init blocks are handled at the
+                // module level
                 Ok(TypeInfo::Unit)
             }
             Statement::Block(block) => self.check_block(block),
         }
     }
 
-    /// Infer the type of an expression
-    pub fn infer_expr_type(&mut self, expr: &Node<Expr>) -> Result<TypeInfo> {
-        let span = expr.span();
-        let ty = (|| -> Result<TypeInfo> {
-            match expr.as_ref() {
-                Expr::Literal(lit) => Ok(match lit.as_ref() {
-                    Literal::Number(num) => {
-                        if num.is_float_literal {
-                            TypeInfo::F64
-                        } else {
-                            TypeInfo::I64
-                        }
-                    }
-                    Literal::String(_) => TypeInfo::Str,
-                    Literal::Bool(_) => TypeInfo::Bool,
-                    Literal::None | Literal::Unit => TypeInfo::Unit,
-                }),
-                Expr::Identifier(name) => {
-                    if let Some(var_type) = self.context.get_variable(name) {
-                        Ok(var_type.clone())
-                    } else {
-                        if self.registry.is_some_and(|r| r.has_module(name)) {
+    /// Type of a number literal, checking that it fits its suffix
+    /// Element type received by `for await` from a value of type `iter_type`
+    fn stream_element_type(&mut self, iter_type: &TypeInfo, span: Span) -> TypeInfo {
+        if let Some(element) = iter_type.stream_element() {
+            return element;
+        }
+        let message = match iter_type {
+            TypeInfo::Unknown | TypeInfo::Error => return TypeInfo::Unknown,
+            // Untyped channel handles from the FFI are plain integers
+            TypeInfo::I64 => "cannot tell what this stream yields".to_string(),
+            other => format!(
+                "`for await` needs a channel or stream, got {}",
+                other.display_name()
+            ),
+        };
+        self.errors.push(
+            TypeError::new(message)
+                .with_span(span)
+                .with_hint(
+                    "create it with task.channel_int/_float/_string, task.bounded_channel_int/_float/_string or task.stream_int/_float/_string, or annotate it as Channel<int>".to_string(),
+                ),
+        );
+        TypeInfo::Unknown
+    }
+
+    fn number_literal_type(&mut self, num: &NumberLiteral, span: Span) -> TypeInfo {
+        let negated = self.negated_literal.take() == Some(span);
+        let value = if negated {
+            -num.int_value()
+        } else {
+            num.int_value()
+        };
+        if let Some(suffix) = num.suffix
+            && let Some((min, max)) = suffix.int_range()
+        {
+            if value < min || value > max {
+                self.errors.push(
+                    TypeError::new(format!("literal `{value}` does not fit in {suffix}"))
+                        .with_hint(format!("{suffix} holds values from {min} to {max}"))
+                        .with_span(span),
+                );
+            }
+        } else if num.suffix.is_none() && !num.is_float_literal && i64::try_from(value).is_err() {
+            // Withdrawn by `adapt_int_literal` if the literal is used as a u64
+            self.errors.push(
+                TypeError::new(INT_LITERAL_OUT_OF_RANGE.to_string())
+                    .with_hint(format!(
+                        "i64 holds values from {} to {}",
+                        i64::MIN,
+                        i64::MAX
+                    ))
+                    .with_help("add a `u64` suffix or annotation for larger values".to_string())
+                    .with_span(span),
+            );
+        }
+        match num.suffix {
+            Some(NumberSuffix::I8) => TypeInfo::I8,
+            Some(NumberSuffix::I16) => TypeInfo::I16,
+            Some(NumberSuffix::I32) => TypeInfo::I32,
+            Some(NumberSuffix::U8) => TypeInfo::U8,
+            Some(NumberSuffix::U16) => TypeInfo::U16,
+            Some(NumberSuffix::U32) => TypeInfo::U32,
+            Some(NumberSuffix::U64) => TypeInfo::U64,
+            _ if num.is_float_literal => TypeInfo::F64,
+            _ => TypeInfo::I64,
+        }
+    }
+
+    /// Checks `const name: ty = expr`. The initializer must be computable at
+    /// compile time; an integer constant takes its declared integer type when
+    /// the value fits, so `const LIMIT: u8 = 2 * 100` needs no suffix.
+    fn check_const(
+        &mut self,
+        name: &Node<String>,
+        ty: Option<&Node<Type>>,
+        expr: &Node<Expr>,
+        span: Span,
+    ) -> Result<()> {
+        let expr_type = self.infer_expr_type(expr)?;
+        let declared = ty.map(|annotation| self.context.type_from_annotation(annotation));
+        let value = eval_const_expr(expr.as_ref(), &|path| self.const_values.get(path).cloned());
+        let value = value.and_then(|value| match &declared {
+            Some(declared) => value.convert_to(declared),
+            None => Ok(value),
+        });
+        let const_type = match value {
+            Ok(value) => {
+                let value_type = value.type_info();
+                let const_type = declared.unwrap_or_else(|| expr_type.clone());
+                let fits = (value_type.is_integer() && const_type.is_integer())
+                    || expr_type.is_compatible_with(&const_type);
+                if fits {
+                    self.record_expr_type(expr, &const_type);
+                    self.const_values.insert(name.as_ref().clone(), value);
+                } else {
+                    self.errors.push(
+                        TypeError::new("type mismatch".to_string())
+                            .with_mismatch(&const_type, &expr_type)
+                            .with_hint(format!(
+                                "The constant `{}` is declared as `{}`",
+                                name,
+                                const_type.display_name()
+                            ))
+                            .with_span(*expr.span()),
+                    );
+                }
+                const_type
+            }
+            Err(reason) => {
+                self.errors.push(
+                    TypeError::new(format!("invalid constant `{}`: {reason}", name))
+                        .with_hint(
+                            "constants are computed while compiling, from literals, operators, casts, \
+                             other constants and pure builtins such as `len` and `str.upper`"
+                                .to_string(),
+                        )
+                        .with_span(span),
+                );
+                declared.unwrap_or(expr_type)
+            }
+        };
+        self.context
+            .insert_variable(name.as_ref().clone(), const_type);
+        Ok(())
+    }
+
+    /// Reports a default value made of constants whose computation fails,
+    /// since codegen folds such defaults to their value while compiling
+    fn check_default_value(&mut self, param: &str, default: &Node<Expr>) {
+        let value = eval_const_expr(default.as_ref(), &|path| {
+            self.const_values.get(path).cloned()
+        });
+        if let Err(reason) = value
+            && (reason == CONST_OVERFLOW || reason == CONST_DIVISION_BY_ZERO)
+        {
+            self.errors.push(
+                TypeError::new(format!(
+                    "invalid default value for parameter `{param}`: {reason}"
+                ))
+                .with_hint(
+                    "defaults built from literals and constants are computed while compiling"
+                        .to_string(),
+                )
+                .with_span(*default.span()),
+            );
+        }
+    }
+
+    /// Value of an integer literal written without a suffix, such as `200` or
+    /// `-5`. Only these literals adapt to the integer type they are used as.
+    fn unsuffixed_int_literal(expr: &Node<Expr>) -> Option<i128> {
+        match expr.as_ref() {
+            Expr::Literal(lit) => match lit.as_ref() {
+                Literal::Number(num) if num.suffix.is_none() && !num.is_float_literal => {
+                    Some(num.int_value())
+                }
+                _ => None,
+            },
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                expr,
+            } => Self::unsuffixed_int_literal(expr).map(|value| -value),
+            _ => None,
+        }
+    }
+
+    /// Gives an unsuffixed integer literal the numeric type `target` when it is
+    /// used where that type is expected, so `let b: u8 = 200` needs no suffix
+    /// and `ratio * 2` multiplies two floats. Returns the literal's type
+    /// afterwards; other expressions keep `ty`.
+    fn adapt_int_literal(
+        &mut self,
+        expr: &Node<Expr>,
+        ty: TypeInfo,
+        target: &TypeInfo,
+    ) -> TypeInfo {
+        let Some(value) = Self::unsuffixed_int_literal(expr) else {
+            return ty;
+        };
+        if *target == TypeInfo::F64 {
+            // Integers up to 2^53 are exactly floats; larger literals keep
+            // their integer type and need `as`
+            if value.unsigned_abs() > 1 << 53 {
+                return ty;
+            }
+            self.record_literal_type(expr, target);
+            return TypeInfo::F64;
+        }
+        let Some((min, max)) = target.int_range() else {
+            return ty;
+        };
+        if *target == ty {
+            return ty;
+        }
+        if i64::try_from(value).is_err() {
+            // The literal is checked against `target` instead
+            let span = *expr.span();
+            self.errors.retain(|error| {
+                error.message != INT_LITERAL_OUT_OF_RANGE
+                    || !error
+                        .span
+                        .is_some_and(|at| at.start() >= span.start() && at.end() <= span.end())
+            });
+        }
+        if value < min || value > max {
+            self.errors.push(
+                TypeError::new(format!(
+                    "literal `{value}` does not fit in {}",
+                    target.display_name()
+                ))
+                .with_hint(format!(
+                    "{} holds values from {min} to {max}",
+                    target.display_name()
+                ))
+                .with_span(*expr.span()),
+            );
+        }
+        self.record_literal_type(expr, target);
+        target.clone()
+    }
+
+    /// Records `ty` for a literal, and for the literal a negation wraps
+    fn record_literal_type(&mut self, expr: &Node<Expr>, ty: &TypeInfo) {
+        self.record_expr_type(expr, ty);
+        if let Expr::Unary { expr: inner, .. } = expr.as_ref() {
+            self.record_expr_type(inner, ty);
+        }
+    }
+
+    /// Adapts whichever operand is an unsuffixed integer literal to the
+    /// numeric type of the other one
+    fn adapt_int_operands(
+        &mut self,
+        left: &Node<Expr>,
+        left_type: TypeInfo,
+        right: &Node<Expr>,
+        right_type: TypeInfo,
+    ) -> (TypeInfo, TypeInfo) {
+        let left_type = self.adapt_int_literal(left, left_type, &right_type);
+        let right_type = self.adapt_int_literal(right, right_type, &left_type);
+        (left_type, right_type)
+    }
+
+    /// Error for numeric operands of different types that neither widens to
+    fn mixed_int_error(&mut self, left: &TypeInfo, right: &TypeInfo, span: Span) -> TypeInfo {
+        let (kind, hint) = if matches!(left, TypeInfo::F64) || matches!(right, TypeInfo::F64) {
+            (
+                "numeric",
+                "f64 cannot hold every 64-bit integer exactly; convert the integer with `as float`",
+            )
+        } else {
+            (
+                "integer",
+                "neither type holds every value of the other; convert one operand explicitly",
+            )
+        };
+        self.errors.push(
+            TypeError::new(format!(
+                "mismatched {kind} types {} and {}",
+                left.display_name(),
+                right.display_name()
+            ))
+            .with_hint(hint.to_string())
+            .with_span(span),
+        );
+        TypeInfo::Error
+    }
+
+    /// Infer the type of an expression
+    pub fn infer_expr_type(&mut self, expr: &Node<Expr>) -> Result<TypeInfo> {
+        let span = expr.span();
+        let ty = (|| -> Result<TypeInfo> {
+            match expr.as_ref() {
+                Expr::Literal(lit) => Ok(match lit.as_ref() {
+                    Literal::Number(num) => self.number_literal_type(num, *span),
+                    Literal::String(_) => TypeInfo::Str,
+                    Literal::Checked { tag, value } => {
+                        self.check_tagged_literal(tag, value, *span);
+                        TypeInfo::Str
+                    }
+                    Literal::Bytes(_) => TypeInfo::List(Box::new(TypeInfo::I64)),
+                    Literal::Symbol(_) => TypeInfo::Symbol,
+                    Literal::Bool(_) => TypeInfo::Bool,
+                    Literal::None | Literal::Unit => TypeInfo::Unit,
+                }),
+                Expr::Identifier(name) => {
+                    if let Some(var_type) = self.context.get_variable(name) {
+                        Ok(var_type.clone())
+                    } else {
+                        if self.registry.is_some_and(|r| r.has_module(name)) {
                             self.errors.push(
                                 TypeError::new(format!("module `{}` is not imported", name))
                                     .with_hint(format!("add `use {}` at the top of the file", name))
@@ -1774,24 +3909,53 @@ impl TypeChecker {
                 }
                 Expr::Binary { op, left, right } => {
                     let left_type = self.infer_expr_type(left)?;
-                    let right_type = self.infer_expr_type(right)?;
+                    // `and` only evaluates its right side when the left is true,
+                    // `or` when it is false
+                    let right_type = match op {
+                        BinaryOp::And => {
+                            let narrowed = self.narrowing_of(left).then;
+                            self.with_narrowing(&narrowed, |checker| {
+                                checker.infer_expr_type(right)
+                            })?
+                        }
+                        BinaryOp::Or => {
+                            let narrowed = self.narrowing_of(left).otherwise;
+                            self.with_narrowing(&narrowed, |checker| {
+                                checker.infer_expr_type(right)
+                            })?
+                        }
+                        _ => self.infer_expr_type(right)?,
+                    };
+                    let (left_type, right_type) = if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                        // The shift amount need not match the shifted value
+                        (left_type, right_type)
+                    } else {
+                        self.adapt_int_operands(left, left_type, right, right_type)
+                    };
 
                     match op {
                         BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
                             // Numeric operations
                             match (&left_type, &right_type) {
                                 // String concatenation (must come before numeric patterns)
-                                (TypeInfo::Str, TypeInfo::Str)
-                                | (TypeInfo::Str, TypeInfo::I64 | TypeInfo::I32)
-                                | (TypeInfo::I64 | TypeInfo::I32, TypeInfo::Str)
-                                    if matches!(op, BinaryOp::Add) =>
+                                (TypeInfo::Str, TypeInfo::Str) if matches!(op, BinaryOp::Add) => {
+                                    Ok(TypeInfo::Str)
+                                }
+                                (TypeInfo::Str, other) | (other, TypeInfo::Str)
+                                    if matches!(op, BinaryOp::Add) && other.is_integer() =>
                                 {
                                     Ok(TypeInfo::Str)
                                 }
-                                // Numeric operations
+                                // Numeric operations, on the type both operands widen to
+                                (left, right)
+                                    if left.numeric().is_some() && right.numeric().is_some() =>
+                                {
+                                    Ok(left.common_numeric_type(right).unwrap_or_else(|| {
+                                        self.mixed_int_error(&left_type, &right_type, *span)
+                                    }))
+                                }
                                 (TypeInfo::F64, _) | (_, TypeInfo::F64) => Ok(TypeInfo::F64),
                                 (TypeInfo::I64, _) | (_, TypeInfo::I64) => Ok(TypeInfo::I64),
-                                (TypeInfo::I32, TypeInfo::I32) => Ok(TypeInfo::I32),
                                 _ => {
                                     if Self::is_unknown_like(&left_type)
                                         || Self::is_unknown_like(&right_type)
@@ -1818,7 +3982,9 @@ impl TypeChecker {
                         | BinaryOp::Gt
                         | BinaryOp::GtEq => {
                             // Comparison operations return bool
-                            if left_type.is_compatible_with(&right_type) {
+                            if left_type.is_compatible_with(&right_type)
+                                || left_type.common_numeric_type(&right_type).is_some()
+                            {
                                 Ok(TypeInfo::Bool)
                             } else {
                                 self.errors.push(
@@ -1876,8 +4042,11 @@ impl TypeChecker {
                         BinaryOp::Mod => {
                             // Modulo requires integer operands
                             match (&left_type, &right_type) {
-                                (TypeInfo::I32, TypeInfo::I32) => Ok(TypeInfo::I32),
-                                (TypeInfo::I64, TypeInfo::I64) => Ok(TypeInfo::I64),
+                                (left, right) if left.is_integer() && right.is_integer() => {
+                                    Ok(left.common_int_type(right).unwrap_or_else(|| {
+                                        self.mixed_int_error(&left_type, &right_type, *span)
+                                    }))
+                                }
                                 _ => {
                                     if Self::is_unknown_like(&left_type)
                                         || Self::is_unknown_like(&right_type)
@@ -1897,9 +4066,54 @@ impl TypeChecker {
                                 }
                             }
                         }
+                        BinaryOp::BitAnd
+                        | BinaryOp::BitOr
+                        | BinaryOp::BitXor
+                        | BinaryOp::Shl
+                        | BinaryOp::Shr => {
+                            // Bitwise operations require integer operands. Shifts keep
+                            // the type of the shifted value; the others need one type
+                            // both operands widen to.
+                            match (&left_type, &right_type) {
+                                (left, right)
+                                    if left.is_integer()
+                                        && right.is_integer()
+                                        && matches!(op, BinaryOp::Shl | BinaryOp::Shr) =>
+                                {
+                                    Ok(left.clone())
+                                }
+                                (left, right) if left.is_integer() && right.is_integer() => {
+                                    Ok(left.common_int_type(right).unwrap_or_else(|| {
+                                        self.mixed_int_error(&left_type, &right_type, *span)
+                                    }))
+                                }
+                                _ => {
+                                    if Self::is_unknown_like(&left_type)
+                                        || Self::is_unknown_like(&right_type)
+                                    {
+                                        Ok(TypeInfo::Unknown)
+                                    } else {
+                                        self.errors.push(
+                                            TypeError::new(format!(
+                                                "bitwise operations require integer operands, got {} and {}",
+                                                left_type.display_name(),
+                                                right_type.display_name()
+                                            ))
+                                            .with_span(*span),
+                                        );
+                                        Ok(TypeInfo::Error)
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
                 Expr::Unary { op, expr } => {
+                    if *op == UnaryOp::Neg
+                        && matches!(expr.as_ref().as_ref(), Expr::Literal(lit) if matches!(lit.as_ref(), Literal::Number(_)))
+                    {
+                        self.negated_literal = Some(*expr.span());
+                    }
                     let expr_type = self.infer_expr_type(expr)?;
                     match op {
                         UnaryOp::Not => {
@@ -1917,15 +4131,31 @@ impl TypeChecker {
                             }
                         }
                         UnaryOp::Neg => {
-                            if expr_type.is_compatible_with(&TypeInfo::I32)
-                                || expr_type.is_compatible_with(&TypeInfo::I64)
-                                || expr_type.is_compatible_with(&TypeInfo::F64)
-                            {
+                            let unsigned = matches!(expr_type.int_layout(), Some((_, false)));
+                            if !unsigned && expr_type.is_compatible_with(&TypeInfo::F64) {
+                                Ok(expr_type)
+                            } else {
+                                let mut error = TypeError::new(format!(
+                                    "negation requires numeric operand, got {}",
+                                    expr_type.display_name()
+                                ))
+                                .with_span(*span);
+                                if unsigned {
+                                    error = error.with_hint(
+                                        "unsigned integers cannot be negative".to_string(),
+                                    );
+                                }
+                                self.errors.push(error);
+                                Ok(TypeInfo::Error)
+                            }
+                        }
+                        UnaryOp::BitNot => {
+                            if expr_type.is_integer() || Self::is_unknown_like(&expr_type) {
                                 Ok(expr_type)
                             } else {
                                 self.errors.push(
                                     TypeError::new(format!(
-                                        "negation requires numeric operand, got {}",
+                                        "bitwise not requires integer operand, got {}",
                                         expr_type.display_name()
                                     ))
                                     .with_span(*span),
@@ -1935,11 +4165,47 @@ impl TypeChecker {
                         }
                     }
                 }
+                Expr::Cast { expr, ty } => {
+                    let source = self.infer_expr_type(expr)?;
+                    let target = TypeInfo::from(ty.as_ref());
+                    if Self::is_unknown_like(&source)
+                        || matches!(source, TypeInfo::Error)
+                        || source.can_cast_to(&target)
+                    {
+                        Ok(target)
+                    } else {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "cannot cast {} to {}",
+                                source.display_name(),
+                                target.display_name()
+                            ))
+                            .with_hint(
+                                "`as` converts between numbers and booleans, and from those to str"
+                                    .to_string(),
+                            )
+                            .with_span(*span),
+                        );
+                        Ok(TypeInfo::Error)
+                    }
+                }
                 Expr::Call { func, args } => {
                     if let Some(enum_type) = self.try_eval_enum_constructor(func.as_ref(), args)? {
+                        self.check_type_bounds(&enum_type, *span);
                         return Ok(enum_type);
                     }
+                    if let Some(config_type) = self.try_eval_config_load(func, args)? {
+                        return Ok(config_type);
+                    }
+                    if let Some(reflect_type) = self.try_eval_reflect_call(func, args)? {
+                        return Ok(reflect_type);
+                    }
+                    if let Some(result_type) = self.try_eval_fallible_method(func, args)? {
+                        return Ok(result_type);
+                    }
                     let span = func.span();
+                    let mut opaque_params = Vec::new();
+                    let mut callback_owner = None;
                     let func_type = match func.as_ref().as_ref() {
                         Expr::Identifier(name) => {
                             if let Some(func) = self.context.get_function(name).cloned() {
@@ -1958,13 +4224,26 @@ impl TypeChecker {
                             // First check registry for exact FFI signatures
                             if let Some(registry) = self.registry {
                                 if let Some(symbol) = registry.resolve(&full_name) {
+                                    callback_owner = Some(full_name.clone());
+                                    opaque_params = symbol
+                                        .signature
+                                        .params
+                                        .iter()
+                                        .map(|param| matches!(param, FfiType::Opaque))
+                                        .collect();
                                     let params: Vec<TypeInfo> = symbol
                                         .signature
                                         .params
                                         .iter()
                                         .map(ffi_type_to_typeinfo)
                                         .collect();
-                                    let return_type = if full_name == "sys.getenv" {
+                                    let return_type = if let Some(element) =
+                                        channel_constructor_element(&full_name)
+                                    {
+                                        TypeInfo::channel_of(element)
+                                    } else if full_name == "semver.parse" {
+                                        TypeInfo::semver_version()
+                                    } else if full_name == "sys.getenv" {
                                         if let Some(option_enum) = self
                                             .context
                                             .build_enum_type("Option", vec![TypeInfo::Str])
@@ -1981,6 +4260,9 @@ impl TypeChecker {
                                         param_defaults: vec![false; symbol.signature.params.len()],
                                         return_type: Box::new(return_type),
                                     }
+                                } else if let Some(unmapped) = registry.unmapped(&full_name) {
+                                    self.errors.push(unmapped_ffi_error(&unmapped, *span));
+                                    return Ok(TypeInfo::Error);
                                 } else {
                                     self.context
                                         .get_function(&full_name)
@@ -2017,13 +4299,30 @@ impl TypeChecker {
                             param_defaults,
                             return_type,
                         } => {
+                            // A method of a generic struct sees the receiver's type
+                            // arguments, so `pop` on a `Stack<int>` returns `int`
+                            let receiver_generics = self.method_receiver_generics(func)?;
+                            let (params, return_type) = if receiver_generics.is_empty() {
+                                (params, return_type)
+                            } else {
+                                (
+                                    params
+                                        .iter()
+                                        .map(|param| param.substitute(&receiver_generics))
+                                        .collect(),
+                                    Box::new(return_type.substitute(&receiver_generics)),
+                                )
+                            };
                             let mut params_slice: &[TypeInfo] = &params;
                             let mut defaults_slice: &[bool] = &param_defaults;
                             let has_signature = !params.is_empty() || !param_defaults.is_empty();
 
                             if let Expr::Member { object, .. } = func.as_ref().as_ref()
                                 && let Ok(object_type) = self.infer_expr_type(object)
-                                && matches!(object_type, TypeInfo::Struct { .. })
+                                && matches!(
+                                    object_type,
+                                    TypeInfo::Struct { .. } | TypeInfo::Enum { .. }
+                                )
                                 && !params.is_empty()
                             {
                                 let self_param = &params[0];
@@ -2047,14 +4346,15 @@ impl TypeChecker {
                                 params_slice = &params[1..];
                                 defaults_slice = &param_defaults[1..];
 
-                                if let TypeInfo::Struct { name, fields } = object_type {
-                                    let inferred =
-                                        self.infer_struct_generics_from_instance(&name, &fields);
+                                if let TypeInfo::Struct { name, .. } = object_type {
                                     let method_name = match func.as_ref().as_ref() {
                                         Expr::Member { field, .. } => format!("{}.{}", name, field),
                                         _ => name.clone(),
                                     };
-                                    self.apply_method_specialization(&method_name, &inferred);
+                                    self.apply_method_specialization(
+                                        &method_name,
+                                        &receiver_generics,
+                                    );
                                 }
                             }
 
@@ -2098,24 +4398,82 @@ impl TypeChecker {
                                 for (i, (arg, param_type)) in
                                     args.iter().zip(params_slice.iter()).enumerate()
                                 {
-                                    let arg_type = self.infer_expr_type(arg)?;
-                                    if !matches!(arg_type, TypeInfo::Error)
-                                        && !arg_type.is_compatible_with(param_type)
+                                    let callback = if opaque_params.get(i).copied().unwrap_or(false)
                                     {
+                                        self.callback_reference(arg)
+                                    } else {
+                                        None
+                                    };
+                                    let arg_type = match callback {
+                                        Some(function) => function,
+                                        None => self.infer_expr_type(arg)?,
+                                    };
+                                    let arg_type =
+                                        self.adapt_int_literal(arg, arg_type, param_type);
+                                    if let Expr::Identifier(name) = func.as_ref().as_ref()
+                                        && let Some(call_sites) = &mut self.call_sites
+                                    {
+                                        let int_literal =
+                                            Self::unsuffixed_int_literal(arg).is_some();
+                                        call_sites.record(name, i, &arg_type, int_literal);
+                                    }
+                                    let is_callback = matches!(arg_type, TypeInfo::Function { .. })
+                                        && opaque_params.get(i).copied().unwrap_or(false);
+                                    if is_callback
+                                        && let Some(owner) = &callback_owner
+                                        && let Some(expected) = callback_params(owner, i)
+                                        && !self.callback_fits(&arg_type, &expected)
+                                    {
+                                        let shape = if expected.is_empty() {
+                                            "no parameters".to_string()
+                                        } else {
+                                            let params: Vec<_> = expected
+                                                .iter()
+                                                .map(TypeInfo::display_name)
+                                                .collect();
+                                            format!("parameters ({})", params.join(", "))
+                                        };
                                         self.errors.push(
                                             TypeError::new(format!(
-                                                "argument {} type mismatch: expected {}, got {}",
+                                                "argument {} to `{}` must be a function with {}",
                                                 i + 1,
-                                                param_type.display_name(),
+                                                owner,
+                                                shape
+                                            ))
+                                            .with_span(*span)
+                                            .with_hint(format!(
+                                                "The runtime calls it through a fixed signature; found `{}`",
                                                 arg_type.display_name()
+                                            )),
+                                        );
+                                    }
+                                    if !matches!(arg_type, TypeInfo::Error)
+                                        && !is_callback
+                                        && !self.accepts(&arg_type, param_type)
+                                    {
+                                        let help = match param_type {
+                                            TypeInfo::Protocol(protocol) => format!(
+                                                "`{}` does not satisfy protocol `{}`: {}",
+                                                arg_type.display_name(),
+                                                protocol,
+                                                self.protocol_mismatches(&arg_type, protocol)
+                                                    .join(", ")
+                                            ),
+                                            _ => "Check the function signature and ensure argument types match".to_string(),
+                                        };
+                                        self.errors.push(
+                                            TypeError::new(format!(
+                                                "argument {} type mismatch",
+                                                i + 1
                                             ))
+                                            .with_mismatch(&param_type, &arg_type)
                                             .with_span(*span)
                                             .with_hint(format!(
                                                 "Argument {} should be of type `{}`",
                                                 i + 1,
                                                 param_type.display_name()
                                             ))
-                                            .with_help("Check the function signature and ensure argument types match".to_string()),
+                                            .with_help(help),
                                         );
                                     }
                                 }
@@ -2200,11 +4558,18 @@ impl TypeChecker {
                         );
                     }
 
-                    let then_type = self.infer_expr_type(then_branch)?;
+                    let narrowing = self.narrowing_of(cond);
+                    let then_type = self.with_narrowing(&narrowing.then, |checker| {
+                        checker.infer_expr_type(then_branch)
+                    })?;
                     if let Some(else_expr) = else_branch {
-                        let else_type = self.infer_expr_type(else_expr)?;
+                        let else_type = self.with_narrowing(&narrowing.otherwise, |checker| {
+                            checker.infer_expr_type(else_expr)
+                        })?;
 
-                        if then_type.is_compatible_with(&else_type) {
+                        if matches!(then_type, TypeInfo::Never) {
+                            Ok(else_type)
+                        } else if then_type.is_compatible_with(&else_type) {
                             Ok(then_type)
                         } else {
                             self.errors.push(
@@ -2308,9 +4673,11 @@ impl TypeChecker {
                             }
                             Ok(TypeInfo::Module(full_name))
                         }
-                        TypeInfo::Struct { fields, .. } => {
-                            if let Some(field_type) = fields.get(field) {
-                                Ok(field_type.clone())
+                        TypeInfo::Struct { name, fields, args } => {
+                            if let Some(field_type) =
+                                self.context.struct_field_type(&name, &args, &fields, field)
+                            {
+                                Ok(field_type)
                             } else {
                                 self.errors.push(
                                     TypeError::new(format!("struct has no field '{}'", field))
@@ -2358,14 +4725,49 @@ impl TypeChecker {
                     // F-strings always evaluate to strings
                     // Type check all embedded expressions
                     for part in parts {
-                        if let FStringPart::Expr(expr) = part.as_ref() {
-                            let _ = self.infer_expr_type(expr)?;
-                            // We don't care about the type, just that it's valid
+                        if let FStringPart::Expr(expr, spec) = part.as_ref() {
+                            let ty = self.infer_expr_type(expr)?;
+                            if let Some(spec) = spec {
+                                self.check_format_spec(spec, &ty);
+                            }
                         }
                     }
                     Ok(TypeInfo::Str)
                 }
-                // Lambda expressions removed - use anonymous fn syntax instead
+                Expr::Lambda { params, body } => {
+                    let param_types: Vec<TypeInfo> = params
+                        .iter()
+                        .map(|param| {
+                            param.as_ref().ty.as_ref().map_or(TypeInfo::Unknown, |ty| {
+                                self.context.type_from_annotation(ty)
+                            })
+                        })
+                        .collect();
+                    // Parameters shadow outer variables only inside the body
+                    let shadowed: Vec<(String, Option<TypeInfo>)> = params
+                        .iter()
+                        .zip(&param_types)
+                        .map(|(param, ty)| {
+                            let name = param.as_ref().name.as_ref().clone();
+                            let previous = self.context.variables.insert(name.clone(), ty.clone());
+                            (name, previous)
+                        })
+                        .collect();
+                    let return_type = self.infer_expr_type(body);
+                    for (name, previous) in shadowed.into_iter().rev() {
+                        match previous {
+                            Some(ty) => self.context.insert_variable(name, ty),
+                            None => {
+                                self.context.remove_variable(&name);
+                            }
+                        }
+                    }
+                    Ok(TypeInfo::Function {
+                        param_defaults: vec![false; param_types.len()],
+                        params: param_types,
+                        return_type: Box::new(return_type?),
+                    })
+                }
                 Expr::Array(elements) => {
                     if elements.is_empty() {
                         // Empty array - can't infer element type
@@ -2387,11 +4789,10 @@ impl TypeChecker {
                             {
                                 self.errors.push(
                                     TypeError::new(format!(
-                                        "array element {} has incompatible type: expected {}, got {}",
-                                        i + 1,
-                                        common_type.display_name(),
-                                        elem_type.display_name()
+                                        "array element {} has incompatible type",
+                                        i + 1
                                     ))
+                                    .with_mismatch(&common_type, elem_type)
                                     .with_span(*span)
                                     .with_hint(
                                         "All array elements must have compatible types".to_string(),
@@ -2429,11 +4830,10 @@ impl TypeChecker {
                             if !common_key_type.is_compatible_with(key_type) {
                                 self.errors.push(
                                     TypeError::new(format!(
-                                        "dictionary key {} has incompatible type: expected {}, got {}",
-                                        i + 1,
-                                        common_key_type.display_name(),
-                                        key_type.display_name()
+                                        "dictionary key {} has incompatible type",
+                                        i + 1
                                     ))
+                                    .with_mismatch(&common_key_type, key_type)
                                     .with_span(*span)
                                     .with_hint(
                                         "All dictionary keys must have compatible types"
@@ -2447,11 +4847,10 @@ impl TypeChecker {
                             if !common_value_type.is_compatible_with(value_type) {
                                 self.errors.push(
                                     TypeError::new(format!(
-                                        "dictionary value {} has incompatible type: expected {}, got {}",
-                                        i + 1,
-                                        common_value_type.display_name(),
-                                        value_type.display_name()
+                                        "dictionary value {} has incompatible type",
+                                        i + 1
                                     ))
+                                    .with_mismatch(&common_value_type, value_type)
                                     .with_span(*span)
                                     .with_hint(
                                         "All dictionary values must have compatible types"
@@ -2621,6 +5020,12 @@ impl TypeChecker {
                         return Ok(TypeInfo::Error);
                     }
 
+                    // `match type_of(x):` narrows `x` in each arm
+                    let type_subject = Self::type_of_subject(value).and_then(|var| {
+                        let current = self.context.get_variable(&var)?.clone();
+                        Some((var, current))
+                    });
+
                     // Type check each arm
                     let mut arm_types = Vec::new();
                     for arm in arms {
@@ -2629,9 +5034,14 @@ impl TypeChecker {
                         let mut normalized_type = self.context.normalize_type(value_type.clone());
 
                         // If normalization didn't work and we have an enum variant pattern, try to build the enum type directly
+                        let arm_pattern = match arm.as_ref().pattern.as_ref() {
+                            Pattern::Or(alternatives) => alternatives
+                                .first()
+                                .map_or(arm.as_ref().pattern.as_ref(), |first| first.as_ref()),
+                            pattern => pattern,
+                        };
                         if let TypeInfo::Generic { base, args } = &normalized_type
-                            && let Pattern::EnumVariant { enum_name, .. } =
-                                &arm.as_ref().pattern.as_ref()
+                            && let Pattern::EnumVariant { enum_name, .. } = arm_pattern
                             && base == enum_name
                         {
                             // Try to build the enum type directly using the pattern's enum name
@@ -2646,6 +5056,12 @@ impl TypeChecker {
                         self.validate_pattern_against_type(&arm.as_ref().pattern, &normalized_type);
 
                         let old_vars = self.context.variables.clone();
+                        if let Some((var, current)) = &type_subject
+                            && let Some(narrowed) =
+                                Self::narrow_by_type_pattern(current, arm.as_ref().pattern.as_ref())
+                        {
+                            self.context.insert_variable(var.clone(), narrowed);
+                        }
                         self.bind_pattern_variables(&arm.as_ref().pattern, &normalized_type);
 
                         // Check guard if present
@@ -2657,7 +5073,7 @@ impl TypeChecker {
                                         "match guard must be bool, got {}",
                                         guard_type.display_name()
                                     ))
-                                    .with_span(*span),
+                                    .with_span(*guard.span()),
                                 );
                             }
                         }
@@ -2670,17 +5086,30 @@ impl TypeChecker {
                         self.context.variables = old_vars;
                     }
 
-                    // All arms must return compatible types
-                    let common_type = arm_types.remove(0);
-                    for (i, arm_type) in arm_types.iter().enumerate() {
-                        if !common_type.is_compatible_with(arm_type) {
+                    self.check_match_exhaustiveness(&value_type, arms, *span);
+                    if let Some((var, TypeInfo::Union(members))) = &type_subject {
+                        self.check_type_match_exhaustiveness(var, members, arms, *span);
+                    }
+
+                    // All arms must return compatible types, except those that
+                    // never complete
+                    let Some(first) = arm_types
+                        .iter()
+                        .position(|arm_type| !matches!(arm_type, TypeInfo::Never))
+                    else {
+                        return Ok(TypeInfo::Never);
+                    };
+                    let common_type = arm_types[first].clone();
+                    for (i, arm_type) in arm_types.iter().enumerate().skip(first + 1) {
+                        if !matches!(arm_type, TypeInfo::Never)
+                            && !common_type.is_compatible_with(arm_type)
+                        {
                             self.errors.push(
                                 TypeError::new(format!(
-                                    "match arm {} returns incompatible type: expected {}, got {}",
-                                    i + 1,
-                                    common_type.display_name(),
-                                    arm_type.display_name()
+                                    "match arm {} returns incompatible type",
+                                    i + 1
                                 ))
+                                .with_mismatch(&common_type, arm_type)
                                 .with_span(*span)
                                 .with_hint(
                                     "All match arms must return compatible types".to_string(),
@@ -2692,6 +5121,12 @@ impl TypeChecker {
                     Ok(common_type)
                 }
                 Expr::Struct { name, fields } => {
+                    if let Some((enum_name, variant_name)) = name.split_once('.') {
+                        let enum_type =
+                            self.check_named_enum_variant(enum_name, variant_name, fields, *span)?;
+                        self.check_type_bounds(&enum_type, *span);
+                        return Ok(enum_type);
+                    }
                     // Get struct definition (clone to avoid borrow checker issues)
                     let struct_def = match self.context.get_struct(name) {
                         Some(def) => def.clone(),
@@ -2756,7 +5191,7 @@ impl TypeChecker {
                         // Substitute inferred generics into field type for checking
                         let concrete_field_type = field_type.substitute(&inferred_generics);
 
-                        if !expr_type.is_compatible_with(&concrete_field_type) {
+                        if !self.accepts(&expr_type, &concrete_field_type) {
                             self.errors.push(
                                 TypeError::new(format!(
                                     "field '{}' of struct '{}' expects type {}, got {}",
@@ -2789,11 +5224,24 @@ impl TypeChecker {
                         .iter()
                         .map(|(k, v)| (k.clone(), v.substitute(&inferred_generics)))
                         .collect();
+                    let args = struct_def
+                        .generics
+                        .iter()
+                        .map(|generic| {
+                            inferred_generics
+                                .get(generic)
+                                .cloned()
+                                .unwrap_or(TypeInfo::Unknown)
+                        })
+                        .collect();
 
-                    Ok(TypeInfo::Struct {
+                    let struct_type = TypeInfo::Struct {
                         name: name.clone(),
                         fields: concrete_fields,
-                    })
+                        args,
+                    };
+                    self.check_type_bounds(&struct_type, *span);
+                    Ok(struct_type)
                 }
                 Expr::Await(expr) => {
                     let inner_type = self.infer_expr_type(expr)?;
@@ -2840,11 +5288,54 @@ impl TypeChecker {
         Ok(ty)
     }
 
+    /// Reports a format specifier of an f-string interpolation that does not
+    /// parse or does not apply to a value of type `ty`
+    fn check_format_spec(&mut self, spec: &Node<String>, ty: &TypeInfo) {
+        let text = spec.as_ref();
+        let span = *spec.span();
+        match FormatSpec::parse(text) {
+            Err(error) => {
+                let start = text
+                    .char_indices()
+                    .nth(error.at)
+                    .map_or(text.len(), |(offset, _)| offset);
+                let start = (span.start() + start).min(span.end());
+                let end = (start + 1).min(span.end()).max(start);
+                self.errors.push(
+                    TypeError::new(format!("invalid format spec `{}`: {}", text, error.message))
+                        .with_span(Span::new(start, end)),
+                );
+            }
+            Ok(parsed) => {
+                let Some(arg) = format_arg(ty) else {
+                    return;
+                };
+                if let Err(reason) = parsed.check(arg) {
+                    self.errors.push(
+                        TypeError::new(format!(
+                            "format spec `{}` does not apply to `{}`: {}",
+                            text,
+                            ty.display_name(),
+                            reason
+                        ))
+                        .with_span(span),
+                    );
+                }
+            }
+        }
+    }
+
     /// Get collected errors
     pub fn errors(&self) -> &[TypeError] {
         &self.errors
     }
 
+    /// Warnings about the last checked program, reported whether or not it
+    /// has errors
+    pub fn warnings(&self) -> &[TypeError] {
+        &self.warnings
+    }
+
     pub fn expr_type_map(&self) -> &HashMap<usize, TypeInfo> {
         &self.expr_types
     }
@@ -2894,11 +5385,24 @@ impl TypeChecker {
                         exports.type_aliases.insert(name.clone(), alias);
                     }
                 }
+                Statement::Protocol { name, public, .. } if *public => {
+                    if let Some(def) = self.context.get_protocol(name).cloned() {
+                        exports.protocols.insert(name.clone(), def);
+                    }
+                }
                 Statement::Let { name, public, .. } if *public => {
                     if let Some(var_type) = self.context.get_variable(name.as_ref()).cloned() {
                         exports.variables.insert(name.as_ref().clone(), var_type);
                     }
                 }
+                Statement::Const { name, public, .. } if *public => {
+                    if let Some(var_type) = self.context.get_variable(name.as_ref()).cloned() {
+                        exports.variables.insert(name.as_ref().clone(), var_type);
+                    }
+                    if let Some(value) = self.const_values.get(name.as_ref()).cloned() {
+                        exports.constants.insert(name.as_ref().clone(), value);
+                    }
+                }
                 _ => {}
             }
         }
@@ -2923,6 +5427,11 @@ impl TypeChecker {
             self.context.insert_variable(qualified, ty.clone());
         }
 
+        for (name, value) in &exports.constants {
+            let qualified = format!("{}.{}", exports.module, name);
+            self.const_values.insert(qualified, value.clone());
+        }
+
         for def in exports.structs.values() {
             self.context.define_struct(def.clone());
         }
@@ -2934,6 +5443,10 @@ impl TypeChecker {
         for (name, ty) in &exports.type_aliases {
             self.context.type_aliases.insert(name.clone(), ty.clone());
         }
+
+        for def in exports.protocols.values() {
+            self.context.define_protocol(def.clone());
+        }
     }
 
     fn build_member_path(&self, object: &Node<Expr>, field: &str) -> String {
@@ -2960,29 +5473,320 @@ impl TypeChecker {
         }
     }
 
-    fn resolve_member_function(
-        &mut self,
-        object: &Node<Expr>,
-        field: &str,
-        span: &Span,
-    ) -> TypeInfo {
-        match self.infer_expr_type(object) {
-            Ok(TypeInfo::Struct { name, .. }) => {
-                let method_name = format!("{}.{}", name, field);
-                self.context
-                    .get_function(&method_name)
-                    .cloned()
-                    .unwrap_or_else(|| {
-                        self.errors.push(
-                            TypeError::new(format!("struct '{}' has no method '{}'", name, field))
-                                .with_span(*span),
-                        );
-                        TypeInfo::Error
-                    })
-            }
-            _ => TypeInfo::Function {
-                params: vec![],
-                param_defaults: vec![],
+    /// Whether `callback` can be called with arguments of the `expected`
+    /// types, as the runtime calls a callback it was handed
+    /// The type of the function a bare name passed to an opaque runtime
+    /// parameter refers to. Only there does a function name stand for the
+    /// function itself, which the runtime gets as a pointer to call back.
+    fn callback_reference(&mut self, arg: &Node<Expr>) -> Option<TypeInfo> {
+        let Expr::Identifier(name) = arg.as_ref() else {
+            return None;
+        };
+        if self.context.get_variable(name).is_some() {
+            return None;
+        }
+        let function = self.context.get_function(name)?.clone();
+        // It can be called from anywhere now
+        if let Some(call_sites) = &mut self.call_sites {
+            call_sites.escape(name);
+        }
+        self.record_expr_type(arg, &function);
+        Some(function)
+    }
+
+    fn callback_fits(&self, callback: &TypeInfo, expected: &[TypeInfo]) -> bool {
+        match callback {
+            TypeInfo::Function { params, .. } => {
+                params.len() == expected.len()
+                    && expected
+                        .iter()
+                        .zip(params)
+                        .all(|(argument, param)| self.accepts(argument, param))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a value of type `from` can be used where `to` is expected,
+    /// counting structs and enums that satisfy a protocol
+    fn accepts(&self, from: &TypeInfo, to: &TypeInfo) -> bool {
+        if from.is_compatible_with(to) {
+            return true;
+        }
+        match (from, to) {
+            (TypeInfo::Union(members), _) => members.iter().all(|member| self.accepts(member, to)),
+            (_, TypeInfo::Union(members)) => {
+                members.iter().any(|member| self.accepts(from, member))
+            }
+            (_, TypeInfo::Protocol(protocol)) => {
+                self.protocol_mismatches(from, protocol).is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Why `ty` does not satisfy `protocol`, one entry per required method
+    /// it lacks or has with another signature. Parameters of a method may be
+    /// wider than the protocol's and its return type narrower.
+    /// Checks that every bound is a protocol or one of [`BUILTIN_BOUNDS`]
+    fn check_bound_declarations(&mut self, statements: &[Node<Statement>]) {
+        for statement in statements {
+            let (Statement::Struct { bounds, .. } | Statement::Enum { bounds, .. }) =
+                statement.as_ref()
+            else {
+                continue;
+            };
+            for bound in bounds {
+                for bound_name in &bound.bounds {
+                    let known = BUILTIN_BOUNDS
+                        .iter()
+                        .any(|(builtin, _)| *builtin == bound_name.as_ref().as_str())
+                        || self.context.get_protocol(bound_name.as_ref()).is_some();
+                    if !known {
+                        let builtins: Vec<&str> =
+                            BUILTIN_BOUNDS.iter().map(|(builtin, _)| *builtin).collect();
+                        self.errors.push(
+                            TypeError::new(format!("unknown bound `{}`", bound_name.as_ref()))
+                                .with_hint(format!(
+                                    "a bound is a protocol or one of {}",
+                                    builtins.join(", ")
+                                ))
+                                .with_span(*bound_name.span()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports the type arguments of `ty` that do not satisfy the bounds of
+    /// their generic parameter. `span` is where the type is instantiated.
+    fn check_type_bounds(&mut self, ty: &TypeInfo, span: Span) {
+        let (name, args) = match ty {
+            TypeInfo::Struct { name, args, .. }
+            | TypeInfo::Enum { name, args, .. }
+            | TypeInfo::Generic { base: name, args } => (name, args),
+            _ => return,
+        };
+        let Some(bounds) = self.generic_bounds.get(name).cloned() else {
+            return;
+        };
+        let generics = match (self.context.get_struct(name), self.context.get_enum(name)) {
+            (Some(definition), _) => definition.generics.clone(),
+            (None, Some(definition)) => definition.generics.clone(),
+            (None, None) => return,
+        };
+        for bound in &bounds {
+            let Some(arg) = generics
+                .iter()
+                .position(|generic| *generic == bound.param)
+                .and_then(|index| args.get(index))
+            else {
+                continue;
+            };
+            // Not known yet, or a generic parameter of an enclosing type
+            if Self::is_unknown_like(arg) || matches!(arg, TypeInfo::Error) {
+                continue;
+            }
+            for bound_name in &bound.bounds {
+                let bound_name = bound_name.as_ref();
+                let help = match BUILTIN_BOUNDS
+                    .iter()
+                    .find(|(builtin, _)| *builtin == bound_name.as_str())
+                {
+                    Some((_, satisfied_by)) if !arg.satisfies_builtin_bound(bound_name) => {
+                        format!("`{bound_name}` is satisfied by {satisfied_by}")
+                    }
+                    Some(_) => continue,
+                    None => {
+                        let mismatches = self.protocol_mismatches(arg, bound_name);
+                        if mismatches.is_empty() {
+                            continue;
+                        }
+                        format!(
+                            "`{}` does not satisfy protocol `{}`: {}",
+                            arg.display_name(),
+                            bound_name,
+                            mismatches.join(", ")
+                        )
+                    }
+                };
+                let declared: Vec<&str> = bound
+                    .bounds
+                    .iter()
+                    .map(|bound_name| bound_name.as_ref().as_str())
+                    .collect();
+                self.errors.push(
+                    TypeError::new(format!(
+                        "`{}` does not satisfy the bound `{}` of `{}` in `{}`",
+                        arg.display_name(),
+                        bound_name,
+                        bound.param,
+                        name
+                    ))
+                    .with_hint(format!(
+                        "`{}` is declared as `{}<{}: {}>`",
+                        name,
+                        name,
+                        bound.param,
+                        declared.join(" + ")
+                    ))
+                    .with_help(help)
+                    .with_span(span),
+                );
+            }
+        }
+    }
+
+    fn protocol_mismatches(&self, ty: &TypeInfo, protocol: &str) -> Vec<String> {
+        let Some(definition) = self.context.get_protocol(protocol) else {
+            return vec![format!("unknown protocol `{}`", protocol)];
+        };
+        let mut mismatches = Vec::new();
+        for (method, required) in &definition.methods {
+            let found = match ty {
+                TypeInfo::Struct { name, .. } | TypeInfo::Enum { name, .. } => self
+                    .context
+                    .get_function(&format!("{}.{}", name, method))
+                    .map(|signature| match signature {
+                        // Drop `self`
+                        TypeInfo::Function {
+                            params,
+                            param_defaults,
+                            return_type,
+                        } => TypeInfo::Function {
+                            params: params.iter().skip(1).cloned().collect(),
+                            param_defaults: param_defaults.iter().skip(1).copied().collect(),
+                            return_type: return_type.clone(),
+                        },
+                        other => other.clone(),
+                    }),
+                TypeInfo::Alias { underlying, .. } => {
+                    return self.protocol_mismatches(underlying, protocol);
+                }
+                TypeInfo::Protocol(other) => self
+                    .context
+                    .get_protocol(other)
+                    .and_then(|other| other.method(method))
+                    .cloned(),
+                _ => {
+                    return vec![format!(
+                        "`{}` has no methods; only structs and enums satisfy protocols",
+                        ty.display_name()
+                    )];
+                }
+            };
+            let Some(found) = found else {
+                mismatches.push(format!("missing method `{}`", method));
+                continue;
+            };
+            let fits = match (&found, required) {
+                (
+                    TypeInfo::Function {
+                        params: found_params,
+                        return_type: found_return,
+                        ..
+                    },
+                    TypeInfo::Function {
+                        params: required_params,
+                        return_type: required_return,
+                        ..
+                    },
+                ) => {
+                    found_params.len() == required_params.len()
+                        && required_params
+                            .iter()
+                            .zip(found_params)
+                            .all(|(required, found)| required.is_compatible_with(found))
+                        && found_return.is_compatible_with(required_return)
+                }
+                _ => false,
+            };
+            if !fits {
+                mismatches.push(format!(
+                    "method `{}` is {}, the protocol needs {}",
+                    method,
+                    found.display_name(),
+                    required.display_name()
+                ));
+            }
+        }
+        mismatches
+    }
+
+    fn resolve_member_function(
+        &mut self,
+        object: &Node<Expr>,
+        field: &str,
+        span: &Span,
+    ) -> TypeInfo {
+        match self.infer_expr_type(object) {
+            Ok(TypeInfo::Struct { name, .. }) => {
+                let method_name = format!("{}.{}", name, field);
+                self.context
+                    .get_function(&method_name)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        self.errors.push(
+                            TypeError::new(format!("struct '{}' has no method '{}'", name, field))
+                                .with_span(*span),
+                        );
+                        TypeInfo::Error
+                    })
+            }
+            Ok(TypeInfo::Enum { name, .. }) => {
+                let method_name = format!("{}.{}", name, field);
+                self.context
+                    .get_function(&method_name)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        self.errors.push(
+                            TypeError::new(format!("enum '{}' has no method '{}'", name, field))
+                                .with_span(*span),
+                        );
+                        TypeInfo::Error
+                    })
+            }
+            Ok(TypeInfo::Protocol(name)) => self
+                .context
+                .get_protocol(&name)
+                .and_then(|protocol| protocol.method(field))
+                .cloned()
+                .unwrap_or_else(|| {
+                    self.errors.push(
+                        TypeError::new(format!("protocol '{}' has no method '{}'", name, field))
+                            .with_span(*span),
+                    );
+                    TypeInfo::Error
+                }),
+            Ok(object_type) if !object_type.builtin_method_names().is_empty() => {
+                match object_type.builtin_method(field) {
+                    Some(method) => TypeInfo::Function {
+                        param_defaults: vec![false; method.params.len()],
+                        params: method.params,
+                        return_type: Box::new(method.return_type),
+                    },
+                    None => {
+                        self.errors.push(
+                            TypeError::new(format!(
+                                "type {} has no method '{}'",
+                                object_type.display_name(),
+                                field
+                            ))
+                            .with_span(*span)
+                            .with_hint(format!(
+                                "{} values have: {}",
+                                object_type.display_name(),
+                                object_type.builtin_method_names().join(", ")
+                            )),
+                        );
+                        TypeInfo::Error
+                    }
+                }
+            }
+            _ => TypeInfo::Function {
+                params: vec![],
+                param_defaults: vec![],
                 return_type: Box::new(TypeInfo::Unknown),
             },
         }
@@ -2995,6 +5799,117 @@ impl Default for TypeChecker {
     }
 }
 
+/// Parameter types of the callback that argument `index` of the runtime
+/// function `name` takes, for callbacks the runtime calls through a fixed C
+/// signature and that must therefore match it exactly
+fn callback_params(name: &str, index: usize) -> Option<Vec<TypeInfo>> {
+    match (name, index) {
+        ("runtime.on_shutdown", 0) => Some(Vec::new()),
+        ("runtime.set_panic_hook", 0) => Some(vec![TypeInfo::Str; 3]),
+        _ => None,
+    }
+}
+
+/// Element type of the channel returned by a `task` channel or stream
+/// constructor or a typed `task.subscribe`, which the FFI signature only
+/// knows as an opaque handle
+fn channel_constructor_element(name: &str) -> Option<TypeInfo> {
+    let suffix = name
+        .strip_prefix("task.channel")
+        .or_else(|| name.strip_prefix("task.bounded_channel"))
+        .or_else(|| name.strip_prefix("task.stream"))
+        .or_else(|| name.strip_prefix("task.subscribe"))?;
+    match suffix {
+        "_int" | "<int>" => Some(TypeInfo::I64),
+        "_float" | "<float>" => Some(TypeInfo::F64),
+        "_string" | "<string>" => Some(TypeInfo::Str),
+        _ => None,
+    }
+}
+
+/// Error for a call to a Rust function the bridge left out, instead of
+/// letting the call fail when the bridge is built or run
+fn unmapped_ffi_error(function: &UnmappedFunction, span: Span) -> TypeError {
+    TypeError::new(format!(
+        "cannot call Rust function `{}`: no Otter equivalent for {}",
+        function.name,
+        function.unmapped.join(", ")
+    ))
+    .with_span(span)
+    .with_help(format!("Rust signature: {}", function.rust_signature))
+    .with_hint(
+        "Rust functions can take bool, integers, floats and strings, and return those, \
+         Option/Result of them or opaque handles; wrap other types in ffi/<crate>/bridge.yaml"
+            .to_string(),
+    )
+}
+
+/// Variable types established by a condition
+#[derive(Default)]
+struct Narrowing {
+    then: Vec<(String, TypeInfo)>,
+    otherwise: Vec<(String, TypeInfo)>,
+}
+
+/// What `type_of` returns for values of `ty`, if it is one of the names
+/// narrowing understands
+fn type_of_label(ty: &TypeInfo) -> Option<&'static str> {
+    Some(match ty {
+        // Symbols are strings at runtime
+        TypeInfo::Str | TypeInfo::Symbol => "string",
+        TypeInfo::F64 => "float",
+        TypeInfo::Bool => "bool",
+        TypeInfo::List(_) => "list",
+        TypeInfo::Dict { .. } => "map",
+        TypeInfo::Alias { underlying, .. } => return type_of_label(underlying),
+        ty if ty.is_integer() => "int",
+        _ => return None,
+    })
+}
+
+/// Whether a union member is picked out by a test for `tested`
+fn type_of_matches(member: &TypeInfo, tested: &TypeInfo) -> bool {
+    match tested {
+        TypeInfo::Unit => matches!(member, TypeInfo::Unit),
+        _ => type_of_label(member).is_some_and(|label| type_of_label(tested) == Some(label)),
+    }
+}
+
+/// The type a `type_of` result names
+fn type_of_name(name: &str) -> Option<TypeInfo> {
+    Some(match name {
+        "str" | "string" => TypeInfo::Str,
+        "int" => TypeInfo::I64,
+        "float" => TypeInfo::F64,
+        "bool" => TypeInfo::Bool,
+        "list" => TypeInfo::List(Box::new(TypeInfo::Unknown)),
+        "map" | "dict" => TypeInfo::Dict {
+            key: Box::new(TypeInfo::Unknown),
+            value: Box::new(TypeInfo::Unknown),
+        },
+        _ => return None,
+    })
+}
+
+/// How a value of `ty` is formatted by a format specifier, if its type is
+/// known well enough to check one
+fn format_arg(ty: &TypeInfo) -> Option<FormatArg> {
+    Some(match ty {
+        TypeInfo::F64 => FormatArg::Float,
+        TypeInfo::Alias { underlying, .. } => return format_arg(underlying),
+        ty if ty.is_integer() => FormatArg::Int,
+        TypeInfo::Unit
+        | TypeInfo::Bool
+        | TypeInfo::Str
+        | TypeInfo::Symbol
+        | TypeInfo::List(_)
+        | TypeInfo::Dict { .. }
+        | TypeInfo::Struct { .. }
+        | TypeInfo::Enum { .. } => FormatArg::Str,
+        _ => return None,
+    })
+}
+
 fn ffi_type_to_typeinfo(ft: &FfiType) -> TypeInfo {
     match ft {
         FfiType::Unit => TypeInfo::Unit,
@@ -3016,7 +5931,8 @@ fn ffi_type_to_typeinfo(ft: &FfiType) -> TypeInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use otterc_ast::nodes::{BinaryOp, Expr, Literal, Node, NumberLiteral};
+    use crate::checked_literals::LiteralError;
+    use otterc_ast::nodes::{BinaryOp, Expr, Literal, Node, NumberLiteral, Type};
     use otterc_span::Span;
     use std::f64;
 
@@ -3072,4 +5988,1647 @@ mod tests {
         let ty = checker.infer_expr_type(&expr).unwrap();
         assert_eq!(ty, TypeInfo::F64);
     }
+
+    #[test]
+    fn suffixed_literals_must_fit_their_type() {
+        let literal = |text: &str, start: usize| {
+            let span = Span::new(start, start + text.len());
+            Node::new(
+                Expr::Literal(Node::new(
+                    Literal::Number(NumberLiteral::parse(text).unwrap()),
+                    span,
+                )),
+                span,
+            )
+        };
+        let mut checker = TypeChecker::new();
+        assert_eq!(
+            checker.infer_expr_type(&literal("7i32", 0)).unwrap(),
+            TypeInfo::I32
+        );
+        assert_eq!(
+            checker.infer_expr_type(&literal("1.5f32", 10)).unwrap(),
+            TypeInfo::F64
+        );
+        let negated = Node::new(
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                expr: Box::new(literal("128i8", 20)),
+            },
+            Span::new(19, 25),
+        );
+        checker.infer_expr_type(&negated).unwrap();
+        assert!(checker.errors.is_empty());
+
+        checker.infer_expr_type(&literal("0x100u8", 30)).unwrap();
+        checker.infer_expr_type(&literal("128i8", 40)).unwrap();
+        let messages: Vec<&str> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "literal `256` does not fit in u8",
+                "literal `128` does not fit in i8"
+            ]
+        );
+    }
+
+    #[test]
+    fn sized_integers_widen_but_do_not_narrow() {
+        let ident = |name: &str| {
+            Box::new(Node::new(
+                Expr::Identifier(name.to_string()),
+                Span::new(0, 0),
+            ))
+        };
+        let add = |left, right| {
+            Node::new(
+                Expr::Binary {
+                    op: BinaryOp::Add,
+                    left,
+                    right,
+                },
+                Span::new(0, 0),
+            )
+        };
+        let one = Box::new(Node::new(
+            Expr::Literal(Node::new(
+                Literal::Number(NumberLiteral::new(1.0, false)),
+                Span::new(5, 6),
+            )),
+            Span::new(5, 6),
+        ));
+        let mut checker = TypeChecker::new();
+        checker
+            .context
+            .insert_variable("byte".to_string(), TypeInfo::U8);
+        checker
+            .context
+            .insert_variable("small".to_string(), TypeInfo::I16);
+        checker
+            .context
+            .insert_variable("big".to_string(), TypeInfo::U64);
+
+        // An unsuffixed literal takes the type of the other operand
+        let byte_plus_one = add(ident("byte"), one);
+        assert_eq!(
+            checker.infer_expr_type(&byte_plus_one).unwrap(),
+            TypeInfo::U8
+        );
+        assert_eq!(checker.expr_types_by_span[&Span::new(5, 6)], TypeInfo::U8);
+        // Every u8 fits in an i16
+        assert_eq!(
+            checker
+                .infer_expr_type(&add(ident("byte"), ident("small")))
+                .unwrap(),
+            TypeInfo::I16
+        );
+        assert!(checker.errors.is_empty());
+
+        assert!(TypeInfo::U32.is_compatible_with(&TypeInfo::I64));
+        assert!(!TypeInfo::I64.is_compatible_with(&TypeInfo::I32));
+        assert!(!TypeInfo::I8.is_compatible_with(&TypeInfo::U64));
+        assert_eq!(
+            checker
+                .infer_expr_type(&add(ident("small"), ident("big")))
+                .unwrap(),
+            TypeInfo::Error
+        );
+        assert_eq!(
+            checker.errors[0].message,
+            "mismatched integer types i16 and u64"
+        );
+    }
+
+    #[test]
+    fn bitwise_operators_require_integers() {
+        let literal = |text: &str| {
+            Box::new(Node::new(
+                Expr::Literal(Node::new(
+                    Literal::Number(NumberLiteral::parse(text).unwrap()),
+                    Span::new(0, 0),
+                )),
+                Span::new(0, 0),
+            ))
+        };
+        let mut checker = TypeChecker::new();
+        let mask = Node::new(
+            Expr::Binary {
+                op: BinaryOp::BitAnd,
+                left: literal("0xff"),
+                right: literal("0b1010"),
+            },
+            Span::new(0, 0),
+        );
+        assert_eq!(checker.infer_expr_type(&mask).unwrap(), TypeInfo::I64);
+        let complement = Node::new(
+            Expr::Unary {
+                op: UnaryOp::BitNot,
+                expr: literal("7i32"),
+            },
+            Span::new(0, 0),
+        );
+        assert_eq!(checker.infer_expr_type(&complement).unwrap(), TypeInfo::I32);
+        assert!(checker.errors.is_empty());
+
+        let shifted_float = Node::new(
+            Expr::Binary {
+                op: BinaryOp::Shl,
+                left: literal("1.5"),
+                right: literal("2"),
+            },
+            Span::new(0, 0),
+        );
+        assert_eq!(
+            checker.infer_expr_type(&shifted_float).unwrap(),
+            TypeInfo::Error
+        );
+        assert_eq!(
+            checker.errors[0].message,
+            "bitwise operations require integer operands, got f64 and i64"
+        );
+    }
+
+    #[test]
+    fn for_await_yields_the_channel_element_type() {
+        let mut checker = TypeChecker::new();
+        let channel = TypeInfo::channel_of(TypeInfo::Str);
+        assert_eq!(
+            checker.stream_element_type(&channel, Span::new(0, 0)),
+            TypeInfo::Str
+        );
+        assert_eq!(
+            channel_constructor_element("task.stream_int"),
+            Some(TypeInfo::I64)
+        );
+        assert_eq!(
+            channel_constructor_element("task.subscribe<float>"),
+            Some(TypeInfo::F64)
+        );
+        assert!(channel.is_compatible_with(&TypeInfo::I64));
+        assert!(checker.errors.is_empty());
+
+        let list = TypeInfo::List(Box::new(TypeInfo::I64));
+        assert_eq!(
+            checker.stream_element_type(&list, Span::new(0, 0)),
+            TypeInfo::Unknown
+        );
+        assert_eq!(
+            checker.errors[0].message,
+            "`for await` needs a channel or stream, got list<i64>"
+        );
+    }
+
+    #[test]
+    fn casts_convert_numbers_and_strings() {
+        let cast = |name: &str, target: &str| {
+            Node::new(
+                Expr::Cast {
+                    expr: Box::new(Node::new(
+                        Expr::Identifier(name.to_string()),
+                        Span::new(0, 1),
+                    )),
+                    ty: Node::new(Type::Simple(target.to_string()), Span::new(5, 8)),
+                },
+                Span::new(0, 8),
+            )
+        };
+        let mut checker = TypeChecker::new();
+        checker
+            .context
+            .insert_variable("n".to_string(), TypeInfo::I64);
+        checker
+            .context
+            .insert_variable("ratio".to_string(), TypeInfo::F64);
+        checker
+            .context
+            .insert_variable("name".to_string(), TypeInfo::Str);
+
+        assert_eq!(
+            checker.infer_expr_type(&cast("n", "f64")).unwrap(),
+            TypeInfo::F64
+        );
+        assert_eq!(
+            checker.infer_expr_type(&cast("ratio", "u8")).unwrap(),
+            TypeInfo::U8
+        );
+        assert_eq!(
+            checker.infer_expr_type(&cast("n", "str")).unwrap(),
+            TypeInfo::Str
+        );
+        assert!(checker.errors.is_empty());
+
+        assert_eq!(
+            checker.infer_expr_type(&cast("name", "int")).unwrap(),
+            TypeInfo::Error
+        );
+        assert_eq!(checker.errors[0].message, "cannot cast str to i64");
+    }
+
+    #[test]
+    fn consts_are_computed_and_range_checked() {
+        let number = |value: f64| {
+            Node::new(
+                Expr::Literal(Node::new(
+                    Literal::Number(NumberLiteral::new(value, false)),
+                    Span::new(0, 1),
+                )),
+                Span::new(0, 1),
+            )
+        };
+        let constant = |name: &str, ty: Option<&str>, expr: Node<Expr>| {
+            Node::new(
+                Statement::Const {
+                    name: Node::new(name.to_string(), Span::new(0, 1)),
+                    ty: ty.map(|ty| Node::new(Type::Simple(ty.to_string()), Span::new(0, 1))),
+                    expr,
+                    public: false,
+                },
+                Span::new(0, 1),
+            )
+        };
+        let answer = Node::new(
+            Expr::Binary {
+                op: BinaryOp::Add,
+                left: Box::new(Node::new(
+                    Expr::Identifier("BASE".to_string()),
+                    Span::new(0, 4),
+                )),
+                right: Box::new(number(2.0)),
+            },
+            Span::new(0, 8),
+        );
+        let program = Program::new(vec![
+            constant("BASE", Some("u8"), number(40.0)),
+            constant("ANSWER", None, answer),
+            constant("LIMIT", Some("u8"), number(300.0)),
+        ]);
+
+        let mut checker = TypeChecker::new();
+        assert!(checker.check_program(&program).is_err());
+
+        assert_eq!(checker.const_values["ANSWER"], ConstValue::Int(42));
+        assert_eq!(checker.context.get_variable("BASE"), Some(&TypeInfo::U8));
+        assert_eq!(
+            checker.errors[0].message,
+            "invalid constant `LIMIT`: constant value 300 does not fit in u8"
+        );
+
+        let assignment = Node::new(
+            Statement::Assignment {
+                name: Node::new("BASE".to_string(), Span::new(0, 4)),
+                expr: number(1.0),
+            },
+            Span::new(0, 8),
+        );
+        checker.check_statement(&assignment).unwrap();
+        assert_eq!(
+            checker.errors[1].message,
+            "cannot assign to constant `BASE`"
+        );
+    }
+
+    #[test]
+    fn enum_variants_take_named_fields_and_methods() {
+        use otterc_ast::nodes::{Block, EnumVariant, Function, Param};
+
+        let span = Span::new(0, 1);
+        let float = || Node::new(Type::Simple("float".to_string()), span);
+        let area = Function::new(
+            "area",
+            vec![Node::new(
+                Param::new(Node::new("self".to_string(), span), None, None),
+                span,
+            )],
+            Some(float()),
+            Node::new(
+                Block::new(vec![Node::new(
+                    Statement::Return(Some(Node::new(
+                        Expr::Literal(Node::new(
+                            Literal::Number(NumberLiteral::new(1.0, true)),
+                            span,
+                        )),
+                        span,
+                    ))),
+                    span,
+                )]),
+                span,
+            ),
+        );
+        let program = Program::new(vec![Node::new(
+            Statement::Enum {
+                name: "Shape".to_string(),
+                variants: vec![Node::new(
+                    EnumVariant::new("Circle", vec![float()])
+                        .with_field_names(vec!["radius".to_string()]),
+                    span,
+                )],
+                methods: vec![Node::new(area, span)],
+                public: false,
+                generics: Vec::new(),
+                bounds: Vec::new(),
+                derives: Vec::new(),
+            },
+            span,
+        )]);
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        let circle = |field: &str| {
+            Node::new(
+                Expr::Struct {
+                    name: "Shape.Circle".to_string(),
+                    fields: vec![(
+                        field.to_string(),
+                        Node::new(
+                            Expr::Literal(Node::new(
+                                Literal::Number(NumberLiteral::new(2.0, true)),
+                                span,
+                            )),
+                            span,
+                        ),
+                    )],
+                },
+                span,
+            )
+        };
+        let call = Node::new(
+            Expr::Call {
+                func: Box::new(Node::new(
+                    Expr::Member {
+                        object: Box::new(circle("radius")),
+                        field: "area".to_string(),
+                    },
+                    span,
+                )),
+                args: Vec::new(),
+            },
+            span,
+        );
+        assert!(matches!(
+            checker.infer_expr_type(&circle("radius")).unwrap(),
+            TypeInfo::Enum { name, .. } if name == "Shape"
+        ));
+        assert_eq!(checker.infer_expr_type(&call).unwrap(), TypeInfo::F64);
+        assert!(checker.errors.is_empty());
+
+        checker.infer_expr_type(&circle("size")).unwrap();
+        assert_eq!(
+            checker.errors[0].message,
+            "enum variant 'Shape.Circle' has no field 'size'"
+        );
+    }
+
+    #[test]
+    fn generic_struct_type_arguments_reach_fields_and_methods() {
+        use otterc_ast::nodes::{Block, Function, Param};
+
+        let span = Span::new(0, 1);
+        let list_of_t = || {
+            Node::new(
+                Type::Generic {
+                    base: "list".to_string(),
+                    args: vec![Node::new(Type::Simple("T".to_string()), span)],
+                },
+                span,
+            )
+        };
+        let self_items = || {
+            Node::new(
+                Expr::Member {
+                    object: Box::new(Node::new(Expr::Identifier("self".to_string()), span)),
+                    field: "items".to_string(),
+                },
+                span,
+            )
+        };
+        let items = Function::new(
+            "all",
+            vec![Node::new(
+                Param::new(Node::new("self".to_string(), span), None, None),
+                span,
+            )],
+            Some(list_of_t()),
+            Node::new(
+                Block::new(vec![Node::new(Statement::Return(Some(self_items())), span)]),
+                span,
+            ),
+        );
+        let program = Program::new(vec![Node::new(
+            Statement::Struct {
+                name: "Stack".to_string(),
+                fields: vec![("items".to_string(), list_of_t())],
+                methods: vec![Node::new(items, span)],
+                public: false,
+                generics: vec!["T".to_string()],
+                bounds: Vec::new(),
+                derives: Vec::new(),
+            },
+            span,
+        )]);
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        let stack = Node::new(
+            Expr::Struct {
+                name: "Stack".to_string(),
+                fields: vec![(
+                    "items".to_string(),
+                    Node::new(
+                        Expr::Array(vec![Node::new(
+                            Expr::Literal(Node::new(
+                                Literal::Number(NumberLiteral::new(1.0, false)),
+                                span,
+                            )),
+                            span,
+                        )]),
+                        span,
+                    ),
+                )],
+            },
+            span,
+        );
+        let stack_type = checker.infer_expr_type(&stack).unwrap();
+        assert_eq!(stack_type.display_name(), "Stack<i64>");
+
+        let call = Node::new(
+            Expr::Call {
+                func: Box::new(Node::new(
+                    Expr::Member {
+                        object: Box::new(stack),
+                        field: "all".to_string(),
+                    },
+                    span,
+                )),
+                args: Vec::new(),
+            },
+            span,
+        );
+        assert_eq!(
+            checker.infer_expr_type(&call).unwrap(),
+            TypeInfo::List(Box::new(TypeInfo::I64))
+        );
+
+        let annotation = |arg: &str| {
+            Node::new(
+                Type::Generic {
+                    base: "Stack".to_string(),
+                    args: vec![Node::new(Type::Simple(arg.to_string()), span)],
+                },
+                span,
+            )
+        };
+        let int_stack = checker.context.type_from_annotation(&annotation("int"));
+        let str_stack = checker.context.type_from_annotation(&annotation("str"));
+        assert!(stack_type.is_compatible_with(&int_stack));
+        assert!(!stack_type.is_compatible_with(&str_stack));
+    }
+
+    #[test]
+    fn nested_functions_see_enclosing_variables_and_stay_local() {
+        use otterc_ast::nodes::{Block, Function, Param};
+
+        let span = Span::new(0, 1);
+        let ident = |name: &str| Node::new(Expr::Identifier(name.to_string()), span);
+        let int = || Node::new(Type::Simple("int".to_string()), span);
+        let call = |name: &str, args: Vec<Node<Expr>>| {
+            Node::new(
+                Expr::Call {
+                    func: Box::new(ident(name)),
+                    args,
+                },
+                span,
+            )
+        };
+        let block = |statements: Vec<Statement>| {
+            Node::new(
+                Block::new(statements.into_iter().map(|s| Node::new(s, span)).collect()),
+                span,
+            )
+        };
+        // fn scale(n: int) -> int: return n * factor
+        let scale = Function::new(
+            "scale",
+            vec![Node::new(
+                Param::new(Node::new("n".to_string(), span), Some(int()), None),
+                span,
+            )],
+            Some(int()),
+            block(vec![Statement::Return(Some(Node::new(
+                Expr::Binary {
+                    op: BinaryOp::Mul,
+                    left: Box::new(ident("n")),
+                    right: Box::new(ident("factor")),
+                },
+                span,
+            )))]),
+        );
+        let outer = Function::new(
+            "outer",
+            vec![Node::new(
+                Param::new(Node::new("factor".to_string(), span), Some(int()), None),
+                span,
+            )],
+            Some(int()),
+            block(vec![
+                Statement::Function(Node::new(scale, span)),
+                Statement::Return(Some(call("scale", vec![ident("factor")]))),
+            ]),
+        );
+        let other = Function::new(
+            "other",
+            Vec::new(),
+            Some(int()),
+            block(vec![Statement::Return(Some(call(
+                "scale",
+                vec![ident("other")],
+            )))]),
+        );
+        let program = Program::new(vec![
+            Node::new(Statement::Function(Node::new(outer, span)), span),
+            Node::new(Statement::Function(Node::new(other, span)), span),
+        ]);
+
+        let mut checker = TypeChecker::new();
+        assert!(checker.check_program(&program).is_err());
+        // `outer` checks cleanly; only `other`, which cannot see `scale`, fails
+        assert_eq!(checker.errors[0].message, "undefined function: scale");
+        assert!(
+            checker
+                .errors
+                .iter()
+                .all(|error| !error.message.contains("factor"))
+        );
+    }
+
+    #[test]
+    fn anonymous_fn_has_a_function_type_and_scoped_params() {
+        use otterc_ast::nodes::Param;
+
+        let span = Span::new(0, 1);
+        let param = |name: &str, ty: &str| {
+            Node::new(
+                Param::new(
+                    Node::new(name.to_string(), span),
+                    Some(Node::new(Type::Simple(ty.to_string()), span)),
+                    None,
+                ),
+                span,
+            )
+        };
+        // fn(x: float) x > 0.5
+        let lambda = Node::new(
+            Expr::Lambda {
+                params: vec![param("x", "float")],
+                body: Box::new(Node::new(
+                    Expr::Binary {
+                        op: BinaryOp::Gt,
+                        left: Box::new(Node::new(Expr::Identifier("x".to_string()), span)),
+                        right: Box::new(Node::new(
+                            Expr::Literal(Node::new(
+                                Literal::Number(NumberLiteral::new(0.5, true)),
+                                span,
+                            )),
+                            span,
+                        )),
+                    },
+                    span,
+                )),
+            },
+            span,
+        );
+
+        let mut checker = TypeChecker::new();
+        checker
+            .context
+            .insert_variable("x".to_string(), TypeInfo::Str);
+        let ty = checker.infer_expr_type(&lambda).expect("infer lambda");
+        assert_eq!(
+            ty,
+            TypeInfo::Function {
+                params: vec![TypeInfo::F64],
+                param_defaults: vec![false],
+                return_type: Box::new(TypeInfo::Bool),
+            }
+        );
+        assert!(checker.errors.is_empty());
+        assert_eq!(checker.context.get_variable("x"), Some(&TypeInfo::Str));
+    }
+
+    #[test]
+    fn enum_matches_must_cover_every_variant() {
+        let check = |arms: &str| {
+            let source = format!(
+                "enum Shape:\n    Circle: (float)\n    Square: (float)\n    Empty\n\n\
+                 fn describe(shape: Shape) -> str:\n    let label = match shape:\n{arms}    return label\n"
+            );
+            let tokens = otterc_lexer::tokenize(&source).expect("tokenize");
+            let program = otterc_parser::parse(&tokens).expect("parse");
+            let mut checker = TypeChecker::new();
+            let _ = checker.check_program(&program);
+            checker.errors
+        };
+
+        let errors = check(
+            "        case Shape.Circle(_):\n            \"round\"\n        \
+             case Shape.Square(size) if size > 1.0:\n            \"big\"\n",
+        );
+        assert_eq!(
+            errors[0].message,
+            "non-exhaustive match on 'Shape': Shape.Square, Shape.Empty not covered"
+        );
+        assert_eq!(
+            errors[0].suggestion.as_deref(),
+            Some("case Shape.Square(_):\ncase Shape.Empty:")
+        );
+
+        let covered = check(
+            "        case Shape.Circle(_) | Shape.Square(_):\n            \"sized\"\n        \
+             case Shape.Empty:\n            \"empty\"\n",
+        );
+        assert!(covered.is_empty(), "{:?}", covered);
+        let wildcard = check(
+            "        case Shape.Circle(_):\n            \"round\"\n        \
+             case _:\n            \"other\"\n",
+        );
+        assert!(wildcard.is_empty(), "{:?}", wildcard);
+    }
+
+    #[test]
+    fn calls_to_unmapped_rust_functions_show_the_rust_signature() {
+        let registry: &'static SymbolRegistry = Box::leak(Box::new(SymbolRegistry::new()));
+        registry.register_unmapped(UnmappedFunction {
+            name: "geo.centroid".to_string(),
+            rust_signature: "fn geo::centroid(Vec<f64>) -> f64".to_string(),
+            unmapped: vec!["parameter 1 type `Vec<f64>`".to_string()],
+        });
+        let tokens =
+            otterc_lexer::tokenize("fn main():\n    geo.centroid(1.0)\n").expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new().with_registry(registry);
+        let _ = checker.check_program(&program);
+
+        assert_eq!(
+            checker.errors[0].message,
+            "cannot call Rust function `geo.centroid`: no Otter equivalent for parameter 1 type `Vec<f64>`"
+        );
+        assert_eq!(
+            checker.errors[0].help.as_deref(),
+            Some("Rust signature: fn geo::centroid(Vec<f64>) -> f64")
+        );
+    }
+
+    #[test]
+    fn runtime_callbacks_must_take_the_arguments_the_runtime_passes() {
+        use otterc_symbol::registry::{FfiFunction, FfiSignature};
+
+        let registry: &'static SymbolRegistry = Box::leak(Box::new(SymbolRegistry::new()));
+        registry.register(FfiFunction {
+            name: "runtime.on_shutdown".into(),
+            symbol: "otter_runtime_on_shutdown".into(),
+            signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+        });
+        registry.register(FfiFunction {
+            name: "runtime.set_panic_hook".into(),
+            symbol: "otter_runtime_set_panic_hook".into(),
+            signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+        });
+        let source = "fn flush():\n    pass\n\
+                      fn save(path: str):\n    pass\n\
+                      fn report(message: str, location: str, backtrace: str):\n    pass\n\
+                      fn main():\n    \
+                      runtime.on_shutdown(flush)\n    \
+                      runtime.on_shutdown(save)\n    \
+                      runtime.set_panic_hook(report)\n    \
+                      runtime.set_panic_hook(save)\n    \
+                      let hook = flush\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new().with_registry(registry);
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "argument 1 to `runtime.on_shutdown` must be a function with no parameters",
+                "argument 1 to `runtime.set_panic_hook` must be a function with parameters (str, str, str)",
+                // Outside a callback argument a function name is not a value
+                "undefined variable: flush",
+            ]
+        );
+    }
+
+    #[test]
+    fn conditions_narrow_dynamic_variables_per_branch() {
+        let source = "fn describe(value) -> int:\n    \
+                      if type_of(value) == \"str\":\n        return value\n    \
+                      elif value is None:\n        return 0\n    \
+                      return value\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        // Only the `str` branch conflicts with the return type; `value` is
+        // dynamic again after the `if`
+        assert_eq!(checker.errors.len(), 1, "{:?}", checker.errors);
+        assert!(checker.errors[0].message.contains("str"));
+    }
+
+    #[test]
+    fn union_types_are_narrowed_by_type_tests() {
+        let source = "fn width(value: int | str | None) -> int:\n    \
+                      if value is None:\n        return 0\n    \
+                      elif type_of(value) == \"string\":\n        return len(value)\n    \
+                      else:\n        return value\n\
+                      fn label(value: int | str) -> str:\n    \
+                      match type_of(value):\n        \
+                      case \"int\":\n            return \"number\"\n    \
+                      return \"text\"\n\
+                      fn main():\n    \
+                      let x: int | str = 1\n    \
+                      x = \"one\"\n    \
+                      let y: int = x\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(
+            messages[0].contains("non-exhaustive match on type_of(value): str not covered"),
+            "{:?}",
+            messages
+        );
+        assert!(messages[1].contains("i64 | str"), "{:?}", messages);
+        assert_eq!(
+            checker.errors[0].suggestion.as_deref(),
+            Some("case \"string\":")
+        );
+    }
+
+    #[test]
+    fn builtin_types_have_methods() {
+        let source = "fn main():\n    \
+                      let name = \"otter\"\n    \
+                      let loud: str = name.upper()\n    \
+                      let parts: list<str> = name.split(\",\")\n    \
+                      let count: int = parts.len()\n    \
+                      let ages = {\"ada\": 36}\n    \
+                      let keys: list<str> = ages.keys()\n    \
+                      let width = 2.5\n    \
+                      let rounded: float = width.round()\n    \
+                      let found: bool = name.starts_with(1)\n    \
+                      name.shout()\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert_eq!(messages[0], "argument 1 type mismatch");
+        let mismatch = checker.errors[0].mismatch.as_ref().expect("mismatch");
+        assert_eq!(mismatch.expected, TypeInfo::Str);
+        assert_eq!(messages[1], "type str has no method 'shout'");
+        assert!(
+            checker.errors[1]
+                .hint
+                .as_deref()
+                .is_some_and(|hint| hint.contains("upper"))
+        );
+    }
+
+    #[test]
+    fn tagged_literals_are_validated_at_compile_time() {
+        struct Shouting;
+        impl LiteralValidator for Shouting {
+            fn validate(&self, value: &str) -> std::result::Result<(), LiteralError> {
+                if value == value.to_uppercase() {
+                    Ok(())
+                } else {
+                    Err(LiteralError::new("must be upper case"))
+                }
+            }
+        }
+
+        let source = "fn main():\n    \
+                      let query: str = sql\"SELECT id FROM users\"\n    \
+                      let broken = sql\"SELECT id, FROM users\"\n    \
+                      let loud = yell\"hi\"\n    \
+                      let typo = sqll\"SELECT 1\"\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new().with_literal_validator("yell", Arc::new(Shouting));
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "invalid sql literal: unexpected `,` before FROM",
+                "invalid yell literal: must be upper case",
+                "unknown string prefix 'sqll'",
+            ]
+        );
+        let broken_start = source.find("sql\"SELECT id,").expect("literal");
+        assert_eq!(
+            checker.errors[0].span.map(|span| span.start()),
+            Some(broken_start)
+        );
+        assert_eq!(checker.errors[2].suggestion.as_deref(), Some("sql"));
+    }
+
+    #[test]
+    fn unused_bindings_and_imports_are_warnings() {
+        let source = "use math\n\
+                      use otter:io\n\
+                      from math import sin, cos\n\
+                      fn scale(value: float, factor: float, _unit: str) -> float:\n    \
+                      let doubled = value * 2.0\n    \
+                      let _ignored = 1\n    \
+                      let label = \"x\"\n    \
+                      label = \"y\"\n    \
+                      return sin(value)\n\
+                      fn main():\n    \
+                      io.println(f\"{scale(1.0, 2.0, \"m\")}\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker
+            .warnings()
+            .iter()
+            .map(|w| w.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "unused import: `math`",
+                "unused import: `cos` from `math`",
+                "unused parameter: `factor`",
+                "unused variable: `doubled`",
+                "unused variable: `label`",
+            ]
+        );
+        let factor = source.find("factor").expect("parameter");
+        assert_eq!(
+            checker.warnings()[2].span.map(|span| span.start()),
+            Some(factor)
+        );
+        assert_eq!(checker.warnings()[2].suggestion.as_deref(), Some("_factor"));
+    }
+
+    #[test]
+    fn statements_after_diverging_ones_are_unreachable() {
+        let source = "fn sign(n: int) -> int:\n    \
+                      if n < 0:\n        \
+                      return -1\n    \
+                      else:\n        \
+                      return 1\n    \
+                      print(\"done\")\n\
+                      fn main():\n    \
+                      for i in 0..3:\n        \
+                      continue\n        \
+                      print(f\"{i}\")\n    \
+                      panic(\"stop\")\n    \
+                      print(f\"{sign(1)}\")\n    \
+                      print(\"never\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let starts: Vec<_> = checker
+            .warnings()
+            .iter()
+            .filter(|w| w.message == "unreachable code")
+            .map(|w| w.span.map(|span| span.start()))
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                source.find("print(\"done\")"),
+                source.find("print(f\"{i}\")"),
+                source.find("print(f\"{sign(1)}\")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn structs_and_enums_can_refer_to_themselves() {
+        let source = "enum Tree:\n    \
+                      Leaf: (int)\n    \
+                      Node: (Tree, Tree)\n\
+                      struct Employee:\n    \
+                      name: str\n    \
+                      team: Team\n\
+                      struct Team:\n    \
+                      lead: Employee\n    \
+                      size: int\n\
+                      fn lead_team_size(employee: Employee) -> int:\n    \
+                      return employee.team.lead.team.size\n\
+                      fn lead_name(team: Team) -> str:\n    \
+                      return team.lead.name\n\
+                      fn main():\n    \
+                      let tree = Tree.Node(Tree.Leaf(1), Tree.Node(Tree.Leaf(2), Tree.Leaf(3)))\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+    }
+
+    #[test]
+    fn with_resources_need_an_exit_method() {
+        let source = "struct Log:\n    \
+                      path: str\n    \
+                      fn close(self):\n        pass\n\
+                      struct Plain:\n    \
+                      path: str\n\
+                      fn record(early: bool) -> int:\n    \
+                      with Log(path=\"app.log\") as log:\n        \
+                      if early:\n            return 1\n        \
+                      let path: str = log.path\n    \
+                      return 0\n\
+                      fn main():\n    \
+                      with Plain(path=\"x\"):\n        pass\n    \
+                      with 5:\n        pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "type Plain { path: str } cannot be used in a `with` statement",
+                "type i64 cannot be used in a `with` statement",
+            ]
+        );
+    }
+
+    #[test]
+    fn structs_satisfy_protocols_by_their_methods() {
+        let source = "protocol Reader:\n    \
+                      fn read(self, n: int) -> str\n\
+                      struct File:\n    \
+                      path: str\n    \
+                      fn read(self, n: int) -> str:\n        \
+                      return self.path\n\
+                      struct Socket:\n    \
+                      port: int\n    \
+                      fn read(self, n: str) -> str:\n        \
+                      return n\n\
+                      struct Clock:\n    \
+                      ticks: int\n\
+                      fn first(reader: Reader) -> str:\n    \
+                      return reader.read(1)\n\
+                      fn main():\n    \
+                      let file = File(path=\"notes.txt\")\n    \
+                      let source: Reader = file\n    \
+                      print(first(source))\n    \
+                      print(first(file))\n    \
+                      print(first(Socket(port=80)))\n    \
+                      print(first(Clock(ticks=0)))\n    \
+                      source.write(\"x\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "argument 1 type mismatch",
+                "argument 1 type mismatch",
+                "protocol 'Reader' has no method 'write'",
+            ]
+        );
+        let found: Vec<_> = checker
+            .errors
+            .iter()
+            .filter_map(|error| error.mismatch.as_ref())
+            .map(|mismatch| mismatch.found.display_name())
+            .collect();
+        assert_eq!(found, ["Socket { port: i64 }", "Clock { ticks: i64 }"]);
+        assert_eq!(
+            checker.errors[0].help.as_deref(),
+            Some(
+                "`Socket { port: i64 }` does not satisfy protocol `Reader`: \
+                 method `read` is fn(str) -> str, the protocol needs fn(i64) -> str"
+            )
+        );
+        assert_eq!(
+            checker.errors[1].help.as_deref(),
+            Some(
+                "`Clock { ticks: i64 }` does not satisfy protocol `Reader`: missing method `read`"
+            )
+        );
+    }
+
+    #[test]
+    fn integer_literals_must_fit_i64_unless_used_as_u64() {
+        let source = "fn main():\n    \
+                      let big = 9_223_372_036_854_775_808\n    \
+                      let smallest = -9_223_372_036_854_775_808\n    \
+                      let max: u64 = 18_446_744_073_709_551_615\n    \
+                      let wrapped: u8 = 9_223_372_036_854_775_808\n    \
+                      print(f\"{big}{smallest}{max}{wrapped}\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                INT_LITERAL_OUT_OF_RANGE,
+                "literal `9223372036854775808` does not fit in u8",
+            ]
+        );
+        assert_eq!(
+            checker.errors[0].span.map(|span| span.start()),
+            source.find("9_223")
+        );
+    }
+
+    #[test]
+    fn derived_methods_are_checked_like_written_ones() {
+        let source = "@derive(json, eq, debug)\n\
+                      struct Point:\n    \
+                      x: int\n    \
+                      label: str\n\
+                      @derive(eq, debug)\n\
+                      enum Shape:\n    \
+                      Circle: (radius: float)\n    \
+                      Pair: (Point, Point)\n    \
+                      Empty\n\
+                      fn same(a: Shape, b: Shape) -> bool:\n    \
+                      return a == b and a.eq(b)\n\
+                      fn main():\n    \
+                      let p = Point(x=1, label=\"a\")\n    \
+                      let equal: bool = p == Point(x=2, label=\"b\")\n    \
+                      let text: str = p.to_json() + p.to_string()\n    \
+                      print(f\"{equal}{text}\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let mut program = otterc_parser::parse(&tokens).expect("parse");
+        let errors = program.expand_derives();
+        assert!(errors.is_empty(), "{errors:?}");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+    }
+
+    #[test]
+    fn derives_report_fields_they_cannot_handle() {
+        let source = "struct Tag:\n    \
+                      name: str\n\
+                      @derive(json, eq, debug)\n\
+                      struct Item:\n    \
+                      tag: Tag\n    \
+                      fn eq(self, other: Item) -> bool:\n        \
+                      return true\n\
+                      @derive(json)\n\
+                      struct Batch:\n    \
+                      ids: list\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let mut program = otterc_parser::parse(&tokens).expect("parse");
+        let errors = program.expand_derives();
+
+        let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "cannot derive json for `Item`: field `tag` has type `Tag`, which does not derive json",
+                "`Item` already has a method `eq`",
+                "cannot derive debug for `Item`: field `tag` has type `Tag`, which does not derive debug",
+                "cannot derive json for `Batch`: field `ids` has type `list`",
+            ]
+        );
+        assert_eq!(
+            errors[0].help.as_deref(),
+            Some("add `json` to the @derive(...) of `Tag`")
+        );
+        assert_eq!(
+            errors[1].span.start(),
+            source.find("eq, debug").expect("derive")
+        );
+    }
+
+    #[test]
+    fn unannotated_parameters_take_the_type_of_their_arguments() {
+        use otterc_symbol::registry::{FfiFunction, FfiSignature};
+
+        let registry: &'static SymbolRegistry = Box::leak(Box::new(SymbolRegistry::new()));
+        registry.register(FfiFunction {
+            name: "events.on_tick".into(),
+            symbol: "otter_events_on_tick".into(),
+            signature: FfiSignature::new(vec![FfiType::Opaque], FfiType::Unit),
+        });
+        let source = "fn scale(x, factor) -> float:\n    \
+                      return x * factor\n\
+                      fn countdown(n):\n    \
+                      if n > 0:\n        \
+                      countdown(n - 1)\n\
+                      fn outer(v):\n    \
+                      inner(v)\n\
+                      fn inner(w):\n    \
+                      print(f\"{w}\")\n\
+                      fn describe(value):\n    \
+                      print(f\"{value}\")\n\
+                      fn twice(k) -> int:\n    \
+                      return k * 2\n\
+                      fn pad(text, width = 8, fill = \" \"):\n    \
+                      print(f\"{text}{width}{fill}\")\n\
+                      fn main():\n    \
+                      let a = scale(2.0, 1.5)\n    \
+                      let b = scale(a, 2.0)\n    \
+                      countdown(3)\n    \
+                      outer(1.5)\n    \
+                      describe(1)\n    \
+                      describe(\"one\")\n    \
+                      events.on_tick(twice)\n    \
+                      let c = twice(4)\n    \
+                      pad(\"a\")\n    \
+                      pad(\"b\", 4)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let mut program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new().with_registry(registry);
+        checker.infer_parameter_types(&mut program);
+        let _ = checker.check_program(&program);
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+
+        let param_types = |name: &str| -> Vec<Option<TypeInfo>> {
+            program
+                .statements
+                .iter()
+                .find_map(|statement| match statement.as_ref() {
+                    Statement::Function(function) if function.as_ref().name == name => Some(
+                        function
+                            .as_ref()
+                            .params
+                            .iter()
+                            .map(|param| param.as_ref().ty.as_ref().map(TypeInfo::from))
+                            .collect(),
+                    ),
+                    _ => None,
+                })
+                .expect("function")
+        };
+        assert_eq!(
+            param_types("scale"),
+            [Some(TypeInfo::F64), Some(TypeInfo::F64)]
+        );
+        assert_eq!(param_types("countdown"), [Some(TypeInfo::I64)]);
+        assert_eq!(param_types("inner"), [Some(TypeInfo::F64)]);
+        // Calls disagree, so the parameter stays dynamic
+        assert_eq!(param_types("describe"), [None]);
+        // `twice` is handed to the runtime, which may call it with anything
+        assert_eq!(param_types("twice"), [None]);
+        // A default counts as one more argument
+        assert_eq!(
+            param_types("pad"),
+            [
+                Some(TypeInfo::Str),
+                Some(TypeInfo::I64),
+                Some(TypeInfo::Str)
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_default_values_are_computed_while_compiling() {
+        let source = "const SECONDS_PER_DAY = 60 * 60 * 24\n\
+                      fn wait(seconds: int = 7 * SECONDS_PER_DAY, label: str = \"week\"):\n    \
+                      print(label)\n\
+                      fn sleep(nanos: int = SECONDS_PER_DAY * 1000000000 * 1000000):\n    \
+                      print(\"zzz\")\n\
+                      fn split(parts: int = 10 / (SECONDS_PER_DAY - 86400)):\n    \
+                      print(\"split\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        assert!(checker.check_program(&program).is_err());
+
+        let messages: Vec<_> = checker
+            .errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "invalid default value for parameter `nanos`: integer overflow in constant",
+                "invalid default value for parameter `parts`: division by zero in constant",
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_bounds_are_checked_where_types_are_built() {
+        let source = "protocol Describable:\n    \
+                      fn describe(self) -> str\n\
+                      struct Label:\n    \
+                      text: str\n    \
+                      fn describe(self) -> str:\n        \
+                      return self.text\n\
+                      struct Stats<T: Numeric>:\n    \
+                      values: list<T>\n\
+                      struct Shelf<T: Describable>:\n    \
+                      items: list<T>\n\
+                      enum Reading<T: Numeric>:\n    \
+                      Value: (T)\n    \
+                      Missing\n\
+                      struct Sorted<T: Sortable>:\n    \
+                      items: list<T>\n\
+                      fn main():\n    \
+                      let ints = Stats(values=[1, 2])\n    \
+                      let floats = Stats(values=[1.5])\n    \
+                      let shelf = Shelf(items=[Label(text=\"otter\")])\n    \
+                      let words = Stats(values=[\"a\"])\n    \
+                      let numbers = Shelf(items=[1])\n    \
+                      let reading = Reading.Value(\"high\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown bound `Sortable`",
+                "`str` does not satisfy the bound `Numeric` of `T` in `Stats`",
+                "`i64` does not satisfy the bound `Describable` of `T` in `Shelf`",
+                "`str` does not satisfy the bound `Numeric` of `T` in `Reading`",
+            ]
+        );
+        let call = source.find("Stats(values=[\"a\"])").expect("call");
+        assert_eq!(checker.errors[1].span.map(|span| span.start()), Some(call));
+        assert_eq!(
+            checker.errors[1].help.as_deref(),
+            Some("`Numeric` is satisfied by the integer types and `float`")
+        );
+    }
+
+    #[test]
+    fn mismatched_types_are_lined_up_with_the_difference_marked() {
+        let source = "fn main():\n    \
+                      let rows: list<dict<str, int>> = [{\"a\": \"x\"}]\n    \
+                      let pairs: dict<str, list<int>> = {\"a\": [1.5]}\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let rendered: Vec<_> = checker
+            .errors
+            .iter()
+            .filter_map(|error| error.mismatch.as_ref())
+            .map(|mismatch| mismatch.render())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "expected: list<dict<str, i64>>\n\
+                 \x20                        ^^^\n\
+                 \x20  found: list<dict<str, str>>\n\
+                 \x20                        ^^^",
+                "expected: dict<str, list<i64>>\n\
+                 \x20                        ^^^\n\
+                 \x20  found: dict<str, list<f64>>\n\
+                 \x20                        ^^^",
+            ]
+        );
+        assert_eq!(checker.errors[0].message, "type mismatch");
+    }
+
+    #[test]
+    fn error_values_chain_context_and_can_be_raised() {
+        let source = "fn load() -> Error:\n    \
+                      return error(\"file not found\")\n\n\
+                      fn main():\n    \
+                      let err: Error = load().context(\"while reading config\")\n    \
+                      let message: str = err.message()\n    \
+                      let report: str = err.to_string()\n    \
+                      panic(err)\n    \
+                      panic(\"plain message\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        assert!(
+            checker.check_program(&program).is_ok(),
+            "{:?}",
+            checker.errors
+        );
+
+        let source = "fn main():\n    \
+                      error(404)\n    \
+                      error(\"x\").cause()\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert_eq!(messages[1], "type Error has no method 'cause'");
+    }
+
+    #[test]
+    fn code_that_never_completes_needs_no_value() {
+        let source = "fn fail(message: str) -> never:\n    \
+                      panic(message)\n\n\
+                      fn serve() -> never:\n    \
+                      while true:\n        \
+                      print(\"waiting\")\n\n\
+                      fn parse_digit(text: str) -> int:\n    \
+                      if text == \"0\":\n        \
+                      return 0\n    \
+                      elif text == \"1\":\n        \
+                      return 1\n    \
+                      else:\n        \
+                      fail(f\"not a digit: {text}\")\n\n\
+                      fn weight(size: str) -> int:\n    \
+                      let grams = match size:\n        \
+                      case \"small\":\n            \
+                      10\n        \
+                      case _:\n            \
+                      panic(\"unknown size\")\n    \
+                      return grams\n\n\
+                      fn halves(n: int) -> int:\n    \
+                      while true:\n        \
+                      if n < 2:\n            \
+                      break\n        \
+                      n = n - 2\n\n\
+                      fn stop() -> never:\n    \
+                      print(\"stopping\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "missing return in function `halves`, which returns i64",
+                "function `stop` is declared `never` but can return",
+            ]
+        );
+        let annotation = source.find("int:\n    while").expect("annotation");
+        assert_eq!(
+            checker.errors[0].span.map(|span| span.start()),
+            Some(annotation)
+        );
+    }
+
+    #[test]
+    fn mixed_numbers_widen_to_a_common_type() {
+        let source = "fn main():\n    \
+                      let count: i32 = 3\n    \
+                      let total: i64 = 10\n    \
+                      let ratio = 0.5\n    \
+                      let below: bool = ratio < count\n    \
+                      let above = total > ratio\n    \
+                      let converted: bool = (total as float) > ratio\n    \
+                      let scaled: float = count * ratio\n    \
+                      let doubled: float = ratio * 2\n    \
+                      let average = total / ratio\n    \
+                      let rounded: float = total\n    \
+                      let sum: i64 = count + total\n    \
+                      let narrowed: i32 = total\n    \
+                      let truncated: int = ratio\n    \
+                      let size: u64 = 4\n    \
+                      let mixed = size < total\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let found: Vec<_> = checker
+            .errors
+            .iter()
+            .map(|error| {
+                let mismatch = error.mismatch.as_ref().map(|mismatch| {
+                    (
+                        mismatch.expected.display_name(),
+                        mismatch.found.display_name(),
+                    )
+                });
+                (error.message.as_str(), mismatch)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("cannot compare i64 and f64", None),
+                ("mismatched numeric types i64 and f64", None),
+                (
+                    "type mismatch",
+                    Some(("f64".to_string(), "i64".to_string()))
+                ),
+                (
+                    "type mismatch",
+                    Some(("i32".to_string(), "i64".to_string()))
+                ),
+                (
+                    "type mismatch",
+                    Some(("i64".to_string(), "f64".to_string()))
+                ),
+                ("cannot compare u64 and i64", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn result_and_option_combinators_carry_the_value_type() {
+        let source = "enum Option<T>:\n    \
+                      Some: (T)\n    \
+                      None\n\n\
+                      enum Result<T, E>:\n    \
+                      Ok: (T)\n    \
+                      Err: (E)\n\n\
+                      fn parse(text: str) -> Result<int, str>:\n    \
+                      if text == \"\":\n        \
+                      return Result.Err(\"empty\")\n    \
+                      return Result.Ok(len(text))\n\n\
+                      fn main():\n    \
+                      let doubled: Result<float, str> = parse(\"42\").map(fn(n) n * 2.0)\n    \
+                      let chained: Result<int, str> = parse(\"4\").and_then(parse_twice)\n    \
+                      let count: int = parse(\"\").unwrap_or(0)\n    \
+                      let name: str = Option.Some(\"otter\").expect(\"a name\")\n\n\
+                      fn parse_twice(n: int) -> Result<int, str>:\n    \
+                      return parse(str(n))\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        assert!(
+            checker.check_program(&program).is_ok(),
+            "{:?}",
+            checker.errors
+        );
+
+        let source = "enum Result<T, E>:\n    \
+                      Ok: (T)\n    \
+                      Err: (E)\n\n\
+                      fn main():\n    \
+                      let parsed = Result.Ok(1)\n    \
+                      parsed.and_then(fn(n) n + 1)\n    \
+                      parsed.unwrap_or(\"none\")\n    \
+                      parsed.expect(404)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "and_then() expects a function returning Result, got one returning int",
+                "unwrap_or() default has the wrong type",
+                "expect() message must be str, got int",
+            ]
+        );
+    }
+
+    #[test]
+    fn fstring_interpolations_are_checked_where_they_are_written() {
+        let source = "fn main():\n    \
+                      let count = 3\n    \
+                      print(f\"total: {cuont + 1}\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        assert_eq!(checker.errors.len(), 1, "{:?}", checker.errors);
+        let error = &checker.errors[0];
+        assert_eq!(error.message, "undefined variable: cuont");
+        assert_eq!(error.hint.as_deref(), Some("did you mean `count`?"));
+        let start = source.find("cuont").expect("typo in source");
+        assert_eq!(
+            error.span.map(|span| (span.start(), span.end())),
+            Some((start, start + "cuont".len()))
+        );
+    }
+
+    #[test]
+    fn init_blocks_are_checked_once_per_module() {
+        let source = "fn register(name: str):\n    \
+                      pass\n\n\
+                      init:\n    \
+                      register(\"otter\")\n\n\
+                      init:\n    \
+                      register(42)\n    \
+                      return 1\n\n\
+                      fn main():\n    \
+                      pass\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert_eq!(messages[0], "a module can have only one `init:` block");
+        assert_eq!(messages[2], "return type mismatch");
+    }
+
+    #[test]
+    fn format_specs_are_checked_against_the_value() {
+        let source = "fn main():\n    \
+                      let name = \"otter\"\n    \
+                      let price = 2.5\n    \
+                      let count = 1200\n    \
+                      print(f\"{name:>8} {price:.2f} {count:,} {count:.1%}\")\n    \
+                      print(f\"{name:.2f} {price:x} {price:.2q}\")\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let errors: Vec<_> = checker
+            .errors
+            .iter()
+            .map(|error| {
+                let span = error.span.expect("format spec errors have spans");
+                (error.message.as_str(), &source[span.start()..span.end()])
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    "format spec `.2f` does not apply to `str`: `f` formats numbers",
+                    ".2f"
+                ),
+                (
+                    "format spec `x` does not apply to `f64`: `x` formats integers",
+                    "x"
+                ),
+                ("invalid format spec `.2q`: unknown format type `q`", "q"),
+            ]
+        );
+    }
+
+    #[test]
+    fn symbols_are_not_strings() {
+        let source = "fn main():\n    \
+                      let state = :ready\n    \
+                      let same = state == sym(\"ready\")\n    \
+                      let name: str = state as str\n    \
+                      match state:\n        \
+                      case :ready:\n            \
+                      pass\n        \
+                      case _:\n            \
+                      pass\n    \
+                      let text: str = state\n    \
+                      let mixed = state == \"ready\"\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["type mismatch", "cannot compare symbol and str"],
+            "{:?}",
+            checker.errors
+        );
+    }
+
+    #[test]
+    fn frozen_collections_return_updated_copies() {
+        let source = "fn main():\n    \
+                      let items: list<int> = [1, 2]\n    \
+                      let frozen = items.freeze()\n    \
+                      let more = frozen.append(3).set(0, 10)\n    \
+                      let first: int = more.get(0)\n    \
+                      let back: list<int> = more.thaw()\n    \
+                      let scores: dict<str, float> = {\"a\": 1.0}\n    \
+                      let snapshot = scores.freeze().set(\"b\", 2.0).remove(\"a\")\n    \
+                      let b: float = snapshot.get(\"b\")\n    \
+                      frozen.push(4)\n    \
+                      let wrong: str = frozen.get(0)\n";
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        let program = otterc_parser::parse(&tokens).expect("parse");
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program(&program);
+
+        let messages: Vec<_> = checker.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["type FrozenList<i64> has no method 'push'", "type mismatch"],
+            "{:?}",
+            checker.errors
+        );
+    }
 }
@@ -0,0 +1,479 @@
+//! Compile-time evaluation of `const` initializers
+//!
+//! A `const` initializer may use literals, earlier constants, arithmetic,
+//! comparison, logical and bitwise operators, `as` casts and calls to the pure
+//! builtins in [`CONST_FUNCTIONS`] and [`const_methods`]. Anything else that
+//! needs the program to run is rejected. The JIT's constant folding evaluates
+//! operators with the same functions, so both agree on the result.
+
+use otterc_ast::nodes::{BinaryOp, Expr, Literal, NumberLiteral, Type, UnaryOp};
+
+use crate::types::TypeInfo;
+
+/// Value of a constant, computed while compiling
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ConstValue {
+    /// The type the value has when no annotation says otherwise
+    pub fn type_info(&self) -> TypeInfo {
+        match self {
+            ConstValue::Int(_) => TypeInfo::I64,
+            ConstValue::Float(_) => TypeInfo::F64,
+            ConstValue::Bool(_) => TypeInfo::Bool,
+            ConstValue::Str(_) => TypeInfo::Str,
+        }
+    }
+
+    /// Converts the value to `ty`, the constant's declared type. Integers
+    /// must fit the target's range.
+    pub fn convert_to(self, ty: &TypeInfo) -> Result<ConstValue, String> {
+        match (self, ty) {
+            (ConstValue::Int(value), ty) if ty.is_integer() => {
+                let (min, max) = ty
+                    .int_range()
+                    .unwrap_or((i64::MIN as i128, i64::MAX as i128));
+                if (value as i128) < min || (value as i128) > max {
+                    Err(format!(
+                        "constant value {value} does not fit in {}",
+                        ty.display_name()
+                    ))
+                } else {
+                    Ok(ConstValue::Int(value))
+                }
+            }
+            (ConstValue::Int(value), TypeInfo::F64) => Ok(ConstValue::Float(value as f64)),
+            (value, _) => Ok(value),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ConstValue::Int(_) => "int",
+            ConstValue::Float(_) => "float",
+            ConstValue::Bool(_) => "bool",
+            ConstValue::Str(_) => "str",
+        }
+    }
+
+    /// The value of a literal, or `None` for `None` and `()`
+    pub fn from_literal(literal: &Literal) -> Option<Result<ConstValue, String>> {
+        Some(match literal {
+            Literal::Number(num) if num.is_float_literal => Ok(ConstValue::Float(num.value)),
+            Literal::Number(num) => i64::try_from(num.int_value())
+                .map(ConstValue::Int)
+                .map_err(|_| "integer literal out of range for i64".to_string()),
+            Literal::Bool(value) => Ok(ConstValue::Bool(*value)),
+            Literal::String(value) | Literal::Checked { value, .. } => {
+                Ok(ConstValue::Str(value.clone()))
+            }
+            Literal::Bytes(_) | Literal::Symbol(_) | Literal::None | Literal::Unit => return None,
+        })
+    }
+
+    /// The literal that evaluates to this value
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            ConstValue::Int(value) => {
+                let mut num = NumberLiteral::new(*value as f64, false);
+                num.integer = u64::try_from(*value).ok();
+                Literal::Number(num)
+            }
+            ConstValue::Float(value) => Literal::Number(NumberLiteral::new(*value, true)),
+            ConstValue::Bool(value) => Literal::Bool(*value),
+            ConstValue::Str(value) => Literal::String(value.clone()),
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            ConstValue::Int(value) => Some(*value as f64),
+            ConstValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn format(&self) -> String {
+        match self {
+            ConstValue::Int(value) => value.to_string(),
+            ConstValue::Float(value) => value.to_string(),
+            ConstValue::Bool(value) => value.to_string(),
+            ConstValue::Str(value) => value.clone(),
+        }
+    }
+}
+
+/// Evaluates `expr`, looking up names of other constants with `lookup`.
+/// Qualified names such as `config.MAX` are passed to `lookup` whole.
+pub fn eval_const_expr(
+    expr: &Expr,
+    lookup: &dyn Fn(&str) -> Option<ConstValue>,
+) -> Result<ConstValue, String> {
+    match expr {
+        Expr::Literal(literal) => ConstValue::from_literal(literal.as_ref()).unwrap_or_else(|| {
+            Err("only numbers, booleans and strings can be constants".to_string())
+        }),
+        Expr::Identifier(name) => lookup(name).ok_or_else(|| format!("`{name}` is not a constant")),
+        Expr::Member { .. } => {
+            let path = member_path(expr).ok_or("this expression is not a constant")?;
+            lookup(&path).ok_or_else(|| format!("`{path}` is not a constant"))
+        }
+        Expr::Unary { op, expr } => {
+            // `-9223372036854775808` is the only way to write i64::MIN
+            if *op == UnaryOp::Neg
+                && let Expr::Literal(literal) = expr.as_ref().as_ref()
+                && let Literal::Number(num) = literal.as_ref()
+                && !num.is_float_literal
+            {
+                return i64::try_from(-num.int_value())
+                    .map(ConstValue::Int)
+                    .map_err(|_| "integer literal out of range for i64".to_string());
+            }
+            let value = eval_const_expr(expr.as_ref().as_ref(), lookup)?;
+            eval_const_unary(*op, value)
+        }
+        Expr::Binary { op, left, right } => {
+            let left = eval_const_expr(left.as_ref().as_ref(), lookup)?;
+            // `and` / `or` only look at the right side when they need it
+            match (op, &left) {
+                (BinaryOp::And, ConstValue::Bool(false)) => return Ok(ConstValue::Bool(false)),
+                (BinaryOp::Or, ConstValue::Bool(true)) => return Ok(ConstValue::Bool(true)),
+                _ => {}
+            }
+            let right = eval_const_expr(right.as_ref().as_ref(), lookup)?;
+            eval_const_binary(*op, left, right)
+        }
+        Expr::Call { func, args } => {
+            let args = args
+                .iter()
+                .map(|arg| eval_const_expr(arg.as_ref(), lookup))
+                .collect::<Result<Vec<_>, _>>()?;
+            match func.as_ref().as_ref() {
+                Expr::Identifier(name) if CONST_FUNCTIONS.contains(&name.as_str()) => {
+                    eval_const_call(name, args)
+                }
+                Expr::Member { object, field } => {
+                    match eval_const_expr(object.as_ref().as_ref(), lookup) {
+                        Ok(receiver) => eval_const_method(receiver, field, args),
+                        Err(_) => Err(format!(
+                            "`{}` cannot be called in a constant",
+                            member_path(func.as_ref().as_ref()).unwrap_or_else(|| field.clone())
+                        )),
+                    }
+                }
+                Expr::Identifier(name) => Err(format!(
+                    "`{name}` cannot be called in a constant; only {} can",
+                    CONST_FUNCTIONS.join(" and ")
+                )),
+                _ => Err("this call cannot be made in a constant".to_string()),
+            }
+        }
+        Expr::Cast { expr, ty } => {
+            let value = eval_const_expr(expr.as_ref().as_ref(), lookup)?;
+            let Type::Simple(target) = ty.as_ref() else {
+                return Err("invalid cast in constant".to_string());
+            };
+            let target = TypeInfo::from(target.as_str());
+            match (value, &target) {
+                (value, TypeInfo::Str) => Ok(ConstValue::Str(value.format())),
+                (ConstValue::Float(value), target) if target.is_integer() => {
+                    ConstValue::Int(value as i64).convert_to(target)
+                }
+                (ConstValue::Bool(value), target) if target.is_integer() => {
+                    Ok(ConstValue::Int(value as i64))
+                }
+                (ConstValue::Int(value), target) if target.is_integer() => {
+                    ConstValue::Int(value).convert_to(target)
+                }
+                (ConstValue::Bool(flag), TypeInfo::F64) => {
+                    Ok(ConstValue::Float(flag as i64 as f64))
+                }
+                (value, TypeInfo::F64) => value
+                    .as_float()
+                    .map(ConstValue::Float)
+                    .ok_or_else(|| format!("cannot cast {} to float", value.describe())),
+                (value, TypeInfo::Bool) if matches!(value, ConstValue::Bool(_)) => Ok(value),
+                (value, target) => Err(format!(
+                    "cannot cast {} to {}",
+                    value.describe(),
+                    target.display_name()
+                )),
+            }
+        }
+        _ => Err("constants must be computed from literals and other constants".to_string()),
+    }
+}
+
+/// Reason given when an integer operation of a constant overflows
+pub const CONST_OVERFLOW: &str = "integer overflow in constant";
+
+/// Reason given when a constant divides by zero
+pub const CONST_DIVISION_BY_ZERO: &str = "division by zero in constant";
+
+/// Builtin functions a constant may call. They are pure, so calling them
+/// while compiling gives the same result as at run time.
+pub const CONST_FUNCTIONS: &[&str] = &["len", "str"];
+
+/// Applies `op` to a constant operand
+pub fn eval_const_unary(op: UnaryOp, value: ConstValue) -> Result<ConstValue, String> {
+    match (op, value) {
+        (UnaryOp::Neg, ConstValue::Int(value)) => value
+            .checked_neg()
+            .map(ConstValue::Int)
+            .ok_or_else(|| CONST_OVERFLOW.to_string()),
+        (UnaryOp::Neg, ConstValue::Float(value)) => Ok(ConstValue::Float(-value)),
+        (UnaryOp::Not, ConstValue::Bool(value)) => Ok(ConstValue::Bool(!value)),
+        (UnaryOp::BitNot, ConstValue::Int(value)) => Ok(ConstValue::Int(!value)),
+        (_, value) => Err(format!("invalid operand {} in constant", value.describe())),
+    }
+}
+
+/// Calls the builtin function `name`, one of [`CONST_FUNCTIONS`]
+pub fn eval_const_call(name: &str, args: Vec<ConstValue>) -> Result<ConstValue, String> {
+    match (name, args.as_slice()) {
+        ("len", [ConstValue::Str(value)]) => Ok(ConstValue::Int(value.len() as i64)),
+        ("str", [value]) => Ok(ConstValue::Str(value.format())),
+        _ => Err(format!("invalid arguments to `{name}` in constant")),
+    }
+}
+
+/// Names of the methods a constant of this value's type may call
+pub fn const_methods(value: &ConstValue) -> &'static [&'static str] {
+    match value {
+        ConstValue::Str(_) => &[
+            "len",
+            "upper",
+            "lower",
+            "strip",
+            "contains",
+            "starts_with",
+            "ends_with",
+            "replace",
+        ],
+        ConstValue::Int(_) => &["abs", "to_string"],
+        ConstValue::Float(_) => &["abs", "floor", "ceil", "round", "to_string"],
+        ConstValue::Bool(_) => &["to_string"],
+    }
+}
+
+/// Calls the builtin method `name` of `receiver`
+pub fn eval_const_method(
+    receiver: ConstValue,
+    name: &str,
+    args: Vec<ConstValue>,
+) -> Result<ConstValue, String> {
+    use ConstValue::{Bool, Float, Int, Str};
+
+    match (&receiver, name, args.as_slice()) {
+        (Str(value), "len", []) => Ok(Int(value.len() as i64)),
+        (Str(value), "upper", []) => Ok(Str(value.to_uppercase())),
+        (Str(value), "lower", []) => Ok(Str(value.to_lowercase())),
+        (Str(value), "strip", []) => Ok(Str(value.trim().to_string())),
+        (Str(value), "contains", [Str(part)]) => Ok(Bool(value.contains(part.as_str()))),
+        (Str(value), "starts_with", [Str(part)]) => Ok(Bool(value.starts_with(part.as_str()))),
+        (Str(value), "ends_with", [Str(part)]) => Ok(Bool(value.ends_with(part.as_str()))),
+        (Str(value), "replace", [Str(from), Str(to)]) => Ok(Str(value.replace(from.as_str(), to))),
+        (Int(value), "abs", []) => value
+            .checked_abs()
+            .map(Int)
+            .ok_or_else(|| CONST_OVERFLOW.to_string()),
+        (Float(value), "abs", []) => Ok(Float(value.abs())),
+        (Float(value), "floor", []) => Ok(Float(value.floor())),
+        (Float(value), "ceil", []) => Ok(Float(value.ceil())),
+        (Float(value), "round", []) => Ok(Float(value.round())),
+        (value, "to_string", []) => Ok(Str(value.format())),
+        (value, name, _) if const_methods(value).contains(&name) => Err(format!(
+            "invalid arguments to `{}.{name}` in constant",
+            value.describe()
+        )),
+        (value, name, _) => Err(format!(
+            "`{}.{name}` cannot be called in a constant",
+            value.describe()
+        )),
+    }
+}
+
+/// Applies `op` to two constant operands
+pub fn eval_const_binary(
+    op: BinaryOp,
+    left: ConstValue,
+    right: ConstValue,
+) -> Result<ConstValue, String> {
+    use ConstValue::{Bool, Float, Int, Str};
+
+    let overflow = || CONST_OVERFLOW.to_string();
+    match (op, left, right) {
+        (BinaryOp::Add, Str(left), right) => Ok(Str(left + &right.format())),
+        (BinaryOp::Add, left, Str(right)) => Ok(Str(left.format() + &right)),
+        (BinaryOp::Add, Int(l), Int(r)) => l.checked_add(r).map(Int).ok_or_else(overflow),
+        (BinaryOp::Sub, Int(l), Int(r)) => l.checked_sub(r).map(Int).ok_or_else(overflow),
+        (BinaryOp::Mul, Int(l), Int(r)) => l.checked_mul(r).map(Int).ok_or_else(overflow),
+        (BinaryOp::Div | BinaryOp::Mod, Int(_), Int(0)) => Err(CONST_DIVISION_BY_ZERO.to_string()),
+        (BinaryOp::Div, Int(l), Int(r)) => l.checked_div(r).map(Int).ok_or_else(overflow),
+        (BinaryOp::Mod, Int(l), Int(r)) => l.checked_rem(r).map(Int).ok_or_else(overflow),
+        (BinaryOp::BitAnd, Int(l), Int(r)) => Ok(Int(l & r)),
+        (BinaryOp::BitOr, Int(l), Int(r)) => Ok(Int(l | r)),
+        (BinaryOp::BitXor, Int(l), Int(r)) => Ok(Int(l ^ r)),
+        (BinaryOp::Shl, Int(l), Int(r)) => Ok(Int(l.wrapping_shl(r as u32))),
+        (BinaryOp::Shr, Int(l), Int(r)) => Ok(Int(l.wrapping_shr(r as u32))),
+        (BinaryOp::And, Bool(l), Bool(r)) => Ok(Bool(l && r)),
+        (BinaryOp::Or, Bool(l), Bool(r)) => Ok(Bool(l || r)),
+        (BinaryOp::Eq | BinaryOp::Is, left, right) => Ok(Bool(const_eq(&left, &right))),
+        (BinaryOp::Ne | BinaryOp::IsNot, left, right) => Ok(Bool(!const_eq(&left, &right))),
+        (BinaryOp::Lt | BinaryOp::Gt | BinaryOp::LtEq | BinaryOp::GtEq, Str(l), Str(r)) => {
+            Ok(Bool(compare(op, l.cmp(&r))))
+        }
+        (op, left, right) => match (left.as_float(), right.as_float()) {
+            (Some(l), Some(r)) => match op {
+                BinaryOp::Add => Ok(Float(l + r)),
+                BinaryOp::Sub => Ok(Float(l - r)),
+                BinaryOp::Mul => Ok(Float(l * r)),
+                BinaryOp::Div => Ok(Float(l / r)),
+                BinaryOp::Mod => Ok(Float(l % r)),
+                BinaryOp::Lt | BinaryOp::Gt | BinaryOp::LtEq | BinaryOp::GtEq => l
+                    .partial_cmp(&r)
+                    .map(|ordering| Bool(compare(op, ordering)))
+                    .ok_or_else(|| "cannot compare NaN in constant".to_string()),
+                _ => Err("bitwise operators need integer constants".to_string()),
+            },
+            _ => Err(format!(
+                "invalid operands {} and {} in constant",
+                left.describe(),
+                right.describe()
+            )),
+        },
+    }
+}
+
+fn const_eq(left: &ConstValue, right: &ConstValue) -> bool {
+    match (left.as_float(), right.as_float()) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(op: BinaryOp, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Gt => ordering.is_gt(),
+        BinaryOp::LtEq => ordering.is_le(),
+        _ => ordering.is_ge(),
+    }
+}
+
+/// `a.b.c` for a chain of member accesses on an identifier
+fn member_path(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Identifier(name) => Some(name.clone()),
+        Expr::Member { object, field } => {
+            member_path(object.as_ref().as_ref()).map(|base| format!("{base}.{field}"))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otterc_ast::nodes::{Node, NumberLiteral};
+    use otterc_span::Span;
+
+    fn number(value: f64) -> Box<Node<Expr>> {
+        Box::new(Node::new(
+            Expr::Literal(Node::new(
+                Literal::Number(NumberLiteral::new(value, false)),
+                Span::new(0, 1),
+            )),
+            Span::new(0, 1),
+        ))
+    }
+
+    fn binary(op: BinaryOp, left: Box<Node<Expr>>, right: Box<Node<Expr>>) -> Box<Node<Expr>> {
+        Box::new(Node::new(Expr::Binary { op, left, right }, Span::new(0, 1)))
+    }
+
+    #[test]
+    fn folds_arithmetic_over_other_constants() {
+        let lookup = |name: &str| (name == "BASE").then_some(ConstValue::Int(40));
+        let base = Box::new(Node::new(
+            Expr::Identifier("BASE".to_string()),
+            Span::new(0, 4),
+        ));
+        let expr = binary(
+            BinaryOp::Add,
+            base,
+            binary(BinaryOp::Mul, number(1.0), number(2.0)),
+        );
+        assert_eq!(
+            eval_const_expr(expr.as_ref().as_ref(), &lookup),
+            Ok(ConstValue::Int(42))
+        );
+    }
+
+    #[test]
+    fn rejects_division_by_zero_and_out_of_range_values() {
+        let lookup = |_: &str| None;
+        let expr = binary(BinaryOp::Div, number(1.0), number(0.0));
+        assert_eq!(
+            eval_const_expr(expr.as_ref().as_ref(), &lookup),
+            Err("division by zero in constant".to_string())
+        );
+        assert_eq!(
+            ConstValue::Int(300).convert_to(&TypeInfo::U8),
+            Err("constant value 300 does not fit in u8".to_string())
+        );
+    }
+
+    #[test]
+    fn calls_pure_builtins() {
+        let lookup = |name: &str| (name == "NAME").then(|| ConstValue::Str(" otter ".to_string()));
+        let name = Box::new(Node::new(
+            Expr::Identifier("NAME".to_string()),
+            Span::new(0, 4),
+        ));
+        let strip = Expr::Call {
+            func: Box::new(Node::new(
+                Expr::Member {
+                    object: name,
+                    field: "strip".to_string(),
+                },
+                Span::new(0, 10),
+            )),
+            args: Vec::new(),
+        };
+        let len = Expr::Call {
+            func: Box::new(Node::new(
+                Expr::Identifier("len".to_string()),
+                Span::new(0, 3),
+            )),
+            args: vec![Node::new(strip, Span::new(4, 16))],
+        };
+        let size = binary(
+            BinaryOp::Mul,
+            Box::new(Node::new(len, Span::new(0, 17))),
+            number(1024.0),
+        );
+        assert_eq!(
+            eval_const_expr(size.as_ref().as_ref(), &lookup),
+            Ok(ConstValue::Int(5 * 1024))
+        );
+
+        assert_eq!(
+            eval_const_method(ConstValue::Float(2.5), "floor", Vec::new()),
+            Ok(ConstValue::Float(2.0))
+        );
+        assert_eq!(
+            eval_const_method(ConstValue::Int(3), "upper", Vec::new()),
+            Err("`int.upper` cannot be called in a constant".to_string())
+        );
+        assert_eq!(
+            eval_const_call("len", vec![ConstValue::Int(3)]),
+            Err("invalid arguments to `len` in constant".to_string())
+        );
+    }
+}
@@ -7,34 +7,47 @@ use super::TypeError;
 pub fn from_type_errors(errors: &[TypeError], source_id: &str, source: &str) -> Vec<Diagnostic> {
     errors
         .iter()
-        .map(|error| to_diagnostic(error, source_id, source))
+        .map(|error| to_diagnostic(error, DiagnosticSeverity::Error, source_id, source))
         .collect()
 }
 
-fn to_diagnostic(error: &TypeError, source_id: &str, source: &str) -> Diagnostic {
+/// Convert type checker warnings into warning diagnostics
+pub fn from_type_warnings(
+    warnings: &[TypeError],
+    source_id: &str,
+    source: &str,
+) -> Vec<Diagnostic> {
+    warnings
+        .iter()
+        .map(|warning| to_diagnostic(warning, DiagnosticSeverity::Warning, source_id, source))
+        .collect()
+}
+
+fn to_diagnostic(
+    error: &TypeError,
+    severity: DiagnosticSeverity,
+    source_id: &str,
+    source: &str,
+) -> Diagnostic {
     let span = error.span.unwrap_or_else(|| guess_span(error, source));
-    let mut diagnostic = Diagnostic::new(
-        DiagnosticSeverity::Error,
-        source_id.to_string(),
-        span,
-        error.message.clone(),
-    );
+    let mut diagnostic =
+        Diagnostic::new(severity, source_id.to_string(), span, error.message.clone());
 
     if let Some(suggestion) = &error.suggestion {
         diagnostic = diagnostic.with_suggestion(suggestion.clone());
     }
 
-    match (&error.hint, &error.help) {
-        (Some(hint), Some(help)) => {
-            diagnostic = diagnostic.with_help(format!("{}\n{}", hint, help));
-        }
-        (Some(hint), None) => {
-            diagnostic = diagnostic.with_help(hint.clone());
-        }
-        (None, Some(help)) => {
-            diagnostic = diagnostic.with_help(help.clone());
-        }
-        (None, None) => {}
+    // The lined-up types of a mismatch come first, ahead of the advice
+    let notes: Vec<String> = error
+        .mismatch
+        .as_ref()
+        .map(|mismatch| mismatch.render())
+        .into_iter()
+        .chain(error.hint.clone())
+        .chain(error.help.clone())
+        .collect();
+    if !notes.is_empty() {
+        diagnostic = diagnostic.with_help(notes.join("\n"));
     }
 
     diagnostic
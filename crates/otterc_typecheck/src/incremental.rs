@@ -0,0 +1,197 @@
+//! Function-level reuse of type checking results
+//!
+//! An editor checks the same file after every keystroke, and most edits touch
+//! a single function body. A [`FunctionCache`] remembers the errors found in
+//! each top-level function together with a fingerprint of its text. While the
+//! text outside function bodies stays the same, a function whose own text did
+//! not change keeps its earlier errors, moved to where the function now
+//! starts, and its body is not checked again.
+//!
+//! Skipped bodies record no expression types, so an incremental check only
+//! produces diagnostics. Codegen needs a full
+//! [`TypeChecker::check_program`](crate::TypeChecker::check_program).
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use otterc_ast::nodes::{Program, Statement};
+use otterc_span::Span;
+
+use crate::types::TypeError;
+
+/// Errors of the function bodies of one file from its previous check
+#[derive(Debug, Default)]
+pub struct FunctionCache {
+    environment: Option<u64>,
+    functions: HashMap<String, CachedFunction>,
+    reused: usize,
+    rechecked: usize,
+}
+
+#[derive(Debug)]
+struct CachedFunction {
+    fingerprint: u64,
+    start: usize,
+    errors: Vec<TypeError>,
+}
+
+impl FunctionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets every function, for when something outside the file changed,
+    /// such as a module it imports
+    pub fn clear(&mut self) {
+        self.environment = None;
+        self.functions.clear();
+    }
+
+    /// Functions whose errors the last check reused
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    /// Functions the last check had to check again
+    pub fn rechecked(&self) -> usize {
+        self.rechecked
+    }
+
+    /// Starts a check of `program`. Any change outside function bodies can
+    /// affect every function, so it drops the whole cache.
+    pub(crate) fn begin(&mut self, program: &Program, source: &str) {
+        let environment = environment_fingerprint(program, source);
+        if self.environment != Some(environment) {
+            self.functions.clear();
+            self.environment = Some(environment);
+        }
+        self.reused = 0;
+        self.rechecked = 0;
+    }
+
+    /// Earlier errors of function `name` if its text is unchanged, moved to
+    /// where it now starts
+    pub(crate) fn lookup(
+        &mut self,
+        name: &str,
+        source: &str,
+        span: Span,
+    ) -> Option<Vec<TypeError>> {
+        let cached = self.functions.get(name)?;
+        if cached.fingerprint != fingerprint(source, span) {
+            return None;
+        }
+        let errors = cached
+            .errors
+            .iter()
+            .cloned()
+            .map(|mut error| {
+                error.span = error.span.map(|s| {
+                    Span::new(
+                        (s.start() + span.start()).saturating_sub(cached.start),
+                        (s.end() + span.start()).saturating_sub(cached.start),
+                    )
+                });
+                error
+            })
+            .collect();
+        self.reused += 1;
+        Some(errors)
+    }
+
+    pub(crate) fn store(&mut self, name: &str, source: &str, span: Span, errors: Vec<TypeError>) {
+        self.rechecked += 1;
+        self.functions.insert(
+            name.to_string(),
+            CachedFunction {
+                fingerprint: fingerprint(source, span),
+                start: span.start(),
+                errors,
+            },
+        );
+    }
+}
+
+fn fingerprint(source: &str, span: Span) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.get(span.start()..span.end()).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash of the source outside top-level function bodies: declarations,
+/// signatures, imports and top-level statements. Positions are left out, so
+/// an edit that only moves code keeps the hash.
+fn environment_fingerprint(program: &Program, source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut offset = 0;
+    for statement in &program.statements {
+        if let Statement::Function(function) = statement.as_ref() {
+            let body = function.as_ref().body.span();
+            source.get(offset..body.start()).hash(&mut hasher);
+            offset = body.end().max(offset);
+        }
+    }
+    source.get(offset..).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TypeChecker;
+
+    fn parse(source: &str) -> Program {
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        otterc_parser::parse(&tokens).expect("parse")
+    }
+
+    fn error_spans(checker: &TypeChecker) -> Vec<Option<Span>> {
+        checker.errors().iter().map(|error| error.span).collect()
+    }
+
+    #[test]
+    fn edit_in_one_body_rechecks_only_that_function() {
+        let before = "fn good() -> int:\n    return 1\n\nfn bad() -> int:\n    return \"x\"\n";
+        let after = "fn good() -> int:\n    return 1 + 41\n\nfn bad() -> int:\n    return \"x\"\n";
+        let mut cache = FunctionCache::new();
+
+        let program = parse(before);
+        let mut checker = TypeChecker::new();
+        assert!(
+            checker
+                .check_program_incremental(&program, before, &mut cache)
+                .is_err()
+        );
+        assert_eq!((cache.rechecked(), cache.reused()), (2, 0));
+
+        let program = parse(after);
+        let mut checker = TypeChecker::new();
+        assert!(
+            checker
+                .check_program_incremental(&program, after, &mut cache)
+                .is_err()
+        );
+        assert_eq!((cache.rechecked(), cache.reused()), (1, 1));
+
+        // The reused error of `bad` moved along with the function
+        let mut full = TypeChecker::new();
+        assert!(full.check_program(&program).is_err());
+        assert!(error_spans(&full).iter().all(Option::is_some));
+        assert_eq!(error_spans(&checker), error_spans(&full));
+    }
+
+    #[test]
+    fn signature_change_rechecks_everything() {
+        let before = "fn one() -> int:\n    return 1\n\nfn two() -> int:\n    return 2\n";
+        let after = "fn one() -> float:\n    return 1.0\n\nfn two() -> int:\n    return 2\n";
+        let mut cache = FunctionCache::new();
+
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program_incremental(&parse(before), before, &mut cache);
+        let mut checker = TypeChecker::new();
+        let _ = checker.check_program_incremental(&parse(after), after, &mut cache);
+
+        assert_eq!((cache.rechecked(), cache.reused()), (2, 0));
+    }
+}
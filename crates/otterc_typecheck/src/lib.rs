@@ -2,12 +2,29 @@
 //!
 //! Provides type inference, validation, and error reporting
 
+pub mod checked_literals;
 pub mod checker;
+pub mod consts;
 pub mod diagnostics;
+pub mod incremental;
+pub mod numeric;
+mod params;
 pub mod types;
+pub mod unreachable;
+pub mod unused;
 pub mod workspace;
 
+pub use checked_literals::{LiteralError, LiteralValidator, LiteralValidators, SqlValidator};
 pub use checker::{ModuleExports, TypeChecker};
+pub use consts::{
+    CONST_FUNCTIONS, ConstValue, const_methods, eval_const_binary, eval_const_call,
+    eval_const_expr, eval_const_method, eval_const_unary,
+};
 pub use diagnostics::from_type_errors as diagnostics_from_type_errors;
-pub use types::{EnumLayout, TypeContext, TypeError, TypeInfo};
+pub use diagnostics::from_type_warnings as diagnostics_from_type_warnings;
+pub use incremental::FunctionCache;
+pub use numeric::Numeric;
+pub use types::{
+    BuiltinMethod, EnumLayout, Fallible, TypeContext, TypeError, TypeInfo, TypeMismatch,
+};
 pub use workspace::{ModuleDependency, ModuleRecord, TypecheckWorkspace};
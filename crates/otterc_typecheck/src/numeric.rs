@@ -0,0 +1,100 @@
+//! Implicit numeric widening
+//!
+//! A number converts implicitly to a type that holds every value it can take:
+//! an integer to a wider integer of the same signedness, an unsigned integer
+//! to a strictly wider signed one, and an integer of at most 32 bits to `f64`,
+//! whose 53-bit significand holds all of its values. Nothing converts
+//! implicitly to a narrower integer, between signed and unsigned integers of
+//! the same width, from `i64` or `u64` to `f64`, which would round values
+//! above 2^53, or from `f64` to an integer. The type checker and codegen
+//! both decide conversions with these functions, so a program that checks
+//! compiles to the conversions the checker allowed.
+
+/// A numeric type as far as widening is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numeric {
+    Int { bits: u32, signed: bool },
+    Float,
+}
+
+impl Numeric {
+    /// Whether every value of `self` is also a value of `target`
+    pub fn widens_to(self, target: Numeric) -> bool {
+        match (self, target) {
+            (Numeric::Float, Numeric::Float) => true,
+            (Numeric::Int { bits, .. }, Numeric::Float) => bits <= 32,
+            (Numeric::Float, Numeric::Int { .. }) => false,
+            (
+                Numeric::Int { bits, signed },
+                Numeric::Int {
+                    bits: to_bits,
+                    signed: to_signed,
+                },
+            ) => {
+                if signed == to_signed {
+                    to_bits >= bits
+                } else {
+                    // Unsigned values need one more bit to fit a signed type
+                    !signed && to_bits > bits
+                }
+            }
+        }
+    }
+
+    /// The type both operands of an arithmetic or comparison operator convert
+    /// to, if one of them holds every value of the other
+    pub fn common(self, other: Numeric) -> Option<Numeric> {
+        if self.widens_to(other) {
+            Some(other)
+        } else if other.widens_to(self) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const I32: Numeric = Numeric::Int {
+        bits: 32,
+        signed: true,
+    };
+    const I64: Numeric = Numeric::Int {
+        bits: 64,
+        signed: true,
+    };
+    const U32: Numeric = Numeric::Int {
+        bits: 32,
+        signed: false,
+    };
+    const U64: Numeric = Numeric::Int {
+        bits: 64,
+        signed: false,
+    };
+
+    #[test]
+    fn widening_never_loses_a_value() {
+        assert!(I32.widens_to(I64));
+        assert!(I32.widens_to(Numeric::Float));
+        assert!(U32.widens_to(Numeric::Float));
+        assert!(U32.widens_to(I64));
+        assert!(!I64.widens_to(Numeric::Float));
+        assert!(!U64.widens_to(Numeric::Float));
+        assert!(!I64.widens_to(I32));
+        assert!(!U64.widens_to(I64));
+        assert!(!I32.widens_to(U64));
+        assert!(!Numeric::Float.widens_to(I64));
+    }
+
+    #[test]
+    fn mixed_operands_meet_at_the_wider_type() {
+        assert_eq!(I32.common(I64), Some(I64));
+        assert_eq!(Numeric::Float.common(I32), Some(Numeric::Float));
+        assert_eq!(Numeric::Float.common(I64), None);
+        assert_eq!(U32.common(I32), None);
+        assert_eq!(U64.common(I64), None);
+    }
+}
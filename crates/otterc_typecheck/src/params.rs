@@ -0,0 +1,136 @@
+//! Parameter types inferred from call sites
+//!
+//! A parameter written without a type takes the type of the arguments passed
+//! to it when every direct call of its function in the module passes the same
+//! type, with a default value counting as one more argument. Otherwise the
+//! parameter stays dynamic, as it was before. Public functions can be called
+//! from other modules and functions passed to the runtime as callbacks can be
+//! called from anywhere, so their parameters are never inferred.
+
+use std::collections::{HashMap, HashSet};
+
+use otterc_ast::nodes::{Node, Type};
+use otterc_span::Span;
+
+use crate::types::TypeInfo;
+
+/// Argument types seen at the direct calls of the module's functions
+#[derive(Debug, Default)]
+pub(crate) struct CallSites {
+    /// Arguments passed to each parameter, by function name and parameter
+    /// index
+    arguments: HashMap<String, HashMap<usize, Vec<Argument>>>,
+    /// Functions referred to other than by calling them
+    escaped: HashSet<String>,
+    /// Function whose body is being checked
+    caller: Option<String>,
+}
+
+#[derive(Debug)]
+struct Argument {
+    ty: TypeInfo,
+    /// Unsuffixed integer literals take whichever integer type the other
+    /// arguments have
+    int_literal: bool,
+}
+
+impl CallSites {
+    pub(crate) fn record(
+        &mut self,
+        function: &str,
+        index: usize,
+        ty: &TypeInfo,
+        int_literal: bool,
+    ) {
+        // A recursive call passes the parameter on before it has a type
+        let recursive = self.caller.as_deref() == Some(function);
+        if matches!(ty, TypeInfo::Error) || (recursive && matches!(ty, TypeInfo::Unknown)) {
+            return;
+        }
+        self.arguments
+            .entry(function.to_string())
+            .or_default()
+            .entry(index)
+            .or_default()
+            .push(Argument {
+                ty: ty.clone(),
+                int_literal,
+            });
+    }
+
+    /// Starts recording the calls made by `function`, returning the caller
+    /// to restore once its body is checked
+    pub(crate) fn enter(&mut self, function: &str) -> Option<String> {
+        self.caller.replace(function.to_string())
+    }
+
+    pub(crate) fn leave(&mut self, caller: Option<String>) {
+        self.caller = caller;
+    }
+
+    pub(crate) fn escape(&mut self, function: &str) {
+        self.escaped.insert(function.to_string());
+    }
+
+    /// The type every call passes to parameter `index` of `function`, if they
+    /// agree on one that can be written as an annotation
+    pub(crate) fn unified(&self, function: &str, index: usize) -> Option<TypeInfo> {
+        if self.escaped.contains(function) {
+            return None;
+        }
+        let seen = self.arguments.get(function)?.get(&index)?;
+        let mut typed = seen.iter().filter(|argument| !argument.int_literal);
+        let ty = match typed.next() {
+            Some(first) => &first.ty,
+            None => &seen.first()?.ty,
+        };
+        if typed.any(|argument| argument.ty != *ty) || !is_concrete(ty) {
+            return None;
+        }
+        if seen.iter().any(|argument| argument.int_literal) && ty.int_range().is_none() {
+            return None;
+        }
+        Some(ty.clone())
+    }
+}
+
+/// Whether `ty` is fully known, so a parameter of that type can be compiled
+/// to a specialized representation
+fn is_concrete(ty: &TypeInfo) -> bool {
+    match ty {
+        TypeInfo::Bool
+        | TypeInfo::I8
+        | TypeInfo::I16
+        | TypeInfo::I32
+        | TypeInfo::I64
+        | TypeInfo::U8
+        | TypeInfo::U16
+        | TypeInfo::U32
+        | TypeInfo::U64
+        | TypeInfo::F64
+        | TypeInfo::Str
+        | TypeInfo::Symbol => true,
+        TypeInfo::List(element) => is_concrete(element),
+        TypeInfo::Dict { key, value } => is_concrete(key) && is_concrete(value),
+        TypeInfo::Struct { args, .. } | TypeInfo::Enum { args, .. } => args.is_empty(),
+        _ => false,
+    }
+}
+
+/// The annotation that stands for `ty`, placed at `span`
+pub(crate) fn annotation_for(ty: &TypeInfo, span: Span) -> Option<Node<Type>> {
+    let ty = match ty {
+        TypeInfo::List(element) => Type::Generic {
+            base: "list".to_string(),
+            args: vec![annotation_for(element, span)?],
+        },
+        TypeInfo::Dict { key, value } => Type::Generic {
+            base: "dict".to_string(),
+            args: vec![annotation_for(key, span)?, annotation_for(value, span)?],
+        },
+        TypeInfo::Struct { name, .. } | TypeInfo::Enum { name, .. } => Type::Simple(name.clone()),
+        ty if is_concrete(ty) => Type::Simple(ty.display_name()),
+        _ => return None,
+    };
+    Some(Node::new(ty, span))
+}
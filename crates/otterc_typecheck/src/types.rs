@@ -5,6 +5,8 @@ use otterc_span::Span;
 
 use otterc_config::LanguageFeatureFlags;
 
+use crate::numeric::Numeric;
+
 /// Represents a type in the type system
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TypeInfo {
@@ -12,14 +14,28 @@ pub enum TypeInfo {
     Unit,
     /// Boolean type
     Bool,
+    /// 8-bit integer
+    I8,
+    /// 16-bit integer
+    I16,
     /// 32-bit integer
     I32,
     /// 64-bit integer
     I64,
+    /// 8-bit unsigned integer
+    U8,
+    /// 16-bit unsigned integer
+    U16,
+    /// 32-bit unsigned integer
+    U32,
+    /// 64-bit unsigned integer
+    U64,
     /// 64-bit floating point
     F64,
     /// String type
     Str,
+    /// Interned name written `:name` or made with `sym(name)`
+    Symbol,
     /// List type with element type information
     List(Box<TypeInfo>),
     /// Dictionary type with key/value types
@@ -41,6 +57,9 @@ pub enum TypeInfo {
     Struct {
         name: String,
         fields: HashMap<String, TypeInfo>,
+        /// Type arguments of a generic struct, in declaration order. Empty
+        /// for non-generic structs.
+        args: Vec<TypeInfo>,
     },
     /// Enum type with named variants
     Enum {
@@ -54,6 +73,16 @@ pub enum TypeInfo {
         underlying: Box<TypeInfo>,
         is_public: bool,
     },
+    /// A value of any one of the member types (`int | str`). Built with
+    /// [`TypeInfo::union`], so it has at least two members and no nested
+    /// unions.
+    Union(Vec<TypeInfo>),
+    /// A protocol, satisfied by any struct or enum with the methods it lists
+    Protocol(String),
+    /// The type of expressions that never produce a value, such as
+    /// `panic(...)` or a `while true:` loop nothing breaks out of. It fits
+    /// wherever a value is expected.
+    Never,
     /// Unknown type (needs inference)
     Unknown,
     /// Error type (used for error recovery)
@@ -66,6 +95,54 @@ pub enum TypeInfo {
 pub struct EnumVariantInfo {
     pub name: String,
     pub fields: Vec<TypeInfo>,
+    /// Names of the fields, empty when they are positional
+    pub field_names: Vec<String>,
+}
+
+/// A `Result` or `Option` value, as [`TypeInfo::fallible`] finds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fallible<'a> {
+    /// `Ok` or `Some`
+    pub success: &'static str,
+    /// `Err` or `None`
+    pub failure: &'static str,
+    /// Type of the value the success variant carries
+    pub value: &'a TypeInfo,
+}
+
+impl Fallible<'_> {
+    /// Methods every `Result` and `Option` has unless the enum defines its own
+    pub const METHODS: &'static [&'static str] = &["map", "and_then", "unwrap_or", "expect"];
+}
+
+/// Bounds a generic parameter may declare besides protocols, with the types
+/// that satisfy them
+pub const BUILTIN_BOUNDS: &[(&str, &str)] = &[
+    ("Numeric", "the integer types and `float`"),
+    ("Integer", "the integer types"),
+];
+
+/// A method of a builtin type such as `"abc".upper()`. Calls are lowered to
+/// the runtime function `function`, with the receiver as first argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuiltinMethod {
+    /// Symbol registry name of the runtime function, e.g. `str.upper`.
+    /// `append` is the exception: codegen picks `append<list,int>` and its
+    /// siblings from the argument.
+    pub function: &'static str,
+    /// Parameters after the receiver
+    pub params: Vec<TypeInfo>,
+    pub return_type: TypeInfo,
+}
+
+impl BuiltinMethod {
+    fn new(function: &'static str, params: Vec<TypeInfo>, return_type: TypeInfo) -> Self {
+        Self {
+            function,
+            params,
+            return_type,
+        }
+    }
 }
 
 impl TypeInfo {
@@ -105,12 +182,16 @@ impl TypeInfo {
                 key: Box::new(key.substitute(substitutions)),
                 value: Box::new(value.substitute(substitutions)),
             },
-            TypeInfo::Struct { name, fields } => TypeInfo::Struct {
+            TypeInfo::Struct { name, fields, args } => TypeInfo::Struct {
                 name: name.clone(),
                 fields: fields
                     .iter()
                     .map(|(k, v)| (k.clone(), v.substitute(substitutions)))
                     .collect(),
+                args: args
+                    .iter()
+                    .map(|arg| arg.substitute(substitutions))
+                    .collect(),
             },
             TypeInfo::Enum {
                 name,
@@ -134,6 +215,7 @@ impl TypeInfo {
                                     .iter()
                                     .map(|field| field.substitute(substitutions))
                                     .collect(),
+                                field_names: variant.field_names.clone(),
                             },
                         )
                     })
@@ -148,24 +230,288 @@ impl TypeInfo {
                 underlying: Box::new(underlying.substitute(substitutions)),
                 is_public: *is_public,
             },
+            TypeInfo::Union(members) => TypeInfo::union(
+                members
+                    .iter()
+                    .map(|member| member.substitute(substitutions))
+                    .collect(),
+            ),
             TypeInfo::Module(name) => TypeInfo::Module(name.clone()),
             _ => self.clone(),
         }
     }
 
+    /// The union of `members`, flattening nested unions and dropping
+    /// duplicates. A single distinct member is returned as is.
+    pub fn union(members: Vec<TypeInfo>) -> TypeInfo {
+        let mut flat: Vec<TypeInfo> = Vec::new();
+        for member in members {
+            let nested = match member {
+                TypeInfo::Union(nested) => nested,
+                other => vec![other],
+            };
+            for member in nested {
+                if !flat.contains(&member) {
+                    flat.push(member);
+                }
+            }
+        }
+        if flat.len() == 1 {
+            flat.remove(0)
+        } else {
+            TypeInfo::Union(flat)
+        }
+    }
+
+    /// `Channel<element>`, the type of task channels and streams
+    pub fn channel_of(element: TypeInfo) -> TypeInfo {
+        TypeInfo::Generic {
+            base: "Channel".to_string(),
+            args: vec![element],
+        }
+    }
+
+    /// `Version`, the type of the handles `semver.parse` returns
+    pub fn semver_version() -> TypeInfo {
+        TypeInfo::Generic {
+            base: "Version".to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// `FrozenList<element>`, the type of `list.freeze()`
+    pub fn frozen_list_of(element: TypeInfo) -> TypeInfo {
+        TypeInfo::Generic {
+            base: "FrozenList".to_string(),
+            args: vec![element],
+        }
+    }
+
+    /// `FrozenDict<key, value>`, the type of `dict.freeze()`
+    pub fn frozen_dict_of(key: TypeInfo, value: TypeInfo) -> TypeInfo {
+        TypeInfo::Generic {
+            base: "FrozenDict".to_string(),
+            args: vec![key, value],
+        }
+    }
+
+    /// The element type of a `FrozenList`
+    fn frozen_list_element(&self) -> Option<&TypeInfo> {
+        match self {
+            TypeInfo::Generic { base, args } if base == "FrozenList" && args.len() == 1 => {
+                Some(&args[0])
+            }
+            _ => None,
+        }
+    }
+
+    /// The key and value types of a `FrozenDict`
+    fn frozen_dict_entry(&self) -> Option<(&TypeInfo, &TypeInfo)> {
+        match self {
+            TypeInfo::Generic { base, args } if base == "FrozenDict" && args.len() == 2 => {
+                Some((&args[0], &args[1]))
+            }
+            _ => None,
+        }
+    }
+
+    /// How the runtime's per-type collection functions name elements of this
+    /// type, as in `append<list,int>`
+    fn element_kind(&self) -> Option<&'static str> {
+        match self {
+            TypeInfo::Alias { underlying, .. } => underlying.element_kind(),
+            TypeInfo::Str | TypeInfo::Symbol => Some("string"),
+            TypeInfo::I64 => Some("int"),
+            TypeInfo::F64 => Some("float"),
+            TypeInfo::Bool => Some("bool"),
+            TypeInfo::List(_) => Some("list"),
+            TypeInfo::Dict { .. } => Some("map"),
+            _ => None,
+        }
+    }
+
+    /// The methods of frozen lists and dicts. Updates return a new collection.
+    fn frozen_method(&self, name: &str) -> Option<BuiltinMethod> {
+        use TypeInfo::{Bool, I64};
+        if let Some(element) = self.frozen_list_element() {
+            let returns_self = || self.clone();
+            return Some(match name {
+                "len" => BuiltinMethod::new("frozen_list.len", vec![], I64),
+                "thaw" => BuiltinMethod::new(
+                    "frozen_list.thaw",
+                    vec![],
+                    TypeInfo::List(Box::new(element.clone())),
+                ),
+                "get" | "set" | "append" => {
+                    let (get, set, append) = match element.element_kind()? {
+                        "string" => (
+                            "frozen_list.get",
+                            "frozen_list.set<string>",
+                            "frozen_list.append<string>",
+                        ),
+                        "int" => (
+                            "frozen_list.get_int",
+                            "frozen_list.set<int>",
+                            "frozen_list.append<int>",
+                        ),
+                        "float" => (
+                            "frozen_list.get_float",
+                            "frozen_list.set<float>",
+                            "frozen_list.append<float>",
+                        ),
+                        "bool" => (
+                            "frozen_list.get_bool",
+                            "frozen_list.set<bool>",
+                            "frozen_list.append<bool>",
+                        ),
+                        "list" => (
+                            "frozen_list.get_list",
+                            "frozen_list.set<list>",
+                            "frozen_list.append<list>",
+                        ),
+                        _ => (
+                            "frozen_list.get_map",
+                            "frozen_list.set<map>",
+                            "frozen_list.append<map>",
+                        ),
+                    };
+                    match name {
+                        "get" => BuiltinMethod::new(get, vec![I64], element.clone()),
+                        "set" => {
+                            BuiltinMethod::new(set, vec![I64, element.clone()], returns_self())
+                        }
+                        _ => BuiltinMethod::new(append, vec![element.clone()], returns_self()),
+                    }
+                }
+                _ => return None,
+            });
+        }
+        let (key, value) = self.frozen_dict_entry()?;
+        let returns_self = || self.clone();
+        Some(match name {
+            "len" => BuiltinMethod::new("frozen_dict.len", vec![], I64),
+            "thaw" => BuiltinMethod::new(
+                "frozen_dict.thaw",
+                vec![],
+                TypeInfo::Dict {
+                    key: Box::new(key.clone()),
+                    value: Box::new(value.clone()),
+                },
+            ),
+            "keys" => BuiltinMethod::new(
+                "frozen_dict.keys",
+                vec![],
+                TypeInfo::List(Box::new(key.clone())),
+            ),
+            "has" => BuiltinMethod::new("frozen_dict.has", vec![key.clone()], Bool),
+            "remove" => BuiltinMethod::new("frozen_dict.remove", vec![key.clone()], returns_self()),
+            "get" | "set" => {
+                let (get, set) = match value.element_kind()? {
+                    "string" => ("frozen_dict.get", "frozen_dict.set<string>"),
+                    "int" => ("frozen_dict.get_int", "frozen_dict.set<int>"),
+                    "float" => ("frozen_dict.get_float", "frozen_dict.set<float>"),
+                    "bool" => ("frozen_dict.get_bool", "frozen_dict.set<bool>"),
+                    "list" => ("frozen_dict.get_list", "frozen_dict.set<list>"),
+                    _ => ("frozen_dict.get_map", "frozen_dict.set<map>"),
+                };
+                if name == "get" {
+                    BuiltinMethod::new(get, vec![key.clone()], value.clone())
+                } else {
+                    BuiltinMethod::new(set, vec![key.clone(), value.clone()], returns_self())
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// `Error`, the type of the values `error(message)` creates
+    pub fn error_value() -> TypeInfo {
+        TypeInfo::Generic {
+            base: "Error".to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn is_error_value(&self) -> bool {
+        match self {
+            TypeInfo::Generic { base, args } => base == "Error" && args.is_empty(),
+            TypeInfo::Alias { underlying, .. } => underlying.is_error_value(),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a [`semver` version](TypeInfo::semver_version) handle
+    pub fn is_semver_version(&self) -> bool {
+        match self {
+            TypeInfo::Generic { base, args } => base == "Version" && args.is_empty(),
+            TypeInfo::Alias { underlying, .. } => underlying.is_semver_version(),
+            _ => false,
+        }
+    }
+
+    /// The variants of a `Result<T, E>` or `Option<T>` shaped like the ones in
+    /// `core`, which get [combinator methods](Fallible::METHODS)
+    pub fn fallible(&self) -> Option<Fallible<'_>> {
+        let TypeInfo::Enum {
+            name,
+            args,
+            variants,
+        } = self
+        else {
+            return None;
+        };
+        let (success, failure, failure_fields) = match name.as_str() {
+            "Result" => ("Ok", "Err", 1),
+            "Option" => ("Some", "None", 0),
+            _ => return None,
+        };
+        let value = match variants.get(success)?.fields.as_slice() {
+            [value] => value,
+            _ => return None,
+        };
+        let fits = variants.len() == 2
+            && variants.get(failure)?.fields.len() == failure_fields
+            && args.first() == Some(value);
+        fits.then_some(Fallible {
+            success,
+            failure,
+            value,
+        })
+    }
+
+    /// The element type a `for await` loop receives from this type
+    pub fn stream_element(&self) -> Option<TypeInfo> {
+        match self {
+            TypeInfo::Generic { base, args } if base == "Channel" && args.len() == 1 => {
+                Some(args[0].clone())
+            }
+            TypeInfo::Alias { underlying, .. } => underlying.stream_element(),
+            _ => None,
+        }
+    }
+
     /// Check if this type is compatible with another type
     pub fn is_compatible_with(&self, other: &TypeInfo) -> bool {
         match (self, other) {
+            // Channels, broadcasts, errors and versions are opaque handles,
+            // which the FFI passes as i64
+            (TypeInfo::Generic { base, .. }, TypeInfo::I64)
+            | (TypeInfo::I64, TypeInfo::Generic { base, .. })
+                if matches!(base.as_str(), "Channel" | "Broadcast" | "Error" | "Version") =>
+            {
+                true
+            }
+            // Code that never produces a value can stand in for any value
+            (TypeInfo::Never, _) => true,
+            // Integers widen to any integer type that holds all of their values,
+            // and to f64
+            (from, to) if from.widens_to(to) => true,
             // Same types are compatible
             (TypeInfo::Unit, TypeInfo::Unit)
             | (TypeInfo::Bool, TypeInfo::Bool)
-            | (TypeInfo::I32, TypeInfo::I32)
-            | (TypeInfo::I64, TypeInfo::I64)
             | (TypeInfo::F64, TypeInfo::F64)
             | (TypeInfo::Str, TypeInfo::Str)
-            // Numeric promotions
-            | (TypeInfo::I32, TypeInfo::I64) | (TypeInfo::I32, TypeInfo::F64)
-            | (TypeInfo::I64, TypeInfo::F64)
+            | (TypeInfo::Symbol, TypeInfo::Symbol)
             // Unknown types are compatible with anything (during inference)
             | (TypeInfo::Unknown, _) | (_, TypeInfo::Unknown) => true,
             // Error types are compatible with strings (for convenience) and themselves
@@ -174,10 +520,32 @@ impl TypeInfo {
             | (TypeInfo::Error, _) // Error types are not compatible with anything else
             | (_, TypeInfo::Error) => false, // Nothing else is compatible with Error (except strings above)
 
-            // Struct types must match exactly
-            (TypeInfo::Struct { name: n1, .. }, TypeInfo::Struct { name: n2, .. })
+            // A union fits where every member fits, and a union accepts a value
+            // that fits any member
+            (TypeInfo::Union(members), to) => {
+                members.iter().all(|member| member.is_compatible_with(to))
+            }
+            (from, TypeInfo::Union(members)) => {
+                members.iter().any(|member| from.is_compatible_with(member))
+            }
+
+            // Struct types must match exactly, including any type arguments
+            (
+                TypeInfo::Struct {
+                    name: n1, args: a1, ..
+                },
+                TypeInfo::Struct {
+                    name: n2, args: a2, ..
+                },
+            ) => {
+                n1 == n2
+                    && (a1.is_empty()
+                        || a2.is_empty()
+                        || a1.iter().zip(a2).all(|(a, b)| a.is_compatible_with(b)))
+            }
             // Module types are compatible with themselves
-            | (TypeInfo::Module(n1), TypeInfo::Module(n2)) => n1 == n2,
+            (TypeInfo::Module(n1), TypeInfo::Module(n2)) => n1 == n2,
+            (TypeInfo::Protocol(p1), TypeInfo::Protocol(p2)) => p1 == p2,
 
             // Generic types must match structure
             (
@@ -297,10 +665,17 @@ impl TypeInfo {
         match self {
             TypeInfo::Unit => "None".to_string(),
             TypeInfo::Bool => "bool".to_string(),
+            TypeInfo::I8 => "i8".to_string(),
+            TypeInfo::I16 => "i16".to_string(),
             TypeInfo::I32 => "i32".to_string(),
             TypeInfo::I64 => "i64".to_string(),
+            TypeInfo::U8 => "u8".to_string(),
+            TypeInfo::U16 => "u16".to_string(),
+            TypeInfo::U32 => "u32".to_string(),
+            TypeInfo::U64 => "u64".to_string(),
             TypeInfo::F64 => "f64".to_string(),
             TypeInfo::Str => "str".to_string(),
+            TypeInfo::Symbol => "symbol".to_string(),
             TypeInfo::Function {
                 params,
                 param_defaults: _,
@@ -332,7 +707,15 @@ impl TypeInfo {
                     format!("{}<{}>", base, args_str)
                 }
             }
-            TypeInfo::Struct { name, fields } => {
+            TypeInfo::Struct { name, args, .. } if !args.is_empty() => {
+                let args_str = args
+                    .iter()
+                    .map(|t| t.display_name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}<{}>", name, args_str)
+            }
+            TypeInfo::Struct { name, fields, .. } => {
                 if fields.is_empty() {
                     name.clone()
                 } else {
@@ -357,15 +740,212 @@ impl TypeInfo {
                 }
             }
             TypeInfo::Alias { name, .. } => name.clone(),
+            TypeInfo::Union(members) => members
+                .iter()
+                .map(|member| member.display_name())
+                .collect::<Vec<_>>()
+                .join(" | "),
+            TypeInfo::Never => "never".to_string(),
             TypeInfo::Unknown => "?".to_string(),
             TypeInfo::Error => "<error>".to_string(),
             TypeInfo::Module(name) => format!("module<{}>", name),
+            TypeInfo::Protocol(name) => name.clone(),
         }
     }
 
-    /// Check if the type is an integer type (i32 or i64)
+    /// Check if the type is an integer type of any width
     pub fn is_integer(&self) -> bool {
-        matches!(self, TypeInfo::I32 | TypeInfo::I64)
+        self.int_layout().is_some()
+    }
+
+    /// Whether the type satisfies `bound`, one of [`BUILTIN_BOUNDS`]
+    pub fn satisfies_builtin_bound(&self, bound: &str) -> bool {
+        match bound {
+            "Numeric" => self.is_integer() || matches!(self, TypeInfo::F64),
+            "Integer" => self.is_integer(),
+            _ => false,
+        }
+    }
+
+    /// Bit width and signedness of an integer type
+    pub fn int_layout(&self) -> Option<(u32, bool)> {
+        Some(match self {
+            TypeInfo::I8 => (8, true),
+            TypeInfo::I16 => (16, true),
+            TypeInfo::I32 => (32, true),
+            TypeInfo::I64 => (64, true),
+            TypeInfo::U8 => (8, false),
+            TypeInfo::U16 => (16, false),
+            TypeInfo::U32 => (32, false),
+            TypeInfo::U64 => (64, false),
+            _ => return None,
+        })
+    }
+
+    /// Smallest and largest value of an integer type
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        let (bits, signed) = self.int_layout()?;
+        Some(if signed {
+            (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+        } else {
+            (0, (1i128 << bits) - 1)
+        })
+    }
+
+    /// The type as the [widening rules](crate::numeric) see it, for the
+    /// integer types and `f64`
+    pub fn numeric(&self) -> Option<Numeric> {
+        match self {
+            TypeInfo::F64 => Some(Numeric::Float),
+            ty => ty
+                .int_layout()
+                .map(|(bits, signed)| Numeric::Int { bits, signed }),
+        }
+    }
+
+    /// Whether every value of this integer type is also a value of `target`
+    pub fn int_widens_to(&self, target: &TypeInfo) -> bool {
+        self.is_integer() && target.is_integer() && self.widens_to(target)
+    }
+
+    /// Whether this numeric type converts implicitly to `target`
+    pub fn widens_to(&self, target: &TypeInfo) -> bool {
+        match (self.numeric(), target.numeric()) {
+            (Some(from), Some(to)) => from.widens_to(to),
+            _ => false,
+        }
+    }
+
+    /// Whether `value as target` is a valid explicit conversion. Numbers
+    /// convert to each other (truncating or rounding toward zero where
+    /// needed), booleans convert to numbers, and numbers, booleans, strings
+    /// and symbols convert to strings.
+    pub fn can_cast_to(&self, target: &TypeInfo) -> bool {
+        let numeric = |ty: &TypeInfo| ty.is_integer() || matches!(ty, TypeInfo::F64);
+        match (self, target) {
+            (TypeInfo::Alias { underlying, .. }, _) => underlying.can_cast_to(target),
+            (_, TypeInfo::Alias { underlying, .. }) => self.can_cast_to(underlying),
+            (from, to) if from == to => true,
+            (from, to) if numeric(to) => numeric(from) || matches!(from, TypeInfo::Bool),
+            (from, TypeInfo::Str) => {
+                numeric(from) || matches!(from, TypeInfo::Bool | TypeInfo::Symbol)
+            }
+            _ => false,
+        }
+    }
+
+    /// The builtin method `name` of strings, lists, maps and numbers
+    pub fn builtin_method(&self, name: &str) -> Option<BuiltinMethod> {
+        use TypeInfo::{Bool, F64, I64, Str};
+        let list_of = |element: TypeInfo| TypeInfo::List(Box::new(element));
+        if let Some(method) = self.frozen_method(name) {
+            return Some(method);
+        }
+        Some(match (self, name) {
+            (TypeInfo::Alias { underlying, .. }, _) => return underlying.builtin_method(name),
+            (Str, "len") => BuiltinMethod::new("len", vec![], I64),
+            (Str, "upper") => BuiltinMethod::new("str.upper", vec![], Str),
+            (Str, "lower") => BuiltinMethod::new("str.lower", vec![], Str),
+            (Str, "strip") => BuiltinMethod::new("str.strip", vec![], Str),
+            (Str, "contains") => BuiltinMethod::new("str.contains", vec![Str], Bool),
+            (Str, "starts_with") => BuiltinMethod::new("str.starts_with", vec![Str], Bool),
+            (Str, "ends_with") => BuiltinMethod::new("str.ends_with", vec![Str], Bool),
+            (Str, "replace") => BuiltinMethod::new("str.replace", vec![Str, Str], Str),
+            (Str, "split") => BuiltinMethod::new("str.split", vec![Str], list_of(Str)),
+            (Str, "to_json") => BuiltinMethod::new("str.to_json", vec![], Str),
+            (TypeInfo::List(_), "len") => BuiltinMethod::new("len<list>", vec![], I64),
+            (TypeInfo::List(element), "append") => {
+                BuiltinMethod::new("append", vec![element.as_ref().clone()], TypeInfo::Unit)
+            }
+            (TypeInfo::List(element), "freeze") => BuiltinMethod::new(
+                "list.freeze",
+                vec![],
+                TypeInfo::frozen_list_of(element.as_ref().clone()),
+            ),
+            (TypeInfo::Dict { .. }, "len") => BuiltinMethod::new("len<map>", vec![], I64),
+            (TypeInfo::Dict { key, value }, "freeze") => BuiltinMethod::new(
+                "map.freeze",
+                vec![],
+                TypeInfo::frozen_dict_of(key.as_ref().clone(), value.as_ref().clone()),
+            ),
+            (TypeInfo::Dict { key, .. }, "keys") => {
+                BuiltinMethod::new("map.keys", vec![], list_of(key.as_ref().clone()))
+            }
+            (TypeInfo::Dict { value, .. }, "values") => {
+                BuiltinMethod::new("map.values", vec![], list_of(value.as_ref().clone()))
+            }
+            (TypeInfo::Dict { key, .. }, "has") => {
+                BuiltinMethod::new("map.has", vec![key.as_ref().clone()], Bool)
+            }
+            (ty, "abs") if ty.is_integer() => BuiltinMethod::new("int.abs", vec![], I64),
+            (ty, "to_string") if ty.is_integer() => {
+                BuiltinMethod::new("stringify<int>", vec![], Str)
+            }
+            (F64, "abs") => BuiltinMethod::new("float.abs", vec![], F64),
+            (F64, "floor") => BuiltinMethod::new("float.floor", vec![], F64),
+            (F64, "ceil") => BuiltinMethod::new("float.ceil", vec![], F64),
+            (F64, "round") => BuiltinMethod::new("float.round", vec![], F64),
+            (F64, "to_string") => BuiltinMethod::new("stringify<float>", vec![], Str),
+            (Bool, "to_string") => BuiltinMethod::new("stringify<bool>", vec![], Str),
+            (ty, "message") if ty.is_error_value() => {
+                BuiltinMethod::new("error.message", vec![], Str)
+            }
+            (ty, "context") if ty.is_error_value() => {
+                BuiltinMethod::new("error.context", vec![Str], TypeInfo::error_value())
+            }
+            (ty, "to_string") if ty.is_error_value() => {
+                BuiltinMethod::new("error.to_string", vec![], Str)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Names of the builtin methods of this type, for error messages
+    pub fn builtin_method_names(&self) -> &'static [&'static str] {
+        match self {
+            TypeInfo::Alias { underlying, .. } => underlying.builtin_method_names(),
+            TypeInfo::Str => &[
+                "len",
+                "upper",
+                "lower",
+                "strip",
+                "contains",
+                "starts_with",
+                "ends_with",
+                "replace",
+                "split",
+                "to_json",
+            ],
+            TypeInfo::List(_) => &["len", "append", "freeze"],
+            TypeInfo::Dict { .. } => &["len", "keys", "values", "has", "freeze"],
+            ty if ty.frozen_list_element().is_some() => &["len", "get", "set", "append", "thaw"],
+            ty if ty.frozen_dict_entry().is_some() => {
+                &["len", "get", "set", "remove", "has", "keys", "thaw"]
+            }
+            TypeInfo::F64 => &["abs", "floor", "ceil", "round", "to_string"],
+            TypeInfo::Bool => &["to_string"],
+            ty if ty.is_integer() => &["abs", "to_string"],
+            ty if ty.is_error_value() => &["message", "context", "to_string"],
+            _ => &[],
+        }
+    }
+
+    /// The integer type both operands widen to without loss, if there is one
+    pub fn common_int_type(&self, other: &TypeInfo) -> Option<TypeInfo> {
+        if !self.is_integer() || !other.is_integer() {
+            return None;
+        }
+        self.common_numeric_type(other)
+    }
+
+    /// The numeric type both operands widen to without loss, if there is one
+    pub fn common_numeric_type(&self, other: &TypeInfo) -> Option<TypeInfo> {
+        let common = self.numeric()?.common(other.numeric()?)?;
+        if self.numeric() == Some(common) {
+            Some(self.clone())
+        } else {
+            Some(other.clone())
+        }
     }
 }
 
@@ -374,11 +954,19 @@ impl From<&Type> for TypeInfo {
         match ty {
             Type::Simple(name) => match name.as_str() {
                 "unit" | "None" | "none" => TypeInfo::Unit,
+                "never" => TypeInfo::Never,
                 "bool" => TypeInfo::Bool,
+                "i8" => TypeInfo::I8,
+                "i16" => TypeInfo::I16,
                 "i32" => TypeInfo::I32,
                 "i64" | "int" => TypeInfo::I64,
+                "u8" => TypeInfo::U8,
+                "u16" => TypeInfo::U16,
+                "u32" => TypeInfo::U32,
+                "u64" => TypeInfo::U64,
                 "f64" | "float" => TypeInfo::F64,
                 "str" | "string" => TypeInfo::Str,
+                "symbol" => TypeInfo::Symbol,
                 "list" | "List" => TypeInfo::List(Box::new(TypeInfo::Unknown)),
                 "dict" | "Dict" => TypeInfo::Dict {
                     key: Box::new(TypeInfo::Unknown),
@@ -414,6 +1002,7 @@ impl From<&Type> for TypeInfo {
                     args: args.iter().map(|t| t.into()).collect(),
                 },
             },
+            Type::Union(members) => TypeInfo::union(members.iter().map(TypeInfo::from).collect()),
         }
     }
 }
@@ -428,11 +1017,19 @@ impl From<&str> for TypeInfo {
     fn from(name: &str) -> Self {
         match name {
             "unit" => TypeInfo::Unit,
+            "never" => TypeInfo::Never,
             "bool" => TypeInfo::Bool,
+            "i8" => TypeInfo::I8,
+            "i16" => TypeInfo::I16,
             "i32" => TypeInfo::I32,
             "i64" => TypeInfo::I64,
+            "u8" => TypeInfo::U8,
+            "u16" => TypeInfo::U16,
+            "u32" => TypeInfo::U32,
+            "u64" => TypeInfo::U64,
             "f64" => TypeInfo::F64,
             "str" => TypeInfo::Str,
+            "symbol" => TypeInfo::Symbol,
             "list" | "List" => TypeInfo::List(Box::new(TypeInfo::Unknown)),
             "dict" | "Dict" => TypeInfo::Dict {
                 key: Box::new(TypeInfo::Unknown),
@@ -446,6 +1043,139 @@ impl From<&str> for TypeInfo {
     }
 }
 
+/// The two types of a mismatch, kept whole so they can be lined up with the
+/// parts that differ marked
+#[derive(Debug, Clone)]
+pub struct TypeMismatch {
+    pub expected: TypeInfo,
+    pub found: TypeInfo,
+}
+
+impl TypeMismatch {
+    /// Both types on lines of their own, each followed by carets under the
+    /// parts that differ from the other:
+    ///
+    /// ```text
+    /// expected: list<dict<str, i64>>
+    ///                          ^^^
+    ///    found: list<dict<str, str>>
+    ///                          ^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let mut expected = MarkedType::default();
+        let mut found = MarkedType::default();
+        diff_types(&self.expected, &self.found, &mut expected, &mut found);
+        format!(
+            "{}\n{}",
+            expected.render("expected: "),
+            found.render("   found: ")
+        )
+    }
+}
+
+/// One side of a rendered mismatch, with the byte ranges that differ
+#[derive(Default)]
+struct MarkedType {
+    text: String,
+    marks: Vec<std::ops::Range<usize>>,
+}
+
+impl MarkedType {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn mark(&mut self, text: &str) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.marks.push(start..self.text.len());
+    }
+
+    fn render(&self, label: &str) -> String {
+        let mut carets = " ".repeat(label.len() + self.text.len());
+        for mark in &self.marks {
+            let range = label.len() + mark.start..label.len() + mark.end;
+            carets.replace_range(range.clone(), &"^".repeat(range.len()));
+        }
+        format!("{label}{}\n{}", self.text, carets.trim_end())
+    }
+}
+
+/// How a type is displayed when it has type arguments: the text before the
+/// arguments, the arguments and the text after them
+fn type_arguments(ty: &TypeInfo) -> Option<(String, Vec<&TypeInfo>, &'static str)> {
+    Some(match ty {
+        TypeInfo::List(element) => ("list<".to_string(), vec![element.as_ref()], ">"),
+        TypeInfo::Dict { key, value } => {
+            ("dict<".to_string(), vec![key.as_ref(), value.as_ref()], ">")
+        }
+        TypeInfo::Range(start, end) => (
+            "range<".to_string(),
+            vec![start.as_ref(), end.as_ref()],
+            ">",
+        ),
+        TypeInfo::Generic { base: name, args }
+        | TypeInfo::Struct { name, args, .. }
+        | TypeInfo::Enum { name, args, .. }
+            if !args.is_empty() =>
+        {
+            (format!("{name}<"), args.iter().collect(), ">")
+        }
+        TypeInfo::Function {
+            params,
+            return_type,
+            ..
+        } => (
+            "fn(".to_string(),
+            params.iter().chain([return_type.as_ref()]).collect(),
+            "",
+        ),
+        _ => return None,
+    })
+}
+
+/// Renders `expected` and `found`, descending into type arguments while both
+/// types have the same shape and marking the parts that differ
+fn diff_types(
+    expected: &TypeInfo,
+    found: &TypeInfo,
+    expected_out: &mut MarkedType,
+    found_out: &mut MarkedType,
+) {
+    if expected == found {
+        expected_out.push(&expected.display_name());
+        found_out.push(&found.display_name());
+        return;
+    }
+    match (type_arguments(expected), type_arguments(found)) {
+        (Some((prefix, expected_args, suffix)), Some((found_prefix, found_args, _)))
+            if prefix == found_prefix && expected_args.len() == found_args.len() =>
+        {
+            expected_out.push(&prefix);
+            found_out.push(&prefix);
+            let function = matches!(expected, TypeInfo::Function { .. });
+            let last = expected_args.len() - 1;
+            for (index, (expected, found)) in expected_args.into_iter().zip(found_args).enumerate()
+            {
+                let separator = match index {
+                    index if function && index == last => ") -> ",
+                    0 => "",
+                    _ => ", ",
+                };
+                expected_out.push(separator);
+                found_out.push(separator);
+                diff_types(expected, found, expected_out, found_out);
+            }
+            expected_out.push(suffix);
+            found_out.push(suffix);
+        }
+        _ => {
+            expected_out.mark(&expected.display_name());
+            found_out.mark(&found.display_name());
+        }
+    }
+}
+
 /// Type checking error
 #[derive(Debug, Clone)]
 pub struct TypeError {
@@ -454,6 +1184,8 @@ pub struct TypeError {
     pub help: Option<String>,
     pub suggestion: Option<String>,
     pub span: Option<Span>,
+    /// The types that did not match, for errors about a mismatch
+    pub mismatch: Option<TypeMismatch>,
 }
 
 impl TypeError {
@@ -464,6 +1196,7 @@ impl TypeError {
             help: None,
             suggestion: None,
             span: None,
+            mismatch: None,
         }
     }
 
@@ -491,11 +1224,22 @@ impl TypeError {
         self.span = span;
         self
     }
+
+    pub fn with_mismatch(mut self, expected: &TypeInfo, found: &TypeInfo) -> Self {
+        self.mismatch = Some(TypeMismatch {
+            expected: expected.clone(),
+            found: found.clone(),
+        });
+        self
+    }
 }
 
 impl std::fmt::Display for TypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.message)?;
+        if let Some(mismatch) = &self.mismatch {
+            write!(f, "\n{}", mismatch.render())?;
+        }
         if let Some(hint) = &self.hint {
             write!(f, "\nSuggestion: {}", hint)?;
         }
@@ -523,6 +1267,8 @@ pub struct TypeContext {
     pub type_aliases: HashMap<String, TypeInfo>,
     /// Enum definitions available in the current module
     pub enums: HashMap<String, EnumDefinition>,
+    /// Protocol definitions: name -> required methods
+    pub protocols: HashMap<String, ProtocolDefinition>,
     /// Active language feature flags
     pub features: LanguageFeatureFlags,
 }
@@ -540,6 +1286,7 @@ impl TypeContext {
             structs: HashMap::new(),
             type_aliases: HashMap::new(),
             enums: HashMap::new(),
+            protocols: HashMap::new(),
             features,
         }
     }
@@ -614,6 +1361,14 @@ impl TypeContext {
         self.enums.get(name)
     }
 
+    pub fn define_protocol(&mut self, definition: ProtocolDefinition) {
+        self.protocols.insert(definition.name.clone(), definition);
+    }
+
+    pub fn get_protocol(&self, name: &str) -> Option<&ProtocolDefinition> {
+        self.protocols.get(name)
+    }
+
     pub fn enum_variant(&self, enum_name: &str, variant: &str) -> Option<&EnumVariant> {
         self.enums
             .get(enum_name)
@@ -653,6 +1408,57 @@ impl TypeContext {
             .collect()
     }
 
+    /// The type of generic struct `name` instantiated with `args`, e.g.
+    /// `Stack<int>`, with the arguments substituted into its fields
+    pub fn build_struct_type(&self, name: &str, args: &[TypeInfo]) -> Option<TypeInfo> {
+        let definition = self.structs.get(name)?;
+        if definition.generics.is_empty() || definition.generics.len() != args.len() {
+            return None;
+        }
+        let substitutions: HashMap<String, TypeInfo> = definition
+            .generics
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect();
+        Some(TypeInfo::Struct {
+            name: name.to_string(),
+            fields: definition
+                .fields
+                .iter()
+                .map(|(field, ty)| (field.clone(), ty.substitute(&substitutions)))
+                .collect(),
+            args: args.to_vec(),
+        })
+    }
+
+    /// The type of `field` on a value of struct `name`. A struct type named
+    /// inside a recursive definition was built before the definition was
+    /// complete and carries no fields, so they are looked up by name.
+    pub fn struct_field_type(
+        &self,
+        name: &str,
+        args: &[TypeInfo],
+        fields: &HashMap<String, TypeInfo>,
+        field: &str,
+    ) -> Option<TypeInfo> {
+        if let Some(ty) = fields.get(field) {
+            return Some(ty.clone());
+        }
+        let definition = self.structs.get(name)?;
+        let ty = definition.fields.get(field)?;
+        if definition.generics.len() != args.len() {
+            return Some(ty.clone());
+        }
+        let substitutions: HashMap<String, TypeInfo> = definition
+            .generics
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect();
+        Some(ty.substitute(&substitutions))
+    }
+
     pub fn build_enum_type(&self, name: &str, args: Vec<TypeInfo>) -> Option<TypeInfo> {
         let definition = self.enums.get(name)?;
         let mut normalized_args = if args.is_empty() {
@@ -696,6 +1502,7 @@ impl TypeContext {
                     EnumVariantInfo {
                         name: variant.name.clone(),
                         fields,
+                        field_names: variant.field_names.clone(),
                     },
                 )
             })
@@ -711,25 +1518,46 @@ impl TypeContext {
     pub fn normalize_type(&self, ty: TypeInfo) -> TypeInfo {
         match ty {
             TypeInfo::Generic { base, args } => {
+                if args.is_empty() && self.protocols.contains_key(&base) {
+                    return TypeInfo::Protocol(base);
+                }
                 if args.is_empty()
                     && let Some(struct_def) = self.structs.get(&base)
                 {
                     return TypeInfo::Struct {
                         name: struct_def.name.clone(),
                         fields: struct_def.fields.clone(),
+                        args: Vec::new(),
                     };
                 }
+                if let Some(struct_ty) = self.build_struct_type(&base, &args) {
+                    return struct_ty;
+                }
                 if let Some(enum_ty) = self.build_enum_type(&base, args.clone()) {
                     enum_ty
                 } else {
                     TypeInfo::Generic { base, args }
                 }
             }
+            TypeInfo::Union(members) => TypeInfo::union(
+                members
+                    .into_iter()
+                    .map(|member| self.normalize_type(member))
+                    .collect(),
+            ),
             other => other,
         }
     }
 
     pub fn type_from_annotation(&self, ty: &Node<Type>) -> TypeInfo {
+        if let Type::Union(members) = ty.as_ref() {
+            return TypeInfo::union(
+                members
+                    .iter()
+                    .map(|member| self.type_from_annotation(member))
+                    .collect(),
+            );
+        }
         let mut info = TypeInfo::from(ty);
         if let TypeInfo::Generic { base, args } = &info
             && args.is_empty()
@@ -767,6 +1595,23 @@ pub struct EnumDefinition {
     pub public: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct ProtocolDefinition {
+    pub name: String,
+    /// Required methods and their function types, without `self`
+    pub methods: Vec<(String, TypeInfo)>,
+    pub public: bool,
+}
+
+impl ProtocolDefinition {
+    pub fn method(&self, name: &str) -> Option<&TypeInfo> {
+        self.methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, ty)| ty)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EnumLayout {
     pub name: String,
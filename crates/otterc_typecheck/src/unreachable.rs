@@ -0,0 +1,171 @@
+//! Warnings for statements that can never run
+//!
+//! A statement is unreachable when an earlier statement of the same block
+//! always leaves it: `return`, `break`, `continue`, a call to `panic`, a
+//! `while true:` loop nothing breaks out of, or an `if` whose branches,
+//! `else` included, all do one of those. Only the first unreachable statement
+//! of a block is reported.
+
+use otterc_ast::nodes::{Block, Expr, Function, Literal, Node, Program, Statement};
+
+use crate::types::TypeError;
+
+/// Builtins that never return to their caller
+const DIVERGING_BUILTINS: &[&str] = &["panic"];
+
+/// Warnings for the unreachable statements of `program`
+pub fn unreachable_statements(program: &Program) -> Vec<TypeError> {
+    let mut warnings = Vec::new();
+    for statement in &program.statements {
+        nested_blocks(statement.as_ref(), &mut warnings);
+    }
+    warnings
+}
+
+fn function_body(function: &Function, warnings: &mut Vec<TypeError>) {
+    block(&function.body.as_ref().statements, warnings);
+}
+
+/// Checks the statements of one block and every block nested in them
+fn block(statements: &[Node<Statement>], warnings: &mut Vec<TypeError>) {
+    let mut diverged = false;
+    for statement in statements {
+        if diverged {
+            warnings.push(
+                TypeError::new("unreachable code".to_string())
+                    .with_span(*statement.span())
+                    .with_help(
+                        "the statement before it always returns, breaks, continues, panics or loops forever"
+                            .to_string(),
+                    ),
+            );
+            break;
+        }
+        nested_blocks(statement.as_ref(), warnings);
+        diverged = diverges(statement.as_ref());
+    }
+}
+
+fn nested_blocks(statement: &Statement, warnings: &mut Vec<TypeError>) {
+    match statement {
+        Statement::Function(function) => function_body(function.as_ref(), warnings),
+        Statement::Struct { methods, .. } | Statement::Enum { methods, .. } => {
+            for method in methods {
+                function_body(method.as_ref(), warnings);
+            }
+        }
+        Statement::If {
+            then_block,
+            elif_blocks,
+            else_block,
+            ..
+        } => {
+            block(&then_block.as_ref().statements, warnings);
+            for (_, elif_block) in elif_blocks {
+                block(&elif_block.as_ref().statements, warnings);
+            }
+            if let Some(else_block) = else_block {
+                block(&else_block.as_ref().statements, warnings);
+            }
+        }
+        Statement::For { body, .. }
+        | Statement::While { body, .. }
+        | Statement::With { body, .. }
+        | Statement::Init(body)
+        | Statement::Block(body) => block(&body.as_ref().statements, warnings),
+        Statement::Expr(expr) => {
+            if let Expr::Match { arms, .. } = expr.as_ref() {
+                for arm in arms {
+                    block(&arm.as_ref().body.as_ref().statements, warnings);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether running `statement` never falls through to the next one
+fn diverges(statement: &Statement) -> bool {
+    match statement {
+        Statement::Return(_) | Statement::Break(_) | Statement::Continue(_) => true,
+        Statement::Expr(expr) => is_diverging_call(expr.as_ref()),
+        Statement::If {
+            then_block,
+            elif_blocks,
+            else_block: Some(else_block),
+            ..
+        } => {
+            block_diverges(then_block.as_ref())
+                && elif_blocks
+                    .iter()
+                    .all(|(_, elif_block)| block_diverges(elif_block.as_ref()))
+                && block_diverges(else_block.as_ref())
+        }
+        Statement::Block(body) => block_diverges(body.as_ref()),
+        Statement::While { cond, body, label } => loops_forever(
+            cond.as_ref(),
+            body.as_ref(),
+            label.as_ref().map(|label| label.as_ref().as_str()),
+        ),
+        _ => false,
+    }
+}
+
+/// Whether a `while` loop with this condition, body and label never ends:
+/// its condition is `true` and no `break` leaves it
+pub(crate) fn loops_forever(cond: &Expr, body: &Block, label: Option<&str>) -> bool {
+    matches!(cond, Expr::Literal(literal) if matches!(literal.as_ref(), Literal::Bool(true)))
+        && !breaks_out(&body.statements, label, 0)
+}
+
+/// Whether a `break` in `statements` leaves the loop labelled `label`, with
+/// `depth` loops nested between them
+fn breaks_out(statements: &[Node<Statement>], label: Option<&str>, depth: usize) -> bool {
+    statements.iter().any(|statement| match statement.as_ref() {
+        Statement::Break(None) => depth == 0,
+        Statement::Break(Some(target)) => label == Some(target.as_ref().as_str()),
+        Statement::For { body, .. } | Statement::While { body, .. } => {
+            breaks_out(&body.as_ref().statements, label, depth + 1)
+        }
+        Statement::If {
+            then_block,
+            elif_blocks,
+            else_block,
+            ..
+        } => {
+            breaks_out(&then_block.as_ref().statements, label, depth)
+                || elif_blocks
+                    .iter()
+                    .any(|(_, block)| breaks_out(&block.as_ref().statements, label, depth))
+                || else_block
+                    .as_ref()
+                    .is_some_and(|block| breaks_out(&block.as_ref().statements, label, depth))
+        }
+        Statement::With { body, .. } | Statement::Block(body) => {
+            breaks_out(&body.as_ref().statements, label, depth)
+        }
+        Statement::Expr(expr)
+        | Statement::Let { expr, .. }
+        | Statement::Assignment { expr, .. }
+        | Statement::Return(Some(expr)) => match expr.as_ref() {
+            Expr::Match { arms, .. } => arms
+                .iter()
+                .any(|arm| breaks_out(&arm.as_ref().body.as_ref().statements, label, depth)),
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+fn block_diverges(body: &Block) -> bool {
+    body.statements
+        .iter()
+        .any(|statement| diverges(statement.as_ref()))
+}
+
+fn is_diverging_call(expr: &Expr) -> bool {
+    let Expr::Call { func, .. } = expr else {
+        return false;
+    };
+    matches!(func.as_ref().as_ref(), Expr::Identifier(name) if DIVERGING_BUILTINS.contains(&name.as_str()))
+}
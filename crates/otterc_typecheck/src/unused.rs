@@ -0,0 +1,468 @@
+//! Warnings for bindings that are never read
+//!
+//! A function's `let` bindings and parameters, and a module's imports, are
+//! reported when no expression reads them. Names starting with `_` are never
+//! reported, which is how a binding is kept on purpose.
+//!
+//! Reads are matched by name within the whole function, nested functions and
+//! lambdas included, so a binding is only reported when nothing in its
+//! function could be reading it.
+
+use std::collections::HashSet;
+
+use otterc_ast::nodes::{
+    Block, Expr, FStringPart, Function, Node, Pattern, Program, Statement, Type, UseImport,
+};
+
+use crate::types::TypeError;
+
+/// Warnings for the unused bindings and imports of `program`
+pub fn unused_bindings(program: &Program) -> Vec<TypeError> {
+    let mut warnings = Vec::new();
+
+    let mut reads = HashSet::new();
+    for statement in &program.statements {
+        statement_reads(statement.as_ref(), &mut reads);
+    }
+    for statement in &program.statements {
+        if let Statement::Use { imports } = statement.as_ref() {
+            for import in imports {
+                unused_imports(import, &reads, &mut warnings);
+            }
+        }
+    }
+
+    for statement in &program.statements {
+        match statement.as_ref() {
+            Statement::Function(function) => unused_in_function(function, &mut warnings),
+            Statement::Struct { methods, .. } | Statement::Enum { methods, .. } => {
+                for method in methods {
+                    unused_in_function(method, &mut warnings);
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+fn is_silenced(name: &str) -> bool {
+    name.starts_with('_')
+}
+
+fn unused_imports(import: &Node<UseImport>, reads: &HashSet<&str>, warnings: &mut Vec<TypeError>) {
+    let import_ref = import.as_ref();
+    if import_ref.glob {
+        return;
+    }
+    if import_ref.items.is_empty() {
+        let name = import_ref.alias.as_deref().unwrap_or_else(|| {
+            let last = import_ref
+                .module
+                .rsplit([':', '/'])
+                .next()
+                .unwrap_or_default();
+            last.strip_suffix(".ot").unwrap_or(last)
+        });
+        if !name.is_empty() && !is_silenced(name) && !reads.contains(name) {
+            warnings.push(
+                TypeError::new(format!("unused import: `{}`", import_ref.module))
+                    .with_span(*import.span())
+                    .with_help("remove the import, or import it `as _name` to keep it".to_string()),
+            );
+        }
+        return;
+    }
+    for item in &import_ref.items {
+        let name = item.as_ref().local_name();
+        if !is_silenced(name) && !reads.contains(name) {
+            warnings.push(
+                TypeError::new(format!(
+                    "unused import: `{}` from `{}`",
+                    item.as_ref().name,
+                    import_ref.module
+                ))
+                .with_span(*item.span())
+                .with_help(format!("remove `{name}` from the import")),
+            );
+        }
+    }
+}
+
+fn unused_in_function(function: &Node<Function>, warnings: &mut Vec<TypeError>) {
+    let function = function.as_ref();
+    let mut reads = HashSet::new();
+    block_reads(function.body.as_ref(), &mut reads);
+
+    for param in &function.params {
+        let name = param.as_ref().name.as_ref().as_str();
+        if name != "self" && !is_silenced(name) && !reads.contains(name) {
+            warnings.push(unused_warning("parameter", &param.as_ref().name));
+        }
+    }
+
+    let mut lets = Vec::new();
+    let mut nested = Vec::new();
+    collect_lets(function.body.as_ref(), &mut lets, &mut nested);
+    for name in lets {
+        let name_ref = name.as_ref().as_str();
+        if !is_silenced(name_ref) && !reads.contains(name_ref) {
+            warnings.push(unused_warning("variable", name));
+        }
+    }
+
+    for function in nested {
+        unused_in_function(function, warnings);
+    }
+}
+
+fn unused_warning(kind: &str, name: &Node<String>) -> TypeError {
+    let name_ref = name.as_ref();
+    TypeError::new(format!("unused {kind}: `{name_ref}`"))
+        .with_span(*name.span())
+        .with_help(format!(
+            "if this is intentional, prefix it with an underscore: `_{name_ref}`"
+        ))
+        .with_suggestion(format!("_{name_ref}"))
+}
+
+/// The `let` bindings of a function body, and the functions declared in it,
+/// which are checked on their own
+fn collect_lets<'a>(
+    block: &'a Block,
+    lets: &mut Vec<&'a Node<String>>,
+    nested: &mut Vec<&'a Node<Function>>,
+) {
+    for statement in &block.statements {
+        match statement.as_ref() {
+            Statement::Let { name, expr, .. } => {
+                lets.push(name);
+                expr_lets(expr, lets, nested);
+            }
+            Statement::Function(function) => nested.push(function),
+            Statement::If {
+                cond,
+                then_block,
+                elif_blocks,
+                else_block,
+            } => {
+                expr_lets(cond, lets, nested);
+                collect_lets(then_block.as_ref(), lets, nested);
+                for (cond, block) in elif_blocks {
+                    expr_lets(cond, lets, nested);
+                    collect_lets(block.as_ref(), lets, nested);
+                }
+                if let Some(block) = else_block {
+                    collect_lets(block.as_ref(), lets, nested);
+                }
+            }
+            Statement::For { body, .. }
+            | Statement::While { body, .. }
+            | Statement::With { body, .. }
+            | Statement::Block(body) => collect_lets(body.as_ref(), lets, nested),
+            Statement::Assignment { expr, .. }
+            | Statement::Expr(expr)
+            | Statement::Return(Some(expr)) => expr_lets(expr, lets, nested),
+            _ => {}
+        }
+    }
+}
+
+/// `let` bindings in the arms of `match` expressions
+fn expr_lets<'a>(
+    expr: &'a Node<Expr>,
+    lets: &mut Vec<&'a Node<String>>,
+    nested: &mut Vec<&'a Node<Function>>,
+) {
+    if let Expr::Match { arms, .. } = expr.as_ref() {
+        for arm in arms {
+            collect_lets(arm.as_ref().body.as_ref(), lets, nested);
+        }
+    }
+}
+
+fn block_reads<'a>(block: &'a Block, reads: &mut HashSet<&'a str>) {
+    for statement in &block.statements {
+        statement_reads(statement.as_ref(), reads);
+    }
+}
+
+fn statement_reads<'a>(statement: &'a Statement, reads: &mut HashSet<&'a str>) {
+    match statement {
+        Statement::Let { expr, ty, .. } | Statement::Const { expr, ty, .. } => {
+            expr_reads(expr, reads);
+            if let Some(ty) = ty {
+                type_reads(ty, reads);
+            }
+        }
+        Statement::Assignment { name, expr } => {
+            // Assigning to `object.field` reads `object`
+            if let Some((object, _)) = name.as_ref().split_once('.') {
+                reads.insert(object);
+            }
+            expr_reads(expr, reads);
+        }
+        Statement::If {
+            cond,
+            then_block,
+            elif_blocks,
+            else_block,
+        } => {
+            expr_reads(cond, reads);
+            block_reads(then_block.as_ref(), reads);
+            for (cond, block) in elif_blocks {
+                expr_reads(cond, reads);
+                block_reads(block.as_ref(), reads);
+            }
+            if let Some(block) = else_block {
+                block_reads(block.as_ref(), reads);
+            }
+        }
+        Statement::For { iterable, body, .. } => {
+            expr_reads(iterable, reads);
+            block_reads(body.as_ref(), reads);
+        }
+        Statement::While { cond, body, .. }
+        | Statement::With {
+            expr: cond, body, ..
+        } => {
+            expr_reads(cond, reads);
+            block_reads(body.as_ref(), reads);
+        }
+        Statement::Return(Some(expr)) | Statement::Defer(expr) | Statement::Expr(expr) => {
+            expr_reads(expr, reads);
+        }
+        Statement::Assert { cond, message } => {
+            expr_reads(cond, reads);
+            if let Some(message) = message {
+                expr_reads(message, reads);
+            }
+        }
+        Statement::Function(function) => function_reads(function.as_ref(), reads),
+        Statement::Struct {
+            fields, methods, ..
+        } => {
+            for (_, ty) in fields {
+                type_reads(ty, reads);
+            }
+            for method in methods {
+                function_reads(method.as_ref(), reads);
+            }
+        }
+        Statement::Enum {
+            variants, methods, ..
+        } => {
+            for variant in variants {
+                for ty in &variant.as_ref().fields {
+                    type_reads(ty, reads);
+                }
+            }
+            for method in methods {
+                function_reads(method.as_ref(), reads);
+            }
+        }
+        Statement::TypeAlias { target, .. } => type_reads(target, reads),
+        Statement::Protocol { methods, .. } => {
+            for method in methods {
+                let method = method.as_ref();
+                for param in &method.params {
+                    if let Some(ty) = &param.as_ref().ty {
+                        type_reads(ty, reads);
+                    }
+                }
+                if let Some(ty) = &method.ret_ty {
+                    type_reads(ty, reads);
+                }
+            }
+        }
+        Statement::PubUse { module, .. } => {
+            reads.insert(module.split('.').next().unwrap_or(module));
+        }
+        Statement::Block(block) | Statement::Init(block) => block_reads(block.as_ref(), reads),
+        Statement::Return(None)
+        | Statement::Break(_)
+        | Statement::Continue(_)
+        | Statement::Pass
+        | Statement::Use { .. } => {}
+    }
+}
+
+fn function_reads<'a>(function: &'a Function, reads: &mut HashSet<&'a str>) {
+    for param in &function.params {
+        if let Some(ty) = &param.as_ref().ty {
+            type_reads(ty, reads);
+        }
+        if let Some(default) = &param.as_ref().default {
+            expr_reads(default, reads);
+        }
+    }
+    if let Some(ty) = &function.ret_ty {
+        type_reads(ty, reads);
+    }
+    block_reads(function.body.as_ref(), reads);
+}
+
+fn type_reads<'a>(ty: &'a Node<Type>, reads: &mut HashSet<&'a str>) {
+    let base = match ty.as_ref() {
+        Type::Simple(name) => name,
+        Type::Generic { base, args } => {
+            for arg in args {
+                type_reads(arg, reads);
+            }
+            base
+        }
+        Type::Union(members) => {
+            for member in members {
+                type_reads(member, reads);
+            }
+            return;
+        }
+    };
+    // `module.Type` reads the module
+    reads.insert(base.split('.').next().unwrap_or(base));
+}
+
+fn pattern_reads<'a>(pattern: &'a Pattern, reads: &mut HashSet<&'a str>) {
+    match pattern {
+        Pattern::EnumVariant {
+            enum_name, fields, ..
+        } => {
+            reads.insert(enum_name.split('.').next().unwrap_or(enum_name));
+            for field in fields {
+                pattern_reads(field.as_ref(), reads);
+            }
+        }
+        Pattern::Struct { name, fields } => {
+            reads.insert(name);
+            for pattern in fields.iter().filter_map(|(_, pattern)| pattern.as_ref()) {
+                pattern_reads(pattern.as_ref(), reads);
+            }
+        }
+        Pattern::Array { patterns, .. } | Pattern::Or(patterns) => {
+            for pattern in patterns {
+                pattern_reads(pattern.as_ref(), reads);
+            }
+        }
+        Pattern::Dict { entries } => {
+            for (_, pattern) in entries {
+                pattern_reads(pattern.as_ref(), reads);
+            }
+        }
+        Pattern::Binding { pattern, .. } => pattern_reads(pattern.as_ref().as_ref(), reads),
+        Pattern::Wildcard
+        | Pattern::Literal(_)
+        | Pattern::Range { .. }
+        | Pattern::Identifier(_) => {}
+    }
+}
+
+fn expr_reads<'a>(expr: &'a Node<Expr>, reads: &mut HashSet<&'a str>) {
+    match expr.as_ref() {
+        Expr::Literal(_) => {}
+        Expr::Identifier(name) => {
+            reads.insert(name);
+        }
+        Expr::Member { object, .. } => expr_reads(object, reads),
+        Expr::Call { func, args } => {
+            expr_reads(func, reads);
+            for arg in args {
+                expr_reads(arg, reads);
+            }
+        }
+        Expr::Binary { left, right, .. }
+        | Expr::Range {
+            start: left,
+            end: right,
+        } => {
+            expr_reads(left, reads);
+            expr_reads(right, reads);
+        }
+        Expr::Unary { expr, .. } | Expr::Await(expr) | Expr::Spawn(expr) => {
+            expr_reads(expr, reads);
+        }
+        Expr::Cast { expr, ty } => {
+            expr_reads(expr, reads);
+            type_reads(ty, reads);
+        }
+        Expr::If {
+            cond,
+            then_branch,
+            else_branch,
+        } => {
+            expr_reads(cond, reads);
+            expr_reads(then_branch, reads);
+            if let Some(else_branch) = else_branch {
+                expr_reads(else_branch, reads);
+            }
+        }
+        Expr::Match { value, arms } => {
+            expr_reads(value, reads);
+            for arm in arms {
+                let arm = arm.as_ref();
+                pattern_reads(arm.pattern.as_ref(), reads);
+                if let Some(guard) = &arm.guard {
+                    expr_reads(guard, reads);
+                }
+                block_reads(arm.body.as_ref(), reads);
+            }
+        }
+        Expr::Array(items) => {
+            for item in items {
+                expr_reads(item, reads);
+            }
+        }
+        Expr::Dict(entries) => {
+            for (key, value) in entries {
+                expr_reads(key, reads);
+                expr_reads(value, reads);
+            }
+        }
+        Expr::ListComprehension {
+            element,
+            iterable,
+            condition,
+            ..
+        } => {
+            expr_reads(element, reads);
+            expr_reads(iterable, reads);
+            if let Some(condition) = condition {
+                expr_reads(condition, reads);
+            }
+        }
+        Expr::DictComprehension {
+            key,
+            value,
+            iterable,
+            condition,
+            ..
+        } => {
+            expr_reads(key, reads);
+            expr_reads(value, reads);
+            expr_reads(iterable, reads);
+            if let Some(condition) = condition {
+                expr_reads(condition, reads);
+            }
+        }
+        Expr::FString { parts } => {
+            for part in parts {
+                if let FStringPart::Expr(expr, _) = part.as_ref() {
+                    expr_reads(expr, reads);
+                }
+            }
+        }
+        Expr::Struct { name, fields } => {
+            reads.insert(name.split('.').next().unwrap_or(name));
+            for (_, value) in fields {
+                expr_reads(value, reads);
+            }
+        }
+        Expr::Lambda { params, body } => {
+            for param in params {
+                if let Some(ty) = &param.as_ref().ty {
+                    type_reads(ty, reads);
+                }
+            }
+            expr_reads(body, reads);
+        }
+    }
+}
@@ -74,9 +74,34 @@ impl TypecheckWorkspace {
             }
         }
 
-        let check_result = checker.check_program(&program);
-        let exports = checker.collect_public_exports(&module_id, &program);
-        let diagnostics = checker.errors().to_vec();
+        let mut check_result = checker.check_program(&program);
+        let mut exports = checker.collect_public_exports(&module_id, &program);
+        let mut diagnostics = checker.errors().to_vec();
+        for statement in &program.statements {
+            let Statement::PubUse {
+                module,
+                item,
+                alias,
+            } = statement.as_ref()
+            else {
+                continue;
+            };
+            let Some(source) = self.modules.get(module) else {
+                continue;
+            };
+            if !exports.re_export(&source.exports, item.as_deref(), alias.as_deref()) {
+                let error = TypeError::new(format!(
+                    "cannot re-export '{}' from '{}': item not found or not public",
+                    item.as_deref().unwrap_or_default(),
+                    module
+                ))
+                .with_span(*statement.span());
+                if check_result.is_ok() {
+                    check_result = Err(anyhow::anyhow!("type checking failed:\n{}", error));
+                }
+                diagnostics.push(error);
+            }
+        }
         let enum_layouts = checker.enum_layouts();
         let (expr_types, span_types, comprehension_types) = checker.into_type_maps();
 
@@ -264,4 +289,53 @@ mod tests {
         assert!(record.diagnostics.is_empty());
         assert!(record.exports.functions.contains_key("main"));
     }
+
+    fn parse(source: &str) -> Program {
+        let tokens = otterc_lexer::tokenize(source).expect("tokenize");
+        otterc_parser::parse(&tokens).expect("parse")
+    }
+
+    #[test]
+    fn from_import_binds_exported_items() {
+        let mut workspace = TypecheckWorkspace::new();
+        workspace
+            .analyze_module(
+                "math",
+                parse("pub fn add_one(value: int) -> int:\n    return value + 1\n"),
+            )
+            .expect("math module should type-check");
+
+        let app = "from math import add_one as inc\n\nfn main() -> int:\n    return inc(41)\n";
+        let result = workspace.analyze_module("app", parse(app));
+        assert!(result.is_ok(), "app should type-check: {:?}", result);
+
+        let missing = "from math import sub_one\n";
+        assert!(workspace.analyze_module("broken", parse(missing)).is_err());
+        let diagnostics = workspace.diagnostics("broken").unwrap();
+        assert_eq!(
+            diagnostics[0].message,
+            "module 'math' has no item 'sub_one'"
+        );
+    }
+
+    #[test]
+    fn glob_imports_see_items_re_exported_by_facades() {
+        let mut workspace = TypecheckWorkspace::new();
+        workspace
+            .analyze_module(
+                "math",
+                parse("pub fn add_one(value: int) -> int:\n    return value + 1\n"),
+            )
+            .expect("math module should type-check");
+        workspace
+            .analyze_module("prelude", parse("pub use math\n"))
+            .expect("facade should type-check");
+
+        let app = "from prelude import *\n\nfn main() -> int:\n    return add_one(41)\n";
+        let result = workspace.analyze_module("app", parse(app));
+        assert!(result.is_ok(), "app should type-check: {:?}", result);
+
+        let broken = "pub use math.sub_one\n";
+        assert!(workspace.analyze_module("broken", parse(broken)).is_err());
+    }
 }
@@ -0,0 +1,241 @@
+//! Machine-readable diagnostics for CI
+//!
+//! `--output-format=github` prints workflow commands that GitHub Actions shows
+//! as inline annotations on a pull request. `--output-format=sarif` prints a
+//! SARIF 2.1.0 log for code scanning dashboards.
+
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use otterc_span::SourceLines;
+use serde_json::{Value, json};
+
+use crate::errors::{Diagnostic, DiagnosticSeverity};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// How diagnostics are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Rendered reports with source snippets
+    #[default]
+    Human,
+    /// GitHub Actions workflow commands
+    Github,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "human" => Ok(Self::Human),
+            "github" => Ok(Self::Github),
+            "sarif" => Ok(Self::Sarif),
+            other => Err(format!(
+                "unknown output format '{other}' (expected human, github or sarif)"
+            )),
+        }
+    }
+}
+
+/// A problem at a location in a file. Positions are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub severity: DiagnosticSeverity,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub message: String,
+    /// The SARIF rule the problem belongs to, if any
+    pub rule_id: Option<String>,
+}
+
+impl Annotation {
+    /// An annotation for the start of `line`
+    pub fn at_line(
+        severity: DiagnosticSeverity,
+        file: impl Into<String>,
+        line: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            file: file.into(),
+            line,
+            column: 1,
+            end_line: line,
+            end_column: 1,
+            message: message.into(),
+            rule_id: None,
+        }
+    }
+
+    pub fn with_rule_id(mut self, rule_id: impl Into<String>) -> Self {
+        self.rule_id = Some(rule_id.into());
+        self
+    }
+
+    /// Locates `diagnostic` in `source`. The suggestion and help are appended
+    /// to the message, since neither format has a place for them.
+    pub fn from_diagnostic(diagnostic: &Diagnostic, source: &str) -> Self {
+        let lines = SourceLines::new(diagnostic.source_id(), source);
+        let span = diagnostic.span();
+        let (line, column) = lines.line_col(span.start());
+        let (end_line, end_column) = lines.line_col(span.end().max(span.start()));
+
+        let mut message = diagnostic.message().to_string();
+        if let Some(suggestion) = diagnostic.suggestion() {
+            let _ = write!(message, "\nSuggestion: {suggestion}");
+        }
+        if let Some(help) = diagnostic.help() {
+            let _ = write!(message, "\n{help}");
+        }
+
+        Self {
+            severity: diagnostic.severity(),
+            file: diagnostic.source_id().to_string(),
+            line,
+            column,
+            end_line,
+            end_column,
+            message,
+            rule_id: None,
+        }
+    }
+}
+
+/// Locates every diagnostic in `source`
+pub fn annotate_diagnostics(diagnostics: &[Diagnostic], source: &str) -> Vec<Annotation> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| Annotation::from_diagnostic(diagnostic, source))
+        .collect()
+}
+
+/// Renders one GitHub Actions workflow command per annotation
+pub fn render_github(annotations: &[Annotation]) -> String {
+    let mut output = String::new();
+    for annotation in annotations {
+        let command = match annotation.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Info | DiagnosticSeverity::Hint => "notice",
+        };
+        let _ = write!(
+            output,
+            "::{command} file={},line={},col={}",
+            escape_property(&annotation.file),
+            annotation.line,
+            annotation.column
+        );
+        if annotation.end_line > annotation.line || annotation.end_column > annotation.column {
+            let _ = write!(
+                output,
+                ",endLine={},endColumn={}",
+                annotation.end_line, annotation.end_column
+            );
+        }
+        if let Some(rule_id) = &annotation.rule_id {
+            let _ = write!(output, ",title={}", escape_property(rule_id));
+        }
+        let _ = writeln!(output, "::{}", escape_data(&annotation.message));
+    }
+    output
+}
+
+/// Renders a SARIF 2.1.0 log with a single run of the `otter` tool
+pub fn render_sarif(annotations: &[Annotation], tool_version: &str) -> String {
+    let results: Vec<Value> = annotations.iter().map(sarif_result).collect();
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "otter",
+                    "version": tool_version,
+                }
+            },
+            "results": results,
+        }],
+    });
+    // A `Value` built from strings and integers always serializes
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+fn sarif_result(annotation: &Annotation) -> Value {
+    let level = match annotation.severity {
+        DiagnosticSeverity::Error => "error",
+        DiagnosticSeverity::Warning => "warning",
+        DiagnosticSeverity::Info | DiagnosticSeverity::Hint => "note",
+    };
+    let mut result = json!({
+        "level": level,
+        "message": { "text": annotation.message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": annotation.file.replace('\\', "/") },
+                "region": {
+                    "startLine": annotation.line,
+                    "startColumn": annotation.column,
+                    "endLine": annotation.end_line,
+                    "endColumn": annotation.end_column,
+                },
+            },
+        }],
+    });
+    if let (Some(rule_id), Some(fields)) = (&annotation.rule_id, result.as_object_mut()) {
+        fields.insert("ruleId".to_string(), Value::from(rule_id.as_str()));
+    }
+    result
+}
+
+/// Escapes the message of a workflow command
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a `key=value` property of a workflow command
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otterc_span::Span;
+
+    #[test]
+    fn renders_diagnostics_as_github_commands_and_sarif() {
+        let source = "let x = 1\nlet y = x + \"a\"\n";
+        let diagnostic =
+            Diagnostic::error("src/main.ot", Span::new(18, 25), "cannot add int and str")
+                .with_help("convert one side first");
+        let annotations = annotate_diagnostics(&[diagnostic], source);
+
+        assert_eq!(
+            render_github(&annotations),
+            "::error file=src/main.ot,line=2,col=9,endLine=2,endColumn=16::cannot add int and str%0Aconvert one side first\n"
+        );
+
+        let log: Value = serde_json::from_str(&render_sarif(&annotations, "0.1.0")).unwrap();
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/main.ot"
+        );
+    }
+}
@@ -110,67 +110,82 @@ impl Diagnostic {
     }
 }
 
-pub fn emit_diagnostics(diagnostics: &[Diagnostic], source: &str) {
-    for diagnostic in diagnostics {
-        let color = match diagnostic.severity {
-            DiagnosticSeverity::Error => Color::Red,
-            DiagnosticSeverity::Warning => Color::Yellow,
-            DiagnosticSeverity::Info => Color::Blue,
-            DiagnosticSeverity::Hint => Color::Cyan,
-        };
-
-        let span: std::ops::Range<usize> = diagnostic.span().into();
-        let mut report = Report::build(
-            diagnostic.report_kind(),
-            diagnostic.source_id().to_string(),
-            span.start,
-        )
-        .with_message(diagnostic.message());
-
-        // Only add a label if there is specific label text, or if we want to point to the span
-        // without repeating the main error message.
-        if let Some(label_text) = diagnostic.label() {
-            report = report.with_label(
-                Label::new((diagnostic.source_id().to_string(), span.clone()))
-                    .with_message(label_text)
-                    .with_color(color),
-            );
-        } else {
-            // Just highlight the span without text if no specific label is provided
-            // to avoid duplicating the main message
-            report = report.with_label(
-                Label::new((diagnostic.source_id().to_string(), span.clone())).with_color(color),
-            );
-        }
-
-        // Add suggestion if available
-        if let Some(suggestion) = diagnostic.suggestion() {
-            // TODO: We could use `ariadne::Config` to make this fancier, but for now
-            // just improving the text format.
-            report = report.with_note(format!("Suggestion: {}", suggestion));
+fn build_report(diagnostic: &Diagnostic) -> Report<'static, (String, std::ops::Range<usize>)> {
+    let color = match diagnostic.severity {
+        DiagnosticSeverity::Error => Color::Red,
+        DiagnosticSeverity::Warning => Color::Yellow,
+        DiagnosticSeverity::Info => Color::Blue,
+        DiagnosticSeverity::Hint => Color::Cyan,
+    };
+
+    let span: std::ops::Range<usize> = diagnostic.span().into();
+    let mut report = Report::build(
+        diagnostic.report_kind(),
+        diagnostic.source_id().to_string(),
+        span.start,
+    )
+    .with_message(diagnostic.message());
+
+    // Only add a label if there is specific label text, or if we want to point to the span
+    // without repeating the main error message.
+    if let Some(label_text) = diagnostic.label() {
+        report = report.with_label(
+            Label::new((diagnostic.source_id().to_string(), span.clone()))
+                .with_message(label_text)
+                .with_color(color),
+        );
+    } else {
+        // Just highlight the span without text if no specific label is provided
+        // to avoid duplicating the main message
+        report = report.with_label(
+            Label::new((diagnostic.source_id().to_string(), span.clone())).with_color(color),
+        );
+    }
+
+    // Add suggestion if available
+    if let Some(suggestion) = diagnostic.suggestion() {
+        // TODO: We could use `ariadne::Config` to make this fancier, but for now
+        // just improving the text format.
+        report = report.with_note(format!("Suggestion: {}", suggestion));
+    }
+
+    // Add help text if available
+    if let Some(help) = diagnostic.help() {
+        report = report.with_note(help);
+    } else {
+        // Only show generic help for errors/warnings
+        if matches!(
+            diagnostic.severity,
+            DiagnosticSeverity::Error | DiagnosticSeverity::Warning
+        ) {
+            report = report
+                .with_note("For more information, re-run with --debug to inspect tokens and AST.");
         }
+    }
 
-        // Add help text if available
-        if let Some(help) = diagnostic.help() {
-            report = report.with_note(help);
-        } else {
-            // Only show generic help for errors/warnings
-            if matches!(
-                diagnostic.severity,
-                DiagnosticSeverity::Error | DiagnosticSeverity::Warning
-            ) {
-                report = report.with_note(
-                    "For more information, re-run with --debug to inspect tokens and AST.",
-                );
-            }
-        }
+    report.finish()
+}
 
-        let _ = report
-            .finish()
+pub fn emit_diagnostics(diagnostics: &[Diagnostic], source: &str) {
+    for diagnostic in diagnostics {
+        let _ = build_report(diagnostic)
             .print((diagnostic.source_id().to_string(), Source::from(source)));
     }
 }
 
+/// Renders diagnostics the way [`emit_diagnostics`] prints them, for output
+/// that is shown by another process
+pub fn render_diagnostics(diagnostics: &[Diagnostic], source: &str) -> String {
+    let mut output = Vec::new();
+    for diagnostic in diagnostics {
+        let _ = build_report(diagnostic).write(
+            (diagnostic.source_id().to_string(), Source::from(source)),
+            &mut output,
+        );
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
 /// Emit a single diagnostic
 pub fn emit_diagnostic(diagnostic: &Diagnostic, source: &str) {
     emit_diagnostics(std::slice::from_ref(diagnostic), source);
@@ -0,0 +1,423 @@
+//! Format specifiers of f-string interpolations
+//!
+//! `f"{price:>10.2f}"` formats `price` with the specifier after the colon. The
+//! grammar is a subset of Python's,
+//! `[[fill]align][sign][#][0][width][grouping][.precision][type]`: align is
+//! `<`, `>` or `^`, sign is `+`, `-` or a space, grouping is `,` or `_`, and
+//! type is one of `b o x X d e E f F g G % s`. The type checker validates a
+//! specifier against the value with [`FormatSpec::parse`] and
+//! [`FormatSpec::check`], and the runtime formats with the same parser, so a
+//! specifier that checks formats the way it was checked.
+
+/// Integer presentation types
+const INTEGER_KINDS: &[char] = &['b', 'o', 'x', 'X', 'd'];
+/// Presentation types that format any number as a float
+const FLOAT_KINDS: &[char] = &['e', 'E', 'f', 'F', 'g', 'G', '%'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// What kind of value a specifier is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatArg {
+    Int,
+    Float,
+    /// Strings and anything formatted through its string form
+    Str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatSpecError {
+    /// Char offset of the problem in the specifier
+    pub at: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatSpec {
+    pub fill: Option<char>,
+    pub align: Option<Align>,
+    /// `+` or a space; `-`, the default, is not kept
+    pub sign: Option<char>,
+    /// `#`: prefix binary, octal and hex with `0b`, `0o` and `0x`
+    pub alternate: bool,
+    /// `0`: pad numbers with zeros after the sign
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub grouping: Option<char>,
+    pub precision: Option<usize>,
+    pub kind: Option<char>,
+}
+
+impl FormatSpec {
+    pub fn parse(spec: &str) -> Result<Self, FormatSpecError> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut parsed = FormatSpec::default();
+        let mut at = 0;
+
+        let align_of = |ch: char| match ch {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        if let Some(align) = chars.get(1).copied().and_then(align_of) {
+            parsed.fill = Some(chars[0]);
+            parsed.align = Some(align);
+            at = 2;
+        } else if let Some(align) = chars.first().copied().and_then(align_of) {
+            parsed.align = Some(align);
+            at = 1;
+        }
+        if let Some(&sign @ ('+' | '-' | ' ')) = chars.get(at) {
+            parsed.sign = (sign != '-').then_some(sign);
+            at += 1;
+        }
+        if chars.get(at) == Some(&'#') {
+            parsed.alternate = true;
+            at += 1;
+        }
+        if chars.get(at) == Some(&'0') {
+            parsed.zero = true;
+            at += 1;
+        }
+        parsed.width = number(&chars, &mut at)?;
+        if let Some(&grouping @ (',' | '_')) = chars.get(at) {
+            parsed.grouping = Some(grouping);
+            at += 1;
+        }
+        if chars.get(at) == Some(&'.') {
+            at += 1;
+            parsed.precision = Some(number(&chars, &mut at)?.ok_or_else(|| FormatSpecError {
+                at,
+                message: "expected digits after `.`".to_string(),
+            })?);
+        }
+        if let Some(&kind) = chars.get(at) {
+            if kind != 's' && !INTEGER_KINDS.contains(&kind) && !FLOAT_KINDS.contains(&kind) {
+                return Err(FormatSpecError {
+                    at,
+                    message: format!("unknown format type `{kind}`"),
+                });
+            }
+            parsed.kind = Some(kind);
+            at += 1;
+        }
+        if let Some(&extra) = chars.get(at) {
+            return Err(FormatSpecError {
+                at,
+                message: format!("unexpected `{extra}` after the format type"),
+            });
+        }
+        Ok(parsed)
+    }
+
+    /// Whether the specifier can format `arg`, with the reason if not
+    pub fn check(&self, arg: FormatArg) -> Result<(), String> {
+        let integer_kind = self.kind.is_some_and(|kind| INTEGER_KINDS.contains(&kind));
+        let float_kind = self.kind.is_some_and(|kind| FLOAT_KINDS.contains(&kind));
+        match (self.kind, arg) {
+            (Some(kind), FormatArg::Float | FormatArg::Str) if integer_kind => {
+                return Err(format!("`{kind}` formats integers"));
+            }
+            (Some(kind), FormatArg::Str) if float_kind => {
+                return Err(format!("`{kind}` formats numbers"));
+            }
+            (Some('s'), FormatArg::Int | FormatArg::Float) => {
+                return Err("`s` formats strings".to_string());
+            }
+            _ => {}
+        }
+        if self.precision.is_some() && (integer_kind || (arg == FormatArg::Int && !float_kind)) {
+            return Err("integers take no precision".to_string());
+        }
+        if self.alternate && !matches!(self.kind, Some('b' | 'o' | 'x' | 'X')) {
+            return Err("`#` applies to `b`, `o`, `x` and `X`".to_string());
+        }
+        if arg == FormatArg::Str {
+            if self.sign.is_some() {
+                return Err("strings take no sign".to_string());
+            }
+            if self.zero {
+                return Err("strings cannot be padded with zeros".to_string());
+            }
+        }
+        if self.grouping.is_some()
+            && (arg == FormatArg::Str || matches!(self.kind, Some('b' | 'o' | 'x' | 'X')))
+        {
+            return Err("digit grouping applies to decimal numbers".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn format_int(&self, value: i64) -> String {
+        if self.kind.is_some_and(|kind| FLOAT_KINDS.contains(&kind)) {
+            return self.format_float(value as f64);
+        }
+        self.format_integer(value < 0, value.unsigned_abs())
+    }
+
+    pub fn format_uint(&self, value: u64) -> String {
+        if self.kind.is_some_and(|kind| FLOAT_KINDS.contains(&kind)) {
+            return self.format_float(value as f64);
+        }
+        self.format_integer(false, value)
+    }
+
+    fn format_integer(&self, negative: bool, magnitude: u64) -> String {
+        let (prefix, digits) = match self.kind {
+            Some('b') => ("0b", format!("{magnitude:b}")),
+            Some('o') => ("0o", format!("{magnitude:o}")),
+            Some('x') => ("0x", format!("{magnitude:x}")),
+            Some('X') => ("0X", format!("{magnitude:X}")),
+            _ => ("", self.group(&magnitude.to_string())),
+        };
+        let mut lead = self.sign_of(negative).to_string();
+        if self.alternate {
+            lead.push_str(prefix);
+        }
+        self.pad_number(&lead, &digits)
+    }
+
+    pub fn format_float(&self, value: f64) -> String {
+        let negative = value.is_sign_negative() && !value.is_nan();
+        let magnitude = value.abs();
+        let upper = matches!(self.kind, Some('E' | 'F' | 'G'));
+        if !magnitude.is_finite() {
+            let text = match (magnitude.is_nan(), upper) {
+                (true, false) => "nan",
+                (true, true) => "NAN",
+                (false, false) => "inf",
+                (false, true) => "INF",
+            };
+            // Zero padding would read as a number
+            return self.pad(format!("{}{text}", self.sign_of(negative)), Align::Right);
+        }
+        let body = match (self.kind, self.precision) {
+            (Some('f' | 'F'), precision) => {
+                self.group_fixed(format!("{:.*}", precision.unwrap_or(6), magnitude))
+            }
+            (Some('e' | 'E'), precision) => {
+                exponent(magnitude, precision.unwrap_or(6), self.kind == Some('E'))
+            }
+            (Some('%'), precision) => format!(
+                "{}%",
+                self.group_fixed(format!("{:.*}", precision.unwrap_or(6), magnitude * 100.0))
+            ),
+            (Some('g' | 'G'), precision) => self.general(magnitude, precision.unwrap_or(6)),
+            (_, Some(precision)) => self.general(magnitude, precision),
+            // Like an interpolation without a specifier
+            (_, None) => self.group_fixed(
+                format!("{magnitude:.9}")
+                    .trim_end_matches('0')
+                    .trim_end_matches('.')
+                    .to_string(),
+            ),
+        };
+        self.pad_number(self.sign_of(negative), &body)
+    }
+
+    pub fn format_str(&self, value: &str) -> String {
+        let text: String = match self.precision {
+            Some(precision) => value.chars().take(precision).collect(),
+            None => value.to_string(),
+        };
+        self.pad(text, Align::Left)
+    }
+
+    fn sign_of(&self, negative: bool) -> &'static str {
+        match (negative, self.sign) {
+            (true, _) => "-",
+            (false, Some('+')) => "+",
+            (false, Some(' ')) => " ",
+            _ => "",
+        }
+    }
+
+    /// `%g`: fixed notation for moderate exponents, scientific otherwise,
+    /// with `precision` significant digits and trailing zeros removed
+    fn general(&self, magnitude: f64, precision: usize) -> String {
+        let precision = precision.max(1);
+        let upper = self.kind == Some('G');
+        if magnitude == 0.0 {
+            return "0".to_string();
+        }
+        // The exponent after rounding to `precision` digits
+        let rounded = format!("{:.*e}", precision - 1, magnitude);
+        let exp: i64 = rounded
+            .rsplit_once('e')
+            .and_then(|(_, exp)| exp.parse().ok())
+            .unwrap_or(0);
+        if exp < -4 || exp >= precision as i64 {
+            let text = exponent(magnitude, precision - 1, upper);
+            match text.split_once(['e', 'E']) {
+                Some((mantissa, exp)) => {
+                    format!(
+                        "{}{}{exp}",
+                        trim_fraction(mantissa),
+                        if upper { 'E' } else { 'e' }
+                    )
+                }
+                None => text,
+            }
+        } else {
+            let decimals = (precision as i64 - 1 - exp).max(0) as usize;
+            self.group_fixed(trim_fraction(&format!("{magnitude:.decimals$}")).to_string())
+        }
+    }
+
+    /// Groups the integer part of a number written in fixed notation
+    fn group_fixed(&self, text: String) -> String {
+        match text.split_once('.') {
+            Some((whole, fraction)) => format!("{}.{fraction}", self.group(whole)),
+            None => self.group(&text),
+        }
+    }
+
+    fn group(&self, digits: &str) -> String {
+        let Some(separator) = self.grouping else {
+            return digits.to_string();
+        };
+        let mut grouped = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    fn pad_number(&self, lead: &str, digits: &str) -> String {
+        let width = self.width.unwrap_or(0);
+        let len = lead.chars().count() + digits.chars().count();
+        if self.zero && self.align.is_none() && len < width {
+            return format!("{lead}{}{digits}", "0".repeat(width - len));
+        }
+        self.pad(format!("{lead}{digits}"), Align::Right)
+    }
+
+    fn pad(&self, text: String, default: Align) -> String {
+        let len = text.chars().count();
+        let Some(padding) = self.width.and_then(|width| width.checked_sub(len)) else {
+            return text;
+        };
+        let (before, after) = match self.align.unwrap_or(default) {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = self.fill.unwrap_or(' ');
+        let mut padded = String::with_capacity(text.len() + padding);
+        padded.extend(std::iter::repeat_n(fill, before));
+        padded.push_str(&text);
+        padded.extend(std::iter::repeat_n(fill, after));
+        padded
+    }
+}
+
+fn number(chars: &[char], at: &mut usize) -> Result<Option<usize>, FormatSpecError> {
+    let start = *at;
+    let digits: String = chars[start..]
+        .iter()
+        .take_while(|ch| ch.is_ascii_digit())
+        .collect();
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    *at += digits.len();
+    digits.parse().map(Some).map_err(|_| FormatSpecError {
+        at: start,
+        message: format!("`{digits}` is too large"),
+    })
+}
+
+/// Scientific notation with a signed exponent of at least two digits, like
+/// `1.50e+03`
+fn exponent(magnitude: f64, precision: usize, upper: bool) -> String {
+    let text = format!("{magnitude:.precision$e}");
+    let Some((mantissa, exp)) = text.split_once('e') else {
+        return text;
+    };
+    let (sign, digits) = match exp.strip_prefix('-') {
+        Some(digits) => ('-', digits),
+        None => ('+', exp),
+    };
+    let marker = if upper { 'E' } else { 'e' };
+    format!("{mantissa}{marker}{sign}{digits:0>2}")
+}
+
+fn trim_fraction(text: &str) -> &str {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(text: &str) -> FormatSpec {
+        FormatSpec::parse(text).expect("valid format spec")
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let parsed = spec("*^+#012,.3f");
+        assert_eq!(parsed.fill, Some('*'));
+        assert_eq!(parsed.align, Some(Align::Center));
+        assert_eq!(parsed.sign, Some('+'));
+        assert!(parsed.alternate && parsed.zero);
+        assert_eq!(parsed.width, Some(12));
+        assert_eq!(parsed.grouping, Some(','));
+        assert_eq!(parsed.precision, Some(3));
+        assert_eq!(parsed.kind, Some('f'));
+
+        let error = FormatSpec::parse(".2q").unwrap_err();
+        assert_eq!(error.at, 2);
+        assert_eq!(error.message, "unknown format type `q`");
+        assert_eq!(FormatSpec::parse(".f").unwrap_err().at, 1);
+        assert_eq!(FormatSpec::parse("dx").unwrap_err().at, 1);
+    }
+
+    #[test]
+    fn checks_the_value_a_specifier_applies_to() {
+        assert!(spec(".2f").check(FormatArg::Int).is_ok());
+        assert_eq!(
+            spec(".2f").check(FormatArg::Str),
+            Err("`f` formats numbers".to_string())
+        );
+        assert_eq!(
+            spec("x").check(FormatArg::Float),
+            Err("`x` formats integers".to_string())
+        );
+        assert_eq!(
+            spec(".2").check(FormatArg::Int),
+            Err("integers take no precision".to_string())
+        );
+        assert!(spec(">10.3").check(FormatArg::Str).is_ok());
+        assert!(spec("+").check(FormatArg::Str).is_err());
+    }
+
+    #[test]
+    fn formats_numbers_and_strings() {
+        assert_eq!(spec(".2f").format_float(3.14159), "3.14");
+        assert_eq!(spec("+08.3f").format_float(-2.5), "-002.500");
+        assert_eq!(spec(",").format_int(1234567), "1,234,567");
+        assert_eq!(spec("#x").format_int(255), "0xff");
+        assert_eq!(spec("08b").format_int(5), "00000101");
+        assert_eq!(spec(".1%").format_float(0.256), "25.6%");
+        assert_eq!(spec(".2e").format_float(1500.0), "1.50e+03");
+        assert_eq!(spec("g").format_float(0.00001), "1e-05");
+        assert_eq!(spec(".3").format_float(2.0 / 3.0), "0.667");
+        assert_eq!(spec("*^7").format_str("otter"), "*otter*");
+        assert_eq!(spec("<4").format_int(7), "7   ");
+        assert_eq!(spec(".3").format_str("otters"), "ott");
+    }
+}
@@ -1,5 +1,7 @@
+pub mod annotations;
 pub mod bench;
 pub mod errors;
+pub mod format_spec;
 pub mod logger;
 pub mod profiler;
 pub mod suggest;
@@ -29,8 +29,25 @@ mod tests {
     fn run_command_parses_path_argument() {
         let cli = OtterCli::parse_from(["otter", "run", "tests/demo.ot"]); // no filesystem access
         match cli.command() {
-            Command::Run { path } => assert_eq!(path.to_string_lossy(), "tests/demo.ot"),
+            Command::Run {
+                path: Some(path),
+                code: None,
+            } => assert_eq!(path.to_string_lossy(), "tests/demo.ot"),
             other => panic!("expected run command, got {other:?}"),
         }
     }
+
+    #[test]
+    fn run_command_takes_inline_code() {
+        let cli = OtterCli::parse_from(["otter", "run", "-c", "print(1 + 1)"]);
+        match cli.command() {
+            Command::Run {
+                path: None,
+                code: Some(code),
+            } => assert_eq!(code, "print(1 + 1)"),
+            other => panic!("expected run command, got {other:?}"),
+        }
+        assert!(OtterCli::try_parse_from(["otter", "run"]).is_err());
+        assert!(OtterCli::try_parse_from(["otter", "run", "a.ot", "-c", "pass"]).is_err());
+    }
 }
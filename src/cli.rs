@@ -4,7 +4,9 @@
     reason = "TODO: Use robust logging"
 )]
 
+use std::ffi::OsString;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
 use std::time::Duration;
@@ -16,20 +18,30 @@ use tracing::{debug, info, warn};
 
 const TASK_RUNTIME_ENABLED: bool = cfg!(feature = "task-runtime");
 
-use otterc_cache::{CacheBuildOptions, CacheEntry, CacheManager, CacheMetadata, CompilationInputs};
-use otterc_codegen::{BuildArtifact, build_executable};
-use otterc_config::{CodegenOptLevel, CodegenOptions, LanguageFeatureFlags, TargetTriple, VERSION};
-use otterc_ffi::{BridgeSymbolRegistry, FunctionSpec, TypeSpec};
-use otterc_lexer::{LexerError, tokenize};
-use otterc_module::ModuleProcessor;
-use otterc_parser::{ParserError, parse};
+use crate::session::{CompilerSession, Phase};
+use crate::toolchain;
+use crate::tools::profiler::{ProfileCommand, render_report};
+use otterc_cache::{
+    CacheBuildOptions, CacheEntry, CacheManager, CacheMetadata, CompilationInputs, SharedCache,
+};
+use otterc_codegen::BuildArtifact;
+use otterc_config::{
+    CfgContext, CodegenOptLevel, CodegenOptions, EmitKind, LanguageFeatureFlags, LtoMode, PgoMode,
+    Sanitizer, TargetTriple, VERSION,
+};
+use otterc_parser::parse;
+use otterc_runtime::limits::{ResourceLimits, parse_duration, parse_size};
 use otterc_runtime::memory::config::GcStrategy;
+use otterc_runtime::profile::RunProfile;
+use otterc_runtime::task::{CpuAffinity, StealPolicy};
+use otterc_span::SourceLines;
 use otterc_symbol::registry::SymbolRegistry;
-use otterc_typecheck::TypeChecker;
-use otterc_utils::errors::{Diagnostic, emit_diagnostics};
+use otterc_utils::annotations::{
+    Annotation, OutputFormat, annotate_diagnostics, render_github, render_sarif,
+};
+use otterc_utils::errors::{Diagnostic, render_diagnostics};
 use otterc_utils::logger;
 use otterc_utils::profiler::{PhaseTiming, Profiler};
-use std::collections::{HashMap, HashSet};
 
 #[derive(Parser, Debug)]
 #[command(name = "otter", version = VERSION, about = "OtterLang compiler")]
@@ -46,6 +58,12 @@ pub struct OtterCli {
     /// Dump the generated LLVM IR.
     dump_ir: bool,
 
+    #[arg(long, global = true, value_name = "kind", value_delimiter = ',')]
+    /// Extra compiler output: `llvm-ir`, `asm` and `obj` write the optimized IR,
+    /// assembly and object file next to the binary, `opt-report` lists what was
+    /// moved out of loops.
+    emit: Vec<String>,
+
     #[arg(long, global = true)]
     /// Display phase timing information.
     time: bool,
@@ -55,9 +73,21 @@ pub struct OtterCli {
     profile: bool,
 
     #[arg(long, global = true)]
-    /// Enable release mode (O3 + LTO) when building binaries.
+    /// Enable release mode (O3 + thin LTO) when building binaries.
     release: bool,
 
+    #[arg(long, global = true, value_name = "off|thin|full")]
+    /// Link-time optimization across the program and its runtime (needs clang and lld)
+    lto: Option<String>,
+
+    #[arg(short = 'O', long, global = true, value_name = "0|1|2|3|z")]
+    /// Optimization level (defaults to 2, or 3 with --release); z optimizes for size
+    opt_level: Option<String>,
+
+    #[arg(long, global = true, value_name = "pipeline")]
+    /// LLVM pass pipeline to run instead of the level's, e.g. `function(instcombine,gvn)`
+    passes: Option<String>,
+
     #[arg(long, global = true)]
     /// Enable the experimental async task runtime when executing programs.
     tasks: bool,
@@ -78,10 +108,26 @@ pub struct OtterCli {
     /// Disable cache for this compilation.
     no_cache: bool,
 
+    #[arg(long, global = true)]
+    /// Compile `assert` statements out of the program.
+    release_asserts_off: bool,
+
+    #[arg(long, global = true)]
+    /// Let long-running loops in tasks hand queued tasks to another thread.
+    preempt_loops: bool,
+
+    #[arg(long, global = true)]
+    /// Compile in this process even when an `otter daemon` is running.
+    no_daemon: bool,
+
     #[arg(long, global = true, value_name = "list")]
     /// Enable experimental language features (comma-separated names or use OTTER_FEATURES env var).
     features: Option<String>,
 
+    #[arg(long, global = true, value_name = "list")]
+    /// Enable `@cfg(feature="...")` items (comma-separated names).
+    cfg_features: Option<String>,
+
     #[arg(long, global = true)]
     /// Target triple for cross-compilation (e.g., wasm32-unknown-unknown, thumbv7m-none-eabi)
     target: Option<String>,
@@ -102,6 +148,51 @@ pub struct OtterCli {
     /// Limit the number of bytes that may be allocated while GC is disabled
     gc_disabled_max_bytes: Option<usize>,
 
+    #[arg(long, global = true)]
+    /// Print every GC pause (duration, bytes reclaimed, heap size) to stderr
+    gc_log: bool,
+
+    #[arg(long, global = true, value_name = "duration")]
+    /// Log the live heap by type and GC generation to stderr at this interval (e.g. 5s)
+    heap_stats_interval: Option<String>,
+
+    #[arg(long, global = true)]
+    /// Disable filesystem, network, process and FFI plugin access for the program
+    sandbox: bool,
+
+    #[arg(long, global = true, value_name = "size")]
+    /// Cap the GC heap of the running program (e.g. 512M, 2G)
+    max_heap: Option<String>,
+
+    #[arg(long, global = true, value_name = "seconds")]
+    /// Cap the CPU time of the running program
+    max_cpu_seconds: Option<f64>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "path",
+        conflicts_with = "replay_schedule"
+    )]
+    /// Record task scheduling and channel operations of the running program to a file
+    record_schedule: Option<PathBuf>,
+
+    #[arg(long, global = true, value_name = "path")]
+    /// Replay a schedule written by --record-schedule
+    replay_schedule: Option<PathBuf>,
+
+    #[arg(long, global = true, value_name = "off|pin")]
+    /// Pin the running program's task workers to CPU cores
+    cpu_affinity: Option<String>,
+
+    #[arg(long, global = true, value_name = "any|numa")]
+    /// Order in which idle task workers steal work; `numa` prefers the same NUMA node
+    steal_policy: Option<String>,
+
+    #[arg(long, global = true, value_name = "human|github|sarif")]
+    /// Report diagnostics as GitHub Actions annotations or a SARIF log instead of for a terminal
+    output_format: Option<String>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -115,18 +206,48 @@ impl OtterCli {
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Lexes, parses, and executes the specified source file via the cached native pipeline.
+    /// Pass `-` to read the program from stdin, or `-c` to give it on the command line.
     #[command(alias = "r")]
-    Run { path: PathBuf },
+    Run {
+        #[arg(required_unless_present = "code")]
+        path: Option<PathBuf>,
+        #[arg(short = 'c', long, value_name = "program", conflicts_with = "path")]
+        /// Run this program instead of a file, e.g. `otter run -c 'print(1 + 1)'`
+        code: Option<String>,
+    },
     /// Builds a native executable from the specified source file.
     #[command(alias = "b")]
     Build {
         path: PathBuf,
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// Emit DWARF debug info so gdb and lldb show `.ot` source lines and locals
+        #[arg(short = 'g', long)]
+        debug_info: bool,
+        /// Instrument the executable to write a profile of each run to `<output>.pgo`
+        #[arg(long, conflicts_with = "pgo_use")]
+        pgo_gen: bool,
+        /// Optimize with the profiles a `--pgo-gen` build wrote, from `<output>.pgo` or
+        /// the given directory, `.profraw` or `.profdata` file
+        #[arg(long, value_name = "path", num_args = 0..=1, default_missing_value = "")]
+        pgo_use: Option<PathBuf>,
+        /// Instrument the executable with sanitizers and link their runtimes
+        #[arg(long, value_name = "address,undefined", value_delimiter = ',')]
+        sanitize: Vec<String>,
     },
     /// Checks the source file for errors without generating code.
     #[command(alias = "c")]
     Check { path: PathBuf },
+    /// Keep a compiler running in the background for fast repeated builds.
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+    },
+    /// Explore Rust crates available through `use rust:<crate>`.
+    Ffi {
+        #[command(subcommand)]
+        action: FfiAction,
+    },
     /// Format OtterLang source code.
     Fmt {
         /// Files to format (defaults to all .ot files in current directory)
@@ -136,7 +257,7 @@ pub enum Command {
     /// Profile OtterLang programs (memory or performance)
     Profile {
         #[command(subcommand)]
-        subcommand: crate::tools::profiler::ProfileCommand,
+        subcommand: ProfileCommand,
     },
     /// Run tests in OtterLang source files
     #[command(alias = "t")]
@@ -154,20 +275,139 @@ pub enum Command {
         #[arg(long)]
         update_snapshots: bool,
     },
+    /// Run programs under every backend and compare their output with each
+    /// other and with `<program>.expected`
+    SelftestBackends {
+        /// Programs or directories of programs (defaults to `examples`)
+        #[arg(default_value = "examples")]
+        paths: Vec<PathBuf>,
+        /// Backends to run, of `llvm` and `llvm-release` (defaults to all)
+        #[arg(long, value_delimiter = ',')]
+        backends: Vec<String>,
+        /// Write `<program>.expected` from the runs instead of comparing with it
+        #[arg(long)]
+        bless: bool,
+        /// Seconds a program may run before it is stopped
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Replace the installed compiler with the newest release, or the given version.
+    Upgrade {
+        /// Version to install, such as `0.2.1`
+        version: Option<String>,
+    },
+    /// Manage the compilers an `otter-toolchain` file can pin.
+    Toolchain {
+        #[command(subcommand)]
+        action: ToolchainAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonAction {
+    /// Serve `run`, `build` and `check` from the current directory (the default).
+    Start,
+    /// Show whether a daemon serves the current directory.
+    Status,
+    /// Stop the daemon serving the current directory.
+    Stop,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ToolchainAction {
+    /// Install a compiler next to the others, by default the one the project pins.
+    Install {
+        /// Version to install, such as `0.2.1`
+        version: Option<String>,
+    },
+    /// List the installed compilers and the one the project pins.
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FfiAction {
+    /// Print the functions, structs and methods of a crate as Otter sees them.
+    Inspect {
+        /// Crate to inspect, as written after `use rust:`
+        crate_name: String,
+        /// Crate version (defaults to the one in its bridge.yaml, or the latest)
+        #[arg(long)]
+        version: Option<String>,
+        /// Read an existing rustdoc JSON file instead of running rustdoc
+        #[arg(long, value_name = "path")]
+        rustdoc_json: Option<PathBuf>,
+    },
+    /// Build a crate's bridge and copy it, with its crate spec, to a directory
+    /// that `OTTER_BRIDGE_PATH` can point at.
+    Prebuild {
+        /// Crate to build, as written after `use rust:`
+        crate_name: String,
+        /// Directory to write `<arch>-<os>/<crate>-<hash>/` into
+        #[arg(long, short, value_name = "dir", default_value = "bridges")]
+        out: PathBuf,
+    },
 }
 
 pub fn run() -> Result<()> {
     logger::init_logging();
+    // The pinned compiler parses the command line, which may use its flags
+    if let Some(status) = toolchain::delegate_to_pinned()? {
+        #[expect(clippy::exit, reason = "The pinned compiler's exit code is ours")]
+        std::process::exit(status.code().unwrap_or(1));
+    }
     maybe_auto_update()?;
     otterc_ffi::bootstrap_stdlib();
-    let cli = OtterCli::parse();
+    let cli = OtterCli::parse_from(script_invocation(std::env::args_os()));
+    if !matches!(
+        cli.command,
+        Command::Upgrade { .. } | Command::Toolchain { .. }
+    ) {
+        toolchain::check_language_version(&std::env::current_dir()?)?;
+    }
     enforce_task_runtime_flags(&cli)?;
+    if cli.sandbox {
+        SymbolRegistry::global().enable_sandbox();
+    }
 
     match &cli.command {
-        Command::Run { path } => handle_run(&cli, path),
-        Command::Build { path, output } => handle_build(&cli, path, output.clone()),
+        Command::Run { path, code } => handle_run(&cli, path.as_deref(), code.as_deref()),
+        Command::Build {
+            path,
+            output,
+            debug_info,
+            pgo_gen,
+            pgo_use,
+            sanitize,
+        } => handle_build(
+            &cli,
+            path,
+            output.clone(),
+            *debug_info,
+            *pgo_gen,
+            pgo_use.clone(),
+            sanitize,
+        ),
         Command::Check { path } => handle_check(&cli, path),
+        Command::Daemon { action } => match action.as_ref().unwrap_or(&DaemonAction::Start) {
+            DaemonAction::Start => crate::daemon::serve(&std::env::current_dir()?),
+            DaemonAction::Status => crate::daemon::status(),
+            DaemonAction::Stop => crate::daemon::stop(),
+        },
+        Command::Ffi {
+            action:
+                FfiAction::Inspect {
+                    crate_name,
+                    version,
+                    rustdoc_json,
+                },
+        } => handle_ffi_inspect(crate_name, version.clone(), rustdoc_json.as_deref()),
+        Command::Ffi {
+            action: FfiAction::Prebuild { crate_name, out },
+        } => handle_ffi_prebuild(crate_name, out),
         Command::Fmt { paths } => handle_fmt(paths),
+        Command::Profile {
+            subcommand: ProfileCommand::Report { program, output },
+        } => handle_profile_report(&cli, program, output.clone()),
         Command::Profile { subcommand } => {
             crate::tools::profiler::run_profiler_subcommand(subcommand)
         }
@@ -177,9 +417,37 @@ pub fn run() -> Result<()> {
             verbose,
             update_snapshots,
         } => handle_test(&cli, paths, *parallel, *verbose, *update_snapshots),
+        Command::SelftestBackends {
+            paths,
+            backends,
+            bless,
+            timeout,
+        } => handle_selftest_backends(&cli, paths, backends, *bless, Duration::from_secs(*timeout)),
+        Command::Upgrade { version } => toolchain::upgrade(version.as_deref()),
+        Command::Toolchain {
+            action: ToolchainAction::Install { version },
+        } => toolchain::install(version.as_deref()),
+        Command::Toolchain {
+            action: ToolchainAction::List,
+        } => toolchain::list(),
     }
 }
 
+/// Treats `otter script.ot` as `otter run script.ot`, which is how a script
+/// starting with `#!/usr/bin/env otter` gets invoked
+pub(crate) fn script_invocation<T: Into<OsString>>(
+    args: impl IntoIterator<Item = T>,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if args
+        .get(1)
+        .is_some_and(|arg| Path::new(arg).extension().is_some_and(|ext| ext == "ot"))
+    {
+        args.insert(1, OsString::from("run"));
+    }
+    args
+}
+
 fn enforce_task_runtime_flags(cli: &OtterCli) -> Result<()> {
     if TASK_RUNTIME_ENABLED {
         return Ok(());
@@ -263,10 +531,32 @@ fn maybe_auto_update() -> Result<()> {
     Ok(())
 }
 
-fn handle_run(cli: &OtterCli, path: &Path) -> Result<()> {
-    let settings = CompilationSettings::from_cli(cli)?;
-    let source = read_source(path)?;
-    let stage = compile_pipeline(path, &source, &settings)?;
+fn handle_run(cli: &OtterCli, path: Option<&Path>, code: Option<&str>) -> Result<()> {
+    let mut settings = CompilationSettings::from_cli(cli)?;
+    let inline = match (path, code) {
+        (_, Some(code)) => Some(("<inline>", code.to_string())),
+        (Some(path), None) if path == Path::new("-") => {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .context("failed to read program from stdin")?;
+            Some(("<stdin>", source))
+        }
+        _ => None,
+    };
+    let stage = if let Some((name, source)) = &inline {
+        // Imports resolve against the current directory. The cache is keyed
+        // by file contents, which a program without a file does not have.
+        settings.no_cache = true;
+        compile_pipeline(
+            &std::env::current_dir()?.join(name),
+            &inline_program(source),
+            &settings,
+        )?
+    } else {
+        let path = path.ok_or_else(|| anyhow!("no program to run"))?;
+        compile(cli, path, &settings)?
+    };
 
     match &stage.result {
         CompilationResult::CacheHit(entry) => {
@@ -281,7 +571,10 @@ fn handle_run(cli: &OtterCli, path: &Path) -> Result<()> {
             execute_binary(&entry.binary_path, &settings)?;
         }
         CompilationResult::Compiled { artifact, metadata } => {
-            println!("{} {}", "Building".blue().bold(), artifact.binary.display());
+            // Only the program writes to stdout when it is part of a pipeline
+            if inline.is_none() {
+                println!("{} {}", "Building".blue().bold(), artifact.binary.display());
+            }
             execute_binary(&artifact.binary, &settings)?;
             if settings.dump_ir
                 && let Some(ir) = &artifact.ir
@@ -289,6 +582,12 @@ fn handle_run(cli: &OtterCli, path: &Path) -> Result<()> {
                 println!("\n{}", "== LLVM IR ==".bold());
                 println!("{ir}");
             }
+            if let Some(report) = &artifact.opt_report {
+                print_opt_report(report);
+            }
+            for path in &artifact.emitted {
+                println!("{} {}", "Emitted".green().bold(), path.display());
+            }
             if settings.profile {
                 print_profile(metadata);
             }
@@ -303,10 +602,69 @@ fn handle_run(cli: &OtterCli, path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn handle_build(cli: &OtterCli, path: &Path, output: Option<PathBuf>) -> Result<()> {
-    let settings = CompilationSettings::from_cli(cli)?;
-    let source = read_source(path)?;
-    let stage = compile_pipeline(path, &source, &settings)?;
+/// Top-level lines that start a declaration rather than a statement
+const DECLARATION_KEYWORDS: &[&str] = &[
+    "use ",
+    "from ",
+    "pub ",
+    "fn ",
+    "struct ",
+    "enum ",
+    "type ",
+    "const ",
+    "protocol ",
+    "@",
+];
+
+/// Source of a program given on the command line or stdin. Unless it defines
+/// `main`, the statements outside its declarations become the body of one,
+/// so `otter run -c 'print(1 + 1)'` runs as written.
+fn inline_program(code: &str) -> String {
+    let mut declarations = String::new();
+    let mut body = String::new();
+    let mut in_declaration = false;
+    for line in code.lines() {
+        if !line.is_empty() && !line.starts_with([' ', '\t']) {
+            if line.starts_with("fn main(") || line.starts_with("pub fn main(") {
+                return code.to_string();
+            }
+            in_declaration = DECLARATION_KEYWORDS
+                .iter()
+                .any(|keyword| line.starts_with(keyword));
+        }
+        let target = if in_declaration {
+            &mut declarations
+        } else {
+            body.push_str("    ");
+            &mut body
+        };
+        target.push_str(line);
+        target.push('\n');
+    }
+    if body.trim().is_empty() {
+        body = "    pass\n".to_string();
+    }
+    format!("{declarations}fn main():\n{body}")
+}
+
+fn handle_build(
+    cli: &OtterCli,
+    path: &Path,
+    output: Option<PathBuf>,
+    debug_info: bool,
+    pgo_gen: bool,
+    pgo_use: Option<PathBuf>,
+    sanitize: &[String],
+) -> Result<()> {
+    let mut settings = CompilationSettings::from_cli(cli)?;
+    settings.debug_info = debug_info;
+    settings.sanitizers = sanitize
+        .iter()
+        .map(|name| name.parse::<Sanitizer>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| anyhow!("invalid --sanitize: {err}"))?;
+    settings.sanitizers.sort();
+    settings.sanitizers.dedup();
 
     let output_path = resolve_output_path(path, output);
     if let Some(parent) = output_path.parent() {
@@ -314,6 +672,36 @@ fn handle_build(cli: &OtterCli, path: &Path, output: Option<PathBuf>) -> Result<
             .with_context(|| format!("failed to create output directory {}", parent.display()))?;
     }
 
+    // Profiles go next to the executable by default, under an absolute path
+    // so the instrumented program finds the directory from wherever it runs
+    let profile_dir = std::path::absolute(output_path.with_extension("pgo"))
+        .context("failed to resolve the profile directory")?;
+    if pgo_gen {
+        // Profiles of an earlier build no longer match this one
+        if let Ok(entries) = fs::read_dir(&profile_dir) {
+            for entry in entries.flatten() {
+                if entry.path().extension().is_some_and(|ext| ext == "profraw") {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+        settings.pgo = PgoMode::Generate {
+            dir: profile_dir.clone(),
+        };
+    } else if let Some(profile) = pgo_use {
+        // A bare `--pgo-use` reads the profiles next to the executable
+        let profile = if profile.as_os_str().is_empty() {
+            profile_dir.clone()
+        } else {
+            profile
+        };
+        let profile = otterc_codegen::merge_pgo_profiles(&profile)?;
+        println!("{} {}", "Merged".green().bold(), profile.display());
+        settings.pgo = PgoMode::Use { profile };
+    }
+
+    let stage = compile(cli, path, &settings)?;
+
     let cached_binary = match &stage.result {
         CompilationResult::CacheHit(entry) => &entry.binary_path,
         CompilationResult::Compiled { artifact, .. } => &artifact.binary,
@@ -329,15 +717,36 @@ fn handle_build(cli: &OtterCli, path: &Path, output: Option<PathBuf>) -> Result<
     })?;
 
     println!("{} {}", "Built".green().bold(), output_path.display());
+    if pgo_gen {
+        println!(
+            "Run it on representative input, then rebuild with --pgo-use to optimize with the \
+             profiles in {}",
+            profile_dir.display()
+        );
+    }
 
     match &stage.result {
         CompilationResult::Compiled { artifact, metadata } => {
+            // Named after the output binary, like the binary they came with
+            for emitted in &artifact.emitted {
+                let path = match emitted.extension() {
+                    Some(extension) => output_path.with_extension(extension),
+                    None => output_path.clone(),
+                };
+                fs::copy(emitted, &path).with_context(|| {
+                    format!("failed to copy {} to {}", emitted.display(), path.display())
+                })?;
+                println!("{} {}", "Emitted".green().bold(), path.display());
+            }
             if settings.dump_ir
                 && let Some(ir) = &artifact.ir
             {
                 println!("\n{}", "== LLVM IR ==".bold());
                 println!("{ir}");
             }
+            if let Some(report) = &artifact.opt_report {
+                print_opt_report(report);
+            }
             if settings.profile {
                 print_profile(metadata);
             }
@@ -357,33 +766,127 @@ fn handle_build(cli: &OtterCli, path: &Path, output: Option<PathBuf>) -> Result<
     Ok(())
 }
 
+/// Compiles `path` with every function instrumented, runs it once and writes
+/// where the compile and the run spent their time to an HTML page
+fn handle_profile_report(cli: &OtterCli, path: &Path, output: Option<PathBuf>) -> Result<()> {
+    let mut settings = CompilationSettings::from_cli(cli)?;
+    settings.profile_functions = true;
+    let stage = compile_pipeline(path, &read_source(path)?, &settings)?;
+    let binary = match &stage.result {
+        CompilationResult::Compiled { artifact, .. } => &artifact.binary,
+        CompilationResult::CacheHit(_) | CompilationResult::Checked => {
+            unreachable!("instrumented builds are never cached or only checked")
+        }
+    };
+
+    let run_path = binary.with_extension("profile.json");
+    let mut command = ProcessCommand::new(binary);
+    settings.apply_runtime_env(&mut command);
+    command.env("OTTER_PROFILE_REPORT", &run_path);
+    let status = command
+        .status()
+        .with_context(|| format!("failed to execute {}", binary.display()))?;
+    let run = fs::read_to_string(&run_path).with_context(|| {
+        format!(
+            "{} exited ({status}) without writing a profile",
+            path.display()
+        )
+    })?;
+    let run: RunProfile = serde_json::from_str(&run)
+        .with_context(|| format!("failed to read the profile in {}", run_path.display()))?;
+    let _ = fs::remove_file(&run_path);
+
+    let output = output.unwrap_or_else(|| path.with_extension("profile.html"));
+    fs::write(&output, render_report(path, stage.timings(), &run, status))
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    println!("{} {}", "Profiled".green().bold(), output.display());
+
+    if !status.success() {
+        bail!("program exited with status {status}");
+    }
+    Ok(())
+}
+
 fn handle_check(cli: &OtterCli, path: &Path) -> Result<()> {
     let mut settings = CompilationSettings::from_cli(cli)?;
     settings.check_only = true;
-    let source = read_source(path)?;
-    let stage = compile_pipeline(path, &source, &settings)?;
+    let stage = compile(cli, path, &settings)?;
 
     if settings.time {
         print_timings(&stage);
     }
 
-    println!("{} {}", "Checked".green().bold(), path.display());
+    match settings.output_format {
+        // Code scanning expects a log even when there is nothing to report
+        OutputFormat::Sarif => {
+            println!("{}", render_sarif(&stage.warnings.annotations, VERSION));
+        }
+        OutputFormat::Human | OutputFormat::Github => {
+            println!("{} {}", "Checked".green().bold(), path.display());
+        }
+    }
     Ok(())
 }
 
+/// Compiles `path` through a running daemon when there is one, and in this
+/// process otherwise
+fn compile(
+    cli: &OtterCli,
+    path: &Path,
+    settings: &CompilationSettings,
+) -> Result<CompilationStage> {
+    if !cli.no_daemon
+        && !cli.sandbox
+        && settings.allow_daemon()
+        && let Some(stage) = crate::daemon::compile_remote()?
+    {
+        return Ok(stage);
+    }
+
+    let source = read_source(path)?;
+    compile_pipeline(path, &source, settings)
+}
+
 pub fn compile_pipeline(
     path: &Path,
     source: &str,
     settings: &CompilationSettings,
 ) -> Result<CompilationStage> {
-    let mut cache_manager = CacheManager::new();
+    compile_quietly(path, source, settings)
+        .inspect(|stage| eprint!("{}", stage.warnings.report))
+        .inspect_err(|err| {
+            if let Some(rejected) = err.downcast_ref::<Rejected>() {
+                print!("{}", rejected.report);
+            }
+        })
+}
+
+/// [`compile_pipeline`] without printing the report of a rejected program, for
+/// callers that collect diagnostics from several compilations
+pub(crate) fn compile_quietly(
+    path: &Path,
+    source: &str,
+    settings: &CompilationSettings,
+) -> Result<CompilationStage> {
+    let mut session = CompilerSession::new()
+        .with_language_features(settings.language_features().clone())
+        .with_cfg(settings.cfg());
+    compile_with_session(&mut session, path, source, settings)
+}
+
+/// [`compile_pipeline`] on a session that may have compiled before, such as
+/// the one a daemon keeps. A program with errors is returned as a
+/// [`Rejected`] error holding its diagnostics instead of printing them.
+pub(crate) fn compile_with_session(
+    session: &mut CompilerSession,
+    path: &Path,
+    source: &str,
+    settings: &CompilationSettings,
+) -> Result<CompilationStage> {
+    let mut cache_manager = CacheManager::new().with_shared(SharedCache::from_env());
     let cache_options = settings.cache_build_options();
     let mut profiler = Profiler::new();
     let source_id = path.display().to_string();
-    let source_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
-
-    // Try to find stdlib directory
-    let stdlib_dir = find_stdlib_dir().ok();
 
     // Initial inputs without module dependencies (will be updated after parsing)
     let mut inputs = CompilationInputs::new(path.to_path_buf(), Vec::new());
@@ -402,93 +905,76 @@ pub fn compile_pipeline(
         return Ok(CompilationStage {
             profiler,
             result: CompilationResult::CacheHit(entry),
+            warnings: Warnings::default(),
         });
     }
 
-    let tokens = match profiler.record_phase("Lexing", || tokenize(source)) {
-        Ok(tokens) => tokens,
-        Err(errors) => {
-            emit_lexer_errors(&source_id, source, &errors);
-            bail!("lexing failed");
-        }
-    };
+    session.set_file(path, source);
+    let analysis = session.analyze(path)?;
+    for timing in &analysis.timings {
+        profiler.push_phase(timing.name.clone(), timing.duration);
+    }
 
-    if settings.dump_tokens {
+    if settings.dump_tokens && !analysis.tokens.is_empty() {
         println!("\n{}", "== Tokens ==".bold());
-        for token in &tokens {
+        for token in &analysis.tokens {
             println!("  {:?} @ {:?}", token.kind(), token.span());
         }
     }
 
-    let program = match profiler.record_phase("Parsing", || parse(&tokens)) {
-        Ok(program) => {
-            if settings.debug {
-                println!("Parsed successfully");
-            }
-            program
+    match analysis.failed {
+        Some(Phase::Lexing) => {
+            return Err(settings
+                .reject(
+                    &analysis.diagnostics,
+                    source,
+                    "Lexical errors",
+                    "lexing failed",
+                )
+                .into());
         }
-        Err(errors) => {
-            emit_parser_errors(&source_id, source, &errors);
-            bail!("parsing failed");
+        Some(Phase::Parsing) => {
+            return Err(settings
+                .reject(
+                    &analysis.diagnostics,
+                    source,
+                    "Parsing errors",
+                    "parsing failed",
+                )
+                .into());
         }
-    };
+        _ => {}
+    }
 
-    if settings.dump_ast {
+    if settings.debug {
+        println!("Parsed successfully");
+    }
+    if settings.dump_ast
+        && let Some(program) = &analysis.program
+    {
         println!("\n{}", "== AST ==".bold());
         println!("{:#?}", program);
     }
 
-    // Process module imports
-    let mut module_processor = ModuleProcessor::new(source_dir.clone(), stdlib_dir.clone());
-    let module_deps = profiler.record_phase("Module Resolution", || {
-        module_processor.process_imports(&program)
-    })?;
-
-    // Resolve re-exports after all modules are loaded
-    profiler.record_phase("Re-export Resolution", || {
-        module_processor.resolve_all_re_exports()
-    })?;
-
-    // Register Rust FFI functions for type checking (before type checking)
-    let registry = otterc_symbol::registry::SymbolRegistry::global();
-    profiler.record_phase("Register FFI Functions", || {
-        register_rust_ffi_functions_for_typecheck(&program, registry)
-    })?;
-
-    // Type check the program
-    let mut type_checker =
-        TypeChecker::with_language_features(settings.language_features().clone())
-            .with_registry(registry);
-
-    for module in module_processor.modules() {
-        type_checker.register_module_definitions(&module.program);
-    }
-    let type_check_result =
-        profiler.record_phase("Type Checking", || type_checker.check_program(&program));
-
-    if let Err(err) = type_check_result {
-        let diagnostics = otterc_typecheck::diagnostics_from_type_errors(
-            type_checker.errors(),
-            &source_id,
-            source,
-        );
-        emit_diagnostics(&diagnostics, source);
-        return Err(err).with_context(|| "type checking failed");
+    if analysis.failed == Some(Phase::TypeChecking) {
+        return Err(settings
+            .reject(&analysis.diagnostics, source, "", "type checking failed")
+            .into());
     }
+    let warnings = settings.warnings(&analysis.diagnostics, source);
 
     if settings.check_only {
         profiler.push_phase("Codegen skipped", Duration::from_millis(0));
         return Ok(CompilationStage {
             profiler,
             result: CompilationResult::Checked,
+            warnings,
         });
     }
 
-    let enum_layouts = type_checker.enum_layouts();
-    let (expr_types, expr_types_by_span, comprehension_var_types) = type_checker.into_type_maps();
-
     // Update inputs with module dependencies for accurate cache fingerprinting
-    inputs.imports = module_deps
+    inputs.imports = analysis
+        .modules
         .iter()
         .map(|p| p.display().to_string())
         .collect();
@@ -507,10 +993,12 @@ pub fn compile_pipeline(
         return Ok(CompilationStage {
             profiler,
             result: CompilationResult::CacheHit(entry),
+            warnings,
         });
     }
 
-    let codegen_options = settings.codegen_options();
+    let mut codegen_options = settings.codegen_options();
+    codegen_options.source = Some(SourceLines::new(source_id.clone(), source));
     let binary_path = if let Some(path) = cache_manager.binary_path(&cache_key) {
         ensure_output_directory(&path)?;
         path
@@ -521,15 +1009,7 @@ pub fn compile_pipeline(
     };
 
     let artifact = profiler.record_phase("Codegen", || {
-        build_executable(
-            &program,
-            &expr_types,
-            &expr_types_by_span,
-            &comprehension_var_types,
-            &enum_layouts,
-            &binary_path,
-            &codegen_options,
-        )
+        session.build(&analysis, &binary_path, &codegen_options)
     })?;
 
     let build_duration_ms = profiler
@@ -551,7 +1031,9 @@ pub fn compile_pipeline(
     )
     .with_llvm_version(otterc_codegen::current_llvm_version());
 
-    if let Err(e) = cache_manager.store(&metadata) {
+    if settings.allow_cache()
+        && let Err(e) = cache_manager.store(&metadata)
+    {
         warn!("Failed to store cache entry: {}", e);
     }
 
@@ -560,6 +1042,7 @@ pub fn compile_pipeline(
     Ok(CompilationStage {
         profiler,
         result: CompilationResult::Compiled { artifact, metadata },
+        warnings,
     })
 }
 
@@ -573,10 +1056,55 @@ fn ensure_output_directory(path: &Path) -> Result<()> {
 }
 
 pub struct CompilationStage {
-    profiler: Profiler,
+    pub(crate) profiler: Profiler,
     pub result: CompilationResult,
+    pub warnings: Warnings,
+}
+
+/// Warnings about a program that compiled, such as unused variables, rendered
+/// like a [`Rejected`] report. The report is empty for SARIF, whose log is
+/// written by the caller from `annotations`.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    pub report: String,
+    pub annotations: Vec<Annotation>,
 }
 
+/// A program the front end rejected, with its diagnostics rendered in the
+/// requested output format. Displays as `message`, which `main` recognises
+/// as already reported.
+#[derive(Debug)]
+pub struct Rejected {
+    pub report: String,
+    pub message: String,
+    /// The located diagnostics, for callers that report them together with
+    /// others. Empty when the program was rejected by a daemon.
+    pub annotations: Vec<Annotation>,
+}
+
+impl Rejected {
+    pub fn new(report: String, message: impl Into<String>) -> Self {
+        Self {
+            report,
+            message: message.into(),
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn with_annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+}
+
+impl std::fmt::Display for Rejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Rejected {}
+
 pub enum CompilationResult {
     CacheHit(CacheEntry),
     Checked,
@@ -597,21 +1125,50 @@ pub struct CompilationSettings {
     dump_tokens: bool,
     dump_ast: bool,
     dump_ir: bool,
+    emit_opt_report: bool,
+    /// Intermediate files to keep, from `--emit`
+    emit: Vec<EmitKind>,
+    /// Instrument every function for `otter profile report`
+    profile_functions: bool,
+    /// Emit DWARF debug info, for `otter build -g`
+    debug_info: bool,
     time: bool,
     profile: bool,
-    release: bool,
+    pub(crate) release: bool,
+    /// `--lto`, or thin LTO for `--release`
+    pub(crate) lto: LtoMode,
+    /// Instrumenting or profile-guided build, from `otter build --pgo-gen/--pgo-use`
+    pgo: PgoMode,
+    /// `-O`, when given; otherwise the level follows `release`
+    opt_level: Option<CodegenOptLevel>,
+    /// `--passes`
+    passes: Option<String>,
+    /// Sanitizers, for `otter build --sanitize`
+    sanitizers: Vec<Sanitizer>,
     tasks: bool,
     tasks_debug: bool,
     tasks_trace: bool,
     debug: bool,
     target: Option<String>,
     no_cache: bool,
+    release_asserts_off: bool,
+    preempt_loops: bool,
+    /// `--sandbox`: fs, net, env and sys calls compile to denying stubs
+    sandbox: bool,
     enable_cache: bool,
     cache_dir: PathBuf,
     max_cache_size: usize,
-    check_only: bool,
+    pub(crate) check_only: bool,
     language_features: LanguageFeatureFlags,
+    cfg_features: Vec<String>,
     gc: GcCliOptions,
+    limits: ResourceLimits,
+    heap_stats_interval: Option<Duration>,
+    record_schedule: Option<PathBuf>,
+    replay_schedule: Option<PathBuf>,
+    cpu_affinity: Option<CpuAffinity>,
+    steal_policy: Option<StealPolicy>,
+    pub(crate) output_format: OutputFormat,
 }
 
 #[derive(Clone, Default)]
@@ -620,6 +1177,7 @@ struct GcCliOptions {
     threshold: Option<f64>,
     interval_ms: Option<u64>,
     disabled_limit: Option<usize>,
+    log: bool,
 }
 
 impl GcCliOptions {
@@ -638,6 +1196,7 @@ impl GcCliOptions {
             threshold: cli.gc_threshold.map(|value| value.clamp(0.0, 1.0)),
             interval_ms: cli.gc_interval_ms,
             disabled_limit: cli.gc_disabled_max_bytes,
+            log: cli.gc_log,
         })
     }
 
@@ -664,6 +1223,9 @@ impl GcCliOptions {
         if let Some(limit) = self.disabled_limit {
             pairs.push(("OTTER_GC_DISABLED_MAX_BYTES", limit.to_string()));
         }
+        if self.log {
+            pairs.push(("OTTER_GC_LOG", "1".to_string()));
+        }
         pairs
     }
 
@@ -678,33 +1240,184 @@ impl GcCliOptions {
 }
 
 impl CompilationSettings {
-    fn from_cli(cli: &OtterCli) -> Result<Self> {
+    pub fn from_cli(cli: &OtterCli) -> Result<Self> {
         let language_features = resolve_language_features(cli);
         let gc = GcCliOptions::from_cli(cli)?;
+        let limits = resolve_resource_limits(cli)?;
+        let heap_stats_interval = match cli.heap_stats_interval.as_deref() {
+            Some(value) => match parse_duration(value) {
+                Some(interval) if !interval.is_zero() => Some(interval),
+                _ => bail!(
+                    "invalid --heap-stats-interval '{}' (expected a duration such as 5s)",
+                    value
+                ),
+            },
+            None => None,
+        };
+        let mut emit_opt_report = false;
+        let mut emit = Vec::new();
+        for kind in &cli.emit {
+            let file = match kind.as_str() {
+                "opt-report" => {
+                    emit_opt_report = true;
+                    continue;
+                }
+                "llvm-ir" => EmitKind::LlvmIr,
+                "asm" => EmitKind::Asm,
+                "obj" => EmitKind::Obj,
+                "clif" => bail!(
+                    "--emit=clif needs the Cranelift backend, and otter only generates code with LLVM (use --emit=llvm-ir)"
+                ),
+                other => bail!(
+                    "invalid --emit '{}' (expected llvm-ir, asm, obj or opt-report)",
+                    other
+                ),
+            };
+            if !emit.contains(&file) {
+                emit.push(file);
+            }
+        }
         Ok(Self {
             dump_tokens: cli.dump_tokens,
             dump_ast: cli.dump_ast,
             dump_ir: cli.dump_ir,
+            emit_opt_report,
+            emit,
+            profile_functions: false,
+            debug_info: false,
             time: cli.time,
             profile: cli.profile,
             release: cli.release,
+            lto: match cli.lto.as_deref() {
+                Some(mode) => mode
+                    .parse::<LtoMode>()
+                    .map_err(|err| anyhow!("invalid --lto: {err}"))?,
+                None if cli.release => LtoMode::Thin,
+                None => LtoMode::Off,
+            },
+            pgo: PgoMode::Off,
+            opt_level: cli
+                .opt_level
+                .as_deref()
+                .map(str::parse::<CodegenOptLevel>)
+                .transpose()
+                .map_err(|err| anyhow!("invalid -O: {err}"))?,
+            passes: cli
+                .passes
+                .clone()
+                .filter(|passes| !passes.trim().is_empty()),
+            sanitizers: Vec::new(),
             tasks: cli.tasks,
             tasks_debug: cli.tasks_debug,
             tasks_trace: cli.tasks_trace,
             debug: cli.debug,
             target: cli.target.clone(),
             no_cache: cli.no_cache,
+            release_asserts_off: cli.release_asserts_off,
+            preempt_loops: cli.preempt_loops,
+            sandbox: cli.sandbox,
             enable_cache: !cli.no_cache,
             cache_dir: PathBuf::from("./cache"),
             max_cache_size: 1024 * 1024 * 1024, // 1GB default
             check_only: false,
             language_features,
+            cfg_features: cli
+                .cfg_features
+                .iter()
+                .flat_map(|list| list.split(','))
+                .map(str::trim)
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
             gc,
+            limits,
+            heap_stats_interval,
+            record_schedule: cli.record_schedule.clone(),
+            replay_schedule: cli.replay_schedule.clone(),
+            cpu_affinity: cli
+                .cpu_affinity
+                .as_deref()
+                .map(str::parse::<CpuAffinity>)
+                .transpose()
+                .map_err(|err: String| anyhow!("invalid --cpu-affinity: {err}"))?,
+            steal_policy: cli
+                .steal_policy
+                .as_deref()
+                .map(str::parse::<StealPolicy>)
+                .transpose()
+                .map_err(|err: String| anyhow!("invalid --steal-policy: {err}"))?,
+            output_format: cli
+                .output_format
+                .as_deref()
+                .map(str::parse::<OutputFormat>)
+                .transpose()
+                .map_err(|err: String| anyhow!("invalid --output-format: {err}"))?
+                .unwrap_or_default(),
         })
     }
 
+    /// Rejects a program, rendering its diagnostics in the requested output
+    /// format. Terminal reports are preceded by `heading` when it is not empty.
+    fn reject(
+        &self,
+        diagnostics: &[Diagnostic],
+        source: &str,
+        heading: &str,
+        message: &str,
+    ) -> Rejected {
+        let annotations = annotate_diagnostics(diagnostics, source);
+        let report = match self.output_format {
+            OutputFormat::Human if heading.is_empty() => render_diagnostics(diagnostics, source),
+            OutputFormat::Human => {
+                format!("\n{heading}:\n{}", render_diagnostics(diagnostics, source))
+            }
+            OutputFormat::Github => render_github(&annotations),
+            OutputFormat::Sarif => format!("{}\n", render_sarif(&annotations, VERSION)),
+        };
+        Rejected::new(report, message).with_annotations(annotations)
+    }
+
+    fn warnings(&self, diagnostics: &[Diagnostic], source: &str) -> Warnings {
+        let annotations = annotate_diagnostics(diagnostics, source);
+        let report = match self.output_format {
+            _ if diagnostics.is_empty() => String::new(),
+            OutputFormat::Human => render_diagnostics(diagnostics, source),
+            OutputFormat::Github => render_github(&annotations),
+            OutputFormat::Sarif => String::new(),
+        };
+        Warnings {
+            report,
+            annotations,
+        }
+    }
+
+    /// Whether a daemon may compile instead, which it cannot when the
+    /// compilation prints or keeps its intermediate stages, instruments the
+    /// program, carries debug info, uses a profile, is sanitized or the
+    /// warnings go into a SARIF log
+    fn allow_daemon(&self) -> bool {
+        !(self.dump_tokens
+            || self.dump_ast
+            || self.debug
+            || self.emit_opt_report
+            || !self.emit.is_empty()
+            || self.profile_functions
+            || self.debug_info
+            || self.pgo.is_enabled()
+            || !self.sanitizers.is_empty())
+            && self.output_format != OutputFormat::Sarif
+    }
+
     fn allow_cache(&self) -> bool {
-        !(self.dump_tokens || self.dump_ast || self.dump_ir || self.no_cache || self.check_only)
+        !(self.dump_tokens
+            || self.dump_ast
+            || self.dump_ir
+            || self.emit_opt_report
+            || !self.emit.is_empty()
+            || self.profile_functions
+            || self.pgo.is_enabled()
+            || self.no_cache
+            || self.check_only)
     }
 
     pub fn apply_runtime_env(&self, command: &mut std::process::Command) {
@@ -722,6 +1435,57 @@ impl CompilationSettings {
             command.env("OTTER_DEBUG", "1");
         }
         self.gc.apply_to_command(command);
+        if let Some(max_heap) = self.limits.max_heap_bytes {
+            command.env("OTTER_MAX_HEAP", max_heap.to_string());
+        }
+        if let Some(max_cpu) = self.limits.max_cpu {
+            command.env("OTTER_MAX_CPU_SECONDS", max_cpu.as_secs_f64().to_string());
+        }
+        if let Some(interval) = self.heap_stats_interval {
+            command.env(
+                "OTTER_HEAP_STATS_INTERVAL_MS",
+                interval.as_millis().max(1).to_string(),
+            );
+        }
+        if let Some(path) = &self.record_schedule {
+            command.env("OTTER_RECORD_SCHEDULE", path);
+        }
+        if let Some(path) = &self.replay_schedule {
+            command.env("OTTER_REPLAY_SCHEDULE", path);
+        }
+        if let Some(affinity) = self.cpu_affinity {
+            command.env("OTTER_CPU_AFFINITY", affinity.to_string());
+        }
+        if let Some(policy) = self.steal_policy {
+            command.env("OTTER_STEAL_POLICY", policy.to_string());
+        }
+    }
+
+    /// The command that runs the compiled program: the binary itself, or for
+    /// a WASI build a WebAssembly runtime (`OTTER_WASM_RUNNER`, wasmtime by
+    /// default) with the working directory preopened
+    pub fn program_command(&self, binary: &Path) -> ProcessCommand {
+        let wasi = self
+            .target
+            .as_deref()
+            .and_then(|target| TargetTriple::parse(target).ok())
+            .is_some_and(|target| target.is_wasi());
+        if !wasi {
+            return ProcessCommand::new(binary);
+        }
+        let runner = std::env::var("OTTER_WASM_RUNNER").unwrap_or_else(|_| "wasmtime".into());
+        let mut command = ProcessCommand::new(runner);
+        command.arg("run").arg("--dir").arg(".").arg(binary);
+        command
+    }
+
+    /// `-O`, or O3 for `--release` and O2 otherwise
+    fn opt_level(&self) -> CodegenOptLevel {
+        self.opt_level.unwrap_or(if self.release {
+            CodegenOptLevel::Aggressive
+        } else {
+            CodegenOptLevel::Default
+        })
     }
 
     fn cache_build_options(&self) -> CacheBuildOptions {
@@ -730,8 +1494,25 @@ impl CompilationSettings {
             cache_dir: self.cache_dir.clone(),
             max_cache_size: self.max_cache_size,
             release: self.release,
-            lto: self.release,
+            lto: self.lto.to_string(),
+            opt_level: self.opt_level().to_string(),
+            passes: self.passes.clone(),
+            sanitizers: self.sanitizers.iter().map(ToString::to_string).collect(),
             emit_ir: self.dump_ir,
+            debug_info: self.debug_info,
+            sandbox: self.sandbox,
+            strip_asserts: self.release_asserts_off,
+            preempt_loops: self.preempt_loops,
+            target: self.target.clone(),
+            features: collect_enabled_feature_names(&self.language_features)
+                .into_iter()
+                .map(str::to_string)
+                .chain(
+                    self.cfg_features
+                        .iter()
+                        .map(|feature| format!("cfg:{feature}")),
+                )
+                .collect(),
         }
     }
 
@@ -746,24 +1527,66 @@ impl CompilationSettings {
 
         CodegenOptions {
             emit_ir: self.dump_ir,
-            opt_level: if self.release {
-                CodegenOptLevel::Aggressive
-            } else {
-                CodegenOptLevel::Default
-            },
-            enable_lto: self.release,
-            enable_pgo: false,
-            pgo_profile_file: None,
+            opt_level: self.opt_level(),
+            passes: self.passes.clone(),
+            lto: self.lto,
+            pgo: self.pgo.clone(),
+            sanitizers: self.sanitizers.clone(),
             inline_threshold: None,
             target,
+            strip_asserts: self.release_asserts_off,
+            preempt_loops: self.preempt_loops,
+            source: None,
+            cfg_features: self.cfg_features.clone(),
+            emit_opt_report: self.emit_opt_report,
+            profile_functions: self.profile_functions,
+            debug_info: self.debug_info,
+            emit: self.emit.clone(),
         }
     }
 
-    fn language_features(&self) -> &LanguageFeatureFlags {
+    /// What `@cfg(...)` attributes are evaluated against. An invalid
+    /// `--target` is reported by [`codegen_options`](Self::codegen_options).
+    pub(crate) fn cfg(&self) -> CfgContext {
+        let cfg = match self.target.as_deref().map(TargetTriple::parse) {
+            Some(Ok(target)) => CfgContext::for_target(&target),
+            _ => CfgContext::host(),
+        };
+        cfg.with_features(self.cfg_features.iter().cloned())
+    }
+
+    pub(crate) fn language_features(&self) -> &LanguageFeatureFlags {
         &self.language_features
     }
 }
 
+fn resolve_resource_limits(cli: &OtterCli) -> Result<ResourceLimits> {
+    let max_heap_bytes = match cli.max_heap.as_deref() {
+        Some(value) => match parse_size(value) {
+            Some(bytes) if bytes > 0 => Some(bytes),
+            _ => bail!(
+                "invalid --max-heap '{}' (expected a size such as 512M)",
+                value
+            ),
+        },
+        None => None,
+    };
+    let max_cpu = match cli.max_cpu_seconds {
+        Some(seconds) if seconds.is_finite() && seconds > 0.0 => {
+            Some(Duration::from_secs_f64(seconds))
+        }
+        Some(seconds) => bail!(
+            "invalid --max-cpu-seconds '{}' (expected a positive number)",
+            seconds
+        ),
+        None => None,
+    };
+    Ok(ResourceLimits {
+        max_heap_bytes,
+        max_cpu,
+    })
+}
+
 fn resolve_language_features(cli: &OtterCli) -> LanguageFeatureFlags {
     let mut flags = LanguageFeatureFlags::default();
 
@@ -835,46 +1658,12 @@ fn canonical_or(path: &Path) -> PathBuf {
     path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
-fn find_stdlib_dir() -> Result<PathBuf> {
-    // Try environment variable first
-    if let Ok(dir) = std::env::var("OTTER_STDLIB_DIR") {
-        let path = PathBuf::from(dir);
-        if path.exists() {
-            return Ok(path);
-        }
-    }
-
-    // Try relative to executable (for development)
-    if let Ok(exe) = std::env::current_exe()
-        && let Some(exe_dir) = exe.parent()
-    {
-        let stdlib = exe_dir
-            .parent()
-            .unwrap_or(exe_dir)
-            .join("stdlib")
-            .join("otter");
-        if stdlib.exists() {
-            return Ok(stdlib);
-        }
-    }
-
-    // Try relative to current directory (for development)
-    let stdlib = PathBuf::from("stdlib").join("otter");
-    if stdlib.exists() {
-        return Ok(stdlib);
-    }
-
-    bail!(
-        "stdlib directory not found. Set OTTER_STDLIB_DIR environment variable or ensure stdlib/otter exists"
-    )
-}
-
 fn execute_binary(path: &Path, settings: &CompilationSettings) -> Result<()> {
     if settings.debug {
         println!("Running program: {}", path.display());
     }
 
-    let mut command = ProcessCommand::new(path);
+    let mut command = settings.program_command(path);
     settings.apply_runtime_env(&mut command);
 
     let status = command
@@ -914,6 +1703,47 @@ fn print_timings(stage: &CompilationStage) {
     println!("  {:20} {:8.2}ms", "Total", total.as_secs_f64() * 1000.0);
 }
 
+fn handle_ffi_inspect(
+    crate_name: &str,
+    version: Option<String>,
+    rustdoc_json: Option<&Path>,
+) -> Result<()> {
+    use otterc_ffi::{BridgeSymbolRegistry, CrateSurface};
+
+    let mut dependency = BridgeSymbolRegistry::global()
+        .ensure_metadata(crate_name)?
+        .dependency;
+    if version.is_some() {
+        dependency.version = version;
+    }
+
+    let spec = match rustdoc_json {
+        Some(path) => otterc_ffi::extract_crate_spec_from_json(
+            crate_name,
+            dependency.version.clone(),
+            path,
+        )?,
+        None => otterc_ffi::extract_crate_spec(&dependency).with_context(|| {
+            format!(
+                "failed to document `{crate_name}` (rustdoc JSON needs a nightly toolchain: `rustup toolchain install nightly`)"
+            )
+        })?,
+    };
+    print!("{}", CrateSurface::from_spec(&spec, &dependency));
+    Ok(())
+}
+
+fn handle_ffi_prebuild(crate_name: &str, out: &Path) -> Result<()> {
+    use otterc_ffi::{BridgeSymbolRegistry, CargoBridge};
+
+    let bridge = CargoBridge::new(BridgeSymbolRegistry::global().clone())?;
+    let dir = bridge
+        .export_prebuilt(crate_name, out)
+        .with_context(|| format!("failed to prebuild the bridge for `{crate_name}`"))?;
+    println!("{} {}", "Prebuilt".green().bold(), dir.display());
+    Ok(())
+}
+
 fn handle_fmt(paths: &[PathBuf]) -> Result<()> {
     use glob::glob;
     use otterc_fmt::Formatter;
@@ -962,7 +1792,10 @@ fn handle_fmt(paths: &[PathBuf]) -> Result<()> {
         let program = parse(&tokens)
             .map_err(|_| anyhow::anyhow!("failed to parse {}", file_path.display()))?;
 
-        let formatted = formatter.format_program(&program);
+        let mut formatted = formatter.format_program(&program);
+        if let Some(shebang) = source.lines().next().filter(|line| line.starts_with("#!")) {
+            formatted = format!("{shebang}\n{formatted}");
+        }
 
         if formatted != source {
             fs::write(&file_path, formatted)
@@ -981,6 +1814,16 @@ fn handle_fmt(paths: &[PathBuf]) -> Result<()> {
     Ok(())
 }
 
+fn print_opt_report(report: &[String]) {
+    println!("\n{}", "== Optimization report ==".bold());
+    if report.is_empty() {
+        println!("  nothing was moved out of a loop");
+    }
+    for line in report {
+        println!("  {line}");
+    }
+}
+
 fn print_profile(metadata: &CacheMetadata) {
     println!("\nProfile:");
     println!("  Binary: {}", metadata.binary_path.display());
@@ -991,22 +1834,53 @@ fn print_profile(metadata: &CacheMetadata) {
     }
 }
 
-fn emit_lexer_errors(source_id: &str, source: &str, errors: &[LexerError]) {
-    println!("\nLexical errors:");
-    let diagnostics: Vec<Diagnostic> = errors
-        .iter()
-        .map(|err| err.to_diagnostic(source_id))
-        .collect();
-    emit_diagnostics(&diagnostics, source);
-}
+fn handle_selftest_backends(
+    cli: &OtterCli,
+    paths: &[PathBuf],
+    backends: &[String],
+    bless: bool,
+    timeout: Duration,
+) -> Result<()> {
+    use crate::selftest::{Backend, check_program, discover};
 
-fn emit_parser_errors(source_id: &str, source: &str, errors: &[ParserError]) {
-    println!("\nParsing errors:");
-    let diagnostics: Vec<Diagnostic> = errors
-        .iter()
-        .map(|err| err.to_diagnostic(source_id))
-        .collect();
-    emit_diagnostics(&diagnostics, source);
+    let settings = CompilationSettings::from_cli(cli)?;
+    let backends = if backends.is_empty() {
+        Backend::ALL.to_vec()
+    } else {
+        backends
+            .iter()
+            .map(|name| Backend::parse(name))
+            .collect::<Result<Vec<_>>>()?
+    };
+    let programs = discover(paths)?;
+    let names: Vec<_> = backends.iter().map(|backend| backend.name()).collect();
+    println!(
+        "Checking {} program(s) under {}...\n",
+        programs.len(),
+        names.join(", ")
+    );
+
+    let mut failed = 0;
+    for program in &programs {
+        let report = check_program(program, &backends, &settings, timeout, bless)?;
+        if report.passed() {
+            let note = if report.blessed { " (blessed)" } else { "" };
+            println!("{} {}{}", "PASS".green().bold(), program.display(), note);
+        } else {
+            failed += 1;
+            println!("{} {}", "FAIL".red().bold(), program.display());
+            for problem in &report.problems {
+                println!("    {problem}");
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", programs.len() - failed, failed);
+    if failed > 0 {
+        #[expect(clippy::exit, reason = "It's desired to exit immediately here")]
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
 fn handle_test(
@@ -1025,15 +1899,17 @@ fn handle_test(
 
     let tests = discovery.discover_all_tests()?;
 
-    if tests.is_empty() {
-        println!("No tests found");
-        return Ok(());
+    let format = settings.output_format;
+    let mut reporter = TestReporter::new(verbose, format);
+    if format != OutputFormat::Sarif {
+        if tests.is_empty() {
+            println!("No tests found");
+            return Ok(());
+        }
+        println!("Running {} test(s)...\n", tests.len());
     }
 
-    println!("Running {} test(s)...\n", tests.len());
-
     let runner = TestRunner::new(settings, update_snapshots);
-    let mut reporter = TestReporter::new(verbose);
 
     if parallel {
         // Run tests in parallel
@@ -1067,153 +1943,3 @@ fn handle_test(
 
     Ok(())
 }
-
-fn register_rust_ffi_functions_for_typecheck(
-    program: &otterc_ast::nodes::Program,
-    registry: &'static SymbolRegistry,
-) -> Result<()> {
-    let imports = collect_rust_imports_for_typecheck(program);
-    if imports.is_empty() {
-        return Ok(());
-    }
-
-    let bridge_registry = BridgeSymbolRegistry::global();
-
-    for (crate_name, aliases) in imports {
-        let metadata = bridge_registry.ensure_metadata(&crate_name)?;
-        register_bridge_functions_for_typecheck(
-            &crate_name,
-            &aliases,
-            &metadata.functions,
-            registry,
-        )?;
-    }
-
-    Ok(())
-}
-
-fn collect_rust_imports_for_typecheck(
-    program: &otterc_ast::nodes::Program,
-) -> HashMap<String, HashSet<String>> {
-    use otterc_ast::nodes::Statement;
-    let mut imports: HashMap<String, HashSet<String>> = HashMap::new();
-
-    for statement in &program.statements {
-        if let Statement::Use {
-            imports: use_imports,
-        } = statement.as_ref()
-        {
-            for import in use_imports {
-                if let Some((namespace, crate_name)) = import.as_ref().module.split_once(':')
-                    && namespace == "rust"
-                {
-                    let aliases = imports.entry(crate_name.to_string()).or_default();
-                    aliases.insert(crate_name.to_string());
-                    if let Some(alias_name) = &import.as_ref().alias {
-                        aliases.insert(alias_name.clone());
-                    }
-                }
-            }
-        }
-    }
-
-    imports
-}
-
-fn register_bridge_functions_for_typecheck(
-    crate_name: &str,
-    aliases: &HashSet<String>,
-    functions: &[FunctionSpec],
-    registry: &SymbolRegistry,
-) -> Result<()> {
-    use otterc_symbol::registry::{FfiFunction, FfiSignature, FfiType};
-
-    if functions.is_empty() {
-        return Ok(());
-    }
-
-    for function in functions {
-        let canonical_name = if function.name.contains(':') || function.name.contains('.') {
-            function.name.clone()
-        } else {
-            format!("{crate_name}:{}", function.name)
-        };
-
-        let params = function
-            .params
-            .iter()
-            .enumerate()
-            .map(|(idx, param)| {
-                type_spec_to_ffi_helper(param, "parameter", &canonical_name).with_context(|| {
-                    format!("parameter {idx} in `{canonical_name}` is not FFI compatible")
-                })
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        let result = type_spec_to_ffi_helper(&function.result, "return", &canonical_name)?;
-        let signature = FfiSignature::new(params.clone(), result.clone());
-
-        registry.register(FfiFunction {
-            name: canonical_name.clone(),
-            symbol: function.symbol.clone(),
-            signature: signature.clone(),
-        });
-
-        for alias in aliases {
-            let alias_name = alias_name_helper(alias, crate_name, &canonical_name);
-            registry.register(FfiFunction {
-                name: alias_name,
-                symbol: function.symbol.clone(),
-                signature: FfiSignature::new(params.clone(), result.clone()),
-            });
-        }
-    }
-
-    registry.register(FfiFunction {
-        name: format!("{crate_name}.__call_json"),
-        symbol: "otter_call_json".into(),
-        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
-    });
-
-    for alias in aliases {
-        registry.register(FfiFunction {
-            name: format!("{alias}.__call_json"),
-            symbol: "otter_call_json".into(),
-            signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
-        });
-    }
-
-    Ok(())
-}
-
-fn type_spec_to_ffi_helper(
-    spec: &TypeSpec,
-    position: &str,
-    function_name: &str,
-) -> Result<otterc_symbol::registry::FfiType> {
-    use otterc_symbol::registry::FfiType;
-
-    match spec {
-        TypeSpec::Unit => {
-            if position == "return" {
-                Ok(FfiType::Unit)
-            } else {
-                bail!("`{function_name}` cannot accept a unit value in parameter position")
-            }
-        }
-        TypeSpec::Bool => Ok(FfiType::Bool),
-        TypeSpec::I32 => Ok(FfiType::I32),
-        TypeSpec::I64 => Ok(FfiType::I64),
-        TypeSpec::F64 => Ok(FfiType::F64),
-        TypeSpec::Str => Ok(FfiType::Str),
-        TypeSpec::Opaque => Ok(FfiType::Opaque),
-    }
-}
-
-fn alias_name_helper(alias: &str, crate_name: &str, canonical: &str) -> String {
-    if let Some(rest) = canonical.strip_prefix(&format!("{}:", crate_name)) {
-        format!("{alias}.{rest}")
-    } else {
-        format!("{alias}.{canonical}")
-    }
-}
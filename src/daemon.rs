@@ -0,0 +1,409 @@
+//! A background compiler that stays warm between builds
+//!
+//! `otter daemon` listens on `.otter/daemon.sock` in the directory it was
+//! started from. `otter run`, `build` and `check` look for that socket in the
+//! current directory and its parents and, when a daemon answers, hand it their
+//! command line instead of compiling in-process. The daemon keeps one
+//! [`CompilerSession`] alive, so unchanged files are not parsed again, the
+//! standard library and FFI registry are loaded once, and LLVM is already
+//! initialised when codegen starts. Programs still run in the client, which
+//! owns the terminal.
+//!
+//! Requests and responses are single lines of JSON.
+
+#![expect(
+    clippy::print_stdout,
+    clippy::print_stderr,
+    reason = "TODO: Use robust logging"
+)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{
+    Command, CompilationResult, CompilationSettings, CompilationStage, OtterCli, Rejected,
+    Warnings, compile_with_session, read_source, script_invocation,
+};
+use crate::session::CompilerSession;
+use otterc_cache::{CacheEntry, CacheMetadata};
+use otterc_codegen::BuildArtifact;
+use otterc_config::{CfgContext, LanguageFeatureFlags};
+use otterc_utils::profiler::Profiler;
+
+/// Socket path, relative to the directory the daemon serves
+pub const SOCKET_PATH: &str = ".otter/daemon.sock";
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    /// Compile for the given `otter` command line, run from `cwd`
+    Compile {
+        cwd: PathBuf,
+        args: Vec<String>,
+    },
+    Status,
+    Shutdown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    Compiled {
+        timings: Vec<(String, Duration)>,
+        outcome: Outcome,
+        /// Rendered warnings, which the client prints
+        warnings: String,
+    },
+    /// The program has errors; `report` holds the rendered diagnostics
+    Rejected {
+        report: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+    Status {
+        root: PathBuf,
+        builds: u64,
+    },
+    ShuttingDown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Outcome {
+    Checked,
+    CacheHit(CacheMetadata),
+    Built {
+        binary: PathBuf,
+        ir: Option<String>,
+        metadata: CacheMetadata,
+    },
+}
+
+/// State kept between requests
+struct Daemon {
+    root: PathBuf,
+    session: CompilerSession,
+    features: LanguageFeatureFlags,
+    cfg: CfgContext,
+    builds: u64,
+}
+
+impl Daemon {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            session: CompilerSession::new(),
+            features: LanguageFeatureFlags::default(),
+            cfg: CfgContext::host(),
+            builds: 0,
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::Compile { cwd, args } => match self.compile(&cwd, args) {
+                Ok(stage) => Response::Compiled {
+                    timings: stage
+                        .profiler
+                        .phases()
+                        .iter()
+                        .map(|phase| (phase.name.clone(), phase.duration))
+                        .collect(),
+                    warnings: stage.warnings.report,
+                    outcome: match stage.result {
+                        CompilationResult::Checked => Outcome::Checked,
+                        CompilationResult::CacheHit(entry) => Outcome::CacheHit(entry.metadata),
+                        CompilationResult::Compiled { artifact, metadata } => Outcome::Built {
+                            binary: artifact.binary,
+                            ir: artifact.ir,
+                            metadata,
+                        },
+                    },
+                },
+                Err(err) => match err.downcast::<Rejected>() {
+                    Ok(rejected) => Response::Rejected {
+                        report: rejected.report,
+                        message: rejected.message,
+                    },
+                    Err(err) => Response::Failed {
+                        message: format!("{err:#}"),
+                    },
+                },
+            },
+            Request::Status => Response::Status {
+                root: self.root.clone(),
+                builds: self.builds,
+            },
+            Request::Shutdown => Response::ShuttingDown,
+        }
+    }
+
+    fn compile(&mut self, cwd: &Path, args: Vec<String>) -> Result<CompilationStage> {
+        let cli = OtterCli::try_parse_from(script_invocation(args))?;
+        let mut settings = CompilationSettings::from_cli(&cli)?;
+        let path = match cli.command() {
+            Command::Run {
+                path: Some(path),
+                code: None,
+            }
+            | Command::Build { path, .. } => path,
+            Command::Check { path } => {
+                settings.check_only = true;
+                path
+            }
+            other => bail!("the daemon does not handle {other:?}"),
+        };
+        let path = cwd.join(path);
+
+        let cfg = settings.cfg();
+        if *settings.language_features() != self.features || cfg != self.cfg {
+            self.features = settings.language_features().clone();
+            self.cfg = cfg;
+            self.session = CompilerSession::new()
+                .with_language_features(self.features.clone())
+                .with_cfg(self.cfg.clone());
+        }
+        self.session.clear_files();
+        self.builds += 1;
+        let source = read_source(&path)?;
+        compile_with_session(&mut self.session, &path, &source, &settings)
+    }
+}
+
+/// Serves requests on `root/.otter/daemon.sock` until asked to shut down
+#[cfg(unix)]
+pub fn serve(root: &Path) -> Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let socket = root.join(SOCKET_PATH);
+    if socket.exists() {
+        if UnixStream::connect(&socket).is_ok() {
+            bail!("a daemon is already listening on {}", socket.display());
+        }
+        std::fs::remove_file(&socket)
+            .with_context(|| format!("failed to remove stale socket {}", socket.display()))?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("failed to listen on {}", socket.display()))?;
+    tracing::info!("otter daemon listening on {}", socket.display());
+
+    let mut daemon = Daemon::new(root.to_path_buf());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                tracing::warn!("failed to accept daemon connection: {err}");
+                continue;
+            }
+        };
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => daemon.handle(request),
+            Err(err) => Response::Failed {
+                message: format!("malformed daemon request: {err}"),
+            },
+        };
+        let shutting_down = matches!(response, Response::ShuttingDown);
+        if let Err(err) = write_message(&stream, &response) {
+            tracing::warn!("failed to answer daemon client: {err}");
+        }
+        if shutting_down {
+            break;
+        }
+    }
+
+    let _ = std::fs::remove_file(&socket);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(_root: &Path) -> Result<()> {
+    bail!("otter daemon needs Unix domain sockets, which this platform does not have")
+}
+
+fn write_message<T: Serialize>(mut stream: impl Write, message: &T) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// The daemon socket serving `dir`, looked up in `dir` and its parents
+pub fn find_socket(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(SOCKET_PATH))
+        .find(|socket| socket.exists())
+}
+
+#[cfg(unix)]
+fn exchange(socket: &Path, request: &Request) -> Result<Response> {
+    let stream = std::os::unix::net::UnixStream::connect(socket)
+        .with_context(|| format!("no daemon is listening on {}", socket.display()))?;
+    write_message(&stream, request)?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    serde_json::from_str(&line).context("malformed daemon response")
+}
+
+#[cfg(not(unix))]
+fn exchange(socket: &Path, _request: &Request) -> Result<Response> {
+    bail!("no daemon is listening on {}", socket.display())
+}
+
+/// Compiles through a running daemon. Returns `None` when no daemon serves
+/// the current directory, so the caller compiles in-process.
+pub fn compile_remote() -> Result<Option<CompilationStage>> {
+    let cwd = std::env::current_dir()?;
+    let Some(socket) = find_socket(&cwd) else {
+        return Ok(None);
+    };
+    let request = Request::Compile {
+        cwd,
+        args: std::env::args().collect(),
+    };
+    let Ok(response) = exchange(&socket, &request) else {
+        tracing::debug!("daemon at {} did not answer", socket.display());
+        return Ok(None);
+    };
+
+    let (timings, outcome) = match response {
+        Response::Compiled {
+            timings,
+            outcome,
+            warnings,
+        } => {
+            eprint!("{warnings}");
+            (timings, outcome)
+        }
+        Response::Rejected { report, message } => {
+            print!("{report}");
+            return Err(anyhow!(message));
+        }
+        Response::Failed { message } => bail!(message),
+        other => bail!("unexpected daemon response: {other:?}"),
+    };
+
+    let mut profiler = Profiler::new();
+    for (name, duration) in timings {
+        profiler.push_phase(name, duration);
+    }
+    let result = match outcome {
+        Outcome::Checked => CompilationResult::Checked,
+        Outcome::CacheHit(metadata) => CompilationResult::CacheHit(CacheEntry {
+            path: metadata.cache_path.clone(),
+            last_modified: metadata.created_at,
+            size: metadata.binary_size,
+            binary_path: metadata.binary_path.clone(),
+            metadata,
+        }),
+        Outcome::Built {
+            binary,
+            ir,
+            metadata,
+        } => CompilationResult::Compiled {
+            artifact: BuildArtifact {
+                binary,
+                ir,
+                opt_report: None,
+                emitted: Vec::new(),
+            },
+            metadata,
+        },
+    };
+    // The warnings were printed with the response
+    Ok(Some(CompilationStage {
+        profiler,
+        result,
+        warnings: Warnings::default(),
+    }))
+}
+
+/// Prints what the daemon serving the current directory is doing
+pub fn status() -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some(socket) = find_socket(&cwd) else {
+        println!("No daemon is running");
+        return Ok(());
+    };
+    match exchange(&socket, &Request::Status) {
+        Ok(Response::Status { root, builds }) => {
+            println!(
+                "Daemon serving {} ({} compilation(s) so far)",
+                root.display(),
+                builds
+            );
+        }
+        Ok(other) => bail!("unexpected daemon response: {other:?}"),
+        Err(_) => println!("No daemon is running (stale socket {})", socket.display()),
+    }
+    Ok(())
+}
+
+/// Asks the daemon serving the current directory to exit
+pub fn stop() -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some(socket) = find_socket(&cwd) else {
+        println!("No daemon is running");
+        return Ok(());
+    };
+    match exchange(&socket, &Request::Shutdown) {
+        Ok(_) => println!("Stopped daemon at {}", socket.display()),
+        Err(_) => {
+            let _ = std::fs::remove_file(&socket);
+            println!("Removed stale socket {}", socket.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::panic, reason = "Panicking on test failures is acceptable")]
+
+    use super::*;
+
+    #[test]
+    fn requests_round_trip_as_single_json_lines() {
+        let request = Request::Compile {
+            cwd: PathBuf::from("/project"),
+            args: vec!["otter".into(), "check".into(), "main.ot".into()],
+        };
+        let mut line = Vec::new();
+        write_message(&mut line, &request).unwrap();
+        assert_eq!(line.iter().filter(|byte| **byte == b'\n').count(), 1);
+
+        let decoded: Request = serde_json::from_slice(&line).unwrap();
+        match decoded {
+            Request::Compile { cwd, args } => {
+                assert_eq!(cwd, PathBuf::from("/project"));
+                assert_eq!(args[1], "check");
+            }
+            other => panic!("expected compile request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn socket_is_found_from_subdirectories() {
+        let root = std::env::temp_dir().join(format!("otter-daemon-{}", std::process::id()));
+        let nested = root.join("src/nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_socket(&nested), None);
+
+        std::fs::create_dir_all(root.join(".otter")).unwrap();
+        std::fs::write(root.join(SOCKET_PATH), "").unwrap();
+        assert_eq!(find_socket(&nested), Some(root.join(SOCKET_PATH)));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
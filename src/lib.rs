@@ -1,6 +1,10 @@
 pub mod cli;
+pub mod daemon;
 pub mod lsp;
+pub mod selftest;
+pub mod session;
 pub mod test;
+pub mod toolchain;
 pub mod tools;
 
 pub use otterc_runtime::*;
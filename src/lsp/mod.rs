@@ -1,17 +1,16 @@
 use std::collections::{BTreeSet, HashMap};
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
 
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use otterc_ast::nodes::{Expr, Function, Node, Program, Statement, Type};
-use otterc_lexer::{LexerError, Token, tokenize};
-use otterc_parser::parse;
+use crate::session::{CancellationToken, Cancelled, CompilerSession, Phase};
+use otterc_ast::nodes::{Derive, Expr, Function, Node, Program, Statement, Type};
+use otterc_lexer::Token;
 use otterc_span::Span;
-use otterc_symbol::registry::SymbolRegistry;
-use otterc_typecheck::{self, TypeChecker};
 use otterc_utils::errors::{
     Diagnostic as OtterDiagnostic, DiagnosticSeverity as OtterDiagSeverity,
 };
@@ -37,6 +36,11 @@ const BUILTIN_FUNCTION_COMPLETIONS: &[(&str, &str)] = &[
     ("type_of", "fn type_of(value: any) -> string"),
     ("fields", "fn fields(obj: any) -> string"),
     ("str", "fn str(value: any) -> string"),
+    ("include_str", "fn include_str(path: string) -> string"),
+    (
+        "include_bytes",
+        "fn include_bytes(path: string) -> list<int>",
+    ),
 ];
 
 const KEYWORD_COMPLETIONS: &[&str] = &[
@@ -124,6 +128,7 @@ enum SymbolKind {
     Struct,
     Enum,
     TypeAlias,
+    Protocol,
     Method,
 }
 
@@ -237,6 +242,18 @@ impl SymbolTable {
         );
     }
 
+    fn add_protocol(&mut self, name: String, span: Span) {
+        self.symbols.insert(
+            name.clone(),
+            SymbolInfo {
+                span,
+                kind: SymbolKind::Protocol,
+                ty: None,
+                callable: None,
+            },
+        );
+    }
+
     fn add_reference(&mut self, name: String, span: Span) {
         self.references.entry(name).or_default().push(span);
     }
@@ -265,12 +282,17 @@ impl SymbolTable {
 struct DocumentStore {
     documents: HashMap<Url, String>,
     symbol_tables: HashMap<Url, SymbolTable>,
+    /// Analyses still running, cancelled when a newer edit arrives
+    pending: HashMap<Url, CancellationToken>,
 }
 
 #[derive(Debug)]
 pub struct Backend {
     client: Client,
     state: Arc<RwLock<DocumentStore>>,
+    /// Kept across edits so unchanged files and functions are not checked
+    /// again
+    session: Arc<Mutex<CompilerSession>>,
 }
 
 impl Backend {
@@ -278,6 +300,7 @@ impl Backend {
         Self {
             client,
             state: Arc::new(RwLock::new(DocumentStore::default())),
+            session: Arc::new(Mutex::new(CompilerSession::new())),
         }
     }
 
@@ -293,6 +316,9 @@ impl Backend {
         {
             let mut state = self.state.write().await;
             state.documents.remove(uri);
+            if let Some(pending) = state.pending.remove(uri) {
+                pending.cancel();
+            }
         }
         let _ = self
             .client
@@ -301,25 +327,39 @@ impl Backend {
     }
 
     async fn publish_diagnostics(&self, uri: Url) {
-        let text = {
-            let state = self.state.read().await;
-            state.documents.get(&uri).cloned()
+        let token = CancellationToken::new();
+        let documents = {
+            let mut state = self.state.write().await;
+            if !state.documents.contains_key(&uri) {
+                return;
+            }
+            if let Some(previous) = state.pending.insert(uri.clone(), token.clone()) {
+                previous.cancel();
+            }
+            state.documents.clone()
         };
 
-        if let Some(text) = text {
-            let (diagnostics, symbol_table) = compute_lsp_diagnostics_and_symbols(&text);
-
-            // Store the symbol table
-            {
-                let mut state = self.state.write().await;
-                state.symbol_tables.insert(uri.clone(), symbol_table);
-            }
+        let analyzed_uri = uri.clone();
+        let session = Arc::clone(&self.session);
+        let analysis = tokio::task::spawn_blocking(move || {
+            compute_lsp_diagnostics_and_symbols(&analyzed_uri, &documents, &session, token)
+        })
+        .await;
+        let Ok(Some((diagnostics, symbol_table))) = analysis else {
+            return;
+        };
 
-            let _ = self
-                .client
-                .publish_diagnostics(uri, diagnostics, None)
-                .await;
+        // Store the symbol table
+        {
+            let mut state = self.state.write().await;
+            state.pending.remove(&uri);
+            state.symbol_tables.insert(uri.clone(), symbol_table);
         }
+
+        let _ = self
+            .client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
     }
 
     #[expect(dead_code, reason = "Work in progress")]
@@ -521,6 +561,7 @@ impl LanguageServer for Backend {
                     SymbolKind::Struct => tower_lsp::lsp_types::SymbolKind::STRUCT,
                     SymbolKind::Enum => tower_lsp::lsp_types::SymbolKind::ENUM,
                     SymbolKind::TypeAlias => tower_lsp::lsp_types::SymbolKind::TYPE_PARAMETER,
+                    SymbolKind::Protocol => tower_lsp::lsp_types::SymbolKind::INTERFACE,
                     SymbolKind::Method => tower_lsp::lsp_types::SymbolKind::METHOD,
                 };
                 #[expect(
@@ -567,6 +608,7 @@ impl LanguageServer for Backend {
                             SymbolKind::TypeAlias => {
                                 tower_lsp::lsp_types::SymbolKind::TYPE_PARAMETER
                             }
+                            SymbolKind::Protocol => tower_lsp::lsp_types::SymbolKind::INTERFACE,
                             SymbolKind::Method => tower_lsp::lsp_types::SymbolKind::METHOD,
                         };
                         #[expect(
@@ -662,6 +704,7 @@ impl LanguageServer for Backend {
                 SymbolKind::Struct => "struct",
                 SymbolKind::Enum => "enum",
                 SymbolKind::TypeAlias => "type",
+                SymbolKind::Protocol => "protocol",
                 SymbolKind::Method => "method",
             };
             let detail = symbol_info
@@ -732,6 +775,7 @@ impl LanguageServer for Backend {
                     SymbolKind::Struct => CompletionItemKind::STRUCT,
                     SymbolKind::Enum => CompletionItemKind::ENUM,
                     SymbolKind::TypeAlias => CompletionItemKind::TYPE_PARAMETER,
+                    SymbolKind::Protocol => CompletionItemKind::INTERFACE,
                     SymbolKind::Method => CompletionItemKind::METHOD,
                 };
                 items.push(CompletionItem {
@@ -853,7 +897,7 @@ impl LanguageServer for Backend {
                     SymbolKind::Parameter => 2,                     // PARAMETER
                     SymbolKind::Struct => 4,                        // CLASS
                     SymbolKind::Enum => 5,                          // ENUM
-                    SymbolKind::TypeAlias => 3,                     // TYPE
+                    SymbolKind::TypeAlias | SymbolKind::Protocol => 3, // TYPE
                 };
 
                 let delta_line = pos.line as u32 - prev_line;
@@ -978,7 +1022,7 @@ fn build_symbol_table_from_statements(
     for stmt in statements {
         let span = stmt.span();
         match stmt.as_ref() {
-            Statement::Let { name, ty, expr, .. } => {
+            Statement::Let { name, ty, expr, .. } | Statement::Const { name, ty, expr, .. } => {
                 let ty_str = ty
                     .as_ref()
                     .map(|ty| format_type(ty.as_ref()))
@@ -1008,28 +1052,50 @@ fn build_symbol_table_from_statements(
                     text,
                 );
             }
-            Statement::Struct { name, methods, .. } => {
+            Statement::Struct {
+                name,
+                methods,
+                derives,
+                ..
+            } => {
                 if let Some(span) = find_name_span(name, tokens, text) {
                     table.add_struct(name.clone(), span);
                 }
                 for method in methods {
-                    if let Some(span) = find_name_span(&method.as_ref().name, tokens, text) {
+                    if let Some(span) = method_span(method, derives, tokens, text) {
                         let sig = format_function_signature(method.as_ref());
                         let callable = Some(CallableInfo::from_function(method.as_ref()));
                         table.add_method(method.as_ref().name.clone(), span, Some(sig), callable);
                     }
                 }
             }
-            Statement::Enum { name, .. } => {
+            Statement::Enum {
+                name,
+                methods,
+                derives,
+                ..
+            } => {
                 if let Some(span) = find_name_span(name, tokens, text) {
                     table.add_enum(name.clone(), span);
                 }
+                for method in methods {
+                    if let Some(span) = method_span(method, derives, tokens, text) {
+                        let sig = format_function_signature(method.as_ref());
+                        let callable = Some(CallableInfo::from_function(method.as_ref()));
+                        table.add_method(method.as_ref().name.clone(), span, Some(sig), callable);
+                    }
+                }
             }
             Statement::TypeAlias { name, .. } => {
                 if let Some(span) = find_name_span(name, tokens, text) {
                     table.add_type_alias(name.clone(), span);
                 }
             }
+            Statement::Protocol { name, .. } => {
+                if let Some(span) = find_name_span(name, tokens, text) {
+                    table.add_protocol(name.clone(), span);
+                }
+            }
             Statement::If {
                 then_block,
                 elif_blocks,
@@ -1066,7 +1132,13 @@ fn build_symbol_table_from_statements(
             Statement::While { body, .. } => {
                 build_symbol_table_from_statements(&body.as_ref().statements, table, tokens, text);
             }
-            Statement::Block(block) => {
+            Statement::With { alias, body, .. } => {
+                if let Some(alias) = alias {
+                    table.add_variable(alias.as_ref().clone(), *span, None);
+                }
+                build_symbol_table_from_statements(&body.as_ref().statements, table, tokens, text);
+            }
+            Statement::Block(block) | Statement::Init(block) => {
                 build_symbol_table_from_statements(&block.as_ref().statements, table, tokens, text);
             }
             _ => {}
@@ -1104,9 +1176,19 @@ fn collect_references_from_statements(
                     text,
                 );
             }
-            Statement::Let { expr, .. } | Statement::Expr(expr) | Statement::Return(Some(expr)) => {
+            Statement::Let { expr, .. }
+            | Statement::Const { expr, .. }
+            | Statement::Expr(expr)
+            | Statement::Return(Some(expr))
+            | Statement::Defer(expr) => {
                 collect_references_from_expr(expr.as_ref(), table, tokens, text);
             }
+            Statement::Assert { cond, message } => {
+                collect_references_from_expr(cond.as_ref(), table, tokens, text);
+                if let Some(message) = message {
+                    collect_references_from_expr(message.as_ref(), table, tokens, text);
+                }
+            }
             Statement::If {
                 cond,
                 then_block,
@@ -1143,7 +1225,10 @@ fn collect_references_from_statements(
                 collect_references_from_expr(iterable.as_ref(), table, tokens, text);
                 collect_references_from_statements(&body.as_ref().statements, table, tokens, text);
             }
-            Statement::While { cond, body } => {
+            Statement::While { cond, body, .. }
+            | Statement::With {
+                expr: cond, body, ..
+            } => {
                 collect_references_from_expr(cond.as_ref(), table, tokens, text);
                 collect_references_from_statements(&body.as_ref().statements, table, tokens, text);
             }
@@ -1178,7 +1263,7 @@ fn collect_references_from_expr(
             collect_references_from_expr(left.as_ref().as_ref(), table, tokens, text);
             collect_references_from_expr(right.as_ref().as_ref(), table, tokens, text);
         }
-        Expr::Unary { expr, .. } => {
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => {
             collect_references_from_expr(expr.as_ref().as_ref(), table, tokens, text);
         }
         Expr::If {
@@ -1207,6 +1292,23 @@ fn collect_references_from_expr(
     }
 }
 
+/// Where a method is defined: its name, or the derive that generated it
+/// for a method from `@derive(...)`, which has no source of its own
+fn method_span(
+    method: &Node<Function>,
+    derives: &[Node<Derive>],
+    tokens: &[Token],
+    text: &str,
+) -> Option<Span> {
+    if method.span().start() >= text.len() {
+        return derives
+            .iter()
+            .find(|derive| derive.as_ref().method() == method.as_ref().name)
+            .map(|derive| *derive.span());
+    }
+    find_name_span(&method.as_ref().name, tokens, text)
+}
+
 /// Find span of a name in tokens (approximate)
 fn find_name_span(name: &str, tokens: &[Token], _text: &str) -> Option<Span> {
     for token in tokens {
@@ -1268,6 +1370,11 @@ fn format_type(ty: &Type) -> String {
             let args_str: Vec<String> = args.iter().map(|t| format_type(t.as_ref())).collect();
             format!("{}<{}>", base, args_str.join(", "))
         }
+        Type::Union(members) => members
+            .iter()
+            .map(|member| format_type(member.as_ref()))
+            .collect::<Vec<_>>()
+            .join(" | "),
     }
 }
 
@@ -1276,61 +1383,64 @@ fn infer_type_from_expr(_expr: &Expr) -> Option<String> {
     None // Could be enhanced with type inference
 }
 
-/// Compute diagnostics and build symbol table from source text
-fn compute_lsp_diagnostics_and_symbols(text: &str) -> (Vec<Diagnostic>, SymbolTable) {
-    let source_id = "lsp";
-    match tokenize(text) {
-        Ok(tokens) => match parse(&tokens) {
-            Ok(program) => {
-                // Build symbol table from the parsed program
-                let symbol_table = build_symbol_table(&program, &tokens, text);
-
-                let diagnostics = {
-                    let mut checker = TypeChecker::new().with_registry(SymbolRegistry::global());
-                    if checker.check_program(&program).is_err() {
-                        otterc_typecheck::diagnostics_from_type_errors(
-                            checker.errors(),
-                            source_id,
-                            text,
-                        )
-                        .into_iter()
-                        .map(|diag| otter_diag_to_lsp(DiagnosticKind::Type, &diag, text))
-                        .collect()
-                    } else {
-                        Vec::new()
-                    }
-                };
-
-                (diagnostics, symbol_table)
-            }
-            Err(errors) => {
-                let diagnostics = errors
-                    .into_iter()
-                    .map(|err| {
-                        otter_diag_to_lsp(
-                            DiagnosticKind::Parser,
-                            &err.to_diagnostic(source_id),
-                            text,
-                        )
-                    })
-                    .collect();
-                (diagnostics, SymbolTable::new())
-            }
-        },
-        Err(errors) => {
-            let diagnostics = errors
-                .into_iter()
-                .map(|err| {
-                    otter_diag_to_lsp(
-                        DiagnosticKind::Lexer,
-                        &lexer_error_to_diag(source_id, &err),
-                        text,
-                    )
-                })
-                .collect();
-            (diagnostics, SymbolTable::new())
-        }
+/// Compute diagnostics and build symbol table for `uri`, reading open
+/// documents instead of their files on disk. Only the functions changed since
+/// the previous analysis are type checked again. Returns `None` when a newer
+/// edit cancelled the analysis.
+fn compute_lsp_diagnostics_and_symbols(
+    uri: &Url,
+    documents: &HashMap<Url, String>,
+    session: &Mutex<CompilerSession>,
+    token: CancellationToken,
+) -> Option<(Vec<Diagnostic>, SymbolTable)> {
+    let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+    if token.is_cancelled() {
+        return None;
     }
+    session.set_cancellation(token);
+    session.clear_files();
+    for (document, text) in documents {
+        session.set_file(document_path(document), text.clone());
+    }
+    let path = document_path(uri);
+    let text = documents.get(uri)?;
+
+    let analysis = match session.check(&path) {
+        Ok(analysis) => analysis,
+        Err(err) if err.is::<Cancelled>() => return None,
+        Err(err) => {
+            let diagnostic = OtterDiagnostic::error("lsp", Span::new(0, 0), format!("{err:#}"));
+            return Some((
+                vec![otter_diag_to_lsp(DiagnosticKind::Module, &diagnostic, text)],
+                SymbolTable::new(),
+            ));
+        }
+    };
+
+    let kind = match analysis.failed {
+        Some(Phase::Lexing) => DiagnosticKind::Lexer,
+        Some(Phase::Parsing) => DiagnosticKind::Parser,
+        _ => DiagnosticKind::Type,
+    };
+    let diagnostics = analysis
+        .diagnostics
+        .iter()
+        .map(|diag| otter_diag_to_lsp(kind, diag, text))
+        .collect();
+    // Build symbol table from the parsed program
+    let symbol_table = analysis
+        .program
+        .as_ref()
+        .map(|program| build_symbol_table(program, &analysis.tokens, text))
+        .unwrap_or_else(SymbolTable::new);
+    Some((diagnostics, symbol_table))
+}
+
+/// File a document is analyzed as. Unsaved documents without a file keep
+/// their URI path, which only the session's overlay knows about.
+fn document_path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.path()))
 }
 
 fn word_at_position(text: &str, position: Position) -> Option<String> {
@@ -1375,6 +1485,7 @@ fn collect_identifiers(text: &str) -> Vec<String> {
 enum DiagnosticKind {
     Lexer,
     Parser,
+    Module,
     Type,
 }
 
@@ -1383,15 +1494,12 @@ impl DiagnosticKind {
         match self {
             DiagnosticKind::Lexer => "lexer",
             DiagnosticKind::Parser => "parser",
+            DiagnosticKind::Module => "module",
             DiagnosticKind::Type => "typecheck",
         }
     }
 }
 
-fn lexer_error_to_diag(source: &str, err: &LexerError) -> OtterDiagnostic {
-    err.to_diagnostic(source)
-}
-
 fn otter_diag_to_lsp(kind: DiagnosticKind, diag: &OtterDiagnostic, text: &str) -> Diagnostic {
     let range = span_to_range(diag.span(), text);
     let mut message = diag.message().to_string();
@@ -1421,7 +1529,10 @@ fn otter_diag_to_lsp(kind: DiagnosticKind, diag: &OtterDiagnostic, text: &str) -
         source: Some("otterlang".into()),
         message,
         related_information: None,
-        tags: None,
+        // Lets editors fade out unused bindings and imports, and dead code
+        tags: (diag.severity() == OtterDiagSeverity::Warning
+            && (diag.message().starts_with("unused ") || diag.message() == "unreachable code"))
+            .then(|| vec![DiagnosticTag::UNNECESSARY]),
         data: None,
     }
 }
@@ -1578,6 +1689,8 @@ mod tests {
     #![expect(clippy::panic, reason = "Panicking on test failures is acceptable")]
 
     use super::*;
+    use otterc_lexer::tokenize;
+    use otterc_parser::parse;
 
     #[test]
     fn test_build_symbol_table() {
@@ -38,7 +38,7 @@ mod tests {
             "target/app",
         ]);
         match cli.command() {
-            Command::Build { path, output } => {
+            Command::Build { path, output, .. } => {
                 assert_eq!(path.to_string_lossy(), "examples/app.ot");
                 assert_eq!(
                     output.as_ref().map(|p| p.to_string_lossy().into_owned()),
@@ -48,4 +48,27 @@ mod tests {
             other => panic!("expected build command, got {other:?}"),
         }
     }
+
+    #[test]
+    fn build_command_takes_debug_info_flag() {
+        let cli = OtterCli::parse_from(["otter", "build", "-g", "examples/app.ot"]);
+        match cli.command() {
+            Command::Build { debug_info, .. } => assert!(debug_info),
+            other => panic!("expected build command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn build_command_takes_comma_separated_sanitizers() {
+        let cli = OtterCli::parse_from([
+            "otter",
+            "build",
+            "--sanitize=address,undefined",
+            "examples/app.ot",
+        ]);
+        match cli.command() {
+            Command::Build { sanitize, .. } => assert_eq!(sanitize, &["address", "undefined"]),
+            other => panic!("expected build command, got {other:?}"),
+        }
+    }
 }
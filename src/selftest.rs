@@ -0,0 +1,358 @@
+//! Backend conformance checks for `otter selftest-backends`
+//!
+//! Every program is compiled and run under each backend, and what it prints
+//! and how it exits are compared between the backends and with the program's
+//! golden file, `<program>.expected` next to it. A program without a golden
+//! file is only compared between backends; `--bless` writes one from the
+//! first backend's run.
+//!
+//! LLVM is the only code generator, so the backends are its default and its
+//! release pipelines, which is where codegen and optimizer bugs show up as
+//! different output. Another code generator becomes one more [`Backend`].
+
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::cli::{CompilationResult, CompilationSettings, compile_quietly, read_source};
+use otterc_config::LtoMode;
+
+/// A way of compiling and running a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// LLVM with the default pipeline, as `otter run` compiles
+    Llvm,
+    /// LLVM with the `--release` pipeline and thin LTO
+    LlvmRelease,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Llvm, Backend::LlvmRelease];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Llvm => "llvm",
+            Backend::LlvmRelease => "llvm-release",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|backend| backend.name()).collect();
+                anyhow!("unknown backend '{}' (expected {})", name, names.join(", "))
+            })
+    }
+
+    fn settings(self, base: &CompilationSettings) -> CompilationSettings {
+        let mut settings = base.clone();
+        settings.release = matches!(self, Backend::LlvmRelease);
+        if settings.release && !settings.lto.is_enabled() {
+            settings.lto = LtoMode::Thin;
+        }
+        settings
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What a run printed to stdout and how it ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Exited { stdout: String, code: Option<i32> },
+    CompileError(String),
+    TimedOut { stdout: String },
+}
+
+impl Outcome {
+    /// The outcome as a golden file holds it: the output, followed by the
+    /// exit code when it is not 0
+    pub fn render(&self) -> String {
+        match self {
+            Outcome::Exited {
+                stdout,
+                code: Some(0),
+            } => stdout.clone(),
+            Outcome::Exited { stdout, code } => {
+                let code = code.map_or_else(|| "signal".to_string(), |code| code.to_string());
+                format!("{stdout}[exit {code}]\n")
+            }
+            Outcome::CompileError(message) => format!("[compile error]\n{message}\n"),
+            Outcome::TimedOut { stdout } => format!("{stdout}[timed out]\n"),
+        }
+    }
+}
+
+/// The result of checking one program
+#[derive(Debug)]
+pub struct ProgramReport {
+    pub path: PathBuf,
+    pub outcomes: Vec<(Backend, Outcome)>,
+    /// What the runs differed in, empty when they all agree with each other
+    /// and with the golden file
+    pub problems: Vec<String>,
+    /// Whether a golden file was written
+    pub blessed: bool,
+}
+
+impl ProgramReport {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Where the golden file of `program` is
+pub fn golden_path(program: &Path) -> PathBuf {
+    program.with_extension("expected")
+}
+
+/// The `.ot` files in `paths`, searching directories recursively, in order
+pub fn discover(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut programs = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_programs(path, &mut programs)?;
+        } else if path.exists() {
+            programs.push(path.clone());
+        } else {
+            bail!("{} does not exist", path.display());
+        }
+    }
+    programs.sort();
+    programs.dedup();
+    Ok(programs)
+}
+
+fn collect_programs(dir: &Path, programs: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_programs(&path, programs)?;
+        } else if path.extension().is_some_and(|ext| ext == "ot") {
+            programs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compiles `program` for `backend` and runs it with no input, stopping it
+/// after `timeout`
+pub fn run_program(
+    program: &Path,
+    backend: Backend,
+    settings: &CompilationSettings,
+    timeout: Duration,
+) -> Result<Outcome> {
+    let settings = backend.settings(settings);
+    let source = read_source(program)?;
+    let stage = match compile_quietly(program, &source, &settings) {
+        Ok(stage) => stage,
+        Err(err) => return Ok(Outcome::CompileError(format!("{err:#}"))),
+    };
+    let binary = match &stage.result {
+        CompilationResult::CacheHit(entry) => entry.binary_path.clone(),
+        CompilationResult::Compiled { artifact, .. } => artifact.binary.clone(),
+        CompilationResult::Checked => unreachable!("selftest builds are never only checked"),
+    };
+
+    let mut command = settings.program_command(&binary);
+    settings.apply_runtime_env(&mut command);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {}", binary.display()))?;
+    let mut pipe = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("no stdout for {}", binary.display()))?;
+    // Read while the program runs, so it never blocks on a full pipe
+    let reader = thread::spawn(move || {
+        let mut stdout = Vec::new();
+        let _ = pipe.read_to_end(&mut stdout);
+        String::from_utf8_lossy(&stdout).into_owned()
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    // A reader that panicked leaves the output empty
+    let stdout = reader.join().unwrap_or_default();
+    Ok(match status {
+        Some(status) => Outcome::Exited {
+            stdout,
+            code: status.code(),
+        },
+        None => Outcome::TimedOut { stdout },
+    })
+}
+
+/// Runs `program` under every backend in `backends` and compares the runs.
+/// With `bless`, a missing or different golden file is replaced by the first
+/// backend's run instead of being reported.
+pub fn check_program(
+    program: &Path,
+    backends: &[Backend],
+    settings: &CompilationSettings,
+    timeout: Duration,
+    bless: bool,
+) -> Result<ProgramReport> {
+    let mut outcomes = Vec::with_capacity(backends.len());
+    for &backend in backends {
+        outcomes.push((backend, run_program(program, backend, settings, timeout)?));
+    }
+
+    let golden = golden_path(program);
+    let expected = if golden.exists() {
+        Some(read_source(&golden)?)
+    } else {
+        None
+    };
+    let mut problems = compare(&outcomes, if bless { None } else { expected.as_deref() });
+
+    let mut blessed = false;
+    if bless
+        && problems.is_empty()
+        && let Some((_, first)) = outcomes.first()
+    {
+        let rendered = first.render();
+        if expected.as_deref() != Some(rendered.as_str()) {
+            fs::write(&golden, rendered)
+                .with_context(|| format!("failed to write {}", golden.display()))?;
+            blessed = true;
+        }
+    } else if bless && outcomes.is_empty() {
+        problems.push("no backend to bless from".to_string());
+    }
+
+    Ok(ProgramReport {
+        path: program.to_path_buf(),
+        outcomes,
+        problems,
+        blessed,
+    })
+}
+
+/// How the runs disagree with each other and with `expected`
+pub fn compare(outcomes: &[(Backend, Outcome)], expected: Option<&str>) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some((reference, first)) = outcomes.first() {
+        let first = first.render();
+        for (backend, outcome) in &outcomes[1..] {
+            if let Some(difference) = first_difference(&first, &outcome.render()) {
+                problems.push(format!("{reference} and {backend} differ at {difference}"));
+            }
+        }
+    }
+    if let Some(expected) = expected {
+        for (backend, outcome) in outcomes {
+            if let Some(difference) = first_difference(expected, &outcome.render()) {
+                problems.push(format!(
+                    "{backend} differs from the golden file at {difference}"
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// The first line `actual` differs from `expected` in, if any
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(a), Some(b)) if a == b => line += 1,
+            (a, b) => {
+                let show = |text: Option<&str>| {
+                    text.map_or_else(|| "end of output".to_string(), |text| format!("`{text}`"))
+                };
+                return Some(format!("line {line}: {} vs {}", show(a), show(b)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exited(stdout: &str, code: i32) -> Outcome {
+        Outcome::Exited {
+            stdout: stdout.to_string(),
+            code: Some(code),
+        }
+    }
+
+    #[test]
+    fn golden_files_hold_output_and_failing_exit_codes() {
+        assert_eq!(exited("1\n2\n", 0).render(), "1\n2\n");
+        assert_eq!(exited("1\n", 3).render(), "1\n[exit 3]\n");
+        assert_eq!(
+            Outcome::TimedOut {
+                stdout: String::new()
+            }
+            .render(),
+            "[timed out]\n"
+        );
+    }
+
+    #[test]
+    fn divergent_backends_are_reported_at_the_first_different_line() {
+        let agreeing = [
+            (Backend::Llvm, exited("a\nb\n", 0)),
+            (Backend::LlvmRelease, exited("a\nb\n", 0)),
+        ];
+        assert!(compare(&agreeing, Some("a\nb\n")).is_empty());
+
+        let diverging = [
+            (Backend::Llvm, exited("a\n0.5\n", 0)),
+            (Backend::LlvmRelease, exited("a\n0\n", 0)),
+        ];
+        assert_eq!(
+            compare(&diverging, None),
+            ["llvm and llvm-release differ at line 2: `0.5` vs `0`"]
+        );
+
+        let short = [(Backend::Llvm, exited("a\n", 0))];
+        assert_eq!(
+            compare(&short, Some("a\nb\n")),
+            ["llvm differs from the golden file at line 2: `b` vs end of output"]
+        );
+    }
+
+    #[test]
+    fn backends_are_named_on_the_command_line() {
+        assert_eq!(
+            Backend::parse("llvm-release").unwrap(),
+            Backend::LlvmRelease
+        );
+        assert!(Backend::parse("cranelift").is_err());
+    }
+}
@@ -0,0 +1,683 @@
+//! The compiler pipeline as a library
+//!
+//! A [`CompilerSession`] runs lexing, parsing, module resolution and type
+//! checking without printing anything, and hands back the tokens, the AST,
+//! the inferred types and the diagnostics. Sources are read from an in-memory
+//! file overlay before the file system, so editors can check unsaved buffers.
+//! The CLI, the language server and embedders all drive the pipeline through
+//! this type.
+//!
+//! A session remembers what it computed. Lexing and parsing a file is skipped
+//! while its contents stay the same, and [`CompilerSession::check`] type
+//! checks again only the functions an edit touched, which keeps an editor
+//! responsive on every keystroke.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context, Result, bail};
+use otterc_ast::nodes::{Program, Statement};
+use otterc_codegen::{BuildArtifact, build_executable};
+use otterc_config::{CfgContext, CodegenOptions, LanguageFeatureFlags};
+use otterc_ffi::{BridgeSymbolRegistry, CargoBridge, FunctionSpec, RustStubGenerator, TypeSpec};
+use otterc_lexer::{Token, tokenize};
+use otterc_module::{ModuleProcessor, resolve_includes};
+use otterc_parser::parse;
+use otterc_span::Span;
+use otterc_symbol::registry::{
+    Capability, FfiFunction, FfiSignature, FfiType, SymbolRegistry, UnmappedFunction,
+};
+use otterc_typecheck::{EnumLayout, FunctionCache, TypeChecker, TypeInfo};
+use otterc_utils::errors::Diagnostic;
+use otterc_utils::profiler::{PhaseTiming, Profiler};
+
+/// Shared flag that stops a running session at the next phase boundary
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Error returned when a session is cancelled before it finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("compilation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// The phase that rejected a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Lexing,
+    Parsing,
+    TypeChecking,
+}
+
+/// Types the checker inferred for a program, keyed the way codegen expects
+pub struct TypedProgram {
+    pub expr_types: HashMap<usize, TypeInfo>,
+    pub expr_types_by_span: HashMap<Span, TypeInfo>,
+    pub comprehension_var_types: HashMap<Span, TypeInfo>,
+    pub enum_layouts: HashMap<String, EnumLayout>,
+}
+
+/// Everything the front end learned about one source file
+pub struct Analysis {
+    pub path: PathBuf,
+    pub source_id: String,
+    pub source: String,
+    /// Empty when lexing failed
+    pub tokens: Vec<Token>,
+    /// `None` when lexing or parsing failed
+    pub program: Option<Program>,
+    /// Files of the modules the program imports
+    pub modules: Vec<PathBuf>,
+    /// Errors of the phase that failed, followed by the type checker's
+    /// warnings
+    pub diagnostics: Vec<Diagnostic>,
+    /// The phase whose errors are in `diagnostics`, if any
+    pub failed: Option<Phase>,
+    /// Present once the program type checks. `expr_types` is keyed by the
+    /// addresses of nodes in `program`, so the two stay together.
+    pub types: Option<TypedProgram>,
+    pub timings: Vec<PhaseTiming>,
+}
+
+impl Analysis {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_none()
+    }
+}
+
+/// Lexing and parsing results for one version of a file
+#[derive(Clone)]
+struct Parsed {
+    source: String,
+    tokens: Vec<Token>,
+    program: std::result::Result<Program, (Phase, Vec<Diagnostic>)>,
+}
+
+/// Results of earlier runs, reused while the inputs they came from are
+/// unchanged
+#[derive(Default)]
+struct Queries {
+    parsed: HashMap<PathBuf, Parsed>,
+    /// Per file, the fingerprint of its imported modules and the function
+    /// results of its last check
+    functions: HashMap<PathBuf, (u64, FunctionCache)>,
+}
+
+/// Front end and codegen driver with an in-memory file overlay
+pub struct CompilerSession {
+    files: HashMap<PathBuf, String>,
+    language_features: LanguageFeatureFlags,
+    cfg: CfgContext,
+    stdlib_dir: Option<PathBuf>,
+    registry: &'static SymbolRegistry,
+    cancellation: CancellationToken,
+    queries: Mutex<Queries>,
+}
+
+impl fmt::Debug for CompilerSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompilerSession")
+            .field("files", &self.files.keys().collect::<Vec<_>>())
+            .field("stdlib_dir", &self.stdlib_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for CompilerSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompilerSession {
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            language_features: LanguageFeatureFlags::default(),
+            cfg: CfgContext::host(),
+            stdlib_dir: find_stdlib_dir().ok(),
+            registry: SymbolRegistry::global(),
+            cancellation: CancellationToken::new(),
+            queries: Mutex::new(Queries::default()),
+        }
+    }
+
+    pub fn with_language_features(mut self, features: LanguageFeatureFlags) -> Self {
+        self.language_features = features;
+        self
+    }
+
+    /// Target and features `@cfg(...)` attributes are evaluated against;
+    /// the host machine unless set
+    pub fn with_cfg(mut self, cfg: CfgContext) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    pub fn cfg(&self) -> &CfgContext {
+        &self.cfg
+    }
+
+    pub fn with_stdlib_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.stdlib_dir = dir;
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Replaces the token, for a long-lived session that starts each run
+    /// with a fresh one
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Token that cancels this session from another thread
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Uses `source` as the contents of `path`, for the file itself and for
+    /// any module that imports it
+    pub fn set_file(&mut self, path: impl Into<PathBuf>, source: impl Into<String>) {
+        let path = path.into();
+        let path = path.canonicalize().unwrap_or(path);
+        self.files.insert(path, source.into());
+    }
+
+    /// Goes back to reading `path` from disk
+    pub fn remove_file(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files.remove(&path);
+    }
+
+    /// Empties the overlay. Results computed from the removed sources stay
+    /// cached and are reused if the same sources come back.
+    pub fn clear_files(&mut self) {
+        self.files.clear();
+    }
+
+    /// Contents of `path`, from the overlay if it has them
+    pub fn read_file(&self, path: &Path) -> Result<String> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(source) = self.files.get(&canonical) {
+            return Ok(source.clone());
+        }
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Lexes, parses, resolves imports and type checks `path`. Errors in the
+    /// program are returned as diagnostics; `Err` means the session was
+    /// cancelled or an import could not be loaded.
+    pub fn analyze(&self, path: &Path) -> Result<Analysis> {
+        self.run(path, false)
+    }
+
+    /// Like [`analyze`](Self::analyze), but only the functions whose text
+    /// changed since the last check of `path` are type checked again. The
+    /// result has diagnostics but no `types`, so it cannot be built.
+    pub fn check(&self, path: &Path) -> Result<Analysis> {
+        self.run(path, true)
+    }
+
+    fn queries(&self) -> std::sync::MutexGuard<'_, Queries> {
+        self.queries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The parse query: tokens and AST of `source`, reused while the file
+    /// has the same contents
+    fn parse_file(&self, path: &Path, source: &str, profiler: &mut Profiler) -> Result<Parsed> {
+        if let Some(parsed) = self.queries().parsed.get(path)
+            && parsed.source == source
+        {
+            return Ok(parsed.clone());
+        }
+
+        let source_id = path.display().to_string();
+        let mut tokens = Vec::new();
+        self.check_cancelled()?;
+        let program = match profiler.record_phase("Lexing", || tokenize(source)) {
+            Ok(lexed) => {
+                tokens = lexed;
+                self.check_cancelled()?;
+                profiler
+                    .record_phase("Parsing", || parse(&tokens))
+                    .map_err(|errors| {
+                        let diagnostics = errors
+                            .iter()
+                            .map(|err| err.to_diagnostic(&source_id))
+                            .collect();
+                        (Phase::Parsing, diagnostics)
+                    })
+            }
+            Err(errors) => {
+                let diagnostics = errors
+                    .iter()
+                    .map(|err| err.to_diagnostic(&source_id))
+                    .collect();
+                Err((Phase::Lexing, diagnostics))
+            }
+        };
+
+        let parsed = Parsed {
+            source: source.to_string(),
+            tokens,
+            program,
+        };
+        self.queries()
+            .parsed
+            .insert(path.to_path_buf(), parsed.clone());
+        Ok(parsed)
+    }
+
+    /// Hash of the sources of the modules a file imports. When it changes,
+    /// any function of the file may check differently.
+    fn modules_fingerprint(&self, modules: &[PathBuf]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut modules = modules.to_vec();
+        modules.sort();
+        for module in &modules {
+            module.hash(&mut hasher);
+            self.read_file(module).ok().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn run(&self, path: &Path, incremental: bool) -> Result<Analysis> {
+        let source = self.read_file(path)?;
+        let mut profiler = Profiler::new();
+        let mut analysis = Analysis {
+            path: path.to_path_buf(),
+            source_id: path.display().to_string(),
+            source,
+            tokens: Vec::new(),
+            program: None,
+            modules: Vec::new(),
+            diagnostics: Vec::new(),
+            failed: None,
+            types: None,
+            timings: Vec::new(),
+        };
+
+        let parsed = self.parse_file(path, &analysis.source, &mut profiler)?;
+        analysis.tokens = parsed.tokens;
+        let mut program = match parsed.program {
+            Ok(program) => program,
+            Err((phase, diagnostics)) => {
+                analysis.diagnostics = diagnostics;
+                analysis.failed = Some(phase);
+                analysis.timings = profiler.phases().to_vec();
+                return Ok(analysis);
+            }
+        };
+
+        program.retain_cfg(|key, value| self.cfg.matches(key, value));
+
+        self.check_cancelled()?;
+        let source_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let confined = self.registry.is_denied(Capability::Filesystem);
+        let include_errors = resolve_includes(&mut program, &source_dir, confined, |file| {
+            let canonical = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+            match self.files.get(&canonical) {
+                Some(source) => Ok(source.clone().into_bytes()),
+                None => fs::read(file),
+            }
+        });
+        let derive_errors = program.expand_derives();
+        let mut module_processor = ModuleProcessor::new(source_dir, self.stdlib_dir.clone());
+        module_processor.set_cfg(self.cfg.clone());
+        module_processor.set_confined_includes(confined);
+        for (file, source) in &self.files {
+            module_processor.set_source(file.clone(), source.clone());
+        }
+        analysis.modules = profiler.record_phase("Module Resolution", || {
+            module_processor.process_imports(&program)
+        })?;
+        profiler.record_phase("Re-export Resolution", || {
+            module_processor.resolve_all_re_exports()
+        })?;
+
+        self.check_cancelled()?;
+        profiler.record_phase("Register FFI Functions", || {
+            register_rust_ffi_functions_for_typecheck(&program, self.registry)
+        })?;
+
+        self.check_cancelled()?;
+        let mut type_checker = TypeChecker::with_language_features(self.language_features.clone())
+            .with_registry(self.registry);
+        for module in module_processor.modules() {
+            type_checker.register_module_definitions(&module.program);
+        }
+        profiler.record_phase("Parameter Inference", || {
+            type_checker.infer_parameter_types(&mut program)
+        });
+        let checked = if incremental {
+            let modules = self.modules_fingerprint(&analysis.modules);
+            let mut queries = self.queries();
+            let (fingerprint, cache) = queries
+                .functions
+                .entry(path.to_path_buf())
+                .or_insert_with(|| (modules, FunctionCache::new()));
+            if *fingerprint != modules {
+                *fingerprint = modules;
+                cache.clear();
+            }
+            profiler.record_phase("Type Checking", || {
+                type_checker.check_program_incremental(&program, &analysis.source, cache)
+            })
+        } else {
+            profiler.record_phase("Type Checking", || type_checker.check_program(&program))
+        };
+        if checked.is_err() {
+            analysis.diagnostics = otterc_typecheck::diagnostics_from_type_errors(
+                type_checker.errors(),
+                &analysis.source_id,
+                &analysis.source,
+            );
+            analysis.failed = Some(Phase::TypeChecking);
+        }
+        // Unresolved includes were checked as calls and derives that could not
+        // be expanded left methods missing, so their errors come first
+        if !include_errors.is_empty() || !derive_errors.is_empty() {
+            let errors = include_errors
+                .iter()
+                .map(|error| (error.span, &error.message, &error.help))
+                .chain(
+                    derive_errors
+                        .iter()
+                        .map(|error| (error.span, &error.message, &error.help)),
+                );
+            let mut diagnostics: Vec<Diagnostic> = errors
+                .map(|(span, message, help)| {
+                    let diagnostic = Diagnostic::error(&analysis.source_id, span, message);
+                    match help {
+                        Some(help) => diagnostic.with_help(help),
+                        None => diagnostic,
+                    }
+                })
+                .collect();
+            diagnostics.append(&mut analysis.diagnostics);
+            analysis.diagnostics = diagnostics;
+            analysis.failed = Some(Phase::TypeChecking);
+        }
+        analysis
+            .diagnostics
+            .extend(otterc_typecheck::diagnostics_from_type_warnings(
+                type_checker.warnings(),
+                &analysis.source_id,
+                &analysis.source,
+            ));
+        if analysis.failed.is_none() && !incremental {
+            let enum_layouts = type_checker.enum_layouts();
+            let (expr_types, expr_types_by_span, comprehension_var_types) =
+                type_checker.into_type_maps();
+            analysis.types = Some(TypedProgram {
+                expr_types,
+                expr_types_by_span,
+                comprehension_var_types,
+                enum_layouts,
+            });
+        }
+
+        analysis.program = Some(program);
+        analysis.timings = profiler.phases().to_vec();
+        Ok(analysis)
+    }
+
+    /// Generates a native executable at `output` for an analysis that type
+    /// checked
+    pub fn build(
+        &self,
+        analysis: &Analysis,
+        output: &Path,
+        options: &CodegenOptions,
+    ) -> Result<BuildArtifact> {
+        self.check_cancelled()?;
+        let (Some(program), Some(types)) = (&analysis.program, &analysis.types) else {
+            bail!("{} has errors and cannot be built", analysis.source_id);
+        };
+        build_executable(
+            program,
+            &types.expr_types,
+            &types.expr_types_by_span,
+            &types.comprehension_var_types,
+            &types.enum_layouts,
+            output,
+            options,
+        )
+    }
+}
+
+pub fn find_stdlib_dir() -> Result<PathBuf> {
+    // Try environment variable first
+    if let Ok(dir) = std::env::var("OTTER_STDLIB_DIR") {
+        let path = PathBuf::from(dir);
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+
+    // Try relative to executable (for development)
+    if let Ok(exe) = std::env::current_exe()
+        && let Some(exe_dir) = exe.parent()
+    {
+        let stdlib = exe_dir
+            .parent()
+            .unwrap_or(exe_dir)
+            .join("stdlib")
+            .join("otter");
+        if stdlib.exists() {
+            return Ok(stdlib);
+        }
+    }
+
+    // Try relative to current directory (for development)
+    let stdlib = PathBuf::from("stdlib").join("otter");
+    if stdlib.exists() {
+        return Ok(stdlib);
+    }
+
+    bail!(
+        "stdlib directory not found. Set OTTER_STDLIB_DIR environment variable or ensure stdlib/otter exists"
+    )
+}
+
+fn register_rust_ffi_functions_for_typecheck(
+    program: &Program,
+    registry: &'static SymbolRegistry,
+) -> Result<()> {
+    let imports = collect_rust_imports_for_typecheck(program);
+    if imports.is_empty() {
+        return Ok(());
+    }
+    if registry.is_denied(Capability::FfiPlugin) {
+        let crates = imports.keys().cloned().collect::<Vec<_>>().join(", ");
+        bail!("cannot import Rust crates ({crates}): FFI plugins are disabled by --sandbox");
+    }
+
+    let bridge_registry = BridgeSymbolRegistry::global();
+    let cargo_bridge = CargoBridge::new(bridge_registry.clone())?;
+
+    for (crate_name, aliases) in imports {
+        let metadata = bridge_registry.ensure_metadata(&crate_name)?;
+        register_bridge_functions_for_typecheck(
+            &crate_name,
+            &aliases,
+            &metadata.functions,
+            registry,
+        )?;
+
+        // Functions the bridge leaves out are recorded so calls to them get
+        // a diagnostic naming the Rust signature
+        let spec = cargo_bridge.crate_spec(&metadata);
+        let generator = RustStubGenerator::new(crate_name.clone(), metadata.dependency.clone());
+        for function in generator.unmapped_functions(&spec) {
+            let Some(rest) = function.name.strip_prefix(&format!("{crate_name}.")) else {
+                registry.register_unmapped(function);
+                continue;
+            };
+            for alias in &aliases {
+                registry.register_unmapped(UnmappedFunction {
+                    name: format!("{alias}.{rest}"),
+                    ..function.clone()
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_rust_imports_for_typecheck(program: &Program) -> HashMap<String, HashSet<String>> {
+    let mut imports: HashMap<String, HashSet<String>> = HashMap::new();
+
+    for statement in &program.statements {
+        if let Statement::Use {
+            imports: use_imports,
+        } = statement.as_ref()
+        {
+            for import in use_imports {
+                if let Some((namespace, crate_name)) = import.as_ref().module.split_once(':')
+                    && namespace == "rust"
+                {
+                    let aliases = imports.entry(crate_name.to_string()).or_default();
+                    aliases.insert(crate_name.to_string());
+                    if let Some(alias_name) = &import.as_ref().alias {
+                        aliases.insert(alias_name.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    imports
+}
+
+fn register_bridge_functions_for_typecheck(
+    crate_name: &str,
+    aliases: &HashSet<String>,
+    functions: &[FunctionSpec],
+    registry: &SymbolRegistry,
+) -> Result<()> {
+    if functions.is_empty() {
+        return Ok(());
+    }
+
+    for function in functions {
+        let canonical_name = if function.name.contains(':') || function.name.contains('.') {
+            function.name.clone()
+        } else {
+            format!("{crate_name}:{}", function.name)
+        };
+
+        let params = function
+            .params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| {
+                type_spec_to_ffi_helper(param, "parameter", &canonical_name).with_context(|| {
+                    format!("parameter {idx} in `{canonical_name}` is not FFI compatible")
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = type_spec_to_ffi_helper(&function.result, "return", &canonical_name)?;
+        let signature = FfiSignature::new(params.clone(), result.clone());
+
+        registry.register_plugin(FfiFunction {
+            name: canonical_name.clone(),
+            symbol: function.symbol.clone(),
+            signature: signature.clone(),
+        });
+
+        for alias in aliases {
+            let alias_name = alias_name_helper(alias, crate_name, &canonical_name);
+            registry.register_plugin(FfiFunction {
+                name: alias_name,
+                symbol: function.symbol.clone(),
+                signature: FfiSignature::new(params.clone(), result.clone()),
+            });
+        }
+    }
+
+    registry.register_plugin(FfiFunction {
+        name: format!("{crate_name}.__call_json"),
+        symbol: "otter_call_json".into(),
+        signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
+    });
+
+    for alias in aliases {
+        registry.register_plugin(FfiFunction {
+            name: format!("{alias}.__call_json"),
+            symbol: "otter_call_json".into(),
+            signature: FfiSignature::new(vec![FfiType::Str, FfiType::Str], FfiType::Str),
+        });
+    }
+
+    Ok(())
+}
+
+fn type_spec_to_ffi_helper(
+    spec: &TypeSpec,
+    position: &str,
+    function_name: &str,
+) -> Result<FfiType> {
+    match spec {
+        TypeSpec::Unit => {
+            if position == "return" {
+                Ok(FfiType::Unit)
+            } else {
+                bail!("`{function_name}` cannot accept a unit value in parameter position")
+            }
+        }
+        TypeSpec::Bool => Ok(FfiType::Bool),
+        TypeSpec::I32 => Ok(FfiType::I32),
+        TypeSpec::I64 => Ok(FfiType::I64),
+        TypeSpec::F64 => Ok(FfiType::F64),
+        TypeSpec::Str => Ok(FfiType::Str),
+        TypeSpec::Opaque => Ok(FfiType::Opaque),
+    }
+}
+
+fn alias_name_helper(alias: &str, crate_name: &str, canonical: &str) -> String {
+    if let Some(rest) = canonical.strip_prefix(&format!("{}:", crate_name)) {
+        format!("{alias}.{rest}")
+    } else {
+        format!("{alias}.{canonical}")
+    }
+}
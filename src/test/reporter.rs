@@ -4,6 +4,9 @@
 )]
 
 use colored::*;
+use otterc_config::VERSION;
+use otterc_utils::annotations::{Annotation, OutputFormat, render_github, render_sarif};
+use otterc_utils::errors::DiagnosticSeverity;
 use std::time::Duration;
 
 use crate::test::TestCase;
@@ -19,6 +22,8 @@ pub enum TestResult {
         duration: Duration,
         output: String,
         span: Option<(usize, usize)>,
+        /// Diagnostics of a test file that failed to compile
+        diagnostics: Vec<Annotation>,
     },
     Skipped {
         reason: String,
@@ -27,14 +32,16 @@ pub enum TestResult {
 
 pub struct TestReporter {
     verbose: bool,
+    format: OutputFormat,
     results: Vec<(TestCase, TestResult)>,
     start_time: std::time::Instant,
 }
 
 impl TestReporter {
-    pub fn new(verbose: bool) -> Self {
+    pub fn new(verbose: bool, format: OutputFormat) -> Self {
         Self {
             verbose,
+            format,
             results: Vec::new(),
             start_time: std::time::Instant::now(),
         }
@@ -45,6 +52,12 @@ impl TestReporter {
     }
 
     pub fn print_result(&self, test: &TestCase, result: &TestResult) {
+        match self.format {
+            // The whole log is printed with the summary
+            OutputFormat::Sarif => return,
+            OutputFormat::Github => print!("{}", render_github(&failure_annotations(test, result))),
+            OutputFormat::Human => {}
+        }
         match result {
             TestResult::Passed { duration, output } => {
                 print!("{}", "✓".green());
@@ -64,6 +77,7 @@ impl TestReporter {
                 duration,
                 output,
                 span,
+                ..
             } => {
                 print!("{}", "✗".red());
                 println!(
@@ -97,6 +111,16 @@ impl TestReporter {
     }
 
     pub fn print_summary(&self) {
+        if self.format == OutputFormat::Sarif {
+            let annotations: Vec<Annotation> = self
+                .results
+                .iter()
+                .flat_map(|(test, result)| failure_annotations(test, result))
+                .collect();
+            println!("{}", render_sarif(&annotations, VERSION));
+            return;
+        }
+
         let total_duration = self.start_time.elapsed();
         let passed = self
             .results
@@ -142,3 +166,25 @@ impl TestReporter {
             .any(|(_, r)| matches!(r, TestResult::Failed { .. }))
     }
 }
+
+/// The problems to annotate for a failed test: the compile errors of its file,
+/// then the failure itself at the test function
+fn failure_annotations(test: &TestCase, result: &TestResult) -> Vec<Annotation> {
+    let TestResult::Failed {
+        error, diagnostics, ..
+    } = result
+    else {
+        return Vec::new();
+    };
+    let mut annotations = diagnostics.clone();
+    annotations.push(
+        Annotation::at_line(
+            DiagnosticSeverity::Error,
+            test.file_path.display().to_string(),
+            test.line_number,
+            format!("test {} failed: {}", test.function_name, error),
+        )
+        .with_rule_id("test-failure"),
+    );
+    annotations
+}
@@ -3,7 +3,9 @@ use std::path::Path;
 use std::process::Command;
 use std::time::Instant;
 
-use crate::cli::CompilationSettings;
+use otterc_utils::annotations::OutputFormat;
+
+use crate::cli::{CompilationSettings, Rejected};
 use crate::test::{TestCase, TestResult};
 
 pub struct TestRunner {
@@ -24,11 +26,16 @@ impl TestRunner {
 
         let compile_result = self.compile_test_file(&test.file_path);
         if let Err(e) = compile_result {
+            let diagnostics = e
+                .downcast_ref::<Rejected>()
+                .map(|rejected| rejected.annotations.clone())
+                .unwrap_or_default();
             return TestResult::Failed {
                 error: format!("Compilation failed: {}", e),
                 duration: start.elapsed(),
                 output: String::new(),
                 span: Some((test.line_number, test.line_number)),
+                diagnostics,
             };
         }
 
@@ -69,6 +76,7 @@ impl TestRunner {
                         duration,
                         output: combined_output,
                         span: Some((test.line_number, test.line_number)),
+                        diagnostics: Vec::new(),
                     }
                 }
             }
@@ -77,15 +85,21 @@ impl TestRunner {
                 duration,
                 output: String::new(),
                 span: Some((test.line_number, test.line_number)),
+                diagnostics: Vec::new(),
             },
         }
     }
 
     fn compile_test_file(&self, file_path: &Path) -> Result<std::path::PathBuf> {
-        use crate::cli::{compile_pipeline, read_source};
+        use crate::cli::{compile_pipeline, compile_quietly, read_source};
 
         let source = read_source(file_path)?;
-        let stage = compile_pipeline(file_path, &source, &self.settings)
+        // Other formats report the diagnostics with the test results
+        let compile = match self.settings.output_format {
+            OutputFormat::Human => compile_pipeline,
+            OutputFormat::Github | OutputFormat::Sarif => compile_quietly,
+        };
+        let stage = compile(file_path, &source, &self.settings)
             .with_context(|| format!("failed to compile test file {}", file_path.display()))?;
 
         let binary_path = match &stage.result {
@@ -0,0 +1,431 @@
+//! Compiler versions: `otter upgrade`, pinned toolchains and the language
+//! version a project needs
+//!
+//! A project pins a compiler with an `otter-toolchain` file in its root,
+//! holding a version such as `0.2.1`. When the running `otter` is another
+//! version, it hands the command line to the pinned compiler, installed next
+//! to the others by `otter toolchain install`. `OTTER_TOOLCHAIN` overrides the
+//! file for one command.
+//!
+//! A project declares the oldest compiler that understands it with
+//! `otter-version` under `[package]` in its `otter.toml`; an older compiler
+//! stops with an error instead of failing on the first newer construct.
+//!
+//! Releases are the `v<version>` tags of the OtterLang repository, and are
+//! installed with `cargo install`, as `INSTALL.md` describes.
+
+#![expect(clippy::print_stdout, reason = "TODO: Use robust logging")]
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+use colored::Colorize;
+use serde::Deserialize;
+
+use otterc_config::VERSION;
+
+/// The file that pins a project's compiler
+pub const TOOLCHAIN_FILE: &str = "otter-toolchain";
+/// The project manifest, which may declare the language version it needs
+pub const MANIFEST_FILE: &str = "otter.toml";
+
+const REPOSITORY: &str = "https://github.com/jonathanmagambo/otterlang";
+
+/// A compiler version, `major.minor.patch`. `0.2` is read as `0.2.0` and a
+/// leading `v`, as in release tags, is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    /// The version of the running compiler
+    pub fn current() -> Self {
+        VERSION.parse().unwrap_or(Version {
+            major: 0,
+            minor: 0,
+            patch: 0,
+        })
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        let invalid = || format!("'{trimmed}' is not a version like 0.2.1");
+        let digits = trimmed.strip_prefix('v').unwrap_or(trimmed);
+        // A pre-release or build suffix does not change what is compatible
+        let digits = digits.split(['-', '+']).next().unwrap_or_default();
+        let parts = digits
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        match parts[..] {
+            [major] => Ok(Version {
+                major,
+                minor: 0,
+                patch: 0,
+            }),
+            [major, minor] => Ok(Version {
+                major,
+                minor,
+                patch: 0,
+            }),
+            [major, minor, patch] => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The nearest `name` in `dir` or its parents
+fn find_upwards(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The version an `otter-toolchain` file pins: its first line that is not
+/// blank or a `#` comment
+pub fn parse_toolchain_file(contents: &str) -> Result<Version, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| "no version in the file".to_string())?
+        .parse()
+}
+
+/// The compiler version pinned for `dir`, and where it was pinned
+pub fn pinned_version(dir: &Path) -> Result<Option<(Version, String)>> {
+    if let Ok(version) = env::var("OTTER_TOOLCHAIN")
+        && !version.trim().is_empty()
+    {
+        let version = version
+            .parse()
+            .map_err(|err| anyhow!("invalid OTTER_TOOLCHAIN: {err}"))?;
+        return Ok(Some((version, "OTTER_TOOLCHAIN".to_string())));
+    }
+    let Some(path) = find_upwards(dir, TOOLCHAIN_FILE) else {
+        return Ok(None);
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let version = parse_toolchain_file(&contents)
+        .map_err(|err| anyhow!("invalid {}: {err}", path.display()))?;
+    Ok(Some((version, path.display().to_string())))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    package: Package,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Package {
+    #[serde(rename = "otter-version")]
+    otter_version: Option<String>,
+}
+
+/// The oldest compiler an `otter.toml` declares it needs, if any
+pub fn required_version(manifest: &str) -> Result<Option<Version>, String> {
+    let manifest: Manifest = toml::from_str(manifest).map_err(|err| err.message().to_string())?;
+    manifest
+        .package
+        .otter_version
+        .map(|version| {
+            version
+                .parse()
+                .map_err(|err| format!("invalid otter-version: {err}"))
+        })
+        .transpose()
+}
+
+/// Fails when the nearest `otter.toml` above `dir` needs a newer compiler
+/// than this one
+pub fn check_language_version(dir: &Path) -> Result<()> {
+    let Some(path) = find_upwards(dir, MANIFEST_FILE) else {
+        return Ok(());
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let required =
+        required_version(&contents).map_err(|err| anyhow!("invalid {}: {err}", path.display()))?;
+    match required {
+        Some(required) if required > Version::current() => bail!(
+            "{} requires otter {required} or newer, but this is otter {}; run `otter upgrade`",
+            path.display(),
+            Version::current()
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Where toolchains are installed: `OTTER_TOOLCHAINS`, or `toolchains` in
+/// the user's data directory
+pub fn toolchains_dir() -> Result<PathBuf> {
+    if let Some(dir) = env::var_os("OTTER_TOOLCHAINS").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let base = directories::BaseDirs::new()
+        .ok_or_else(|| anyhow!("could not determine the data directory; set OTTER_TOOLCHAINS"))?;
+    Ok(base.data_dir().join("otterlang").join("toolchains"))
+}
+
+/// The `otter` of an installed toolchain
+fn toolchain_binary(root: &Path, version: Version) -> PathBuf {
+    root.join(version.to_string())
+        .join("bin")
+        .join(format!("otter{}", env::consts::EXE_SUFFIX))
+}
+
+/// Runs the command line with the compiler pinned for the current directory
+/// when that is not this compiler, returning how it exited. Nothing is
+/// delegated for `otter upgrade` and `otter toolchain`, which manage the
+/// compilers themselves.
+pub fn delegate_to_pinned() -> Result<Option<ExitStatus>> {
+    let args: Vec<_> = env::args_os().skip(1).collect();
+    if args
+        .first()
+        .is_some_and(|command| command == "upgrade" || command == "toolchain")
+    {
+        return Ok(None);
+    }
+    let cwd = env::current_dir()?;
+    let Some((pinned, source)) = pinned_version(&cwd)? else {
+        return Ok(None);
+    };
+    if pinned == Version::current() {
+        return Ok(None);
+    }
+
+    let binary = toolchain_binary(&toolchains_dir()?, pinned);
+    if !binary.is_file() {
+        bail!(
+            "{source} pins otter {pinned}, but this is otter {}; install it with \
+             `otter toolchain install {pinned}`",
+            Version::current()
+        );
+    }
+    let status = Command::new(&binary)
+        .args(args)
+        // The pinned compiler runs itself rather than looking again
+        .env("OTTER_TOOLCHAIN", pinned.to_string())
+        .status()
+        .with_context(|| format!("failed to run {}", binary.display()))?;
+    Ok(Some(status))
+}
+
+/// The newest release, from the repository's tags
+pub fn latest_release() -> Result<Version> {
+    let repository = repository();
+    let output = Command::new("git")
+        .args(["ls-remote", "--tags", "--refs", &repository])
+        .output()
+        .context("failed to run git to look up releases")?;
+    if !output.status.success() {
+        bail!(
+            "failed to list the releases of {repository}:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    releases_in(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .max()
+        .ok_or_else(|| anyhow!("{repository} has no releases"))
+}
+
+/// The release versions in `git ls-remote --tags` output
+fn releases_in(refs: &str) -> Vec<Version> {
+    refs.lines()
+        .filter_map(|line| line.split_whitespace().nth(1)?.strip_prefix("refs/tags/v"))
+        .filter_map(|tag| tag.parse().ok())
+        .collect()
+}
+
+/// The repository releases are installed from, `OTTER_UPGRADE_REPOSITORY` for
+/// a fork or mirror
+fn repository() -> String {
+    env::var("OTTER_UPGRADE_REPOSITORY")
+        .ok()
+        .filter(|repository| !repository.is_empty())
+        .unwrap_or_else(|| REPOSITORY.to_string())
+}
+
+/// Installs `version` with `cargo install`, into `root` or cargo's own
+/// `bin` directory
+fn cargo_install(version: Version, root: Option<&Path>) -> Result<()> {
+    let repository = repository();
+    let mut cargo = Command::new("cargo");
+    cargo
+        .args(["install", "--git", &repository, "--tag"])
+        .arg(format!("v{version}"))
+        .args(["--bin", "otter", "--locked", "--force"]);
+    if let Some(root) = root {
+        cargo.arg("--root").arg(root);
+    }
+    let status = cargo
+        .status()
+        .context("failed to run cargo install; otter is installed with cargo")?;
+    if !status.success() {
+        bail!("cargo install of otter {version} failed ({status})");
+    }
+    Ok(())
+}
+
+/// `otter upgrade`: replaces the installed `otter` with `version`, or the
+/// newest release
+pub fn upgrade(version: Option<&str>) -> Result<()> {
+    let current = Version::current();
+    let target = match version {
+        Some(version) => version.parse().map_err(|err: String| anyhow!(err))?,
+        None => latest_release()?,
+    };
+    if version.is_none() && target <= current {
+        println!("otter {current} is up to date");
+        return Ok(());
+    }
+    println!("{} otter {current} to {target}", "Upgrading".green().bold());
+    cargo_install(target, None)?;
+    println!("{} otter {target}", "Installed".green().bold());
+    Ok(())
+}
+
+/// `otter toolchain install`: installs `version`, or the version pinned for
+/// the current directory, next to the other toolchains
+pub fn install(version: Option<&str>) -> Result<()> {
+    let version = match version {
+        Some(version) => version.parse().map_err(|err: String| anyhow!(err))?,
+        None => {
+            pinned_version(&env::current_dir()?)?
+                .ok_or_else(|| anyhow!("no version given and no {TOOLCHAIN_FILE} file pins one"))?
+                .0
+        }
+    };
+    let root = toolchains_dir()?;
+    if toolchain_binary(&root, version).is_file() {
+        println!("otter {version} is already installed");
+        return Ok(());
+    }
+    println!("{} otter {version}", "Installing".green().bold());
+    cargo_install(version, Some(&root.join(version.to_string())))?;
+    println!(
+        "{} otter {version} in {}",
+        "Installed".green().bold(),
+        root.display()
+    );
+    Ok(())
+}
+
+/// `otter toolchain list`: the installed toolchains, marking this compiler
+/// and the one pinned for the current directory
+pub fn list() -> Result<()> {
+    let root = toolchains_dir()?;
+    let mut versions: Vec<Version> = fs::read_dir(&root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .filter(|&version| toolchain_binary(&root, version).is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    let current = Version::current();
+    if !versions.contains(&current) {
+        versions.push(current);
+    }
+    versions.sort();
+
+    let pinned = pinned_version(&env::current_dir()?)?;
+    for version in versions {
+        let mut notes = Vec::new();
+        if version == current {
+            notes.push("running".to_string());
+        }
+        if let Some((pinned, source)) = &pinned
+            && *pinned == version
+        {
+            notes.push(format!("pinned by {source}"));
+        }
+        if notes.is_empty() {
+            println!("{version}");
+        } else {
+            println!("{version} ({})", notes.join(", "));
+        }
+    }
+    if let Some((pinned, source)) = pinned
+        && !toolchain_binary(&root, pinned).is_file()
+        && pinned != current
+    {
+        println!("{pinned} (pinned by {source}, not installed)");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(version("v0.2"), version("0.2.0"));
+        assert_eq!(version("1.2.3-beta.1").to_string(), "1.2.3");
+        assert!(version("0.10.0") > version("0.9.9"));
+        assert!(version("1") > version("0.99"));
+        assert!("0.2.x".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+        assert!("".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn toolchain_files_pin_their_first_version_line() {
+        assert_eq!(
+            parse_toolchain_file("# pinned for the 0.3 syntax\n\n0.3.1\n"),
+            Ok(version("0.3.1"))
+        );
+        assert!(parse_toolchain_file("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn manifests_declare_the_oldest_compiler() {
+        assert_eq!(
+            required_version("[package]\nname = \"app\"\notter-version = \"0.4\"\n"),
+            Ok(Some(version("0.4.0")))
+        );
+        assert_eq!(
+            required_version("[scheduler]\nsteal_policy = \"numa\"\n"),
+            Ok(None)
+        );
+        assert!(required_version("[package]\notter-version = \"new\"\n").is_err());
+    }
+
+    #[test]
+    fn releases_are_read_from_version_tags() {
+        let refs = "abc\trefs/tags/v0.1.0\ndef\trefs/tags/v0.10.2\n123\trefs/tags/nightly\n";
+        assert_eq!(releases_in(refs).into_iter().max(), Some(version("0.10.2")));
+    }
+}
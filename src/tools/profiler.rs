@@ -4,14 +4,18 @@
 //!
 //! Provides command-line interface for profiling OtterLang programs
 
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use colored::Colorize;
 
 use otterc_runtime::memory::profiler::{ProfilingStats, get_profiler};
+use otterc_runtime::profile::RunProfile;
+use otterc_utils::profiler::PhaseTiming;
 
 /// Profile command for CLI integration
 #[derive(Clone, Debug, clap::Subcommand)]
@@ -38,6 +42,15 @@ pub enum ProfileCommand {
         #[arg(long)]
         file: Option<PathBuf>,
     },
+    /// Compile and run a program once, writing an HTML report of where the
+    /// compile and the run spent their time
+    Report {
+        /// OtterLang program to profile
+        program: PathBuf,
+        /// Report file (defaults to the program's name with `.profile.html`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 pub fn run_profiler_subcommand(command: &ProfileCommand) -> Result<()> {
@@ -54,6 +67,10 @@ pub fn run_profiler_subcommand(command: &ProfileCommand) -> Result<()> {
         ProfileCommand::Stats { file } => {
             show_stats(file.clone())?;
         }
+        // Compiling takes the options of the `otter` command line
+        ProfileCommand::Report { .. } => {
+            bail!("profile reports are written by `otter profile report`")
+        }
     }
     Ok(())
 }
@@ -158,3 +175,213 @@ fn show_stats(file: Option<PathBuf>) -> Result<()> {
     }
     Ok(())
 }
+
+/// Functions listed in a report, hottest first
+const REPORT_FUNCTIONS: usize = 25;
+
+/// GC pauses listed in a report, longest first
+const REPORT_PAUSES: usize = 25;
+
+/// Renders the compile `phases` of `program` and the profile of one `run`
+/// as a self-contained HTML page
+pub fn render_report(
+    program: &Path,
+    phases: &[PhaseTiming],
+    run: &RunProfile,
+    status: ExitStatus,
+) -> String {
+    let mut html = String::new();
+    let title = format!("Profile of {}", escape_html(&program.display().to_string()));
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>"
+    );
+    html.push_str(REPORT_STYLE);
+    let _ = writeln!(html, "</head>\n<body>\n<h1>{title}</h1>");
+    let compile_ns: u64 = phases
+        .iter()
+        .map(|phase| u64::try_from(phase.duration.as_nanos()).unwrap_or(u64::MAX))
+        .sum();
+    let _ = writeln!(
+        html,
+        "<p>Compiled in {}, ran for {} and exited with {}.</p>",
+        format_ns(compile_ns),
+        format_ns(run.wall_ns),
+        escape_html(&status.to_string())
+    );
+
+    html.push_str("<h2>Compile time</h2>\n");
+    html.push_str("<table>\n<tr><th>Phase</th><th>Time</th><th>Share</th></tr>\n");
+    for phase in phases {
+        let ns = u64::try_from(phase.duration.as_nanos()).unwrap_or(u64::MAX);
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td>{}</tr>",
+            escape_html(&phase.name),
+            format_ns(ns),
+            share_cell(ns, compile_ns)
+        );
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Hottest functions</h2>\n");
+    if run.functions.is_empty() {
+        html.push_str("<p>No function returned during the run.</p>\n");
+    } else {
+        html.push_str(
+            "<table>\n<tr><th>Function</th><th>Calls</th><th>Total</th><th>Self</th>\
+             <th>Share of the run</th></tr>\n",
+        );
+        for function in run.functions.iter().take(REPORT_FUNCTIONS) {
+            let _ = writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+                escape_html(&function.name),
+                function.calls,
+                format_ns(function.total_ns),
+                format_ns(function.self_ns),
+                share_cell(function.total_ns, run.wall_ns)
+            );
+        }
+        html.push_str("</table>\n");
+        if run.functions.len() > REPORT_FUNCTIONS {
+            let _ = writeln!(
+                html,
+                "<p>{} more functions are not shown.</p>",
+                run.functions.len() - REPORT_FUNCTIONS
+            );
+        }
+    }
+
+    html.push_str("<h2>GC pauses</h2>\n");
+    if run.gc_pauses.is_empty() {
+        html.push_str("<p>The garbage collector did not run.</p>\n");
+    } else {
+        let _ = writeln!(
+            html,
+            "<p>{} collections paused the program for {} in total ({:.1}% of the run), \
+             {} at most. {} were reclaimed.</p>",
+            run.gc.cycles,
+            format_ns(run.gc.total_pause_ns),
+            percent(run.gc.total_pause_ns, run.wall_ns),
+            format_ns(run.gc.max_pause_ns),
+            format_bytes(run.gc.bytes_reclaimed)
+        );
+        let mut pauses: Vec<_> = run.gc_pauses.iter().collect();
+        pauses.sort_by(|a, b| b.pause_ns.cmp(&a.pause_ns).then(a.cycle.cmp(&b.cycle)));
+        html.push_str(
+            "<table>\n<tr><th>Cycle</th><th>Strategy</th><th>Ended at</th><th>Pause</th>\
+             <th>Reclaimed</th><th>Heap after</th></tr>\n",
+        );
+        for pause in pauses.iter().take(REPORT_PAUSES) {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                pause.cycle,
+                escape_html(&pause.strategy),
+                format_ns(pause.at_ns),
+                format_ns(pause.pause_ns),
+                format_bytes(pause.bytes_reclaimed),
+                format_bytes(pause.heap_bytes)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Scheduler utilization</h2>\n");
+    if run.workers.is_empty() {
+        html.push_str("<p>The program did not start the task scheduler.</p>\n");
+    } else {
+        let busy_ns: u64 = run.workers.iter().map(|worker| worker.busy_ns).sum();
+        let capacity_ns = run.wall_ns.saturating_mul(run.workers.len() as u64);
+        let _ = writeln!(
+            html,
+            "<p>{} tasks spawned, {} completed. {} workers were busy {:.1}% of the run.</p>",
+            run.tasks_spawned,
+            run.tasks_completed,
+            run.workers.len(),
+            percent(busy_ns, capacity_ns)
+        );
+        html.push_str(
+            "<table>\n<tr><th>Worker</th><th>Tasks</th><th>Busy</th><th>Utilization</th></tr>\n",
+        );
+        for worker in &run.workers {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+                worker.id,
+                worker.tasks_processed,
+                format_ns(worker.busy_ns),
+                share_cell(worker.busy_ns, run.wall_ns)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+const REPORT_STYLE: &str = "<style>
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { padding: 0.25em 0.75em; text-align: left; border-bottom: 1px solid #ddd; }
+td.share { min-width: 12em; }
+.bar { display: inline-block; height: 0.8em; background: #4a7fb5; margin-right: 0.5em; }
+</style>
+";
+
+/// A table cell showing `part` as a share of `whole`, with a bar
+fn share_cell(part: u64, whole: u64) -> String {
+    let share = percent(part, whole);
+    format!(
+        "<td class=\"share\"><span class=\"bar\" style=\"width: {:.1}%\"></span>{share:.1}%</td>",
+        share.min(100.0) * 0.6
+    )
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+fn format_ns(ns: u64) -> String {
+    let ms = ns as f64 / 1e6;
+    if ms >= 1000.0 {
+        format!("{:.2}s", ms / 1000.0)
+    } else {
+        format!("{ms:.3}ms")
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}